@@ -6,10 +6,11 @@ use near_primitives::time::Utc;
 use near_primitives::block::BlockValidityError;
 use near_primitives::challenge::{ChunkProofs, ChunkState};
 use near_primitives::errors::{EpochError, StorageError};
+use near_primitives::hash::CryptoHash;
 use near_primitives::serialize::to_base;
 use near_primitives::shard_layout::ShardLayoutError;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
-use near_primitives::types::{BlockHeight, EpochId, ShardId};
+use near_primitives::types::{BlockHeight, EpochId, NumBlocks, ShardId};
 
 #[derive(thiserror::Error, Debug)]
 pub enum QueryError {
@@ -51,9 +52,13 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
-    #[error("The state of account {requested_account_id} is too large")]
+    #[error(
+        "The state of account {requested_account_id} is too large: {state_size} bytes, the limit is {state_size_limit}"
+    )]
     TooLargeContractState {
         requested_account_id: near_primitives::types::AccountId,
+        state_size: u64,
+        state_size_limit: u64,
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
@@ -204,6 +209,9 @@ pub enum Error {
     /// Not found record in the DB.
     #[error("DB Not Found Error: {0}")]
     DBNotFoundErr(String),
+    /// Data needed for a merkle proof of block inclusion is not in the DB.
+    #[error("Missing Block Merkle Data: {0}")]
+    MissingBlockMerkleData(MissingBlockMerkleData),
     /// Storage error. Used for internal passing the error.
     #[error("Storage Error: {0}")]
     StorageError(#[from] StorageError),
@@ -215,6 +223,16 @@ pub enum Error {
     Other(String),
 }
 
+/// A piece of the block merkle tree data that is not stored on the node, e.g. because the blocks
+/// before the one the node synced from were never processed.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MissingBlockMerkleData {
+    #[error("partial merkle tree of block {0}")]
+    PartialMerkleTree(CryptoHash),
+    #[error("hash of block with ordinal {0}")]
+    BlockOrdinal(NumBlocks),
+}
+
 /// For now StorageError can happen at any time from ViewClient because of
 /// the used isolation level + running ViewClient in a separate thread.
 pub trait LogTransientStorageError {
@@ -246,7 +264,8 @@ impl Error {
             | Error::ChallengedBlockOnChain
             | Error::StorageError(_)
             | Error::GCError(_)
-            | Error::DBNotFoundErr(_) => false,
+            | Error::DBNotFoundErr(_)
+            | Error::MissingBlockMerkleData(_) => false,
             Error::InvalidBlockPastTime(_, _)
             | Error::InvalidBlockFutureTime(_)
             | Error::InvalidBlockHeight(_)
@@ -289,7 +308,10 @@ impl Error {
 
     pub fn is_error(&self) -> bool {
         match self {
-            Error::IOErr(_) | Error::Other(_) | Error::DBNotFoundErr(_) => true,
+            Error::IOErr(_)
+            | Error::Other(_)
+            | Error::DBNotFoundErr(_)
+            | Error::MissingBlockMerkleData(_) => true,
             _ => false,
         }
     }
@@ -13,7 +13,9 @@ use rand::seq::SliceRandom;
 use rand::SeedableRng;
 use tracing::{debug, error, info, warn, Span};
 
-use near_chain_primitives::error::{BlockKnownError, Error, LogTransientStorageError};
+use near_chain_primitives::error::{
+    BlockKnownError, Error, LogTransientStorageError, MissingBlockMerkleData,
+};
 use near_primitives::block::{genesis_chunks, Tip};
 use near_primitives::challenge::{
     BlockDoubleSign, Challenge, ChallengeBody, ChallengesResult, ChunkProofs, ChunkState,
@@ -35,6 +37,7 @@ use near_primitives::syncing::{
     ShardStateSyncResponseHeaderV1, ShardStateSyncResponseHeaderV2, StateHeaderKey, StatePartKey,
 };
 use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, SignedTransaction};
+use near_primitives::trie_key::{trie_key_parsers, TrieKeyColumn};
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{
     AccountId, Balance, BlockExtra, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash,
@@ -47,7 +50,7 @@ use near_primitives::views::{
     FinalExecutionOutcomeWithReceiptView, FinalExecutionStatus, LightClientBlockView,
     SignedTransactionView,
 };
-use near_store::{DBCol, ShardTries, StoreUpdate};
+use near_store::{DBCol, KeyForStateChanges, ShardTries, StoreUpdate};
 
 use crate::block_processing_utils::{
     BlockPreprocessInfo, BlockProcessingArtifact, BlocksInProcessing,
@@ -61,7 +64,7 @@ use crate::store::{ChainStore, ChainStoreAccess, ChainStoreUpdate, GCMode};
 use crate::types::{
     AcceptedBlock, ApplySplitStateResult, ApplySplitStateResultOrStateChanges,
     ApplyTransactionResult, Block, BlockEconomicsConfig, BlockHeader, BlockHeaderInfo, BlockStatus,
-    ChainGenesis, Provenance, RuntimeAdapter,
+    ChainGenesis, PinnedStateRoots, Provenance, RuntimeAdapter,
 };
 use crate::validate::{
     validate_challenge, validate_chunk_proofs, validate_chunk_with_chunk_extra,
@@ -455,6 +458,9 @@ pub struct Chain {
     /// the next block we'll be processing, keeping them in this field in the
     /// meantime.
     pending_state_patch: Option<SandboxStatePatch>,
+
+    /// Last final block up to which `pin_latest_state_roots` pinned state roots.
+    last_pinned_final_block: Option<CryptoHash>,
 }
 
 impl ChainAccess for Chain {
@@ -517,6 +523,7 @@ impl Chain {
             apply_chunks_sender: sc,
             apply_chunks_receiver: rc,
             pending_state_patch: None,
+            last_pinned_final_block: None,
         })
     }
 
@@ -650,6 +657,7 @@ impl Chain {
             apply_chunks_sender: sc,
             apply_chunks_receiver: rc,
             pending_state_patch: None,
+            last_pinned_final_block: None,
         })
     }
 
@@ -670,13 +678,8 @@ impl Chain {
     ) -> Result<CryptoHash, Error> {
         let bps = runtime_adapter.get_epoch_block_producers_ordered(&epoch_id, last_known_hash)?;
         let protocol_version = runtime_adapter.get_epoch_protocol_version(&prev_epoch_id)?;
-        if checked_feature!("stable", BlockHeaderV3, protocol_version) {
-            let validator_stakes = bps.into_iter().map(|(bp, _)| bp).collect();
-            Chain::compute_collection_hash(validator_stakes)
-        } else {
-            let validator_stakes = bps.into_iter().map(|(bp, _)| bp.into_v1()).collect();
-            Chain::compute_collection_hash(validator_stakes)
-        }
+        let validator_stakes = bps.into_iter().map(|(bp, _)| bp).collect();
+        Ok(BlockHeader::compute_bp_hash(validator_stakes, protocol_version))
     }
 
     /// Creates a light client block for the last final block from perspective of some other block
@@ -868,6 +871,12 @@ impl Chain {
         }
         let mut gc_blocks_remaining = gc_config.gc_blocks_limit;
 
+        // Deletions deferred while lower state roots were pinned
+        let mut chain_store_update = self.store.store_update();
+        chain_store_update
+            .release_deferred_trie_changes(tries.clone(), gc_config.gc_blocks_limit)?;
+        chain_store_update.commit()?;
+
         // Forks Cleaning
         let gc_fork_clean_step = gc_config.gc_fork_clean_step;
         let stop_height = tail.max(fork_tail.saturating_sub(gc_fork_clean_step));
@@ -943,6 +952,193 @@ impl Chain {
         chain_store_update.commit()
     }
 
+    /// Keeps the state after block `block_hash` from garbage collection, for the shards this
+    /// node has the state of, until `unpin_state_roots` is called for the block. The state stays
+    /// queryable at the block after the block itself is garbage collected.
+    ///
+    /// Pinning doesn't touch the state: while the roots are pinned, garbage collection defers
+    /// the deletions of the blocks above, see `ChainStoreUpdate::release_deferred_trie_changes`.
+    /// Only final blocks can be pinned, as the state of a block which ends up on a fork is
+    /// reverted.
+    pub fn pin_state_roots(&mut self, block_hash: &CryptoHash) -> Result<(), Error> {
+        let header = self.get_block_header(block_hash)?;
+        if header.height() > self.store.final_head()?.height {
+            return Err(Error::Other(format!("{} is not final", block_hash)));
+        }
+        self.is_on_current_chain(&header)?;
+        self.update_state_roots_pin(block_hash, |pin| pin.manual = true)
+    }
+
+    /// Releases the state roots pinned by `pin_state_roots`. If the block is already garbage
+    /// collected, so is its state, unless the roots are still pinned for an account by
+    /// `pin_latest_state_roots`.
+    pub fn unpin_state_roots(&mut self, block_hash: &CryptoHash) -> Result<(), Error> {
+        match self.store.get_pinned_state_roots(block_hash)? {
+            Some(pin) if pin.manual => {
+                self.update_state_roots_pin(block_hash, |pin| pin.manual = false)
+            }
+            _ => Err(Error::DBNotFoundErr(format!("PINNED STATE ROOTS: {}", block_hash))),
+        }
+    }
+
+    /// Pins the state roots of the blocks which became final since the last call, up to
+    /// `last_final_block`, which change one of `gc_config.pinned_state_root_accounts`. For each
+    /// account, only the `gc_config.num_pinned_state_roots` latest of these stay pinned.
+    ///
+    /// Blocks which became final before the first call aren't pinned.
+    pub fn pin_latest_state_roots(
+        &mut self,
+        last_final_block: &CryptoHash,
+        gc_config: &near_chain_configs::GCConfig,
+    ) -> Result<(), Error> {
+        if gc_config.pinned_state_root_accounts.is_empty() || gc_config.num_pinned_state_roots == 0
+        {
+            return Ok(());
+        }
+        let mut new_final_blocks = vec![];
+        let mut block_hash = *last_final_block;
+        while Some(block_hash) != self.last_pinned_final_block
+            && block_hash != CryptoHash::default()
+        {
+            let header = self.get_block_header(&block_hash)?;
+            new_final_blocks.push(block_hash);
+            if self.last_pinned_final_block.is_none() {
+                break;
+            }
+            block_hash = *header.prev_hash();
+        }
+        for block_hash in new_final_blocks.iter().rev() {
+            for account_id in &gc_config.pinned_state_root_accounts {
+                if self.is_account_changed_in_block(block_hash, account_id)? {
+                    self.pin_latest_state_root(
+                        block_hash,
+                        account_id,
+                        gc_config.num_pinned_state_roots,
+                    )?;
+                }
+            }
+        }
+        self.last_pinned_final_block = Some(*last_final_block);
+        Ok(())
+    }
+
+    /// Pins the state roots of `block_hash` for `account_id`, and unpins the roots pinned for
+    /// it which are no longer among its `num_roots` latest ones.
+    fn pin_latest_state_root(
+        &mut self,
+        block_hash: &CryptoHash,
+        account_id: &AccountId,
+        num_roots: NumBlocks,
+    ) -> Result<(), Error> {
+        self.update_state_roots_pin(block_hash, |pin| {
+            if !pin.accounts.contains(account_id) {
+                pin.accounts.push(account_id.clone());
+            }
+        })?;
+        let mut pins: Vec<_> = self
+            .store
+            .iterate_pinned_state_roots()?
+            .into_iter()
+            .filter(|(_, pin)| pin.accounts.contains(account_id))
+            .collect();
+        pins.sort_by_key(|(_, pin)| std::cmp::Reverse(pin.height));
+        for (block_hash, _) in pins.into_iter().skip(num_roots as usize) {
+            self.update_state_roots_pin(&block_hash, |pin| {
+                pin.accounts.retain(|pinned_account_id| pinned_account_id != account_id)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Whether the block changes any data of `account_id`, according to its state changes.
+    fn is_account_changed_in_block(
+        &self,
+        block_hash: &CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        let store = self.store.store();
+        for (column, prefix) in trie_key_parsers::get_raw_prefixes_for_account(account_id) {
+            let key = KeyForStateChanges::from_raw_key(block_hash, &prefix);
+            // The prefixes of these columns are whole keys, which are prefixes of the keys of
+            // other accounts too.
+            let change = match column {
+                TrieKeyColumn::Account | TrieKeyColumn::ContractCode => {
+                    key.find_exact_iter(store).next()
+                }
+                _ => key.find_iter(store).next(),
+            };
+            if change.transpose()?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Applies `update` to the pin of the state roots of `block_hash`, and removes the pin once
+    /// the roots are no longer pinned.
+    fn update_state_roots_pin(
+        &mut self,
+        block_hash: &CryptoHash,
+        update: impl FnOnce(&mut PinnedStateRoots),
+    ) -> Result<(), Error> {
+        let mut pin = match self.store.get_pinned_state_roots(block_hash)? {
+            Some(pin) => pin,
+            None => self.get_state_roots_to_pin(block_hash)?,
+        };
+        update(&mut pin);
+
+        let mut store_update = self.store.store().store_update();
+        if pin.is_pinned() {
+            store_update.set_ser(DBCol::PinnedStateRoots, block_hash.as_ref(), &pin)?;
+        } else {
+            store_update.delete(DBCol::PinnedStateRoots, block_hash.as_ref());
+        }
+        store_update.commit()?;
+        Ok(())
+    }
+
+    /// The state roots after block `block_hash` of the shards this node has the state of.
+    fn get_state_roots_to_pin(&self, block_hash: &CryptoHash) -> Result<PinnedStateRoots, Error> {
+        let header = self.get_block_header(block_hash)?;
+        let shard_layout = self.runtime_adapter.get_shard_layout(header.epoch_id())?;
+        let mut state_roots = vec![];
+        for shard_uid in shard_layout.get_shard_uids() {
+            match self.get_chunk_extra(block_hash, &shard_uid) {
+                Ok(chunk_extra) => state_roots.push((shard_uid, *chunk_extra.state_root())),
+                Err(Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if state_roots.is_empty() {
+            return Err(Error::DBNotFoundErr(format!("STATE ROOTS: {}", block_hash)));
+        }
+        Ok(PinnedStateRoots {
+            height: header.height(),
+            state_roots,
+            manual: false,
+            accounts: vec![],
+        })
+    }
+
+    /// Starts bringing the flat index of the contract data of each shard this node has the
+    /// state of to the state after the head, see `RuntimeAdapter::update_flat_contract_data`.
+    pub fn update_flat_contract_data(&self) -> Result<(), Error> {
+        let head = self.head()?;
+        let shard_layout = self.runtime_adapter.get_shard_layout(&head.epoch_id)?;
+        for shard_uid in shard_layout.get_shard_uids() {
+            match self.get_chunk_extra(&head.last_block_hash, &shard_uid) {
+                Ok(chunk_extra) => self.runtime_adapter.update_flat_contract_data(
+                    shard_uid,
+                    &head.last_block_hash,
+                    chunk_extra.state_root(),
+                )?,
+                Err(Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
     pub fn clear_forks_data(
         &mut self,
         tries: ShardTries,
@@ -1239,7 +1435,7 @@ impl Chain {
 
             let block_merkle_tree = self.store.get_block_merkle_tree(header.prev_hash())?;
             let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-            block_merkle_tree.insert(*header.prev_hash());
+            block_merkle_tree.append(*header.prev_hash());
             if &block_merkle_tree.root() != header.block_merkle_root() {
                 return Err(Error::InvalidBlockMerkleRoot);
             }
@@ -3650,6 +3846,31 @@ impl Chain {
         )
     }
 
+    /// Gets the partial merkle tree of a block for a block proof, reporting it as missing block
+    /// merkle data if it is not stored.
+    fn get_proof_merkle_tree(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Arc<PartialMerkleTree>, Error> {
+        self.store().get_block_merkle_tree(block_hash).map_err(|err| match err {
+            Error::DBNotFoundErr(_) => Error::MissingBlockMerkleData(
+                MissingBlockMerkleData::PartialMerkleTree(*block_hash),
+            ),
+            err => err,
+        })
+    }
+
+    /// Gets the hash of the block with the given ordinal for a block proof, reporting it as
+    /// missing block merkle data if it is not stored.
+    fn get_proof_block_hash(&self, block_ordinal: NumBlocks) -> Result<CryptoHash, Error> {
+        self.store().get_block_hash_from_ordinal(block_ordinal).map_err(|err| match err {
+            Error::DBNotFoundErr(_) => {
+                Error::MissingBlockMerkleData(MissingBlockMerkleData::BlockOrdinal(block_ordinal))
+            }
+            err => err,
+        })
+    }
+
     /// Get node at given position (index, level). If the node does not exist, return `None`.
     fn get_merkle_tree_node(
         &self,
@@ -3663,11 +3884,8 @@ impl Chain {
             Ok(*hash)
         } else {
             if level == 0 {
-                let maybe_hash = if index >= tree_size {
-                    None
-                } else {
-                    Some(self.store().get_block_hash_from_ordinal(index)?)
-                };
+                let maybe_hash =
+                    if index >= tree_size { None } else { Some(self.get_proof_block_hash(index)?) };
                 tree_nodes.insert((index, level), maybe_hash);
                 Ok(maybe_hash)
             } else {
@@ -3695,8 +3913,7 @@ impl Chain {
                 } else {
                     Some(
                         *self
-                            .store()
-                            .get_block_merkle_tree_from_ordinal(cur_tree_size)?
+                            .get_proof_merkle_tree(&self.get_proof_block_hash(cur_tree_size)?)?
                             .get_path()
                             .last()
                             .ok_or_else(|| Error::Other("Merkle tree node missing".to_string()))?,
@@ -3721,11 +3938,8 @@ impl Chain {
             Ok(*hash)
         } else {
             if level == 0 {
-                let maybe_hash = if index >= tree_size {
-                    None
-                } else {
-                    Some(self.store().get_block_hash_from_ordinal(index)?)
-                };
+                let maybe_hash =
+                    if index >= tree_size { None } else { Some(self.get_proof_block_hash(index)?) };
                 tree_nodes.insert((index, level), maybe_hash);
                 Ok(maybe_hash)
             } else {
@@ -3757,8 +3971,8 @@ impl Chain {
         block_hash: &CryptoHash,
         head_block_hash: &CryptoHash,
     ) -> Result<MerklePath, Error> {
-        let leaf_index = self.store().get_block_merkle_tree(block_hash)?.size();
-        let tree_size = self.store().get_block_merkle_tree(head_block_hash)?.size();
+        let leaf_index = self.get_proof_merkle_tree(block_hash)?.size();
+        let tree_size = self.get_proof_merkle_tree(head_block_hash)?.size();
         if leaf_index >= tree_size {
             if block_hash == head_block_hash {
                 // special case if the block to prove is the same as head
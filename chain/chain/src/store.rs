@@ -12,7 +12,9 @@ use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::receipt::Receipt;
-use near_primitives::shard_layout::{account_id_to_shard_id, get_block_shard_uid, ShardUId};
+use near_primitives::shard_layout::{
+    account_id_to_shard_id, get_block_shard_uid, get_block_shard_uid_rev, ShardUId,
+};
 use near_primitives::sharding::{
     ChunkHash, EncodedShardChunk, PartialEncodedChunk, ReceiptProof, ShardChunk, ShardChunkHeader,
     StateSyncInfo,
@@ -34,12 +36,12 @@ use near_primitives::types::{
 use near_primitives::utils::{get_block_shard_id, index_to_bytes, to_timestamp};
 use near_primitives::views::LightClientBlockView;
 use near_store::{
-    DBCol, KeyForStateChanges, ShardTries, Store, StoreUpdate, WrappedTrieChanges, CHUNK_TAIL_KEY,
-    FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY, LARGEST_TARGET_HEIGHT_KEY,
-    LATEST_KNOWN_KEY, TAIL_KEY,
+    DBCol, KeyForStateChanges, ShardTries, Store, StoreUpdate, TrieChanges, WrappedTrieChanges,
+    CHUNK_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
+    LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, TAIL_KEY,
 };
 
-use crate::types::{Block, BlockHeader, LatestKnown};
+use crate::types::{Block, BlockHeader, LatestKnown, PinnedStateRoots};
 use crate::{byzantine_assert, RuntimeAdapter};
 use near_store::db::StoreStatistics;
 use std::sync::Arc;
@@ -69,6 +71,28 @@ fn get_height_shard_id(height: BlockHeight, shard_id: ShardId) -> Vec<u8> {
     res
 }
 
+/// The heights come first, so that iterating `DBCol::DeferredTrieChanges` goes from the lowest.
+fn get_deferred_trie_changes_key(
+    height: BlockHeight,
+    block_hash: &CryptoHash,
+    shard_uid: &ShardUId,
+) -> Vec<u8> {
+    let mut res = Vec::with_capacity(48);
+    res.extend_from_slice(&height.to_be_bytes());
+    res.extend_from_slice(&get_block_shard_uid(block_hash, shard_uid));
+    res
+}
+
+fn get_deferred_trie_changes_key_rev(key: &[u8]) -> Result<(BlockHeight, ShardUId), Error> {
+    if key.len() != 48 {
+        return Err(Error::Other(format!("wrong key length: {key:?}")));
+    }
+    let height = BlockHeight::from_be_bytes(key[..8].try_into().unwrap());
+    let (_, shard_uid) =
+        get_block_shard_uid_rev(&key[8..]).map_err(|err| Error::Other(err.to_string()))?;
+    Ok((height, shard_uid))
+}
+
 /// Accesses the chain store. Used to create atomic editable views that can be reverted.
 pub trait ChainStoreAccess {
     /// Returns underlaying store.
@@ -435,6 +459,36 @@ impl ChainStore {
             .collect()
     }
 
+    pub fn get_pinned_state_roots(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<PinnedStateRoots>, Error> {
+        Ok(self.store.get_ser(DBCol::PinnedStateRoots, block_hash.as_ref())?)
+    }
+
+    pub fn iterate_pinned_state_roots(&self) -> Result<Vec<(CryptoHash, PinnedStateRoots)>, Error> {
+        self.store
+            .iter(DBCol::PinnedStateRoots)
+            .map(|item| match item {
+                Ok((k, v)) => Ok((
+                    CryptoHash::try_from(k.as_ref()).map_err(|_| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("wrong key length: {k:?}"),
+                        )
+                    })?,
+                    PinnedStateRoots::try_from_slice(v.as_ref())?,
+                )),
+                Err(err) => Err(err.into()),
+            })
+            .collect()
+    }
+
+    /// The height of the lowest block whose state roots are pinned, if any.
+    pub fn get_min_pinned_state_root_height(&self) -> Result<Option<BlockHeight>, Error> {
+        Ok(self.iterate_pinned_state_roots()?.into_iter().map(|(_, pin)| pin.height).min())
+    }
+
     pub fn get_state_changes_for_split_states(
         &self,
         block_hash: &CryptoHash,
@@ -1736,7 +1790,7 @@ impl<'a> ChainStoreUpdate<'a> {
         } else {
             let old_merkle_tree = self.get_block_merkle_tree(&prev_hash)?;
             let mut new_merkle_tree = PartialMerkleTree::clone(&old_merkle_tree);
-            new_merkle_tree.insert(prev_hash);
+            new_merkle_tree.append(prev_hash);
             self.save_block_merkle_tree(*header.hash(), new_merkle_tree);
         }
         Ok(())
@@ -2047,6 +2101,40 @@ impl<'a> ChainStoreUpdate<'a> {
         shard_uids_to_gc
     }
 
+    /// Applies the deletions deferred by `clear_block_data` which are no longer held back by a
+    /// pinned state root, lowest blocks first and for at most `limit` blocks.
+    pub fn release_deferred_trie_changes(
+        &mut self,
+        tries: ShardTries,
+        limit: NumBlocks,
+    ) -> Result<(), Error> {
+        let min_pinned_height = self.chain_store.get_min_pinned_state_root_height()?;
+        let mut store_update = self.store().store_update();
+        let mut last_block_key = None;
+        let mut num_blocks = 0;
+        for item in self.store().iter(DBCol::DeferredTrieChanges) {
+            let (key, value) = item?;
+            let (height, shard_uid) = get_deferred_trie_changes_key_rev(&key)?;
+            if min_pinned_height.map_or(false, |pinned_height| pinned_height < height) {
+                break;
+            }
+            // There is a row per shard, the height and block hash come first in the key.
+            let block_key = &key[..40];
+            if last_block_key.as_deref() != Some(block_key) {
+                if num_blocks == limit {
+                    break;
+                }
+                num_blocks += 1;
+                last_block_key = Some(block_key.to_vec());
+            }
+            let trie_changes = TrieChanges::try_from_slice(&value)?;
+            tries.apply_deletions(&trie_changes, shard_uid, &mut store_update);
+            store_update.delete(DBCol::DeferredTrieChanges, &key);
+        }
+        self.merge(store_update);
+        Ok(())
+    }
+
     // Clearing block data of `block_hash`, if on a fork.
     // Clearing block data of `block_hash.prev`, if on the Canonical Chain.
     pub fn clear_block_data(
@@ -2079,14 +2167,29 @@ impl<'a> ChainStoreUpdate<'a> {
                     }
                 }
                 GCMode::Canonical(tries) => {
-                    // If the block is on canonical chain, we delete the state that's before applying this block
+                    // If the block is on canonical chain, we delete the state that's before applying this block.
+                    // The deletions are deferred while the state after a lower block is pinned, as
+                    // they may remove parts of it, see `release_deferred_trie_changes`.
+                    let height = self.get_block_header(&block_hash)?.height();
+                    let defer_deletions = self
+                        .chain_store
+                        .get_min_pinned_state_root_height()?
+                        .map_or(false, |pinned_height| pinned_height < height);
                     for shard_uid in shard_uids_to_gc {
                         let trie_changes = self.store().get_ser(
                             DBCol::TrieChanges,
                             &get_block_shard_uid(&block_hash, &shard_uid),
                         )?;
                         if let Some(trie_changes) = trie_changes {
-                            tries.apply_deletions(&trie_changes, shard_uid, &mut store_update);
+                            if defer_deletions {
+                                store_update.set_ser(
+                                    DBCol::DeferredTrieChanges,
+                                    &get_deferred_trie_changes_key(height, &block_hash, &shard_uid),
+                                    &trie_changes,
+                                )?;
+                            } else {
+                                tries.apply_deletions(&trie_changes, shard_uid, &mut store_update);
+                            }
                             self.gc_col(
                                 DBCol::TrieChanges,
                                 &get_block_shard_uid(&block_hash, &shard_uid),
@@ -2436,7 +2539,11 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::_LastBlockWithNewChunk
             | DBCol::_TransactionRefCount
             | DBCol::StateChangesForSplitStates
-            | DBCol::CachedContractCode => {
+            | DBCol::CachedContractCode
+            | DBCol::PinnedStateRoots
+            | DBCol::FlatContractData
+            | DBCol::FlatContractDataHead
+            | DBCol::DeferredTrieChanges => {
                 unreachable!();
             }
         }
@@ -2772,6 +2879,7 @@ impl<'a> ChainStoreUpdate<'a> {
         }
         for mut wrapped_trie_changes in self.trie_changes.drain(..) {
             wrapped_trie_changes.insertions_into(&mut store_update);
+            wrapped_trie_changes.flat_contract_data_into(&mut store_update)?;
             wrapped_trie_changes.state_changes_into(&mut store_update);
 
             if self.chain_store.save_trie_changes {
@@ -29,6 +29,7 @@ use near_primitives::transaction::{
     Action, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus,
     SignedTransaction, TransferAction,
 };
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
     AccountId, ApprovalStake, Balance, BlockHeight, EpochHeight, EpochId, Gas, Nonce, NumBlocks,
@@ -523,6 +524,15 @@ impl RuntimeAdapter for KeyValueRuntime {
         Ok(validators.iter().map(|x| (x.clone(), false)).collect())
     }
 
+    fn get_bp_hash_inputs(
+        &self,
+        epoch_id: &EpochId,
+        prev_epoch_id: &EpochId,
+    ) -> Result<(Vec<ValidatorStake>, ProtocolVersion), Error> {
+        let validators = self.get_block_producers(self.get_valset_for_epoch(epoch_id)?);
+        Ok((validators.to_vec(), self.get_epoch_protocol_version(prev_epoch_id)?))
+    }
+
     fn get_epoch_block_approvers_ordered(
         &self,
         parent_hash: &CryptoHash,
@@ -877,6 +887,7 @@ impl RuntimeAdapter for KeyValueRuntime {
                         tokens_burnt: 0,
                         executor_id: to.clone(),
                         metadata: ExecutionMetadata::V1,
+                        events: vec![],
                     },
                 });
             }
@@ -984,6 +995,10 @@ impl RuntimeAdapter for KeyValueRuntime {
                 kind: QueryResponseKind::ViewState(ViewStateResult {
                     values: Default::default(),
                     proof: vec![],
+                    next_key: None,
+                    proof_kind: None,
+                    truncated: false,
+                    account_state_size: None,
                 }),
                 block_height,
                 block_hash: *block_hash,
@@ -999,6 +1014,51 @@ impl RuntimeAdapter for KeyValueRuntime {
         }
     }
 
+    fn view_state_batch(
+        &self,
+        _shard_uid: ShardUId,
+        _state_root: &StateRoot,
+        block_height: BlockHeight,
+        block_hash: &CryptoHash,
+        _account_id: &AccountId,
+        _prefix: &[u8],
+        _from_key: Option<&[u8]>,
+        _max_items: u64,
+    ) -> Result<QueryResponse, near_chain_primitives::error::QueryError> {
+        Ok(QueryResponse {
+            kind: QueryResponseKind::ViewState(ViewStateResult {
+                values: Default::default(),
+                proof: vec![],
+                next_key: None,
+                proof_kind: None,
+                truncated: false,
+                account_state_size: None,
+            }),
+            block_height,
+            block_hash: *block_hash,
+        })
+    }
+
+    fn prove_trie_key(
+        &self,
+        _shard_uid: ShardUId,
+        _state_root: &StateRoot,
+        _block_height: BlockHeight,
+        _block_hash: &CryptoHash,
+        _key: &TrieKey,
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), near_chain_primitives::error::QueryError> {
+        Ok((None, vec![]))
+    }
+
+    fn update_flat_contract_data(
+        &self,
+        _shard_uid: ShardUId,
+        _block_hash: &CryptoHash,
+        _state_root: &StateRoot,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn obtain_state_part(
         &self,
         _shard_id: ShardId,
@@ -1190,6 +1250,8 @@ impl RuntimeAdapter for KeyValueRuntime {
             prev_epoch_kickout: vec![],
             epoch_start_height: 0,
             epoch_height: 1,
+            current_bp_hash: CryptoHash::default(),
+            next_bp_hash: CryptoHash::default(),
         })
     }
 
@@ -1,19 +1,24 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use crate::chain::Chain;
 use crate::test_utils::KeyValueRuntime;
 use crate::types::{ChainGenesis, Tip};
-use crate::DoomslugThresholdMode;
+use crate::{ChainStoreAccess, DoomslugThresholdMode};
 
 use near_chain_configs::GCConfig;
 use near_crypto::KeyType;
 use near_primitives::block::Block;
+use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::merkle::PartialMerkleTree;
 use near_primitives::shard_layout::ShardUId;
-use near_primitives::types::{NumBlocks, NumShards, StateRoot};
+use near_primitives::state_part::PartId;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{NumBlocks, NumShards, RawStateChangesWithTrieKey, StateRoot};
 use near_primitives::validator_signer::InMemoryValidatorSigner;
 use near_store::test_utils::{create_test_store, gen_changes};
-use near_store::{ShardTries, StoreUpdate, Trie, WrappedTrieChanges};
+use near_store::{DBCol, KeyForStateChanges, ShardTries, StoreUpdate, Trie, WrappedTrieChanges};
 use rand::Rng;
 
 fn get_chain(num_shards: NumShards) -> Chain {
@@ -724,3 +729,166 @@ fn test_fork_far_away_from_epoch_end() {
         );
     }
 }
+
+// Saves the state root of the only shard after the block, as pinning reads it from chunk extra.
+fn save_state_root(chain: &mut Chain, block: &Block, state_root: &StateRoot) {
+    let mut store_update = chain.mut_store().store_update();
+    store_update.save_chunk_extra(
+        block.hash(),
+        &ShardUId::single_shard(),
+        ChunkExtra::new_with_only_state_root(state_root),
+    );
+    store_update.commit().unwrap();
+}
+
+fn count_deferred_trie_changes(chain: &Chain) -> usize {
+    chain.store().store().iter(DBCol::DeferredTrieChanges).count()
+}
+
+fn get_trie_node_hashes(trie: &Trie, state_root: &StateRoot) -> HashSet<CryptoHash> {
+    if *state_root == Trie::empty_root() {
+        return HashSet::new();
+    }
+    let nodes = trie.get_trie_nodes_for_part(PartId::new(0, 1), state_root).unwrap();
+    nodes.0.iter().map(|node| hash(node)).collect()
+}
+
+#[test]
+fn test_gc_pinned_state_roots() {
+    let mut chain = get_chain(1);
+    let tries = chain.runtime_adapter.get_tries();
+    let shard_uid = ShardUId::single_shard();
+    let trie = tries.get_trie_for_shard(shard_uid);
+    let genesis = chain.get_block_by_height(0).unwrap();
+    let mut states = vec![(genesis.clone(), vec![Trie::empty_root()], vec![Vec::new()])];
+    do_fork(
+        genesis,
+        vec![Trie::empty_root()],
+        tries.clone(),
+        &mut chain,
+        100,
+        &mut states,
+        5,
+        false,
+    );
+
+    // Only final blocks can be pinned.
+    let (block, state_roots, _) = states[30].clone();
+    save_state_root(&mut chain, &block, &state_roots[0]);
+    assert!(chain.pin_state_roots(block.hash()).is_err());
+    let head = chain.head().unwrap();
+    let mut store_update = chain.mut_store().store_update();
+    store_update.save_final_head(&head).unwrap();
+    store_update.commit().unwrap();
+
+    let pinned = [10, 20];
+    let mut items = vec![];
+    for &i in pinned.iter() {
+        let (block, state_roots, _) = states[i].clone();
+        save_state_root(&mut chain, &block, &state_roots[0]);
+        chain.pin_state_roots(block.hash()).unwrap();
+        items.push(trie.iter(&state_roots[0]).unwrap().map(Result::unwrap).collect::<Vec<_>>());
+    }
+    chain
+        .clear_data(tries.clone(), &GCConfig { gc_blocks_limit: 1000, ..GCConfig::default() })
+        .unwrap();
+
+    // The blocks are garbage collected, but not their state: the deletions of the blocks above
+    // the lowest pin are deferred.
+    assert!(count_deferred_trie_changes(&chain) > 0);
+    for (&i, items) in pinned.iter().zip(items.iter()) {
+        let (block, state_roots, _) = &states[i];
+        assert!(!chain.block_exists(block.hash()).unwrap());
+        let pin = chain.store().get_pinned_state_roots(block.hash()).unwrap().unwrap();
+        assert_eq!(pin.state_roots, vec![(shard_uid, state_roots[0])]);
+        assert!(pin.manual);
+        let state_items =
+            trie.iter(&state_roots[0]).unwrap().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(&state_items, items);
+    }
+
+    // Once unpinned, the state of the first block is gone, except for the nodes shared with the
+    // state still in use.
+    let (block, state_roots, _) = &states[pinned[0]];
+    let nodes = get_trie_node_hashes(&trie, &state_roots[0]);
+    chain.unpin_state_roots(block.hash()).unwrap();
+    assert!(chain.unpin_state_roots(block.hash()).is_err());
+    // The deferred deletions are released by the following garbage collections, a bounded number
+    // of blocks at a time.
+    let num_deferred = count_deferred_trie_changes(&chain);
+    chain
+        .clear_data(tries.clone(), &GCConfig { gc_blocks_limit: 1, ..GCConfig::default() })
+        .unwrap();
+    assert_eq!(count_deferred_trie_changes(&chain), num_deferred - 1);
+    chain
+        .clear_data(tries.clone(), &GCConfig { gc_blocks_limit: 1000, ..GCConfig::default() })
+        .unwrap();
+    // The deletions of the blocks above the other pin are still deferred.
+    let mut kept_nodes = HashSet::new();
+    for (i, (block, state_roots, _)) in states.iter().enumerate() {
+        if i >= pinned[1] || chain.block_exists(block.hash()).unwrap() {
+            kept_nodes.extend(get_trie_node_hashes(&trie, &state_roots[0]));
+        }
+    }
+    for hash in nodes.difference(&kept_nodes) {
+        assert_eq!(tries.get_trie_node(shard_uid, hash).unwrap(), None);
+    }
+    let pins = chain.store().iterate_pinned_state_roots().unwrap();
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0].0, *states[pinned[1]].0.hash());
+
+    assert!(count_deferred_trie_changes(&chain) > 0);
+    chain.unpin_state_roots(states[pinned[1]].0.hash()).unwrap();
+    chain
+        .clear_data(tries.clone(), &GCConfig { gc_blocks_limit: 1000, ..GCConfig::default() })
+        .unwrap();
+    assert_eq!(count_deferred_trie_changes(&chain), 0);
+}
+
+#[test]
+fn test_pin_latest_state_roots() {
+    let mut chain = get_chain(1);
+    let tries = chain.runtime_adapter.get_tries();
+    let genesis = chain.get_block_by_height(0).unwrap();
+    let mut states = vec![(genesis.clone(), vec![Trie::empty_root()], vec![Vec::new()])];
+    do_fork(
+        genesis,
+        vec![Trie::empty_root()],
+        tries.clone(),
+        &mut chain,
+        30,
+        &mut states,
+        5,
+        false,
+    );
+
+    // "alice" changes in blocks 10 to 14, "alice.near" in block 15.
+    let mut store_update = chain.store().store().store_update();
+    for (i, account_id) in (10..15).map(|i| (i, "alice")).chain(std::iter::once((15, "alice.near")))
+    {
+        let (block, state_roots, _) = states[i].clone();
+        save_state_root(&mut chain, &block, &state_roots[0]);
+        let trie_key = TrieKey::Account { account_id: account_id.parse().unwrap() };
+        let key = KeyForStateChanges::from_trie_key(block.hash(), &trie_key);
+        let changes = RawStateChangesWithTrieKey { trie_key, changes: vec![] };
+        store_update.set_ser(DBCol::StateChanges, key.as_ref(), &changes).unwrap();
+    }
+    store_update.commit().unwrap();
+
+    let gc_config = GCConfig {
+        pinned_state_root_accounts: vec!["alice".parse().unwrap()],
+        num_pinned_state_roots: 2,
+        ..GCConfig::default()
+    };
+    chain.pin_latest_state_roots(states[5].0.hash(), &gc_config).unwrap();
+    chain.pin_latest_state_roots(states[20].0.hash(), &gc_config).unwrap();
+    let mut pins = chain.store().iterate_pinned_state_roots().unwrap();
+    pins.sort_by_key(|(_, pin)| pin.height);
+    assert_eq!(pins.len(), 2);
+    for ((block_hash, pin), i) in pins.iter().zip([13, 14]) {
+        assert_eq!(block_hash, states[i].0.hash());
+        assert_eq!(pin.height, i as u64);
+        assert_eq!(pin.accounts, vec!["alice".parse().unwrap()]);
+        assert!(!pin.manual);
+    }
+}
@@ -26,6 +26,7 @@ use near_primitives::shard_layout::{ShardLayout, ShardUId};
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::state_part::PartId;
 use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
     AccountId, ApprovalStake, Balance, BlockHeight, BlockHeightDelta, EpochHeight, EpochId, Gas,
@@ -403,6 +404,15 @@ pub trait RuntimeAdapter: Send + Sync {
         last_known_block_hash: &CryptoHash,
     ) -> Result<Vec<(ValidatorStake, bool)>, Error>;
 
+    /// Unique block producers of `epoch_id` in settlement order and the protocol version of
+    /// `prev_epoch_id`, from which `BlockHeader::compute_bp_hash` computes the `next_bp_hash` of
+    /// the blocks of `prev_epoch_id`.
+    fn get_bp_hash_inputs(
+        &self,
+        epoch_id: &EpochId,
+        prev_epoch_id: &EpochId,
+    ) -> Result<(Vec<ValidatorStake>, ProtocolVersion), Error>;
+
     fn get_epoch_block_approvers_ordered(
         &self,
         parent_hash: &CryptoHash,
@@ -690,6 +700,45 @@ pub trait RuntimeAdapter: Send + Sync {
         request: &QueryRequest,
     ) -> Result<QueryResponse, near_chain_primitives::error::QueryError>;
 
+    /// Returns up to `max_items` items of the contract state of `account_id` under `prefix`,
+    /// strictly after `from_key`. Items are streamed from the state, so that the state of an
+    /// account of any size can be read in batches. `next_key` of the result is set when more
+    /// items follow.
+    fn view_state_batch(
+        &self,
+        shard_uid: ShardUId,
+        state_root: &StateRoot,
+        block_height: BlockHeight,
+        block_hash: &CryptoHash,
+        account_id: &AccountId,
+        prefix: &[u8],
+        from_key: Option<&[u8]>,
+        max_items: u64,
+    ) -> Result<QueryResponse, near_chain_primitives::error::QueryError>;
+
+    /// Returns the value stored under the trie `key` under `state_root`, `None` if there is none,
+    /// along with the trie nodes on the path to it, in order from the root, which prove the value
+    /// or its absence.
+    fn prove_trie_key(
+        &self,
+        shard_uid: ShardUId,
+        state_root: &StateRoot,
+        block_height: BlockHeight,
+        block_hash: &CryptoHash,
+        key: &TrieKey,
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), near_chain_primitives::error::QueryError>;
+
+    /// Starts bringing the flat index of the contract data of the shard, if it is enabled, to
+    /// `state_root`, the state after `block_hash`, unless it's already there. The index is
+    /// updated in the background, from the state changes of the blocks on the way or by
+    /// rebuilding it from the trie, and isn't used until then.
+    fn update_flat_contract_data(
+        &self,
+        shard_uid: ShardUId,
+        block_hash: &CryptoHash,
+        state_root: &StateRoot,
+    ) -> Result<(), Error>;
+
     fn get_validator_info(
         &self,
         epoch_id: ValidatorInfoIdentifier,
@@ -786,6 +835,24 @@ pub struct LatestKnown {
     pub seen: u64,
 }
 
+/// State roots of a block kept from garbage collection, along with what keeps them.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PinnedStateRoots {
+    pub height: BlockHeight,
+    /// Roots of the state after the block of the shards this node has the state of.
+    pub state_roots: Vec<(ShardUId, StateRoot)>,
+    /// Whether the roots were pinned by `Chain::pin_state_roots`.
+    pub manual: bool,
+    /// Accounts changed by the block which are pinned by `Chain::pin_latest_state_roots`.
+    pub accounts: Vec<AccountId>,
+}
+
+impl PinnedStateRoots {
+    pub fn is_pinned(&self) -> bool {
+        self.manual || !self.accounts.is_empty()
+    }
+}
+
 /// Either an epoch id or latest block hash.  When `EpochId` variant is used it
 /// must be an identifier of a past epoch.  When `BlockHeight` is used it must
 /// be hash of the latest block in the current epoch.  Using current epoch id
@@ -863,6 +930,7 @@ mod tests {
                 tokens_burnt: 10000,
                 executor_id: "alice".parse().unwrap(),
                 metadata: ExecutionMetadata::V1,
+                events: vec![],
             },
         };
         let outcome2 = ExecutionOutcomeWithId {
@@ -875,6 +943,7 @@ mod tests {
                 tokens_burnt: 0,
                 executor_id: "bob".parse().unwrap(),
                 metadata: ExecutionMetadata::V1,
+                events: vec![],
             },
         };
         let outcomes = vec![outcome1, outcome2];
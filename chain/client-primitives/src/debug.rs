@@ -9,8 +9,9 @@ use chrono::DateTime;
 use near_primitives::{
     block_header::ApprovalInner,
     hash::CryptoHash,
+    shard_layout::ShardUId,
     sharding::ChunkHash,
-    types::{AccountId, BlockHeight},
+    types::{AccountId, BlockHeight, StateRoot},
     views::ValidatorInfo,
 };
 use serde::{Deserialize, Serialize};
@@ -169,3 +170,55 @@ pub enum DebugStatusResponse {
     // Detailed information about the validator (approvals, block & chunk production etc.)
     ValidatorStatus(ValidatorStatus),
 }
+
+/// Manages the state roots kept from garbage collection.
+#[derive(Debug)]
+pub enum StateRootPins {
+    // Keeps the state roots of the block from garbage collection.
+    Pin(CryptoHash),
+    // Releases the state roots of the block pinned with `Pin`.
+    Unpin(CryptoHash),
+    // Lists all pinned state roots, ordered by block hash.
+    List,
+}
+
+impl Message for StateRootPins {
+    type Result = Result<Vec<PinnedStateRootsView>, StateRootPinsError>;
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PinnedStateRootsView {
+    pub block_hash: CryptoHash,
+    pub height: BlockHeight,
+    pub state_roots: Vec<(ShardUId, StateRoot)>,
+    // Whether the state roots were pinned with `StateRootPins::Pin`.
+    pub manual: bool,
+    // Accounts from `pinned_state_root_accounts` that changed in this block.
+    pub accounts: Vec<AccountId>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StateRootPinsError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("State roots are not available: {0}")]
+    UnknownStateRoots(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for StateRootPinsError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            near_chain_primitives::Error::DBNotFoundErr(error_message) => {
+                Self::UnknownStateRoots(error_message)
+            }
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
@@ -13,16 +13,17 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::sharding::ChunkHash;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockHeightDelta, BlockReference, EpochId, EpochReference,
+    MaybeBlockId, ShardId, TransactionOrReceiptId,
 };
 use near_primitives::utils::generate_random_string;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
-    QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView, StateChangesRequestView,
-    StateChangesView,
+    BlockView, ChunkStateRootProofView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
+    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, GasPriceRangeView, GasPriceView,
+    LightClientBlockLiteView, LightClientBlockView, NextValidatorSetView, ProducerScheduleView,
+    QueryRequest, QueryResponse, ReceiptView, StateChangeProofView, StateChangesKindsView,
+    StateChangesRequestView, StateChangesView, StateProofView, TrieNodeResponse, TxExecutionStatus,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use serde::Serialize;
@@ -221,6 +222,14 @@ impl Message for GetChunk {
     type Result = Result<ChunkView, GetChunkError>;
 }
 
+/// Proof of inclusion of the header of the chunk `GetChunk` refers to in a block. A chunk given
+/// by its hash is looked up in the canonical block after its previous block.
+pub struct GetChunkStateRootProof(pub GetChunk);
+
+impl Message for GetChunkStateRootProof {
+    type Result = Result<ChunkStateRootProofView, GetChunkError>;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetChunkError {
     #[error("IO Error: {error_message}")]
@@ -305,9 +314,13 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
-    #[error("State of contract {contract_account_id} is too large to be viewed")]
+    #[error(
+        "State of contract {contract_account_id} is too large to be viewed: {state_size} bytes, the limit is {state_size_limit}"
+    )]
     TooLargeContractState {
         contract_account_id: near_primitives::types::AccountId,
+        state_size: u64,
+        state_size_limit: u64,
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
@@ -326,11 +339,12 @@ pub enum QueryError {
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
     #[error(
-        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+        "The data for block #{block_height} is garbage collected on this node (data is kept from block #{gc_horizon}), use an archival node to fetch historical data"
     )]
     GarbageCollectedBlock {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
+        gc_horizon: near_primitives::types::BlockHeight,
     },
     #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
     UnknownBlock { block_reference: near_primitives::types::BlockReference },
@@ -342,6 +356,92 @@ pub enum QueryError {
     Unreachable { error_message: String },
 }
 
+impl From<near_chain_primitives::error::QueryError> for QueryError {
+    fn from(error: near_chain_primitives::error::QueryError) -> Self {
+        match error {
+            near_chain_primitives::error::QueryError::InternalError { error_message, .. } => {
+                Self::InternalError { error_message }
+            }
+            near_chain_primitives::error::QueryError::InvalidAccount {
+                requested_account_id,
+                block_height,
+                block_hash,
+            } => Self::InvalidAccount { requested_account_id, block_height, block_hash },
+            near_chain_primitives::error::QueryError::UnknownAccount {
+                requested_account_id,
+                block_height,
+                block_hash,
+            } => Self::UnknownAccount { requested_account_id, block_height, block_hash },
+            near_chain_primitives::error::QueryError::NoContractCode {
+                contract_account_id,
+                block_height,
+                block_hash,
+            } => Self::NoContractCode { contract_account_id, block_height, block_hash },
+            near_chain_primitives::error::QueryError::UnknownAccessKey {
+                public_key,
+                block_height,
+                block_hash,
+            } => Self::UnknownAccessKey { public_key, block_height, block_hash },
+            near_chain_primitives::error::QueryError::ContractExecutionError {
+                error_message,
+                block_hash,
+                block_height,
+            } => Self::ContractExecutionError { vm_error: error_message, block_height, block_hash },
+            near_chain_primitives::error::QueryError::TooLargeContractState {
+                requested_account_id,
+                state_size,
+                state_size_limit,
+                block_height,
+                block_hash,
+            } => Self::TooLargeContractState {
+                contract_account_id: requested_account_id,
+                state_size,
+                state_size_limit,
+                block_height,
+                block_hash,
+            },
+        }
+    }
+}
+
+/// Reads a batch of the contract state of an account, with at most `max_items` items whose keys
+/// start with `prefix` and come strictly after `from_key`. Unlike `QueryRequest::ViewState`, it
+/// works for accounts of any size: passing the decoded `next_key` of a result as `from_key` reads
+/// the next batch.
+#[derive(Clone, Debug)]
+pub struct GetStateBatch {
+    pub block_reference: BlockReference,
+    pub account_id: AccountId,
+    pub prefix: Vec<u8>,
+    pub from_key: Option<Vec<u8>>,
+    pub max_items: u64,
+}
+
+impl Message for GetStateBatch {
+    type Result = Result<QueryResponse, QueryError>;
+}
+
+/// Proves the value, or the absence, of the single contract data `key` of `account_id` at a
+/// block.
+#[derive(Clone, Debug)]
+pub struct GetStateProof {
+    pub block_reference: BlockReference,
+    pub account_id: AccountId,
+    pub key: Vec<u8>,
+}
+
+/// The proof of `GetStateProof`, at the block with the given height and hash.
+#[derive(Clone, Debug)]
+pub struct StateProofResponse {
+    pub state_proof: StateProofView,
+    pub block_height: BlockHeight,
+    pub block_hash: CryptoHash,
+}
+
+impl Message for GetStateProof {
+    type Result = Result<StateProofResponse, QueryError>;
+}
+
 pub struct Status {
     pub is_health_check: bool,
     // If true - return more detailed information about the current status (recent blocks etc).
@@ -443,12 +543,27 @@ impl Message for GetGasPrice {
     type Result = Result<GasPriceView, GetGasPriceError>;
 }
 
+/// Maximum number of heights covered by one `GetGasPriceRange` response.
+pub const MAX_GAS_PRICE_RANGE_HEIGHTS: BlockHeightDelta = 2000;
+
+/// Gas prices of the canonical chain from `from_height` to `to_height` inclusive.
+pub struct GetGasPriceRange {
+    pub from_height: BlockHeight,
+    pub to_height: BlockHeight,
+}
+
+impl Message for GetGasPriceRange {
+    type Result = Result<GasPriceRangeView, GetGasPriceError>;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetGasPriceError {
     #[error("Internal error: {error_message}")]
     InternalError { error_message: String },
     #[error("Block either has never been observed on the node or has been garbage collected: {error_message}")]
     UnknownBlock { error_message: String },
+    #[error("Invalid height range from {from_height} to {to_height}")]
+    InvalidHeightRange { from_height: BlockHeight, to_height: BlockHeight },
     // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
     // expected cases, we cannot statically guarantee that no other errors will be returned
     // in the future.
@@ -518,6 +633,42 @@ impl Message for TxStatus {
     type Result = Result<Option<FinalExecutionOutcomeViewEnum>, TxStatusError>;
 }
 
+/// Progress of a transaction on the node, to wait until it reaches a `TxExecutionStatus`.
+pub struct GetTxExecutionProgress {
+    pub tx_hash: CryptoHash,
+    pub signer_account_id: AccountId,
+}
+
+#[derive(Debug)]
+pub struct TxExecutionProgress {
+    /// Outcome of the transaction, once it is included in a block.
+    pub transaction_outcome: Option<ExecutionOutcomeWithIdView>,
+    /// Outcomes of the transaction and of all its receipts, once they are all executed.
+    pub final_outcome: Option<FinalExecutionOutcomeView>,
+    /// Whether the block including the transaction is final.
+    pub transaction_final: bool,
+    /// Whether the blocks of all the receipt outcomes are final, once they are all executed.
+    pub receipts_final: bool,
+}
+
+impl TxExecutionProgress {
+    pub fn reached(&self, status: TxExecutionStatus) -> bool {
+        let executed = self.final_outcome.is_some();
+        match status {
+            TxExecutionStatus::None => true,
+            TxExecutionStatus::Included => self.transaction_outcome.is_some(),
+            TxExecutionStatus::IncludedFinal => self.transaction_final,
+            TxExecutionStatus::ExecutedOptimistic => executed,
+            TxExecutionStatus::ExecutedFinal => executed && self.transaction_final,
+            TxExecutionStatus::Final => executed && self.transaction_final && self.receipts_final,
+        }
+    }
+}
+
+impl Message for GetTxExecutionProgress {
+    type Result = Result<TxExecutionProgress, TxStatusError>;
+}
+
 pub struct GetValidatorInfo {
     pub epoch_reference: EpochReference,
 }
@@ -561,6 +712,25 @@ impl Message for GetValidatorOrdered {
     type Result = Result<Vec<ValidatorStakeView>, GetValidatorInfoError>;
 }
 
+/// Block and chunk producers of each height of an epoch. The current and next epochs are known,
+/// as well as the past ones the node has the validator info of.
+pub struct GetProducerSchedule {
+    pub epoch_reference: EpochReference,
+}
+
+impl Message for GetProducerSchedule {
+    type Result = Result<ProducerScheduleView, GetValidatorInfoError>;
+}
+
+/// Block producers of the next epoch of a block, which its `next_bp_hash` commits to.
+pub struct GetNextValidatorSet {
+    pub block_reference: BlockReference,
+}
+
+impl Message for GetNextValidatorSet {
+    type Result = Result<NextValidatorSetView, GetBlockError>;
+}
+
 pub struct GetStateChanges {
     pub block_hash: CryptoHash,
     pub state_changes_request: StateChangesRequestView,
@@ -600,6 +770,28 @@ impl Message for GetStateChanges {
     type Result = Result<StateChangesView, GetStateChangesError>;
 }
 
+/// Like `GetStateChanges`, but also proves the values the changed keys have after the block.
+/// Returns the changes starting at `cursor`, at most a page of them.
+pub struct GetStateChangesWithProof {
+    pub block_hash: CryptoHash,
+    pub state_changes_request: StateChangesRequestView,
+    pub cursor: u64,
+}
+
+/// The changes of `GetStateChangesWithProof`, with the proofs of the data, account and access
+/// key changes that are the last change of their key in the block.
+#[derive(Debug)]
+pub struct StateChangesWithProofResponse {
+    pub changes: StateChangesView,
+    pub proofs: Vec<StateChangeProofView>,
+    /// Where the next page starts, `None` if this is the last one.
+    pub next_cursor: Option<u64>,
+}
+
+impl Message for GetStateChangesWithProof {
+    type Result = Result<StateChangesWithProofResponse, GetStateChangesError>;
+}
+
 pub struct GetStateChangesInBlock {
     pub block_hash: CryptoHash,
 }
@@ -713,6 +905,15 @@ pub struct GetBlockProofResponse {
 pub enum GetBlockProofError {
     #[error("Block either has never been observed on the node or has been garbage collected: {error_message}")]
     UnknownBlock { error_message: String },
+    #[error("Partial merkle tree of block {block_hash} is not available on the node")]
+    MissingBlockMerkleTree { block_hash: near_primitives::hash::CryptoHash },
+    #[error("Hash of the block with ordinal {block_ordinal} is not available on the node")]
+    MissingBlockOrdinal { block_ordinal: near_primitives::types::NumBlocks },
+    #[error("Block {block_hash} is after head block {head_block_hash}")]
+    BlockAfterHead {
+        block_hash: near_primitives::hash::CryptoHash,
+        head_block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("Internal error: {error_message}")]
     InternalError { error_message: String },
     // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
@@ -729,6 +930,14 @@ impl From<near_chain_primitives::error::Error> for GetBlockProofError {
             near_chain_primitives::error::Error::DBNotFoundErr(error_message) => {
                 Self::UnknownBlock { error_message }
             }
+            near_chain_primitives::error::Error::MissingBlockMerkleData(missing) => match missing {
+                near_chain_primitives::error::MissingBlockMerkleData::PartialMerkleTree(
+                    block_hash,
+                ) => Self::MissingBlockMerkleTree { block_hash },
+                near_chain_primitives::error::MissingBlockMerkleData::BlockOrdinal(
+                    block_ordinal,
+                ) => Self::MissingBlockOrdinal { block_ordinal },
+            },
             near_chain_primitives::error::Error::Other(error_message) => {
                 Self::InternalError { error_message }
             }
@@ -772,18 +981,64 @@ impl Message for GetReceipt {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 }
 
+/// Reads the trie node, or value, stored under `hash` in the state of shard `shard_id`, in the
+/// shard layout of the head. Used for debugging.
+pub struct GetTrieNode {
+    pub shard_id: ShardId,
+    pub hash: CryptoHash,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetTrieNodeError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Shard {0} does not exist")]
+    UnknownShard(ShardId),
+    #[error("Trie nodes are not served by validator nodes")]
+    ValidatorNode,
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetTrieNodeError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+impl Message for GetTrieNode {
+    type Result = Result<Option<TrieNodeResponse>, GetTrieNodeError>;
+}
+
 pub struct GetProtocolConfig(pub BlockReference);
 
 impl Message for GetProtocolConfig {
     type Result = Result<ProtocolConfigView, GetProtocolConfigError>;
 }
 
+/// Protocol config in force during an epoch. Unlike [`GetProtocolConfig`], it doesn't need a
+/// block of the epoch, so any epoch known to the epoch manager can be requested.
+pub struct GetEpochProtocolConfig(pub EpochId);
+
+impl Message for GetEpochProtocolConfig {
+    type Result = Result<ProtocolConfigView, GetProtocolConfigError>;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetProtocolConfigError {
     #[error("IO Error: {0}")]
     IOError(String),
     #[error("Block has never been observed: {0}")]
     UnknownBlock(String),
+    #[error("Epoch is unknown: {0}")]
+    UnknownEpoch(String),
     // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
     // expected cases, we cannot statically guarantee that no other errors will be returned
     // in the future.
@@ -797,6 +1052,9 @@ impl From<near_chain_primitives::Error> for GetProtocolConfigError {
         match error {
             near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
             near_chain_primitives::Error::DBNotFoundErr(s) => Self::UnknownBlock(s),
+            near_chain_primitives::Error::EpochOutOfBounds(epoch_id) => {
+                Self::UnknownEpoch(format!("{:?}", epoch_id))
+            }
             _ => Self::Unreachable(error.to_string()),
         }
     }
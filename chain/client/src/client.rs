@@ -505,7 +505,7 @@ impl Client {
         // Get block extra from previous block.
         let block_merkle_tree = self.chain.store().get_block_merkle_tree(&prev_hash)?;
         let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-        block_merkle_tree.insert(prev_hash);
+        block_merkle_tree.append(prev_hash);
         let block_merkle_root = block_merkle_tree.root();
         // The number of leaves in Block Merkle Tree is the amount of Blocks on the Canonical Chain by construction.
         // The ordinal of the next Block will be equal to this amount plus one.
@@ -1150,12 +1150,20 @@ impl Client {
                 let result = if self.config.archive {
                     self.chain.clear_archive_data(self.config.gc.gc_blocks_limit)
                 } else {
+                    let result =
+                        self.chain.pin_latest_state_roots(last_final_block, &self.config.gc);
+                    log_assert!(result.is_ok(), "Can't pin state roots, {:?}", result);
                     let tries = self.runtime_adapter.get_tries();
                     self.chain.clear_data(tries, &self.config.gc)
                 };
                 log_assert!(result.is_ok(), "Can't clear old data, {:?}", result);
             }
 
+            if self.config.flat_contract_data_index {
+                let result = self.chain.update_flat_contract_data();
+                log_assert!(result.is_ok(), "Can't update flat contract data, {:?}", result);
+            }
+
             if self.runtime_adapter.is_next_block_epoch_start(block.hash()).unwrap_or(false) {
                 let next_epoch_protocol_version = unwrap_or_return!(self
                     .runtime_adapter
@@ -7,8 +7,8 @@ use borsh::BorshSerialize;
 use near_chain::crypto_hash_timer::CryptoHashTimer;
 use near_chain::{near_chain_primitives, ChainStoreAccess};
 use near_client_primitives::debug::{
-    BlockProduction, ChunkProduction, DebugStatus, DebugStatusResponse, ProductionAtHeight,
-    ValidatorStatus,
+    BlockProduction, ChunkProduction, DebugStatus, DebugStatusResponse, PinnedStateRootsView,
+    ProductionAtHeight, StateRootPins, StateRootPinsError, ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -64,6 +64,33 @@ impl Handler<DebugStatus> for ClientActor {
     }
 }
 
+impl Handler<StateRootPins> for ClientActor {
+    type Result = Result<Vec<PinnedStateRootsView>, StateRootPinsError>;
+
+    #[perf]
+    fn handle(&mut self, msg: StateRootPins, _ctx: &mut Context<Self>) -> Self::Result {
+        match msg {
+            StateRootPins::Pin(block_hash) => self.client.chain.pin_state_roots(&block_hash)?,
+            StateRootPins::Unpin(block_hash) => self.client.chain.unpin_state_roots(&block_hash)?,
+            StateRootPins::List => {}
+        }
+        Ok(self
+            .client
+            .chain
+            .store()
+            .iterate_pinned_state_roots()?
+            .into_iter()
+            .map(|(block_hash, pin)| PinnedStateRootsView {
+                block_hash,
+                height: pin.height,
+                state_roots: pin.state_roots,
+                manual: pin.manual,
+                accounts: pin.accounts,
+            })
+            .collect())
+    }
+}
+
 impl ClientActor {
     // Gets a list of block producers and chunk-only producers for a given epoch.
     fn get_producers_for_epoch(
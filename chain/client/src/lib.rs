@@ -1,13 +1,16 @@
 pub use near_client_primitives::types::{
     Error, GetBlock, GetBlockHash, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree,
-    GetChunk, GetExecutionOutcome, GetExecutionOutcomeResponse, GetExecutionOutcomesForBlock,
-    GetGasPrice, GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt,
-    GetStateChanges, GetStateChangesInBlock, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfo, GetValidatorOrdered, Query,
-    QueryError, Status, StatusResponse, SyncStatus, TxStatus, TxStatusError,
+    GetChunk, GetChunkStateRootProof, GetEpochProtocolConfig, GetExecutionOutcome,
+    GetExecutionOutcomeResponse, GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceRange,
+    GetNetworkInfo, GetNextLightClientBlock, GetNextValidatorSet, GetProducerSchedule,
+    GetProtocolConfig, GetReceipt, GetStateBatch, GetStateChanges, GetStateChangesInBlock,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetStateChangesWithProof, GetStateProof, GetTrieNode, GetTxExecutionProgress, GetValidatorInfo,
+    GetValidatorOrdered, Query, QueryError, Status, StatusResponse, SyncStatus,
+    TxExecutionProgress, TxStatus, TxStatusError,
 };
 
-pub use near_client_primitives::debug::DebugStatus;
+pub use near_client_primitives::debug::{DebugStatus, StateRootPins};
 
 pub use crate::client::Client;
 pub use crate::client_actor::{start_client, ClientActor};
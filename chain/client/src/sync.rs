@@ -1510,7 +1510,7 @@ mod test {
                 block_merkle_tree.root(),
                 None,
             );
-            block_merkle_tree.insert(*block.hash());
+            block_merkle_tree.append(*block.hash());
 
             all_blocks.push(block);
 
@@ -1489,7 +1489,7 @@ impl TestEnv {
                 last_block.header().prev_hash(),
                 last_block.header().hash(),
                 last_block.header().epoch_id(),
-                &QueryRequest::ViewAccount { account_id },
+                &QueryRequest::ViewAccount { account_id, include_proof: false },
             )
             .unwrap();
         match response.kind {
@@ -1512,7 +1512,16 @@ impl TestEnv {
                 last_block.header().prev_hash(),
                 last_block.header().hash(),
                 last_block.header().epoch_id(),
-                &QueryRequest::ViewState { account_id, prefix: vec![].into() },
+                &QueryRequest::ViewState {
+                    account_id,
+                    prefix: vec![].into(),
+                    from_key: None,
+                    limit: None,
+                    max_value_size: None,
+                    state_size_limit: None,
+                    include_proof: true,
+                    allow_partial: false,
+                },
             )
             .unwrap();
         match response.kind {
@@ -1671,7 +1680,7 @@ pub fn create_chunk(
     }
     let block_merkle_tree = client.chain.store().get_block_merkle_tree(last_block.hash()).unwrap();
     let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-    block_merkle_tree.insert(*last_block.hash());
+    block_merkle_tree.append(*last_block.hash());
     let block = Block::produce(
         PROTOCOL_VERSION,
         PROTOCOL_VERSION,
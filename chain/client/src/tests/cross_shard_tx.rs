@@ -76,7 +76,10 @@ fn test_keyvalue_runtime_balances() {
                     .1
                     .send(Query::new(
                         BlockReference::latest(),
-                        QueryRequest::ViewAccount { account_id: flat_validators[i].clone() },
+                        QueryRequest::ViewAccount {
+                            account_id: flat_validators[i].clone(),
+                            include_proof: false,
+                        },
                     ))
                     .then(move |res| {
                         let query_response = res.unwrap().unwrap();
@@ -195,7 +198,10 @@ fn test_cross_shard_tx_callback(
                     .1
                     .send(Query::new(
                         BlockReference::latest(),
-                        QueryRequest::ViewAccount { account_id: account_id.clone() },
+                        QueryRequest::ViewAccount {
+                            account_id: account_id.clone(),
+                            include_proof: false,
+                        },
                     ))
                     .then(move |x| {
                         test_cross_shard_tx_callback(
@@ -291,7 +297,10 @@ fn test_cross_shard_tx_callback(
                             .1
                             .send(Query::new(
                                 BlockReference::latest(),
-                                QueryRequest::ViewAccount { account_id: validators[i].clone() },
+                                QueryRequest::ViewAccount {
+                                    account_id: validators[i].clone(),
+                                    include_proof: false,
+                                },
                             ))
                             .then(move |x| {
                                 test_cross_shard_tx_callback(
@@ -343,7 +352,10 @@ fn test_cross_shard_tx_callback(
                     .1
                     .send(Query::new(
                         BlockReference::latest(),
-                        QueryRequest::ViewAccount { account_id: account_id.clone() },
+                        QueryRequest::ViewAccount {
+                            account_id: account_id.clone(),
+                            include_proof: false,
+                        },
                     ))
                     .then(move |x| {
                         test_cross_shard_tx_callback(
@@ -473,7 +485,10 @@ fn test_cross_shard_tx_common(
                     .1
                     .send(Query::new(
                         BlockReference::latest(),
-                        QueryRequest::ViewAccount { account_id: flat_validators[i].clone() },
+                        QueryRequest::ViewAccount {
+                            account_id: flat_validators[i].clone(),
+                            include_proof: false,
+                        },
                     ))
                     .then(move |x| {
                         test_cross_shard_tx_callback(
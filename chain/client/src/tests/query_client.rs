@@ -6,11 +6,12 @@ use std::time::Duration;
 
 use crate::test_utils::{setup_mock_all_validators, setup_no_network, setup_only_view};
 use crate::{
-    GetBlock, GetBlockWithMerkleTree, GetExecutionOutcomesForBlock, Query, QueryError, Status,
-    TxStatus,
+    GetBlock, GetBlockWithMerkleTree, GetExecutionOutcomesForBlock, GetGasPriceRange, Query,
+    QueryError, Status, TxStatus,
 };
 use near_actix_test_utils::run_actix;
 use near_chain_configs::DEFAULT_GC_NUM_EPOCHS_TO_KEEP;
+use near_client_primitives::types::GetGasPriceError;
 use near_crypto::{InMemorySigner, KeyType};
 use near_logger_utils::init_test_logger;
 use near_network::test_utils::MockPeerManagerAdapter;
@@ -42,7 +43,10 @@ fn query_client() {
             view_client
                 .send(Query::new(
                     BlockReference::latest(),
-                    QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                    QueryRequest::ViewAccount {
+                        account_id: "test".parse().unwrap(),
+                        include_proof: false,
+                    },
                 ))
                 .then(|res| {
                     match res.unwrap().unwrap().kind {
@@ -70,7 +74,7 @@ fn query_status_not_crash() {
             let (block, block_merkle_tree) = res.unwrap().unwrap();
             let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
             let header: BlockHeader = block.header.clone().into();
-            block_merkle_tree.insert(*header.hash());
+            block_merkle_tree.append(*header.hash());
             let mut next_block = Block::produce(
                 PROTOCOL_VERSION,
                 PROTOCOL_VERSION,
@@ -301,6 +305,7 @@ fn test_garbage_collection() {
                                         BlockReference::BlockId(BlockId::Height(prev_height)),
                                         QueryRequest::ViewAccount {
                                             account_id: "test1".parse().unwrap(),
+                                            include_proof: false,
                                         },
                                     ))
                                     .then(move |res| {
@@ -321,6 +326,7 @@ fn test_garbage_collection() {
                                     BlockReference::BlockId(BlockId::Height(1)),
                                     QueryRequest::ViewAccount {
                                         account_id: "test1".parse().unwrap(),
+                                        include_proof: false,
                                     },
                                 ))
                                 .then(move |res| {
@@ -328,7 +334,11 @@ fn test_garbage_collection() {
                                     match res {
                                         Err(err) => assert!(matches!(
                                             err,
-                                            QueryError::GarbageCollectedBlock { .. }
+                                            QueryError::GarbageCollectedBlock {
+                                                block_height: 1,
+                                                gc_horizon,
+                                                ..
+                                            } if gc_horizon > 1
                                         )),
                                         Ok(_) => panic!("Unexpected Ok variant"),
                                     }
@@ -343,6 +353,7 @@ fn test_garbage_collection() {
                                     BlockReference::BlockId(BlockId::Height(1)),
                                     QueryRequest::ViewAccount {
                                         account_id: "test1".parse().unwrap(),
+                                        include_proof: false,
                                     },
                                 ))
                                 .then(move |res| {
@@ -367,3 +378,115 @@ fn test_garbage_collection() {
         near_network::test_utils::wait_or_panic(block_prod_time * target_height * 2 + 2000);
     })
 }
+
+#[test]
+/// Gas prices over a range of heights skip the heights without a block on the canonical chain.
+fn test_gas_price_range() {
+    init_test_logger();
+    run_actix(async {
+        let block_prod_time = 100;
+        let target_height = 20;
+        // Blocks of `test2` never reach `test1`, so every other height is skipped on its chain.
+        let network_mock: Arc<
+            RwLock<
+                Box<
+                    dyn FnMut(
+                        AccountId,
+                        &PeerManagerMessageRequest,
+                    ) -> (PeerManagerMessageResponse, bool),
+                >,
+            >,
+        > = Arc::new(RwLock::new(Box::new(|sender: AccountId, _: &PeerManagerMessageRequest| {
+            (NetworkResponses::NoResponse.into(), sender.as_ref() != "test2")
+        })));
+
+        let (_, conns, _) = setup_mock_all_validators(
+            vec![vec!["test1".parse().unwrap(), "test2".parse().unwrap()]],
+            vec![PeerInfo::random(), PeerInfo::random()],
+            1,
+            true,
+            block_prod_time,
+            false,
+            false,
+            1000,
+            false,
+            vec![false, false],
+            vec![true, true],
+            false,
+            network_mock.clone(),
+        );
+
+        *network_mock.write().unwrap() = Box::new(
+            move |sender: AccountId,
+                  msg: &PeerManagerMessageRequest|
+                  -> (PeerManagerMessageResponse, bool) {
+                if sender.as_ref() == "test2" {
+                    return (NetworkResponses::NoResponse.into(), false);
+                }
+                if let NetworkRequests::Block { block } = msg.as_network_requests_ref() {
+                    if block.header().height() > target_height {
+                        let view_client = conns[0].1.clone();
+                        let head_height = block.header().height();
+                        actix::spawn(async move {
+                            let range = view_client
+                                .send(GetGasPriceRange { from_height: 1, to_height: target_height })
+                                .await
+                                .unwrap()
+                                .unwrap();
+                            assert!(!range.gas_prices.is_empty());
+                            assert!(!range.missing_heights.is_empty());
+                            assert_eq!(range.next_height, None);
+                            let mut heights: Vec<_> = range
+                                .gas_prices
+                                .iter()
+                                .map(|gas_price| gas_price.height)
+                                .chain(range.missing_heights.iter().copied())
+                                .collect();
+                            heights.sort();
+                            assert_eq!(heights, (1..=target_height).collect::<Vec<_>>());
+                            for gas_price in range.gas_prices {
+                                let block = view_client
+                                    .send(GetBlock(BlockReference::BlockId(BlockId::Height(
+                                        gas_price.height,
+                                    ))))
+                                    .await
+                                    .unwrap()
+                                    .unwrap();
+                                assert_eq!(block.header.hash, gas_price.block_hash);
+                                assert_eq!(block.header.gas_price, gas_price.gas_price);
+                            }
+
+                            // Heights beyond the head are left for a later request.
+                            let range = view_client
+                                .send(GetGasPriceRange {
+                                    from_height: target_height,
+                                    to_height: head_height + 1000,
+                                })
+                                .await
+                                .unwrap()
+                                .unwrap();
+                            let next_height = range.next_height.unwrap();
+                            assert!(next_height > head_height);
+                            assert!(range.gas_prices.last().unwrap().height < next_height);
+
+                            let res = view_client
+                                .send(GetGasPriceRange { from_height: 2, to_height: 1 })
+                                .await
+                                .unwrap();
+                            assert!(matches!(
+                                res,
+                                Err(GetGasPriceError::InvalidHeightRange {
+                                    from_height: 2,
+                                    to_height: 1
+                                })
+                            ));
+                            System::current().stop();
+                        });
+                    }
+                }
+                (NetworkResponses::NoResponse.into(), true)
+            },
+        );
+        near_network::test_utils::wait_or_panic(block_prod_time * target_height * 10);
+    })
+}
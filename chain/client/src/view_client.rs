@@ -10,6 +10,7 @@ use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 
 use actix::{Actor, Addr, Handler, SyncArbiter, SyncContext};
+use borsh::BorshSerialize;
 use tracing::{debug, error, info, trace, warn};
 
 use near_chain::types::ValidatorInfoIdentifier;
@@ -20,12 +21,15 @@ use near_chain::{
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
 use near_client_primitives::types::{
     Error, GetBlock, GetBlockError, GetBlockHash, GetBlockProof, GetBlockProofError,
-    GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome,
-    GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError,
-    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
-    GetReceiptError, GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
-    TxStatus, TxStatusError,
+    GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError, GetChunkStateRootProof,
+    GetEpochProtocolConfig, GetExecutionOutcome, GetExecutionOutcomeError,
+    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetGasPriceRange,
+    GetNextLightClientBlockError, GetNextValidatorSet, GetProducerSchedule, GetProtocolConfig,
+    GetProtocolConfigError, GetReceipt, GetReceiptError, GetStateBatch, GetStateChangesError,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetStateChangesWithProof, GetStateProof, GetTrieNode, GetTrieNodeError, GetTxExecutionProgress,
+    GetValidatorInfoError, Query, QueryError, StateChangesWithProofResponse, StateProofResponse,
+    TxExecutionProgress, TxStatus, TxStatusError, MAX_GAS_PRICE_RANGE_HEIGHTS,
 };
 use near_network::types::{NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest};
 #[cfg(feature = "test_features")]
@@ -39,22 +43,28 @@ use near_primitives::block::{Block, BlockHeader, GenesisId, Tip};
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, PartialMerkleTree};
 use near_primitives::network::AnnounceAccount;
+use near_primitives::serialize::to_base64;
+use near_primitives::shard_layout::ShardUId;
 use near_primitives::sharding::ShardChunk;
 use near_primitives::syncing::{
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV1,
     ShardStateSyncResponseV2,
 };
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockId, BlockReference, EpochId, EpochReference, Finality,
-    MaybeBlockId, ShardId, TransactionOrReceiptId,
+    MaybeBlockId, ShardId, StateChangeValue, StateRoot, TransactionOrReceiptId,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
-    LightClientBlockView, QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView,
-    StateChangesView,
+    BlockView, ChunkStateRootProofView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
+    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus,
+    GasPriceRangeView, GasPriceView, HeightGasPriceView, HeightProducersView, LightClientBlockView,
+    NextValidatorSetView, ProducerScheduleView, QueryRequest, QueryResponse, ReceiptView,
+    StateChangeProofView, StateChangesKindsView, StateChangesView, StateProofView,
+    TrieNodeResponse,
 };
+use near_store::Trie;
 
 use crate::{
     sync, GetChunk, GetExecutionOutcomeResponse, GetNextLightClientBlock, GetStateChanges,
@@ -63,6 +73,10 @@ use crate::{
 
 /// Max number of queries that we keep.
 const QUERY_REQUEST_LIMIT: usize = 500;
+/// Max number of items in a batch of contract state.
+const MAX_STATE_BATCH_ITEMS: u64 = 1_000;
+/// Max number of state changes returned at once along with their proofs.
+const MAX_STATE_CHANGES_WITH_PROOF: usize = 100;
 /// Waiting time between requests, in ms
 const REQUEST_WAIT_TIME: u64 = 1000;
 
@@ -187,8 +201,14 @@ impl ViewClientActor {
         }
     }
 
-    fn handle_query(&mut self, msg: Query) -> Result<QueryResponse, QueryError> {
-        let header = match msg.block_reference {
+    /// Finds the header of the block of `block_reference`, the shard of `account_id` and the
+    /// state root of the shard at that block.
+    fn get_query_state_root(
+        &mut self,
+        block_reference: &BlockReference,
+        account_id: &AccountId,
+    ) -> Result<(BlockHeader, ShardUId, StateRoot), QueryError> {
+        let header = match *block_reference {
             BlockReference::BlockId(BlockId::Height(block_height)) => {
                 self.chain.get_header_by_height(block_height)
             }
@@ -203,9 +223,7 @@ impl ViewClientActor {
                     .get_block_hash_by_sync_checkpoint(synchronization_checkpoint)
                     .map_err(|err| match err {
                         near_chain::near_chain_primitives::Error::DBNotFoundErr(_) => {
-                            QueryError::UnknownBlock {
-                                block_reference: msg.block_reference.clone(),
-                            }
+                            QueryError::UnknownBlock { block_reference: block_reference.clone() }
                         }
                         near_chain::near_chain_primitives::Error::IOErr(error) => {
                             QueryError::InternalError { error_message: error.to_string() }
@@ -221,7 +239,7 @@ impl ViewClientActor {
         };
         let header = header.map_err(|err| match err {
             near_chain::near_chain_primitives::Error::DBNotFoundErr(_) => {
-                QueryError::UnknownBlock { block_reference: msg.block_reference.clone() }
+                QueryError::UnknownBlock { block_reference: block_reference.clone() }
             }
             near_chain::near_chain_primitives::Error::IOErr(error) => {
                 QueryError::InternalError { error_message: error.to_string() }
@@ -229,14 +247,6 @@ impl ViewClientActor {
             _ => QueryError::Unreachable { error_message: err.to_string() },
         })?;
 
-        let account_id = match &msg.request {
-            QueryRequest::ViewAccount { account_id, .. } => account_id,
-            QueryRequest::ViewState { account_id, .. } => account_id,
-            QueryRequest::ViewAccessKey { account_id, .. } => account_id,
-            QueryRequest::ViewAccessKeyList { account_id, .. } => account_id,
-            QueryRequest::CallFunction { account_id, .. } => account_id,
-            QueryRequest::ViewCode { account_id, .. } => account_id,
-        };
         let shard_id =
             self.runtime_adapter
                 .account_id_to_shard_id(account_id, header.epoch_id())
@@ -247,8 +257,26 @@ impl ViewClientActor {
             .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
 
         let tip = self.chain.head();
-        let chunk_extra =
-            self.chain.get_chunk_extra(header.hash(), &shard_uid).map_err(|err| match err {
+        let state_root = self
+            .chain
+            .get_chunk_extra(header.hash(), &shard_uid)
+            .map(|chunk_extra| *chunk_extra.state_root())
+            .or_else(|err| match err {
+                // The state of pinned blocks outlives their chunk extras.
+                near_chain::near_chain_primitives::Error::DBNotFoundErr(_) => self
+                    .chain
+                    .store()
+                    .get_pinned_state_roots(header.hash())?
+                    .and_then(|pin| {
+                        pin.state_roots
+                            .into_iter()
+                            .find(|(pinned_shard_uid, _)| *pinned_shard_uid == shard_uid)
+                    })
+                    .map(|(_, state_root)| state_root)
+                    .ok_or(err),
+                _ => Err(err),
+            })
+            .map_err(|err| match err {
                 near_chain::near_chain_primitives::Error::DBNotFoundErr(_) => match tip {
                     Ok(tip) => {
                         let gc_stop_height =
@@ -257,6 +285,7 @@ impl ViewClientActor {
                             QueryError::GarbageCollectedBlock {
                                 block_height: header.height(),
                                 block_hash: header.hash().clone(),
+                                gc_horizon: gc_stop_height,
                             }
                         } else {
                             QueryError::UnavailableShard { requested_shard_id: shard_id }
@@ -270,63 +299,32 @@ impl ViewClientActor {
                 _ => QueryError::Unreachable { error_message: err.to_string() },
             })?;
 
-        let state_root = chunk_extra.state_root();
-        match self.runtime_adapter.query(
-            shard_uid,
-            state_root,
-            header.height(),
-            header.raw_timestamp(),
-            header.prev_hash(),
-            header.hash(),
-            header.epoch_id(),
-            &msg.request,
-        ) {
-            Ok(query_response) => Ok(query_response),
-            Err(query_error) => Err(match query_error {
-                near_chain::near_chain_primitives::error::QueryError::InternalError {
-                    error_message,
-                    ..
-                } => QueryError::InternalError { error_message },
-                near_chain::near_chain_primitives::error::QueryError::InvalidAccount {
-                    requested_account_id,
-                    block_height,
-                    block_hash,
-                } => QueryError::InvalidAccount { requested_account_id, block_height, block_hash },
-                near_chain::near_chain_primitives::error::QueryError::UnknownAccount {
-                    requested_account_id,
-                    block_height,
-                    block_hash,
-                } => QueryError::UnknownAccount { requested_account_id, block_height, block_hash },
-                near_chain::near_chain_primitives::error::QueryError::NoContractCode {
-                    contract_account_id,
-                    block_height,
-                    block_hash,
-                } => QueryError::NoContractCode { contract_account_id, block_height, block_hash },
-                near_chain::near_chain_primitives::error::QueryError::UnknownAccessKey {
-                    public_key,
-                    block_height,
-                    block_hash,
-                } => QueryError::UnknownAccessKey { public_key, block_height, block_hash },
-                near_chain::near_chain_primitives::error::QueryError::ContractExecutionError {
-                    error_message,
-                    block_hash,
-                    block_height,
-                } => QueryError::ContractExecutionError {
-                    vm_error: error_message,
-                    block_height,
-                    block_hash,
-                },
-                near_chain::near_chain_primitives::error::QueryError::TooLargeContractState {
-                    requested_account_id,
-                    block_height,
-                    block_hash,
-                } => QueryError::TooLargeContractState {
-                    contract_account_id: requested_account_id,
-                    block_height,
-                    block_hash,
-                },
-            }),
-        }
+        Ok((header, shard_uid, state_root))
+    }
+
+    fn handle_query(&mut self, msg: Query) -> Result<QueryResponse, QueryError> {
+        let account_id = match &msg.request {
+            QueryRequest::ViewAccount { account_id, .. } => account_id,
+            QueryRequest::ViewState { account_id, .. } => account_id,
+            QueryRequest::ViewAccessKey { account_id, .. } => account_id,
+            QueryRequest::ViewAccessKeyList { account_id, .. } => account_id,
+            QueryRequest::CallFunction { account_id, .. } => account_id,
+            QueryRequest::ViewCode { account_id, .. } => account_id,
+        };
+        let (header, shard_uid, state_root) =
+            self.get_query_state_root(&msg.block_reference, account_id)?;
+        self.runtime_adapter
+            .query(
+                shard_uid,
+                &state_root,
+                header.height(),
+                header.raw_timestamp(),
+                header.prev_hash(),
+                header.hash(),
+                header.epoch_id(),
+                &msg.request,
+            )
+            .map_err(QueryError::from)
     }
 
     fn request_receipt_outcome(
@@ -357,6 +355,20 @@ impl ViewClientActor {
         Ok(())
     }
 
+    /// Whether the block is on the canonical chain at or below the final head.
+    fn is_final_block(&self, final_head: &Tip, block_hash: &CryptoHash) -> bool {
+        match self.chain.get_block_header(block_hash) {
+            Ok(header) => {
+                header.height() <= final_head.height
+                    && self
+                        .chain
+                        .get_block_hash_by_height(header.height())
+                        .map_or(false, |hash| hash == *block_hash)
+            }
+            Err(_) => false,
+        }
+    }
+
     fn get_tx_status(
         &mut self,
         tx_hash: CryptoHash,
@@ -646,6 +658,57 @@ impl Handler<GetChunk> for ViewClientActor {
     }
 }
 
+/// Returns the merkle path from the header of a chunk to the chunk headers root of the block
+/// including it.
+impl Handler<GetChunkStateRootProof> for ViewClientActor {
+    type Result = Result<ChunkStateRootProofView, GetChunkError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetChunkStateRootProof, _: &mut Self::Context) -> Self::Result {
+        let (block, shard_id) = match msg.0 {
+            GetChunk::Height(height, shard_id) => {
+                (self.chain.get_block_by_height(height)?, shard_id)
+            }
+            GetChunk::BlockHash(block_hash, shard_id) => {
+                (self.chain.get_block(&block_hash)?, shard_id)
+            }
+            GetChunk::ChunkHash(chunk_hash) => {
+                // A chunk can only be included in a block right after its previous block.
+                let chunk = self.chain.get_chunk(&chunk_hash)?;
+                let shard_id = chunk.shard_id();
+                let block = self
+                    .chain
+                    .store()
+                    .get_next_block_hash(chunk.prev_block())
+                    .and_then(|block_hash| self.chain.get_block(&block_hash))
+                    .map_err(|_| GetChunkError::UnknownChunk { chunk_hash: chunk_hash.clone() })?;
+                let included = block.chunks().get(shard_id as usize).map_or(false, |header| {
+                    header.chunk_hash() == chunk_hash
+                        && header.height_included() == block.header().height()
+                });
+                if !included {
+                    return Err(GetChunkError::UnknownChunk { chunk_hash });
+                }
+                (block, shard_id)
+            }
+        };
+        let chunk_header = block
+            .chunks()
+            .get(shard_id as usize)
+            .ok_or(GetChunkError::InvalidShardId { shard_id })?
+            .clone();
+        let (_, mut paths) = Block::compute_chunk_headers_root(block.chunks().iter());
+        Ok(ChunkStateRootProofView {
+            block_hash: *block.hash(),
+            raw_chunk_header: chunk_header
+                .try_to_vec()
+                .map_err(|err| GetChunkError::IOError { error_message: err.to_string() })?,
+            chunk_header: chunk_header.into(),
+            chunk_headers_proof: paths.swap_remove(shard_id as usize),
+        })
+    }
+}
+
 impl Handler<TxStatus> for ViewClientActor {
     type Result = Result<Option<FinalExecutionOutcomeViewEnum>, TxStatusError>;
 
@@ -655,6 +718,47 @@ impl Handler<TxStatus> for ViewClientActor {
     }
 }
 
+impl Handler<GetTxExecutionProgress> for ViewClientActor {
+    type Result = Result<TxExecutionProgress, TxStatusError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetTxExecutionProgress, _: &mut Self::Context) -> Self::Result {
+        let final_outcome =
+            self.get_tx_status(msg.tx_hash, msg.signer_account_id, false)?.map(|outcome| {
+                match outcome {
+                    FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(outcome) => outcome,
+                    FinalExecutionOutcomeViewEnum::FinalExecutionOutcomeWithReceipt(outcome) => {
+                        outcome.into()
+                    }
+                }
+            });
+        let transaction_outcome = match &final_outcome {
+            Some(outcome) => Some(outcome.transaction_outcome.clone()),
+            None => match self.chain.get_execution_outcome(&msg.tx_hash) {
+                Ok(outcome) => Some(outcome.into()),
+                // Either the transaction isn't included yet or its shard isn't tracked.
+                Err(near_chain::Error::DBNotFoundErr(_)) => None,
+                Err(err) => return Err(TxStatusError::ChainError(err)),
+            },
+        };
+        let final_head = self.chain.final_head().map_err(|e| TxStatusError::ChainError(e))?;
+        let transaction_final = transaction_outcome
+            .as_ref()
+            .map_or(false, |outcome| self.is_final_block(&final_head, &outcome.block_hash));
+        let receipts_final = final_outcome.as_ref().map_or(false, |outcome| {
+            outcome.receipts_outcome.iter().all(|receipt_outcome| {
+                self.is_final_block(&final_head, &receipt_outcome.block_hash)
+            })
+        });
+        Ok(TxExecutionProgress {
+            transaction_outcome,
+            final_outcome,
+            transaction_final,
+            receipts_final,
+        })
+    }
+}
+
 impl Handler<GetValidatorInfo> for ViewClientActor {
     type Result = Result<EpochValidatorInfo, GetValidatorInfoError>;
 
@@ -716,6 +820,102 @@ impl Handler<GetValidatorOrdered> for ViewClientActor {
             })?)
     }
 }
+
+impl Handler<GetProducerSchedule> for ViewClientActor {
+    type Result = Result<ProducerScheduleView, GetValidatorInfoError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetProducerSchedule, _: &mut Self::Context) -> Self::Result {
+        // use header head because this is latest from the perspective of epoch manager
+        let tip = self.chain.header_head()?;
+        let (epoch_id, epoch_start_height) = match msg.epoch_reference {
+            EpochReference::EpochId(id) if id == tip.epoch_id => {
+                (id, self.runtime_adapter.get_epoch_start_height(&tip.last_block_hash)?)
+            }
+            EpochReference::EpochId(id) if id == tip.next_epoch_id => {
+                // The next epoch starts once the current one reached its length, unless the
+                // blocks finalizing the current epoch are late.
+                let start_height =
+                    self.runtime_adapter.get_epoch_start_height(&tip.last_block_hash)?
+                        + self.config.epoch_length;
+                (id, start_height)
+            }
+            EpochReference::EpochId(id) => {
+                let validator_info = self
+                    .runtime_adapter
+                    .get_validator_info(ValidatorInfoIdentifier::EpochId(id.clone()))?;
+                (id, validator_info.epoch_start_height)
+            }
+            EpochReference::BlockId(block_id) => {
+                let block_header = match block_id {
+                    BlockId::Hash(h) => self.chain.get_block_header(&h)?,
+                    BlockId::Height(h) => self.chain.get_header_by_height(h)?,
+                };
+                let start_height =
+                    self.runtime_adapter.get_epoch_start_height(block_header.hash())?;
+                (block_header.epoch_id().clone(), start_height)
+            }
+            EpochReference::Latest => {
+                (tip.epoch_id, self.runtime_adapter.get_epoch_start_height(&tip.last_block_hash)?)
+            }
+        };
+        let num_shards = self.runtime_adapter.num_shards(&epoch_id)?;
+        let producers = (epoch_start_height..epoch_start_height + self.config.epoch_length)
+            .map(|height| {
+                Ok(HeightProducersView {
+                    height,
+                    block_producer: self.runtime_adapter.get_block_producer(&epoch_id, height)?,
+                    chunk_producers: (0..num_shards)
+                        .map(|shard_id| {
+                            self.runtime_adapter.get_chunk_producer(&epoch_id, height, shard_id)
+                        })
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, near_chain::Error>>()?;
+        Ok(ProducerScheduleView { epoch_id: epoch_id.0, epoch_start_height, producers })
+    }
+}
+
+impl Handler<GetNextValidatorSet> for ViewClientActor {
+    type Result = Result<NextValidatorSetView, GetBlockError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetNextValidatorSet, _: &mut Self::Context) -> Self::Result {
+        let header = match msg.block_reference {
+            BlockReference::Finality(finality) => {
+                let block_hash = self.get_block_hash_by_finality(&finality)?;
+                self.chain.get_block_header(&block_hash)
+            }
+            BlockReference::BlockId(BlockId::Height(height)) => {
+                self.chain.get_header_by_height(height)
+            }
+            BlockReference::BlockId(BlockId::Hash(hash)) => self.chain.get_block_header(&hash),
+            BlockReference::SyncCheckpoint(sync_checkpoint) => {
+                if let Some(block_hash) =
+                    self.get_block_hash_by_sync_checkpoint(&sync_checkpoint)?
+                {
+                    self.chain.get_block_header(&block_hash)
+                } else {
+                    return Err(GetBlockError::NotSyncedYet);
+                }
+            }
+        }?;
+        // The blocks of an epoch commit to the block producers of the next one, hashed the way
+        // of the protocol version of their own epoch.
+        let (block_producers, protocol_version) =
+            self.runtime_adapter.get_bp_hash_inputs(header.next_epoch_id(), header.epoch_id())?;
+        Ok(NextValidatorSetView {
+            block_hash: *header.hash(),
+            block_height: header.height(),
+            epoch_id: header.epoch_id().clone(),
+            next_epoch_id: header.next_epoch_id().clone(),
+            next_bp_hash: *header.next_bp_hash(),
+            protocol_version,
+            next_block_producers: block_producers.into_iter().map(Into::into).collect(),
+        })
+    }
+}
 /// Returns a list of change kinds per account in a store for a given block.
 impl Handler<GetStateChangesInBlock> for ViewClientActor {
     type Result = Result<StateChangesKindsView, GetStateChangesError>;
@@ -732,6 +932,69 @@ impl Handler<GetStateChangesInBlock> for ViewClientActor {
     }
 }
 
+/// Reads a batch of the contract state of an account, of at most `MAX_STATE_BATCH_ITEMS` items.
+impl Handler<GetStateBatch> for ViewClientActor {
+    type Result = Result<QueryResponse, QueryError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetStateBatch, _: &mut Self::Context) -> Self::Result {
+        let (header, shard_uid, state_root) =
+            self.get_query_state_root(&msg.block_reference, &msg.account_id)?;
+        self.runtime_adapter
+            .view_state_batch(
+                shard_uid,
+                &state_root,
+                header.height(),
+                header.hash(),
+                &msg.account_id,
+                &msg.prefix,
+                msg.from_key.as_deref(),
+                msg.max_items.clamp(1, MAX_STATE_BATCH_ITEMS),
+            )
+            .map_err(QueryError::from)
+    }
+}
+
+/// Proves the value, or the absence, of a single contract data key at a block. Archival nodes
+/// serve it for any block, other nodes until the state of the block is garbage collected.
+impl Handler<GetStateProof> for ViewClientActor {
+    type Result = Result<StateProofResponse, QueryError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetStateProof, _: &mut Self::Context) -> Self::Result {
+        let (header, shard_uid, state_root) =
+            self.get_query_state_root(&msg.block_reference, &msg.account_id)?;
+        let (value, nodes) = self.runtime_adapter.prove_trie_key(
+            shard_uid,
+            &state_root,
+            header.height(),
+            header.hash(),
+            &TrieKey::ContractData { account_id: msg.account_id, key: msg.key },
+        )?;
+        let chunk_hash = self
+            .chain
+            .get_block(header.hash())
+            .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?
+            .chunks()
+            .get(shard_uid.shard_id as usize)
+            .map(|chunk_header| chunk_header.chunk_hash())
+            .ok_or_else(|| QueryError::Unreachable {
+                error_message: format!("No chunk of shard {} in the block", shard_uid.shard_id),
+            })?;
+        Ok(StateProofResponse {
+            state_proof: StateProofView {
+                value,
+                proof: nodes.iter().map(to_base64).collect(),
+                state_root,
+                shard_id: shard_uid.shard_id as ShardId,
+                chunk_hash,
+            },
+            block_height: header.height(),
+            block_hash: *header.hash(),
+        })
+    }
+}
+
 /// Returns a list of changes in a store for a given block filtering by the state changes request.
 impl Handler<GetStateChanges> for ViewClientActor {
     type Result = Result<StateChangesView, GetStateChangesError>;
@@ -748,6 +1011,89 @@ impl Handler<GetStateChanges> for ViewClientActor {
     }
 }
 
+/// Returns a page of the changes of `GetStateChanges`, with proofs of the values the changed
+/// keys have after the block. The value a key has after the block is the one set by its last
+/// change, so only the last change of each key gets a proof. Contract code changes have none, as
+/// proving them means reading the whole code.
+impl Handler<GetStateChangesWithProof> for ViewClientActor {
+    type Result = Result<StateChangesWithProofResponse, GetStateChangesError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetStateChangesWithProof, _: &mut Self::Context) -> Self::Result {
+        let block = self.chain.get_block(&msg.block_hash)?;
+        let changes = self
+            .chain
+            .store()
+            .get_state_changes(&msg.block_hash, &msg.state_changes_request.into())?;
+        let start = (msg.cursor as usize).min(changes.len());
+        let end = (start + MAX_STATE_CHANGES_WITH_PROOF).min(changes.len());
+
+        let mut last_change_of_key = HashMap::new();
+        for (index, change) in changes.iter().enumerate() {
+            last_change_of_key.insert(change.value.trie_key().to_vec(), index);
+        }
+        let epoch_id = block.header().epoch_id();
+        let shard_layout = self.runtime_adapter.get_shard_layout(epoch_id)?;
+        let mut proofs = vec![];
+        for (index, change) in changes.iter().enumerate().take(end).skip(start) {
+            if matches!(
+                change.value,
+                StateChangeValue::ContractCodeUpdate { .. }
+                    | StateChangeValue::ContractCodeDeletion { .. }
+            ) {
+                continue;
+            }
+            let trie_key = change.value.trie_key();
+            if last_change_of_key.get(&trie_key.to_vec()) != Some(&index) {
+                continue;
+            }
+            let shard_id = self
+                .runtime_adapter
+                .account_id_to_shard_id(change.value.affected_account_id(), epoch_id)?;
+            let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+            // State changes are only stored for the shards the node applied, so their chunk
+            // extras are there.
+            let state_root = *self
+                .chain
+                .get_chunk_extra(&msg.block_hash, &shard_uid)
+                .map_err(|err| GetStateChangesError::Unreachable {
+                    error_message: err.to_string(),
+                })?
+                .state_root();
+            let (_, nodes) = self
+                .runtime_adapter
+                .prove_trie_key(
+                    shard_uid,
+                    &state_root,
+                    block.header().height(),
+                    &msg.block_hash,
+                    &trie_key,
+                )
+                .map_err(|err| GetStateChangesError::IOError { error_message: err.to_string() })?;
+            let chunk_hash = block
+                .chunks()
+                .get(shard_id as usize)
+                .map(|chunk_header| chunk_header.chunk_hash())
+                .ok_or_else(|| GetStateChangesError::Unreachable {
+                    error_message: format!("No chunk of shard {} in the block", shard_id),
+                })?;
+            proofs.push(StateChangeProofView {
+                change_index: (index - start) as u64,
+                proof: nodes.iter().map(to_base64).collect(),
+                state_root,
+                shard_id,
+                chunk_hash,
+            });
+        }
+        let next_cursor = if end < changes.len() { Some(end as u64) } else { None };
+        Ok(StateChangesWithProofResponse {
+            changes: changes.into_iter().take(end).skip(start).map(Into::into).collect(),
+            proofs,
+            next_cursor,
+        })
+    }
+}
+
 /// Returns a list of changes in a store with causes for a given block.
 impl Handler<GetStateChangesWithCauseInBlock> for ViewClientActor {
     type Result = Result<StateChangesView, GetStateChangesError>;
@@ -963,14 +1309,50 @@ impl Handler<GetReceipt> for ViewClientActor {
     }
 }
 
+impl Handler<GetTrieNode> for ViewClientActor {
+    type Result = Result<Option<TrieNodeResponse>, GetTrieNodeError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetTrieNode, _: &mut Self::Context) -> Self::Result {
+        // Nodes are read straight from the DB, which validators shouldn't spend time on.
+        if self.validator_account_id.is_some() {
+            return Err(GetTrieNodeError::ValidatorNode);
+        }
+        let head = self.chain.head()?;
+        let shard_layout = self.runtime_adapter.get_shard_layout(&head.epoch_id)?;
+        if msg.shard_id >= shard_layout.num_shards() {
+            return Err(GetTrieNodeError::UnknownShard(msg.shard_id));
+        }
+        let shard_uid = ShardUId::from_shard_id_and_layout(msg.shard_id, &shard_layout);
+        let raw = self
+            .runtime_adapter
+            .get_tries()
+            .get_trie_node(shard_uid, &msg.hash)
+            .map_err(|err| GetTrieNodeError::IOError(err.to_string()))?;
+        Ok(raw.map(|raw| TrieNodeResponse {
+            hash: msg.hash,
+            node: Trie::debug_decode_node(&raw),
+            raw,
+        }))
+    }
+}
+
 impl Handler<GetBlockProof> for ViewClientActor {
     type Result = Result<GetBlockProofResponse, GetBlockProofError>;
 
     #[perf]
     fn handle(&mut self, msg: GetBlockProof, _: &mut Self::Context) -> Self::Result {
+        let block_header = self.chain.get_block_header(&msg.block_hash)?;
+        let head_header = self.chain.get_block_header(&msg.head_block_hash)?;
+        if block_header.height() > head_header.height() {
+            return Err(GetBlockProofError::BlockAfterHead {
+                block_hash: msg.block_hash,
+                head_block_hash: msg.head_block_hash,
+            });
+        }
         self.chain.check_block_final_and_canonical(&msg.block_hash)?;
         self.chain.check_block_final_and_canonical(&msg.head_block_hash)?;
-        let block_header_lite = self.chain.get_block_header(&msg.block_hash)?.into();
+        let block_header_lite = block_header.into();
         let block_proof = self.chain.get_block_proof(&msg.block_hash, &msg.head_block_hash)?;
         Ok(GetBlockProofResponse { block_header_lite, proof: block_proof })
     }
@@ -1008,6 +1390,16 @@ impl Handler<GetProtocolConfig> for ViewClientActor {
     }
 }
 
+impl Handler<GetEpochProtocolConfig> for ViewClientActor {
+    type Result = Result<ProtocolConfigView, GetProtocolConfigError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetEpochProtocolConfig, _: &mut Self::Context) -> Self::Result {
+        let config = self.runtime_adapter.get_protocol_config(&msg.0)?;
+        Ok(config.into())
+    }
+}
+
 impl Handler<NetworkViewClientMessages> for ViewClientActor {
     type Result = NetworkViewClientResponses;
 
@@ -1357,6 +1749,47 @@ impl Handler<GetGasPrice> for ViewClientActor {
     }
 }
 
+impl Handler<GetGasPriceRange> for ViewClientActor {
+    type Result = Result<GasPriceRangeView, GetGasPriceError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetGasPriceRange, _ctx: &mut Self::Context) -> Self::Result {
+        let GetGasPriceRange { from_height, to_height } = msg;
+        if from_height > to_height {
+            return Err(GetGasPriceError::InvalidHeightRange { from_height, to_height });
+        }
+        let tail = self.chain.tail()?;
+        if from_height < tail {
+            return Err(GetGasPriceError::UnknownBlock {
+                error_message: format!(
+                    "Height {} is below the tail {} of the chain",
+                    from_height, tail
+                ),
+            });
+        }
+        let last_height = to_height
+            .min(self.chain.head()?.height)
+            .min(from_height + MAX_GAS_PRICE_RANGE_HEIGHTS - 1);
+        let mut gas_prices = vec![];
+        let mut missing_heights = vec![];
+        for height in from_height..=last_height {
+            match self.chain.get_header_by_height(height) {
+                Ok(header) => gas_prices.push(HeightGasPriceView {
+                    height,
+                    block_hash: *header.hash(),
+                    gas_price: header.gas_price(),
+                }),
+                // The height was skipped on the canonical chain.
+                Err(near_chain::Error::DBNotFoundErr(_)) => missing_heights.push(height),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let next_height =
+            if last_height < to_height { Some(from_height.max(last_height + 1)) } else { None };
+        Ok(GasPriceRangeView { gas_prices, missing_heights, next_height })
+    }
+}
+
 /// Starts the View Client in a new arbiter (thread).
 pub fn start_view_client(
     validator_account_id: Option<AccountId>,
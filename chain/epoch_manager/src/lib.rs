@@ -6,6 +6,7 @@ use near_cache::SyncLruCache;
 use primitive_types::U256;
 use tracing::{debug, warn};
 
+use near_primitives::block_header::BlockHeader;
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::epoch_manager::epoch_info::{EpochInfo, EpochSummary};
 use near_primitives::epoch_manager::{
@@ -1043,91 +1044,93 @@ impl EpochManager {
         // This ugly code arises because of the incompatible types between `block_tracker` in `EpochInfoAggregator`
         // and `validator_block_chunk_stats` in `EpochSummary`. Rust currently has no support for Either type
         // in std.
-        let (current_validators, next_epoch_id, all_proposals) = match &epoch_identifier {
-            ValidatorInfoIdentifier::EpochId(id) => {
-                let epoch_summary = self.get_epoch_validator_info(id)?;
-                let cur_validators = cur_epoch_info
-                    .validators_iter()
-                    .enumerate()
-                    .map(|(validator_id, info)| {
-                        let validator_stats = epoch_summary
-                            .validator_block_chunk_stats
-                            .get(info.account_id())
-                            .unwrap_or(&BlockChunkValidatorStats {
-                                block_stats: ValidatorStats { produced: 0, expected: 0 },
-                                chunk_stats: ValidatorStats { produced: 0, expected: 0 },
-                            });
-                        let mut shards = validator_to_shard[validator_id]
-                            .iter()
-                            .cloned()
-                            .collect::<Vec<ShardId>>();
-                        shards.sort();
-                        let (account_id, public_key, stake) = info.destructure();
-                        Ok(CurrentEpochValidatorInfo {
-                            is_slashed: false, // currently there is no slashing
-                            account_id,
-                            public_key,
-                            stake,
-                            shards,
-                            num_produced_blocks: validator_stats.block_stats.produced,
-                            num_expected_blocks: validator_stats.block_stats.expected,
-                            num_produced_chunks: validator_stats.chunk_stats.produced,
-                            num_expected_chunks: validator_stats.chunk_stats.expected,
+        let (current_validators, prev_epoch_id, next_epoch_id, all_proposals) =
+            match &epoch_identifier {
+                ValidatorInfoIdentifier::EpochId(id) => {
+                    let epoch_summary = self.get_epoch_validator_info(id)?;
+                    let cur_validators = cur_epoch_info
+                        .validators_iter()
+                        .enumerate()
+                        .map(|(validator_id, info)| {
+                            let validator_stats = epoch_summary
+                                .validator_block_chunk_stats
+                                .get(info.account_id())
+                                .unwrap_or(&BlockChunkValidatorStats {
+                                    block_stats: ValidatorStats { produced: 0, expected: 0 },
+                                    chunk_stats: ValidatorStats { produced: 0, expected: 0 },
+                                });
+                            let mut shards = validator_to_shard[validator_id]
+                                .iter()
+                                .cloned()
+                                .collect::<Vec<ShardId>>();
+                            shards.sort();
+                            let (account_id, public_key, stake) = info.destructure();
+                            Ok(CurrentEpochValidatorInfo {
+                                is_slashed: false, // currently there is no slashing
+                                account_id,
+                                public_key,
+                                stake,
+                                shards,
+                                num_produced_blocks: validator_stats.block_stats.produced,
+                                num_expected_blocks: validator_stats.block_stats.expected,
+                                num_produced_chunks: validator_stats.chunk_stats.produced,
+                                num_expected_chunks: validator_stats.chunk_stats.expected,
+                            })
                         })
-                    })
-                    .collect::<Result<Vec<CurrentEpochValidatorInfo>, EpochError>>()?;
-                (
-                    cur_validators,
-                    EpochId(epoch_summary.prev_epoch_last_block_hash),
-                    epoch_summary.all_proposals.into_iter().map(Into::into).collect(),
-                )
-            }
-            ValidatorInfoIdentifier::BlockHash(ref h) => {
-                // If we are here, `h` is hash of the latest block of the
-                // current epoch.
-                let aggregator = self.get_epoch_info_aggregator_upto_last(h)?;
-                let cur_validators = cur_epoch_info
-                    .validators_iter()
-                    .enumerate()
-                    .map(|(validator_id, info)| {
-                        let block_stats = aggregator
-                            .block_tracker
-                            .get(&(validator_id as u64))
-                            .unwrap_or_else(|| &ValidatorStats { produced: 0, expected: 0 })
-                            .clone();
-
-                        let mut chunk_stats = ValidatorStats { produced: 0, expected: 0 };
-                        for (_shard, tracker) in aggregator.shard_tracker.iter() {
-                            if let Some(stats) = tracker.get(&(validator_id as u64)) {
-                                chunk_stats.produced += stats.produced;
-                                chunk_stats.expected += stats.expected;
+                        .collect::<Result<Vec<CurrentEpochValidatorInfo>, EpochError>>()?;
+                    (
+                        cur_validators,
+                        self.get_epoch_id(&epoch_summary.prev_epoch_last_block_hash)?,
+                        EpochId(epoch_summary.prev_epoch_last_block_hash),
+                        epoch_summary.all_proposals.into_iter().map(Into::into).collect(),
+                    )
+                }
+                ValidatorInfoIdentifier::BlockHash(ref h) => {
+                    // If we are here, `h` is hash of the latest block of the
+                    // current epoch.
+                    let aggregator = self.get_epoch_info_aggregator_upto_last(h)?;
+                    let cur_validators = cur_epoch_info
+                        .validators_iter()
+                        .enumerate()
+                        .map(|(validator_id, info)| {
+                            let block_stats = aggregator
+                                .block_tracker
+                                .get(&(validator_id as u64))
+                                .unwrap_or_else(|| &ValidatorStats { produced: 0, expected: 0 })
+                                .clone();
+
+                            let mut chunk_stats = ValidatorStats { produced: 0, expected: 0 };
+                            for (_shard, tracker) in aggregator.shard_tracker.iter() {
+                                if let Some(stats) = tracker.get(&(validator_id as u64)) {
+                                    chunk_stats.produced += stats.produced;
+                                    chunk_stats.expected += stats.expected;
+                                }
                             }
-                        }
-                        let mut shards = validator_to_shard[validator_id]
-                            .clone()
-                            .into_iter()
-                            .collect::<Vec<ShardId>>();
-                        shards.sort();
-                        let (account_id, public_key, stake) = info.destructure();
-                        Ok(CurrentEpochValidatorInfo {
-                            is_slashed: false, // currently there is no slashing
-                            account_id,
-                            public_key,
-                            stake,
-                            shards,
-                            num_produced_blocks: block_stats.produced,
-                            num_expected_blocks: block_stats.expected,
-                            num_produced_chunks: chunk_stats.produced,
-                            num_expected_chunks: chunk_stats.expected,
+                            let mut shards = validator_to_shard[validator_id]
+                                .clone()
+                                .into_iter()
+                                .collect::<Vec<ShardId>>();
+                            shards.sort();
+                            let (account_id, public_key, stake) = info.destructure();
+                            Ok(CurrentEpochValidatorInfo {
+                                is_slashed: false, // currently there is no slashing
+                                account_id,
+                                public_key,
+                                stake,
+                                shards,
+                                num_produced_blocks: block_stats.produced,
+                                num_expected_blocks: block_stats.expected,
+                                num_produced_chunks: chunk_stats.produced,
+                                num_expected_chunks: chunk_stats.expected,
+                            })
                         })
-                    })
-                    .collect::<Result<Vec<CurrentEpochValidatorInfo>, EpochError>>()?;
-                let all_proposals =
-                    aggregator.all_proposals.iter().map(|(_, p)| p.clone().into()).collect();
-                let next_epoch_id = self.get_next_epoch_id(h)?;
-                (cur_validators, next_epoch_id, all_proposals)
-            }
-        };
+                        .collect::<Result<Vec<CurrentEpochValidatorInfo>, EpochError>>()?;
+                    let all_proposals =
+                        aggregator.all_proposals.iter().map(|(_, p)| p.clone().into()).collect();
+                    let next_epoch_id = self.get_next_epoch_id(h)?;
+                    (cur_validators, self.get_prev_epoch_id(h)?, next_epoch_id, all_proposals)
+                }
+            };
 
         let next_epoch_info = self.get_epoch_info(&next_epoch_id)?;
         let mut next_validator_to_shard = (0..next_epoch_info.validators_len())
@@ -1171,9 +1174,42 @@ impl EpochManager {
             prev_epoch_kickout,
             epoch_start_height,
             epoch_height,
+            current_bp_hash: self.compute_bp_hash(&epoch_id, &prev_epoch_id)?,
+            next_bp_hash: self.compute_bp_hash(&next_epoch_id, &epoch_id)?,
         })
     }
 
+    /// Commitment to the unique block producers of the epoch in settlement order, as computed
+    /// for the `next_bp_hash` of the blocks of the previous epoch.
+    fn compute_bp_hash(
+        &self,
+        epoch_id: &EpochId,
+        prev_epoch_id: &EpochId,
+    ) -> Result<CryptoHash, EpochError> {
+        let (block_producers, protocol_version) =
+            self.get_bp_hash_inputs(epoch_id, prev_epoch_id)?;
+        Ok(BlockHeader::compute_bp_hash(block_producers, protocol_version))
+    }
+
+    /// Inputs of `BlockHeader::compute_bp_hash` for the block producers of `epoch_id`: its
+    /// unique block producers in settlement order, and the protocol version of `prev_epoch_id`.
+    pub fn get_bp_hash_inputs(
+        &self,
+        epoch_id: &EpochId,
+        prev_epoch_id: &EpochId,
+    ) -> Result<(Vec<ValidatorStake>, ProtocolVersion), EpochError> {
+        let epoch_info = self.get_epoch_info(epoch_id)?;
+        let mut validator_ids = HashSet::new();
+        let block_producers = epoch_info
+            .block_producers_settlement()
+            .iter()
+            .filter(|&&validator_id| validator_ids.insert(validator_id))
+            .map(|&validator_id| epoch_info.get_validator(validator_id))
+            .collect();
+        let protocol_version = self.get_epoch_info(prev_epoch_id)?.protocol_version();
+        Ok((block_producers, protocol_version))
+    }
+
     /// Compare two epoch ids based on their start height. This works because finality gadget
     /// guarantees that we cannot have two different epochs on two forks
     pub fn compare_epoch_id(
@@ -10,6 +10,12 @@ pub struct RpcStateChangesInBlockRequest {
 pub struct RpcStateChangesInBlockResponse {
     pub block_hash: near_primitives::hash::CryptoHash,
     pub changes: near_primitives::views::StateChangesView,
+    /// Set if the request has `include_proof`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proofs: Option<Vec<near_primitives::views::StateChangeProofView>>,
+    /// Cursor of the next page of changes, if there are more of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +24,12 @@ pub struct RpcStateChangesInBlockByTypeRequest {
     pub block_reference: near_primitives::types::BlockReference,
     #[serde(flatten)]
     pub state_changes_request: near_primitives::views::StateChangesRequestView,
+    /// Whether to prove the values the data, account and access key changes set. The changes
+    /// then come in pages, starting at `cursor`.
+    #[serde(default)]
+    pub include_proof: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +25,18 @@ pub struct RpcChunkResponse {
     pub chunk_view: near_primitives::views::ChunkView,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcChunkStateRootProofRequest {
+    #[serde(flatten)]
+    pub chunk_reference: ChunkReference,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcChunkStateRootProofResponse {
+    #[serde(flatten)]
+    pub proof: near_primitives::views::ChunkStateRootProofView,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcChunkError {
@@ -1,10 +1,19 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Block whose epoch to get the protocol config of, or the epoch itself. Epochs can be
+/// requested as long as the epoch manager knows them, even if their blocks aren't available.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProtocolConfigReference {
+    EpochId { epoch_id: near_primitives::types::EpochId },
+    BlockReference(near_primitives::types::BlockReference),
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcProtocolConfigRequest {
     #[serde(flatten)]
-    pub block_reference: near_primitives::types::BlockReference,
+    pub protocol_config_reference: ProtocolConfigReference,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -21,6 +30,11 @@ pub enum RpcProtocolConfigError {
         #[serde(skip_serializing)]
         error_message: String,
     },
+    #[error("Epoch is unknown: {error_message}")]
+    UnknownEpoch {
+        #[serde(skip_serializing)]
+        error_message: String,
+    },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
 }
@@ -31,6 +45,9 @@ impl From<RpcProtocolConfigError> for crate::errors::RpcError {
             RpcProtocolConfigError::UnknownBlock { error_message } => {
                 Some(Value::String(format!("Block Not Found: {}", error_message)))
             }
+            RpcProtocolConfigError::UnknownEpoch { error_message } => {
+                Some(Value::String(format!("Unknown Epoch: {}", error_message)))
+            }
             RpcProtocolConfigError::InternalError { .. } => Some(Value::String(error.to_string())),
         };
 
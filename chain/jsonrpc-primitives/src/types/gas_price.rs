@@ -1,4 +1,4 @@
-use near_primitives::types::MaybeBlockId;
+use near_primitives::types::{BlockHeight, MaybeBlockId};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -13,6 +13,18 @@ pub struct RpcGasPriceResponse {
     pub gas_price_view: near_primitives::views::GasPriceView,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcGasPriceRangeRequest {
+    pub from_height: BlockHeight,
+    pub to_height: BlockHeight,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcGasPriceRangeResponse {
+    #[serde(flatten)]
+    pub gas_price_range: near_primitives::views::GasPriceRangeView,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcGasPriceError {
@@ -23,6 +35,8 @@ pub enum RpcGasPriceError {
         #[serde(skip_serializing)]
         error_message: String,
     },
+    #[error("Invalid height range from {from_height} to {to_height}")]
+    InvalidHeightRange { from_height: BlockHeight, to_height: BlockHeight },
 }
 
 impl From<RpcGasPriceError> for crate::errors::RpcError {
@@ -32,7 +46,8 @@ impl From<RpcGasPriceError> for crate::errors::RpcError {
                 "DB Not Found Error: {} \n Cause: Unknown",
                 error_message
             ))),
-            RpcGasPriceError::InternalError { .. } => Some(Value::String(error.to_string())),
+            RpcGasPriceError::InternalError { .. }
+            | RpcGasPriceError::InvalidHeightRange { .. } => Some(Value::String(error.to_string())),
         };
 
         let error_data_value = match serde_json::to_value(error) {
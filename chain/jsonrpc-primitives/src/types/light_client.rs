@@ -7,6 +7,16 @@ pub struct RpcLightClientExecutionProofRequest {
     #[serde(flatten)]
     pub id: near_primitives::types::TransactionOrReceiptId,
     pub light_client_head: near_primitives::hash::CryptoHash,
+    #[serde(default)]
+    pub encoding: crate::types::proof_encoding::ProofEncoding,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcBlockMerkleProofRequest {
+    pub block_hash: near_primitives::hash::CryptoHash,
+    pub head_block_hash: near_primitives::hash::CryptoHash,
+    #[serde(default)]
+    pub encoding: crate::types::proof_encoding::ProofEncoding,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,6 +32,14 @@ pub struct RpcLightClientExecutionProofResponse {
     pub block_proof: near_primitives::merkle::MerklePath,
 }
 
+/// Merkle path of a block in the `block_merkle_root` of a head block, which covers the blocks
+/// before the head. The path is empty if both blocks are the same, as a block is not in its own
+/// root.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcBlockMerkleProofResponse {
+    pub block_proof: near_primitives::merkle::MerklePath,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RpcLightClientNextBlockResponse {
     #[serde(flatten)]
@@ -50,6 +68,15 @@ pub enum RpcLightClientProofError {
         transaction_or_receipt_id: near_primitives::hash::CryptoHash,
         shard_id: near_primitives::types::ShardId,
     },
+    #[error("Partial merkle tree of block {block_hash} is not available on the node")]
+    MissingBlockMerkleTree { block_hash: near_primitives::hash::CryptoHash },
+    #[error("Hash of the block with ordinal {block_ordinal} is not available on the node")]
+    MissingBlockOrdinal { block_ordinal: near_primitives::types::NumBlocks },
+    #[error("Block {block_hash} is after head block {head_block_hash}")]
+    BlockAfterHead {
+        block_hash: near_primitives::hash::CryptoHash,
+        head_block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("Internal error: {error_message}")]
     InternalError { error_message: String },
 }
@@ -5,9 +5,14 @@ pub mod config;
 pub mod gas_price;
 pub mod light_client;
 pub mod network_info;
+pub mod proof_encoding;
 pub mod query;
 pub mod receipts;
 pub mod sandbox;
+pub mod state_proof;
+pub mod state_root_pins;
 pub mod status;
 pub mod transactions;
+pub mod trie_key;
+pub mod trie_node;
 pub mod validator;
@@ -0,0 +1,239 @@
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Fields holding proofs, either as arrays of trie nodes or as merkle paths.
+const PROOF_FIELDS: [&str; 3] = ["proof", "outcome_root_proof", "block_proof"];
+
+const HEX_PREFIX: &str = "0x";
+
+/// Encoding of the proof fields of a response, chosen by the `encoding` field of the request.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofEncoding {
+    /// Trie nodes in base64 and merkle path hashes in base58, as in the views.
+    Base64,
+    /// Trie nodes and merkle path hashes in `0x`-prefixed hex.
+    Hex,
+}
+
+impl Default for ProofEncoding {
+    fn default() -> Self {
+        Self::Base64
+    }
+}
+
+/// Response whose proof fields are serialized with the given encoding.
+///
+/// Deserialization accepts either encoding and converts the proofs back, so `inner` is the same
+/// as the one serialized. Responses mixing both encodings are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithProofEncoding<T> {
+    pub inner: T,
+    pub encoding: ProofEncoding,
+}
+
+impl<T> WithProofEncoding<T> {
+    pub fn new(inner: T, encoding: ProofEncoding) -> Self {
+        Self { inner, encoding }
+    }
+}
+
+impl<T: Serialize> Serialize for WithProofEncoding<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = serde_json::to_value(&self.inner).map_err(S::Error::custom)?;
+        if self.encoding == ProofEncoding::Hex {
+            visit_proofs(&mut value, &mut encode_hex).map_err(S::Error::custom)?;
+        }
+        value.serialize(serializer)
+    }
+}
+
+impl<'de, T: serde::de::DeserializeOwned> Deserialize<'de> for WithProofEncoding<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let mut found = None;
+        visit_proofs(&mut value, &mut |node| {
+            let encoding = node_encoding(node);
+            if *found.get_or_insert(encoding) != encoding {
+                return Err("proof nodes mix base64 and hex encodings".to_string());
+            }
+            match encoding {
+                ProofEncoding::Base64 => Ok(()),
+                ProofEncoding::Hex => decode_hex(node),
+            }
+        })
+        .map_err(D::Error::custom)?;
+        let inner = T::deserialize(value).map_err(D::Error::custom)?;
+        Ok(Self { inner, encoding: found.unwrap_or_default() })
+    }
+}
+
+/// Calls `f` on every node of the proof fields in `value`: trie nodes are strings and merkle path
+/// items are objects with a `hash`.
+fn visit_proofs(
+    value: &mut Value,
+    f: &mut impl FnMut(&mut Value) -> Result<(), String>,
+) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                match field {
+                    Value::Array(nodes) if PROOF_FIELDS.contains(&key.as_str()) => {
+                        for node in nodes {
+                            f(node)?;
+                        }
+                    }
+                    _ => visit_proofs(field, f)?,
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                visit_proofs(item, f)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Returns the string holding the bytes of a proof node and whether it's a merkle path hash.
+fn node_bytes(node: &mut Value) -> Result<(&mut String, bool), String> {
+    match node {
+        Value::String(s) => Ok((s, false)),
+        Value::Object(item) => match item.get_mut("hash") {
+            Some(Value::String(s)) => Ok((s, true)),
+            _ => Err("merkle path item without a hash".to_string()),
+        },
+        other => Err(format!("unexpected proof node {}", other)),
+    }
+}
+
+/// Trie nodes start with a small tag byte, so their base64 never starts with the hex prefix, and
+/// neither does base58.
+fn node_encoding(node: &Value) -> ProofEncoding {
+    let s = match node {
+        Value::Object(item) => item.get("hash").and_then(Value::as_str),
+        _ => node.as_str(),
+    };
+    if s.map_or(false, |s| s.starts_with(HEX_PREFIX)) {
+        ProofEncoding::Hex
+    } else {
+        ProofEncoding::Base64
+    }
+}
+
+fn encode_hex(node: &mut Value) -> Result<(), String> {
+    let (s, is_hash) = node_bytes(node)?;
+    let bytes = if is_hash {
+        near_primitives::serialize::from_base(s)
+    } else {
+        near_primitives::serialize::from_base64(s)
+    }
+    .map_err(|err| err.to_string())?;
+    *s = format!("{}{}", HEX_PREFIX, hex::encode(bytes));
+    Ok(())
+}
+
+fn decode_hex(node: &mut Value) -> Result<(), String> {
+    let (s, is_hash) = node_bytes(node)?;
+    let bytes = hex::decode(&s[HEX_PREFIX.len()..])
+        .map_err(|err| format!("invalid hex proof node {}: {}", s, err))?;
+    *s = if is_hash {
+        near_primitives::serialize::to_base(bytes)
+    } else {
+        near_primitives::serialize::to_base64(bytes)
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::light_client::RpcBlockMerkleProofResponse;
+    use crate::types::state_proof::RpcStateProofResponse;
+    use near_primitives::hash::hash;
+    use near_primitives::merkle::{Direction, MerklePathItem};
+    use near_primitives::sharding::ChunkHash;
+    use near_primitives::views::StateProofView;
+
+    fn state_proof_response() -> RpcStateProofResponse {
+        RpcStateProofResponse {
+            state_proof: StateProofView {
+                value: Some(vec![1, 2, 3]),
+                proof: vec!["AQID".to_string(), "BAU=".to_string()],
+                state_root: hash(b"root"),
+                shard_id: 1,
+                chunk_hash: ChunkHash(hash(b"chunk")),
+            },
+            block_height: 1,
+            block_hash: hash(b"block"),
+        }
+    }
+
+    #[test]
+    fn test_state_proof_round_trip() {
+        for (encoding, proof) in [
+            (ProofEncoding::Base64, serde_json::json!(["AQID", "BAU="])),
+            (ProofEncoding::Hex, serde_json::json!(["0x010203", "0x0405"])),
+        ] {
+            let response = WithProofEncoding::new(state_proof_response(), encoding);
+            let json = serde_json::to_value(&response).unwrap();
+            assert_eq!(json["proof"], proof);
+            // Only the proof is affected by the encoding.
+            assert_eq!(json["value_base64"], serde_json::json!("AQID"));
+            let parsed: WithProofEncoding<RpcStateProofResponse> =
+                serde_json::from_value(json).unwrap();
+            assert_eq!(parsed.encoding, encoding);
+            assert_eq!(parsed.inner.state_proof, response.inner.state_proof);
+        }
+    }
+
+    #[test]
+    fn test_merkle_path_round_trip() {
+        let block_proof = vec![
+            MerklePathItem { hash: hash(b"left"), direction: Direction::Left },
+            MerklePathItem { hash: hash(b"right"), direction: Direction::Right },
+        ];
+        for encoding in [ProofEncoding::Base64, ProofEncoding::Hex] {
+            let response = WithProofEncoding::new(
+                RpcBlockMerkleProofResponse { block_proof: block_proof.clone() },
+                encoding,
+            );
+            let json = serde_json::to_value(&response).unwrap();
+            let expected = match encoding {
+                ProofEncoding::Base64 => serde_json::json!(hash(b"left")),
+                ProofEncoding::Hex => {
+                    serde_json::json!(format!("0x{}", hex::encode(hash(b"left").as_bytes())))
+                }
+            };
+            assert_eq!(json["block_proof"][0]["hash"], expected);
+            assert_eq!(json["block_proof"][0]["direction"], serde_json::json!("Left"));
+            let parsed: WithProofEncoding<RpcBlockMerkleProofResponse> =
+                serde_json::from_value(json).unwrap();
+            assert_eq!(parsed.encoding, encoding);
+            assert_eq!(parsed.inner.block_proof, block_proof);
+        }
+    }
+
+    #[test]
+    fn test_mixed_encodings_rejected() {
+        let mut json = serde_json::to_value(&state_proof_response()).unwrap();
+        json["proof"] = serde_json::json!(["AQID", "0x0405"]);
+        let err =
+            serde_json::from_value::<WithProofEncoding<RpcStateProofResponse>>(json).unwrap_err();
+        assert_eq!(err.to_string(), "proof nodes mix base64 and hex encodings");
+    }
+
+    #[test]
+    fn test_encoding_request_field() {
+        assert_eq!(serde_json::to_value(ProofEncoding::Hex).unwrap(), serde_json::json!("hex"));
+        assert_eq!(
+            serde_json::from_value::<ProofEncoding>(serde_json::json!("base64")).unwrap(),
+            ProofEncoding::Base64
+        );
+        assert!(serde_json::from_value::<ProofEncoding>(serde_json::json!("base58")).is_err());
+    }
+}
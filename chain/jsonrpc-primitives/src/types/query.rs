@@ -6,6 +6,8 @@ pub struct RpcQueryRequest {
     pub block_reference: near_primitives::types::BlockReference,
     #[serde(flatten)]
     pub request: near_primitives::views::QueryRequest,
+    #[serde(default)]
+    pub encoding: crate::types::proof_encoding::ProofEncoding,
 }
 
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
@@ -16,11 +18,12 @@ pub enum RpcQueryError {
     #[error("The node does not track the shard ID {requested_shard_id}")]
     UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
     #[error(
-        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+        "The data for block #{block_height} is garbage collected on this node (data is kept from block #{gc_horizon}), use an archival node to fetch historical data"
     )]
     GarbageCollectedBlock {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
+        gc_horizon: near_primitives::types::BlockHeight,
     },
     #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
     UnknownBlock { block_reference: near_primitives::types::BlockReference },
@@ -44,9 +47,13 @@ pub enum RpcQueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
-    #[error("State of contract {contract_account_id} is too large to be viewed")]
+    #[error(
+        "State of contract {contract_account_id} is too large to be viewed: {state_size} bytes, the limit is {state_size_limit}"
+    )]
     TooLargeContractState {
         contract_account_id: near_primitives::types::AccountId,
+        state_size: u64,
+        state_size_limit: u64,
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
@@ -77,11 +84,16 @@ pub struct RpcQueryResponse {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(untagged)]
 pub enum QueryResponseKind {
+    // Variants with proofs go before the plain ones, which they would also parse as.
+    ViewAccountWithProof(near_primitives::views::AccountWithProofView),
     ViewAccount(near_primitives::views::AccountView),
+    ViewCodeWithProof(near_primitives::views::ContractCodeWithProofView),
     ViewCode(near_primitives::views::ContractCodeView),
     ViewState(near_primitives::views::ViewStateResult),
     CallResult(near_primitives::views::CallResult),
+    AccessKeyWithProof(near_primitives::views::AccessKeyWithProofView),
     AccessKey(near_primitives::views::AccessKeyView),
+    AccessKeyListWithProof(near_primitives::views::AccessKeyListWithProof),
     AccessKeyList(near_primitives::views::AccessKeyList),
 }
 
@@ -100,3 +112,255 @@ impl From<RpcQueryError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(error_data, error_data_value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::proof_encoding::{ProofEncoding, WithProofEncoding};
+    use near_primitives::views::{
+        AccessKeyList, AccessKeyListWithProof, AccessKeyPermissionView, AccessKeyView,
+        AccessKeyWithProofView, AccountView, AccountWithProofView, ContractCodeView,
+        ContractCodeWithProofView, ElidedValueView, QueryRequest, StateItem, ViewStateProofKind,
+        ViewStateResult,
+    };
+
+    fn parse_view_state(params: serde_json::Value) -> (bool, Option<Vec<u8>>, Option<u64>) {
+        let request: RpcQueryRequest = serde_json::from_value(params).unwrap();
+        // Serialized requests parse back to the same request.
+        let request: RpcQueryRequest =
+            serde_json::from_value(serde_json::to_value(request).unwrap()).unwrap();
+        match request.request {
+            QueryRequest::ViewState { include_proof, from_key, limit, .. } => {
+                (include_proof, from_key, limit)
+            }
+            request => panic!("unexpected request: {:?}", request),
+        }
+    }
+
+    #[test]
+    fn test_view_state_request_serde() {
+        let params = serde_json::json!({
+            "request_type": "view_state",
+            "finality": "final",
+            "account_id": "test",
+            "prefix_base64": "",
+        });
+        assert_eq!(parse_view_state(params.clone()), (true, None, None));
+
+        let mut with_options = params;
+        with_options["include_proof"] = false.into();
+        with_options["from_key_base64"] = "dGVzdA==".into();
+        with_options["limit"] = 2.into();
+        assert_eq!(parse_view_state(with_options), (false, Some(b"test".to_vec()), Some(2)));
+    }
+
+    #[test]
+    fn test_view_account_serde() {
+        let params = serde_json::json!({
+            "request_type": "view_account",
+            "finality": "final",
+            "account_id": "test",
+        });
+        let request: RpcQueryRequest = serde_json::from_value(params.clone()).unwrap();
+        assert!(matches!(request.request, QueryRequest::ViewAccount { include_proof: false, .. }));
+        let mut with_proof = params;
+        with_proof["include_proof"] = true.into();
+        let request: RpcQueryRequest = serde_json::from_value(with_proof.clone()).unwrap();
+        assert!(matches!(request.request, QueryRequest::ViewAccount { include_proof: true, .. }));
+        assert_eq!(request.encoding, ProofEncoding::Base64);
+        let mut with_hex = with_proof;
+        with_hex["encoding"] = "hex".into();
+        let request: RpcQueryRequest = serde_json::from_value(with_hex).unwrap();
+        assert!(matches!(request.request, QueryRequest::ViewAccount { include_proof: true, .. }));
+        assert_eq!(request.encoding, ProofEncoding::Hex);
+
+        let account = AccountView {
+            amount: 1,
+            locked: 0,
+            code_hash: Default::default(),
+            storage_usage: 100,
+            storage_paid_at: 0,
+        };
+        let with_proof =
+            AccountWithProofView { account: account.clone(), proof: vec!["AQID".to_string()] };
+        for kind in [
+            QueryResponseKind::ViewAccount(account),
+            QueryResponseKind::ViewAccountWithProof(with_proof),
+        ] {
+            let response =
+                RpcQueryResponse { kind, block_height: 1, block_hash: Default::default() };
+            let json = serde_json::to_value(&response).unwrap();
+            let parsed: RpcQueryResponse = serde_json::from_value(json).unwrap();
+            // The proof in hex parses back to the same response.
+            let json =
+                serde_json::to_value(WithProofEncoding::new(parsed, ProofEncoding::Hex)).unwrap();
+            let parsed: WithProofEncoding<RpcQueryResponse> = serde_json::from_value(json).unwrap();
+            match (response.kind, parsed.inner.kind) {
+                (QueryResponseKind::ViewAccount(a), QueryResponseKind::ViewAccount(b)) => {
+                    assert_eq!(a, b)
+                }
+                (
+                    QueryResponseKind::ViewAccountWithProof(a),
+                    QueryResponseKind::ViewAccountWithProof(b),
+                ) => assert_eq!(a, b),
+                (kind, parsed) => panic!("{:?} parsed as {:?}", kind, parsed),
+            }
+        }
+    }
+
+    #[test]
+    fn test_access_key_response_serde() {
+        let access_key =
+            AccessKeyView { nonce: 1, permission: AccessKeyPermissionView::FullAccess };
+        let proof = vec!["AQID".to_string()];
+        for kind in [
+            QueryResponseKind::AccessKey(access_key.clone()),
+            QueryResponseKind::AccessKeyWithProof(AccessKeyWithProofView {
+                access_key,
+                proof: proof.clone(),
+            }),
+            QueryResponseKind::AccessKeyList(AccessKeyList { keys: vec![] }),
+            QueryResponseKind::AccessKeyListWithProof(AccessKeyListWithProof {
+                keys: vec![],
+                proof,
+            }),
+        ] {
+            let response =
+                RpcQueryResponse { kind, block_height: 1, block_hash: Default::default() };
+            let json = serde_json::to_value(&response).unwrap();
+            let parsed: RpcQueryResponse = serde_json::from_value(json).unwrap();
+            assert_eq!(format!("{:?}", parsed.kind), format!("{:?}", response.kind));
+        }
+    }
+
+    #[test]
+    fn test_contract_code_serde() {
+        let params = serde_json::json!({
+            "request_type": "view_code",
+            "finality": "final",
+            "account_id": "test",
+        });
+        let request: RpcQueryRequest = serde_json::from_value(params.clone()).unwrap();
+        assert!(matches!(
+            request.request,
+            QueryRequest::ViewCode { include_proof: false, include_code: true, .. }
+        ));
+        let mut hash_only = params;
+        hash_only["include_proof"] = true.into();
+        hash_only["include_code"] = false.into();
+        let request: RpcQueryRequest = serde_json::from_value(hash_only).unwrap();
+        assert!(matches!(
+            request.request,
+            QueryRequest::ViewCode { include_proof: true, include_code: false, .. }
+        ));
+
+        let account = AccountView {
+            amount: 1,
+            locked: 0,
+            code_hash: Default::default(),
+            storage_usage: 100,
+            storage_paid_at: 0,
+        };
+        let with_proof = ContractCodeWithProofView {
+            code: Some(vec![1, 2, 3]),
+            hash: Default::default(),
+            account,
+            proof: vec!["AQID".to_string()],
+        };
+        for kind in [
+            QueryResponseKind::ViewCode(ContractCodeView {
+                code: vec![1, 2, 3],
+                hash: Default::default(),
+            }),
+            QueryResponseKind::ViewCodeWithProof(with_proof.clone()),
+            QueryResponseKind::ViewCodeWithProof(ContractCodeWithProofView {
+                code: None,
+                ..with_proof
+            }),
+        ] {
+            let response =
+                RpcQueryResponse { kind, block_height: 1, block_hash: Default::default() };
+            let json = serde_json::to_value(&response).unwrap();
+            let parsed: RpcQueryResponse = serde_json::from_value(json).unwrap();
+            assert_eq!(format!("{:?}", parsed.kind), format!("{:?}", response.kind));
+        }
+    }
+
+    #[test]
+    fn test_view_state_response_serde() {
+        let with_proof = ViewStateResult {
+            values: vec![],
+            proof: vec!["AQID".to_string()],
+            next_key: None,
+            proof_kind: Some(ViewStateProofKind::Range),
+            truncated: false,
+            account_state_size: None,
+        };
+        let with_non_existence_proof = ViewStateResult {
+            proof_kind: Some(ViewStateProofKind::NonExistence),
+            ..with_proof.clone()
+        };
+        let without_proof =
+            ViewStateResult { proof: vec![], proof_kind: None, ..with_proof.clone() };
+        for (result, proof_kind) in [
+            (with_proof, Some("range")),
+            (with_non_existence_proof, Some("non_existence")),
+            (without_proof, None),
+        ] {
+            let response = RpcQueryResponse {
+                kind: QueryResponseKind::ViewState(result.clone()),
+                block_height: 1,
+                block_hash: Default::default(),
+            };
+            let json = serde_json::to_value(&response).unwrap();
+            assert_eq!(json["proof"], serde_json::json!(result.proof));
+            assert_eq!(json.get("proof_kind"), proof_kind.map(serde_json::Value::from).as_ref());
+            assert!(json.get("next_key").is_none());
+            assert!(json.get("truncated").is_none());
+            assert!(json.get("account_state_size").is_none());
+            let response: RpcQueryResponse = serde_json::from_value(json).unwrap();
+            match response.kind {
+                QueryResponseKind::ViewState(parsed) => assert_eq!(parsed, result),
+                kind => panic!("unexpected response: {:?}", kind),
+            }
+        }
+
+        let partial = ViewStateResult {
+            values: vec![],
+            proof: vec![],
+            next_key: Some("dGVzdA==".to_string()),
+            proof_kind: None,
+            truncated: true,
+            account_state_size: Some(50_001),
+        };
+        let json = serde_json::to_value(&partial).unwrap();
+        assert_eq!(json["truncated"], serde_json::json!(true));
+        assert_eq!(json["account_state_size"], serde_json::json!(50_001));
+        assert_eq!(serde_json::from_value::<ViewStateResult>(json).unwrap(), partial);
+    }
+
+    #[test]
+    fn test_state_item_serde() {
+        // Items without an elided value keep their former form.
+        let json = serde_json::json!({"key": "AQ==", "value": "Ag==", "proof": []});
+        let item: StateItem = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(item.elided_value, None);
+        assert_eq!(serde_json::to_value(&item).unwrap(), json);
+
+        let elided = StateItem {
+            key: "AQ==".to_string(),
+            value: String::new(),
+            proof: vec![],
+            elided_value: Some(ElidedValueView { hash: Default::default(), length: 1000 }),
+            proof_node_indices: vec![],
+        };
+        let json = serde_json::to_value(&elided).unwrap();
+        assert_eq!(json["elided_value"]["length"], 1000);
+        assert_eq!(serde_json::from_value::<StateItem>(json).unwrap(), elided);
+
+        let proven = StateItem { proof_node_indices: vec![0, 3, 5], ..item };
+        let json = serde_json::to_value(&proven).unwrap();
+        assert_eq!(json["proof_node_indices"], serde_json::json!([0, 3, 5]));
+        assert_eq!(serde_json::from_value::<StateItem>(json).unwrap(), proven);
+    }
+}
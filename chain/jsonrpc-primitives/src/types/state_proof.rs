@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcStateProofRequest {
+    #[serde(flatten)]
+    pub block_reference: near_primitives::types::BlockReference,
+    pub account_id: near_primitives::types::AccountId,
+    #[serde(rename = "key_base64", with = "near_primitives::serialize::base64_format")]
+    pub key: Vec<u8>,
+    #[serde(default)]
+    pub encoding: crate::types::proof_encoding::ProofEncoding,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcStateProofResponse {
+    #[serde(flatten)]
+    pub state_proof: near_primitives::views::StateProofView,
+    pub block_height: near_primitives::types::BlockHeight,
+    pub block_hash: near_primitives::hash::CryptoHash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+    use near_primitives::sharding::ChunkHash;
+    use near_primitives::types::{BlockId, BlockReference};
+    use near_primitives::views::StateProofView;
+
+    #[test]
+    fn test_state_proof_request_serde() {
+        let request: RpcStateProofRequest = serde_json::from_value(serde_json::json!({
+            "block_id": 10,
+            "account_id": "alice.near",
+            "key_base64": "AQI=",
+        }))
+        .unwrap();
+        assert!(matches!(request.block_reference, BlockReference::BlockId(BlockId::Height(10))));
+        assert_eq!(request.account_id.as_ref(), "alice.near");
+        assert_eq!(request.key, vec![1, 2]);
+
+        let request: Result<RpcStateProofRequest, _> = serde_json::from_value(serde_json::json!({
+            "finality": "final",
+            "account_id": "alice.near",
+        }));
+        assert!(request.is_err());
+    }
+
+    #[test]
+    fn test_state_proof_response_serde() {
+        let present = StateProofView {
+            value: Some(vec![1, 2, 3]),
+            proof: vec!["AQID".to_string()],
+            state_root: hash(b"root"),
+            shard_id: 1,
+            chunk_hash: ChunkHash(hash(b"chunk")),
+        };
+        let absent = StateProofView { value: None, ..present.clone() };
+        for (state_proof, value) in
+            [(present, serde_json::json!("AQID")), (absent, serde_json::Value::Null)]
+        {
+            let response = RpcStateProofResponse {
+                state_proof: state_proof.clone(),
+                block_height: 1,
+                block_hash: hash(b"block"),
+            };
+            let json = serde_json::to_value(&response).unwrap();
+            assert_eq!(json["value_base64"], value);
+            assert_eq!(json["proof"], serde_json::json!(["AQID"]));
+            assert_eq!(json["chunk_hash"], serde_json::json!(hash(b"chunk")));
+            let parsed: RpcStateProofResponse = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed.state_proof, state_proof);
+            assert_eq!(parsed.block_height, 1);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcStateRootPinRequest {
+    pub block_hash: near_primitives::hash::CryptoHash,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcStateRootPinsResponse {
+    pub pins: Vec<near_client_primitives::debug::PinnedStateRootsView>,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcStateRootPinsError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+    #[error("State roots are not available: {error_message}")]
+    UnknownStateRoots { error_message: String },
+}
+
+impl From<RpcStateRootPinsError> for crate::errors::RpcError {
+    fn from(error: RpcStateRootPinsError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcStateRootPinsError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
@@ -6,9 +6,16 @@ pub struct RpcBroadcastTransactionRequest {
     pub signed_transaction: near_primitives::transaction::SignedTransaction,
 }
 
+#[derive(Debug, Clone)]
+pub struct RpcSendTransactionRequest {
+    pub signed_transaction: near_primitives::transaction::SignedTransaction,
+    pub wait_until: near_primitives::views::TxExecutionStatus,
+}
+
 #[derive(Debug)]
 pub struct RpcTransactionStatusCommonRequest {
     pub transaction_info: TransactionInfo,
+    pub include_proofs: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -44,6 +51,30 @@ pub enum RpcTransactionError {
 pub struct RpcTransactionResponse {
     #[serde(flatten)]
     pub final_execution_outcome: near_primitives::views::FinalExecutionOutcomeViewEnum,
+    /// Outcome root proofs of the transaction outcome and the receipt outcomes, if requested.
+    /// Outcomes not followed by a chunk of their shard yet have no proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome_root_proofs: Option<Vec<near_primitives::views::OutcomeRootProofView>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSendTransactionResponse {
+    /// The status the transaction was waited for.
+    pub final_execution_status: near_primitives::views::TxExecutionStatus,
+    #[serde(flatten)]
+    pub outcome: RpcSendTransactionOutcome,
+}
+
+/// The outcomes available once the transaction reached the requested status.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum RpcSendTransactionOutcome {
+    /// The transaction and all its receipts are executed.
+    Executed(near_primitives::views::FinalExecutionOutcomeView),
+    /// The transaction is included in a block.
+    Included { transaction_outcome: near_primitives::views::ExecutionOutcomeWithIdView },
+    /// The transaction is accepted by the node.
+    Accepted { transaction_hash: near_primitives::hash::CryptoHash },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcRawTrieKeyRequest {
+    #[serde(flatten)]
+    pub trie_key: near_primitives::views::TrieKeyView,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct RpcRawTrieKeyResponse {
+    /// Column id the raw key starts with.
+    pub column: u8,
+    #[serde(rename = "raw_key_base64", with = "near_primitives::serialize::base64_format")]
+    pub raw_key: Vec<u8>,
+}
+
+impl From<near_primitives::trie_key::TrieKey> for RpcRawTrieKeyResponse {
+    fn from(trie_key: near_primitives::trie_key::TrieKey) -> Self {
+        let raw_key = trie_key.to_vec();
+        Self { column: raw_key[0], raw_key }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::hash::hash;
+    use near_primitives::serialize::to_base64;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::views::TrieKeyView;
+
+    fn raw_trie_key(params: serde_json::Value) -> RpcRawTrieKeyResponse {
+        let request: RpcRawTrieKeyRequest = serde_json::from_value(params.clone()).unwrap();
+        // Serialized requests parse back to the same request.
+        assert_eq!(serde_json::to_value(&request).unwrap(), params);
+        TrieKey::from(request.trie_key).into()
+    }
+
+    #[test]
+    fn test_raw_trie_key_request_serde() {
+        let account_id: near_primitives::types::AccountId = "alice.near".parse().unwrap();
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let data_id = hash(b"data");
+        let receipt_id = hash(b"receipt");
+        let cases = [
+            (
+                serde_json::json!({"key_type": "account", "account_id": "alice.near"}),
+                TrieKey::Account { account_id: account_id.clone() },
+            ),
+            (
+                serde_json::json!({"key_type": "contract_code", "account_id": "alice.near"}),
+                TrieKey::ContractCode { account_id: account_id.clone() },
+            ),
+            (
+                serde_json::json!({
+                    "key_type": "access_key",
+                    "account_id": "alice.near",
+                    "public_key": public_key.to_string(),
+                }),
+                TrieKey::AccessKey { account_id: account_id.clone(), public_key },
+            ),
+            (
+                serde_json::json!({
+                    "key_type": "received_data",
+                    "receiver_id": "alice.near",
+                    "data_id": data_id,
+                }),
+                TrieKey::ReceivedData { receiver_id: account_id.clone(), data_id },
+            ),
+            (
+                serde_json::json!({
+                    "key_type": "postponed_receipt_id",
+                    "receiver_id": "alice.near",
+                    "data_id": data_id,
+                }),
+                TrieKey::PostponedReceiptId { receiver_id: account_id.clone(), data_id },
+            ),
+            (
+                serde_json::json!({
+                    "key_type": "pending_data_count",
+                    "receiver_id": "alice.near",
+                    "receipt_id": receipt_id,
+                }),
+                TrieKey::PendingDataCount { receiver_id: account_id.clone(), receipt_id },
+            ),
+            (
+                serde_json::json!({
+                    "key_type": "postponed_receipt",
+                    "receiver_id": "alice.near",
+                    "receipt_id": receipt_id,
+                }),
+                TrieKey::PostponedReceipt { receiver_id: account_id.clone(), receipt_id },
+            ),
+            (
+                serde_json::json!({"key_type": "delayed_receipt_indices"}),
+                TrieKey::DelayedReceiptIndices,
+            ),
+            (
+                serde_json::json!({"key_type": "delayed_receipt", "index": 7}),
+                TrieKey::DelayedReceipt { index: 7 },
+            ),
+            (
+                serde_json::json!({
+                    "key_type": "contract_data",
+                    "account_id": "alice.near",
+                    "key_base64": "AQI=",
+                }),
+                TrieKey::ContractData { account_id, key: vec![1, 2] },
+            ),
+        ];
+        for (params, trie_key) in cases {
+            let response = raw_trie_key(params);
+            assert_eq!(response.raw_key, trie_key.to_vec());
+            assert_eq!(response.column, trie_key.to_vec()[0]);
+        }
+    }
+
+    #[test]
+    fn test_raw_trie_key_response_serde() {
+        let trie_key = TrieKey::ContractData {
+            account_id: "alice.near".parse().unwrap(),
+            key: b"key".to_vec(),
+        };
+        let response = RpcRawTrieKeyResponse::from(trie_key.clone());
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"column": 9, "raw_key_base64": to_base64(&trie_key.to_vec())})
+        );
+        assert_eq!(serde_json::from_value::<RpcRawTrieKeyResponse>(json).unwrap(), response);
+    }
+
+    #[test]
+    fn test_raw_trie_key_invalid_account_id() {
+        for params in [
+            serde_json::json!({"key_type": "account", "account_id": "Alice.near"}),
+            serde_json::json!({"key_type": "contract_data", "account_id": "", "key_base64": ""}),
+            serde_json::json!({
+                "key_type": "received_data",
+                "receiver_id": "a",
+                "data_id": hash(b"data"),
+            }),
+        ] {
+            let request: Result<RpcRawTrieKeyRequest, _> = serde_json::from_value(params);
+            assert!(request.is_err());
+        }
+        let request: Result<RpcRawTrieKeyRequest, _> = serde_json::from_value(
+            serde_json::json!({"key_type": "unknown", "account_id": "alice.near"}),
+        );
+        assert!(request.is_err());
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTrieNodeRequest {
+    pub shard_id: near_primitives::types::ShardId,
+    pub hash: near_primitives::hash::CryptoHash,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTrieNodeResponse {
+    #[serde(flatten)]
+    pub trie_node: near_primitives::views::TrieNodeResponse,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcTrieNodeError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+    #[error("Shard {shard_id} does not exist")]
+    UnknownShard { shard_id: near_primitives::types::ShardId },
+    #[error("Trie node with hash {hash} is not stored on this node")]
+    UnknownTrieNode { hash: near_primitives::hash::CryptoHash },
+    #[error("Trie nodes are not served by validator nodes")]
+    ValidatorNode,
+}
+
+impl From<RpcTrieNodeError> for crate::errors::RpcError {
+    fn from(error: RpcTrieNodeError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcTrieNodeError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
@@ -32,6 +32,30 @@ pub struct RpcValidatorResponse {
     pub validator_info: near_primitives::views::EpochValidatorInfo,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcProducerScheduleRequest {
+    #[serde(flatten)]
+    pub epoch_reference: near_primitives::types::EpochReference,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcProducerScheduleResponse {
+    #[serde(flatten)]
+    pub producer_schedule: near_primitives::views::ProducerScheduleView,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcNextValidatorSetRequest {
+    #[serde(flatten)]
+    pub block_reference: near_primitives::types::BlockReference,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcNextValidatorSetResponse {
+    #[serde(flatten)]
+    pub next_validator_set: near_primitives::views::NextValidatorSetView,
+}
+
 impl From<RpcValidatorError> for crate::errors::RpcError {
     fn from(error: RpcValidatorError) -> Self {
         let error_data = match &error {
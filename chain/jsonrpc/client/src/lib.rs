@@ -8,15 +8,16 @@ use serde::Serialize;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{from_slice, Message};
 use near_jsonrpc_primitives::types::changes::{
-    RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockByTypeResponse,
+    RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockResponse,
 };
+use near_jsonrpc_primitives::types::transactions::RpcSendTransactionResponse;
 use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{AccountId, BlockId, BlockReference, MaybeBlockId, ShardId};
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, EpochValidatorInfo, FinalExecutionOutcomeView, GasPriceView,
-    StatusResponse,
+    StatusResponse, TxExecutionStatus,
 };
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -185,6 +186,7 @@ macro_rules! jsonrpc_client {
 jsonrpc_client!(pub struct JsonRpcClient {
     pub fn broadcast_tx_async(&self, tx: String) -> RpcRequest<String>;
     pub fn broadcast_tx_commit(&self, tx: String) -> RpcRequest<FinalExecutionOutcomeView>;
+    pub fn send_tx(&self, tx: String, wait_until: TxExecutionStatus) -> RpcRequest<RpcSendTransactionResponse>;
     pub fn status(&self) -> RpcRequest<StatusResponse>;
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_check_tx(&self, tx: String) -> RpcRequest<serde_json::Value>;
@@ -219,6 +221,11 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "query", request)
     }
 
+    /// Calls `method` with the given parameters, returning the result as JSON.
+    pub fn call(&self, method: &str, params: serde_json::Value) -> RpcRequest<serde_json::Value> {
+        call_method(&self.client, &self.server_addr, method, params)
+    }
+
     pub fn block_by_id(&self, block_id: BlockId) -> RpcRequest<BlockView> {
         call_method(&self.client, &self.server_addr, "block", [block_id])
     }
@@ -227,14 +234,39 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "block", request)
     }
 
+    pub fn light_client_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofRequest,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse,
+    > {
+        call_method(&self.client, &self.server_addr, "light_client_proof", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_block_merkle_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::light_client::RpcBlockMerkleProofRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::light_client::RpcBlockMerkleProofResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_block_merkle_proof", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_changes(
         &self,
         request: RpcStateChangesInBlockByTypeRequest,
-    ) -> RpcRequest<RpcStateChangesInBlockByTypeResponse> {
+    ) -> RpcRequest<RpcStateChangesInBlockResponse> {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_changes", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_chunk_state_root_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::chunks::RpcChunkStateRootProofRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::chunks::RpcChunkStateRootProofResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_chunk_state_root_proof", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_validators_ordered(
         &self,
@@ -243,6 +275,30 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validators_ordered", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_gas_price_range(
+        &self,
+        request: near_jsonrpc_primitives::types::gas_price::RpcGasPriceRangeRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::gas_price::RpcGasPriceRangeResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_gas_price_range", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_next_validator_set(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcNextValidatorSetRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::validator::RpcNextValidatorSetResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_next_validator_set", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_producer_schedule(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcProducerScheduleRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::validator::RpcProducerScheduleResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_producer_schedule", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_receipt(
         &self,
@@ -258,6 +314,30 @@ impl JsonRpcClient {
     ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse> {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_protocol_config", request)
     }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_raw_trie_key(
+        &self,
+        request: near_jsonrpc_primitives::types::trie_key::RpcRawTrieKeyRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::trie_key::RpcRawTrieKeyResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_raw_trie_key", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_state_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::state_proof::RpcStateProofRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::state_proof::RpcStateProofResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_state_proof", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_trie_node(
+        &self,
+        request: near_jsonrpc_primitives::types::trie_node::RpcTrieNodeRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::trie_node::RpcTrieNodeResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_trie_node", request)
+    }
 }
 
 fn create_client() -> Client {
@@ -6,7 +6,8 @@ use serde_json::json;
 
 use near_actix_test_utils::run_actix;
 use near_crypto::{KeyType, PublicKey, Signature};
-use near_jsonrpc::client::{new_client, ChunkId};
+use near_jsonrpc::client::{new_client, ChunkId, JsonRpcClient};
+use near_jsonrpc::RpcLimitsConfig;
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
 use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
 use near_logger_utils::init_test_logger;
@@ -158,21 +159,33 @@ fn test_query_account() {
         let query_response_1 = client
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::latest(),
-                request: QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "test".parse().unwrap(),
+                    include_proof: false,
+                },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
         let query_response_2 = client
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::BlockId(BlockId::Height(0)),
-                request: QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "test".parse().unwrap(),
+                    include_proof: false,
+                },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
         let query_response_3 = client
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::BlockId(BlockId::Hash(block_hash)),
-                request: QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "test".parse().unwrap(),
+                    include_proof: false,
+                },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
@@ -221,7 +234,11 @@ fn test_query_access_keys() {
         let query_response = client
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::latest(),
-                request: QueryRequest::ViewAccessKeyList { account_id: "test".parse().unwrap() },
+                request: QueryRequest::ViewAccessKeyList {
+                    account_id: "test".parse().unwrap(),
+                    include_proof: false,
+                },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
@@ -272,7 +289,9 @@ fn test_query_access_key() {
                     public_key: "ed25519:23vYngy8iL7q94jby3gszBnZ9JptpMf5Hgf7KVVa2yQ2"
                         .parse()
                         .unwrap(),
+                    include_proof: false,
                 },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
@@ -297,7 +316,14 @@ fn test_query_state() {
                 request: QueryRequest::ViewState {
                     account_id: "test".parse().unwrap(),
                     prefix: vec![].into(),
+                    from_key: None,
+                    limit: None,
+                    max_value_size: None,
+                    state_size_limit: None,
+                    include_proof: true,
+                    allow_partial: false,
                 },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
@@ -322,7 +348,9 @@ fn test_query_call_function() {
                     account_id: "test".parse().unwrap(),
                     method_name: "method".to_string(),
                     args: vec![].into(),
+                    max_gas: None,
                 },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
@@ -347,7 +375,12 @@ fn test_query_contract_code() {
         let query_response = client
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::latest(),
-                request: QueryRequest::ViewCode { account_id: "test".parse().unwrap() },
+                request: QueryRequest::ViewCode {
+                    account_id: "test".parse().unwrap(),
+                    include_proof: false,
+                    include_code: true,
+                },
+                encoding: Default::default(),
             })
             .await
             .unwrap();
@@ -586,3 +619,101 @@ fn test_get_chunk_with_object_in_params() {
         assert_eq!(chunk.header.chunk_hash, same_chunk.header.chunk_hash);
     });
 }
+
+/// Trie nodes can only be fetched with the debug RPC enabled, which it isn't by default.
+#[test]
+fn test_trie_node_disabled_by_default() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let error = test_utils::call_method::<serde_json::Value>(
+            &client.client,
+            &client.server_addr,
+            "EXPERIMENTAL_trie_node",
+            json!({
+                "shard_id": 0u64,
+                "hash": CryptoHash::default(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.code, -32_601);
+    });
+}
+
+/// State roots can only be pinned with the debug RPC enabled, which it isn't by default.
+#[test]
+fn test_debug_pin_state_root_disabled_by_default() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let error = test_utils::call_method::<serde_json::Value>(
+            &client.client,
+            &client.server_addr,
+            "debug_pin_state_root",
+            json!({ "block_hash": CryptoHash::default() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.code, -32_601);
+    });
+}
+
+/// Posts `body` to the server as is and returns the response.
+async fn post_json(client: &JsonRpcClient, body: &serde_json::Value) -> serde_json::Value {
+    client
+        .client
+        .post(&client.server_addr)
+        .insert_header(("Content-Type", "application/json"))
+        .send_json(body)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Every request of a batch gets its own response, in the same order and with the same id.
+#[test]
+fn test_batch_with_invalid_requests() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let response = post_json(
+            &client,
+            &json!([
+                {"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}},
+                {"jsonrpc": "2.0", "id": "two", "method": "no_such_method", "params": []},
+                true,
+                {"jsonrpc": "2.0", "id": 4, "method": "block", "params": {"block_id": 1000}},
+                {"jsonrpc": "2.0", "id": 5, "method": "gas_price", "params": [null]},
+            ]),
+        )
+        .await;
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["id"], json!(1));
+        assert_eq!(responses[0]["result"]["header"]["height"], json!(0));
+        assert_eq!(responses[1]["id"], json!("two"));
+        assert_eq!(responses[1]["error"]["code"], json!(-32_601));
+        assert_eq!(responses[2]["id"], json!(null));
+        assert_eq!(responses[2]["error"]["code"], json!(-32_700));
+        assert_eq!(responses[3]["id"], json!(4));
+        assert_eq!(responses[3]["error"]["cause"]["name"], json!("UNKNOWN_BLOCK"));
+        assert_eq!(responses[4]["id"], json!(5));
+        assert!(responses[4]["result"]["gas_price"].is_string());
+    });
+}
+
+/// Batches larger than `max_batch_size`, or empty ones, are rejected as a whole.
+#[test]
+fn test_batch_over_limit() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let max_batch_size = RpcLimitsConfig::default().max_batch_size;
+        let request =
+            json!({"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}});
+
+        let response = post_json(&client, &json!(vec![request.clone(); max_batch_size])).await;
+        assert_eq!(response.as_array().unwrap().len(), max_batch_size);
+
+        for batch_size in [max_batch_size + 1, 0] {
+            let response = post_json(&client, &json!(vec![request.clone(); batch_size])).await;
+            assert_eq!(response["id"], json!(null));
+            assert_eq!(response["error"]["code"], json!(-32_700));
+        }
+    });
+}
@@ -2,7 +2,9 @@ use serde_json::Value;
 
 use near_client_primitives::types::{GetChunk, GetChunkError};
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::chunks::{ChunkReference, RpcChunkError, RpcChunkRequest};
+use near_jsonrpc_primitives::types::chunks::{
+    ChunkReference, RpcChunkError, RpcChunkRequest, RpcChunkStateRootProofRequest,
+};
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{BlockId, ShardId};
 
@@ -25,6 +27,12 @@ impl RpcRequest for RpcChunkRequest {
     }
 }
 
+impl RpcRequest for RpcChunkStateRootProofRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcChunkError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
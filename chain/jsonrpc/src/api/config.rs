@@ -2,14 +2,23 @@ use serde_json::Value;
 
 use near_client_primitives::types::GetProtocolConfigError;
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::config::{RpcProtocolConfigError, RpcProtocolConfigRequest};
+use near_jsonrpc_primitives::types::config::{
+    ProtocolConfigReference, RpcProtocolConfigError, RpcProtocolConfigRequest,
+};
 use near_primitives::types::BlockReference;
 
 use super::{parse_params, RpcFrom, RpcRequest};
 
 impl RpcRequest for RpcProtocolConfigRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
-        parse_params::<BlockReference>(value).map(|block_reference| Self { block_reference })
+        // Try to parse a block reference first to keep its errors for invalid params
+        let protocol_config_reference =
+            if let Ok(block_reference) = parse_params::<BlockReference>(value.clone()) {
+                ProtocolConfigReference::BlockReference(block_reference)
+            } else {
+                parse_params::<ProtocolConfigReference>(value)?
+            };
+        Ok(Self { protocol_config_reference })
     }
 }
 
@@ -25,6 +34,9 @@ impl RpcFrom<GetProtocolConfigError> for RpcProtocolConfigError {
             GetProtocolConfigError::UnknownBlock(error_message) => {
                 Self::UnknownBlock { error_message }
             }
+            GetProtocolConfigError::UnknownEpoch(error_message) => {
+                Self::UnknownEpoch { error_message }
+            }
             GetProtocolConfigError::IOError(error_message) => Self::InternalError { error_message },
             GetProtocolConfigError::Unreachable(ref error_message) => {
                 tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
@@ -2,8 +2,10 @@ use serde_json::Value;
 
 use near_client_primitives::types::GetGasPriceError;
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::gas_price::{RpcGasPriceError, RpcGasPriceRequest};
-use near_primitives::types::MaybeBlockId;
+use near_jsonrpc_primitives::types::gas_price::{
+    RpcGasPriceError, RpcGasPriceRangeRequest, RpcGasPriceRequest,
+};
+use near_primitives::types::{BlockHeight, MaybeBlockId};
 
 use super::{parse_params, RpcFrom, RpcRequest};
 
@@ -13,6 +15,18 @@ impl RpcRequest for RpcGasPriceRequest {
     }
 }
 
+impl RpcRequest for RpcGasPriceRangeRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        if let Ok((from_height, to_height)) =
+            parse_params::<(BlockHeight, BlockHeight)>(value.clone())
+        {
+            Ok(Self { from_height, to_height })
+        } else {
+            parse_params::<Self>(value)
+        }
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcGasPriceError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -28,6 +42,9 @@ impl RpcFrom<GetGasPriceError> for RpcGasPriceError {
             GetGasPriceError::InternalError { error_message } => {
                 Self::InternalError { error_message }
             }
+            GetGasPriceError::InvalidHeightRange { from_height, to_height } => {
+                Self::InvalidHeightRange { from_height, to_height }
+            }
             GetGasPriceError::Unreachable { ref error_message } => {
                 tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
                 crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
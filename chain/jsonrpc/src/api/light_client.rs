@@ -7,7 +7,7 @@ use near_client_primitives::types::{
 };
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::light_client::{
-    RpcLightClientExecutionProofRequest, RpcLightClientNextBlockError,
+    RpcBlockMerkleProofRequest, RpcLightClientExecutionProofRequest, RpcLightClientNextBlockError,
     RpcLightClientNextBlockRequest, RpcLightClientNextBlockResponse, RpcLightClientProofError,
 };
 use near_primitives::hash::CryptoHash;
@@ -21,6 +21,12 @@ impl RpcRequest for RpcLightClientExecutionProofRequest {
     }
 }
 
+impl RpcRequest for RpcBlockMerkleProofRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        Ok(parse_params::<Self>(value)?)
+    }
+}
+
 impl RpcRequest for RpcLightClientNextBlockRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
         if let Ok((last_block_hash,)) = parse_params::<(CryptoHash,)>(value.clone()) {
@@ -82,6 +88,15 @@ impl RpcFrom<GetBlockProofError> for RpcLightClientProofError {
             GetBlockProofError::UnknownBlock { error_message } => {
                 Self::UnknownBlock { error_message }
             }
+            GetBlockProofError::MissingBlockMerkleTree { block_hash } => {
+                Self::MissingBlockMerkleTree { block_hash }
+            }
+            GetBlockProofError::MissingBlockOrdinal { block_ordinal } => {
+                Self::MissingBlockOrdinal { block_ordinal }
+            }
+            GetBlockProofError::BlockAfterHead { block_hash, head_block_hash } => {
+                Self::BlockAfterHead { block_hash, head_block_hash }
+            }
             GetBlockProofError::InternalError { error_message } => {
                 Self::InternalError { error_message }
             }
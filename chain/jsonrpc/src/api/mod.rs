@@ -15,8 +15,12 @@ mod network_info;
 mod query;
 mod receipts;
 mod sandbox;
+mod state_proof;
+mod state_root_pins;
 mod status;
 mod transactions;
+mod trie_key;
+mod trie_node;
 mod validator;
 
 pub(crate) trait RpcRequest: Sized {
@@ -90,6 +94,12 @@ fn parse_signed_transaction(
     value: Option<Value>,
 ) -> Result<near_primitives::transaction::SignedTransaction, RpcParseError> {
     let (encoded,) = parse_params::<(String,)>(value)?;
+    decode_signed_transaction(&encoded)
+}
+
+fn decode_signed_transaction(
+    encoded: &str,
+) -> Result<near_primitives::transaction::SignedTransaction, RpcParseError> {
     let bytes = near_primitives::serialize::from_base64(&encoded)
         .map_err(|err| RpcParseError(err.to_string()))?;
     Ok(near_primitives::transaction::SignedTransaction::try_from_slice(&bytes)
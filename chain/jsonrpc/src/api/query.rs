@@ -41,30 +41,47 @@ impl RpcRequest for RpcQueryRequest {
             let maybe_extra_arg = path_parts.next();
 
             let request = match query_command {
-                "account" => QueryRequest::ViewAccount { account_id },
+                "account" => QueryRequest::ViewAccount { account_id, include_proof: false },
                 "access_key" => match maybe_extra_arg {
-                    None => QueryRequest::ViewAccessKeyList { account_id },
+                    None => QueryRequest::ViewAccessKeyList { account_id, include_proof: false },
                     Some(pk) => QueryRequest::ViewAccessKey {
                         account_id,
                         public_key: pk
                             .parse()
                             .map_err(|_| RpcParseError("Invalid public key".to_string()))?,
+                        include_proof: false,
                     },
                 },
-                "code" => QueryRequest::ViewCode { account_id },
-                "contract" => QueryRequest::ViewState { account_id, prefix: data.into() },
+                "code" => {
+                    QueryRequest::ViewCode { account_id, include_proof: false, include_code: true }
+                }
+                "contract" => QueryRequest::ViewState {
+                    account_id,
+                    prefix: data.into(),
+                    from_key: None,
+                    limit: None,
+                    max_value_size: None,
+                    state_size_limit: None,
+                    include_proof: true,
+                    allow_partial: false,
+                },
                 "call" => match maybe_extra_arg {
                     Some(method_name) => QueryRequest::CallFunction {
                         account_id,
                         method_name: method_name.to_string(),
                         args: data.into(),
+                        max_gas: None,
                     },
                     None => return Err(RpcParseError("Method name is missing".to_string())),
                 },
                 _ => return Err(RpcParseError(format!("Unknown path {}", query_command))),
             };
             // Use Finality::None here to make backward compatibility tests work
-            Self { request, block_reference: BlockReference::latest() }
+            Self {
+                request,
+                block_reference: BlockReference::latest(),
+                encoding: Default::default(),
+            }
         } else {
             parse_params::<Self>(value)?
         };
@@ -87,8 +104,8 @@ impl RpcFrom<QueryError> for RpcQueryError {
                 Self::UnavailableShard { requested_shard_id }
             }
             QueryError::UnknownBlock { block_reference } => Self::UnknownBlock { block_reference },
-            QueryError::GarbageCollectedBlock { block_height, block_hash } => {
-                Self::GarbageCollectedBlock { block_height, block_hash }
+            QueryError::GarbageCollectedBlock { block_height, block_hash, gc_horizon } => {
+                Self::GarbageCollectedBlock { block_height, block_hash, gc_horizon }
             }
             QueryError::InvalidAccount { requested_account_id, block_height, block_hash } => {
                 Self::InvalidAccount { requested_account_id, block_height, block_hash }
@@ -112,9 +129,19 @@ impl RpcFrom<QueryError> for RpcQueryError {
                     .inc();
                 Self::InternalError { error_message: error.to_string() }
             }
-            QueryError::TooLargeContractState { contract_account_id, block_height, block_hash } => {
-                Self::TooLargeContractState { contract_account_id, block_height, block_hash }
-            }
+            QueryError::TooLargeContractState {
+                contract_account_id,
+                state_size,
+                state_size_limit,
+                block_height,
+                block_hash,
+            } => Self::TooLargeContractState {
+                contract_account_id,
+                state_size,
+                state_size_limit,
+                block_height,
+                block_hash,
+            },
         }
     }
 }
@@ -137,9 +164,15 @@ impl RpcFrom<near_primitives::views::QueryResponseKind>
             near_primitives::views::QueryResponseKind::ViewAccount(account_view) => {
                 Self::ViewAccount(account_view)
             }
+            near_primitives::views::QueryResponseKind::ViewAccountWithProof(account_view) => {
+                Self::ViewAccountWithProof(account_view)
+            }
             near_primitives::views::QueryResponseKind::ViewCode(contract_code_view) => {
                 Self::ViewCode(contract_code_view)
             }
+            near_primitives::views::QueryResponseKind::ViewCodeWithProof(contract_code_view) => {
+                Self::ViewCodeWithProof(contract_code_view)
+            }
             near_primitives::views::QueryResponseKind::ViewState(view_state_result) => {
                 Self::ViewState(view_state_result)
             }
@@ -149,9 +182,15 @@ impl RpcFrom<near_primitives::views::QueryResponseKind>
             near_primitives::views::QueryResponseKind::AccessKey(access_key_view) => {
                 Self::AccessKey(access_key_view)
             }
+            near_primitives::views::QueryResponseKind::AccessKeyWithProof(access_key_view) => {
+                Self::AccessKeyWithProof(access_key_view)
+            }
             near_primitives::views::QueryResponseKind::AccessKeyList(access_key_list) => {
                 Self::AccessKeyList(access_key_list)
             }
+            near_primitives::views::QueryResponseKind::AccessKeyListWithProof(access_key_list) => {
+                Self::AccessKeyListWithProof(access_key_list)
+            }
         }
     }
 }
@@ -0,0 +1,23 @@
+use serde_json::Value;
+
+use near_client_primitives::types::StateProofResponse;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::state_proof::{RpcStateProofRequest, RpcStateProofResponse};
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcStateProofRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<StateProofResponse> for RpcStateProofResponse {
+    fn rpc_from(response: StateProofResponse) -> Self {
+        Self {
+            state_proof: response.state_proof,
+            block_height: response.block_height,
+            block_hash: response.block_hash,
+        }
+    }
+}
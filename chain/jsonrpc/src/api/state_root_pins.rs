@@ -0,0 +1,39 @@
+use serde_json::Value;
+
+use near_client_primitives::debug::StateRootPinsError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::state_root_pins::{
+    RpcStateRootPinRequest, RpcStateRootPinsError,
+};
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcStateRootPinRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcStateRootPinsError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<StateRootPinsError> for RpcStateRootPinsError {
+    fn rpc_from(error: StateRootPinsError) -> Self {
+        match error {
+            StateRootPinsError::IOError(error_message) => Self::InternalError { error_message },
+            StateRootPinsError::UnknownStateRoots(error_message) => {
+                Self::UnknownStateRoots { error_message }
+            }
+            StateRootPinsError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcStateRootPinsError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
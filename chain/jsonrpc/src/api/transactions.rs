@@ -1,16 +1,18 @@
 use serde_json::Value;
 
-use near_client_primitives::types::TxStatusError;
+use near_client_primitives::types::{GetExecutionOutcomeError, TxStatusError};
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::transactions::{
-    RpcBroadcastTransactionRequest, RpcTransactionError, RpcTransactionResponse,
-    RpcTransactionStatusCommonRequest, TransactionInfo,
+    RpcBroadcastTransactionRequest, RpcSendTransactionRequest, RpcTransactionError,
+    RpcTransactionResponse, RpcTransactionStatusCommonRequest, TransactionInfo,
 };
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::AccountId;
-use near_primitives::views::FinalExecutionOutcomeViewEnum;
+use near_primitives::views::{FinalExecutionOutcomeViewEnum, TxExecutionStatus};
 
-use super::{parse_params, parse_signed_transaction, RpcFrom, RpcRequest};
+use super::{
+    decode_signed_transaction, parse_params, parse_signed_transaction, RpcFrom, RpcRequest,
+};
 
 impl RpcRequest for RpcBroadcastTransactionRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
@@ -19,15 +21,49 @@ impl RpcRequest for RpcBroadcastTransactionRequest {
     }
 }
 
+#[derive(serde::Deserialize)]
+struct RpcSendTransactionParams {
+    signed_tx_base64: String,
+    #[serde(default)]
+    wait_until: TxExecutionStatus,
+}
+
+impl RpcRequest for RpcSendTransactionRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        let (encoded, wait_until) = if let Ok((encoded, wait_until)) =
+            parse_params::<(String, TxExecutionStatus)>(value.clone())
+        {
+            (encoded, wait_until)
+        } else if let Ok((encoded,)) = parse_params::<(String,)>(value.clone()) {
+            (encoded, TxExecutionStatus::default())
+        } else {
+            let params = parse_params::<RpcSendTransactionParams>(value)?;
+            (params.signed_tx_base64, params.wait_until)
+        };
+        let signed_transaction = decode_signed_transaction(&encoded)?;
+        Ok(Self { signed_transaction, wait_until })
+    }
+}
+
 impl RpcRequest for RpcTransactionStatusCommonRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
         if let Ok((hash, account_id)) = parse_params::<(CryptoHash, AccountId)>(value.clone()) {
             let transaction_info = TransactionInfo::TransactionId { hash, account_id };
-            Ok(Self { transaction_info })
+            Ok(Self { transaction_info, include_proofs: false })
+        } else if let Ok((hash, account_id, include_proofs)) =
+            parse_params::<(CryptoHash, AccountId, bool)>(value.clone())
+        {
+            let transaction_info = TransactionInfo::TransactionId { hash, account_id };
+            Ok(Self { transaction_info, include_proofs })
+        } else if let Ok((encoded, include_proofs)) = parse_params::<(String, bool)>(value.clone())
+        {
+            let signed_transaction = decode_signed_transaction(&encoded)?;
+            let transaction_info = TransactionInfo::Transaction(signed_transaction);
+            Ok(Self { transaction_info, include_proofs })
         } else {
             let signed_transaction = parse_signed_transaction(value)?;
             let transaction_info = TransactionInfo::Transaction(signed_transaction);
-            Ok(Self { transaction_info })
+            Ok(Self { transaction_info, include_proofs: false })
         }
     }
 }
@@ -54,8 +90,14 @@ impl RpcFrom<TxStatusError> for RpcTransactionError {
     }
 }
 
+impl RpcFrom<GetExecutionOutcomeError> for RpcTransactionError {
+    fn rpc_from(error: GetExecutionOutcomeError) -> Self {
+        Self::InternalError { debug_info: error.to_string() }
+    }
+}
+
 impl RpcFrom<FinalExecutionOutcomeViewEnum> for RpcTransactionResponse {
     fn rpc_from(final_execution_outcome: FinalExecutionOutcomeViewEnum) -> Self {
-        Self { final_execution_outcome }
+        Self { final_execution_outcome, outcome_root_proofs: None }
     }
 }
@@ -0,0 +1,12 @@
+use serde_json::Value;
+
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::trie_key::RpcRawTrieKeyRequest;
+
+use super::{parse_params, RpcRequest};
+
+impl RpcRequest for RpcRawTrieKeyRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
@@ -0,0 +1,36 @@
+use serde_json::Value;
+
+use near_client_primitives::types::GetTrieNodeError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::trie_node::{RpcTrieNodeError, RpcTrieNodeRequest};
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcTrieNodeRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcTrieNodeError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetTrieNodeError> for RpcTrieNodeError {
+    fn rpc_from(error: GetTrieNodeError) -> Self {
+        match error {
+            GetTrieNodeError::IOError(error_message) => Self::InternalError { error_message },
+            GetTrieNodeError::UnknownShard(shard_id) => Self::UnknownShard { shard_id },
+            GetTrieNodeError::ValidatorNode => Self::ValidatorNode,
+            GetTrieNodeError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcTrieNodeError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
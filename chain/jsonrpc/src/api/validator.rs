@@ -3,7 +3,8 @@ use serde_json::Value;
 use near_client_primitives::types::GetValidatorInfoError;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::validator::{
-    RpcValidatorError, RpcValidatorRequest, RpcValidatorsOrderedRequest,
+    RpcNextValidatorSetRequest, RpcProducerScheduleRequest, RpcValidatorError, RpcValidatorRequest,
+    RpcValidatorsOrderedRequest,
 };
 use near_primitives::types::{EpochReference, MaybeBlockId};
 
@@ -24,6 +25,19 @@ impl RpcRequest for RpcValidatorRequest {
     }
 }
 
+impl RpcRequest for RpcProducerScheduleRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        let RpcValidatorRequest { epoch_reference } = RpcValidatorRequest::parse(value)?;
+        Ok(Self { epoch_reference })
+    }
+}
+
+impl RpcRequest for RpcNextValidatorSetRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
 impl RpcRequest for RpcValidatorsOrderedRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
         parse_params::<Self>(value)
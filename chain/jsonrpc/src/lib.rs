@@ -16,25 +16,36 @@ use tracing::info;
 
 use near_chain_configs::GenesisConfig;
 use near_client::{
-    ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetExecutionOutcome, GetGasPrice,
-    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, Query, Status, TxStatus,
-    ViewClientActor,
+    ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetChunkStateRootProof,
+    GetEpochProtocolConfig, GetExecutionOutcome, GetGasPrice, GetGasPriceRange, GetNetworkInfo,
+    GetNextLightClientBlock, GetNextValidatorSet, GetProducerSchedule, GetProtocolConfig,
+    GetReceipt, GetStateChanges, GetStateChangesInBlock, GetStateChangesWithProof, GetStateProof,
+    GetTrieNode, GetTxExecutionProgress, GetValidatorInfo, GetValidatorOrdered, Query,
+    StateRootPins, Status, TxExecutionProgress, TxStatus, ViewClientActor,
 };
+use near_client_primitives::types::GetExecutionOutcomeError;
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{Message, Request};
-use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
+use near_jsonrpc_primitives::types::config::{ProtocolConfigReference, RpcProtocolConfigResponse};
+use near_jsonrpc_primitives::types::proof_encoding::WithProofEncoding;
+use near_jsonrpc_primitives::types::query::RpcQueryError;
 use near_metrics::{prometheus, Encoder, TextEncoder};
 use near_network::types::{NetworkClientMessages, NetworkClientResponses};
 use near_primitives::hash::CryptoHash;
-use near_primitives::serialize::BaseEncode;
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
-use near_primitives::views::FinalExecutionOutcomeViewEnum;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::{AccountId, TransactionOrReceiptId};
+use near_primitives::views::{
+    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, OutcomeRootProofView,
+    TxExecutionStatus,
+};
 
 mod api;
 mod metrics;
+mod subscriptions;
+
+pub use subscriptions::RpcSubscriptionsConfig;
 
 use api::RpcRequest;
 pub use api::{RpcFrom, RpcInto};
@@ -54,15 +65,33 @@ impl Default for RpcPollingConfig {
     }
 }
 
+fn default_max_batch_size() -> usize {
+    100
+}
+
+fn default_batch_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RpcLimitsConfig {
     /// Maximum byte size of the json payload.
     pub json_payload_max_size: usize,
+    /// Maximum number of requests in a batch. Larger batches are rejected as a whole.
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Time the requests of a batch have to complete. Those which don't get an error response.
+    #[serde(default = "default_batch_timeout")]
+    pub batch_timeout: Duration,
 }
 
 impl Default for RpcLimitsConfig {
     fn default() -> Self {
-        Self { json_payload_max_size: 10 * 1024 * 1024 }
+        Self {
+            json_payload_max_size: 10 * 1024 * 1024,
+            max_batch_size: default_max_batch_size(),
+            batch_timeout: default_batch_timeout(),
+        }
     }
 }
 
@@ -70,6 +99,10 @@ fn default_enable_debug_rpc() -> bool {
     false
 }
 
+fn default_archival_fallback_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RpcConfig {
     pub addr: String,
@@ -79,10 +112,22 @@ pub struct RpcConfig {
     pub polling_config: RpcPollingConfig,
     #[serde(default)]
     pub limits_config: RpcLimitsConfig,
-    // If true, enable some debug RPC endpoints (like one to get the latest block).
+    // If true, enable some debug RPC endpoints (like one to get the latest block), the
+    // `EXPERIMENTAL_trie_node` method, served by non-validator nodes only, the
+    // `EXPERIMENTAL_raw_trie_key` method and the methods managing state roots kept from garbage
+    // collection.
     // We disable it by default, as some of those endpoints might be quite CPU heavy.
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    // If provided, queries of data garbage collected on this node are forwarded to the archival
+    // node serving JSON RPC at this address, instead of failing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archival_fallback_url: Option<String>,
+    // Time the archival node has to answer a forwarded query.
+    #[serde(default = "default_archival_fallback_timeout")]
+    pub archival_fallback_timeout: Duration,
+    #[serde(default)]
+    pub subscriptions_config: RpcSubscriptionsConfig,
 }
 
 impl Default for RpcConfig {
@@ -94,6 +139,9 @@ impl Default for RpcConfig {
             polling_config: Default::default(),
             limits_config: Default::default(),
             enable_debug_rpc: false,
+            archival_fallback_url: None,
+            archival_fallback_timeout: default_archival_fallback_timeout(),
+            subscriptions_config: Default::default(),
         }
     }
 }
@@ -151,11 +199,14 @@ fn process_query_response(
         near_jsonrpc_primitives::types::query::RpcQueryResponse,
         near_jsonrpc_primitives::types::query::RpcQueryError,
     >,
+    encoding: near_jsonrpc_primitives::types::proof_encoding::ProofEncoding,
 ) -> Result<Value, RpcError> {
     // This match is used here to give backward compatible error message for specific
     // error variants. Should be refactored once structured errors fully shipped
     match query_response {
-        Ok(rpc_query_response) => serialize_response(rpc_query_response),
+        Ok(rpc_query_response) => {
+            serialize_response(WithProofEncoding::new(rpc_query_response, encoding))
+        }
         Err(err) => match err {
             near_jsonrpc_primitives::types::query::RpcQueryError::ContractExecutionError {
                 vm_error,
@@ -217,6 +268,10 @@ struct JsonRpcHandler {
     polling_config: RpcPollingConfig,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
+    max_batch_size: usize,
+    batch_timeout: Duration,
+    archival_fallback_url: Option<String>,
+    archival_fallback_timeout: Duration,
 }
 
 impl JsonRpcHandler {
@@ -226,12 +281,51 @@ impl JsonRpcHandler {
             Message::Request(request) => {
                 Ok(Message::response(id, self.process_request(request).await))
             }
+            Message::Batch(messages) => Ok(self.process_batch(messages).await),
             _ => Ok(Message::error(RpcError::parse_error(
                 "JSON RPC Request format was expected".to_owned(),
             ))),
         }
     }
 
+    /// Processes the requests of a batch concurrently. Every message of the batch gets a response
+    /// at the same position, with the id of its request, so a failing request doesn't fail the
+    /// others.
+    async fn process_batch(&self, messages: Vec<Message>) -> Message {
+        metrics::RPC_BATCH_SIZE.observe(messages.len() as f64);
+        if messages.is_empty() || messages.len() > self.max_batch_size {
+            return Message::error(RpcError::parse_error(format!(
+                "A batch of 1 to {} requests was expected, got {}",
+                self.max_batch_size,
+                messages.len()
+            )));
+        }
+        let responses = messages.into_iter().map(|message| async move {
+            let request = match message {
+                Message::Request(request) => request,
+                _ => {
+                    return Message::error(RpcError::parse_error(
+                        "JSON RPC Request format was expected".to_owned(),
+                    ))
+                }
+            };
+            let id = request.id.clone();
+            let method = request.method.clone();
+            let response = timeout(self.batch_timeout, self.process_request(request))
+                .await
+                .unwrap_or_else(|_| {
+                    metrics::RPC_TIMEOUT_TOTAL.inc();
+                    tracing::warn!(target: "jsonrpc", "Timeout: {} method in a batch", method);
+                    Err(RpcError::new_internal_error(
+                        None,
+                        format!("Request of a batch timed out after {:?}", self.batch_timeout),
+                    ))
+                });
+            Message::response(id, response)
+        });
+        Message::Batch(futures::future::join_all(responses).await)
+    }
+
     // `process_request` increments affected metrics but the request processing is done by
     // `process_request_internal`.
     async fn process_request(&self, request: Request) -> Result<Value, RpcError> {
@@ -270,16 +364,21 @@ impl JsonRpcHandler {
             // Handlers ordered alphabetically
             "block" => process_method_call(request, |params| self.block(params)).await,
             "broadcast_tx_async" => {
-                process_method_call(request, |params| async {
-                    let tx = self.send_tx_async(params).await.to_base();
-                    Result::<_, std::convert::Infallible>::Ok(tx)
-                })
-                .await
+                process_method_call(request, |params| self.send_tx_async(params)).await
             }
             "broadcast_tx_commit" => {
                 process_method_call(request, |params| self.send_tx_commit(params)).await
             }
             "chunk" => process_method_call(request, |params| self.chunk(params)).await,
+            "debug_pin_state_root" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.pin_state_root(params)).await
+            }
+            "debug_pinned_state_roots" if self.enable_debug_rpc => {
+                process_method_call(request, |_params: ()| self.pinned_state_roots()).await
+            }
+            "debug_unpin_state_root" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.unpin_state_root(params)).await
+            }
             "gas_price" => process_method_call(request, |params| self.gas_price(params)).await,
             "health" => process_method_call(request, |_params: ()| self.health()).await,
             "light_client_proof" => {
@@ -293,15 +392,31 @@ impl JsonRpcHandler {
             }
             "network_info" => process_method_call(request, |_params: ()| self.network_info()).await,
             "query" => {
-                let params = RpcRequest::parse(request.params)?;
+                // The query is forwarded as it is if its data turns out to be garbage collected.
+                let raw_params =
+                    self.archival_fallback_url.as_ref().map(|_| request.params.clone());
+                let params: near_jsonrpc_primitives::types::query::RpcQueryRequest =
+                    RpcRequest::parse(request.params)?;
+                let encoding = params.encoding;
                 let query_response = self.query(params).await;
-                process_query_response(query_response)
+                if let (Some(url), Some(raw_params)) = (&self.archival_fallback_url, raw_params) {
+                    if matches!(query_response, Err(RpcQueryError::GarbageCollectedBlock { .. })) {
+                        return self.query_archival_node(url, raw_params).await;
+                    }
+                }
+                process_query_response(query_response, encoding)
+            }
+            "send_tx" => {
+                process_method_call(request, |params| self.send_tx_wait_until(params)).await
             }
             "status" => process_method_call(request, |_params: ()| self.status()).await,
             "tx" => {
                 process_method_call(request, |params| self.tx_status_common(params, false)).await
             }
             "validators" => process_method_call(request, |params| self.validators(params)).await,
+            "EXPERIMENTAL_block_merkle_proof" => {
+                process_method_call(request, |params| self.block_merkle_proof(params)).await
+            }
             "EXPERIMENTAL_broadcast_tx_sync" => {
                 process_method_call(request, |params| self.send_tx_sync(params)).await
             }
@@ -314,6 +429,12 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_check_tx" => {
                 process_method_call(request, |params| self.check_tx(params)).await
             }
+            "EXPERIMENTAL_chunk_state_root_proof" => {
+                process_method_call(request, |params| self.chunk_state_root_proof(params)).await
+            }
+            "EXPERIMENTAL_gas_price_range" => {
+                process_method_call(request, |params| self.gas_price_range(params)).await
+            }
             "EXPERIMENTAL_genesis_config" => {
                 process_method_call(request, |_params: ()| async {
                     Result::<_, std::convert::Infallible>::Ok(&self.genesis_config)
@@ -326,12 +447,27 @@ impl JsonRpcHandler {
                 })
                 .await
             }
+            "EXPERIMENTAL_next_validator_set" => {
+                process_method_call(request, |params| self.next_validator_set(params)).await
+            }
+            "EXPERIMENTAL_producer_schedule" => {
+                process_method_call(request, |params| self.producer_schedule(params)).await
+            }
             "EXPERIMENTAL_protocol_config" => {
                 process_method_call(request, |params| self.protocol_config(params)).await
             }
+            "EXPERIMENTAL_raw_trie_key" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.raw_trie_key(params)).await
+            }
             "EXPERIMENTAL_receipt" => {
                 process_method_call(request, |params| self.receipt(params)).await
             }
+            "EXPERIMENTAL_state_proof" => {
+                process_method_call(request, |params| self.state_proof(params)).await
+            }
+            "EXPERIMENTAL_trie_node" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.trie_node(params)).await
+            }
             "EXPERIMENTAL_tx_status" => {
                 process_method_call(request, |params| self.tx_status_common(params, true)).await
             }
@@ -407,15 +543,16 @@ impl JsonRpcHandler {
     async fn send_tx_async(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
-    ) -> CryptoHash {
-        let tx = request_data.signed_transaction;
-        let hash = tx.get_hash().clone();
-        self.client_addr.do_send(NetworkClientMessages::Transaction {
-            transaction: tx,
-            is_forwarded: false,
-            check_only: false, // if we set true here it will not actually send the transaction
-        });
-        hash
+    ) -> Result<CryptoHash, near_jsonrpc_primitives::types::transactions::RpcTransactionError> {
+        let tx_hash = request_data.signed_transaction.get_hash();
+        self.send_tx_wait_until(
+            near_jsonrpc_primitives::types::transactions::RpcSendTransactionRequest {
+                signed_transaction: request_data.signed_transaction,
+                wait_until: TxExecutionStatus::None,
+            },
+        )
+        .await?;
+        Ok(tx_hash)
     }
 
     async fn tx_exists(
@@ -521,31 +658,90 @@ impl JsonRpcHandler {
         })?
     }
 
-    async fn tx_polling(
+    /// Sends a transaction and waits until it reaches the requested status. A transaction which
+    /// is already included in a block isn't sent again.
+    async fn send_tx_wait_until(
         &self,
-        tx_info: near_jsonrpc_primitives::types::transactions::TransactionInfo,
+        request_data: near_jsonrpc_primitives::types::transactions::RpcSendTransactionRequest,
     ) -> Result<
-        near_jsonrpc_primitives::types::transactions::RpcTransactionResponse,
+        near_jsonrpc_primitives::types::transactions::RpcSendTransactionResponse,
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
+        let near_jsonrpc_primitives::types::transactions::RpcSendTransactionRequest {
+            signed_transaction: tx,
+            wait_until,
+        } = request_data;
+        let tx_hash = tx.get_hash();
+        let signer_account_id = tx.transaction.signer_id.clone();
+        let response = |progress: Option<TxExecutionProgress>| {
+            use near_jsonrpc_primitives::types::transactions::RpcSendTransactionOutcome;
+            let outcome = match progress {
+                Some(TxExecutionProgress { final_outcome: Some(outcome), .. }) => {
+                    RpcSendTransactionOutcome::Executed(outcome)
+                }
+                Some(TxExecutionProgress { transaction_outcome: Some(outcome), .. }) => {
+                    RpcSendTransactionOutcome::Included { transaction_outcome: outcome }
+                }
+                _ => RpcSendTransactionOutcome::Accepted { transaction_hash: tx_hash },
+            };
+            near_jsonrpc_primitives::types::transactions::RpcSendTransactionResponse {
+                final_execution_status: wait_until,
+                outcome,
+            }
+        };
+        if wait_until == TxExecutionStatus::None {
+            self.client_addr.do_send(NetworkClientMessages::Transaction {
+                transaction: tx,
+                is_forwarded: false,
+                check_only: false, // if we set true here it will not actually send the transaction
+            });
+            return Ok(response(None));
+        }
+
+        let progress = self
+            .view_client_send(GetTxExecutionProgress {
+                tx_hash,
+                signer_account_id: signer_account_id.clone(),
+            })
+            .await;
+        match progress {
+            Ok(progress) if progress.reached(wait_until) => return Ok(response(Some(progress))),
+            Ok(TxExecutionProgress { transaction_outcome: Some(_), .. }) => {}
+            Ok(_)
+            | Err(near_jsonrpc_primitives::types::transactions::RpcTransactionError::UnknownTransaction {
+                ..
+            }) => match self.send_tx(tx, false).await? {
+                NetworkClientResponses::ValidTx | NetworkClientResponses::RequestRouted => {}
+                network_client_response => {
+                    return Err(
+                        near_jsonrpc_primitives::types::transactions::RpcTransactionError::from_network_client_responses(
+                            network_client_response
+                        )
+                    );
+                }
+            },
+            Err(err) => return Err(err),
+        }
+
         timeout(self.polling_config.polling_timeout, async {
             loop {
-                match self.tx_status_fetch(tx_info.clone(), false).await {
-                    Ok(tx_status) => {
-                        break Ok(
-                            near_jsonrpc_primitives::types::transactions::RpcTransactionResponse {
-                                final_execution_outcome: tx_status,
-                            },
-                        )
+                match self
+                    .view_client_send(GetTxExecutionProgress {
+                        tx_hash,
+                        signer_account_id: signer_account_id.clone(),
+                    })
+                    .await
+                {
+                    Ok(progress) if progress.reached(wait_until) => {
+                        break Ok(response(Some(progress)))
                     }
-                    // If transaction is missing, keep polling.
-                    Err(near_jsonrpc_primitives::types::transactions::RpcTransactionError::UnknownTransaction {
+                    // If transaction is missing or hasn't progressed enough, keep polling.
+                    Ok(_)
+                    | Err(near_jsonrpc_primitives::types::transactions::RpcTransactionError::UnknownTransaction {
                         ..
                     }) => {}
                     // If we hit any other error, we return to the user.
-                    Err(err) => {
-                        break Err(err.rpc_into());
-                    }
+                    Err(err) => break Err(err),
                 }
                 let _ = sleep(self.polling_config.polling_interval).await;
             }
@@ -554,8 +750,9 @@ impl JsonRpcHandler {
         .map_err(|_| {
             metrics::RPC_TIMEOUT_TOTAL.inc();
             tracing::warn!(
-                target: "jsonrpc", "Timeout: tx_polling method. tx_info {:?}",
-                tx_info,
+                target: "jsonrpc", "Timeout: send_tx method. tx_hash {:?} wait_until {:?}",
+                tx_hash,
+                wait_until,
             );
             near_jsonrpc_primitives::types::transactions::RpcTransactionError::TimeoutError
         })?
@@ -658,39 +855,28 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcTransactionResponse,
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
-        let tx = request_data.signed_transaction;
-        match self
-            .tx_status_fetch(
-                near_jsonrpc_primitives::types::transactions::TransactionInfo::Transaction(
-                    tx.clone(),
-                ),
-                false,
+        let response = self
+            .send_tx_wait_until(
+                near_jsonrpc_primitives::types::transactions::RpcSendTransactionRequest {
+                    signed_transaction: request_data.signed_transaction,
+                    wait_until: TxExecutionStatus::ExecutedOptimistic,
+                },
             )
-            .await
-        {
-            Ok(outcome) => {
-                return Ok(near_jsonrpc_primitives::types::transactions::RpcTransactionResponse {
-                    final_execution_outcome: outcome,
-                });
-            }
-            Err(err @ near_jsonrpc_primitives::types::transactions::RpcTransactionError::InvalidTransaction {
-                ..
-            }) => {
-                return Err(err);
-            }
-            _ => {}
-        }
-        match self.send_tx(tx.clone(), false).await? {
-            NetworkClientResponses::ValidTx | NetworkClientResponses::RequestRouted => {
-                self.tx_polling(near_jsonrpc_primitives::types::transactions::TransactionInfo::Transaction(tx)).await
-            }
-            network_client_response=> {
-                Err(
-                    near_jsonrpc_primitives::types::transactions::RpcTransactionError::from_network_client_responses(
-                        network_client_response
-                    )
-                )
-            }
+            .await?;
+        match response.outcome {
+            near_jsonrpc_primitives::types::transactions::RpcSendTransactionOutcome::Executed(
+                outcome,
+            ) => Ok(near_jsonrpc_primitives::types::transactions::RpcTransactionResponse {
+                final_execution_outcome: FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(
+                    outcome,
+                ),
+                outcome_root_proofs: None,
+            }),
+            outcome => Err(
+                near_jsonrpc_primitives::types::transactions::RpcTransactionError::InternalError {
+                    debug_info: format!("Transaction is not executed: {:?}", outcome),
+                },
+            ),
         }
     }
 
@@ -760,8 +946,14 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse,
         near_jsonrpc_primitives::types::config::RpcProtocolConfigError,
     > {
-        let config_view =
-            self.view_client_send(GetProtocolConfig(request_data.block_reference)).await?;
+        let config_view = match request_data.protocol_config_reference {
+            ProtocolConfigReference::EpochId { epoch_id } => {
+                self.view_client_send(GetEpochProtocolConfig(epoch_id)).await?
+            }
+            ProtocolConfigReference::BlockReference(block_reference) => {
+                self.view_client_send(GetProtocolConfig(block_reference)).await?
+            }
+        };
         Ok(RpcProtocolConfigResponse { config_view })
     }
 
@@ -778,6 +970,47 @@ impl JsonRpcHandler {
         Ok(query_response.rpc_into())
     }
 
+    /// Forwards a query of data garbage collected on this node to the archival node at `url`,
+    /// returning its response as it is.
+    async fn query_archival_node(
+        &self,
+        url: &str,
+        params: Option<Value>,
+    ) -> Result<Value, RpcError> {
+        metrics::RPC_ARCHIVAL_FALLBACK_TOTAL.inc();
+        let client = near_jsonrpc_client::new_client(url);
+        timeout(self.archival_fallback_timeout, client.call("query", params.unwrap_or_default()))
+            .await
+            .unwrap_or_else(|_| {
+                metrics::RPC_TIMEOUT_TOTAL.inc();
+                tracing::warn!(target: "jsonrpc", "Timeout: query forwarded to archival node {}", url);
+                Err(RpcError::new_internal_error(
+                    None,
+                    format!(
+                        "Archival node {} did not answer within {:?}",
+                        url, self.archival_fallback_timeout
+                    ),
+                ))
+            })
+    }
+
+    async fn state_proof(
+        &self,
+        request_data: near_jsonrpc_primitives::types::state_proof::RpcStateProofRequest,
+    ) -> Result<
+        WithProofEncoding<near_jsonrpc_primitives::types::state_proof::RpcStateProofResponse>,
+        near_jsonrpc_primitives::types::query::RpcQueryError,
+    > {
+        let state_proof = self
+            .view_client_send(GetStateProof {
+                block_reference: request_data.block_reference,
+                account_id: request_data.account_id,
+                key: request_data.key,
+            })
+            .await?;
+        Ok(WithProofEncoding::new(state_proof.rpc_into(), request_data.encoding))
+    }
+
     async fn tx_status_common(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcTransactionStatusCommonRequest,
@@ -787,7 +1020,58 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
         let tx_status = self.tx_status_fetch(request_data.transaction_info, fetch_receipt).await?;
-        Ok(tx_status.rpc_into())
+        let mut response: near_jsonrpc_primitives::types::transactions::RpcTransactionResponse =
+            tx_status.rpc_into();
+        if request_data.include_proofs {
+            let final_outcome = match &response.final_execution_outcome {
+                FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(outcome) => outcome,
+                FinalExecutionOutcomeViewEnum::FinalExecutionOutcomeWithReceipt(outcome) => {
+                    &outcome.final_outcome
+                }
+            };
+            response.outcome_root_proofs = Some(self.outcome_root_proofs(final_outcome).await?);
+        }
+        Ok(response)
+    }
+
+    /// Proofs from the outcome roots of the chunks including the outcomes of a transaction to
+    /// the outcome roots of blocks, built the same way as for light client execution proofs.
+    async fn outcome_root_proofs(
+        &self,
+        final_outcome: &FinalExecutionOutcomeView,
+    ) -> Result<
+        Vec<OutcomeRootProofView>,
+        near_jsonrpc_primitives::types::transactions::RpcTransactionError,
+    > {
+        let mut proofs = vec![];
+        let outcomes = std::iter::once(&final_outcome.transaction_outcome)
+            .chain(&final_outcome.receipts_outcome);
+        for (index, outcome) in outcomes.enumerate() {
+            let id = if index == 0 {
+                TransactionOrReceiptId::Transaction {
+                    transaction_hash: outcome.id,
+                    sender_id: outcome.outcome.executor_id.clone(),
+                }
+            } else {
+                TransactionOrReceiptId::Receipt {
+                    receipt_id: outcome.id,
+                    receiver_id: outcome.outcome.executor_id.clone(),
+                }
+            };
+            let result = self.view_client_addr.send(GetExecutionOutcome { id }).await.map_err(
+                near_jsonrpc_primitives::types::transactions::RpcTransactionError::rpc_from,
+            )?;
+            match result {
+                Ok(response) => proofs.push(OutcomeRootProofView {
+                    id: outcome.id,
+                    block_hash: response.outcome_proof.block_hash,
+                    outcome_root_proof: response.outcome_root_proof,
+                }),
+                Err(GetExecutionOutcomeError::NotConfirmed { .. }) => {}
+                Err(err) => return Err(RpcFrom::rpc_from(err)),
+            }
+        }
+        Ok(proofs)
     }
 
     async fn block(
@@ -813,6 +1097,21 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::chunks::RpcChunkResponse { chunk_view })
     }
 
+    async fn chunk_state_root_proof(
+        &self,
+        request_data: near_jsonrpc_primitives::types::chunks::RpcChunkStateRootProofRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::chunks::RpcChunkStateRootProofResponse,
+        near_jsonrpc_primitives::types::chunks::RpcChunkError,
+    > {
+        let proof = self
+            .view_client_send(GetChunkStateRootProof(GetChunk::rpc_from(
+                request_data.chunk_reference,
+            )))
+            .await?;
+        Ok(near_jsonrpc_primitives::types::chunks::RpcChunkStateRootProofResponse { proof })
+    }
+
     async fn receipt(
         &self,
         request_data: near_jsonrpc_primitives::types::receipts::RpcReceiptRequest,
@@ -835,6 +1134,73 @@ impl JsonRpcHandler {
         }
     }
 
+    async fn trie_node(
+        &self,
+        request_data: near_jsonrpc_primitives::types::trie_node::RpcTrieNodeRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::trie_node::RpcTrieNodeResponse,
+        near_jsonrpc_primitives::types::trie_node::RpcTrieNodeError,
+    > {
+        match self
+            .view_client_send(GetTrieNode {
+                shard_id: request_data.shard_id,
+                hash: request_data.hash,
+            })
+            .await?
+        {
+            Some(trie_node) => {
+                Ok(near_jsonrpc_primitives::types::trie_node::RpcTrieNodeResponse { trie_node })
+            }
+            None => {
+                Err(near_jsonrpc_primitives::types::trie_node::RpcTrieNodeError::UnknownTrieNode {
+                    hash: request_data.hash,
+                })
+            }
+        }
+    }
+
+    async fn raw_trie_key(
+        &self,
+        request_data: near_jsonrpc_primitives::types::trie_key::RpcRawTrieKeyRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::trie_key::RpcRawTrieKeyResponse,
+        std::convert::Infallible,
+    > {
+        Ok(TrieKey::from(request_data.trie_key).into())
+    }
+
+    async fn pin_state_root(
+        &self,
+        request_data: near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsResponse,
+        near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsError,
+    > {
+        let pins = self.client_send(StateRootPins::Pin(request_data.block_hash)).await?;
+        Ok(near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsResponse { pins })
+    }
+
+    async fn unpin_state_root(
+        &self,
+        request_data: near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsResponse,
+        near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsError,
+    > {
+        let pins = self.client_send(StateRootPins::Unpin(request_data.block_hash)).await?;
+        Ok(near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsResponse { pins })
+    }
+
+    async fn pinned_state_roots(
+        &self,
+    ) -> Result<
+        near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsResponse,
+        near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsError,
+    > {
+        let pins = self.client_send(StateRootPins::List).await?;
+        Ok(near_jsonrpc_primitives::types::state_root_pins::RpcStateRootPinsResponse { pins })
+    }
+
     async fn changes_in_block(
         &self,
         request: near_jsonrpc_primitives::types::changes::RpcStateChangesInBlockRequest,
@@ -865,6 +1231,21 @@ impl JsonRpcHandler {
             self.view_client_send(GetBlock(request.block_reference)).await?;
 
         let block_hash = block.header.hash.clone();
+        if request.include_proof {
+            let response = self
+                .view_client_send(GetStateChangesWithProof {
+                    block_hash,
+                    state_changes_request: request.state_changes_request,
+                    cursor: request.cursor.unwrap_or_default(),
+                })
+                .await?;
+            return Ok(near_jsonrpc_primitives::types::changes::RpcStateChangesInBlockResponse {
+                block_hash: block.header.hash,
+                changes: response.changes,
+                proofs: Some(response.proofs),
+                next_cursor: response.next_cursor,
+            });
+        }
         let changes = self
             .view_client_send(GetStateChanges {
                 block_hash,
@@ -875,6 +1256,8 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::changes::RpcStateChangesInBlockResponse {
             block_hash: block.header.hash,
             changes,
+            proofs: None,
+            next_cursor: None,
         })
     }
 
@@ -895,12 +1278,15 @@ impl JsonRpcHandler {
         &self,
         request: near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofRequest,
     ) -> Result<
-        near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse,
+        WithProofEncoding<
+            near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse,
+        >,
         near_jsonrpc_primitives::types::light_client::RpcLightClientProofError,
     > {
         let near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofRequest {
             id,
             light_client_head,
+            encoding,
         } = request;
 
         let execution_outcome_proof: near_client_primitives::types::GetExecutionOutcomeResponse =
@@ -913,12 +1299,38 @@ impl JsonRpcHandler {
             })
             .await?;
 
-        Ok(near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse {
-            outcome_proof: execution_outcome_proof.outcome_proof,
-            outcome_root_proof: execution_outcome_proof.outcome_root_proof,
-            block_header_lite: block_proof.block_header_lite,
-            block_proof: block_proof.proof,
-        })
+        Ok(WithProofEncoding::new(
+            near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse {
+                outcome_proof: execution_outcome_proof.outcome_proof,
+                outcome_root_proof: execution_outcome_proof.outcome_root_proof,
+                block_header_lite: block_proof.block_header_lite,
+                block_proof: block_proof.proof,
+            },
+            encoding,
+        ))
+    }
+
+    async fn block_merkle_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::light_client::RpcBlockMerkleProofRequest,
+    ) -> Result<
+        WithProofEncoding<
+            near_jsonrpc_primitives::types::light_client::RpcBlockMerkleProofResponse,
+        >,
+        near_jsonrpc_primitives::types::light_client::RpcLightClientProofError,
+    > {
+        let block_proof: near_client_primitives::types::GetBlockProofResponse = self
+            .view_client_send(GetBlockProof {
+                block_hash: request.block_hash,
+                head_block_hash: request.head_block_hash,
+            })
+            .await?;
+        Ok(WithProofEncoding::new(
+            near_jsonrpc_primitives::types::light_client::RpcBlockMerkleProofResponse {
+                block_proof: block_proof.proof,
+            },
+            request.encoding,
+        ))
     }
 
     async fn network_info(
@@ -943,6 +1355,22 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::gas_price::RpcGasPriceResponse { gas_price_view })
     }
 
+    async fn gas_price_range(
+        &self,
+        request_data: near_jsonrpc_primitives::types::gas_price::RpcGasPriceRangeRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::gas_price::RpcGasPriceRangeResponse,
+        near_jsonrpc_primitives::types::gas_price::RpcGasPriceError,
+    > {
+        let gas_price_range = self
+            .view_client_send(GetGasPriceRange {
+                from_height: request_data.from_height,
+                to_height: request_data.to_height,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::gas_price::RpcGasPriceRangeResponse { gas_price_range })
+    }
+
     async fn validators(
         &self,
         request_data: near_jsonrpc_primitives::types::validator::RpcValidatorRequest,
@@ -971,6 +1399,40 @@ impl JsonRpcHandler {
         let validators = self.view_client_send(GetValidatorOrdered { block_id }).await?;
         Ok(validators)
     }
+
+    /// Returns the validators assigned to produce the block and the chunks of each shard at each
+    /// height of an epoch, as the epoch manager assigns them.
+    async fn producer_schedule(
+        &self,
+        request_data: near_jsonrpc_primitives::types::validator::RpcProducerScheduleRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator::RpcProducerScheduleResponse,
+        near_jsonrpc_primitives::types::validator::RpcValidatorError,
+    > {
+        let producer_schedule = self
+            .view_client_send(GetProducerSchedule { epoch_reference: request_data.epoch_reference })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::validator::RpcProducerScheduleResponse {
+            producer_schedule,
+        })
+    }
+
+    /// Returns the block producers of the next epoch of a block, along with what a light client
+    /// needs to check them against the `next_bp_hash` of the block.
+    async fn next_validator_set(
+        &self,
+        request_data: near_jsonrpc_primitives::types::validator::RpcNextValidatorSetRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator::RpcNextValidatorSetResponse,
+        near_jsonrpc_primitives::types::blocks::RpcBlockError,
+    > {
+        let next_validator_set = self
+            .view_client_send(GetNextValidatorSet { block_reference: request_data.block_reference })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::validator::RpcNextValidatorSetResponse {
+            next_validator_set,
+        })
+    }
 }
 
 #[cfg(feature = "sandbox")]
@@ -1194,7 +1656,8 @@ fn rpc_handler(
         let message = handler.process(message.0).await?;
         Ok(HttpResponse::Ok().json(&message))
     };
-    response.boxed()
+    // Not `Send`, as queries may be forwarded to the archival node with an `awc` client.
+    response.boxed_local()
 }
 
 fn status_handler(
@@ -1333,9 +1796,16 @@ pub fn start_http(
         polling_config,
         limits_config,
         enable_debug_rpc,
+        archival_fallback_url,
+        archival_fallback_timeout,
+        subscriptions_config,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr);
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
+    let subscriptions = web::Data::new(subscriptions::Subscriptions::start(
+        view_client_addr.clone(),
+        &subscriptions_config,
+    ));
     info!(target:"network", "Starting http server at {}", addr);
     let mut servers = Vec::new();
     let server = HttpServer::new(move || {
@@ -1347,8 +1817,13 @@ pub fn start_http(
                 polling_config,
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
+                max_batch_size: limits_config.max_batch_size,
+                batch_timeout: limits_config.batch_timeout,
+                archival_fallback_url: archival_fallback_url.clone(),
+                archival_fallback_timeout,
             }))
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
+            .app_data(subscriptions.clone())
             .wrap(middleware::Logger::default())
             .service(web::resource("/").route(web::post().to(rpc_handler)))
             .service(
@@ -1362,6 +1837,7 @@ pub fn start_http(
                     .route(web::head().to(health_handler)),
             )
             .service(web::resource("/network_info").route(web::get().to(network_info_handler)))
+            .service(web::resource("/ws").route(web::get().to(subscriptions::ws_handler)))
             .service(web::resource("/metrics").route(web::get().to(prometheus_handler)))
             .service(web::resource("/debug/api/{api}").route(web::get().to(debug_handler)))
             .service(debug_html)
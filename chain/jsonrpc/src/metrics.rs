@@ -1,4 +1,4 @@
-use near_metrics::{exponential_buckets, HistogramVec, IntCounter, IntCounterVec};
+use near_metrics::{exponential_buckets, Histogram, HistogramVec, IntCounter, IntCounterVec};
 use once_cell::sync::Lazy;
 
 pub static RPC_PROCESSING_TIME: Lazy<HistogramVec> = Lazy::new(|| {
@@ -10,6 +10,14 @@ pub static RPC_PROCESSING_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static RPC_BATCH_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    near_metrics::try_create_histogram_with_buckets(
+        "near_rpc_batch_size",
+        "Number of requests in batches of rpc requests",
+        exponential_buckets(1.0, 2.0, 12).unwrap(),
+    )
+    .unwrap()
+});
 pub static RPC_TIMEOUT_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
     near_metrics::try_create_int_counter(
         "near_rpc_timeout_total",
@@ -55,3 +63,10 @@ pub static RPC_UNREACHABLE_ERROR_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static RPC_ARCHIVAL_FALLBACK_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    near_metrics::try_create_int_counter(
+        "near_rpc_archival_fallback_total",
+        "Total count of queries of garbage collected data forwarded to the archival node",
+    )
+    .unwrap()
+});
@@ -0,0 +1,451 @@
+//! Subscriptions to the heads of the node over WebSocket.
+//!
+//! A client connected to `/ws` calls the `subscribe_new_heads`, `subscribe_final_heads` and
+//! `subscribe_light_client_blocks` JSON RPC methods, answered with the id of the new
+//! subscription, and `unsubscribe` with such an id. The events of a subscription are then pushed
+//! as `subscription` notifications, whose params hold the id of the subscription and the event in
+//! `result`, shaped like the response of the `block` method for heads and of the
+//! `next_light_client_block` method for light client blocks.
+//!
+//! Heads replaced between two checks of the node are sent too, found by following the previous
+//! hashes back to the last head sent. After a switch to another fork, the heads continue with the
+//! first block of the new fork above the last head sent, so heights only increase. When heads
+//! can't be found, e.g. after the node catches up with many blocks at once, their subscriptions get
+//! a notification with the range of heights skipped in `gap` instead.
+//!
+//! The events are broadcast to all the connections through a buffer of limited size. When a
+//! connection doesn't send its events fast enough, the oldest ones are dropped and each of its
+//! subscriptions gets a notification with the number of dropped events in `gap`.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::Addr;
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{web, Error as HttpError, HttpRequest, HttpResponse};
+use futures::future::{self, Either};
+use futures::stream::{self, LocalBoxStream};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use near_client::{GetBlock, GetNextLightClientBlock, ViewClientActor};
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{from_slice, Message, Request};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, BlockId, BlockReference, Finality};
+use near_primitives::views::{BlockHeaderView, BlockView};
+
+use crate::api::parse_params;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RpcSubscriptionsConfig {
+    /// How often the heads of the node are checked.
+    pub polling_interval: Duration,
+    /// Maximum number of subscriptions of a connection.
+    pub max_subscriptions_per_connection: usize,
+    /// Number of events kept for the connections which didn't send them yet. Beyond it, the
+    /// oldest events are dropped.
+    pub buffer_size: usize,
+}
+
+impl Default for RpcSubscriptionsConfig {
+    fn default() -> Self {
+        Self {
+            polling_interval: Duration::from_millis(100),
+            max_subscriptions_per_connection: 10,
+            buffer_size: 128,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SubscriptionKind {
+    NewHeads,
+    FinalHeads,
+    LightClientBlocks,
+}
+
+impl SubscriptionKind {
+    fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "subscribe_new_heads" => Some(Self::NewHeads),
+            "subscribe_final_heads" => Some(Self::FinalHeads),
+            "subscribe_light_client_blocks" => Some(Self::LightClientBlocks),
+            _ => None,
+        }
+    }
+}
+
+/// An event for the subscriptions of a kind, serialized once for all of them.
+#[derive(Debug)]
+struct Event {
+    kind: SubscriptionKind,
+    data: EventData,
+}
+
+#[derive(Debug)]
+enum EventData {
+    /// Sent in `result`.
+    Result(Value),
+    /// Events which couldn't be produced, sent in `gap`.
+    Gap(Value),
+}
+
+fn send_event(
+    events: &broadcast::Sender<Arc<Event>>,
+    kind: SubscriptionKind,
+    result: &impl Serialize,
+) {
+    match serde_json::to_value(result) {
+        Ok(result) => {
+            // Fails only when no connection is left, which then has nothing to miss.
+            let _ = events.send(Arc::new(Event { kind, data: EventData::Result(result) }));
+        }
+        Err(err) => {
+            tracing::warn!(target: "jsonrpc", "Failed to serialize {:?} event: {}", kind, err)
+        }
+    }
+}
+
+/// Sends a gap for the heads with heights in `from_height..=to_height` which were not sent.
+fn send_gap(
+    events: &broadcast::Sender<Arc<Event>>,
+    kind: SubscriptionKind,
+    (from_height, to_height): (BlockHeight, BlockHeight),
+) {
+    let gap = json!({"from_height": from_height, "to_height": to_height});
+    let _ = events.send(Arc::new(Event { kind, data: EventData::Gap(gap) }));
+}
+
+/// Shared by the connections of all the workers of the server.
+pub(crate) struct Subscriptions {
+    events: broadcast::Sender<Arc<Event>>,
+    max_subscriptions_per_connection: usize,
+}
+
+impl Subscriptions {
+    /// Starts checking the heads of the node for the connections to come.
+    pub(crate) fn start(
+        view_client_addr: Addr<ViewClientActor>,
+        config: &RpcSubscriptionsConfig,
+    ) -> Self {
+        let (events, _) = broadcast::channel(config.buffer_size.max(1));
+        actix::spawn(stream_heads(
+            view_client_addr,
+            config.polling_interval,
+            config.buffer_size.max(1),
+            events.clone(),
+        ));
+        Self { events, max_subscriptions_per_connection: config.max_subscriptions_per_connection }
+    }
+}
+
+/// Sends an event for each new head and final head of the node, and one for the light client
+/// block following the last final head of an epoch once the final head enters the next epoch.
+/// That block carries the block producers of the epoch after it.
+async fn stream_heads(
+    view_client_addr: Addr<ViewClientActor>,
+    polling_interval: Duration,
+    max_heads_per_check: usize,
+    events: broadcast::Sender<Arc<Event>>,
+) {
+    let mut last_head: Option<BlockHeaderView> = None;
+    let mut last_final_head: Option<BlockHeaderView> = None;
+    loop {
+        tokio::time::sleep(polling_interval).await;
+        if events.receiver_count() == 0 {
+            // Connections to come start from the heads at the time.
+            last_head = None;
+            last_final_head = None;
+            continue;
+        }
+
+        let head = view_client_addr.send(GetBlock(BlockReference::Finality(Finality::None))).await;
+        if let Ok(Ok(head)) = head {
+            let (heads, gap) =
+                get_heads_since(&view_client_addr, head, last_head.as_ref(), max_heads_per_check)
+                    .await;
+            if let Some(gap) = gap {
+                send_gap(&events, SubscriptionKind::NewHeads, gap);
+            }
+            for block in heads {
+                send_event(&events, SubscriptionKind::NewHeads, &block);
+                last_head = Some(block.header);
+            }
+        }
+
+        let final_head =
+            view_client_addr.send(GetBlock(BlockReference::Finality(Finality::Final))).await;
+        let final_head = match final_head {
+            Ok(Ok(block)) => block,
+            _ => continue,
+        };
+        let (final_heads, gap) = get_heads_since(
+            &view_client_addr,
+            final_head,
+            last_final_head.as_ref(),
+            max_heads_per_check,
+        )
+        .await;
+        if let Some(gap) = gap {
+            send_gap(&events, SubscriptionKind::FinalHeads, gap);
+            // The skipped heads may cross epochs.
+            send_gap(&events, SubscriptionKind::LightClientBlocks, gap);
+        }
+        for block in final_heads {
+            send_event(&events, SubscriptionKind::FinalHeads, &block);
+            match &last_final_head {
+                Some(last_final_head) if last_final_head.epoch_id != block.header.epoch_id => {
+                    let light_client_block = view_client_addr
+                        .send(GetNextLightClientBlock { last_block_hash: last_final_head.hash })
+                        .await;
+                    if let Ok(Ok(Some(light_client_block))) = light_client_block {
+                        send_event(
+                            &events,
+                            SubscriptionKind::LightClientBlocks,
+                            &light_client_block,
+                        );
+                    }
+                }
+                _ => {}
+            }
+            last_final_head = Some(block.header);
+        }
+    }
+}
+
+/// The heads to send after `last_sent`, lowest first: the blocks from `head` back to `last_sent`,
+/// or down to its height if `head` is on another fork. At most `max_heads` of them, the others
+/// are returned as a gap of heights.
+async fn get_heads_since(
+    view_client_addr: &Addr<ViewClientActor>,
+    head: BlockView,
+    last_sent: Option<&BlockHeaderView>,
+    max_heads: usize,
+) -> (Vec<BlockView>, Option<(BlockHeight, BlockHeight)>) {
+    let last_sent = match last_sent {
+        Some(last_sent) => last_sent,
+        None => return (vec![head], None),
+    };
+    let mut heads = vec![];
+    let mut block = head;
+    let mut gap = None;
+    while block.header.hash != last_sent.hash && block.header.height > last_sent.height {
+        if heads.len() == max_heads {
+            gap = Some((last_sent.height + 1, block.header.height));
+            break;
+        }
+        let (prev_hash, height) = (block.header.prev_hash, block.header.height);
+        heads.push(block);
+        if prev_hash == last_sent.hash {
+            break;
+        }
+        let prev = view_client_addr
+            .send(GetBlock(BlockReference::BlockId(BlockId::Hash(prev_hash))))
+            .await;
+        block = match prev {
+            Ok(Ok(prev)) => prev,
+            _ => {
+                if height - 1 > last_sent.height {
+                    gap = Some((last_sent.height + 1, height - 1));
+                }
+                break;
+            }
+        };
+    }
+    heads.reverse();
+    (heads, gap)
+}
+
+/// Accepts a WebSocket connection and serves its subscriptions until either side closes it.
+pub(crate) async fn ws_handler(
+    req: HttpRequest,
+    payload: web::Payload,
+    subscriptions: web::Data<Subscriptions>,
+) -> Result<HttpResponse, HttpError> {
+    ws::verify_handshake(req.head())?;
+    // The handshake was verified to have the key.
+    let key = ws::hash_key(req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap().as_ref());
+    let connection = Connection {
+        frames: decode_frames(payload),
+        events: subscriptions.events.subscribe(),
+        codec: ws::Codec::new(),
+        subscriptions: BTreeMap::new(),
+        next_subscription_id: 0,
+        max_subscriptions: subscriptions.max_subscriptions_per_connection,
+        pending: VecDeque::new(),
+        closed: false,
+    };
+    Ok(HttpResponse::build(StatusCode::SWITCHING_PROTOCOLS)
+        .upgrade("websocket")
+        // The hashed key is ASCII.
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, HeaderValue::from_bytes(&key).unwrap()))
+        .streaming(connection.into_stream()))
+}
+
+/// Frames sent by the client, decoded from the payload of the upgraded request.
+fn decode_frames(
+    payload: web::Payload,
+) -> LocalBoxStream<'static, Result<ws::Frame, ws::ProtocolError>> {
+    stream::unfold(
+        (payload, BytesMut::new(), ws::Codec::new()),
+        |(mut payload, mut buf, mut codec)| async move {
+            loop {
+                match codec.decode(&mut buf) {
+                    Ok(Some(frame)) => return Some((Ok(frame), (payload, buf, codec))),
+                    Ok(None) => {}
+                    Err(err) => return Some((Err(err), (payload, buf, codec))),
+                }
+                match payload.next().await {
+                    Some(Ok(bytes)) => buf.extend_from_slice(&bytes),
+                    _ => return None,
+                }
+            }
+        },
+    )
+    .boxed_local()
+}
+
+/// A WebSocket connection. Its messages are produced as the response body is sent, so a client
+/// reading slowly makes its connection lag behind the events rather than buffer them.
+struct Connection {
+    frames: LocalBoxStream<'static, Result<ws::Frame, ws::ProtocolError>>,
+    events: broadcast::Receiver<Arc<Event>>,
+    codec: ws::Codec,
+    subscriptions: BTreeMap<u64, SubscriptionKind>,
+    next_subscription_id: u64,
+    max_subscriptions: usize,
+    /// Messages to send before handling anything else.
+    pending: VecDeque<ws::Message>,
+    closed: bool,
+}
+
+impl Connection {
+    fn into_stream(self) -> impl Stream<Item = Result<Bytes, ws::ProtocolError>> {
+        stream::unfold(self, |mut connection| async move {
+            let message = connection.next_message().await?;
+            let mut buf = BytesMut::new();
+            let bytes = connection.codec.encode(message, &mut buf).map(|()| buf.freeze());
+            Some((bytes, connection))
+        })
+    }
+
+    /// The next message to send, or `None` once the connection is closed.
+    async fn next_message(&mut self) -> Option<ws::Message> {
+        loop {
+            if let Some(message) = self.pending.pop_front() {
+                return Some(message);
+            }
+            if self.closed {
+                return None;
+            }
+            let next = match future::select(self.frames.next(), Box::pin(self.events.recv())).await
+            {
+                Either::Left((frame, _)) => Either::Left(frame),
+                Either::Right((event, _)) => Either::Right(event),
+            };
+            match next {
+                Either::Left(frame) => self.handle_frame(frame),
+                Either::Right(event) => self.handle_event(event),
+            }
+        }
+    }
+
+    fn send(&mut self, message: Message) {
+        let text: String = message.into();
+        self.pending.push_back(ws::Message::Text(text.into()));
+    }
+
+    fn handle_frame(&mut self, frame: Option<Result<ws::Frame, ws::ProtocolError>>) {
+        match frame {
+            Some(Ok(ws::Frame::Text(text))) => {
+                let response = self.handle_message(&text);
+                self.send(response);
+            }
+            Some(Ok(ws::Frame::Binary(_))) | Some(Ok(ws::Frame::Continuation(_))) => {
+                self.send(Message::error(RpcError::parse_error(
+                    "A text message of a single frame was expected".to_owned(),
+                )));
+            }
+            Some(Ok(ws::Frame::Ping(data))) => self.pending.push_back(ws::Message::Pong(data)),
+            Some(Ok(ws::Frame::Pong(_))) => {}
+            Some(Ok(ws::Frame::Close(reason))) => {
+                self.pending.push_back(ws::Message::Close(reason));
+                self.closed = true;
+            }
+            Some(Err(_)) | None => self.closed = true,
+        }
+    }
+
+    fn handle_message(&mut self, text: &[u8]) -> Message {
+        match from_slice(text) {
+            Ok(Message::Request(request)) => {
+                let result = self.handle_request(&request);
+                Message::response(request.id, result)
+            }
+            Ok(_) => Message::error(RpcError::parse_error(
+                "JSON RPC Request format was expected".to_owned(),
+            )),
+            Err(broken) => broken.reply(),
+        }
+    }
+
+    fn handle_request(&mut self, request: &Request) -> Result<Value, RpcError> {
+        if request.method == "unsubscribe" {
+            let (id,) = parse_params::<(u64,)>(request.params.clone())?;
+            return Ok(Value::Bool(self.subscriptions.remove(&id).is_some()));
+        }
+        let kind = SubscriptionKind::from_method(&request.method)
+            .ok_or_else(|| RpcError::method_not_found(request.method.clone()))?;
+        if self.subscriptions.len() >= self.max_subscriptions {
+            return Err(RpcError::new_internal_or_handler_error(
+                Some(Value::String(format!(
+                    "A connection can have at most {} subscriptions",
+                    self.max_subscriptions
+                ))),
+                json!({
+                    "name": "TOO_MANY_SUBSCRIPTIONS",
+                    "info": {"max_subscriptions": self.max_subscriptions},
+                }),
+            ));
+        }
+        let id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        self.subscriptions.insert(id, kind);
+        Ok(json!(id))
+    }
+
+    fn handle_event(&mut self, event: Result<Arc<Event>, broadcast::error::RecvError>) {
+        let notifications: Vec<Value> = match event {
+            Ok(event) => self
+                .subscriptions
+                .iter()
+                .filter(|(_, kind)| **kind == event.kind)
+                .map(|(id, _)| match &event.data {
+                    EventData::Result(result) => json!({"subscription": id, "result": result}),
+                    EventData::Gap(gap) => json!({"subscription": id, "gap": gap}),
+                })
+                .collect(),
+            Err(broadcast::error::RecvError::Lagged(dropped_events)) => self
+                .subscriptions
+                .keys()
+                .map(|id| json!({"subscription": id, "gap": {"dropped_events": dropped_events}}))
+                .collect(),
+            Err(broadcast::error::RecvError::Closed) => {
+                self.closed = true;
+                vec![]
+            }
+        };
+        for params in notifications {
+            self.send(Message::notification("subscription".to_owned(), Some(params)));
+        }
+    }
+}
@@ -314,7 +314,7 @@ pub(crate) async fn query_account(
 > {
     let query = near_client::Query::new(
         block_id,
-        near_primitives::views::QueryRequest::ViewAccount { account_id },
+        near_primitives::views::QueryRequest::ViewAccount { account_id, include_proof: false },
     );
     let account_info_response = match view_client_addr.send(query).await? {
         Ok(query_response) => query_response,
@@ -382,7 +382,11 @@ pub(crate) async fn query_access_key(
 > {
     let access_key_query = near_client::Query::new(
         block_id,
-        near_primitives::views::QueryRequest::ViewAccessKey { account_id, public_key },
+        near_primitives::views::QueryRequest::ViewAccessKey {
+            account_id,
+            public_key,
+            include_proof: false,
+        },
     );
     let access_key_query_response = match view_client_addr.send(access_key_query).await? {
         Ok(query_response) => query_response,
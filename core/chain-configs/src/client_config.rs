@@ -40,6 +40,15 @@ pub struct GCConfig {
     /// Number of epochs for which we keep store data.
     #[serde(default = "default_gc_num_epochs_to_keep")]
     pub gc_num_epochs_to_keep: u64,
+
+    /// Accounts for which the state roots of the latest final blocks changing them are kept
+    /// from garbage collection, e.g. to serve proofs to light clients lagging behind.
+    #[serde(default)]
+    pub pinned_state_root_accounts: Vec<AccountId>,
+
+    /// Number of state roots kept for each of `pinned_state_root_accounts`.
+    #[serde(default = "default_num_pinned_state_roots")]
+    pub num_pinned_state_roots: NumBlocks,
 }
 
 impl Default for GCConfig {
@@ -48,6 +57,8 @@ impl Default for GCConfig {
             gc_blocks_limit: 2,
             gc_fork_clean_step: 100,
             gc_num_epochs_to_keep: DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+            pinned_state_root_accounts: vec![],
+            num_pinned_state_roots: 10,
         }
     }
 }
@@ -64,6 +75,10 @@ fn default_gc_num_epochs_to_keep() -> u64 {
     GCConfig::default().gc_num_epochs_to_keep()
 }
 
+fn default_num_pinned_state_roots() -> NumBlocks {
+    GCConfig::default().num_pinned_state_roots
+}
+
 impl GCConfig {
     pub fn gc_num_epochs_to_keep(&self) -> u64 {
         max(MIN_GC_NUM_EPOCHS_TO_KEEP, self.gc_num_epochs_to_keep)
@@ -150,10 +165,26 @@ pub struct ClientConfig {
     pub view_client_throttle_period: Duration,
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     pub trie_viewer_state_size_limit: Option<u64>,
+    /// Whether queries may raise `trie_viewer_state_size_limit`, up to
+    /// `trie_viewer_state_size_ceiling`. Lowering it is always allowed.
+    pub allow_state_size_override: bool,
+    pub trie_viewer_state_size_ceiling: u64,
     /// Max burnt gas per view method.  If present, overrides value stored in
     /// genesis file.  The value only affects the RPCs without influencing the
     /// protocol thus changing it per-node doesn’t affect the blockchain.
+    /// Queries may ask for a different limit; both are capped by
+    /// `MAX_GAS_BURNT_VIEW_CEILING` of the runtime.
     pub max_gas_burnt_view: Option<Gas>,
+    /// Max number of logs per view method.  If not present, a multiple of
+    /// the protocol limit.  Like `max_gas_burnt_view`, only affects the RPCs.
+    pub max_number_logs_view: Option<u64>,
+    /// Max total length in bytes of the logs per view method.  If not present,
+    /// a multiple of the protocol limit.  Only affects the RPCs.
+    pub max_total_log_length_view: Option<u64>,
+    /// Keep a flat index of the contract data of the tracked shards at the head, to serve
+    /// `view_state` queries without proof at the latest block from it.  Takes extra disk space
+    /// and time on block application.
+    pub flat_contract_data_index: bool,
     /// Re-export storage layer statistics as prometheus metrics.
     pub enable_statistics_export: bool,
 }
@@ -213,7 +244,12 @@ impl ClientConfig {
             epoch_sync_enabled,
             view_client_throttle_period: Duration::from_secs(1),
             trie_viewer_state_size_limit: None,
+            allow_state_size_override: false,
+            trie_viewer_state_size_ceiling: 0,
             max_gas_burnt_view: None,
+            max_number_logs_view: None,
+            max_total_log_length_view: None,
+            flat_contract_data_index: false,
             enable_statistics_export: true,
         }
     }
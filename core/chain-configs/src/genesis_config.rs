@@ -684,10 +684,45 @@ pub fn get_initial_supply(records: &[StateRecord]) -> Balance {
 
 #[cfg(test)]
 mod test {
-    use crate::genesis_config::RecordsProcessor;
+    use crate::genesis_config::{
+        GenesisConfig, ProtocolConfig, ProtocolConfigView, RecordsProcessor,
+    };
+    use near_primitives::runtime::config_store::{RuntimeConfigStore, FORK_PROTOCOL_VERSION};
     use near_primitives::state_record::StateRecord;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_primitives_core::parameter::Parameter;
     use serde::Deserializer;
 
+    /// Fields of the JSON of `ProtocolConfigView`, which RPC clients rely on.
+    const PROTOCOL_CONFIG_VIEW_FIELDS: &[&str] = &[
+        "avg_hidden_validator_seats_per_shard",
+        "block_producer_kickout_threshold",
+        "chain_id",
+        "chunk_producer_kickout_threshold",
+        "dynamic_resharding",
+        "epoch_length",
+        "fishermen_threshold",
+        "gas_limit",
+        "gas_price_adjustment_rate",
+        "genesis_height",
+        "genesis_time",
+        "max_gas_price",
+        "max_inflation_rate",
+        "min_gas_price",
+        "minimum_stake_divisor",
+        "num_block_producer_seats",
+        "num_block_producer_seats_per_shard",
+        "num_blocks_per_year",
+        "online_max_threshold",
+        "online_min_threshold",
+        "protocol_reward_rate",
+        "protocol_treasury_account",
+        "protocol_upgrade_stake_threshold",
+        "protocol_version",
+        "runtime_config",
+        "transaction_validity_period",
+    ];
+
     fn stream_records_from_json_str(genesis: &str) -> serde_json::Result<()> {
         let mut deserializer = serde_json::Deserializer::from_reader(genesis.as_bytes());
         let records_processor = RecordsProcessor { sink: &mut |_record: StateRecord| {} };
@@ -834,4 +869,38 @@ mod test {
         }"#;
         stream_records_from_json_str(genesis).expect("error reading records from genesis");
     }
+
+    /// The protocol config of every version serializes to the same fields, with the full runtime
+    /// config of that version including the costs of this fork.
+    #[test]
+    fn test_protocol_config_view_json() {
+        let store = RuntimeConfigStore::new(None);
+        let protocol_versions =
+            [0, 42, 48, 49, 50, 52, 53, FORK_PROTOCOL_VERSION - 1, FORK_PROTOCOL_VERSION];
+        for protocol_version in protocol_versions.into_iter().chain([PROTOCOL_VERSION]) {
+            let runtime_config = store.get_config(protocol_version).as_ref().clone();
+            let view = ProtocolConfigView::from(ProtocolConfig {
+                genesis_config: GenesisConfig { protocol_version, ..GenesisConfig::default() },
+                runtime_config: runtime_config.clone(),
+            });
+            let json = serde_json::to_value(&view).unwrap();
+            let fields: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+            assert_eq!(fields, PROTOCOL_CONFIG_VIEW_FIELDS, "protocol version {protocol_version}");
+            assert_eq!(json["protocol_version"], protocol_version);
+            assert_eq!(json["runtime_config"], serde_json::to_value(&runtime_config).unwrap());
+
+            let ext_costs = json["runtime_config"]["wasm_config"]["ext_costs"].as_object().unwrap();
+            for param in Parameter::fork_ext_costs() {
+                let name = param.to_string();
+                let name = name.strip_prefix("wasm_").unwrap();
+                assert!(
+                    ext_costs.contains_key(name),
+                    "{name} missing in version {protocol_version}"
+                );
+            }
+
+            let deserialized: ProtocolConfigView = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&deserialized).unwrap(), json);
+        }
+    }
 }
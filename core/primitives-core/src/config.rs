@@ -6,6 +6,7 @@ use std::hash::{Hash, Hasher};
 use strum::{Display, EnumCount};
 
 #[derive(Clone, Debug, Hash, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct VMConfig {
     /// Costs for runtime externals
     pub ext_costs: ExtCostsConfig,
@@ -22,6 +23,7 @@ pub struct VMConfig {
 /// Describes limits for VM and Runtime.
 /// TODO #4139: consider switching to strongly-typed wrappers instead of raw quantities
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct VMLimitConfig {
     /// Max amount of gas that can be used, excluding gas attached to promises.
     pub max_gas_burnt: Gas,
@@ -96,6 +98,89 @@ pub struct VMLimitConfig {
     /// historically.
     #[serde(default = "AccountIdValidityRulesVersion::v0")]
     pub account_id_validity_rules_version: AccountIdValidityRulesVersion,
+    /// Max size in bytes of a serialized ICS-23 commitment proof.
+    #[serde(default = "fork_defaults::max_ics23_proof_size")]
+    pub max_ics23_proof_size: u64,
+    /// Max size in bytes of a serialized NEAR state proof.
+    #[serde(default = "fork_defaults::max_state_proof_size")]
+    pub max_state_proof_size: u64,
+    /// Max number of trie nodes in a NEAR state proof.
+    #[serde(default = "fork_defaults::max_number_state_proof_nodes")]
+    pub max_number_state_proof_nodes: u64,
+    /// Max number of items in the merkle path of an execution outcome proof.
+    #[serde(default = "fork_defaults::max_outcome_proof_length")]
+    pub max_outcome_proof_length: u64,
+    /// Whether `storage_read_with_proof` may be called outside of view calls.
+    #[serde(default = "fork_defaults::allow_storage_proofs_in_transactions")]
+    pub allow_storage_proofs_in_transactions: bool,
+    /// Max number of blocks before the current one whose hash `block_hash` returns.
+    #[serde(default = "fork_defaults::max_block_hash_lookback")]
+    pub max_block_hash_lookback: u64,
+    /// Max number of events emitted with `emit_event`.
+    #[serde(default = "fork_defaults::max_number_events")]
+    pub max_number_events: u64,
+    /// Max total length of the standards and data of all events.
+    #[serde(default = "fork_defaults::max_total_event_length")]
+    pub max_total_event_length: u64,
+    /// Max number of validators in a Tendermint validator set.
+    #[serde(default = "fork_defaults::max_number_tendermint_validators")]
+    pub max_number_tendermint_validators: u64,
+    /// Whether `ecrecover` rejects signatures in the upper range of `s` regardless of the
+    /// malleability flag passed by the contract.
+    #[serde(default = "fork_defaults::force_ecrecover_malleability_check")]
+    pub force_ecrecover_malleability_check: bool,
+}
+
+/// Declares the values that the costs and limits added by this fork take when deserializing a
+/// config written before they existed. They are the same in every protocol version, a test in
+/// `near-primitives` checks that they match the parameter files.
+macro_rules! fork_defaults {
+    ($($name:ident: $ty:ty = $value:expr;)*) => {
+        mod fork_defaults {
+            use super::Gas;
+
+            $(pub(super) fn $name() -> $ty {
+                $value
+            })*
+        }
+    };
+}
+
+fork_defaults! {
+    verify_ics23_proof_base: Gas = 20_000_000_000;
+    verify_ics23_proof_byte: Gas = 30_000_000;
+    verify_ics23_proof_inner_op: Gas = 5_000_000_000;
+    verify_near_state_proof_base: Gas = 10_000_000_000;
+    verify_near_state_proof_byte: Gas = 30_000_000;
+    verify_near_state_proof_node: Gas = 5_000_000_000;
+    verify_outcome_proof_base: Gas = 5_000_000_000;
+    verify_outcome_proof_item: Gas = 6_000_000_000;
+    validate_light_client_block_base: Gas = 20_000_000_000;
+    validate_light_client_block_byte: Gas = 30_000_000;
+    validate_light_client_block_signature: Gas = 210_000_000_000;
+    verify_mmr_proof_base: Gas = 5_000_000_000;
+    verify_mmr_proof_byte: Gas = 21_471_105;
+    verify_mmr_proof_item: Gas = 7_500_000_000;
+    verify_beefy_commitment_base: Gas = 5_000_000_000;
+    verify_beefy_commitment_byte: Gas = 21_471_105;
+    verify_beefy_commitment_signature: Gas = 3_365_369_625_000;
+    storage_read_proof_byte: Gas = 30_000_000;
+    block_hash_base: Gas = 911_834_726_400;
+    event_base: Gas = 3_543_313_050;
+    event_byte: Gas = 13_198_791;
+    verify_tendermint_commit_base: Gas = 5_000_000_000;
+    verify_tendermint_commit_byte: Gas = 30_000_000;
+    verify_tendermint_commit_signature: Gas = 210_611_100_000;
+    max_ics23_proof_size: u64 = 65_536;
+    max_state_proof_size: u64 = 524_288;
+    max_number_state_proof_nodes: u64 = 512;
+    max_outcome_proof_length: u64 = 64;
+    allow_storage_proofs_in_transactions: bool = false;
+    max_block_hash_lookback: u64 = 256;
+    max_number_events: u64 = 100;
+    max_total_event_length: u64 = 16_384;
+    max_number_tendermint_validators: u64 = 256;
+    force_ecrecover_malleability_check: bool = false;
 }
 
 fn wasmer2_stack_limit_default() -> i32 {
@@ -238,6 +323,28 @@ impl VMLimitConfig {
             // is 4 bytes worth of code for each local.
             max_locals_per_contract: Some(max_contract_size / 4),
             account_id_validity_rules_version: AccountIdValidityRulesVersion::V1,
+            // Bounds protobuf decoding work, a typical IAVL proof is well below 1 KiB.
+            max_ics23_proof_size: 64 * 1024,
+            // A full branch node serializes to well under 1 KiB.
+            max_state_proof_size: 512 * 1024,
+            // A path to a value is at most one node per nibble of the trie key.
+            max_number_state_proof_nodes: 512,
+            // Paths of `merklize` are logarithmic in the number of outcomes, the
+            // outcome root of a block adds a level per chunk merkle tree.
+            max_outcome_proof_length: 64,
+            // Recording the trie nodes touched by a read is not priced yet.
+            allow_storage_proofs_in_transactions: false,
+            // Lookups walk back the chain one block at a time, so this is
+            // kept well below the length of an epoch.
+            max_block_hash_lookback: 256,
+            // Events are kept apart from logs, but limited the same way.
+            max_number_events: 100,
+            max_total_event_length: 16 * 1024,
+            // Comfortably above the active set of Cosmos chains, a commit signed by all
+            // of them still fits in the gas limit.
+            max_number_tendermint_validators: 256,
+            // Contracts decide whether to reject malleable signatures.
+            force_ecrecover_malleability_check: false,
         }
     }
 }
@@ -247,9 +354,38 @@ impl VMLimitConfig {
 pub struct ViewConfig {
     /// If specified, defines max burnt gas per view method.
     pub max_gas_burnt: Gas,
+    /// Max number of log entries of a view method. Defaults to `VIEW_LOG_LIMITS_MULTIPLIER`
+    /// times `max_number_logs`.
+    #[serde(default)]
+    pub max_number_logs: Option<u64>,
+    /// Max total length in bytes of all log messages of a view method. Defaults to
+    /// `VIEW_LOG_LIMITS_MULTIPLIER` times `max_total_log_length`.
+    #[serde(default)]
+    pub max_total_log_length: Option<u64>,
+}
+
+/// Nothing logged by a view method is persisted, so view methods may log more than the
+/// consensus limits allow.
+pub const VIEW_LOG_LIMITS_MULTIPLIER: u64 = 4;
+
+impl ViewConfig {
+    /// Max number of log entries of a view method under `limit_config`.
+    pub fn max_number_logs(&self, limit_config: &VMLimitConfig) -> u64 {
+        self.max_number_logs.unwrap_or_else(|| {
+            limit_config.max_number_logs.saturating_mul(VIEW_LOG_LIMITS_MULTIPLIER)
+        })
+    }
+
+    /// Max total length of the log messages of a view method under `limit_config`.
+    pub fn max_total_log_length(&self, limit_config: &VMLimitConfig) -> u64 {
+        self.max_total_log_length.unwrap_or_else(|| {
+            limit_config.max_total_log_length.saturating_mul(VIEW_LOG_LIMITS_MULTIPLIER)
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct ExtCostsConfig {
     /// Base cost for calling a host function.
     pub base: Gas,
@@ -415,6 +551,114 @@ pub struct ExtCostsConfig {
     pub alt_bn128_pairing_check_base: Gas,
     /// Per element cost for pairing check
     pub alt_bn128_pairing_check_element: Gas,
+
+    // #########
+    // # ICS23 #
+    // #########
+    /// Base cost for verifying an ICS-23 commitment proof
+    #[serde(default = "fork_defaults::verify_ics23_proof_base")]
+    pub verify_ics23_proof_base: Gas,
+    /// Cost per byte of the serialized proof
+    #[serde(default = "fork_defaults::verify_ics23_proof_byte")]
+    pub verify_ics23_proof_byte: Gas,
+    /// Cost per inner op applied while computing the root
+    #[serde(default = "fork_defaults::verify_ics23_proof_inner_op")]
+    pub verify_ics23_proof_inner_op: Gas,
+
+    // ###############
+    // # State proof #
+    // ###############
+    /// Base cost for verifying a NEAR state proof
+    #[serde(default = "fork_defaults::verify_near_state_proof_base")]
+    pub verify_near_state_proof_base: Gas,
+    /// Cost per byte of the serialized proof
+    #[serde(default = "fork_defaults::verify_near_state_proof_byte")]
+    pub verify_near_state_proof_byte: Gas,
+    /// Cost per trie node in the proof
+    #[serde(default = "fork_defaults::verify_near_state_proof_node")]
+    pub verify_near_state_proof_node: Gas,
+
+    // #################
+    // # Outcome proof #
+    // #################
+    /// Base cost for verifying an execution outcome merkle proof
+    #[serde(default = "fork_defaults::verify_outcome_proof_base")]
+    pub verify_outcome_proof_base: Gas,
+    /// Cost per merkle path item, each combining two hashes
+    #[serde(default = "fork_defaults::verify_outcome_proof_item")]
+    pub verify_outcome_proof_item: Gas,
+
+    // ################
+    // # Light client #
+    // ################
+    /// Base cost for validating a NEAR light client block
+    #[serde(default = "fork_defaults::validate_light_client_block_base")]
+    pub validate_light_client_block_base: Gas,
+    /// Cost per byte of the serialized inputs
+    #[serde(default = "fork_defaults::validate_light_client_block_byte")]
+    pub validate_light_client_block_byte: Gas,
+    /// Cost per verified approval signature
+    #[serde(default = "fork_defaults::validate_light_client_block_signature")]
+    pub validate_light_client_block_signature: Gas,
+
+    // ##############
+    // # BEEFY, MMR #
+    // ##############
+    /// Base cost for verifying an MMR inclusion proof
+    #[serde(default = "fork_defaults::verify_mmr_proof_base")]
+    pub verify_mmr_proof_base: Gas,
+    /// Cost per byte of the hashed leaf
+    #[serde(default = "fork_defaults::verify_mmr_proof_byte")]
+    pub verify_mmr_proof_byte: Gas,
+    /// Cost per proof item, each merging two hashes
+    #[serde(default = "fork_defaults::verify_mmr_proof_item")]
+    pub verify_mmr_proof_item: Gas,
+    /// Base cost for verifying a BEEFY commitment
+    #[serde(default = "fork_defaults::verify_beefy_commitment_base")]
+    pub verify_beefy_commitment_base: Gas,
+    /// Cost per byte of the hashed commitment
+    #[serde(default = "fork_defaults::verify_beefy_commitment_byte")]
+    pub verify_beefy_commitment_byte: Gas,
+    /// Cost per recovered signature
+    #[serde(default = "fork_defaults::verify_beefy_commitment_signature")]
+    pub verify_beefy_commitment_signature: Gas,
+
+    // ##################
+    // # Storage proofs #
+    // ##################
+    /// Cost per byte of the proof written by `storage_read_with_proof`
+    #[serde(default = "fork_defaults::storage_read_proof_byte")]
+    pub storage_read_proof_byte: Gas,
+
+    // ##############
+    // # Block hash #
+    // ##############
+    /// Cost of calling `block_hash`
+    #[serde(default = "fork_defaults::block_hash_base")]
+    pub block_hash_base: Gas,
+
+    // ##########
+    // # Events #
+    // ##########
+    /// Cost for calling `emit_event`
+    #[serde(default = "fork_defaults::event_base")]
+    pub event_base: Gas,
+    /// Cost for emitting an event per byte of its standard and data
+    #[serde(default = "fork_defaults::event_byte")]
+    pub event_byte: Gas,
+
+    // ##############
+    // # Tendermint #
+    // ##############
+    /// Base cost for verifying a Tendermint commit
+    #[serde(default = "fork_defaults::verify_tendermint_commit_base")]
+    pub verify_tendermint_commit_base: Gas,
+    /// Cost per byte of the decoded validator set and commit
+    #[serde(default = "fork_defaults::verify_tendermint_commit_byte")]
+    pub verify_tendermint_commit_byte: Gas,
+    /// Cost per verified ed25519 signature
+    #[serde(default = "fork_defaults::verify_tendermint_commit_signature")]
+    pub verify_tendermint_commit_signature: Gas,
 }
 
 fn default_read_cached_trie_node() -> Gas {
@@ -469,14 +713,14 @@ impl ExtCostsConfig {
             storage_remove_ret_value_byte: SAFETY_MULTIPLIER * 3843852,
             storage_has_key_base: SAFETY_MULTIPLIER * 18013298875,
             storage_has_key_byte: SAFETY_MULTIPLIER * 10263615,
-            storage_iter_create_prefix_base: SAFETY_MULTIPLIER * 0,
-            storage_iter_create_prefix_byte: SAFETY_MULTIPLIER * 0,
-            storage_iter_create_range_base: SAFETY_MULTIPLIER * 0,
-            storage_iter_create_from_byte: SAFETY_MULTIPLIER * 0,
-            storage_iter_create_to_byte: SAFETY_MULTIPLIER * 0,
-            storage_iter_next_base: SAFETY_MULTIPLIER * 0,
-            storage_iter_next_key_byte: SAFETY_MULTIPLIER * 0,
-            storage_iter_next_value_byte: SAFETY_MULTIPLIER * 0,
+            storage_iter_create_prefix_base: SAFETY_MULTIPLIER * 18785615250,
+            storage_iter_create_prefix_byte: SAFETY_MULTIPLIER * 10317511,
+            storage_iter_create_range_base: SAFETY_MULTIPLIER * 18785615250,
+            storage_iter_create_from_byte: SAFETY_MULTIPLIER * 10317511,
+            storage_iter_create_to_byte: SAFETY_MULTIPLIER * 10317511,
+            storage_iter_next_base: SAFETY_MULTIPLIER * 18785615250,
+            storage_iter_next_key_byte: SAFETY_MULTIPLIER * 10317511,
+            storage_iter_next_value_byte: SAFETY_MULTIPLIER * 1870335,
             touching_trie_node: SAFETY_MULTIPLIER * 5367318642,
             read_cached_trie_node: default_read_cached_trie_node(),
             promise_and_base: SAFETY_MULTIPLIER * 488337800,
@@ -492,6 +736,30 @@ impl ExtCostsConfig {
             alt_bn128_pairing_check_element: 5_102_000_000_000,
             alt_bn128_g1_sum_base: 3_000_000_000,
             alt_bn128_g1_sum_element: 5_000_000_000,
+            verify_ics23_proof_base: 20_000_000_000,
+            verify_ics23_proof_byte: 30_000_000,
+            verify_ics23_proof_inner_op: 5_000_000_000,
+            verify_near_state_proof_base: 10_000_000_000,
+            verify_near_state_proof_byte: 30_000_000,
+            verify_near_state_proof_node: 5_000_000_000,
+            verify_outcome_proof_base: 5_000_000_000,
+            verify_outcome_proof_item: 6_000_000_000,
+            validate_light_client_block_base: 20_000_000_000,
+            validate_light_client_block_byte: 30_000_000,
+            validate_light_client_block_signature: 210_000_000_000,
+            verify_mmr_proof_base: 5_000_000_000,
+            verify_mmr_proof_byte: 21_471_105,
+            verify_mmr_proof_item: 7_500_000_000,
+            verify_beefy_commitment_base: 5_000_000_000,
+            verify_beefy_commitment_byte: 21_471_105,
+            verify_beefy_commitment_signature: 3_365_369_625_000,
+            storage_read_proof_byte: 30_000_000,
+            block_hash_base: 911_834_726_400,
+            event_base: 3_543_313_050,
+            event_byte: 13_198_791,
+            verify_tendermint_commit_base: 5_000_000_000,
+            verify_tendermint_commit_byte: 30_000_000,
+            verify_tendermint_commit_signature: 210_611_100_000,
         }
     }
 
@@ -558,6 +826,30 @@ impl ExtCostsConfig {
             alt_bn128_pairing_check_element: 0,
             alt_bn128_g1_sum_base: 0,
             alt_bn128_g1_sum_element: 0,
+            verify_ics23_proof_base: 0,
+            verify_ics23_proof_byte: 0,
+            verify_ics23_proof_inner_op: 0,
+            verify_near_state_proof_base: 0,
+            verify_near_state_proof_byte: 0,
+            verify_near_state_proof_node: 0,
+            verify_outcome_proof_base: 0,
+            verify_outcome_proof_item: 0,
+            validate_light_client_block_base: 0,
+            validate_light_client_block_byte: 0,
+            validate_light_client_block_signature: 0,
+            verify_mmr_proof_base: 0,
+            verify_mmr_proof_byte: 0,
+            verify_mmr_proof_item: 0,
+            verify_beefy_commitment_base: 0,
+            verify_beefy_commitment_byte: 0,
+            verify_beefy_commitment_signature: 0,
+            storage_read_proof_byte: 0,
+            block_hash_base: 0,
+            event_base: 0,
+            event_byte: 0,
+            verify_tendermint_commit_base: 0,
+            verify_tendermint_commit_byte: 0,
+            verify_tendermint_commit_signature: 0,
         }
     }
 }
@@ -625,6 +917,30 @@ pub enum ExtCosts {
     alt_bn128_pairing_check_element,
     alt_bn128_g1_sum_base,
     alt_bn128_g1_sum_element,
+    verify_ics23_proof_base,
+    verify_ics23_proof_byte,
+    verify_ics23_proof_inner_op,
+    verify_near_state_proof_base,
+    verify_near_state_proof_byte,
+    verify_near_state_proof_node,
+    verify_outcome_proof_base,
+    verify_outcome_proof_item,
+    validate_light_client_block_base,
+    validate_light_client_block_byte,
+    validate_light_client_block_signature,
+    verify_mmr_proof_base,
+    verify_mmr_proof_byte,
+    verify_mmr_proof_item,
+    verify_beefy_commitment_base,
+    verify_beefy_commitment_byte,
+    verify_beefy_commitment_signature,
+    storage_read_proof_byte,
+    block_hash_base,
+    event_base,
+    event_byte,
+    verify_tendermint_commit_base,
+    verify_tendermint_commit_byte,
+    verify_tendermint_commit_signature,
 }
 
 // Type of an action, used in fees logic.
@@ -706,6 +1022,30 @@ impl ExtCosts {
             alt_bn128_pairing_check_element => config.alt_bn128_pairing_check_element,
             alt_bn128_g1_sum_base => config.alt_bn128_g1_sum_base,
             alt_bn128_g1_sum_element => config.alt_bn128_g1_sum_element,
+            verify_ics23_proof_base => config.verify_ics23_proof_base,
+            verify_ics23_proof_byte => config.verify_ics23_proof_byte,
+            verify_ics23_proof_inner_op => config.verify_ics23_proof_inner_op,
+            verify_near_state_proof_base => config.verify_near_state_proof_base,
+            verify_near_state_proof_byte => config.verify_near_state_proof_byte,
+            verify_near_state_proof_node => config.verify_near_state_proof_node,
+            verify_outcome_proof_base => config.verify_outcome_proof_base,
+            verify_outcome_proof_item => config.verify_outcome_proof_item,
+            validate_light_client_block_base => config.validate_light_client_block_base,
+            validate_light_client_block_byte => config.validate_light_client_block_byte,
+            validate_light_client_block_signature => config.validate_light_client_block_signature,
+            verify_mmr_proof_base => config.verify_mmr_proof_base,
+            verify_mmr_proof_byte => config.verify_mmr_proof_byte,
+            verify_mmr_proof_item => config.verify_mmr_proof_item,
+            verify_beefy_commitment_base => config.verify_beefy_commitment_base,
+            verify_beefy_commitment_byte => config.verify_beefy_commitment_byte,
+            verify_beefy_commitment_signature => config.verify_beefy_commitment_signature,
+            storage_read_proof_byte => config.storage_read_proof_byte,
+            block_hash_base => config.block_hash_base,
+            event_base => config.event_base,
+            event_byte => config.event_byte,
+            verify_tendermint_commit_base => config.verify_tendermint_commit_base,
+            verify_tendermint_commit_byte => config.verify_tendermint_commit_byte,
+            verify_tendermint_commit_signature => config.verify_tendermint_commit_signature,
         }
     }
 }
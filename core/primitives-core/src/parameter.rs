@@ -143,6 +143,30 @@ pub enum Parameter {
     WasmAltBn128PairingCheckElement,
     WasmAltBn128G1SumBase,
     WasmAltBn128G1SumElement,
+    WasmVerifyIcs23ProofBase,
+    WasmVerifyIcs23ProofByte,
+    WasmVerifyIcs23ProofInnerOp,
+    WasmVerifyNearStateProofBase,
+    WasmVerifyNearStateProofByte,
+    WasmVerifyNearStateProofNode,
+    WasmVerifyOutcomeProofBase,
+    WasmVerifyOutcomeProofItem,
+    WasmValidateLightClientBlockBase,
+    WasmValidateLightClientBlockByte,
+    WasmValidateLightClientBlockSignature,
+    WasmVerifyMmrProofBase,
+    WasmVerifyMmrProofByte,
+    WasmVerifyMmrProofItem,
+    WasmVerifyBeefyCommitmentBase,
+    WasmVerifyBeefyCommitmentByte,
+    WasmVerifyBeefyCommitmentSignature,
+    WasmStorageReadProofByte,
+    WasmBlockHashBase,
+    WasmEventBase,
+    WasmEventByte,
+    WasmVerifyTendermintCommitBase,
+    WasmVerifyTendermintCommitByte,
+    WasmVerifyTendermintCommitSignature,
 
     // Smart contract limits
     MaxGasBurnt,
@@ -172,6 +196,16 @@ pub enum Parameter {
     Wasmer2StackLimit,
     MaxLocalsPerContract,
     AccountIdValidityRulesVersion,
+    MaxIcs23ProofSize,
+    MaxStateProofSize,
+    MaxNumberStateProofNodes,
+    MaxOutcomeProofLength,
+    AllowStorageProofsInTransactions,
+    MaxBlockHashLookback,
+    MaxNumberEvents,
+    MaxTotalEventLength,
+    MaxNumberTendermintValidators,
+    ForceEcrecoverMalleabilityCheck,
 }
 
 #[derive(
@@ -271,6 +305,70 @@ impl Parameter {
             Parameter::WasmAltBn128PairingCheckElement,
             Parameter::WasmAltBn128G1SumBase,
             Parameter::WasmAltBn128G1SumElement,
+            Parameter::WasmVerifyIcs23ProofBase,
+            Parameter::WasmVerifyIcs23ProofByte,
+            Parameter::WasmVerifyIcs23ProofInnerOp,
+            Parameter::WasmVerifyNearStateProofBase,
+            Parameter::WasmVerifyNearStateProofByte,
+            Parameter::WasmVerifyNearStateProofNode,
+            Parameter::WasmVerifyOutcomeProofBase,
+            Parameter::WasmVerifyOutcomeProofItem,
+            Parameter::WasmValidateLightClientBlockBase,
+            Parameter::WasmValidateLightClientBlockByte,
+            Parameter::WasmValidateLightClientBlockSignature,
+            Parameter::WasmVerifyMmrProofBase,
+            Parameter::WasmVerifyMmrProofByte,
+            Parameter::WasmVerifyMmrProofItem,
+            Parameter::WasmVerifyBeefyCommitmentBase,
+            Parameter::WasmVerifyBeefyCommitmentByte,
+            Parameter::WasmVerifyBeefyCommitmentSignature,
+            Parameter::WasmStorageReadProofByte,
+            Parameter::WasmBlockHashBase,
+            Parameter::WasmEventBase,
+            Parameter::WasmEventByte,
+            Parameter::WasmVerifyTendermintCommitBase,
+            Parameter::WasmVerifyTendermintCommitByte,
+            Parameter::WasmVerifyTendermintCommitSignature,
+        ]
+        .iter()
+    }
+
+    /// Iterate through the external gas costs priced by this fork rather than
+    /// upstream, which can be overridden by a fork parameter overlay.
+    pub fn fork_ext_costs() -> slice::Iter<'static, Parameter> {
+        [
+            Parameter::WasmStorageIterCreatePrefixBase,
+            Parameter::WasmStorageIterCreatePrefixByte,
+            Parameter::WasmStorageIterCreateRangeBase,
+            Parameter::WasmStorageIterCreateFromByte,
+            Parameter::WasmStorageIterCreateToByte,
+            Parameter::WasmStorageIterNextBase,
+            Parameter::WasmStorageIterNextKeyByte,
+            Parameter::WasmStorageIterNextValueByte,
+            Parameter::WasmVerifyIcs23ProofBase,
+            Parameter::WasmVerifyIcs23ProofByte,
+            Parameter::WasmVerifyIcs23ProofInnerOp,
+            Parameter::WasmVerifyNearStateProofBase,
+            Parameter::WasmVerifyNearStateProofByte,
+            Parameter::WasmVerifyNearStateProofNode,
+            Parameter::WasmVerifyOutcomeProofBase,
+            Parameter::WasmVerifyOutcomeProofItem,
+            Parameter::WasmValidateLightClientBlockBase,
+            Parameter::WasmValidateLightClientBlockByte,
+            Parameter::WasmValidateLightClientBlockSignature,
+            Parameter::WasmVerifyMmrProofBase,
+            Parameter::WasmVerifyMmrProofByte,
+            Parameter::WasmVerifyMmrProofItem,
+            Parameter::WasmVerifyBeefyCommitmentBase,
+            Parameter::WasmVerifyBeefyCommitmentByte,
+            Parameter::WasmVerifyBeefyCommitmentSignature,
+            Parameter::WasmStorageReadProofByte,
+            Parameter::WasmBlockHashBase,
+            Parameter::WasmEventBase,
+            Parameter::WasmEventByte,
+            Parameter::WasmVerifyTendermintCommitBase,
+            Parameter::WasmVerifyTendermintCommitByte,
+            Parameter::WasmVerifyTendermintCommitSignature,
         ]
         .iter()
     }
@@ -305,6 +403,16 @@ impl Parameter {
             Parameter::Wasmer2StackLimit,
             Parameter::MaxLocalsPerContract,
             Parameter::AccountIdValidityRulesVersion,
+            Parameter::MaxIcs23ProofSize,
+            Parameter::MaxStateProofSize,
+            Parameter::MaxNumberStateProofNodes,
+            Parameter::MaxOutcomeProofLength,
+            Parameter::AllowStorageProofsInTransactions,
+            Parameter::MaxBlockHashLookback,
+            Parameter::MaxNumberEvents,
+            Parameter::MaxTotalEventLength,
+            Parameter::MaxNumberTendermintValidators,
+            Parameter::ForceEcrecoverMalleabilityCheck,
         ]
         .iter()
     }
@@ -252,6 +252,30 @@ impl Cost {
         Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_pairing_check_element },
         Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_base },
         Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_element },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_ics23_proof_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_ics23_proof_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_ics23_proof_inner_op },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_near_state_proof_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_near_state_proof_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_near_state_proof_node },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_outcome_proof_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_outcome_proof_item },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::validate_light_client_block_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::validate_light_client_block_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::validate_light_client_block_signature },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_mmr_proof_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_mmr_proof_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_mmr_proof_item },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_beefy_commitment_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_beefy_commitment_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_beefy_commitment_signature },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::storage_read_proof_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::block_hash_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::event_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::event_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_tendermint_commit_base },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_tendermint_commit_byte },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::verify_tendermint_commit_signature },
     ];
 
     pub fn index(self) -> usize {
@@ -326,6 +350,30 @@ impl Cost {
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_pairing_check_element } => 67,
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_base } => 68,
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_element } => 69,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_ics23_proof_base } => 70,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_ics23_proof_byte } => 71,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_ics23_proof_inner_op } => 72,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_near_state_proof_base } => 73,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_near_state_proof_byte } => 74,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_near_state_proof_node } => 75,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_outcome_proof_base } => 76,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_outcome_proof_item } => 77,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::validate_light_client_block_base } => 78,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::validate_light_client_block_byte } => 79,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::validate_light_client_block_signature } => 80,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_mmr_proof_base } => 81,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_mmr_proof_byte } => 82,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_mmr_proof_item } => 83,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_beefy_commitment_base } => 84,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_beefy_commitment_byte } => 85,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_beefy_commitment_signature } => 86,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::storage_read_proof_byte } => 87,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::block_hash_base } => 88,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::event_base } => 89,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::event_byte } => 90,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_tendermint_commit_base } => 91,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_tendermint_commit_byte } => 92,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::verify_tendermint_commit_signature } => 93,
         }
     }
 }
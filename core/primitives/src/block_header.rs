@@ -343,6 +343,22 @@ impl BlockHeader {
         combine_hash(&hash_inner, &prev_hash)
     }
 
+    /// Commitment to the block producers of an epoch, which the blocks of the previous epoch
+    /// carry as `next_bp_hash`. `block_producers` are the unique block producers of the epoch in
+    /// the order of the settlement, and `protocol_version` is the one of the previous epoch.
+    pub fn compute_bp_hash(
+        block_producers: Vec<ValidatorStake>,
+        protocol_version: ProtocolVersion,
+    ) -> CryptoHash {
+        if crate::checked_feature!("stable", BlockHeaderV3, protocol_version) {
+            CryptoHash::hash_borsh(&block_producers)
+        } else {
+            let block_producers: Vec<ValidatorStakeV1> =
+                block_producers.into_iter().map(ValidatorStake::into_v1).collect();
+            CryptoHash::hash_borsh(&block_producers)
+        }
+    }
+
     pub fn new(
         this_epoch_protocol_version: ProtocolVersion,
         next_epoch_protocol_version: ProtocolVersion,
@@ -1,5 +1,5 @@
 use crate::serialize::u128_dec_format;
-use crate::types::{AccountId, Balance, EpochId, Gas, Nonce};
+use crate::types::{AccountId, Balance, EpochId, Gas, Nonce, ProtocolVersion};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -344,6 +344,7 @@ pub enum ContractCallError {
     MethodResolveError(MethodResolveError),
     CompilationError(CompilationError),
     ExecutionError { msg: String },
+    UnsupportedHostFunction { name: String, protocol_version: ProtocolVersion },
 }
 
 impl From<ContractCallError> for FunctionCallErrorSer {
@@ -352,6 +353,9 @@ impl From<ContractCallError> for FunctionCallErrorSer {
             ContractCallError::CompilationError(e) => FunctionCallErrorSer::CompilationError(e),
             ContractCallError::MethodResolveError(e) => FunctionCallErrorSer::MethodResolveError(e),
             ContractCallError::ExecutionError { msg } => FunctionCallErrorSer::ExecutionError(msg),
+            ContractCallError::UnsupportedHostFunction { name, protocol_version } => {
+                FunctionCallErrorSer::UnsupportedHostFunction { name, protocol_version }
+            }
         }
     }
 }
@@ -373,6 +377,9 @@ impl From<FunctionCallErrorSer> for ContractCallError {
             FunctionCallErrorSer::HostError(e) => {
                 ContractCallError::ExecutionError { msg: format!("Host: {:?}", e) }
             }
+            FunctionCallErrorSer::UnsupportedHostFunction { name, protocol_version } => {
+                ContractCallError::UnsupportedHostFunction { name, protocol_version }
+            }
         }
     }
 }
@@ -28,6 +28,7 @@ pub mod telemetry;
 pub mod test_utils;
 pub mod time;
 pub mod transaction;
+pub mod trie_encoding;
 pub mod trie_key;
 pub mod types;
 mod upgrade_schedule;
@@ -123,8 +123,91 @@ pub fn compute_root_from_path_and_item<T: BorshSerialize>(
     compute_root_from_path(path, hash)
 }
 
+/// Root of the tree of `PartialMerkleTree` with the given leaves. The tree of `n > 1` leaves
+/// combines, with `combine_hash`, the root of the tree of the first `n.next_power_of_two() / 2`
+/// leaves with the root of the tree of the rest. The root of a single leaf is the leaf itself
+/// and the root of no leaves is `CryptoHash::default()`.
+fn compute_root(leaves: &[MerkleHash]) -> MerkleHash {
+    match leaves {
+        [] => CryptoHash::default(),
+        [leaf] => *leaf,
+        _ => {
+            let split = leaves.len().next_power_of_two() / 2;
+            combine_hash(&compute_root(&leaves[..split]), &compute_root(&leaves[split..]))
+        }
+    }
+}
+
+/// Directions of the path of leaf `leaf_index` in the tree of `PartialMerkleTree` with `size`
+/// leaves, from the leaf up. Levels at which the node has no sibling, which happens on the right
+/// edge of the tree, are left out.
+fn path_directions(leaf_index: u64, size: u64) -> Vec<Direction> {
+    let mut directions = vec![];
+    let (mut index, mut size) = (leaf_index, size);
+    while size > 1 {
+        let split = size.next_power_of_two() / 2;
+        if index < split {
+            directions.push(Direction::Right);
+            size = split;
+        } else {
+            directions.push(Direction::Left);
+            index -= split;
+            size -= split;
+        }
+    }
+    directions.reverse();
+    directions
+}
+
+/// Path of leaf `leaf_index` in the tree of `PartialMerkleTree` with the first `size` of
+/// `leaves`, the same as the block proof the chain gives for the block with ordinal
+/// `leaf_index` in the block merkle tree of the block with ordinal `size`.
+///
+/// Panics unless `leaf_index < size <= leaves.len()`.
+pub fn compute_path(leaves: &[MerkleHash], leaf_index: u64, size: u64) -> MerklePath {
+    assert!(leaf_index < size && size <= leaves.len() as u64, "leaf index or size out of range");
+    let mut leaves = &leaves[..size as usize];
+    let mut index = leaf_index as usize;
+    let mut path = vec![];
+    while leaves.len() > 1 {
+        let split = leaves.len().next_power_of_two() / 2;
+        if index < split {
+            path.push(MerklePathItem {
+                hash: compute_root(&leaves[split..]),
+                direction: Direction::Right,
+            });
+            leaves = &leaves[..split];
+        } else {
+            path.push(MerklePathItem {
+                hash: compute_root(&leaves[..split]),
+                direction: Direction::Left,
+            });
+            leaves = &leaves[split..];
+            index -= split;
+        }
+    }
+    path.reverse();
+    path
+}
+
+/// Verifies that `item_hash` is leaf `leaf_index` of the tree of `PartialMerkleTree` with `size`
+/// leaves and root `root`, given its `path`. Unlike `verify_hash`, this checks the position of
+/// the leaf, which takes the size of the tree because the levels at which the node has no
+/// sibling are left out of paths.
+pub fn verify_path_for_index(
+    root: MerkleHash,
+    item_hash: MerkleHash,
+    path: &MerklePath,
+    leaf_index: u64,
+    size: u64,
+) -> bool {
+    leaf_index < size
+        && path.iter().map(|item| &item.direction).eq(path_directions(leaf_index, size).iter())
+        && verify_hash(root, path, item_hash)
+}
+
 /// Merkle tree that only maintains the path for the next leaf, i.e,
-/// when a new leaf is inserted, the existing `path` is its proof.
+/// when a new leaf is appended, the existing `path` is its proof.
 /// The root can be computed by folding `path` from right but is not explicitly
 /// maintained to save space.
 /// The size of the object is O(log(n)) where n is the number of leaves in the tree, i.e, `size`.
@@ -138,6 +221,7 @@ pub struct PartialMerkleTree {
 }
 
 impl PartialMerkleTree {
+    /// Root of the tree, see `compute_root` for how the leaves are combined.
     pub fn root(&self) -> MerkleHash {
         if self.path.is_empty() {
             CryptoHash::default()
@@ -151,7 +235,8 @@ impl PartialMerkleTree {
         }
     }
 
-    pub fn insert(&mut self, elem: MerkleHash) {
+    /// Appends a leaf to the tree.
+    pub fn append(&mut self, elem: MerkleHash) {
         let mut s = self.size;
         let mut node = elem;
         while s % 2 == 1 {
@@ -163,6 +248,7 @@ impl PartialMerkleTree {
         self.size += 1;
     }
 
+    /// Number of leaves in the tree, which is also the index of the next leaf.
     pub fn size(&self) -> u64 {
         self.size
     }
@@ -218,21 +304,6 @@ mod tests {
         assert_ne!(root, root2);
     }
 
-    /// Compute the merkle root of a given array.
-    fn compute_root(hashes: &[CryptoHash]) -> CryptoHash {
-        if hashes.is_empty() {
-            CryptoHash::default()
-        } else if hashes.len() == 1 {
-            hashes[0]
-        } else {
-            let len = hashes.len();
-            let subtree_len = len.next_power_of_two() / 2;
-            let left_root = compute_root(&hashes[0..subtree_len]);
-            let right_root = compute_root(&hashes[subtree_len..len]);
-            combine_hash(&left_root, &right_root)
-        }
-    }
-
     #[test]
     fn test_merkle_tree() {
         let mut tree = PartialMerkleTree::default();
@@ -241,10 +312,56 @@ mod tests {
             assert_eq!(compute_root(&hashes), tree.root());
             let cur_hash = hash(&[i]);
             hashes.push(cur_hash);
-            tree.insert(cur_hash);
+            tree.append(cur_hash);
+        }
+    }
+
+    #[test]
+    fn test_partial_merkle_tree_paths() {
+        let mut rng: StdRng = SeedableRng::seed_from_u64(2);
+        for _ in 0..20 {
+            let n = rng.gen_range(1, 100);
+            let mut tree = PartialMerkleTree::default();
+            let mut leaves = vec![];
+            for _ in 0..n {
+                // The path kept by the tree is the proof of the next leaf, top down.
+                let leaf = hash(&rng.gen::<[u8; 32]>());
+                let next_path = tree.get_path().to_vec();
+                tree.append(leaf);
+                leaves.push(leaf);
+                let size = tree.size();
+                let path = compute_path(&leaves, size - 1, size);
+                assert_eq!(path.iter().rev().map(|item| item.hash).collect::<Vec<_>>(), next_path);
+            }
+            assert_eq!(tree.size(), n);
+            let root = tree.root();
+            for (index, leaf) in leaves.iter().enumerate() {
+                let index = index as u64;
+                let path = compute_path(&leaves, index, n);
+                assert!(verify_path_for_index(root, *leaf, &path, index, n));
+                assert!(verify_hash(root, &path, *leaf));
+                assert!(!verify_path_for_index(root, hash(b"other"), &path, index, n));
+                assert!(!verify_path_for_index(root, *leaf, &path, index, n + 1));
+                if index + 1 < n {
+                    assert!(!verify_path_for_index(root, *leaf, &path, index + 1, n));
+                }
+            }
         }
     }
 
+    #[test]
+    fn test_verify_path_for_index_position() {
+        // The path of leaf 1 of two leaves is also a path for leaf 2 of three leaves, with
+        // the level without sibling left out, so the size tells the two apart.
+        let leaves = [hash(b"0"), hash(b"1")];
+        let root = compute_root(&leaves);
+        let path = compute_path(&leaves, 1, 2);
+        assert_eq!(path_directions(2, 3), vec![Direction::Left]);
+        assert!(verify_path_for_index(root, leaves[1], &path, 1, 2));
+        assert!(!verify_path_for_index(root, leaves[1], &path, 2, 3));
+        assert!(compute_path(&leaves, 0, 1).is_empty());
+    }
+
     #[test]
     fn test_combine_hash_stability() {
         let a = MerkleHash::default();
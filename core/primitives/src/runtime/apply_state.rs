@@ -20,6 +20,8 @@ pub struct ApplyState {
     pub epoch_id: EpochId,
     /// Current epoch height
     pub epoch_height: EpochHeight,
+    /// The chain id from the genesis config.
+    pub chain_id: String,
     /// Price for the gas.
     pub gas_price: Balance,
     /// The current block timestamp (number of non-leap-nanoseconds since January 1, 1970 0:00:00 UTC).
@@ -12,6 +12,7 @@ use super::parameter_table::InvalidConfigError;
 
 /// The structure that holds the parameters of the runtime, mostly economics.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct RuntimeConfig {
     /// Amount of yN per byte required to have on the account.  See
     /// <https://nomicon.io/Economics/README.html#state-stake> for details.
@@ -1,6 +1,7 @@
 use crate::runtime::config::RuntimeConfig;
-use crate::runtime::parameter_table::{ParameterTable, ParameterTableDiff};
+use crate::runtime::parameter_table::{ParameterTable, ParameterTableDiff, ParameterTableOverlay};
 use crate::types::ProtocolVersion;
+use crate::version::ProtocolFeature;
 use std::collections::BTreeMap;
 use std::ops::Bound;
 use std::sync::Arc;
@@ -27,10 +28,16 @@ static CONFIG_DIFFS: &[(ProtocolVersion, &str)] = &[
     // Increased deployment costs, increased wasmer2 stack_limit, added limiting of contract locals,
     // set read_cached_trie_node cost, decrease storage key limit
     (53, include_config!("53.txt")),
+    // Priced the re-enabled storage iterators
+    (56, include_config!("56.txt")),
     #[cfg(feature = "protocol_feature_account_id_in_function_call_permission")]
     (130, include_config!("130.txt")),
 ];
 
+/// Protocol version from which on a fork parameter overlay is applied, the
+/// version introducing the first host functions of this fork.
+pub const FORK_PROTOCOL_VERSION: ProtocolVersion = ProtocolFeature::Ics23.protocol_version();
+
 /// Testnet parameters for versions <= 29, which (incorrectly) differed from mainnet parameters
 pub static INITIAL_TESTNET_CONFIG: &str = include_config!("parameters_testnet.txt");
 
@@ -50,18 +57,46 @@ impl RuntimeConfigStore {
     /// runtime config by sequential modifications to the genesis runtime config.
     /// TODO #4775: introduce new protocol version to have the same runtime config for all chains
     pub fn new(genesis_runtime_config: Option<&RuntimeConfig>) -> Self {
+        Self::with_fork_parameters(genesis_runtime_config, None)
+    }
+
+    /// Constructs a new store like [`RuntimeConfigStore::new`], with
+    /// `fork_parameters` overriding the fork parameters of all protocol
+    /// versions starting from [`FORK_PROTOCOL_VERSION`].
+    pub fn with_fork_parameters(
+        genesis_runtime_config: Option<&RuntimeConfig>,
+        fork_parameters: Option<&ParameterTableOverlay>,
+    ) -> Self {
         let mut params: ParameterTable =
             BASE_CONFIG.parse().expect("Failed parsing base parameter file.");
 
-        let mut store = BTreeMap::new();
-        let initial_config = RuntimeConfig::new(&params).unwrap_or_else(|err| panic!("Failed generating `RuntimeConfig` from parameters for base parameter file. Error: {err}"));
-        store.insert(0, Arc::new(initial_config));
+        let mut tables = BTreeMap::new();
+        tables.insert(0, params.clone());
 
         for (protocol_version, diff_bytes) in CONFIG_DIFFS {
             let diff :ParameterTableDiff= diff_bytes.parse().unwrap_or_else(|err| panic!("Failed parsing runtime parameters diff for version {protocol_version}. Error: {err}"));
             params.apply_diff(diff).unwrap_or_else(|err| panic!("Failed applying diff to `RuntimeConfig` for version {protocol_version}. Error: {err}"));
+            tables.insert(*protocol_version, params.clone());
+        }
+
+        if let Some(overlay) = fork_parameters {
+            // The overlay takes effect at the fork version even if no diff
+            // starts there.
+            let (_, fork_params) = tables
+                .range(..=FORK_PROTOCOL_VERSION)
+                .next_back()
+                .expect("base parameters are defined for version 0");
+            let fork_params = fork_params.clone();
+            tables.entry(FORK_PROTOCOL_VERSION).or_insert(fork_params);
+            for (_, params) in tables.range_mut(FORK_PROTOCOL_VERSION..) {
+                params.apply_overlay(overlay);
+            }
+        }
+
+        let mut store = BTreeMap::new();
+        for (protocol_version, params) in tables {
             store.insert(
-                *protocol_version,
+                protocol_version,
                 Arc::new(RuntimeConfig::new(&params).unwrap_or_else(|err| panic!("Failed generating `RuntimeConfig` from parameters for version {protocol_version}. Error: {err}"))),
             );
         }
@@ -109,7 +144,11 @@ mod tests {
     use super::*;
     use crate::version::ProtocolFeature::{
         LowerDataReceiptAndEcrecoverBaseCost, LowerStorageCost, LowerStorageKeyLimit,
+        StorageIterators,
     };
+    use crate::version::PROTOCOL_VERSION;
+    use near_primitives_core::parameter::Parameter;
+    use near_primitives_core::profile::Cost;
 
     const GENESIS_PROTOCOL_VERSION: ProtocolVersion = 29;
     const RECEIPTS_DEPTH: u64 = 63;
@@ -129,6 +168,40 @@ mod tests {
         }
     }
 
+    /// Host functions must never be free on the network, a cost missing from
+    /// the parameter table would silently charge nothing. The storage
+    /// iterators are only priced from the version re-enabling them.
+    #[test]
+    fn test_ext_costs_are_priced() {
+        let store = RuntimeConfigStore::new(None);
+        for (protocol_version, config) in store.store.iter() {
+            for cost in Cost::ALL {
+                if let Cost::ExtCost { ext_cost_kind } = *cost {
+                    if *protocol_version < StorageIterators.protocol_version()
+                        && ext_cost_kind.to_string().starts_with("storage_iter_")
+                    {
+                        continue;
+                    }
+                    assert!(
+                        ext_cost_kind.value(&config.wasm_config.ext_costs) > 0,
+                        "{} is free in protocol version {}",
+                        ext_cost_kind,
+                        protocol_version
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_price_storage_iterators() {
+        let store = RuntimeConfigStore::new(None);
+        let base_cfg = store.get_config(StorageIterators.protocol_version() - 1);
+        let new_cfg = store.get_config(StorageIterators.protocol_version());
+        assert_eq!(base_cfg.wasm_config.ext_costs.storage_iter_next_base, 0);
+        assert!(new_cfg.wasm_config.ext_costs.storage_iter_next_base > 0);
+    }
+
     #[test]
     fn test_lower_storage_cost() {
         let store = RuntimeConfigStore::new(None);
@@ -258,4 +331,84 @@ mod tests {
             insta::assert_json_snapshot!(snapshot_name, store.get_config(*version));
         }
     }
+
+    /// Use snapshot testing to check the effective external costs when fork
+    /// parameters are overridden.
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_fork_parameters_json() {
+        let overlay: ParameterTableOverlay = Parameter::fork_ext_costs()
+            .enumerate()
+            .map(|(i, param)| format!("{param}: {}\n", 1_000 * (i + 1)))
+            .collect::<String>()
+            .parse()
+            .unwrap();
+        let store = RuntimeConfigStore::with_fork_parameters(None, Some(&overlay));
+        let base_store = RuntimeConfigStore::new(None);
+
+        assert_eq!(
+            store.get_config(FORK_PROTOCOL_VERSION - 1),
+            base_store.get_config(FORK_PROTOCOL_VERSION - 1)
+        );
+        let ext_costs = &store.get_config(FORK_PROTOCOL_VERSION).wasm_config.ext_costs;
+        insta::assert_json_snapshot!("fork_parameters_ext_costs.json", ext_costs);
+    }
+
+    /// Mainnet config of protocol version 53 as written before the costs and limits of this fork
+    /// were added.
+    static PRE_FORK_MAINNET_53: &str = include_str!("test_data/pre_fork_mainnet_53.json");
+
+    /// Removes from `json` the fields missing in `PRE_FORK_MAINNET_53`.
+    fn remove_fork_fields(json: &mut serde_json::Value) {
+        let pre_fork: serde_json::Value = serde_json::from_str(PRE_FORK_MAINNET_53).unwrap();
+        for config in ["ext_costs", "limit_config"] {
+            let pre_fork_fields = pre_fork["wasm_config"][config].as_object().unwrap();
+            let fields = json["wasm_config"][config].as_object_mut().unwrap();
+            fields.retain(|name, _| pre_fork_fields.contains_key(name));
+        }
+    }
+
+    #[test]
+    fn test_deserialize_pre_fork_config() {
+        let store = RuntimeConfigStore::new(None);
+        let config: RuntimeConfig = serde_json::from_str(PRE_FORK_MAINNET_53).unwrap();
+        assert_eq!(&config, store.get_config(53).as_ref());
+        // Configs are always written with all fields.
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(store.get_config(53).as_ref()).unwrap()
+        );
+    }
+
+    /// Missing fields of this fork take their values in every protocol version.
+    #[test]
+    fn test_deserialize_without_fork_fields() {
+        let store = RuntimeConfigStore::new(None);
+        for (protocol_version, config) in store.store.iter() {
+            let mut json = serde_json::to_value(config.as_ref()).unwrap();
+            remove_fork_fields(&mut json);
+            let deserialized: RuntimeConfig = serde_json::from_value(json).unwrap();
+            assert_eq!(&deserialized, config.as_ref(), "protocol version {}", protocol_version);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_current_config() {
+        let store = RuntimeConfigStore::new(None);
+        let config = store.get_config(PROTOCOL_VERSION);
+        let json = serde_json::to_string(config.as_ref()).unwrap();
+        assert_eq!(&serde_json::from_str::<RuntimeConfig>(&json).unwrap(), config.as_ref());
+    }
+
+    #[test]
+    fn test_deserialize_unknown_field() {
+        let store = RuntimeConfigStore::new(None);
+        let json = serde_json::to_value(store.get_config(PROTOCOL_VERSION).as_ref()).unwrap();
+        for config in ["ext_costs", "limit_config"] {
+            let mut json = json.clone();
+            json["wasm_config"][config]["unknown_field"] = 1.into();
+            let err = serde_json::from_value::<RuntimeConfig>(json).unwrap_err();
+            assert!(err.to_string().contains("unknown field `unknown_field`"), "{}", err);
+        }
+    }
 }
@@ -2,6 +2,7 @@ use near_primitives_core::parameter::{FeeParameter, Parameter};
 use serde_json::json;
 use std::collections::BTreeMap;
 
+#[derive(Clone)]
 pub(crate) struct ParameterTable {
     parameters: BTreeMap<Parameter, serde_json::Value>,
 }
@@ -11,10 +12,19 @@ pub(crate) struct ParameterTableDiff {
     parameters: BTreeMap<Parameter, (serde_json::Value, serde_json::Value)>,
 }
 
+/// Values overriding the fork specific parameters of `Parameter::fork_ext_costs()`.
+///
+/// Unlike diffs, an overlay has to define every fork parameter and nothing
+/// else, so that a typo can't silently leave a cost at its built-in value.
+#[derive(Clone, Debug)]
+pub struct ParameterTableOverlay {
+    parameters: BTreeMap<Parameter, serde_json::Value>,
+}
+
 /// Error returned by ParameterTable::from_txt() that parses a runtime
 /// configuration TXT file.
 #[derive(thiserror::Error, Debug)]
-pub(crate) enum InvalidConfigError {
+pub enum InvalidConfigError {
     #[error("could not parse `{1}` as a parameter")]
     UnknownParameter(#[source] strum::ParseError, String),
     #[error("could not parse `{1}` as a value")]
@@ -31,6 +41,12 @@ pub(crate) enum InvalidConfigError {
     NoOldValueExists(Parameter, String),
     #[error("expected old value `{1}` but found `{2}` for parameter `{0}` in config diff")]
     WrongOldValue(Parameter, String, String),
+    #[error("parameter `{0}` is not a fork parameter and can't be overridden")]
+    NotAForkParameter(Parameter),
+    #[error("missing value for fork parameter `{0}`")]
+    MissingForkParameter(Parameter),
+    #[error("expected a gas value for fork parameter `{0}` but found `{1}`")]
+    InvalidForkParameterValue(Parameter, String),
 }
 
 impl std::str::FromStr for ParameterTable {
@@ -103,6 +119,13 @@ impl ParameterTable {
         Ok(())
     }
 
+    /// Overrides the fork parameters with the values of `overlay`.
+    pub(crate) fn apply_overlay(&mut self, overlay: &ParameterTableOverlay) {
+        for (key, value) in &overlay.parameters {
+            self.parameters.insert(*key, value.clone());
+        }
+    }
+
     fn transaction_costs_json(&self) -> serde_json::Value {
         json!( {
             "action_receipt_creation_config": self.fee_json(FeeParameter::ActionReceiptCreation),
@@ -196,6 +219,33 @@ impl std::str::FromStr for ParameterTableDiff {
     }
 }
 
+impl std::str::FromStr for ParameterTableOverlay {
+    type Err = InvalidConfigError;
+    fn from_str(arg: &str) -> Result<ParameterTableOverlay, InvalidConfigError> {
+        let parameters = txt_to_key_values(arg)
+            .map(|result| {
+                let (typed_key, value) = result?;
+                if !Parameter::fork_ext_costs().any(|param| *param == typed_key) {
+                    return Err(InvalidConfigError::NotAForkParameter(typed_key));
+                }
+                match parse_parameter_txt_value(value.trim())? {
+                    value @ serde_json::Value::Number(_) => Ok((typed_key, value)),
+                    _ => Err(InvalidConfigError::InvalidForkParameterValue(
+                        typed_key,
+                        value.trim().to_owned(),
+                    )),
+                }
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        if let Some(missing) =
+            Parameter::fork_ext_costs().find(|param| !parameters.contains_key(param))
+        {
+            return Err(InvalidConfigError::MissingForkParameter(*missing));
+        }
+        Ok(ParameterTableOverlay { parameters })
+    }
+}
+
 fn txt_to_key_values(
     arg: &str,
 ) -> impl Iterator<Item = Result<(Parameter, &str), InvalidConfigError>> {
@@ -245,6 +295,8 @@ fn parse_parameter_txt_value(value: &str) -> Result<serde_json::Value, InvalidCo
         } else {
             Ok(serde_json::Value::String(raw_number))
         }
+    } else if let Ok(flag) = value.parse::<bool>() {
+        Ok(serde_json::Value::Bool(flag))
     } else {
         Ok(serde_json::Value::String(value.to_owned()))
     }
@@ -252,7 +304,7 @@ fn parse_parameter_txt_value(value: &str) -> Result<serde_json::Value, InvalidCo
 
 #[cfg(test)]
 mod tests {
-    use super::{InvalidConfigError, ParameterTable, ParameterTableDiff};
+    use super::{InvalidConfigError, ParameterTable, ParameterTableDiff, ParameterTableOverlay};
     use assert_matches::assert_matches;
     use near_primitives_core::parameter::Parameter;
     use std::collections::BTreeMap;
@@ -337,6 +389,11 @@ wasm_regular_op_cost: 3_856_371 -> 0
 max_memory_pages: 512
 "#;
 
+    /// An overlay setting every fork parameter to 1_000.
+    fn fork_overlay() -> String {
+        Parameter::fork_ext_costs().map(|param| format!("{param}: 1_000\n")).collect()
+    }
+
     // Tests synthetic small example configurations. For tests with "real"
     // input data, we already have
     // `test_old_and_new_runtime_config_format_match` in `configs_store.rs`.
@@ -430,6 +487,15 @@ max_memory_pages: 512
         );
     }
 
+    #[test]
+    fn test_parameter_table_with_bool_value() {
+        check_parameter_table(
+            "allow_storage_proofs_in_transactions: false",
+            &["allow_storage_proofs_in_transactions: false -> true"],
+            [(Parameter::AllowStorageProofsInTransactions, "true")],
+        );
+    }
+
     #[test]
     fn test_parameter_table_invalid_key() {
         // Key that is not a `Parameter`
@@ -525,4 +591,55 @@ max_memory_pages: 512
             }
         );
     }
+
+    #[test]
+    fn test_parameter_table_with_overlay() {
+        let mut params: ParameterTable =
+            "wasm_regular_op_cost: 100\nwasm_event_base: 3_543_313_050".parse().unwrap();
+        let overlay: ParameterTableOverlay = fork_overlay().parse().unwrap();
+        params.apply_overlay(&overlay);
+
+        assert_eq!(params.parameters[&Parameter::WasmRegularOpCost], 100);
+        for param in Parameter::fork_ext_costs() {
+            assert_eq!(params.parameters[param], 1_000);
+        }
+    }
+
+    #[test]
+    fn test_parameter_table_overlay_invalid_key() {
+        let overlay = format!("{}invalid_key: 100", fork_overlay());
+        assert_matches!(
+            overlay.parse::<ParameterTableOverlay>(),
+            Err(InvalidConfigError::UnknownParameter(_, _))
+        );
+    }
+
+    #[test]
+    fn test_parameter_table_overlay_not_a_fork_parameter() {
+        let overlay = format!("{}wasm_regular_op_cost: 100", fork_overlay());
+        assert_matches!(
+            overlay.parse::<ParameterTableOverlay>(),
+            Err(InvalidConfigError::NotAForkParameter(Parameter::WasmRegularOpCost))
+        );
+    }
+
+    #[test]
+    fn test_parameter_table_overlay_missing_parameter() {
+        let overlay = fork_overlay().replace("wasm_event_byte: 1_000\n", "");
+        assert_matches!(
+            overlay.parse::<ParameterTableOverlay>(),
+            Err(InvalidConfigError::MissingForkParameter(Parameter::WasmEventByte))
+        );
+    }
+
+    #[test]
+    fn test_parameter_table_overlay_invalid_value() {
+        let overlay = fork_overlay().replace("wasm_event_byte: 1_000", "wasm_event_byte: lots");
+        assert_matches!(
+            overlay.parse::<ParameterTableOverlay>(),
+            Err(InvalidConfigError::InvalidForkParameterValue(Parameter::WasmEventByte, value)) => {
+                assert_eq!(value, "lots");
+            }
+        );
+    }
 }
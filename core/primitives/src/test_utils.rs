@@ -356,7 +356,7 @@ impl Block {
         signer: &dyn ValidatorSigner,
         block_merkle_tree: &mut PartialMerkleTree,
     ) -> Self {
-        block_merkle_tree.insert(*prev.hash());
+        block_merkle_tree.append(*prev.hash());
         Self::empty_with_approvals(
             prev,
             height,
@@ -460,11 +460,13 @@ impl Block {
 #[derive(Default)]
 pub struct MockEpochInfoProvider {
     pub validators: HashMap<AccountId, Balance>,
+    pub validator_public_keys: HashMap<AccountId, PublicKey>,
+    pub block_hashes: HashMap<BlockHeight, CryptoHash>,
 }
 
 impl MockEpochInfoProvider {
     pub fn new(validators: impl Iterator<Item = (AccountId, Balance)>) -> Self {
-        MockEpochInfoProvider { validators: validators.collect() }
+        MockEpochInfoProvider { validators: validators.collect(), ..Default::default() }
     }
 }
 
@@ -478,6 +480,15 @@ impl EpochInfoProvider for MockEpochInfoProvider {
         Ok(self.validators.get(account_id).cloned())
     }
 
+    fn validator_public_key(
+        &self,
+        _epoch_id: &EpochId,
+        _last_block_hash: &CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<Option<PublicKey>, EpochError> {
+        Ok(self.validator_public_keys.get(account_id).cloned())
+    }
+
     fn validator_total_stake(
         &self,
         _epoch_id: &EpochId,
@@ -489,6 +500,14 @@ impl EpochInfoProvider for MockEpochInfoProvider {
     fn minimum_stake(&self, _prev_block_hash: &CryptoHash) -> Result<Balance, EpochError> {
         Ok(0)
     }
+
+    fn block_hash_by_height(
+        &self,
+        _last_block_hash: &CryptoHash,
+        height: BlockHeight,
+    ) -> Result<Option<CryptoHash>, EpochError> {
+        Ok(self.block_hashes.get(&height).cloned())
+    }
 }
 
 impl FinalExecutionStatus {
@@ -373,6 +373,17 @@ pub struct ExecutionOutcome {
     pub status: ExecutionStatus,
     /// Execution metadata, versioned
     pub metadata: ExecutionMetadata,
+    /// Events emitted by this receipt.
+    pub events: Vec<Event>,
+}
+
+/// Event emitted by a contract through the `emit_event` host function.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug)]
+pub struct Event {
+    /// The standard the event follows.
+    pub standard: String,
+    /// Opaque event data.
+    pub data: Vec<u8>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug)]
@@ -398,6 +409,9 @@ impl ExecutionOutcome {
         for log in self.logs.iter() {
             result.push(hash(log.as_bytes()));
         }
+        for event in self.events.iter() {
+            result.push(hash(&event.try_to_vec().expect("Failed to serialize")));
+        }
         result
     }
 }
@@ -411,6 +425,7 @@ impl fmt::Debug for ExecutionOutcome {
             .field("tokens_burnt", &self.tokens_burnt)
             .field("status", &self.status)
             .field("metadata", &self.metadata)
+            .field("events", &self.events)
             .finish()
     }
 }
@@ -552,8 +567,28 @@ mod tests {
             tokens_burnt: 1234000,
             executor_id: "alice".parse().unwrap(),
             metadata: ExecutionMetadata::V1,
+            events: vec![],
+        };
+        let hashes = outcome.to_hashes();
+        assert_eq!(hashes.len(), 3);
+    }
+
+    #[test]
+    fn test_outcome_with_events_to_hashes() {
+        let event = Event { standard: "nep171".to_string(), data: vec![1, 2, 3] };
+        let outcome = ExecutionOutcome {
+            status: ExecutionStatus::SuccessValue(vec![123]),
+            logs: vec!["123".to_string()],
+            receipt_ids: vec![],
+            gas_burnt: 123,
+            tokens_burnt: 1234000,
+            executor_id: "alice".parse().unwrap(),
+            metadata: ExecutionMetadata::V1,
+            events: vec![event.clone()],
         };
         let hashes = outcome.to_hashes();
         assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[1], hash(b"123"));
+        assert_eq!(hashes[2], hash(&event.try_to_vec().unwrap()));
     }
 }
@@ -0,0 +1,462 @@
+//! Byte encoding of trie nodes and of the nibble paths they store.
+//!
+//! A node is referenced from its parent, and the trie from its state root,
+//! by `node_hash` of its `RawTrieNodeWithSize` encoding. A value is
+//! referenced by its length and `node_hash` of its bytes. The encodings are
+//! part of the protocol, since the state root depends on them.
+//!
+//! Trie keys are split into nibbles, most significant first, and nodes
+//! store the nibbles of their part of the path as described in
+//! `encode_nibbles`.
+//!
+//! This is the only decoder of trie nodes: the trie itself, the state proof
+//! verifiers of `near-store` and the `verify_near_state_proof` host function
+//! all use it, so they can't disagree on what a proof proves.
+
+use std::io::{Cursor, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::hash::{hash, CryptoHash};
+use crate::views::{TrieNodeKindView, TrieNodeView, TrieValueRefView};
+
+/// A trie node as stored, with its children and value referenced by hash.
+///
+/// The encoding starts with a one-byte tag, followed by little-endian
+/// fields:
+/// - `Leaf(key, value_length, value_hash)`: `0`, `key.len(): u32`, `key`,
+///   `value_length: u32`, `value_hash: [u8; 32]`;
+/// - `Branch(children, None)`: `1`, `bitmap: u16`, then the hash of each
+///   child present, bit `i` of `bitmap` telling whether `children[i]` is;
+/// - `Branch(children, Some((value_length, value_hash)))`: `2`,
+///   `value_length: u32`, `value_hash: [u8; 32]`, then the children as
+///   above;
+/// - `Extension(key, child)`: `3`, `key.len(): u32`, `key`,
+///   `child: [u8; 32]`.
+///
+/// The keys of leaves and extensions are nibble paths encoded with
+/// `encode_nibbles`.
+#[derive(Debug, Eq, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum RawTrieNode {
+    Leaf(Vec<u8>, u32, CryptoHash),
+    Branch([Option<CryptoHash>; 16], Option<(u32, CryptoHash)>),
+    Extension(Vec<u8>, CryptoHash),
+}
+
+/// Trie node + memory cost of its subtree
+/// memory_usage is serialized, stored, and contributes to hash
+///
+/// Encoded as the `RawTrieNode` encoding of `node` followed by
+/// `memory_usage: u64` in little endian. This is what trie nodes are
+/// stored and hashed as.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RawTrieNodeWithSize {
+    pub node: RawTrieNode,
+    pub memory_usage: u64,
+}
+
+const LEAF_NODE: u8 = 0;
+const BRANCH_NODE_NO_VALUE: u8 = 1;
+const BRANCH_NODE_WITH_VALUE: u8 = 2;
+const EXTENSION_NODE: u8 = 3;
+
+fn invalid_data(msg: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+fn decode_hash(cursor: &mut Cursor<&[u8]>) -> Result<CryptoHash, std::io::Error> {
+    let mut arr = [0; 32];
+    cursor.read_exact(&mut arr)?;
+    Ok(CryptoHash(arr))
+}
+
+/// Reads a length-prefixed key. The length is checked against the remaining
+/// bytes before allocating, since nodes may come from untrusted proofs.
+fn decode_key(cursor: &mut Cursor<&[u8]>) -> Result<Vec<u8>, std::io::Error> {
+    let key_length = cursor.read_u32::<LittleEndian>()? as usize;
+    let remaining = cursor.get_ref().len().saturating_sub(cursor.position() as usize);
+    if key_length > remaining {
+        return Err(invalid_data("Key length exceeds node length"));
+    }
+    let mut key = vec![0; key_length];
+    cursor.read_exact(&mut key)?;
+    Ok(key)
+}
+
+fn decode_children(cursor: &mut Cursor<&[u8]>) -> Result<[Option<CryptoHash>; 16], std::io::Error> {
+    let mut children: [Option<CryptoHash>; 16] = Default::default();
+    let bitmap = cursor.read_u16::<LittleEndian>()?;
+    let mut pos = 1;
+    for child in &mut children {
+        if bitmap & pos != 0 {
+            *child = Some(decode_hash(cursor)?);
+        }
+        pos <<= 1;
+    }
+    Ok(children)
+}
+
+impl RawTrieNode {
+    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), std::io::Error> {
+        let mut cursor = Cursor::new(out);
+        // size in state_parts = size + 8 for RawTrieNodeWithSize + 8 for borsh vector length
+        match &self {
+            // size <= 1 + 4 + 4 + 32 + key_length + value_length
+            RawTrieNode::Leaf(key, value_length, value_hash) => {
+                cursor.write_u8(LEAF_NODE)?;
+                cursor.write_u32::<LittleEndian>(key.len() as u32)?;
+                cursor.write_all(key)?;
+                cursor.write_u32::<LittleEndian>(*value_length)?;
+                cursor.write_all(value_hash.as_ref())?;
+            }
+            // size <= 1 + 4 + 32 + value_length + 2 + 32 * num_children
+            RawTrieNode::Branch(children, value) => {
+                if let Some((value_length, value_hash)) = value {
+                    cursor.write_u8(BRANCH_NODE_WITH_VALUE)?;
+                    cursor.write_u32::<LittleEndian>(*value_length)?;
+                    cursor.write_all(value_hash.as_ref())?;
+                } else {
+                    cursor.write_u8(BRANCH_NODE_NO_VALUE)?;
+                }
+                let mut bitmap: u16 = 0;
+                let mut pos: u16 = 1;
+                for child in children.iter() {
+                    if child.is_some() {
+                        bitmap |= pos
+                    }
+                    pos <<= 1;
+                }
+                cursor.write_u16::<LittleEndian>(bitmap)?;
+                for child in children.iter() {
+                    if let Some(hash) = child {
+                        cursor.write_all(hash.as_ref())?;
+                    }
+                }
+            }
+            // size <= 1 + 4 + key_length + 32
+            RawTrieNode::Extension(key, child) => {
+                cursor.write_u8(EXTENSION_NODE)?;
+                cursor.write_u32::<LittleEndian>(key.len() as u32)?;
+                cursor.write_all(key)?;
+                cursor.write_all(child.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, std::io::Error> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out)?;
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, std::io::Error> {
+        let mut cursor = Cursor::new(bytes);
+        match cursor.read_u8()? {
+            LEAF_NODE => {
+                let key = decode_key(&mut cursor)?;
+                let value_length = cursor.read_u32::<LittleEndian>()?;
+                let value_hash = decode_hash(&mut cursor)?;
+                Ok(RawTrieNode::Leaf(key, value_length, value_hash))
+            }
+            BRANCH_NODE_NO_VALUE => {
+                let children = decode_children(&mut cursor)?;
+                Ok(RawTrieNode::Branch(children, None))
+            }
+            BRANCH_NODE_WITH_VALUE => {
+                let value_length = cursor.read_u32::<LittleEndian>()?;
+                let value_hash = decode_hash(&mut cursor)?;
+                let children = decode_children(&mut cursor)?;
+                Ok(RawTrieNode::Branch(children, Some((value_length, value_hash))))
+            }
+            EXTENSION_NODE => {
+                let key = decode_key(&mut cursor)?;
+                let child = decode_hash(&mut cursor)?;
+                Ok(RawTrieNode::Extension(key, child))
+            }
+            _ => Err(std::io::Error::new(std::io::ErrorKind::Other, "Wrong type")),
+        }
+    }
+}
+
+impl RawTrieNodeWithSize {
+    pub fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), std::io::Error> {
+        self.node.encode_into(out)?;
+        out.write_u64::<LittleEndian>(self.memory_usage)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, std::io::Error> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out)?;
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, std::io::Error> {
+        if bytes.len() < 8 {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "Wrong type"));
+        }
+        let node = RawTrieNode::decode(&bytes[0..bytes.len() - 8])?;
+        let mut arr: [u8; 8] = Default::default();
+        arr.copy_from_slice(&bytes[bytes.len() - 8..]);
+        let memory_usage = u64::from_le_bytes(arr);
+        Ok(RawTrieNodeWithSize { node, memory_usage })
+    }
+}
+
+/// Hash by which a trie node is referenced, given its `RawTrieNodeWithSize`
+/// encoding. Values are referenced by the same hash of their bytes.
+pub fn node_hash(bytes: &[u8]) -> CryptoHash {
+    hash(bytes)
+}
+
+/// Splits `key` into nibbles, most significant first.
+pub fn key_to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Joins an even number of `nibbles` back into the key they were split from.
+pub fn nibbles_to_key(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect()
+}
+
+/// Encodes the `nibbles` of the key of a leaf, if `is_leaf`, or of an
+/// extension.
+///
+/// The first byte holds flags in its high nibble: `0x20` if `is_leaf`, and
+/// `0x10` if the number of nibbles is odd, in which case the first nibble is
+/// in its low nibble, which is zero otherwise. The other nibbles follow,
+/// two per byte, most significant first.
+pub fn encode_nibbles(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let odd = nibbles.len() % 2;
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let flags = if is_leaf { 0x20 } else { 0 };
+    encoded.push(if odd == 1 { flags | 0x10 | nibbles[0] } else { flags });
+    encoded.extend(nibbles[odd..].chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    encoded
+}
+
+/// Decodes the output of `encode_nibbles`, returning the nibbles and
+/// whether they are the key of a leaf.
+pub fn decode_nibbles(encoded: &[u8]) -> Result<(Vec<u8>, bool), std::io::Error> {
+    let first = match encoded.first() {
+        Some(first) => *first,
+        None => return Err(std::io::Error::new(std::io::ErrorKind::Other, "Empty nibbles")),
+    };
+    let offset = if first & 0x10 != 0 { 1 } else { 2 };
+    Ok((key_to_nibbles(encoded).split_off(offset), first & 0x20 != 0))
+}
+
+impl From<RawTrieNodeWithSize> for TrieNodeView {
+    fn from(node: RawTrieNodeWithSize) -> Self {
+        let nibbles = |key: &[u8]| -> String {
+            decode_nibbles(key)
+                .map(|(nibbles, _)| nibbles)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|nibble| char::from_digit(nibble as u32, 16).unwrap())
+                .collect()
+        };
+        let value = |(length, hash)| TrieValueRefView { length, hash };
+        let kind = match node.node {
+            RawTrieNode::Leaf(key, length, hash) => {
+                TrieNodeKindView::Leaf { key: nibbles(&key), value: value((length, hash)) }
+            }
+            RawTrieNode::Branch(children, branch_value) => TrieNodeKindView::Branch {
+                children: children.to_vec(),
+                value: branch_value.map(value),
+            },
+            RawTrieNode::Extension(key, child) => {
+                TrieNodeKindView::Extension { key: nibbles(&key), child }
+            }
+        };
+        TrieNodeView { memory_usage: node.memory_usage, kind }
+    }
+}
+
+/// Where the path to a key goes from a trie node, see `step`.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Step<'k> {
+    /// The path ends at the node, with the length and hash of the value of
+    /// the key if the node has one.
+    End(Option<(u32, CryptoHash)>),
+    /// The path goes on to the child with the given hash, with the rest of
+    /// the key.
+    Child(CryptoHash, &'k [u8]),
+}
+
+/// Follows the path to the nibbles of `key` through the node with the given
+/// `RawTrieNodeWithSize` encoding. Fails if the node can't be decoded.
+///
+/// Looking a key up in a set of nodes is a matter of calling `step` from the
+/// state root until it returns `Step::End`.
+pub fn step<'k>(bytes: &[u8], key: &'k [u8]) -> Result<Step<'k>, std::io::Error> {
+    Ok(match RawTrieNodeWithSize::decode(bytes)?.node {
+        RawTrieNode::Leaf(leaf_key, value_length, value_hash) => {
+            let leaf_key = decode_nibbles(&leaf_key)?.0;
+            Step::End((leaf_key == key).then(|| (value_length, value_hash)))
+        }
+        RawTrieNode::Extension(extension_key, child) => {
+            let extension_key = decode_nibbles(&extension_key)?.0;
+            match key.strip_prefix(extension_key.as_slice()) {
+                Some(rest) => Step::Child(child, rest),
+                None => Step::End(None),
+            }
+        }
+        RawTrieNode::Branch(children, value) => match key.split_first() {
+            None => Step::End(value),
+            Some((nibble, rest)) => match children[*nibble as usize] {
+                Some(child) => Step::Child(child, rest),
+                None => Step::End(None),
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn random_hash(rng: &mut impl Rng) -> CryptoHash {
+        CryptoHash(rng.gen())
+    }
+
+    fn random_nibbles(rng: &mut impl Rng, min_len: usize) -> Vec<u8> {
+        let len = rng.gen_range(min_len, 20);
+        (0..len).map(|_| rng.gen_range(0, 16)).collect()
+    }
+
+    fn random_node(rng: &mut impl Rng) -> RawTrieNode {
+        match rng.gen_range(0, 3) {
+            0 => RawTrieNode::Leaf(
+                encode_nibbles(&random_nibbles(rng, 0), true),
+                rng.gen(),
+                random_hash(rng),
+            ),
+            1 => {
+                let mut children: [Option<CryptoHash>; 16] = Default::default();
+                for child in children.iter_mut() {
+                    if rng.gen_bool(0.5) {
+                        *child = Some(random_hash(rng));
+                    }
+                }
+                let value =
+                    if rng.gen_bool(0.5) { Some((rng.gen(), random_hash(rng))) } else { None };
+                RawTrieNode::Branch(children, value)
+            }
+            _ => RawTrieNode::Extension(
+                encode_nibbles(&random_nibbles(rng, 1), false),
+                random_hash(rng),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_nibbles_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let nibbles = random_nibbles(&mut rng, 0);
+            let is_leaf = rng.gen_bool(0.5);
+            let encoded = encode_nibbles(&nibbles, is_leaf);
+            assert_eq!(encoded.len(), nibbles.len() / 2 + 1);
+            assert_eq!(decode_nibbles(&encoded).unwrap(), (nibbles.clone(), is_leaf));
+            if nibbles.len() % 2 == 0 {
+                assert_eq!(key_to_nibbles(&nibbles_to_key(&nibbles)), nibbles);
+            }
+        }
+        assert_eq!(encode_nibbles(&[1, 2, 3], true), vec![0x31, 0x23]);
+        assert_eq!(encode_nibbles(&[1, 2], false), vec![0x00, 0x12]);
+        assert_eq!(key_to_nibbles(&[0xab, 0x01]), vec![0xa, 0xb, 0x0, 0x1]);
+        assert!(decode_nibbles(&[]).is_err());
+    }
+
+    #[test]
+    fn test_node_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let node = RawTrieNodeWithSize { node: random_node(&mut rng), memory_usage: rng.gen() };
+            let bytes = node.encode().unwrap();
+            assert_eq!(RawTrieNodeWithSize::decode(&bytes).unwrap(), node);
+            assert_eq!(node_hash(&bytes), hash(&bytes));
+            // Truncated encodings don't decode.
+            let len = rng.gen_range(0, bytes.len());
+            assert!(RawTrieNodeWithSize::decode(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_oversized_key_length() {
+        // A key length past the end of the node is rejected before the key
+        // is allocated.
+        for tag in [LEAF_NODE, EXTENSION_NODE] {
+            let bytes = [&[tag][..], &u32::MAX.to_le_bytes(), &[0; 40]].concat();
+            let err = RawTrieNode::decode(&bytes).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let value_hash = hash(b"value");
+        let child = hash(b"child");
+        let encode = |node| RawTrieNodeWithSize { node, memory_usage: 0 }.encode().unwrap();
+
+        let leaf = encode(RawTrieNode::Leaf(encode_nibbles(&[1, 2], true), 5, value_hash));
+        assert_eq!(step(&leaf, &[1, 2]).unwrap(), Step::End(Some((5, value_hash))));
+        assert_eq!(step(&leaf, &[1, 3]).unwrap(), Step::End(None));
+
+        let extension = encode(RawTrieNode::Extension(encode_nibbles(&[1], false), child));
+        assert_eq!(step(&extension, &[1, 2]).unwrap(), Step::Child(child, &[2]));
+        assert_eq!(step(&extension, &[2]).unwrap(), Step::End(None));
+
+        let mut children: [Option<CryptoHash>; 16] = Default::default();
+        children[3] = Some(child);
+        let branch = encode(RawTrieNode::Branch(children, Some((5, value_hash))));
+        assert_eq!(step(&branch, &[]).unwrap(), Step::End(Some((5, value_hash))));
+        assert_eq!(step(&branch, &[3, 4]).unwrap(), Step::Child(child, &[4]));
+        assert_eq!(step(&branch, &[4]).unwrap(), Step::End(None));
+
+        assert!(step(&leaf[..leaf.len() - 1], &[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_node_layout() {
+        let value_hash = hash(b"value");
+        let child = hash(b"child");
+        let mut children: [Option<CryptoHash>; 16] = Default::default();
+        children[1] = Some(child);
+        children[15] = Some(child);
+        let nodes = [
+            (
+                RawTrieNode::Leaf(vec![0x20, 0xab], 5, value_hash),
+                [&[0, 2, 0, 0, 0, 0x20, 0xab, 5, 0, 0, 0][..], value_hash.as_ref()].concat(),
+            ),
+            (
+                RawTrieNode::Branch(children, None),
+                [&[1, 0b10, 0b1000_0000][..], child.as_ref(), child.as_ref()].concat(),
+            ),
+            (
+                RawTrieNode::Branch(children, Some((5, value_hash))),
+                [
+                    &[2, 5, 0, 0, 0][..],
+                    value_hash.as_ref(),
+                    &[0b10, 0b1000_0000],
+                    child.as_ref(),
+                    child.as_ref(),
+                ]
+                .concat(),
+            ),
+            (
+                RawTrieNode::Extension(vec![0x1a], child),
+                [&[3, 1, 0, 0, 0, 0x1a][..], child.as_ref()].concat(),
+            ),
+        ];
+        for (node, expected) in nodes {
+            let node = RawTrieNodeWithSize { node, memory_usage: 0x0102 };
+            let expected = [&expected[..], &[2, 1, 0, 0, 0, 0, 0, 0]].concat();
+            assert_eq!(node.encode().unwrap(), expected);
+        }
+    }
+}
@@ -48,6 +48,62 @@ pub(crate) mod col {
     ];
 }
 
+/// Columns of the trie keys which are scoped to an account, i.e. whose raw keys start with the
+/// column id followed by the account id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrieKeyColumn {
+    Account,
+    ContractCode,
+    AccessKey,
+    ReceivedData,
+    PostponedReceiptId,
+    PendingDataCount,
+    PostponedReceipt,
+    ContractData,
+}
+
+impl TrieKeyColumn {
+    /// All the columns scoped to an account.
+    pub const ALL: &'static [TrieKeyColumn] = &[
+        TrieKeyColumn::Account,
+        TrieKeyColumn::ContractCode,
+        TrieKeyColumn::AccessKey,
+        TrieKeyColumn::ReceivedData,
+        TrieKeyColumn::PostponedReceiptId,
+        TrieKeyColumn::PendingDataCount,
+        TrieKeyColumn::PostponedReceipt,
+        TrieKeyColumn::ContractData,
+    ];
+
+    /// Column id the raw keys of the column start with.
+    pub fn col(self) -> &'static [u8] {
+        match self {
+            TrieKeyColumn::Account => col::ACCOUNT,
+            TrieKeyColumn::ContractCode => col::CONTRACT_CODE,
+            TrieKeyColumn::AccessKey => col::ACCESS_KEY,
+            TrieKeyColumn::ReceivedData => col::RECEIVED_DATA,
+            TrieKeyColumn::PostponedReceiptId => col::POSTPONED_RECEIPT_ID,
+            TrieKeyColumn::PendingDataCount => col::PENDING_DATA_COUNT,
+            TrieKeyColumn::PostponedReceipt => col::POSTPONED_RECEIPT,
+            TrieKeyColumn::ContractData => col::CONTRACT_DATA,
+        }
+    }
+
+    /// Name of the `TrieKey` variant stored in the column.
+    pub fn name(self) -> &'static str {
+        match self {
+            TrieKeyColumn::Account => "Account",
+            TrieKeyColumn::ContractCode => "ContractCode",
+            TrieKeyColumn::AccessKey => "AccessKey",
+            TrieKeyColumn::ReceivedData => "ReceivedData",
+            TrieKeyColumn::PostponedReceiptId => "PostponedReceiptId",
+            TrieKeyColumn::PendingDataCount => "PendingDataCount",
+            TrieKeyColumn::PostponedReceipt => "PostponedReceipt",
+            TrieKeyColumn::ContractData => "ContractData",
+        }
+    }
+}
+
 /// Describes the key of a specific key-value record in a state trie.
 #[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 pub enum TrieKey {
@@ -397,6 +453,150 @@ pub mod trie_key_parsers {
         })
     }
 
+    fn parse_account_id_and_hash_from_trie_key_with_separator(
+        col: &[u8],
+        raw_key: &[u8],
+        col_name: &str,
+    ) -> Result<(AccountId, CryptoHash), std::io::Error> {
+        let account_id = parse_account_id_from_trie_key_with_separator(col, raw_key, col_name)?;
+        let prefix_len = col.len() + account_id.len() + ACCOUNT_DATA_SEPARATOR.len();
+        let hash = CryptoHash::try_from(&raw_key[prefix_len..]).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Can't parse CryptoHash for TrieKey::{}", col_name),
+            )
+        })?;
+        Ok((account_id, hash))
+    }
+
+    pub fn parse_trie_key_account_from_raw_key(raw_key: &[u8]) -> Result<TrieKey, std::io::Error> {
+        let account_id = parse_account_id_from_account_key(raw_key)?;
+        Ok(TrieKey::Account { account_id })
+    }
+
+    pub fn parse_trie_key_contract_code_from_raw_key(
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        let account_id = parse_account_id_from_contract_code_key(raw_key)?;
+        Ok(TrieKey::ContractCode { account_id })
+    }
+
+    pub fn parse_trie_key_received_data_from_raw_key(
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        let (receiver_id, data_id) = parse_account_id_and_hash_from_trie_key_with_separator(
+            col::RECEIVED_DATA,
+            raw_key,
+            "ReceivedData",
+        )?;
+        Ok(TrieKey::ReceivedData { receiver_id, data_id })
+    }
+
+    pub fn parse_trie_key_postponed_receipt_id_from_raw_key(
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        let (receiver_id, data_id) = parse_account_id_and_hash_from_trie_key_with_separator(
+            col::POSTPONED_RECEIPT_ID,
+            raw_key,
+            "PostponedReceiptId",
+        )?;
+        Ok(TrieKey::PostponedReceiptId { receiver_id, data_id })
+    }
+
+    pub fn parse_trie_key_pending_data_count_from_raw_key(
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        let (receiver_id, receipt_id) = parse_account_id_and_hash_from_trie_key_with_separator(
+            col::PENDING_DATA_COUNT,
+            raw_key,
+            "PendingDataCount",
+        )?;
+        Ok(TrieKey::PendingDataCount { receiver_id, receipt_id })
+    }
+
+    pub fn parse_trie_key_postponed_receipt_from_raw_key(
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        let (receiver_id, receipt_id) = parse_account_id_and_hash_from_trie_key_with_separator(
+            col::POSTPONED_RECEIPT,
+            raw_key,
+            "PostponedReceipt",
+        )?;
+        Ok(TrieKey::PostponedReceipt { receiver_id, receipt_id })
+    }
+
+    pub fn parse_trie_key_contract_data_from_raw_key(
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        let account_id = parse_account_id_from_contract_data_key(raw_key)?;
+        let key = parse_data_key_from_contract_data_key(raw_key, &account_id)?.to_vec();
+        Ok(TrieKey::ContractData { account_id, key })
+    }
+
+    /// Parses a raw key of the given column back into its `TrieKey`.
+    pub fn parse_trie_key_from_raw_key(
+        column: TrieKeyColumn,
+        raw_key: &[u8],
+    ) -> Result<TrieKey, std::io::Error> {
+        match column {
+            TrieKeyColumn::Account => parse_trie_key_account_from_raw_key(raw_key),
+            TrieKeyColumn::ContractCode => parse_trie_key_contract_code_from_raw_key(raw_key),
+            TrieKeyColumn::AccessKey => parse_trie_key_access_key_from_raw_key(raw_key),
+            TrieKeyColumn::ReceivedData => parse_trie_key_received_data_from_raw_key(raw_key),
+            TrieKeyColumn::PostponedReceiptId => {
+                parse_trie_key_postponed_receipt_id_from_raw_key(raw_key)
+            }
+            TrieKeyColumn::PendingDataCount => {
+                parse_trie_key_pending_data_count_from_raw_key(raw_key)
+            }
+            TrieKeyColumn::PostponedReceipt => {
+                parse_trie_key_postponed_receipt_from_raw_key(raw_key)
+            }
+            TrieKeyColumn::ContractData => parse_trie_key_contract_data_from_raw_key(raw_key),
+        }
+    }
+
+    /// Raw prefix of the keys of `account_id` in the given column.
+    ///
+    /// The `Account` and `ContractCode` keys have nothing after the account id, so their prefix
+    /// is the whole key: other keys starting with it belong to other accounts, e.g. `near.a` for
+    /// `near`. In the other columns the account id is followed by a byte which can't appear in
+    /// account ids, so all the keys starting with the prefix belong to `account_id`.
+    pub fn get_raw_prefix_for_account_column(
+        column: TrieKeyColumn,
+        account_id: &AccountId,
+    ) -> Vec<u8> {
+        match column {
+            TrieKeyColumn::Account => TrieKey::Account { account_id: account_id.clone() }.to_vec(),
+            TrieKeyColumn::ContractCode => {
+                TrieKey::ContractCode { account_id: account_id.clone() }.to_vec()
+            }
+            TrieKeyColumn::AccessKey => get_raw_prefix_for_access_keys(account_id),
+            TrieKeyColumn::ContractData => get_raw_prefix_for_contract_data(account_id, &[]),
+            TrieKeyColumn::ReceivedData
+            | TrieKeyColumn::PostponedReceiptId
+            | TrieKeyColumn::PendingDataCount
+            | TrieKeyColumn::PostponedReceipt => {
+                let col = column.col();
+                let mut res =
+                    Vec::with_capacity(col.len() + account_id.len() + ACCOUNT_DATA_SEPARATOR.len());
+                res.extend(col);
+                res.extend(account_id.as_ref().as_bytes());
+                res.extend(ACCOUNT_DATA_SEPARATOR);
+                res
+            }
+        }
+    }
+
+    /// Raw prefixes of everything stored for `account_id`, one per column, see
+    /// `get_raw_prefix_for_account_column`.
+    pub fn get_raw_prefixes_for_account(account_id: &AccountId) -> Vec<(TrieKeyColumn, Vec<u8>)> {
+        TrieKeyColumn::ALL
+            .iter()
+            .map(|column| (*column, get_raw_prefix_for_account_column(*column, account_id)))
+            .collect()
+    }
+
     pub fn get_raw_prefix_for_access_keys(account_id: &AccountId) -> Vec<u8> {
         let mut res = Vec::with_capacity(col::ACCESS_KEY.len() * 2 + account_id.len());
         res.extend(col::ACCESS_KEY);
@@ -615,6 +815,107 @@ mod tests {
         }
     }
 
+    /// Keys of `account_id` in every column scoped to an account, with hashes and data keys made
+    /// of the bytes which separate the account id from the rest of the key.
+    fn account_keys(account_id: &AccountId) -> Vec<(TrieKeyColumn, TrieKey)> {
+        let account_id = account_id.clone();
+        let hash = CryptoHash([ACCOUNT_DATA_SEPARATOR[0]; 32]);
+        let receiver_id = account_id.clone();
+        vec![
+            (TrieKeyColumn::Account, TrieKey::Account { account_id: account_id.clone() }),
+            (TrieKeyColumn::ContractCode, TrieKey::ContractCode { account_id: account_id.clone() }),
+            (
+                TrieKeyColumn::AccessKey,
+                TrieKey::AccessKey {
+                    account_id: account_id.clone(),
+                    public_key: PublicKey::empty(KeyType::ED25519),
+                },
+            ),
+            (
+                TrieKeyColumn::ReceivedData,
+                TrieKey::ReceivedData { receiver_id: receiver_id.clone(), data_id: hash },
+            ),
+            (
+                TrieKeyColumn::PostponedReceiptId,
+                TrieKey::PostponedReceiptId { receiver_id: receiver_id.clone(), data_id: hash },
+            ),
+            (
+                TrieKeyColumn::PendingDataCount,
+                TrieKey::PendingDataCount { receiver_id: receiver_id.clone(), receipt_id: hash },
+            ),
+            (
+                TrieKeyColumn::PostponedReceipt,
+                TrieKey::PostponedReceipt { receiver_id, receipt_id: hash },
+            ),
+            (
+                TrieKeyColumn::ContractData,
+                TrieKey::ContractData {
+                    account_id,
+                    key: [&ACCOUNT_DATA_SEPARATOR[..], col::ACCESS_KEY].concat(),
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_raw_prefixes_for_account() {
+        let account_ids: Vec<AccountId> =
+            OK_ACCOUNT_IDS.iter().map(|x| x.parse().unwrap()).collect();
+        for account_id in &account_ids {
+            let prefixes = trie_key_parsers::get_raw_prefixes_for_account(account_id);
+            assert_eq!(
+                prefixes.iter().map(|(column, _)| *column).collect::<Vec<_>>(),
+                TrieKeyColumn::ALL
+            );
+            for ((column, key), (_, prefix)) in account_keys(account_id).into_iter().zip(&prefixes)
+            {
+                let raw_key = key.to_vec();
+                assert!(raw_key.starts_with(prefix), "{:?}", key);
+                assert!(raw_key.starts_with(column.col()));
+                assert_eq!(
+                    trie_key_parsers::parse_trie_key_from_raw_key(column, &raw_key).unwrap(),
+                    key
+                );
+                assert_eq!(
+                    trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().unwrap(),
+                    *account_id
+                );
+            }
+            // The keys of other accounts never fall under the prefixes, even for account ids
+            // which are prefixes of one another, except for the `Account` and `ContractCode`
+            // keys which are the whole prefix.
+            for other_account_id in account_ids.iter().filter(|other| *other != account_id) {
+                for ((column, key), (_, prefix)) in
+                    account_keys(other_account_id).into_iter().zip(&prefixes)
+                {
+                    let raw_key = key.to_vec();
+                    match column {
+                        TrieKeyColumn::Account | TrieKeyColumn::ContractCode => {
+                            assert_ne!(&raw_key, prefix)
+                        }
+                        _ => assert!(!raw_key.starts_with(prefix), "{:?}", key),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_trie_key_from_raw_key_of_other_column() {
+        let account_id: AccountId = "near".parse().unwrap();
+        for (column, key) in account_keys(&account_id) {
+            let raw_key = key.to_vec();
+            for other_column in TrieKeyColumn::ALL.iter().filter(|other| **other != column) {
+                assert!(
+                    trie_key_parsers::parse_trie_key_from_raw_key(*other_column, &raw_key).is_err(),
+                    "{:?} parsed as {:?}",
+                    key,
+                    other_column
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_key_for_delayed_receipts_consistency() {
         let key = TrieKey::DelayedReceiptIndices;
@@ -247,6 +247,31 @@ impl StateChangeValue {
             | StateChangeValue::ContractCodeDeletion { account_id } => account_id,
         }
     }
+
+    /// The trie key whose value changed.
+    pub fn trie_key(&self) -> TrieKey {
+        match self {
+            StateChangeValue::AccountUpdate { account_id, .. }
+            | StateChangeValue::AccountDeletion { account_id } => {
+                TrieKey::Account { account_id: account_id.clone() }
+            }
+            StateChangeValue::AccessKeyUpdate { account_id, public_key, .. }
+            | StateChangeValue::AccessKeyDeletion { account_id, public_key } => {
+                TrieKey::AccessKey {
+                    account_id: account_id.clone(),
+                    public_key: public_key.clone(),
+                }
+            }
+            StateChangeValue::DataUpdate { account_id, key, .. }
+            | StateChangeValue::DataDeletion { account_id, key } => {
+                TrieKey::ContractData { account_id: account_id.clone(), key: key.as_ref().to_vec() }
+            }
+            StateChangeValue::ContractCodeUpdate { account_id, .. }
+            | StateChangeValue::ContractCodeDeletion { account_id } => {
+                TrieKey::ContractCode { account_id: account_id.clone() }
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -905,6 +930,15 @@ pub trait EpochInfoProvider {
         account_id: &AccountId,
     ) -> Result<Option<Balance>, EpochError>;
 
+    /// Get the public key of a validator in the given epoch.
+    /// If the account is not a validator, returns `None`.
+    fn validator_public_key(
+        &self,
+        epoch_id: &EpochId,
+        last_block_hash: &CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<Option<PublicKey>, EpochError>;
+
     /// Get the total stake of the given epoch.
     fn validator_total_stake(
         &self,
@@ -913,6 +947,14 @@ pub trait EpochInfoProvider {
     ) -> Result<Balance, EpochError>;
 
     fn minimum_stake(&self, prev_block_hash: &CryptoHash) -> Result<Balance, EpochError>;
+
+    /// Get the hash of the block at the given height on the chain ending with
+    /// `last_block_hash`. If the height was skipped, returns `None`.
+    fn block_hash_by_height(
+        &self,
+        last_block_hash: &CryptoHash,
+        height: BlockHeight,
+    ) -> Result<Option<CryptoHash>, EpochError>;
 }
 
 /// Mode of the trie cache.
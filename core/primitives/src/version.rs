@@ -20,7 +20,7 @@ pub struct Version {
 pub type DbVersion = u32;
 
 /// Current version of the database.
-pub const DB_VERSION: DbVersion = 31;
+pub const DB_VERSION: DbVersion = 32;
 
 use crate::upgrade_schedule::{get_protocol_version_internal, ProtocolUpgradeVotingSchedule};
 /// Protocol version type.
@@ -154,6 +154,53 @@ pub enum ProtocolFeature {
     LowerStorageKeyLimit,
     // alt_bn128_g1_multiexp, alt_bn128_g1_sum, alt_bn128_pairing_check host functions
     AltBn128,
+    /// `verify_ics23_proof` host function for Cosmos SDK commitment proofs.
+    Ics23,
+    /// `verify_near_state_proof` host function for proofs of NEAR's own state.
+    NearStateProof,
+    /// `verify_outcome_proof` host function for execution outcome inclusion proofs.
+    OutcomeProof,
+    /// `validate_light_client_block` host function running the NEAR light client update rule.
+    LightClientBlock,
+    /// `verify_mmr_proof` and `verify_beefy_commitment` host functions for Polkadot BEEFY.
+    Beefy,
+    /// `verify_near_state_proof_status` host function telling why a state proof failed.
+    StateProofStatus,
+    /// Re-enables `storage_iter_prefix`, `storage_iter_range` and `storage_iter_next`.
+    StorageIterators,
+    /// `storage_read_with_proof` host function returning the trie nodes of a read.
+    StorageReadWithProof,
+    /// `gas_price` host function returning the gas price of the current block.
+    GasPrice,
+    /// `block_hash` host function returning the hash of a recent block.
+    BlockHash,
+    /// `epoch_id` host function returning the id of the current epoch.
+    EpochId,
+    /// `validator_public_key` host function returning the public key of a validator.
+    ValidatorPublicKey,
+    /// `chain_id` host function returning the chain id of the genesis config.
+    ChainId,
+    /// `emit_event` host function recording events into the execution outcome.
+    Events,
+    /// `contract_data_trie_prefix` host function returning the raw trie key prefix of contract
+    /// data.
+    ContractDataTriePrefix,
+    /// `verify_tendermint_commit` host function for ICS-07 Tendermint light clients.
+    TendermintCommit,
+    /// Report contracts importing a host function that isn't available at the current protocol
+    /// version with `ContractCallError::UnsupportedHostFunction` instead of a link error.
+    UnsupportedHostFunctionError,
+    /// Charge `utf8_decoding_byte` for every byte examined while scanning a nul-terminated utf-8
+    /// string, including when the scan stops because the log is too long.
+    NulTerminatedUtf8DecodingCost,
+    /// Check UTF-16 logs against `max_total_log_length` by their UTF-8 length, which is what is
+    /// stored in the outcome, instead of by their UTF-16 length.
+    Utf16LogUtf8Length,
+    /// `value_return_append` host function building the return value in several parts.
+    ValueReturnAppend,
+    /// `promise_result_len` and `promise_result_receipt_id` host functions describing the
+    /// promise results of a callback.
+    PromiseResultMetadata,
 
     #[cfg(feature = "protocol_feature_chunk_only_producers")]
     ChunkOnlyProducers,
@@ -177,7 +224,7 @@ pub const PEER_MIN_ALLOWED_PROTOCOL_VERSION: ProtocolVersion = STABLE_PROTOCOL_V
 /// Current protocol version used on the mainnet.
 /// Some features (e. g. FixStorageUsage) require that there is at least one epoch with exactly
 /// the corresponding version
-const STABLE_PROTOCOL_VERSION: ProtocolVersion = 55;
+const STABLE_PROTOCOL_VERSION: ProtocolVersion = 56;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "nightly_protocol")] {
@@ -246,6 +293,27 @@ impl ProtocolFeature {
             | ProtocolFeature::ChunkNodesCache
             | ProtocolFeature::LowerStorageKeyLimit => 53,
             ProtocolFeature::AltBn128 => 55,
+            ProtocolFeature::Ics23
+            | ProtocolFeature::NearStateProof
+            | ProtocolFeature::OutcomeProof
+            | ProtocolFeature::LightClientBlock
+            | ProtocolFeature::Beefy
+            | ProtocolFeature::StateProofStatus
+            | ProtocolFeature::StorageIterators
+            | ProtocolFeature::StorageReadWithProof
+            | ProtocolFeature::GasPrice
+            | ProtocolFeature::BlockHash
+            | ProtocolFeature::EpochId
+            | ProtocolFeature::ValidatorPublicKey
+            | ProtocolFeature::ChainId
+            | ProtocolFeature::Events
+            | ProtocolFeature::ContractDataTriePrefix
+            | ProtocolFeature::TendermintCommit
+            | ProtocolFeature::UnsupportedHostFunctionError
+            | ProtocolFeature::NulTerminatedUtf8DecodingCost
+            | ProtocolFeature::Utf16LogUtf8Length
+            | ProtocolFeature::ValueReturnAppend
+            | ProtocolFeature::PromiseResultMetadata => 56,
 
             // Nightly & shardnet features
             #[cfg(feature = "protocol_feature_chunk_only_producers")]
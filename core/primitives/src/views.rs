@@ -36,9 +36,11 @@ use crate::sharding::{
 };
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, SignedTransaction, StakeAction, TransferAction,
+    DeployContractAction, Event, ExecutionMetadata, ExecutionOutcome,
+    ExecutionOutcomeWithIdAndProof, ExecutionStatus, FunctionCallAction, SignedTransaction,
+    StakeAction, TransferAction,
 };
+use crate::trie_key::TrieKey;
 use crate::types::{
     AccountId, AccountWithPublicKey, Balance, BlockHeight, CompiledContractCache, EpochHeight,
     EpochId, FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind,
@@ -63,6 +65,15 @@ pub struct AccountView {
     pub storage_paid_at: BlockHeight,
 }
 
+/// A view of the account along with the trie nodes proving its record in the state.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct AccountWithProofView {
+    #[serde(flatten)]
+    pub account: AccountView,
+    pub proof: TrieProofPath,
+}
+
 /// A view of the contract code.
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -72,6 +83,27 @@ pub struct ContractCodeView {
     pub hash: CryptoHash,
 }
 
+/// A view of the contract code along with the trie nodes proving it in the state.
+///
+/// The proof covers the account record, whose `code_hash` commits to the code, and, when the
+/// code is returned, the path to the code record. The code bytes themselves are not part of the
+/// proof since they are returned alongside.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ContractCodeWithProofView {
+    /// The code, unless only its hash was asked for.
+    #[serde(
+        default,
+        rename = "code_base64",
+        with = "option_base64_format",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub code: Option<Vec<u8>>,
+    pub hash: CryptoHash,
+    pub account: AccountView,
+    pub proof: TrieProofPath,
+}
+
 /// State for the view call.
 #[derive(Debug)]
 pub struct ViewApplyState {
@@ -85,6 +117,8 @@ pub struct ViewApplyState {
     pub epoch_id: EpochId,
     /// Current epoch height
     pub epoch_height: EpochHeight,
+    /// The chain id from the genesis config.
+    pub chain_id: String,
     /// The current block timestamp (number of non-leap-nanoseconds since January 1, 1970 0:00:00 UTC).
     pub block_timestamp: u64,
     /// Current Protocol version when we apply the state transition
@@ -204,15 +238,117 @@ pub type TrieProofPath = Vec<String>;
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct StateItem {
     pub key: String,
+    /// Empty if the value is elided.
     pub value: String,
+    /// Always empty, kept for compatibility. See `proof_node_indices` for the proof of the item.
     pub proof: TrieProofPath,
+    /// Set instead of the value when it is larger than the `max_value_size` of the query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elided_value: Option<ElidedValueView>,
+    /// Indices in the `proof` of the `ViewStateResult` of the trie nodes on the path to the item,
+    /// root first, which prove the item on their own. Empty if the proof wasn't requested.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proof_node_indices: Vec<u32>,
+}
+
+/// Hash and length of a state value left out of a `view_state` result. The trie commits to
+/// both, so the item can be proven without the value.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ElidedValueView {
+    pub hash: CryptoHash,
+    pub length: u64,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ViewStateResult {
     pub values: Vec<StateItem>,
+    /// Trie nodes covering the range of the result, without the values, root first, if requested.
     pub proof: TrieProofPath,
+    /// Key of the last item of a truncated page, serialized in base64. Passing it as `from_key`
+    /// resumes iteration after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+    /// What `proof` shows, set if the proof was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_kind: Option<ViewStateProofKind>,
+    /// Whether the items stop at the state size limit because the state of the account was too
+    /// large to be returned at once and partial results were allowed. `next_key` resumes them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Byte size of the contract state of the account, according to its storage usage, set when
+    /// it was too large and partial results were returned instead of an error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_state_size: Option<u64>,
+}
+
+/// Kind of the proof of a `ViewStateResult`.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewStateProofKind {
+    /// The trie nodes covering the range of the result, showing that it has every item of the
+    /// range.
+    Range,
+    /// No contract data key of the account starts with the queried prefix. The proof is the
+    /// trie nodes on the path to the raw trie key of the prefix, in order from the root to the
+    /// node where the path diverges from it.
+    NonExistence,
+}
+
+/// The value of a single contract data key, or its absence, with its proof against the state
+/// root of a shard at a block.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StateProofView {
+    /// `None` if no value is stored under the key.
+    #[serde(rename = "value_base64", with = "option_base64_format")]
+    pub value: Option<Vec<u8>>,
+    /// Trie nodes on the path to the key, in order from the root to the last node read, without
+    /// the value. See `near_store::proof_verifier::verify_state_proof`.
+    pub proof: TrieProofPath,
+    /// State root of the shard after the block. The next chunk of the shard commits to it as its
+    /// `prev_state_root`, and the header of the block including that chunk to the chunk.
+    pub state_root: StateRoot,
+    pub shard_id: ShardId,
+    /// Hash of the chunk of the shard in the block.
+    pub chunk_hash: ChunkHash,
+}
+
+/// A trie node as stored in the state, along with its decoding. Used for debugging.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TrieNodeResponse {
+    pub hash: CryptoHash,
+    #[serde(rename = "raw_base64", with = "base64_format")]
+    pub raw: Vec<u8>,
+    /// `None` if the bytes stored under the hash are not a trie node, e.g. a value.
+    pub node: Option<TrieNodeView>,
+}
+
+/// A decoded trie node, with the memory usage of its subtree.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TrieNodeView {
+    pub memory_usage: u64,
+    #[serde(flatten)]
+    pub kind: TrieNodeKindView,
+}
+
+/// Keys of the nodes are the nibbles they add to the path, one hex digit per nibble.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrieNodeKindView {
+    Leaf { key: String, value: TrieValueRefView },
+    Branch { children: Vec<Option<CryptoHash>>, value: Option<TrieValueRefView> },
+    Extension { key: String, child: CryptoHash },
+}
+
+/// Length and hash of a value referenced by a trie node.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct TrieValueRefView {
+    pub length: u32,
+    pub hash: CryptoHash,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -244,6 +380,24 @@ pub struct AccessKeyList {
     pub keys: Vec<AccessKeyInfoView>,
 }
 
+/// A view of the access key along with the trie nodes proving its record in the state.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct AccessKeyWithProofView {
+    #[serde(flatten)]
+    pub access_key: AccessKeyView,
+    pub proof: TrieProofPath,
+}
+
+/// The access keys of an account along with the trie nodes of the whole subtree
+/// holding them, which prove that no other key exists.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct AccessKeyListWithProof {
+    pub keys: Vec<AccessKeyInfoView>,
+    pub proof: TrieProofPath,
+}
+
 impl FromIterator<AccessKeyInfoView> for AccessKeyList {
     fn from_iter<I: IntoIterator<Item = AccessKeyInfoView>>(iter: I) -> Self {
         Self { keys: iter.into_iter().collect() }
@@ -254,11 +408,15 @@ impl FromIterator<AccessKeyInfoView> for AccessKeyList {
 #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
 pub enum QueryResponseKind {
     ViewAccount(AccountView),
+    ViewAccountWithProof(AccountWithProofView),
     ViewCode(ContractCodeView),
+    ViewCodeWithProof(ContractCodeWithProofView),
     ViewState(ViewStateResult),
     CallResult(CallResult),
     AccessKey(AccessKeyView),
+    AccessKeyWithProof(AccessKeyWithProofView),
     AccessKeyList(AccessKeyList),
+    AccessKeyListWithProof(AccessKeyListWithProof),
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -267,30 +425,78 @@ pub enum QueryResponseKind {
 pub enum QueryRequest {
     ViewAccount {
         account_id: AccountId,
+        /// Whether to return the proof of the account record in the state.
+        #[serde(default)]
+        include_proof: bool,
     },
     ViewCode {
         account_id: AccountId,
+        /// Whether to return the proof of the code in the state.
+        #[serde(default)]
+        include_proof: bool,
+        /// Whether to return the code along with its proof. Without it only the code hash is
+        /// proven, which avoids sending large contracts.
+        #[serde(default = "default_true")]
+        include_code: bool,
     },
     ViewState {
         account_id: AccountId,
         #[serde(rename = "prefix_base64", with = "base64_format")]
         prefix: StoreKey,
+        /// Only return keys strictly after this one.
+        #[serde(
+            default,
+            rename = "from_key_base64",
+            with = "option_base64_format",
+            skip_serializing_if = "Option::is_none"
+        )]
+        from_key: Option<Vec<u8>>,
+        /// Max number of items to return.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u64>,
+        /// Values larger than this many bytes are replaced by their hash and length.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_value_size: Option<u64>,
+        /// Byte size of contract state above which it's too large to be viewed, instead of the
+        /// limit of the node. Only the node's ceiling, if it allows overrides, can be exceeded.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state_size_limit: Option<u64>,
+        /// Whether to return the proof of the result. Skipping it saves recording trie nodes.
+        #[serde(default = "default_true")]
+        include_proof: bool,
+        /// Whether to return the items up to the state size limit when the state of the account
+        /// is too large, instead of failing.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_partial: bool,
     },
     ViewAccessKey {
         account_id: AccountId,
         public_key: PublicKey,
+        /// Whether to return the proof of the access key record in the state.
+        #[serde(default)]
+        include_proof: bool,
     },
     ViewAccessKeyList {
         account_id: AccountId,
+        /// Whether to return the proof of all the access keys of the account in the state.
+        #[serde(default)]
+        include_proof: bool,
     },
     CallFunction {
         account_id: AccountId,
         method_name: String,
         #[serde(rename = "args_base64", with = "base64_format")]
         args: FunctionArgs,
+        /// Gas limit of the call. None is the node's limit of view calls.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_gas: Option<Gas>,
     },
 }
 
+fn default_true() -> bool {
+    true
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
 pub struct QueryResponse {
@@ -793,6 +999,37 @@ impl From<ChunkHeaderView> for ShardChunkHeader {
     }
 }
 
+/// The header of a chunk with the proof of its inclusion in a block. It connects the state root
+/// the chunk is applied to, which state proofs are verified against, to the block header.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChunkStateRootProofView {
+    /// Hash of the block including the chunk.
+    pub block_hash: CryptoHash,
+    pub chunk_header: ChunkHeaderView,
+    /// Borsh serialization of the chunk header, from which its hash is computed. `chunk_header`
+    /// is a view of it.
+    #[serde(rename = "chunk_header_base64", with = "base64_format")]
+    pub raw_chunk_header: Vec<u8>,
+    /// Path from the chunk hash and the height the chunk is included at to the chunk headers root
+    /// of the block.
+    pub chunk_headers_proof: MerklePath,
+}
+
+impl ChunkStateRootProofView {
+    /// Verifies the proof against the chunk headers root of an already verified header of block
+    /// `block_hash`. Returns the chunk header the proof is for, whose `prev_state_root` and
+    /// `shard_id` can then be trusted, or `None` if the proof is invalid.
+    pub fn verify(&self, chunk_headers_root: &CryptoHash) -> Option<ShardChunkHeader> {
+        let chunk_header = ShardChunkHeader::try_from_slice(&self.raw_chunk_header).ok()?;
+        Block::validate_chunk_header_proof(
+            &chunk_header,
+            chunk_headers_root,
+            &self.chunk_headers_proof,
+        )
+        .then(|| chunk_header)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BlockView {
     pub author: AccountId,
@@ -1064,6 +1301,8 @@ pub struct CostGasUsed {
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Eq, Debug)]
 pub struct ExecutionMetadataView {
     pub version: u32,
+    /// Gas burnt per cost, sorted by category and cost name. Set for the outcomes of receipts,
+    /// which is where contracts run.
     pub gas_profile: Option<Vec<CostGasUsed>>,
 }
 
@@ -1140,6 +1379,23 @@ pub struct ExecutionOutcomeView {
     /// Execution metadata, versioned
     #[serde(default)]
     pub metadata: ExecutionMetadataView,
+    /// Events emitted by this receipt.
+    #[serde(default)]
+    pub events: Vec<EventView>,
+}
+
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EventView {
+    pub standard: String,
+    #[serde(with = "base64_format")]
+    pub data: Vec<u8>,
+}
+
+impl From<Event> for EventView {
+    fn from(event: Event) -> Self {
+        Self { standard: event.standard, data: event.data }
+    }
 }
 
 impl From<ExecutionOutcome> for ExecutionOutcomeView {
@@ -1152,6 +1408,7 @@ impl From<ExecutionOutcome> for ExecutionOutcomeView {
             executor_id: outcome.executor_id,
             status: outcome.status.into(),
             metadata: outcome.metadata.into(),
+            events: outcome.events.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -1165,6 +1422,16 @@ pub struct ExecutionOutcomeWithIdView {
     pub outcome: ExecutionOutcomeView,
 }
 
+/// Proof that the outcome root of the chunk including execution outcome `id` is part of the
+/// outcome root of block `block_hash`, the next block with a new chunk of the same shard.
+/// Together with `ExecutionOutcomeWithIdView::proof` it connects the outcome to a block header.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OutcomeRootProofView {
+    pub id: CryptoHash,
+    pub block_hash: CryptoHash,
+    pub outcome_root_proof: MerklePath,
+}
+
 impl From<ExecutionOutcomeWithIdAndProof> for ExecutionOutcomeWithIdView {
     fn from(outcome_with_id_and_proof: ExecutionOutcomeWithIdAndProof) -> Self {
         Self {
@@ -1229,6 +1496,31 @@ impl From<FinalExecutionOutcomeWithReceiptView> for FinalExecutionOutcomeView {
     }
 }
 
+/// How far a sent transaction has to progress before the node responds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TxExecutionStatus {
+    /// The transaction is accepted by the node, without waiting for it to be included in a block.
+    None,
+    /// The transaction is included in a block, which may not be final yet.
+    Included,
+    /// The transaction is included in a final block.
+    IncludedFinal,
+    /// All the receipts of the transaction are executed, in blocks which may not be final yet.
+    ExecutedOptimistic,
+    /// All the receipts of the transaction are executed and the block including the transaction
+    /// is final.
+    ExecutedFinal,
+    /// All the receipts of the transaction are executed in final blocks.
+    Final,
+}
+
+impl Default for TxExecutionStatus {
+    fn default() -> Self {
+        Self::ExecutedOptimistic
+    }
+}
+
 pub mod validator_stake_view {
     use crate::types::validator_stake::ValidatorStake;
     use borsh::{BorshDeserialize, BorshSerialize};
@@ -1447,6 +1739,12 @@ pub struct EpochValidatorInfo {
     pub epoch_start_height: BlockHeight,
     /// Epoch height
     pub epoch_height: EpochHeight,
+    /// Commitment to the block producers of the current epoch, the `next_bp_hash` of the blocks
+    /// of the previous epoch. See `BlockHeader::compute_bp_hash`.
+    pub current_bp_hash: CryptoHash,
+    /// Commitment to the block producers of the next epoch, the `next_bp_hash` of the blocks of
+    /// the current epoch.
+    pub next_bp_hash: CryptoHash,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -1483,6 +1781,56 @@ pub struct NextEpochValidatorInfo {
     pub shards: Vec<ShardId>,
 }
 
+/// Validators assigned to produce the block and the chunks at each height of an epoch.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ProducerScheduleView {
+    pub epoch_id: CryptoHash,
+    /// Epoch start block height, estimated for the next epoch
+    pub epoch_start_height: BlockHeight,
+    /// Producers of the `epoch_length` heights from the epoch start. An epoch lasts longer when
+    /// its last blocks are missing, the producers of later heights are assigned the same way.
+    pub producers: Vec<HeightProducersView>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct HeightProducersView {
+    pub height: BlockHeight,
+    pub block_producer: AccountId,
+    /// Chunk producers, indexed by shard id
+    pub chunk_producers: Vec<AccountId>,
+}
+
+/// Block producers of the epoch after the one of a block, along with what the block commits to
+/// them with in its `next_bp_hash`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct NextValidatorSetView {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub epoch_id: EpochId,
+    pub next_epoch_id: EpochId,
+    pub next_bp_hash: CryptoHash,
+    /// Protocol version of the epoch of the block, which decides how the block producers are
+    /// serialized for the hash.
+    pub protocol_version: ProtocolVersion,
+    /// Unique block producers of the next epoch, in the order they are hashed in.
+    pub next_block_producers: Vec<ValidatorStakeView>,
+}
+
+impl NextValidatorSetView {
+    /// Recomputes the `next_bp_hash` of the block from the block producers, to be compared with
+    /// the one of a trusted header.
+    pub fn compute_next_bp_hash(&self) -> CryptoHash {
+        BlockHeader::compute_bp_hash(
+            self.next_block_producers
+                .iter()
+                .cloned()
+                .map(ValidatorStakeView::into_validator_stake)
+                .collect(),
+            self.protocol_version,
+        )
+    }
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, BorshDeserialize, BorshSerialize)]
 pub struct LightClientBlockView {
@@ -1517,6 +1865,26 @@ pub struct GasPriceView {
     pub gas_price: Balance,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HeightGasPriceView {
+    pub height: BlockHeight,
+    pub block_hash: CryptoHash,
+    #[serde(with = "u128_dec_format")]
+    pub gas_price: Balance,
+}
+
+/// Gas prices of the blocks of the canonical chain in a range of heights.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GasPriceRangeView {
+    /// Gas prices in increasing height order.
+    pub gas_prices: Vec<HeightGasPriceView>,
+    /// Heights of the covered part of the range without a block on the canonical chain.
+    pub missing_heights: Vec<BlockHeight>,
+    /// First height of the range not covered by this response, if any. Either the range was
+    /// longer than the limit of a single request or it goes beyond the head of the chain.
+    pub next_height: Option<BlockHeight>,
+}
+
 /// It is a [serializable view] of [`StateChangesRequest`].
 ///
 /// [serializable view]: ./index.html
@@ -1565,6 +1933,77 @@ impl From<StateChangesRequestView> for StateChangesRequest {
     }
 }
 
+/// It is a [serializable view] of [`TrieKey`].
+///
+/// [serializable view]: ./index.html
+/// [`TrieKey`]: ../trie_key/enum.TrieKey.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "key_type", rename_all = "snake_case")]
+pub enum TrieKeyView {
+    Account {
+        account_id: AccountId,
+    },
+    ContractCode {
+        account_id: AccountId,
+    },
+    AccessKey {
+        account_id: AccountId,
+        public_key: PublicKey,
+    },
+    ReceivedData {
+        receiver_id: AccountId,
+        data_id: CryptoHash,
+    },
+    PostponedReceiptId {
+        receiver_id: AccountId,
+        data_id: CryptoHash,
+    },
+    PendingDataCount {
+        receiver_id: AccountId,
+        receipt_id: CryptoHash,
+    },
+    PostponedReceipt {
+        receiver_id: AccountId,
+        receipt_id: CryptoHash,
+    },
+    DelayedReceiptIndices,
+    DelayedReceipt {
+        index: u64,
+    },
+    ContractData {
+        account_id: AccountId,
+        #[serde(rename = "key_base64", with = "base64_format")]
+        key: Vec<u8>,
+    },
+}
+
+impl From<TrieKeyView> for TrieKey {
+    fn from(trie_key: TrieKeyView) -> Self {
+        match trie_key {
+            TrieKeyView::Account { account_id } => Self::Account { account_id },
+            TrieKeyView::ContractCode { account_id } => Self::ContractCode { account_id },
+            TrieKeyView::AccessKey { account_id, public_key } => {
+                Self::AccessKey { account_id, public_key }
+            }
+            TrieKeyView::ReceivedData { receiver_id, data_id } => {
+                Self::ReceivedData { receiver_id, data_id }
+            }
+            TrieKeyView::PostponedReceiptId { receiver_id, data_id } => {
+                Self::PostponedReceiptId { receiver_id, data_id }
+            }
+            TrieKeyView::PendingDataCount { receiver_id, receipt_id } => {
+                Self::PendingDataCount { receiver_id, receipt_id }
+            }
+            TrieKeyView::PostponedReceipt { receiver_id, receipt_id } => {
+                Self::PostponedReceipt { receiver_id, receipt_id }
+            }
+            TrieKeyView::DelayedReceiptIndices => Self::DelayedReceiptIndices,
+            TrieKeyView::DelayedReceipt { index } => Self::DelayedReceipt { index },
+            TrieKeyView::ContractData { account_id, key } => Self::ContractData { account_id, key },
+        }
+    }
+}
+
 /// It is a [serializable view] of [`StateChangeKind`].
 ///
 /// [serializable view]: ./index.html
@@ -1728,3 +2167,19 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 }
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
+
+/// Proof of the value a key has after a block, as set by one of the state changes of the block.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StateChangeProofView {
+    /// Index of the change in the list of changes it comes with. It is the last change of its
+    /// key in the block.
+    pub change_index: u64,
+    /// Trie nodes on the path to the key, in order from the root to the last node read, without
+    /// the value. See `near_store::proof_verifier::verify_state_proof`.
+    pub proof: TrieProofPath,
+    /// State root of the shard after the block.
+    pub state_root: StateRoot,
+    pub shard_id: ShardId,
+    /// Hash of the chunk of the shard in the block.
+    pub chunk_hash: ChunkHash,
+}
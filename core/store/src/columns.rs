@@ -233,6 +233,23 @@ pub enum DBCol {
     /// - *Rows*: BlockShardId (BlockHash || ShardId) - 40 bytes
     /// - *Column type*: StateChangesForSplitStates
     StateChangesForSplitStates = 49,
+    /// State roots kept from garbage collection, see `Chain::pin_state_roots`.
+    /// - *Rows*: BlockHash (CryptoHash)
+    /// - *Column type*: PinnedStateRoots
+    PinnedStateRoots = 50,
+    /// Flat index of the contract data of a shard, see `flat_contract_data`.
+    /// - *Rows*: ShardUId || trie key of the contract data (TrieKey::ContractData)
+    /// - *Column type*: ValueRef
+    FlatContractData = 51,
+    /// The state the flat index of the contract data of a shard is at.
+    /// - *Rows*: ShardUId
+    /// - *Column type*: FlatContractDataHead
+    FlatContractDataHead = 52,
+    /// Trie changes of garbage collected blocks whose deletions wait for the state roots pinned
+    /// before the block to be unpinned, see `Chain::pin_state_roots`.
+    /// - *Rows*: height (big-endian u64) || BlockShardId (BlockHash || ShardUId) - 48 bytes
+    /// - *Column type*: TrieChanges
+    DeferredTrieChanges = 53,
 }
 
 impl DBCol {
@@ -327,7 +344,11 @@ impl DBCol {
             | DBCol::EpochInfo           // https://github.com/nearprotocol/nearcore/pull/2952
             | DBCol::EpochValidatorInfo  // https://github.com/nearprotocol/nearcore/pull/2952
             | DBCol::EpochStart          // https://github.com/nearprotocol/nearcore/pull/2952
-            | DBCol::CachedContractCode => false,
+            | DBCol::CachedContractCode
+            | DBCol::PinnedStateRoots
+            | DBCol::FlatContractData
+            | DBCol::FlatContractDataHead
+            | DBCol::DeferredTrieChanges => false,
             _ => true,
         }
     }
@@ -405,6 +426,10 @@ impl fmt::Display for DBCol {
             Self::EpochValidatorInfo => "epoch validator info",
             Self::HeaderHashesByHeight => "header hashes indexed by their height",
             Self::StateChangesForSplitStates => "state changes indexed by block hash and shard id",
+            Self::PinnedStateRoots => "state roots kept from garbage collection",
+            Self::FlatContractData => "flat index of contract data",
+            Self::FlatContractDataHead => "state the flat index of contract data is at",
+            Self::DeferredTrieChanges => "trie changes waiting for pinned state roots",
         };
         write!(f, "{}", desc)
     }
@@ -29,11 +29,13 @@ use crate::db::{
     GENESIS_JSON_HASH_KEY, GENESIS_STATE_ROOTS_KEY,
 };
 pub use crate::trie::iterator::TrieIterator;
-pub use crate::trie::update::{TrieUpdate, TrieUpdateIterator, TrieUpdateValuePtr};
+pub use crate::trie::update::{
+    TrieRecordingIterator, TrieUpdate, TrieUpdateIterator, TrieUpdateValuePtr,
+};
 pub use crate::trie::{
-    estimator, split_state, ApplyStatePartResult, KeyForStateChanges, PartialStorage, ShardTries,
-    Trie, TrieCache, TrieCacheFactory, TrieCachingStorage, TrieChanges, TrieStorage,
-    WrappedTrieChanges,
+    encoding, estimator, flat_contract_data, proof_verifier, split_state, verify_state_part,
+    ApplyStatePartResult, KeyForStateChanges, PartialStorage, ShardTries, Trie, TrieCache,
+    TrieCacheFactory, TrieCachingStorage, TrieChanges, TrieStorage, WrappedTrieChanges,
 };
 
 mod columns;
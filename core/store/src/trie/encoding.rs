@@ -0,0 +1,36 @@
+//! Byte encoding of trie nodes and of the nibble paths they store, for code
+//! which verifies proofs made of trie nodes outside of the trie.
+//!
+//! The encoding lives in `near_primitives::trie_encoding`, so that the
+//! runtime verifies proofs with the same decoder as the trie uses; this
+//! module re-exports it.
+
+pub use near_primitives::trie_encoding::*;
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::trie::nibble_slice::NibbleSlice;
+
+    #[test]
+    fn test_nibbles_match_nibble_slice() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let len = rng.gen_range(0, 20);
+            let nibbles: Vec<u8> = (0..len).map(|_| rng.gen_range(0, 16)).collect();
+            let is_leaf = rng.gen_bool(0.5);
+            let encoded = encode_nibbles(&nibbles, is_leaf);
+            assert_eq!(&encoded[..], &NibbleSlice::encode_nibbles(&nibbles, is_leaf)[..]);
+            let (slice, slice_is_leaf) = NibbleSlice::from_encoded(&encoded);
+            assert_eq!(
+                (slice.iter().collect::<Vec<_>>(), slice_is_leaf),
+                decode_nibbles(&encoded).unwrap()
+            );
+
+            let key: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            assert_eq!(key_to_nibbles(&key), NibbleSlice::new(&key).iter().collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Flat index of the contract data of a shard at a single state. It maps the trie key of each
+//! `TrieKey::ContractData` entry to the hash and length of its value, so reading a range of
+//! contract data takes one database iteration instead of a walk over the trie nodes.
+//!
+//! The index follows its state through `WrappedTrieChanges::flat_contract_data_into`, as long
+//! as the applied chunks extend that state. Otherwise, e.g. after switching to another fork, it
+//! is moved with `FlatContractData::update_entries` to the new state, given the keys changed on
+//! the way, or rebuilt from the trie with `FlatContractData::build`.
+
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::trie_key::{col, TrieKey};
+use near_primitives::types::{RawStateChange, RawStateChangesWithTrieKey, StateRoot};
+
+use crate::{DBCol, StorageError, Store, StoreUpdate, Trie};
+
+/// Number of entries written to the database at once while building the index.
+const BUILD_BATCH_SIZE: usize = 10_000;
+
+/// Where the value of an entry is stored in `DBCol::State`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueRef {
+    pub length: u32,
+    pub hash: CryptoHash,
+}
+
+impl ValueRef {
+    pub fn new(value: &[u8]) -> Self {
+        Self { length: value.len() as u32, hash: hash(value) }
+    }
+}
+
+/// The state the index of a shard is at.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FlatContractDataHead {
+    pub block_hash: CryptoHash,
+    pub state_root: StateRoot,
+}
+
+/// The flat index of the contract data of one shard.
+pub struct FlatContractData {
+    store: Store,
+    shard_uid: ShardUId,
+}
+
+impl FlatContractData {
+    pub fn new(store: Store, shard_uid: ShardUId) -> Self {
+        Self { store, shard_uid }
+    }
+
+    /// Deletes the indexes of all shards.
+    pub fn delete_all(store: &Store) -> io::Result<()> {
+        let mut store_update = store.store_update();
+        store_update.delete_all(DBCol::FlatContractDataHead);
+        store_update.delete_all(DBCol::FlatContractData);
+        store_update.commit()
+    }
+
+    /// The state the index is at, or `None` if the shard has no complete index.
+    pub fn get_head(&self) -> io::Result<Option<FlatContractDataHead>> {
+        self.store.get_ser(DBCol::FlatContractDataHead, &self.shard_uid.to_bytes())
+    }
+
+    /// Deletes the head, so that the index is neither used nor moved by `apply_state_changes`
+    /// until `update_entries` or `build` brings it to a state again.
+    pub fn reset_head(&self) -> io::Result<()> {
+        let mut store_update = self.store.store_update();
+        store_update.delete(DBCol::FlatContractDataHead, &self.shard_uid.to_bytes());
+        store_update.commit()
+    }
+
+    /// The database key of the entry with the given trie key. A prefix of a trie key gives the
+    /// prefix of the keys of the entries to pass to `iter`.
+    pub fn key(&self, trie_key: &[u8]) -> Vec<u8> {
+        [&self.shard_uid.to_bytes()[..], trie_key].concat()
+    }
+
+    /// Iterates over the entries whose database keys start with `key_prefix`, in key order,
+    /// yielding their trie keys and where their values are.
+    pub fn iter<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Iterator<Item = io::Result<(Box<[u8]>, ValueRef)>> + 'a {
+        let shard_uid_len = self.shard_uid.to_bytes().len();
+        self.store.iter_prefix(DBCol::FlatContractData, key_prefix).map(move |item| {
+            let (key, value) = item?;
+            Ok((key[shard_uid_len..].into(), ValueRef::try_from_slice(&value)?))
+        })
+    }
+
+    /// Moves the index from state `old_root` to `new_root`, the state after `block_hash`, by
+    /// applying the contract data changes among `state_changes`. Does nothing if the index isn't
+    /// at `old_root`, in particular if there is no index.
+    pub(crate) fn apply_state_changes(
+        &self,
+        old_root: &StateRoot,
+        new_root: &StateRoot,
+        block_hash: &CryptoHash,
+        state_changes: &[RawStateChangesWithTrieKey],
+        store_update: &mut StoreUpdate,
+    ) -> io::Result<()> {
+        match self.get_head()? {
+            Some(head) if head.state_root == *old_root => {}
+            _ => return Ok(()),
+        }
+        for RawStateChangesWithTrieKey { trie_key, changes } in state_changes {
+            if !matches!(trie_key, TrieKey::ContractData { .. }) {
+                continue;
+            }
+            let key = self.key(&trie_key.to_vec());
+            match changes.last() {
+                Some(RawStateChange { data: Some(value), .. }) => {
+                    store_update.set_ser(DBCol::FlatContractData, &key, &ValueRef::new(value))?
+                }
+                Some(RawStateChange { data: None, .. }) => {
+                    store_update.delete(DBCol::FlatContractData, &key)
+                }
+                None => {}
+            }
+        }
+        store_update.set_ser(
+            DBCol::FlatContractDataHead,
+            &self.shard_uid.to_bytes(),
+            &FlatContractDataHead { block_hash: *block_hash, state_root: *new_root },
+        )
+    }
+
+    /// Moves the index to `state_root`, the state after `block_hash`, by reading the values of
+    /// `trie_keys` from `trie`. These have to include every contract data key whose value
+    /// differs between the entries and `state_root`, e.g. all those changed by the blocks
+    /// between the two states. Keys of other shards aren't in `trie`, so they are no-ops.
+    pub fn update_entries(
+        &self,
+        trie: &Trie,
+        block_hash: &CryptoHash,
+        state_root: &StateRoot,
+        trie_keys: &[Vec<u8>],
+    ) -> Result<(), StorageError> {
+        let mut store_update = self.store.store_update();
+        for trie_key in trie_keys {
+            let key = self.key(trie_key);
+            match trie.get_ref(state_root, trie_key)? {
+                Some((length, hash)) => store_update
+                    .set_ser(DBCol::FlatContractData, &key, &ValueRef { length, hash })
+                    .map_err(|_| StorageError::StorageInternalError)?,
+                None => store_update.delete(DBCol::FlatContractData, &key),
+            }
+        }
+        store_update
+            .set_ser(
+                DBCol::FlatContractDataHead,
+                &self.shard_uid.to_bytes(),
+                &FlatContractDataHead { block_hash: *block_hash, state_root: *state_root },
+            )
+            .map_err(|_| StorageError::StorageInternalError)?;
+        store_update.commit().map_err(|_| StorageError::StorageInternalError)
+    }
+
+    /// Rebuilds the index from `trie` at `state_root`, the state after `block_hash`.
+    ///
+    /// This reads all the contract data of the shard, so it takes long on large states. The
+    /// index has no head until it is complete, so it isn't used in the meantime.
+    pub fn build(
+        &self,
+        trie: &Trie,
+        block_hash: &CryptoHash,
+        state_root: &StateRoot,
+    ) -> Result<(), StorageError> {
+        let mut store_update = self.store.store_update();
+        store_update.delete(DBCol::FlatContractDataHead, &self.shard_uid.to_bytes());
+        for item in self.store.iter_prefix(DBCol::FlatContractData, &self.shard_uid.to_bytes()) {
+            let (key, _) = item.map_err(|_| StorageError::StorageInternalError)?;
+            store_update.delete(DBCol::FlatContractData, &key);
+        }
+        store_update.commit().map_err(|_| StorageError::StorageInternalError)?;
+
+        let mut store_update = self.store.store_update();
+        let mut iter = trie.iter(state_root)?;
+        iter.seek(col::CONTRACT_DATA)?;
+        for (num_entries, item) in iter.enumerate() {
+            let (trie_key, value) = item?;
+            if !trie_key.starts_with(col::CONTRACT_DATA) {
+                break;
+            }
+            store_update
+                .set_ser(DBCol::FlatContractData, &self.key(&trie_key), &ValueRef::new(&value))
+                .map_err(|_| StorageError::StorageInternalError)?;
+            if (num_entries + 1) % BUILD_BATCH_SIZE == 0 {
+                std::mem::replace(&mut store_update, self.store.store_update())
+                    .commit()
+                    .map_err(|_| StorageError::StorageInternalError)?;
+            }
+        }
+        store_update
+            .set_ser(
+                DBCol::FlatContractDataHead,
+                &self.shard_uid.to_bytes(),
+                &FlatContractDataHead { block_hash: *block_hash, state_root: *state_root },
+            )
+            .map_err(|_| StorageError::StorageInternalError)?;
+        store_update.commit().map_err(|_| StorageError::StorageInternalError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_primitives::trie_key::trie_key_parsers;
+    use near_primitives::types::StateChangeCause;
+
+    use super::*;
+    use crate::test_utils::{create_tries, test_populate_trie};
+
+    fn contract_data_key(account_id: &str, key: &[u8]) -> TrieKey {
+        TrieKey::ContractData { account_id: account_id.parse().unwrap(), key: key.to_vec() }
+    }
+
+    fn get_entries(flat: &FlatContractData, trie_key_prefix: &[u8]) -> Vec<(Vec<u8>, ValueRef)> {
+        let key_prefix = flat.key(trie_key_prefix);
+        flat.iter(&key_prefix)
+            .map(|item| item.map(|(key, value)| (key.into(), value)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_flat_contract_data() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let changes = vec![
+            (contract_data_key("alice", b"a"), Some(b"1".to_vec())),
+            (contract_data_key("alice", b"b"), Some(b"2".to_vec())),
+            (contract_data_key("alice.near", b"a"), Some(b"3".to_vec())),
+            (contract_data_key("bob", b"c"), Some(b"4".to_vec())),
+            (TrieKey::Account { account_id: "alice".parse().unwrap() }, Some(b"5".to_vec())),
+        ];
+        let root = test_populate_trie(
+            &tries,
+            &Trie::empty_root(),
+            shard_uid,
+            changes.iter().map(|(key, value)| (key.to_vec(), value.clone())).collect(),
+        );
+        let flat = FlatContractData::new(tries.get_store(), shard_uid);
+        assert_eq!(flat.get_head().unwrap(), None);
+
+        let block_hash = hash(b"block");
+        flat.build(&tries.get_trie_for_shard(shard_uid), &block_hash, &root).unwrap();
+        assert_eq!(
+            flat.get_head().unwrap(),
+            Some(FlatContractDataHead { block_hash, state_root: root })
+        );
+        let alice_prefix =
+            trie_key_parsers::get_raw_prefix_for_contract_data(&"alice".parse().unwrap(), b"");
+        assert_eq!(
+            get_entries(&flat, &alice_prefix),
+            vec![
+                (contract_data_key("alice", b"a").to_vec(), ValueRef::new(b"1")),
+                (contract_data_key("alice", b"b").to_vec(), ValueRef::new(b"2")),
+            ]
+        );
+        assert_eq!(get_entries(&flat, col::CONTRACT_DATA).len(), 4);
+
+        // Changes apply only on top of the state the index is at.
+        let state_changes = vec![
+            RawStateChangesWithTrieKey {
+                trie_key: contract_data_key("alice", b"a"),
+                changes: vec![RawStateChange { cause: StateChangeCause::InitialState, data: None }],
+            },
+            RawStateChangesWithTrieKey {
+                trie_key: contract_data_key("alice", b"c"),
+                changes: vec![
+                    RawStateChange {
+                        cause: StateChangeCause::InitialState,
+                        data: Some(b"6".to_vec()),
+                    },
+                    RawStateChange {
+                        cause: StateChangeCause::InitialState,
+                        data: Some(b"7".to_vec()),
+                    },
+                ],
+            },
+        ];
+        let new_root = hash(b"new root");
+        let new_block_hash = hash(b"new block");
+        let mut store_update = tries.get_store().store_update();
+        flat.apply_state_changes(
+            &new_root,
+            &new_root,
+            &new_block_hash,
+            &state_changes,
+            &mut store_update,
+        )
+        .unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(get_entries(&flat, &alice_prefix).len(), 2);
+
+        let mut store_update = tries.get_store().store_update();
+        flat.apply_state_changes(
+            &root,
+            &new_root,
+            &new_block_hash,
+            &state_changes,
+            &mut store_update,
+        )
+        .unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(
+            flat.get_head().unwrap(),
+            Some(FlatContractDataHead { block_hash: new_block_hash, state_root: new_root })
+        );
+        assert_eq!(
+            get_entries(&flat, &alice_prefix),
+            vec![
+                (contract_data_key("alice", b"b").to_vec(), ValueRef::new(b"2")),
+                (contract_data_key("alice", b"c").to_vec(), ValueRef::new(b"7")),
+            ]
+        );
+
+        // Building again replaces the whole index.
+        flat.build(&tries.get_trie_for_shard(shard_uid), &block_hash, &root).unwrap();
+        assert_eq!(get_entries(&flat, &alice_prefix).len(), 2);
+        assert_eq!(get_entries(&flat, &alice_prefix)[0].1, ValueRef::new(b"1"));
+
+        FlatContractData::delete_all(&tries.get_store()).unwrap();
+        assert_eq!(flat.get_head().unwrap(), None);
+        assert!(get_entries(&flat, col::CONTRACT_DATA).is_empty());
+    }
+
+    #[test]
+    fn test_update_entries() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let trie = tries.get_trie_for_shard(shard_uid);
+        let changes = vec![
+            (contract_data_key("alice", b"a"), Some(b"1".to_vec())),
+            (contract_data_key("alice", b"b"), Some(b"2".to_vec())),
+            (contract_data_key("bob", b"c"), Some(b"3".to_vec())),
+        ];
+        let root = test_populate_trie(
+            &tries,
+            &Trie::empty_root(),
+            shard_uid,
+            changes.iter().map(|(key, value)| (key.to_vec(), value.clone())).collect(),
+        );
+        let flat = FlatContractData::new(tries.get_store(), shard_uid);
+        flat.build(&trie, &hash(b"block"), &root).unwrap();
+
+        let changes = vec![
+            (contract_data_key("alice", b"a"), None),
+            (contract_data_key("alice", b"c"), Some(b"4".to_vec())),
+        ];
+        let new_root = test_populate_trie(
+            &tries,
+            &root,
+            shard_uid,
+            changes.iter().map(|(key, value)| (key.to_vec(), value.clone())).collect(),
+        );
+        flat.reset_head().unwrap();
+        assert_eq!(flat.get_head().unwrap(), None);
+        // The key of another shard isn't in the trie of this one.
+        let mut trie_keys: Vec<_> = changes.iter().map(|(key, _)| key.to_vec()).collect();
+        trie_keys.push(contract_data_key("carol", b"d").to_vec());
+        let new_block_hash = hash(b"new block");
+        flat.update_entries(&trie, &new_block_hash, &new_root, &trie_keys).unwrap();
+        assert_eq!(
+            flat.get_head().unwrap(),
+            Some(FlatContractDataHead { block_hash: new_block_hash, state_root: new_root })
+        );
+        let entries = get_entries(&flat, col::CONTRACT_DATA);
+        flat.build(&trie, &new_block_hash, &new_root).unwrap();
+        assert_eq!(entries, get_entries(&flat, col::CONTRACT_DATA));
+        assert_eq!(entries.len(), 3);
+    }
+}
@@ -1,7 +1,10 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
 use near_primitives::hash::CryptoHash;
 
 use crate::trie::nibble_slice::NibbleSlice;
-use crate::trie::{TrieNode, TrieNodeWithSize, ValueHandle};
+use crate::trie::{RawTrieNodeWithSize, TrieNode, TrieNodeWithSize, ValueHandle};
 use crate::{StorageError, Trie};
 
 #[derive(Debug)]
@@ -37,6 +40,8 @@ pub struct TrieIterator<'a> {
     trail: Vec<Crumb>,
     pub(crate) key_nibbles: Vec<u8>,
     root: CryptoHash,
+    /// Encoded trie nodes visited so far, each one once, if they are remembered.
+    visited_nodes: Option<(HashSet<CryptoHash>, Vec<Arc<[u8]>>)>,
 }
 
 pub type TrieItem = (Vec<u8>, Vec<u8>);
@@ -58,12 +63,40 @@ impl<'a> TrieIterator<'a> {
             trail: Vec::with_capacity(8),
             key_nibbles: Vec::with_capacity(64),
             root: *root,
+            visited_nodes: None,
         };
         let node = trie.retrieve_node(root)?;
         r.descend_into_node(node);
         Ok(r)
     }
 
+    /// Starts or stops remembering the encoded trie nodes the iterator visits from now on, see
+    /// `into_visited_nodes`. Values are not nodes, so they are not remembered.
+    pub fn remember_visited_nodes(&mut self, remember: bool) {
+        self.visited_nodes = remember.then(Default::default);
+    }
+
+    /// Returns the encoded trie nodes remembered, in the order they were first visited.
+    pub fn into_visited_nodes(self) -> Vec<Arc<[u8]>> {
+        self.visited_nodes.map(|(_, nodes)| nodes).unwrap_or_default()
+    }
+
+    /// Fetches the node with the given hash, remembering it if asked to.
+    fn fetch_node(&mut self, hash: &CryptoHash) -> Result<TrieNodeWithSize, StorageError> {
+        let (hashes, nodes) = match &mut self.visited_nodes {
+            Some(visited_nodes) if *hash != Trie::empty_root() => visited_nodes,
+            _ => return self.trie.retrieve_node(hash),
+        };
+        let bytes = self.trie.storage.retrieve_raw_bytes(hash)?;
+        let node = RawTrieNodeWithSize::decode(&bytes).map_err(|_| {
+            StorageError::StorageInconsistentState(format!("Failed to decode node {}", hash))
+        })?;
+        if hashes.insert(*hash) {
+            nodes.push(bytes);
+        }
+        Ok(TrieNodeWithSize::from_raw(node))
+    }
+
     /// Position the iterator on the first element with key => `key`.
     pub fn seek<K: AsRef<[u8]>>(&mut self, key: K) -> Result<(), StorageError> {
         self.seek_nibble_slice(NibbleSlice::new(key.as_ref())).map(drop)
@@ -78,7 +111,7 @@ impl<'a> TrieIterator<'a> {
         self.key_nibbles.clear();
         let mut hash = self.root;
         loop {
-            let node = self.trie.retrieve_node(&hash)?;
+            let node = self.fetch_node(&hash)?;
             self.trail.push(Crumb { status: CrumbStatus::Entering, node });
             let Crumb { status, node } = self.trail.last_mut().unwrap();
             match &node.node {
@@ -279,7 +312,7 @@ impl<'a> TrieIterator<'a> {
                     if self.key_nibbles[prefix..] >= path_end[prefix..] {
                         break;
                     }
-                    let node = self.trie.retrieve_node(&hash)?;
+                    let node = self.fetch_node(&hash)?;
                     self.descend_into_node(node);
                     nodes_list.push(TrieTraversalItem { hash, key: None });
                 }
@@ -314,7 +347,7 @@ impl<'a> Iterator for TrieIterator<'a> {
                 IterStep::PopTrail => {
                     self.trail.pop();
                 }
-                IterStep::Descend(hash) => match self.trie.retrieve_node(&hash) {
+                IterStep::Descend(hash) => match self.fetch_node(&hash) {
                     Ok(node) => self.descend_into_node(node),
                     Err(e) => return Some(Err(e)),
                 },
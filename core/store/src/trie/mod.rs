@@ -2,31 +2,36 @@ use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
-use std::io::{Cursor, Read, Write};
+use std::sync::Arc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use near_primitives::challenge::PartialState;
 use near_primitives::contract::ContractCode;
 use near_primitives::hash::{hash, CryptoHash};
 pub use near_primitives::shard_layout::ShardUId;
 use near_primitives::types::{StateRoot, StateRootNode};
+use near_primitives::views::TrieNodeView;
 
+use crate::trie::encoding::{RawTrieNode, RawTrieNodeWithSize};
 use crate::trie::insert_delete::NodesStorage;
 use crate::trie::iterator::TrieIterator;
 use crate::trie::nibble_slice::NibbleSlice;
 pub use crate::trie::shard_tries::{
     KeyForStateChanges, ShardTries, TrieCacheFactory, WrappedTrieChanges,
 };
+pub use crate::trie::state_parts::verify_state_part;
 pub use crate::trie::trie_storage::{TrieCache, TrieCachingStorage, TrieStorage};
 use crate::trie::trie_storage::{TrieMemoryPartialStorage, TrieRecordingStorage};
 use crate::StorageError;
 pub use near_primitives::types::TrieNodesCount;
 
+pub mod encoding;
+pub mod flat_contract_data;
 mod insert_delete;
 pub mod iterator;
 mod nibble_slice;
+pub mod proof_verifier;
 mod shard_tries;
 pub mod split_state;
 mod state_parts;
@@ -252,160 +257,6 @@ impl TrieNode {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-#[allow(clippy::large_enum_variant)]
-enum RawTrieNode {
-    Leaf(Vec<u8>, u32, CryptoHash),
-    Branch([Option<CryptoHash>; 16], Option<(u32, CryptoHash)>),
-    Extension(Vec<u8>, CryptoHash),
-}
-
-/// Trie node + memory cost of its subtree
-/// memory_usage is serialized, stored, and contributes to hash
-#[derive(Debug, Eq, PartialEq)]
-struct RawTrieNodeWithSize {
-    node: RawTrieNode,
-    memory_usage: u64,
-}
-
-const LEAF_NODE: u8 = 0;
-const BRANCH_NODE_NO_VALUE: u8 = 1;
-const BRANCH_NODE_WITH_VALUE: u8 = 2;
-const EXTENSION_NODE: u8 = 3;
-
-fn decode_children(cursor: &mut Cursor<&[u8]>) -> Result<[Option<CryptoHash>; 16], std::io::Error> {
-    let mut children: [Option<CryptoHash>; 16] = Default::default();
-    let bitmap = cursor.read_u16::<LittleEndian>()?;
-    let mut pos = 1;
-    for child in &mut children {
-        if bitmap & pos != 0 {
-            let mut arr = [0; 32];
-            cursor.read_exact(&mut arr)?;
-            *child = Some(CryptoHash::try_from(&arr[..]).unwrap());
-        }
-        pos <<= 1;
-    }
-    Ok(children)
-}
-
-impl RawTrieNode {
-    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), std::io::Error> {
-        let mut cursor = Cursor::new(out);
-        // size in state_parts = size + 8 for RawTrieNodeWithSize + 8 for borsh vector length
-        match &self {
-            // size <= 1 + 4 + 4 + 32 + key_length + value_length
-            RawTrieNode::Leaf(key, value_length, value_hash) => {
-                cursor.write_u8(LEAF_NODE)?;
-                cursor.write_u32::<LittleEndian>(key.len() as u32)?;
-                cursor.write_all(key)?;
-                cursor.write_u32::<LittleEndian>(*value_length)?;
-                cursor.write_all(value_hash.as_ref())?;
-            }
-            // size <= 1 + 4 + 32 + value_length + 2 + 32 * num_children
-            RawTrieNode::Branch(children, value) => {
-                if let Some((value_length, value_hash)) = value {
-                    cursor.write_u8(BRANCH_NODE_WITH_VALUE)?;
-                    cursor.write_u32::<LittleEndian>(*value_length)?;
-                    cursor.write_all(value_hash.as_ref())?;
-                } else {
-                    cursor.write_u8(BRANCH_NODE_NO_VALUE)?;
-                }
-                let mut bitmap: u16 = 0;
-                let mut pos: u16 = 1;
-                for child in children.iter() {
-                    if child.is_some() {
-                        bitmap |= pos
-                    }
-                    pos <<= 1;
-                }
-                cursor.write_u16::<LittleEndian>(bitmap)?;
-                for child in children.iter() {
-                    if let Some(hash) = child {
-                        cursor.write_all(hash.as_ref())?;
-                    }
-                }
-            }
-            // size <= 1 + 4 + key_length + 32
-            RawTrieNode::Extension(key, child) => {
-                cursor.write_u8(EXTENSION_NODE)?;
-                cursor.write_u32::<LittleEndian>(key.len() as u32)?;
-                cursor.write_all(key)?;
-                cursor.write_all(child.as_ref())?;
-            }
-        }
-        Ok(())
-    }
-
-    #[allow(dead_code)]
-    fn encode(&self) -> Result<Vec<u8>, std::io::Error> {
-        let mut out = Vec::new();
-        self.encode_into(&mut out)?;
-        Ok(out)
-    }
-
-    fn decode(bytes: &[u8]) -> Result<Self, std::io::Error> {
-        let mut cursor = Cursor::new(bytes);
-        match cursor.read_u8()? {
-            LEAF_NODE => {
-                let key_length = cursor.read_u32::<LittleEndian>()?;
-                let mut key = vec![0; key_length as usize];
-                cursor.read_exact(&mut key)?;
-                let value_length = cursor.read_u32::<LittleEndian>()?;
-                let mut arr = [0; 32];
-                cursor.read_exact(&mut arr)?;
-                let value_hash = CryptoHash(arr);
-                Ok(RawTrieNode::Leaf(key, value_length, value_hash))
-            }
-            BRANCH_NODE_NO_VALUE => {
-                let children = decode_children(&mut cursor)?;
-                Ok(RawTrieNode::Branch(children, None))
-            }
-            BRANCH_NODE_WITH_VALUE => {
-                let value_length = cursor.read_u32::<LittleEndian>()?;
-                let mut arr = [0; 32];
-                cursor.read_exact(&mut arr)?;
-                let value_hash = CryptoHash(arr);
-                let children = decode_children(&mut cursor)?;
-                Ok(RawTrieNode::Branch(children, Some((value_length, value_hash))))
-            }
-            EXTENSION_NODE => {
-                let key_length = cursor.read_u32::<LittleEndian>()?;
-                let mut key = vec![0; key_length as usize];
-                cursor.read_exact(&mut key)?;
-                let mut child = [0; 32];
-                cursor.read_exact(&mut child)?;
-                Ok(RawTrieNode::Extension(key, CryptoHash(child)))
-            }
-            _ => Err(std::io::Error::new(std::io::ErrorKind::Other, "Wrong type")),
-        }
-    }
-}
-
-impl RawTrieNodeWithSize {
-    fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), std::io::Error> {
-        self.node.encode_into(out)?;
-        out.write_u64::<LittleEndian>(self.memory_usage)
-    }
-
-    #[allow(dead_code)]
-    fn encode(&self) -> Result<Vec<u8>, std::io::Error> {
-        let mut out = Vec::new();
-        self.encode_into(&mut out)?;
-        Ok(out)
-    }
-
-    fn decode(bytes: &[u8]) -> Result<Self, std::io::Error> {
-        if bytes.len() < 8 {
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, "Wrong type"));
-        }
-        let node = RawTrieNode::decode(&bytes[0..bytes.len() - 8])?;
-        let mut arr: [u8; 8] = Default::default();
-        arr.copy_from_slice(&bytes[bytes.len() - 8..]);
-        let memory_usage = u64::from_le_bytes(arr);
-        Ok(RawTrieNodeWithSize { node, memory_usage })
-    }
-}
-
 pub struct Trie {
     pub(crate) storage: Box<dyn TrieStorage>,
 }
@@ -475,14 +326,19 @@ impl Trie {
     }
 
     pub fn recording_reads(&self) -> Self {
-        let storage =
-            self.storage.as_caching_storage().expect("Storage should be TrieCachingStorage");
+        self.try_recording_reads().expect("Storage should be TrieCachingStorage")
+    }
+
+    /// Same as `recording_reads`, but returns `None` if the storage isn't
+    /// `TrieCachingStorage`, e.g. when it is a partial storage itself.
+    pub fn try_recording_reads(&self) -> Option<Self> {
+        let storage = self.storage.as_caching_storage()?;
         let storage = TrieRecordingStorage {
             store: storage.store.clone(),
             shard_uid: storage.shard_uid,
             recorded: RefCell::new(Default::default()),
         };
-        Trie { storage: Box::new(storage) }
+        Some(Trie { storage: Box::new(storage) })
     }
 
     pub fn empty_root() -> StateRoot {
@@ -491,10 +347,9 @@ impl Trie {
 
     pub fn recorded_storage(&self) -> Option<PartialStorage> {
         let storage = self.storage.as_recording_storage()?;
-        let mut nodes: Vec<_> =
+        let nodes: Vec<_> =
             storage.recorded.borrow_mut().drain().map(|(_key, value)| value).collect();
-        nodes.sort();
-        Some(PartialStorage { nodes: PartialState(nodes) })
+        Some(PartialStorage { nodes: PartialState(proof_verifier::canonical_proof(nodes)) })
     }
 
     pub fn from_recorded_storage(partial_storage: PartialStorage) -> Self {
@@ -609,6 +464,37 @@ impl Trie {
         }
     }
 
+    /// Decodes an encoded trie node for debugging, or returns `None` if the bytes are not a trie
+    /// node, e.g. they are a value.
+    pub fn debug_decode_node(bytes: &[u8]) -> Option<TrieNodeView> {
+        RawTrieNodeWithSize::decode(bytes).ok().map(TrieNodeView::from)
+    }
+
+    /// Returns the node with the given hash, decoded for debugging, or `None` if the storage
+    /// doesn't have it.
+    pub fn debug_get_node(&self, hash: &CryptoHash) -> Result<Option<TrieNodeView>, StorageError> {
+        let bytes = match self.storage.as_caching_storage() {
+            Some(storage) => {
+                TrieCachingStorage::read_from_db(&storage.store, storage.shard_uid, hash)?
+            }
+            None => match self.storage.retrieve_raw_bytes(hash) {
+                Ok(bytes) => Some(bytes.to_vec()),
+                Err(StorageError::TrieNodeMissing) => None,
+                Err(err) => return Err(err),
+            },
+        };
+        bytes
+            .map(|bytes| {
+                Self::debug_decode_node(&bytes).ok_or_else(|| {
+                    StorageError::StorageInconsistentState(format!(
+                        "Failed to decode node {}",
+                        hash
+                    ))
+                })
+            })
+            .transpose()
+    }
+
     pub fn retrieve_root_node(&self, root: &StateRoot) -> Result<StateRootNode, StorageError> {
         if *root == Trie::empty_root() {
             return Ok(StateRootNode::empty());
@@ -626,10 +512,12 @@ impl Trie {
         }
     }
 
+    /// Looks `key` up, pushing the nodes on its path to `path` if given.
     fn lookup(
         &self,
         root: &CryptoHash,
         mut key: NibbleSlice<'_>,
+        mut path: Option<&mut Vec<Arc<[u8]>>>,
     ) -> Result<Option<(u32, CryptoHash)>, StorageError> {
         let mut hash = *root;
 
@@ -638,6 +526,9 @@ impl Trie {
                 return Ok(None);
             }
             let bytes = self.storage.retrieve_raw_bytes(&hash)?;
+            if let Some(path) = path.as_deref_mut() {
+                path.push(bytes.clone());
+            }
             let node = RawTrieNodeWithSize::decode(&bytes).map_err(|_| {
                 StorageError::StorageInconsistentState("RawTrieNode decode failed".to_string())
             })?;
@@ -687,7 +578,136 @@ impl Trie {
         key: &[u8],
     ) -> Result<Option<(u32, CryptoHash)>, StorageError> {
         let key = NibbleSlice::new(key);
-        self.lookup(root, key)
+        self.lookup(root, key, None)
+    }
+
+    /// Returns the value under `key` along with the nodes on the path to it, from the root to
+    /// the last node read, which prove the value, or its absence, against `root`. The proof
+    /// holds no other node and not the value itself, see `proof_verifier::verify_state_proof`.
+    pub fn get_with_proof(
+        &self,
+        root: &CryptoHash,
+        key: &[u8],
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), StorageError> {
+        let mut nodes = vec![];
+        let value = match self.lookup(root, NibbleSlice::new(key), Some(&mut nodes))? {
+            Some((_length, hash)) => Some(self.storage.retrieve_raw_bytes(&hash)?.to_vec()),
+            None => None,
+        };
+        Ok((value, nodes))
+    }
+
+    /// Returns the proof that no value is stored under `key`, or `None` if there is one.
+    ///
+    /// The proof is the encoded `RawTrieNodeWithSize` nodes on the path to `key`, in order from
+    /// the root to the node where the path diverges from `key`. Each node holds the hashes of all
+    /// its children, so the last one shows that `key` goes nowhere further. It is empty for the
+    /// empty trie. See `proof_verifier::verify_non_existence`.
+    pub fn prove_non_existence(
+        &self,
+        root: &CryptoHash,
+        key: &[u8],
+    ) -> Result<Option<Vec<Arc<[u8]>>>, StorageError> {
+        let mut nodes = vec![];
+        Ok(match self.lookup(root, NibbleSlice::new(key), Some(&mut nodes))? {
+            Some(_) => None,
+            None => Some(nodes),
+        })
+    }
+
+    /// Returns all the key/value pairs with keys in `[start, end)`, in key order, with the
+    /// encoded nodes the range goes through, in depth-first order and without the values. The
+    /// nodes pin both ends of the range, so that no pair can be left out, see
+    /// `proof_verifier::verify_range_proof`.
+    ///
+    /// Once `limit` nodes are collected, the proof is cut short, keeping at least one pair:
+    /// `next_key` is then the key of the first pair left out, and the proof covers the range up
+    /// to it. The next range to prove starts at `next_key`.
+    pub fn prove_range(
+        &self,
+        root: &CryptoHash,
+        start: &[u8],
+        end: &[u8],
+        limit: usize,
+    ) -> Result<proof_verifier::RangeProof, StorageError> {
+        let mut proof = proof_verifier::RangeProof::default();
+        if *root != Trie::empty_root() {
+            let range = proof_verifier::KeyRange::new(
+                &[],
+                std::ops::Bound::Included(start),
+                std::ops::Bound::Excluded(end),
+            );
+            self.prove_range_from(root, &mut vec![], &range, limit, &mut proof)?;
+        }
+        Ok(proof)
+    }
+
+    fn prove_range_from(
+        &self,
+        hash: &CryptoHash,
+        path: &mut Vec<u8>,
+        range: &proof_verifier::KeyRange,
+        limit: usize,
+        proof: &mut proof_verifier::RangeProof,
+    ) -> Result<(), StorageError> {
+        if proof.next_key.is_some() {
+            return Ok(());
+        }
+        let bytes = self.storage.retrieve_raw_bytes(hash)?;
+        let node = RawTrieNodeWithSize::decode(&bytes).map_err(|_| {
+            StorageError::StorageInconsistentState("RawTrieNode decode failed".to_string())
+        })?;
+        proof.nodes.push(bytes);
+        let path_len = path.len();
+        match node.node {
+            RawTrieNode::Leaf(key, _, value_hash) => {
+                path.extend(NibbleSlice::from_encoded(&key).0.iter());
+                if range.contains(path) {
+                    self.add_range_item(path, &value_hash, limit, proof)?;
+                }
+            }
+            RawTrieNode::Extension(key, child) => {
+                path.extend(NibbleSlice::from_encoded(&key).0.iter());
+                if range.intersects(path) {
+                    self.prove_range_from(&child, path, range, limit, proof)?;
+                }
+            }
+            RawTrieNode::Branch(children, value) => {
+                if let Some((_, value_hash)) = value {
+                    if range.contains(path) {
+                        self.add_range_item(path, &value_hash, limit, proof)?;
+                    }
+                }
+                for (nibble, child) in children.iter().enumerate() {
+                    if let Some(child) = child {
+                        path.push(nibble as u8);
+                        if range.intersects(path) {
+                            self.prove_range_from(child, path, range, limit, proof)?;
+                        }
+                        path.pop();
+                    }
+                }
+            }
+        }
+        path.truncate(path_len);
+        Ok(())
+    }
+
+    fn add_range_item(
+        &self,
+        path: &[u8],
+        value_hash: &CryptoHash,
+        limit: usize,
+        proof: &mut proof_verifier::RangeProof,
+    ) -> Result<(), StorageError> {
+        let key = proof_verifier::from_nibbles(path);
+        if !proof.items.is_empty() && proof.nodes.len() >= limit {
+            proof.next_key = Some(key);
+        } else {
+            let value = self.storage.retrieve_raw_bytes(value_hash)?;
+            proof.items.push((key, value.to_vec()));
+        }
+        Ok(())
     }
 
     pub fn get(&self, root: &CryptoHash, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
@@ -1026,6 +1046,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compute_new_root() {
+        let mut rng = rand::thread_rng();
+        let shard_uid = ShardUId::single_shard();
+        for _test_run in 0..20 {
+            let tries = create_tries();
+            let base_root = test_populate_trie(
+                &tries,
+                &Trie::empty_root(),
+                shard_uid,
+                gen_changes(&mut rng, 50),
+            );
+            let changes = gen_changes(&mut rng, 50);
+
+            let (new_root, nodes) =
+                tries.compute_new_root_with_nodes(shard_uid, &base_root, &changes).unwrap();
+            assert_eq!(tries.compute_new_root(shard_uid, &base_root, &changes).unwrap(), new_root);
+            // Nothing was written.
+            if new_root != base_root {
+                assert_eq!(tries.get_trie_node(shard_uid, &new_root).unwrap(), None);
+            }
+            for (node_hash, bytes) in &nodes {
+                assert_eq!(hash(bytes), *node_hash);
+            }
+
+            let trie = tries.get_trie_for_shard(shard_uid);
+            let trie_changes = trie.update(&base_root, changes.iter().cloned()).unwrap();
+            let (store_update, root) = tries.apply_all(&trie_changes, shard_uid);
+            store_update.commit().unwrap();
+            assert_eq!(root, new_root);
+            for (node_hash, bytes) in &nodes {
+                assert_eq!(
+                    tries.get_trie_node(shard_uid, node_hash).unwrap().as_ref(),
+                    Some(bytes)
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_iterator_seek() {
         let mut rng = rand::thread_rng();
@@ -1143,6 +1202,108 @@ mod tests {
         assert_eq!(trie3.get(&root, b"doge"), Err(StorageError::TrieNodeMissing));
     }
 
+    #[test]
+    fn test_trie_get_with_proof() {
+        let tries = create_tries();
+        let changes = vec![
+            (b"doge".to_vec(), Some(b"coin".to_vec())),
+            (b"docu".to_vec(), Some(b"value".to_vec())),
+            (b"do".to_vec(), Some(b"verb".to_vec())),
+            (b"horse".to_vec(), Some(b"stallion".to_vec())),
+            (b"dog".to_vec(), Some(b"puppy".to_vec())),
+            (b"h".to_vec(), Some(b"value".to_vec())),
+        ];
+        let root = test_populate_trie(
+            &tries,
+            &Trie::empty_root(),
+            ShardUId::single_shard(),
+            changes.clone(),
+        );
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard());
+
+        let absent: &[&[u8]] = &[b"d", b"dogs", b"docx", b"hors", b"x", b""];
+        let keys = changes.iter().map(|(key, _)| key.as_slice()).chain(absent.iter().copied());
+        for key in keys {
+            let (value, proof) = trie.get_with_proof(&root, key).unwrap();
+            assert_eq!(value, trie.get(&root, key).unwrap());
+            assert_eq!(hash(&proof[0]), root);
+            if let Some(value) = &value {
+                assert!(proof.iter().all(|node| **node != **value));
+            }
+            assert_eq!(
+                proof_verifier::verify_state_proof(&proof, &root, key, value.as_deref()),
+                Ok(())
+            );
+            // Every node is needed.
+            for i in 0..proof.len() {
+                let mut truncated = proof.clone();
+                truncated.remove(i);
+                assert!(proof_verifier::verify_state_proof(
+                    &truncated,
+                    &root,
+                    key,
+                    value.as_deref()
+                )
+                .is_err());
+            }
+        }
+
+        let (value, proof) = trie.get_with_proof(&Trie::empty_root(), b"doge").unwrap();
+        assert_eq!(value, None);
+        assert!(proof.is_empty());
+    }
+
+    #[test]
+    fn test_trie_debug_get_node() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let changes = vec![
+            (b"doge".to_vec(), Some(b"coin".to_vec())),
+            (b"docu".to_vec(), Some(b"value".to_vec())),
+            (b"do".to_vec(), Some(b"verb".to_vec())),
+            (b"horse".to_vec(), Some(b"stallion".to_vec())),
+        ];
+        let root = test_populate_trie(&tries, &Trie::empty_root(), shard_uid, changes.clone());
+        let trie = tries.get_trie_for_shard(shard_uid);
+
+        let mut iter = trie.iter(&root).unwrap();
+        iter.remember_visited_nodes(true);
+        iter.seek(b"").unwrap();
+        assert_eq!(iter.by_ref().count(), changes.len());
+        let nodes = iter.into_visited_nodes();
+        assert!(nodes.len() > changes.len());
+        for node in &nodes {
+            let node_hash = hash(node);
+            assert_eq!(
+                tries.get_trie_node(shard_uid, &node_hash).unwrap().as_deref(),
+                Some(&**node)
+            );
+            let view = trie.debug_get_node(&node_hash).unwrap().unwrap();
+            assert_eq!(Some(view), Trie::debug_decode_node(node));
+        }
+        let root_view = trie.debug_get_node(&root).unwrap().unwrap();
+        assert_eq!(root_view.memory_usage, trie.retrieve_root_node(&root).unwrap().memory_usage);
+
+        // Values are stored under their hash too, but they are not nodes.
+        let value_hash = hash(b"stallion");
+        assert_eq!(
+            tries.get_trie_node(shard_uid, &value_hash).unwrap(),
+            Some(b"stallion".to_vec())
+        );
+        assert!(Trie::debug_decode_node(b"stallion").is_none());
+
+        let unknown = hash(b"unknown");
+        assert_eq!(tries.get_trie_node(shard_uid, &unknown).unwrap(), None);
+        assert_eq!(trie.debug_get_node(&unknown).unwrap(), None);
+        let other_shard = ShardUId { version: 0, shard_id: 1 };
+        assert_eq!(tries.get_trie_node(other_shard, &root).unwrap(), None);
+        let partial_trie = Trie::from_recorded_storage(PartialStorage {
+            nodes: PartialState(nodes.iter().map(|node| node.to_vec()).collect()),
+        });
+        assert_eq!(partial_trie.debug_get_node(&root).unwrap(), Some(root_view));
+        assert_eq!(partial_trie.debug_get_node(&unknown).unwrap(), None);
+    }
+
     #[test]
     fn test_trie_recording_reads_update() {
         let store = create_test_store();
@@ -0,0 +1,839 @@
+//! Verification of state proofs: the trie nodes on the path to a key, as
+//! recorded by `Trie::recording_reads`, checked against a state root.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::Arc;
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::from_base64;
+use near_primitives::trie_key::trie_key_parsers::get_raw_prefix_for_contract_data;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::{AccountId, StateRoot};
+use near_primitives::views::{StateItem, ViewStateResult};
+
+use crate::trie::encoding::{
+    self, decode_nibbles, key_to_nibbles, nibbles_to_key, step, RawTrieNode, RawTrieNodeWithSize,
+    Step,
+};
+use crate::Trie;
+
+/// Why a state proof doesn't verify.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProofError {
+    #[error("no node of the proof hashes to the state root")]
+    RootMismatch,
+    #[error("node {0} on the path to the key is missing from the proof")]
+    MissingNode(CryptoHash),
+    #[error("node {0} of the proof can't be decoded")]
+    MalformedNode(CryptoHash),
+    #[error("the key is absent while a value was expected")]
+    KeyAbsent,
+    #[error("the key is present while it was expected to be absent")]
+    UnexpectedValue,
+    #[error("the key maps to another value than the expected one")]
+    ValueMismatch,
+    #[error("the result can't be decoded")]
+    InvalidEncoding,
+    #[error("the item with key {0:?} is missing from the result")]
+    MissingItem(Vec<u8>),
+    #[error("the item with key {0:?} isn't in the state")]
+    UnexpectedItem(Vec<u8>),
+    #[error("the next key of the result isn't the key of its last item")]
+    NextKeyMismatch,
+    #[error("the proof has nodes past the end of the path to the key")]
+    TrailingNodes,
+}
+
+impl ProofError {
+    /// Whether the proof itself is invalid, as opposed to a valid proof of
+    /// another state than the expected one.
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            Self::RootMismatch
+                | Self::MissingNode(_)
+                | Self::MalformedNode(_)
+                | Self::InvalidEncoding
+                | Self::TrailingNodes
+        )
+    }
+}
+
+/// Range of raw trie keys, as nibbles: the keys starting with `prefix`
+/// within the `lower` and `upper` bounds.
+pub(crate) struct KeyRange {
+    prefix: Vec<u8>,
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+}
+
+impl KeyRange {
+    pub(crate) fn new(prefix: &[u8], lower: Bound<&[u8]>, upper: Bound<&[u8]>) -> Self {
+        let nibble_bound = |bound: Bound<&[u8]>| match bound {
+            Bound::Included(key) => Bound::Included(key_to_nibbles(key)),
+            Bound::Excluded(key) => Bound::Excluded(key_to_nibbles(key)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        Self {
+            prefix: key_to_nibbles(prefix),
+            lower: nibble_bound(lower),
+            upper: nibble_bound(upper),
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &[u8]) -> bool {
+        key.len() % 2 == 0
+            && key.starts_with(&self.prefix)
+            && match &self.lower {
+                Bound::Included(lower) => key >= lower.as_slice(),
+                Bound::Excluded(lower) => key > lower.as_slice(),
+                Bound::Unbounded => true,
+            }
+            && match &self.upper {
+                Bound::Included(upper) => key <= upper.as_slice(),
+                Bound::Excluded(upper) => key < upper.as_slice(),
+                Bound::Unbounded => true,
+            }
+    }
+
+    /// Whether some key starting with `path` may be in the range.
+    pub(crate) fn intersects(&self, path: &[u8]) -> bool {
+        (path.starts_with(&self.prefix) || self.prefix.starts_with(path))
+            && match &self.lower {
+                Bound::Included(lower) | Bound::Excluded(lower) => {
+                    path >= lower.as_slice() || lower.starts_with(path)
+                }
+                Bound::Unbounded => true,
+            }
+            && match &self.upper {
+                Bound::Included(upper) => path <= upper.as_slice(),
+                Bound::Excluded(upper) => path < upper.as_slice(),
+                Bound::Unbounded => true,
+            }
+    }
+}
+
+/// Key/value pairs of a range of the trie, with the proof that they are all
+/// the pairs of the range, see `Trie::prove_range`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RangeProof {
+    pub items: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Trie nodes the range goes through, without the values.
+    pub nodes: Vec<Arc<[u8]>>,
+    /// Key of the first pair left out when the proof was cut short by the
+    /// node limit. The proof then covers the range up to this key.
+    pub next_key: Option<Vec<u8>>,
+}
+
+/// Looks keys up in a trie using only a set of its nodes.
+///
+/// The nodes may come in any order and may include unrelated nodes and
+/// values. A lookup fails if a node on the path to the key is missing.
+pub struct ProofVerifier<'a> {
+    nodes: HashMap<CryptoHash, &'a [u8]>,
+}
+
+impl<'a> ProofVerifier<'a> {
+    pub fn new<N: AsRef<[u8]>>(nodes: &'a [N]) -> Self {
+        let nodes =
+            nodes.iter().map(|node| (encoding::node_hash(node.as_ref()), node.as_ref())).collect();
+        Self { nodes }
+    }
+
+    /// Returns the length and hash of the value stored under the raw trie
+    /// `key`, or `None` if the nodes prove that `key` is absent.
+    pub fn lookup(
+        &self,
+        root: &StateRoot,
+        key: &[u8],
+    ) -> Result<Option<(u32, CryptoHash)>, ProofError> {
+        self.walk(root, key, |_| ())
+    }
+
+    /// Returns the hashes of the nodes on the path to the raw trie `key`, in order from the
+    /// root. These nodes are enough for `lookup` of `key`.
+    pub fn path(&self, root: &StateRoot, key: &[u8]) -> Result<Vec<CryptoHash>, ProofError> {
+        let mut path = vec![];
+        self.walk(root, key, |node_hash| path.push(*node_hash))?;
+        Ok(path)
+    }
+
+    /// Follows the path to the raw trie `key`, calling `visit` on the hash of each node on the
+    /// way, and returns what `lookup` does.
+    fn walk(
+        &self,
+        root: &StateRoot,
+        key: &[u8],
+        mut visit: impl FnMut(&CryptoHash),
+    ) -> Result<Option<(u32, CryptoHash)>, ProofError> {
+        if *root == Trie::empty_root() {
+            return Ok(None);
+        }
+        let nibbles = key_to_nibbles(key);
+        let mut key = &nibbles[..];
+        let mut node_hash = *root;
+        loop {
+            let bytes = match self.nodes.get(&node_hash) {
+                Some(bytes) => bytes,
+                None if node_hash == *root => return Err(ProofError::RootMismatch),
+                None => return Err(ProofError::MissingNode(node_hash)),
+            };
+            visit(&node_hash);
+            match step(bytes, key).map_err(|_| ProofError::MalformedNode(node_hash))? {
+                Step::End(value) => return Ok(value),
+                Step::Child(child, rest) => {
+                    node_hash = child;
+                    key = rest;
+                }
+            }
+        }
+    }
+
+    /// Verifies that the raw trie `key` maps to `expected`, or that it is
+    /// absent if `expected` is `None`.
+    pub fn verify(
+        &self,
+        root: &StateRoot,
+        key: &[u8],
+        expected: Option<&[u8]>,
+    ) -> Result<(), ProofError> {
+        match (self.lookup(root, key)?, expected) {
+            (None, None) => Ok(()),
+            (None, Some(_)) => Err(ProofError::KeyAbsent),
+            (Some(_), None) => Err(ProofError::UnexpectedValue),
+            (Some((value_length, value_hash)), Some(value))
+                if value_length as usize == value.len()
+                    && value_hash == encoding::node_hash(value) =>
+            {
+                Ok(())
+            }
+            (Some(_), Some(_)) => Err(ProofError::ValueMismatch),
+        }
+    }
+
+    /// Returns the raw keys, with the length and hash of their values, of
+    /// all the values whose key starts with `prefix`, is strictly greater
+    /// than `after` and at most `until`, in key order.
+    ///
+    /// Fails if a node the range goes through is missing, so the returned
+    /// values are all the values of the range.
+    pub fn values_in_range(
+        &self,
+        root: &StateRoot,
+        prefix: &[u8],
+        after: Option<&[u8]>,
+        until: Option<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, (u32, CryptoHash))>, ProofError> {
+        let range = KeyRange::new(
+            prefix,
+            after.map_or(Bound::Unbounded, Bound::Excluded),
+            until.map_or(Bound::Unbounded, Bound::Included),
+        );
+        self.values_in(root, &range)
+    }
+
+    fn values_in(
+        &self,
+        root: &StateRoot,
+        range: &KeyRange,
+    ) -> Result<Vec<(Vec<u8>, (u32, CryptoHash))>, ProofError> {
+        let mut values = vec![];
+        if *root == Trie::empty_root() {
+            return Ok(values);
+        }
+        if !self.nodes.contains_key(root) {
+            return Err(ProofError::RootMismatch);
+        }
+        self.collect_values(root, &mut vec![], range, &mut values)?;
+        Ok(values)
+    }
+
+    fn collect_values(
+        &self,
+        node_hash: &CryptoHash,
+        path: &mut Vec<u8>,
+        range: &KeyRange,
+        values: &mut Vec<(Vec<u8>, (u32, CryptoHash))>,
+    ) -> Result<(), ProofError> {
+        let bytes = self.nodes.get(node_hash).ok_or(ProofError::MissingNode(*node_hash))?;
+        let malformed = || ProofError::MalformedNode(*node_hash);
+        let node = RawTrieNodeWithSize::decode(bytes).map_err(|_| malformed())?.node;
+        let path_len = path.len();
+        match node {
+            RawTrieNode::Leaf(leaf_key, value_length, value_hash) => {
+                path.extend(decode_nibbles(&leaf_key).map_err(|_| malformed())?.0);
+                if range.contains(path) {
+                    values.push((nibbles_to_key(path), (value_length, value_hash)));
+                }
+            }
+            RawTrieNode::Extension(extension_key, child) => {
+                path.extend(decode_nibbles(&extension_key).map_err(|_| malformed())?.0);
+                if range.intersects(path) {
+                    self.collect_values(&child, path, range, values)?;
+                }
+            }
+            RawTrieNode::Branch(children, value) => {
+                if let Some(value) = value {
+                    if range.contains(path) {
+                        values.push((nibbles_to_key(path), value));
+                    }
+                }
+                for (nibble, child) in children.iter().enumerate() {
+                    if let Some(child) = child {
+                        path.push(nibble as u8);
+                        if range.intersects(path) {
+                            self.collect_values(child, path, range, values)?;
+                        }
+                        path.pop();
+                    }
+                }
+            }
+        }
+        path.truncate(path_len);
+        Ok(())
+    }
+}
+
+/// Verifies that the raw trie `key` maps to `expected` in the trie with the
+/// given `root`, or that it is absent if `expected` is `None`, using the
+/// trie `nodes` of the proof.
+pub fn verify_state_proof<N: AsRef<[u8]>>(
+    nodes: &[N],
+    root: &StateRoot,
+    key: &[u8],
+    expected: Option<&[u8]>,
+) -> Result<(), ProofError> {
+    ProofVerifier::new(nodes).verify(root, key, expected)
+}
+
+/// Verifies that no value is stored under the raw trie `key` in the trie
+/// with the given `root`, using a proof from `Trie::prove_non_existence`:
+/// the encoded nodes on the path to `key`, in order from the root to the
+/// node where the path diverges from `key`, and no other node.
+pub fn verify_non_existence<N: AsRef<[u8]>>(
+    root: &StateRoot,
+    key: &[u8],
+    proof: &[N],
+) -> Result<(), ProofError> {
+    let mut nodes = proof.iter().map(|node| node.as_ref());
+    if *root != Trie::empty_root() {
+        let nibbles = key_to_nibbles(key);
+        let mut key = &nibbles[..];
+        let mut node_hash = *root;
+        loop {
+            let bytes = match nodes.next() {
+                Some(bytes) if encoding::node_hash(bytes) == node_hash => bytes,
+                _ if node_hash == *root => return Err(ProofError::RootMismatch),
+                _ => return Err(ProofError::MissingNode(node_hash)),
+            };
+            match step(bytes, key).map_err(|_| ProofError::MalformedNode(node_hash))? {
+                Step::End(None) => break,
+                Step::End(Some(_)) => return Err(ProofError::UnexpectedValue),
+                Step::Child(child, rest) => {
+                    node_hash = child;
+                    key = rest;
+                }
+            }
+        }
+    }
+    match nodes.next() {
+        Some(_) => Err(ProofError::TrailingNodes),
+        None => Ok(()),
+    }
+}
+
+/// Verifies that `result`, returned by a `view_state` query of the contract
+/// data of `account_id` under `prefix` from `from_key`, has every item of
+/// the range it covers in the trie with the given `root`, and only those.
+///
+/// The range ends at the last item of a truncated page, when `next_key` is
+/// set, and at the end of `prefix` otherwise. Elided values are checked
+/// against their hash and length.
+pub fn verify_view_state_result(
+    root: &StateRoot,
+    account_id: &AccountId,
+    prefix: &[u8],
+    from_key: Option<&[u8]>,
+    result: &ViewStateResult,
+) -> Result<(), ProofError> {
+    let decode = |s: &str| from_base64(s).map_err(|_| ProofError::InvalidEncoding);
+    let nodes = result.proof.iter().map(|node| decode(node)).collect::<Result<Vec<_>, _>>()?;
+    let keys = result.values.iter().map(|item| decode(&item.key)).collect::<Result<Vec<_>, _>>()?;
+    let until = match &result.next_key {
+        Some(next_key) => match keys.last() {
+            Some(last_key) if decode(next_key)? == *last_key => {
+                Some(contract_data_key(account_id, last_key))
+            }
+            _ => return Err(ProofError::NextKeyMismatch),
+        },
+        None => None,
+    };
+    let query = get_raw_prefix_for_contract_data(account_id, prefix);
+    let acc_sep_len = query.len() - prefix.len();
+    let after = from_key.map(|from_key| contract_data_key(account_id, from_key));
+    let expected = ProofVerifier::new(&nodes).values_in_range(
+        root,
+        &query,
+        after.as_deref(),
+        until.as_deref(),
+    )?;
+
+    let expected =
+        expected.into_iter().map(|(key, value_ref)| (key[acc_sep_len..].to_vec(), value_ref));
+    let mut items = vec![];
+    for (item, key) in result.values.iter().zip(keys) {
+        let (length, value_hash) = item_value_ref(item)?;
+        items.push((key, length, value_hash));
+    }
+    check_items(expected, items)
+}
+
+/// Verifies a single `item` of a `view_state` result of the contract data of `account_id`
+/// against the given `root`, using only the trie `nodes` its `proof_node_indices` refer to in
+/// the proof of the result. Elided values are checked against their hash and length.
+pub fn verify_view_state_item<N: AsRef<[u8]>>(
+    root: &StateRoot,
+    account_id: &AccountId,
+    item: &StateItem,
+    nodes: &[N],
+) -> Result<(), ProofError> {
+    let key = from_base64(&item.key).map_err(|_| ProofError::InvalidEncoding)?;
+    let (length, value_hash) = item_value_ref(item)?;
+    match ProofVerifier::new(nodes).lookup(root, &contract_data_key(account_id, &key))? {
+        Some((value_length, value_ref_hash))
+            if value_length as u64 == length && value_ref_hash == value_hash =>
+        {
+            Ok(())
+        }
+        Some(_) => Err(ProofError::ValueMismatch),
+        None => Err(ProofError::KeyAbsent),
+    }
+}
+
+/// Length and hash of the value of a `view_state` item, whether or not it is elided.
+fn item_value_ref(item: &StateItem) -> Result<(u64, CryptoHash), ProofError> {
+    Ok(match &item.elided_value {
+        Some(elided) => (elided.length, elided.hash),
+        None => {
+            let value = from_base64(&item.value).map_err(|_| ProofError::InvalidEncoding)?;
+            (value.len() as u64, encoding::node_hash(&value))
+        }
+    })
+}
+
+/// Verifies that `items` are all the key/value pairs with keys in
+/// `[start, end)` in the trie with the given `root`, in key order, using the
+/// nodes of a proof from `Trie::prove_range`. For a proof cut short, `end`
+/// is its `next_key`.
+pub fn verify_range_proof<N: AsRef<[u8]>>(
+    root: &StateRoot,
+    start: &[u8],
+    end: &[u8],
+    items: &[(Vec<u8>, Vec<u8>)],
+    proof: &[N],
+) -> Result<(), ProofError> {
+    let range = KeyRange::new(&[], Bound::Included(start), Bound::Excluded(end));
+    let expected = ProofVerifier::new(proof).values_in(root, &range)?;
+    let items = items
+        .iter()
+        .map(|(key, value)| (key.clone(), value.len() as u64, encoding::node_hash(value)))
+        .collect();
+    check_items(expected, items)
+}
+
+/// Puts the nodes of a proof in canonical order, so that proofs made of the
+/// same nodes are equal whatever order the nodes were read in: each node
+/// once, the root first, then the nodes level by level, as in a breadth-first
+/// traversal, and by hash within a level. Values come after the node which
+/// refers to them. Nodes no other node of the proof refers to are roots, so
+/// the order is the same whether or not the proof holds the values.
+pub fn canonical_proof<N: AsRef<[u8]>>(nodes: Vec<N>) -> Vec<N> {
+    let mut nodes: BTreeMap<CryptoHash, N> =
+        nodes.into_iter().map(|node| (encoding::node_hash(node.as_ref()), node)).collect();
+    let children: HashMap<CryptoHash, Vec<CryptoHash>> =
+        nodes.iter().map(|(node_hash, node)| (*node_hash, node_children(node.as_ref()))).collect();
+    let referenced: HashSet<&CryptoHash> = children.values().flatten().collect();
+    let mut level: Vec<CryptoHash> =
+        nodes.keys().filter(|node_hash| !referenced.contains(node_hash)).copied().collect();
+    let mut ordered = Vec::with_capacity(nodes.len());
+    while !level.is_empty() {
+        let mut next_level = BTreeSet::new();
+        for node_hash in level {
+            if let Some(node) = nodes.remove(&node_hash) {
+                ordered.push(node);
+                next_level.extend(children[&node_hash].iter().filter(|h| nodes.contains_key(h)));
+            }
+        }
+        level = next_level.into_iter().collect();
+    }
+    ordered.extend(nodes.into_values());
+    ordered
+}
+
+/// Hashes of the children and value of an encoded trie node, or nothing if
+/// the bytes aren't a node.
+fn node_children(bytes: &[u8]) -> Vec<CryptoHash> {
+    match RawTrieNodeWithSize::decode(bytes).map(|node| node.node) {
+        Ok(RawTrieNode::Leaf(_, _, value_hash)) => vec![value_hash],
+        Ok(RawTrieNode::Extension(_, child)) => vec![child],
+        Ok(RawTrieNode::Branch(children, value)) => children
+            .iter()
+            .flatten()
+            .copied()
+            .chain(value.map(|(_, value_hash)| value_hash))
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Checks that the keys, value lengths and value hashes of `items` are the
+/// `expected` ones.
+fn check_items(
+    expected: impl IntoIterator<Item = (Vec<u8>, (u32, CryptoHash))>,
+    items: Vec<(Vec<u8>, u64, CryptoHash)>,
+) -> Result<(), ProofError> {
+    let mut items = items.into_iter();
+    for (key, (value_length, value_hash)) in expected {
+        let (item_key, item_length, item_hash) = match items.next() {
+            Some(item) => item,
+            None => return Err(ProofError::MissingItem(key)),
+        };
+        if item_key > key {
+            return Err(ProofError::MissingItem(key));
+        }
+        if item_key < key {
+            return Err(ProofError::UnexpectedItem(item_key));
+        }
+        if item_length != value_length as u64 || item_hash != value_hash {
+            return Err(ProofError::ValueMismatch);
+        }
+    }
+    match items.next() {
+        Some((item_key, _, _)) => Err(ProofError::UnexpectedItem(item_key)),
+        None => Ok(()),
+    }
+}
+
+/// Raw trie key of the contract data `key` of `account_id`.
+pub fn contract_data_key(account_id: &AccountId, key: &[u8]) -> Vec<u8> {
+    TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() }.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use near_primitives::hash::hash;
+
+    use super::*;
+    use crate::test_utils::{create_tries, test_populate_trie};
+    use crate::ShardUId;
+
+    fn populate(changes: &[(&str, &str)]) -> (Trie, StateRoot) {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let changes = changes
+            .iter()
+            .map(|(key, value)| (key.as_bytes().to_vec(), Some(value.as_bytes().to_vec())))
+            .collect();
+        let root = test_populate_trie(&tries, &Trie::empty_root(), shard_uid, changes);
+        (tries.get_trie_for_shard(shard_uid), root)
+    }
+
+    /// Records the nodes on the path to `key`, leaving out its value.
+    fn prove(trie: &Trie, root: &StateRoot, key: &[u8]) -> Vec<Vec<u8>> {
+        let trie = trie.recording_reads();
+        trie.get_ref(root, key).unwrap();
+        trie.recorded_storage().unwrap().nodes.0
+    }
+
+    /// Records the nodes visited while iterating over the keys under `prefix`.
+    fn record_range(trie: &Trie, root: &StateRoot, prefix: &[u8]) -> Vec<Vec<u8>> {
+        let trie = trie.recording_reads();
+        let mut iter = trie.iter(root).unwrap();
+        iter.seek(prefix).unwrap();
+        for item in iter {
+            if !item.unwrap().0.starts_with(prefix) {
+                break;
+            }
+        }
+        trie.recorded_storage().unwrap().nodes.0
+    }
+
+    const CHANGES: &[(&str, &str)] =
+        &[("aaa", "1"), ("aab", "2"), ("abc", "3"), ("b", "4"), ("bcd", "5")];
+
+    #[test]
+    fn test_verify_state_proof() {
+        let (trie, root) = populate(CHANGES);
+        for (key, value) in CHANGES {
+            let key = key.as_bytes();
+            let proof = prove(&trie, &root, key);
+            assert_eq!(verify_state_proof(&proof, &root, key, Some(value.as_bytes())), Ok(()));
+            assert_eq!(
+                verify_state_proof(&proof, &root, key, Some(b"6")),
+                Err(ProofError::ValueMismatch)
+            );
+            assert_eq!(
+                verify_state_proof(&proof, &root, key, None),
+                Err(ProofError::UnexpectedValue)
+            );
+        }
+        for key in [b"a".as_slice(), b"aa", b"aac", b"abcd", b"bc", b"c", b""] {
+            let proof = prove(&trie, &root, key);
+            assert_eq!(verify_state_proof(&proof, &root, key, None), Ok(()));
+            assert_eq!(
+                verify_state_proof(&proof, &root, key, Some(b"1")),
+                Err(ProofError::KeyAbsent)
+            );
+        }
+        let no_nodes: &[Vec<u8>] = &[];
+        assert_eq!(verify_state_proof(no_nodes, &Trie::empty_root(), b"aaa", None), Ok(()));
+    }
+
+    #[test]
+    fn test_corrupted_proof() {
+        let (trie, root) = populate(CHANGES);
+        let proof = prove(&trie, &root, b"aab");
+        assert!(proof.len() > 1);
+        for i in 0..proof.len() {
+            let mut corrupted = proof.clone();
+            *corrupted[i].last_mut().unwrap() ^= 1;
+            let err = verify_state_proof(&corrupted, &root, b"aab", Some(b"2")).unwrap_err();
+            assert!(err.is_malformed(), "{:?}", err);
+        }
+        let err = verify_state_proof(&proof, &hash(b"root"), b"aab", Some(b"2")).unwrap_err();
+        assert_eq!(err, ProofError::RootMismatch);
+
+        let garbage = vec![vec![42u8; 20]];
+        let root = hash(&garbage[0]);
+        assert_eq!(
+            verify_state_proof(&garbage, &root, b"aab", None),
+            Err(ProofError::MalformedNode(root))
+        );
+    }
+
+    #[test]
+    fn test_truncated_proof() {
+        let (trie, root) = populate(CHANGES);
+        let proof = prove(&trie, &root, b"aab");
+        for i in 0..proof.len() {
+            let mut truncated = proof.clone();
+            truncated.remove(i);
+            let err = verify_state_proof(&truncated, &root, b"aab", Some(b"2")).unwrap_err();
+            assert!(err.is_malformed(), "{:?}", err);
+        }
+        // Nodes of the proof of another key don't cover the path to this one.
+        let other_proof = prove(&trie, &root, b"bcd");
+        let err = verify_state_proof(&other_proof, &root, b"aab", Some(b"2")).unwrap_err();
+        assert!(matches!(err, ProofError::MissingNode(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn test_values_in_range() {
+        let (trie, root) = populate(CHANGES);
+        let proof = record_range(&trie, &root, b"a");
+        let verifier = ProofVerifier::new(&proof);
+        let keys = |after: Option<&[u8]>, until: Option<&[u8]>| {
+            let values = verifier.values_in_range(&root, b"a", after, until).unwrap();
+            values.into_iter().map(|(key, _)| String::from_utf8(key).unwrap()).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(None, None), ["aaa", "aab", "abc"]);
+        assert_eq!(keys(Some(b"aaa"), None), ["aab", "abc"]);
+        assert_eq!(keys(None, Some(b"aab")), ["aaa", "aab"]);
+        assert_eq!(keys(Some(b"aaa"), Some(b"aab")), ["aab"]);
+        assert_eq!(keys(Some(b"ab"), Some(b"abb")), Vec::<String>::new());
+        assert_eq!(
+            verifier.values_in_range(&root, b"abc", None, None).unwrap(),
+            [(b"abc".to_vec(), (1, hash(b"3")))]
+        );
+
+        // Nodes of the range under another prefix don't cover this one.
+        let other_proof = record_range(&trie, &root, b"b");
+        let err = ProofVerifier::new(&other_proof).values_in_range(&root, b"a", None, None);
+        assert!(matches!(err, Err(ProofError::MissingNode(_))), "{:?}", err);
+    }
+
+    #[test]
+    fn test_verify_non_existence() {
+        let long_prefix = "a_long_prefix_shared_by_keys_";
+        let long_keys = [format!("{}1", long_prefix), format!("{}2", long_prefix)];
+        let mut changes = CHANGES.to_vec();
+        changes.extend(long_keys.iter().map(|key| (key.as_str(), "6")));
+        let (trie, root) = populate(&changes);
+
+        let strict_prefixes = ["a", "aa", "ab", "bc"];
+        let long_prefix_keys = [&long_prefix[..10], long_prefix, &format!("{}3", long_prefix)];
+        let others = ["aac", "abcd", "c", ""];
+        for key in strict_prefixes.iter().chain(&long_prefix_keys).chain(&others) {
+            let key = key.as_bytes();
+            let proof = trie.prove_non_existence(&root, key).unwrap().unwrap();
+            assert_eq!(verify_non_existence(&root, key, &proof), Ok(()));
+            assert_eq!(hash(&proof[0]), root);
+            assert_eq!(
+                verify_non_existence(&hash(b"root"), key, &proof),
+                Err(ProofError::RootMismatch)
+            );
+            if proof.len() > 1 {
+                let mut reversed = proof.clone();
+                reversed.reverse();
+                assert!(verify_non_existence(&root, key, &reversed).unwrap_err().is_malformed());
+                let mut truncated = proof.clone();
+                truncated.pop();
+                assert!(verify_non_existence(&root, key, &truncated).unwrap_err().is_malformed());
+            }
+            let mut extended = proof.clone();
+            extended.push(proof[0].clone());
+            assert_eq!(verify_non_existence(&root, key, &extended), Err(ProofError::TrailingNodes));
+        }
+
+        for key in ["aab", "b", long_keys[0].as_str()] {
+            let key = key.as_bytes();
+            assert_eq!(trie.prove_non_existence(&root, key).unwrap(), None);
+            let (_, path) = trie.get_with_proof(&root, key).unwrap();
+            assert_eq!(verify_non_existence(&root, key, &path), Err(ProofError::UnexpectedValue));
+        }
+
+        let empty_root = Trie::empty_root();
+        let proof = trie.prove_non_existence(&empty_root, b"aab").unwrap().unwrap();
+        assert!(proof.is_empty());
+        assert_eq!(verify_non_existence(&empty_root, b"aab", &proof), Ok(()));
+        let other_proof = trie.prove_non_existence(&root, b"aac").unwrap().unwrap();
+        assert_eq!(
+            verify_non_existence(&empty_root, b"aab", &other_proof),
+            Err(ProofError::TrailingNodes)
+        );
+    }
+
+    #[test]
+    fn test_verify_range_proof() {
+        let mut changes = CHANGES.to_vec();
+        changes.extend([("a_long_prefix_1", "6"), ("a_long_prefix_2", "7")]);
+        let (trie, root) = populate(&changes);
+        let pairs = |start: &str, end: &str| {
+            let mut pairs: Vec<_> = changes
+                .iter()
+                .filter(|(key, _)| start <= *key && *key < end)
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+                .collect();
+            pairs.sort();
+            pairs
+        };
+
+        let ranges = [
+            ("", "z"),
+            ("a", "b"),
+            ("aab", "bcd"),
+            // Starting and ending within the extension of the long keys.
+            ("a_lo", "a_long_prefix_2"),
+            ("a_long_prefix_1", "a_long_prefix_3"),
+            // Empty ranges.
+            ("a_lo", "a_lon"),
+            ("aac", "ab"),
+            ("c", "d"),
+            ("b", "b"),
+            ("bcd", "a"),
+        ];
+        for (start, end) in ranges {
+            let expected = pairs(start, end);
+            let (start, end) = (start.as_bytes(), end.as_bytes());
+            let proof = trie.prove_range(&root, start, end, usize::MAX).unwrap();
+            let nodes = &proof.nodes;
+            assert_eq!(proof.next_key, None);
+            assert_eq!(proof.items, expected);
+            assert_eq!(verify_range_proof(&root, start, end, &proof.items, nodes), Ok(()));
+            assert!(verify_range_proof(&hash(b"root"), start, end, &proof.items, nodes).is_err());
+            // Every node is needed to pin the range.
+            for i in 0..nodes.len() {
+                let mut truncated = nodes.clone();
+                truncated.remove(i);
+                let err = verify_range_proof(&root, start, end, &proof.items, &truncated);
+                assert!(err.unwrap_err().is_malformed());
+            }
+            if proof.items.is_empty() {
+                continue;
+            }
+            for i in 0..proof.items.len() {
+                let mut items = proof.items.clone();
+                let (key, _) = items.remove(i);
+                assert_eq!(
+                    verify_range_proof(&root, start, end, &items, nodes),
+                    Err(ProofError::MissingItem(key))
+                );
+            }
+            let mut items = proof.items.clone();
+            items[0].1.push(0);
+            assert_eq!(
+                verify_range_proof(&root, start, end, &items, nodes),
+                Err(ProofError::ValueMismatch)
+            );
+            let mut items = proof.items.clone();
+            items.push((b"zzz".to_vec(), b"1".to_vec()));
+            assert_eq!(
+                verify_range_proof(&root, start, end, &items, nodes),
+                Err(ProofError::UnexpectedItem(b"zzz".to_vec()))
+            );
+        }
+
+        // With a node limit, the range is proven piece by piece.
+        let mut items = vec![];
+        let mut start = vec![];
+        loop {
+            let proof = trie.prove_range(&root, &start, b"z", 1).unwrap();
+            assert_eq!(proof.items.len(), 1);
+            let end = proof.next_key.clone().unwrap_or_else(|| b"z".to_vec());
+            assert_eq!(verify_range_proof(&root, &start, &end, &proof.items, &proof.nodes), Ok(()));
+            items.extend(proof.items);
+            match proof.next_key {
+                Some(next_key) => start = next_key,
+                None => break,
+            }
+        }
+        assert_eq!(items, pairs("", "z"));
+
+        let proof = trie.prove_range(&Trie::empty_root(), b"", b"z", usize::MAX).unwrap();
+        assert_eq!(proof, RangeProof::default());
+        assert_eq!(verify_range_proof(&Trie::empty_root(), b"", b"z", &[], &proof.nodes), Ok(()));
+    }
+
+    #[test]
+    fn test_contract_data_key() {
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let key = contract_data_key(&account_id, b"key");
+        assert_eq!(key, get_raw_prefix_for_contract_data(&account_id, b"key"));
+        assert!(key.starts_with(&get_raw_prefix_for_contract_data(&account_id, b"")));
+    }
+
+    #[test]
+    fn test_canonical_proof() {
+        let (trie, root) = populate(CHANGES);
+        let proof = record_range(&trie, &root, b"a");
+        // Recording again reads the nodes in another order, but emits them in the same one.
+        for _ in 0..10 {
+            assert_eq!(record_range(&trie, &root, b"a"), proof);
+        }
+        assert_eq!(hash(&proof[0]), root);
+        assert_eq!(canonical_proof(proof.clone()), proof);
+
+        // Leaving the values out keeps the order of the nodes.
+        let values: Vec<&[u8]> = vec![b"1", b"2", b"3", b"4"];
+        assert!(values.iter().all(|value| proof.iter().any(|node| node == value)));
+        let nodes: Vec<_> = proof.iter().filter(|node| !values.contains(&&node[..])).collect();
+        assert_eq!(canonical_proof(nodes.iter().rev().cloned().collect()), nodes);
+
+        // The order doesn't matter for verification, and any order is made canonical.
+        for shift in 0..proof.len() {
+            let mut shuffled = proof.clone();
+            shuffled.rotate_left(shift);
+            shuffled.reverse();
+            shuffled.push(shuffled[0].clone());
+            for (key, value) in &CHANGES[..3] {
+                let key = key.as_bytes();
+                assert_eq!(
+                    verify_state_proof(&shuffled, &root, key, Some(value.as_bytes())),
+                    Ok(())
+                );
+            }
+            assert_eq!(canonical_proof(shuffled), proof);
+        }
+        assert_eq!(canonical_proof(Vec::<Vec<u8>>::new()), Vec::<Vec<u8>>::new());
+    }
+}
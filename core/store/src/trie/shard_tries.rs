@@ -12,10 +12,11 @@ use near_primitives::types::{
     NumShards, RawStateChange, RawStateChangesWithTrieKey, StateChangeCause, StateRoot,
 };
 
+use crate::trie::flat_contract_data::FlatContractData;
 use crate::trie::trie_storage::{TrieCache, TrieCachingStorage};
 use crate::trie::{TrieRefcountChange, POISONED_LOCK_ERR};
 use crate::{DBCol, DBOp, DBTransaction};
-use crate::{Store, StoreUpdate, Trie, TrieChanges, TrieUpdate};
+use crate::{StorageError, Store, StoreUpdate, Trie, TrieChanges, TrieUpdate};
 
 /// Responsible for creation of trie caches, stores necessary configuration for it.
 #[derive(Default)]
@@ -117,6 +118,16 @@ impl ShardTries {
         self.0.store.clone()
     }
 
+    /// Returns the encoded trie node, or value, with the given hash in the state of the shard,
+    /// or `None` if there is none. Used for debugging.
+    pub fn get_trie_node(
+        &self,
+        shard_uid: ShardUId,
+        hash: &CryptoHash,
+    ) -> Result<Option<Vec<u8>>, StorageError> {
+        TrieCachingStorage::read_from_db(&self.0.store, shard_uid, hash)
+    }
+
     pub(crate) fn update_cache(&self, transaction: &DBTransaction) -> std::io::Result<()> {
         let mut caches = self.0.caches.write().expect(POISONED_LOCK_ERR);
         let mut shards = HashMap::new();
@@ -238,6 +249,36 @@ impl ShardTries {
     ) -> (StoreUpdate, StateRoot) {
         self.apply_all_inner(trie_changes, shard_uid, true)
     }
+
+    /// Returns the state root that applying `changes` on top of `base_root` would give, without
+    /// writing anything: the update runs in memory over the nodes already in the store.
+    pub fn compute_new_root(
+        &self,
+        shard_uid: ShardUId,
+        base_root: &StateRoot,
+        changes: &[(Vec<u8>, Option<Vec<u8>>)],
+    ) -> Result<StateRoot, StorageError> {
+        Ok(self.compute_new_root_with_nodes(shard_uid, base_root, changes)?.0)
+    }
+
+    /// Same as `compute_new_root`, also returning the trie nodes and values the update would
+    /// write, by hash.
+    pub fn compute_new_root_with_nodes(
+        &self,
+        shard_uid: ShardUId,
+        base_root: &StateRoot,
+        changes: &[(Vec<u8>, Option<Vec<u8>>)],
+    ) -> Result<(StateRoot, HashMap<CryptoHash, Vec<u8>>), StorageError> {
+        // The view trie keeps the reads out of the cache used to apply chunks.
+        let trie = self.get_view_trie_for_shard(shard_uid);
+        let trie_changes = trie.update(base_root, changes.iter().cloned())?;
+        let nodes = trie_changes
+            .insertions
+            .into_iter()
+            .map(|change| (change.trie_node_or_value_hash, change.trie_node_or_value))
+            .collect();
+        Ok((trie_changes.new_root, nodes))
+    }
 }
 
 pub struct WrappedTrieChanges {
@@ -267,6 +308,20 @@ impl WrappedTrieChanges {
         self.tries.apply_insertions(&self.trie_changes, self.shard_uid, store_update)
     }
 
+    /// Moves the flat index of contract data of the shard to the state after the chunk, if it is
+    /// at the state before it.
+    ///
+    /// NOTE: call it before `state_changes_into`, which drains the changes.
+    pub fn flat_contract_data_into(&self, store_update: &mut StoreUpdate) -> io::Result<()> {
+        FlatContractData::new(self.tries.get_store(), self.shard_uid).apply_state_changes(
+            &self.trie_changes.old_root,
+            &self.trie_changes.new_root,
+            &self.block_hash,
+            &self.state_changes,
+            store_update,
+        )
+    }
+
     /// Save state changes into Store.
     ///
     /// NOTE: the changes are drained from `self`.
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use borsh::BorshDeserialize;
 use near_primitives::challenge::PartialState;
 use near_primitives::hash::CryptoHash;
 use near_primitives::state_part::PartId;
@@ -195,6 +196,55 @@ impl Trie {
         Ok(())
     }
 
+    /// Computes the trie nodes and values of a state part covering the keys in `[start, end)`,
+    /// along with the nodes proving its boundaries against the state root. Unlike the parts of
+    /// `get_trie_nodes_for_part`, the boundaries are chosen by the caller, e.g. to cover the
+    /// state of a single account, and the part holds the values of these keys only.
+    ///
+    /// # Errors
+    /// StorageError if the storage is corrupted
+    pub fn get_trie_nodes_for_custom_part(
+        &self,
+        state_root: &StateRoot,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<PartialState, StorageError> {
+        let range = self.prove_range(state_root, start, end, usize::MAX)?;
+        let mut trie_nodes: Vec<Vec<u8>> = range
+            .nodes
+            .iter()
+            .map(|node| node.to_vec())
+            .chain(range.items.into_iter().map(|(_key, value)| value))
+            .collect();
+        trie_nodes.sort();
+        trie_nodes.dedup();
+        Ok(PartialState(trie_nodes))
+    }
+
+    /// Validate a state part from `get_trie_nodes_for_custom_part`.
+    ///
+    /// # Errors
+    /// StorageError::TrieNodeMissing if some nodes are missing
+    pub fn validate_custom_state_part(
+        state_root: &StateRoot,
+        start: &[u8],
+        end: &[u8],
+        trie_nodes: PartialState,
+    ) -> Result<(), StorageError> {
+        let num_nodes = trie_nodes.0.len();
+        let trie = Trie::from_recorded_storage(PartialStorage { nodes: trie_nodes });
+
+        trie.prove_range(state_root, start, end, usize::MAX)?;
+        let storage = trie.storage.as_partial_storage().unwrap();
+
+        if storage.visited_nodes.borrow().len() != num_nodes {
+            // TODO #1603 not actually TrieNodeMissing.
+            // The error is that the proof has more nodes than needed.
+            return Err(StorageError::TrieNodeMissing);
+        }
+        Ok(())
+    }
+
     fn apply_state_part_impl(
         state_root: &StateRoot,
         part_id: PartId,
@@ -257,10 +307,40 @@ impl Trie {
     }
 }
 
+/// Checks that `part` is a valid borsh-serialized state part `part_id` out of `num_parts` for
+/// the trie with the given root. Only the part bytes are needed, so this can be used to check
+/// downloaded parts without a store or a runtime.
+pub fn verify_state_part(
+    state_root: &StateRoot,
+    part_id: u64,
+    num_parts: u64,
+    part: &[u8],
+) -> bool {
+    if part_id >= num_parts {
+        return false;
+    }
+    match PartialState::try_from_slice(part) {
+        Ok(trie_nodes) => {
+            match Trie::validate_trie_nodes_for_part(
+                state_root,
+                PartId::new(part_id, num_parts),
+                trie_nodes,
+            ) {
+                Ok(_) => true,
+                // Storage error should not happen
+                Err(_) => false,
+            }
+        }
+        // Deserialization error means we've got the data from malicious peer
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
+    use borsh::BorshSerialize;
     use rand::prelude::ThreadRng;
     use rand::Rng;
 
@@ -271,7 +351,10 @@ mod tests {
     use crate::trie::{TrieRefcountChange, ValueHandle};
 
     use super::*;
+    use near_crypto::{KeyType, PublicKey};
     use near_primitives::shard_layout::ShardUId;
+    use near_primitives::trie_key::{trie_key_parsers, TrieKey, TrieKeyColumn};
+    use near_primitives::types::AccountId;
 
     impl Trie {
         /// Combines all parts and returns TrieChanges that can be applied to storage.
@@ -737,4 +820,117 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_get_trie_nodes_for_custom_part() {
+        let tries = create_tries();
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard());
+        let accounts: Vec<AccountId> =
+            vec!["alice".parse().unwrap(), "alice.near".parse().unwrap(), "bob".parse().unwrap()];
+        let mut changes = vec![];
+        for account_id in &accounts {
+            let keys = vec![
+                TrieKey::Account { account_id: account_id.clone() },
+                TrieKey::ContractCode { account_id: account_id.clone() },
+                TrieKey::AccessKey {
+                    account_id: account_id.clone(),
+                    public_key: PublicKey::empty(KeyType::ED25519),
+                },
+                TrieKey::ContractData { account_id: account_id.clone(), key: b"a".to_vec() },
+                TrieKey::ContractData { account_id: account_id.clone(), key: b"b".to_vec() },
+            ];
+            for (i, key) in keys.into_iter().enumerate() {
+                let value = format!("{}-{}", account_id, i).into_bytes();
+                changes.push((key.to_vec(), Some(value)));
+            }
+        }
+        let state_root = test_populate_trie(
+            &tries,
+            &Trie::empty_root(),
+            ShardUId::single_shard(),
+            changes.clone(),
+        );
+
+        let alice = &accounts[0];
+        for (column, prefix) in trie_key_parsers::get_raw_prefixes_for_account(alice) {
+            let end = match column {
+                TrieKeyColumn::Account | TrieKeyColumn::ContractCode => {
+                    [&prefix[..], &[0]].concat()
+                }
+                _ => {
+                    let mut end = prefix.clone();
+                    *end.last_mut().unwrap() += 1;
+                    end
+                }
+            };
+            let trie_nodes =
+                trie.get_trie_nodes_for_custom_part(&state_root, &prefix, &end).unwrap();
+            Trie::validate_custom_state_part(&state_root, &prefix, &end, trie_nodes.clone())
+                .expect("validate ok");
+
+            // The part holds all and only the records of alice in this column.
+            let part_trie =
+                Trie::from_recorded_storage(PartialStorage { nodes: trie_nodes.clone() });
+            let items =
+                part_trie.prove_range(&state_root, &prefix, &end, usize::MAX).unwrap().items;
+            let expected = changes
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix) && key[..] < end[..])
+                .map(|(key, value)| (key.clone(), value.clone().unwrap()))
+                .collect::<Vec<_>>();
+            assert_eq!(items, expected);
+            for (key, value) in &changes {
+                if !expected.iter().any(|(expected_key, _)| expected_key == key) {
+                    assert!(!trie_nodes.0.contains(value.as_ref().unwrap()));
+                }
+            }
+
+            let mut missing_node = trie_nodes.clone();
+            missing_node.0.pop();
+            assert!(
+                Trie::validate_custom_state_part(&state_root, &prefix, &end, missing_node).is_err()
+            );
+            let mut extra_node = trie_nodes;
+            extra_node.0.push(b"bob-0".to_vec());
+            assert!(
+                Trie::validate_custom_state_part(&state_root, &prefix, &end, extra_node).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_state_part() {
+        let tries = create_tries();
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard());
+        let changes = (0..100u64)
+            .map(|i| (hash(&i.to_le_bytes()).as_ref().to_vec(), Some(i.to_le_bytes().to_vec())))
+            .collect::<Vec<_>>();
+        let state_root =
+            test_populate_trie(&tries, &Trie::empty_root(), ShardUId::single_shard(), changes);
+
+        let num_parts = 4;
+        let parts = (0..num_parts)
+            .map(|part_id| {
+                trie.get_trie_nodes_for_part(PartId::new(part_id, num_parts), &state_root)
+                    .unwrap()
+                    .try_to_vec()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        for (part_id, part) in parts.iter().enumerate() {
+            let part_id = part_id as u64;
+            assert!(verify_state_part(&state_root, part_id, num_parts, part));
+
+            let wrong_part_id = (part_id + 1) % num_parts;
+            assert!(!verify_state_part(&state_root, wrong_part_id, num_parts, part));
+            assert!(!verify_state_part(&state_root, num_parts, num_parts, part));
+            assert!(!verify_state_part(&CryptoHash::default(), part_id, num_parts, part));
+
+            for i in [0, part.len() / 2, part.len() - 1] {
+                let mut flipped = part.clone();
+                flipped[i] ^= 1;
+                assert!(!verify_state_part(&state_root, part_id, num_parts, &flipped));
+            }
+        }
+    }
 }
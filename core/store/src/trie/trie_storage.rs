@@ -215,6 +215,17 @@ impl TrieCachingStorage {
         key
     }
 
+    /// Reads the node or value with the given hash from the state of the shard in the DB,
+    /// bypassing the caches.
+    pub(crate) fn read_from_db(
+        store: &Store,
+        shard_uid: ShardUId,
+        hash: &CryptoHash,
+    ) -> Result<Option<Vec<u8>>, StorageError> {
+        let key = Self::get_key_from_shard_uid_and_hash(shard_uid, hash);
+        store.get(DBCol::State, key.as_ref()).map_err(|_| StorageError::StorageInternalError)
+    }
+
     fn inc_db_read_nodes(&self) {
         self.db_read_nodes.set(self.db_read_nodes.get() + 1);
     }
@@ -248,12 +259,8 @@ impl TrieStorage for TrieCachingStorage {
             None => {
                 near_o11y::io_trace!(count: "shard_cache_miss");
                 // If value is not present in cache, get it from the storage.
-                let key = Self::get_key_from_shard_uid_and_hash(self.shard_uid, hash);
-                let val = self
-                    .store
-                    .get(DBCol::State, key.as_ref())
-                    .map_err(|_| StorageError::StorageInternalError)?
-                    .ok_or_else(|| {
+                let val =
+                    Self::read_from_db(&self.store, self.shard_uid, hash)?.ok_or_else(|| {
                         StorageError::StorageInconsistentState("Trie node missing".to_string())
                     })?;
                 let val: Arc<[u8]> = val.into();
@@ -9,9 +9,11 @@ use near_primitives::types::{
 use crate::trie::TrieChanges;
 use crate::StorageError;
 
-use super::{Trie, TrieIterator};
+use super::iterator::TrieItem;
+use super::{proof_verifier, Trie, TrieIterator};
 use near_primitives::trie_key::TrieKey;
 use std::rc::Rc;
+use std::sync::Arc;
 
 /// Key-value update. Contains a TrieKey and a value.
 pub struct TrieKeyValueUpdate {
@@ -62,6 +64,16 @@ impl TrieUpdate {
         self.trie.as_ref()
     }
 
+    /// Returns the value of `key` in the trie, along with the nodes proving it against the root
+    /// of the update, see `Trie::get_with_proof`. Changes made through the update aren't in the
+    /// trie yet, so they are ignored.
+    pub fn get_with_proof(
+        &self,
+        key: &TrieKey,
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), StorageError> {
+        self.trie.get_with_proof(&self.root, &key.to_vec())
+    }
+
     pub fn get(&self, key: &TrieKey) -> Result<Option<Vec<u8>>, StorageError> {
         let key = key.to_vec();
         if let Some(key_value) = self.prospective.get(&key) {
@@ -159,6 +171,19 @@ impl TrieUpdate {
         TrieUpdateIterator::new(self, key_prefix, b"", None)
     }
 
+    /// Iterates over the key/value pairs under `key_prefix` in the trie, recording the trie nodes
+    /// visited so that they prove the items against the state root. Ignores the uncommitted
+    /// changes, which are not in the trie.
+    pub fn iter_with_recording(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<TrieRecordingIterator<'_>, StorageError> {
+        let mut trie_iter = self.trie.iter(&self.root)?;
+        trie_iter.remember_visited_nodes(true);
+        trie_iter.seek(key_prefix)?;
+        Ok(TrieRecordingIterator { prefix: key_prefix.to_vec(), trie_iter, done: false })
+    }
+
     pub fn range(
         &self,
         prefix: &[u8],
@@ -349,6 +374,46 @@ impl<'a> Iterator for TrieUpdateIterator<'a> {
     }
 }
 
+/// Iterator over the key/value pairs under a prefix in a trie, which records the trie nodes it
+/// visits, see `TrieUpdate::iter_with_recording`.
+pub struct TrieRecordingIterator<'a> {
+    prefix: Vec<u8>,
+    trie_iter: TrieIterator<'a>,
+    done: bool,
+}
+
+impl<'a> TrieRecordingIterator<'a> {
+    /// Positions the iterator on the first key under the prefix which is `>= key`.
+    pub fn seek(&mut self, key: &[u8]) -> Result<(), StorageError> {
+        self.done = false;
+        self.trie_iter.seek(std::cmp::max(key, self.prefix.as_slice()))
+    }
+
+    /// Returns the encoded trie nodes visited, each one once, without the values, in the
+    /// order of `proof_verifier::canonical_proof`.
+    pub fn into_recorded_nodes(self) -> Vec<Arc<[u8]>> {
+        proof_verifier::canonical_proof(self.trie_iter.into_visited_nodes())
+    }
+}
+
+impl<'a> Iterator for TrieRecordingIterator<'a> {
+    type Item = Result<TrieItem, StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.trie_iter.next() {
+            Some(Ok((key, value))) if key.starts_with(&self.prefix) => Some(Ok((key, value))),
+            Some(Err(e)) => Some(Err(e)),
+            _ => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_utils::{create_tries, create_tries_complex};
@@ -515,4 +580,47 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn trie_iter_with_recording() {
+        let tries = create_tries();
+        let mut trie_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        for key in [&b"aaa"[..], b"dog", b"dog2", b"dog3", b"xxx"] {
+            trie_update.set(test_key(key.to_vec()), [key, b"-puppy"].concat());
+        }
+        trie_update
+            .commit(StateChangeCause::TransactionProcessing { tx_hash: CryptoHash::default() });
+        let trie_changes = trie_update.finalize().unwrap().0;
+        let (store_update, new_root) = tries.apply_all(&trie_changes, ShardUId::single_shard());
+        store_update.commit().unwrap();
+
+        let trie_update = tries.new_trie_update(ShardUId::single_shard(), new_root);
+        let item = |key: &[u8]| (test_key(key.to_vec()).to_vec(), [key, b"-puppy"].concat());
+        let mut iter =
+            trie_update.iter_with_recording(&test_key(b"dog".to_vec()).to_vec()).unwrap();
+        let items: Vec<_> = iter.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, vec![item(b"dog"), item(b"dog2"), item(b"dog3")]);
+        iter.seek(&test_key(b"dog2".to_vec()).to_vec()).unwrap();
+        let items: Vec<_> = iter.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, vec![item(b"dog2"), item(b"dog3")]);
+
+        // The nodes are recorded once each, and prove the items against the state root.
+        let nodes = iter.into_recorded_nodes();
+        let hashes: std::collections::HashSet<_> =
+            nodes.iter().map(|node| near_primitives::hash::hash(node)).collect();
+        assert_eq!(hashes.len(), nodes.len());
+        let items = vec![item(b"dog"), item(b"dog2"), item(b"dog3")];
+        assert!(items.iter().all(|(_, value)| !nodes.iter().any(|node| **node == **value)));
+        assert_eq!(
+            crate::proof_verifier::verify_range_proof(
+                &new_root,
+                &test_key(b"dog".to_vec()).to_vec(),
+                &test_key(b"doh".to_vec()).to_vec(),
+                &items,
+                &nodes,
+            ),
+            Ok(())
+        );
+    }
 }
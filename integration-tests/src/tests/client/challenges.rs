@@ -103,7 +103,7 @@ fn test_verify_block_double_sign_challenge() {
     let signer =
         InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
     let mut block_merkle_tree = PartialMerkleTree::default();
-    block_merkle_tree.insert(*genesis.hash());
+    block_merkle_tree.append(*genesis.hash());
     let b2 = Block::produce(
         PROTOCOL_VERSION,
         PROTOCOL_VERSION,
@@ -404,7 +404,7 @@ fn test_verify_chunk_invalid_state_challenge() {
     let block_merkle_tree =
         client.chain.mut_store().get_block_merkle_tree(last_block.hash()).unwrap();
     let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-    block_merkle_tree.insert(*last_block.hash());
+    block_merkle_tree.append(*last_block.hash());
     let block = Block::produce(
         PROTOCOL_VERSION,
         PROTOCOL_VERSION,
@@ -12,11 +12,12 @@ use near_primitives::num_rational::{Ratio, Rational32};
 
 use near_actix_test_utils::run_actix;
 use near_chain::chain::ApplyStatePartsRequest;
-use near_chain::types::LatestKnown;
+use near_chain::near_chain_primitives::error::MissingBlockMerkleData;
+use near_chain::types::{LatestKnown, ValidatorInfoIdentifier};
 use near_chain::validate::validate_chunk_with_chunk_extra;
 use near_chain::{
-    Block, BlockProcessingArtifact, ChainGenesis, ChainStore, ChainStoreAccess, Error, Provenance,
-    RuntimeAdapter,
+    Block, BlockProcessingArtifact, Chain, ChainGenesis, ChainStore, ChainStoreAccess,
+    DoomslugThresholdMode, Error, Provenance, RuntimeAdapter,
 };
 use near_chain_configs::{ClientConfig, Genesis, DEFAULT_GC_NUM_EPOCHS_TO_KEEP};
 use near_chunks::{ChunkStatus, ShardsManager};
@@ -39,10 +40,13 @@ use near_primitives::epoch_manager::RngSeed;
 use near_primitives::errors::TxExecutionError;
 use near_primitives::errors::{ActionErrorKind, InvalidTxError};
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::merkle::{verify_hash, PartialMerkleTree};
+use near_primitives::merkle::{
+    compute_path, verify_hash, verify_path_for_index, PartialMerkleTree,
+};
 use near_primitives::receipt::DelayedReceiptIndices;
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::serialize::from_base64;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::sharding::{
     EncodedShardChunk, ReedSolomonWrapper, ShardChunkHeader, ShardChunkHeaderInner,
@@ -57,12 +61,15 @@ use near_primitives::transaction::{
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{AccountId, BlockHeight, EpochId, NumBlocks, ProtocolVersion};
-use near_primitives::utils::to_timestamp;
+use near_primitives::utils::{index_to_bytes, to_timestamp};
 use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
 use near_primitives::version::ProtocolFeature;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{
-    BlockHeaderView, FinalExecutionStatus, QueryRequest, QueryResponseKind,
+    BlockHeaderView, FinalExecutionStatus, QueryRequest, QueryResponseKind, ViewStateProofKind,
+};
+use near_store::proof_verifier::{
+    contract_data_key, verify_non_existence, verify_view_state_result,
 };
 use near_store::test_utils::create_test_store;
 use near_store::{get, DBCol};
@@ -350,7 +357,7 @@ fn receive_network_block() {
         actix::spawn(view_client.send(GetBlockWithMerkleTree::latest()).then(move |res| {
             let (last_block, block_merkle_tree) = res.unwrap().unwrap();
             let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-            block_merkle_tree.insert(last_block.header.hash);
+            block_merkle_tree.append(last_block.header.hash);
             let signer = InMemoryValidatorSigner::from_seed(
                 "test1".parse().unwrap(),
                 KeyType::ED25519,
@@ -428,7 +435,7 @@ fn produce_block_with_approvals() {
         actix::spawn(view_client.send(GetBlockWithMerkleTree::latest()).then(move |res| {
             let (last_block, block_merkle_tree) = res.unwrap().unwrap();
             let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-            block_merkle_tree.insert(last_block.header.hash);
+            block_merkle_tree.append(last_block.header.hash);
             let signer1 = InMemoryValidatorSigner::from_seed(
                 "test2".parse().unwrap(),
                 KeyType::ED25519,
@@ -619,7 +626,7 @@ fn invalid_blocks_common(is_requested: bool) {
         actix::spawn(view_client.send(GetBlockWithMerkleTree::latest()).then(move |res| {
             let (last_block, block_merkle_tree) = res.unwrap().unwrap();
             let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-            block_merkle_tree.insert(last_block.header.hash);
+            block_merkle_tree.append(last_block.header.hash);
             let signer = InMemoryValidatorSigner::from_seed(
                 "test".parse().unwrap(),
                 KeyType::ED25519,
@@ -1449,6 +1456,56 @@ fn test_gc_long_epoch() {
     test_gc_with_epoch_length_common(200);
 }
 
+/// The block producer hashes in the validator info of an epoch are the `next_bp_hash` of the
+/// blocks of the previous epoch and of the epoch itself.
+#[test]
+fn test_validator_info_bp_hashes() {
+    let epoch_length = 5;
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 2);
+    genesis.config.epoch_length = epoch_length;
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .clients_count(2)
+        .validator_seats(2)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 2))
+        .build();
+    let mut headers = vec![env.clients[0].chain.get_block_by_height(0).unwrap().header().clone()];
+    for height in 1..=epoch_length * 4 {
+        let head = env.clients[0].chain.head().unwrap();
+        let epoch_id = env.clients[0]
+            .runtime_adapter
+            .get_epoch_id_from_prev_block(&head.last_block_hash)
+            .unwrap();
+        let block_producer =
+            env.clients[0].runtime_adapter.get_block_producer(&epoch_id, height).unwrap();
+        let block = env.client(&block_producer).produce_block(height).unwrap().unwrap();
+        for j in 0..2 {
+            env.process_block(j, block.clone(), Provenance::NONE);
+        }
+        env.process_partial_encoded_chunks();
+        headers.push(block.header().clone());
+    }
+
+    let runtime_adapter = &env.clients[0].runtime_adapter;
+    let prev_epoch_header = |header: &BlockHeader| {
+        headers.iter().find(|prev| prev.next_epoch_id() == header.epoch_id()).unwrap().clone()
+    };
+    for header in &headers[1..] {
+        let info = runtime_adapter
+            .get_validator_info(ValidatorInfoIdentifier::BlockHash(*header.hash()))
+            .unwrap();
+        assert_eq!(&info.next_bp_hash, header.next_bp_hash());
+        assert_eq!(&info.current_bp_hash, prev_epoch_header(header).next_bp_hash());
+        // Only finished epochs can be looked up by id.
+        if let Ok(info) = runtime_adapter
+            .get_validator_info(ValidatorInfoIdentifier::EpochId(header.epoch_id().clone()))
+        {
+            assert_eq!(&info.next_bp_hash, header.next_bp_hash());
+            assert_eq!(&info.current_bp_hash, prev_epoch_header(header).next_bp_hash());
+        }
+    }
+}
+
 #[test]
 fn test_gc_block_skips() {
     let mut chain_genesis = ChainGenesis::test();
@@ -1981,9 +2038,16 @@ fn test_block_merkle_proof_with_len(n: NumBlocks, rng: &mut StdRng) {
             assert_eq!(block_hash, block_hash1);
         }
     }
-    for block in blocks {
+    // The blocks before the head are the leaves of its block merkle tree, in ordinal order.
+    let leaves = blocks.iter().map(|block| *block.hash()).collect::<Vec<_>>();
+    let tree_size =
+        env.clients[0].chain.mut_store().get_block_merkle_tree(head.hash()).unwrap().size();
+    assert_eq!(tree_size, leaves.len() as u64);
+    for (index, block) in blocks.iter().enumerate() {
         let proof = env.clients[0].chain.get_block_proof(block.hash(), head.hash()).unwrap();
         assert!(verify_hash(*root, &proof, *block.hash()));
+        assert_eq!(proof, compute_path(&leaves, index as u64, tree_size));
+        assert!(verify_path_for_index(*root, *block.hash(), &proof, index as u64, tree_size));
     }
 }
 
@@ -2004,6 +2068,48 @@ fn test_block_merkle_proof_same_hash() {
     assert!(proof.is_empty());
 }
 
+/// Block proofs report which block merkle tree data is missing on the node.
+#[test]
+fn test_block_merkle_proof_missing_data() {
+    let chain_genesis = ChainGenesis::test();
+    let mut env = TestEnv::builder(chain_genesis.clone()).build();
+    for height in 1..10 {
+        env.produce_block(0, height);
+    }
+    // Without skipped heights, the ordinal of a block is its height.
+    let block_hash = *env.clients[0].chain.get_block_by_height(4).unwrap().hash();
+    let head_hash = *env.clients[0].chain.get_block_by_height(9).unwrap().hash();
+    assert!(env.clients[0].chain.get_block_proof(&block_hash, &head_hash).is_ok());
+
+    // A new chain on the same store doesn't have the deleted data cached.
+    let new_chain = || {
+        Chain::new(
+            env.clients[0].runtime_adapter.clone(),
+            &chain_genesis,
+            DoomslugThresholdMode::NoApprovals,
+            true,
+        )
+        .unwrap()
+    };
+    let store = env.clients[0].chain.store().store().clone();
+    let mut store_update = store.store_update();
+    store_update.delete(DBCol::BlockOrdinal, &index_to_bytes(5));
+    store_update.commit().unwrap();
+    assert_matches!(
+        new_chain().get_block_proof(&block_hash, &head_hash),
+        Err(Error::MissingBlockMerkleData(MissingBlockMerkleData::BlockOrdinal(5)))
+    );
+
+    let mut store_update = store.store_update();
+    store_update.delete(DBCol::BlockMerkleTree, head_hash.as_ref());
+    store_update.commit().unwrap();
+    assert_matches!(
+        new_chain().get_block_proof(&block_hash, &head_hash),
+        Err(Error::MissingBlockMerkleData(MissingBlockMerkleData::PartialMerkleTree(hash)))
+            if hash == head_hash
+    );
+}
+
 #[test]
 fn test_data_reset_before_state_sync() {
     let mut genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
@@ -2041,7 +2147,10 @@ fn test_data_reset_before_state_sync() {
             &head.prev_block_hash,
             &head.last_block_hash,
             head_block.header().epoch_id(),
-            &QueryRequest::ViewAccount { account_id: "test_account".parse().unwrap() },
+            &QueryRequest::ViewAccount {
+                account_id: "test_account".parse().unwrap(),
+                include_proof: false,
+            },
         )
         .unwrap();
     assert_matches!(response.kind, QueryResponseKind::ViewAccount(_));
@@ -2055,12 +2164,84 @@ fn test_data_reset_before_state_sync() {
         &head.prev_block_hash,
         &head.last_block_hash,
         head_block.header().epoch_id(),
-        &QueryRequest::ViewAccount { account_id: "test_account".parse().unwrap() },
+        &QueryRequest::ViewAccount {
+            account_id: "test_account".parse().unwrap(),
+            include_proof: false,
+        },
     );
     // TODO(#3742): ViewClient still has data in cache by current design.
     assert!(response.is_ok());
 }
 
+/// Queries contract data which doesn't exist with a proof, as the RPC does, and checks that the
+/// proof shows its absence from the state of the block.
+#[test]
+fn test_view_state_non_existence_proof() {
+    let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+    let mut env = TestEnv::builder(ChainGenesis::test())
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    for i in 1..5 {
+        env.produce_block(0, i);
+    }
+    let head = env.clients[0].chain.head().unwrap();
+    let head_block = env.clients[0].chain.get_block(&head.last_block_hash).unwrap();
+    let state_root = *env.clients[0]
+        .chain
+        .get_chunk_extra(&head.last_block_hash, &ShardUId::single_shard())
+        .unwrap()
+        .state_root();
+    let account_id: AccountId = "test0".parse().unwrap();
+    let response = env.clients[0]
+        .runtime_adapter
+        .query(
+            ShardUId::single_shard(),
+            &state_root,
+            head.height,
+            0,
+            &head.prev_block_hash,
+            &head.last_block_hash,
+            head_block.header().epoch_id(),
+            &QueryRequest::ViewState {
+                account_id: account_id.clone(),
+                prefix: b"missing".to_vec().into(),
+                from_key: None,
+                limit: None,
+                max_value_size: None,
+                state_size_limit: None,
+                include_proof: true,
+                allow_partial: false,
+            },
+        )
+        .unwrap();
+    // Go through the JSON of the RPC response.
+    let response = near_jsonrpc_primitives::types::query::RpcQueryResponse {
+        kind: response.kind,
+        block_height: response.block_height,
+        block_hash: response.block_hash,
+    };
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["proof_kind"], "non_existence");
+    let response: near_jsonrpc_primitives::types::query::RpcQueryResponse =
+        serde_json::from_value(json).unwrap();
+    let result = match response.kind {
+        QueryResponseKind::ViewState(result) => result,
+        kind => panic!("unexpected response: {:?}", kind),
+    };
+    assert_eq!(result.values, []);
+    assert_eq!(result.proof_kind, Some(ViewStateProofKind::NonExistence));
+
+    let proof: Vec<Vec<u8>> = result.proof.iter().map(|node| from_base64(node).unwrap()).collect();
+    let key = contract_data_key(&account_id, b"missing");
+    assert_eq!(verify_non_existence(&state_root, &key, &proof), Ok(()));
+    assert!(verify_non_existence(&CryptoHash::default(), &key, &proof).is_err());
+    assert!(verify_non_existence(&state_root, &key, &proof[..proof.len() - 1]).is_err());
+    assert_eq!(
+        verify_view_state_result(&state_root, &account_id, b"missing", None, &result),
+        Ok(())
+    );
+}
+
 #[test]
 fn test_sync_hash_validity() {
     let epoch_length = 5;
@@ -2970,7 +3151,7 @@ fn test_query_final_state() {
                 &final_head.prev_block_hash,
                 last_final_block.hash(),
                 last_final_block.header().epoch_id(),
-                &QueryRequest::ViewAccount { account_id },
+                &QueryRequest::ViewAccount { account_id, include_proof: false },
             )
             .unwrap();
         match response.kind {
@@ -4511,6 +4692,7 @@ mod contract_precompilation_tests {
             block_hash: *block.hash(),
             epoch_id: block.header().epoch_id().clone(),
             epoch_height: 1,
+            chain_id: "test-chain".to_string(),
             block_timestamp: block.header().raw_timestamp(),
             current_protocol_version: PROTOCOL_VERSION,
             cache: Some(caches[1].clone()),
@@ -4522,6 +4704,8 @@ mod contract_precompilation_tests {
                 &"test0".parse().unwrap(),
                 "log_something",
                 &[],
+                None,
+                false,
                 &mut logs,
                 &MockEpochInfoProvider::default(),
             )
@@ -391,7 +391,10 @@ fn check_account(env: &mut TestEnv, account_id: &AccountId, block: &Block) {
                     prev_hash,
                     block.hash(),
                     block.header().epoch_id(),
-                    &QueryRequest::ViewAccount { account_id: account_id.clone() },
+                    &QueryRequest::ViewAccount {
+                        account_id: account_id.clone(),
+                        include_proof: false,
+                    },
                 )
                 .unwrap();
 
@@ -407,7 +410,10 @@ fn check_account(env: &mut TestEnv, account_id: &AccountId, block: &Block) {
                         block.header().prev_hash(),
                         block.hash(),
                         block.header().epoch_id(),
-                        &QueryRequest::ViewAccount { account_id: account_id.clone() },
+                        &QueryRequest::ViewAccount {
+                            account_id: account_id.clone(),
+                            include_proof: false,
+                        },
                     )
                     .unwrap();
             }
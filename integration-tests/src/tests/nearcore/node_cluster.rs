@@ -7,8 +7,10 @@ use near_chain_configs::Genesis;
 use near_client::{ClientActor, ViewClientActor};
 use near_logger_utils::init_integration_logger;
 use near_network::test_utils::{convert_boot_nodes, open_port};
-use near_primitives::types::{BlockHeight, BlockHeightDelta, NumSeats, NumShards};
+use near_primitives::runtime::parameter_table::ParameterTableOverlay;
+use near_primitives::types::{BlockHeight, BlockHeightDelta, NumSeats, NumShards, ProtocolVersion};
 use nearcore::{config::GenesisExt, load_test_config, start_with_config};
+use std::time::Duration;
 
 fn start_nodes(
     temp_dir: &std::path::Path,
@@ -18,6 +20,11 @@ fn start_nodes(
     num_lightclient: NumSeats,
     epoch_length: BlockHeightDelta,
     genesis_height: BlockHeight,
+    enable_debug_rpc: bool,
+    archival_fallback: bool,
+    rpc_polling_timeout: Option<Duration>,
+    genesis_protocol_version: Option<ProtocolVersion>,
+    fork_parameters: Option<ParameterTableOverlay>,
 ) -> (Genesis, Vec<String>, Vec<(Addr<ClientActor>, Addr<ViewClientActor>, Vec<ArbiterHandle>)>) {
     init_integration_logger();
 
@@ -30,6 +37,9 @@ fn start_nodes(
     );
     genesis.config.epoch_length = epoch_length;
     genesis.config.genesis_height = genesis_height;
+    if let Some(protocol_version) = genesis_protocol_version {
+        genesis.config.protocol_version = protocol_version;
+    }
 
     let validators = (0..num_validator_seats).map(|i| format!("near.{}", i)).collect::<Vec<_>>();
     let mut near_configs = vec![];
@@ -52,8 +62,28 @@ fn start_nodes(
             near_config.client_config.tracked_shards = vec![0];
         }
         near_config.client_config.epoch_sync_enabled = false;
+        near_config.fork_parameters = fork_parameters.clone();
+        if let Some(rpc_config) = &mut near_config.rpc_config {
+            rpc_config.enable_debug_rpc = enable_debug_rpc;
+            if let Some(polling_timeout) = rpc_polling_timeout {
+                rpc_config.polling_config.polling_timeout = polling_timeout;
+            }
+        }
         near_configs.push(near_config);
     }
+    // The last node is archival, and the other non-validators forward queries of garbage collected
+    // data to it.
+    if archival_fallback {
+        let archival_url = format!("http://{}", rpc_addrs.last().unwrap());
+        let (archival_config, near_configs) = near_configs.split_last_mut().unwrap();
+        archival_config.client_config.archive = true;
+        archival_config.network_config.archive = true;
+        for near_config in near_configs.iter_mut().skip(num_validator_seats as usize) {
+            if let Some(rpc_config) = &mut near_config.rpc_config {
+                rpc_config.archival_fallback_url = Some(archival_url.clone());
+            }
+        }
+    }
 
     let mut res = vec![];
     for (i, near_config) in near_configs.into_iter().enumerate() {
@@ -74,6 +104,11 @@ pub struct NodeCluster {
     num_lightclient: Option<NumSeats>,
     epoch_length: Option<BlockHeightDelta>,
     genesis_height: Option<BlockHeight>,
+    enable_debug_rpc: bool,
+    archival_fallback: bool,
+    rpc_polling_timeout: Option<Duration>,
+    genesis_protocol_version: Option<ProtocolVersion>,
+    fork_parameters: Option<ParameterTableOverlay>,
 }
 
 impl NodeCluster {
@@ -107,6 +142,36 @@ impl NodeCluster {
         self
     }
 
+    pub fn set_enable_debug_rpc(mut self, enable: bool) -> Self {
+        self.enable_debug_rpc = enable;
+        self
+    }
+
+    /// Makes the last node archival and the other non-validator nodes forward the queries of data
+    /// they garbage collected to it.
+    pub fn set_archival_fallback(mut self, enable: bool) -> Self {
+        self.archival_fallback = enable;
+        self
+    }
+
+    /// How long the RPC of the nodes waits for transactions to be executed.
+    pub fn set_rpc_polling_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_polling_timeout = Some(timeout);
+        self
+    }
+
+    /// Starts the chain at an older protocol version, which the nodes upgrade from to the latest
+    /// one after a few epochs.
+    pub fn set_genesis_protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.genesis_protocol_version = Some(protocol_version);
+        self
+    }
+
+    pub fn set_fork_parameters(mut self, fork_parameters: ParameterTableOverlay) -> Self {
+        self.fork_parameters = Some(fork_parameters);
+        self
+    }
+
     pub fn exec_until_stop<F, R>(self, f: F)
     where
         R: future::Future<Output = ()> + 'static,
@@ -144,6 +209,11 @@ impl NodeCluster {
                     num_lightclient,
                     epoch_length,
                     genesis_height,
+                    self.enable_debug_rpc,
+                    self.archival_fallback,
+                    self.rpc_polling_timeout,
+                    self.genesis_protocol_version,
+                    self.fork_parameters,
                 );
                 spawn_interruptible(f(genesis, rpc_addrs, clients));
             });
@@ -173,7 +173,7 @@ fn test_protocol_config_unknown_block_error() {
                                 client
                                     .EXPERIMENTAL_protocol_config(
                                         near_jsonrpc_primitives::types::config::RpcProtocolConfigRequest {
-                                            block_reference: near_primitives::types::BlockReference::BlockId(BlockId::Height(block.header.height + 100))
+                                            protocol_config_reference: near_jsonrpc_primitives::types::config::ProtocolConfigReference::BlockReference(near_primitives::types::BlockReference::BlockId(BlockId::Height(block.header.height + 100)))
                                         }
                                     )
                                     .map_err(|err| {
@@ -429,7 +429,9 @@ fn test_query_rpc_account_view_unknown_block_must_return_error() {
                 )),
                 request: near_primitives::views::QueryRequest::ViewAccount {
                     account_id: "near.0".parse().unwrap(),
+                    include_proof: false,
                 },
+                encoding: Default::default(),
             })
             .await;
 
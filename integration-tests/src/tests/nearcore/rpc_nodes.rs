@@ -5,23 +5,56 @@ use actix::{Actor, System};
 use assert_matches::assert_matches;
 use borsh::BorshSerialize;
 use futures::future::join_all;
-use futures::{future, FutureExt, TryFutureExt};
+use futures::{future, FutureExt, Sink, SinkExt, Stream, StreamExt, TryFutureExt};
 use near_actix_test_utils::spawn_interruptible;
-use near_client::{GetBlock, GetExecutionOutcome, GetValidatorInfo};
+use near_client::{GetBlock, GetExecutionOutcome, GetValidatorInfo, TxStatus};
 use near_crypto::{InMemorySigner, KeyType};
-use near_jsonrpc::client::new_client;
+use near_jsonrpc::client::{new_client, ChunkId, JsonRpcClient};
+use near_jsonrpc_primitives::types::changes::RpcStateChangesInBlockByTypeRequest;
+use near_jsonrpc_primitives::types::chunks::{ChunkReference, RpcChunkStateRootProofRequest};
+use near_jsonrpc_primitives::types::config::{ProtocolConfigReference, RpcProtocolConfigRequest};
+use near_jsonrpc_primitives::types::light_client::{
+    RpcBlockMerkleProofRequest, RpcLightClientExecutionProofRequest,
+};
+use near_jsonrpc_primitives::types::state_proof::RpcStateProofRequest;
+use near_jsonrpc_primitives::types::transactions::RpcSendTransactionOutcome;
+use near_jsonrpc_primitives::types::trie_key::RpcRawTrieKeyRequest;
+use near_jsonrpc_primitives::types::trie_node::RpcTrieNodeRequest;
+use near_jsonrpc_primitives::types::validator::{
+    RpcNextValidatorSetRequest, RpcProducerScheduleRequest, RpcValidatorsOrderedRequest,
+};
 use near_logger_utils::init_integration_logger;
 use near_network::test_utils::WaitOrTimeoutActor;
+use near_primitives::account::{AccessKey, Account};
+use near_primitives::block::BlockHeader;
+use near_primitives::block_header::BlockHeaderInnerLite;
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::merkle::{compute_root_from_path_and_item, verify_path};
-use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::merkle::{
+    combine_hash, compute_root_from_path_and_item, verify_hash, verify_path,
+};
+use near_primitives::runtime::config_store::{RuntimeConfigStore, FORK_PROTOCOL_VERSION};
+use near_primitives::runtime::parameter_table::ParameterTableOverlay;
 use near_primitives::serialize::{from_base64, to_base64};
-use near_primitives::transaction::{PartialExecutionStatus, SignedTransaction};
+use near_primitives::transaction::{
+    Action, DeployContractAction, FunctionCallAction, PartialExecutionStatus, SignedTransaction,
+};
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
-    BlockId, BlockReference, EpochId, EpochReference, Finality, TransactionOrReceiptId,
+    AccountId, Balance, BlockHeight, BlockId, BlockReference, EpochId, EpochReference, Finality,
+    ShardId, TransactionOrReceiptId,
 };
 use near_primitives::version::ProtocolVersion;
-use near_primitives::views::{ExecutionOutcomeView, ExecutionStatusView};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{
+    BlockHeaderView, BlockView, ExecutionOutcomeView, ExecutionStatusView, FinalExecutionStatus,
+    LightClientBlockLiteView, LightClientBlockView, OutcomeRootProofView, StateChangeValueView,
+    StateChangesRequestView, TrieKeyView, TrieNodeKindView, TxExecutionStatus,
+};
+use near_primitives_core::parameter::Parameter;
+use near_store::encoding::{RawTrieNode, RawTrieNodeWithSize};
+use near_store::proof_verifier::{contract_data_key, verify_non_existence, verify_state_proof};
+use near_vm_logic::validate_light_client_block;
+use std::collections::HashMap;
 use std::time::Duration;
 
 #[test]
@@ -71,6 +104,165 @@ fn test_get_validator_info_rpc() {
     });
 }
 
+/// The producer schedule matches the producers of the blocks and chunks, whose signatures are
+/// checked against the keys of the validators, and covers the next epoch too.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_producer_schedule_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(2)
+        .set_num_validator_seats(2)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let final_block = wait_for_final_block(&client, 5).await;
+        let public_keys: HashMap<_, _> = client
+            .validators(Some(BlockId::Hash(final_block.header.hash)))
+            .await
+            .unwrap()
+            .current_validators
+            .into_iter()
+            .map(|validator| (validator.account_id, validator.public_key))
+            .collect();
+
+        let schedule = client
+            .EXPERIMENTAL_producer_schedule(RpcProducerScheduleRequest {
+                epoch_reference: EpochReference::BlockId(BlockId::Height(
+                    final_block.header.height,
+                )),
+            })
+            .await
+            .unwrap()
+            .producer_schedule;
+        assert_eq!(schedule.epoch_id, final_block.header.epoch_id);
+        assert_eq!(schedule.epoch_start_height, 1);
+        assert_eq!(schedule.producers.len(), 10);
+        for height in 1..=final_block.header.height {
+            let producers = &schedule.producers[(height - 1) as usize];
+            assert_eq!(producers.height, height);
+            let block = match client.block_by_id(BlockId::Height(height)).await {
+                Ok(block) => block,
+                // Skipped height.
+                Err(_) => continue,
+            };
+            assert_eq!(block.author, producers.block_producer);
+            let header = BlockHeader::from(block.header);
+            assert!(header
+                .signature()
+                .verify(header.hash().as_ref(), &public_keys[&producers.block_producer]));
+            for (shard_id, chunk_producer) in producers.chunk_producers.iter().enumerate() {
+                if block.chunks[shard_id].height_created != height {
+                    continue;
+                }
+                let chunk = client
+                    .chunk(ChunkId::BlockShardId(BlockId::Height(height), shard_id as ShardId))
+                    .await
+                    .unwrap();
+                assert_eq!(&chunk.author, chunk_producer);
+            }
+        }
+
+        let latest_schedule = client
+            .EXPERIMENTAL_producer_schedule(RpcProducerScheduleRequest {
+                epoch_reference: EpochReference::Latest,
+            })
+            .await
+            .unwrap()
+            .producer_schedule;
+        let next_schedule = client
+            .EXPERIMENTAL_producer_schedule(RpcProducerScheduleRequest {
+                epoch_reference: EpochReference::EpochId(EpochId(final_block.header.next_epoch_id)),
+            })
+            .await
+            .unwrap()
+            .producer_schedule;
+        if latest_schedule.epoch_id == schedule.epoch_id {
+            assert_eq!(latest_schedule, schedule);
+            assert_eq!(next_schedule.epoch_start_height, 11);
+        }
+        assert_eq!(next_schedule.epoch_id, final_block.header.next_epoch_id);
+        assert_eq!(next_schedule.producers.len(), 10);
+
+        let error = client
+            .EXPERIMENTAL_producer_schedule(RpcProducerScheduleRequest {
+                epoch_reference: EpochReference::EpochId(EpochId(hash(b"unknown"))),
+            })
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_EPOCH"));
+        System::current().stop();
+    });
+}
+
+/// The block producers of the next epoch hash to the `next_bp_hash` of the blocks on both sides
+/// of an epoch boundary, and those of the first epoch are the next ones of genesis.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_next_validator_set_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(2)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let final_block = wait_for_final_block(&client, 15).await;
+        let request = |block_reference| RpcNextValidatorSetRequest { block_reference };
+
+        let mut prev_header: Option<BlockHeaderView> = None;
+        let mut epoch_boundaries = 0;
+        for height in 0..=final_block.header.height {
+            let block = match client.block_by_id(BlockId::Height(height)).await {
+                Ok(block) => block,
+                // Skipped height.
+                Err(_) => continue,
+            };
+            let next_validator_set = client
+                .EXPERIMENTAL_next_validator_set(request(BlockReference::BlockId(BlockId::Height(
+                    height,
+                ))))
+                .await
+                .unwrap()
+                .next_validator_set;
+            assert_eq!(next_validator_set.block_hash, block.header.hash);
+            assert_eq!(next_validator_set.epoch_id.0, block.header.epoch_id);
+            assert_eq!(next_validator_set.next_epoch_id.0, block.header.next_epoch_id);
+            assert_eq!(next_validator_set.next_bp_hash, block.header.next_bp_hash);
+            assert_eq!(next_validator_set.compute_next_bp_hash(), block.header.next_bp_hash);
+            assert!(!next_validator_set.next_block_producers.is_empty());
+            // At the boundary, the next epoch of the previous block becomes the current one.
+            if let Some(prev_header) = prev_header {
+                if block.header.epoch_id != prev_header.epoch_id {
+                    assert_eq!(block.header.epoch_id, prev_header.next_epoch_id);
+                    epoch_boundaries += 1;
+                }
+            }
+            prev_header = Some(block.header);
+        }
+        assert!(epoch_boundaries > 0);
+
+        let error = client
+            .EXPERIMENTAL_next_validator_set(request(BlockReference::BlockId(BlockId::Hash(hash(
+                b"unknown",
+            )))))
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_BLOCK"));
+        System::current().stop();
+    });
+}
+
 fn outcome_view_to_hashes(outcome: &ExecutionOutcomeView) -> Vec<CryptoHash> {
     let status = match &outcome.status {
         ExecutionStatusView::Unknown => PartialExecutionStatus::Unknown,
@@ -94,6 +286,13 @@ fn outcome_view_to_hashes(outcome: &ExecutionOutcomeView) -> Vec<CryptoHash> {
     for log in outcome.logs.iter() {
         result.push(hash(log.as_bytes()));
     }
+    for event in outcome.events.iter() {
+        result.push(hash(
+            &(event.standard.clone(), event.data.clone())
+                .try_to_vec()
+                .expect("Failed to serialize"),
+        ));
+    }
     result
 }
 
@@ -216,119 +415,905 @@ fn test_get_execution_outcome(is_tx_successful: bool) {
     });
 }
 
+/// Whether the block is on the chain of the final block of the node, at or below it.
+async fn is_final_block(client: &JsonRpcClient, block_hash: CryptoHash) -> bool {
+    let block = client.block(BlockReference::BlockId(BlockId::Hash(block_hash))).await.unwrap();
+    let final_block = client.block(BlockReference::Finality(Finality::Final)).await.unwrap();
+    let canonical_block =
+        client.block(BlockReference::BlockId(BlockId::Height(block.header.height))).await.unwrap();
+    block.header.height <= final_block.header.height && canonical_block.header.hash == block_hash
+}
+
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
-fn test_get_execution_outcome_tx_success() {
-    test_get_execution_outcome(true);
+fn test_send_tx_wait_until_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(1000)
+        .set_genesis_height(0)
+        .set_rpc_polling_timeout(Duration::from_secs(60));
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let genesis_hash = *genesis_block(&genesis).hash();
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let levels = [
+            TxExecutionStatus::None,
+            TxExecutionStatus::Included,
+            TxExecutionStatus::IncludedFinal,
+            TxExecutionStatus::ExecutedOptimistic,
+            TxExecutionStatus::ExecutedFinal,
+            TxExecutionStatus::Final,
+        ];
+        let mut last_transaction = None;
+        for (nonce, wait_until) in (1..).zip(levels) {
+            let transaction = SignedTransaction::send_money(
+                nonce,
+                "near.0".parse().unwrap(),
+                "near.1".parse().unwrap(),
+                &signer,
+                10000,
+                genesis_hash,
+            );
+            let bytes = transaction.try_to_vec().unwrap();
+            let response = client.send_tx(to_base64(&bytes), wait_until).await.unwrap();
+            assert_eq!(response.final_execution_status, wait_until);
+            match (wait_until, response.outcome) {
+                (
+                    TxExecutionStatus::None,
+                    RpcSendTransactionOutcome::Accepted { transaction_hash },
+                ) => {
+                    assert_eq!(transaction_hash, transaction.get_hash());
+                    // Make sure the next transaction doesn't overtake this one.
+                    client
+                        .send_tx(to_base64(&bytes), TxExecutionStatus::ExecutedOptimistic)
+                        .await
+                        .unwrap();
+                }
+                (
+                    TxExecutionStatus::Included | TxExecutionStatus::IncludedFinal,
+                    RpcSendTransactionOutcome::Included { transaction_outcome },
+                ) => {
+                    assert_eq!(transaction_outcome.id, transaction.get_hash());
+                    if wait_until == TxExecutionStatus::IncludedFinal {
+                        assert!(is_final_block(&client, transaction_outcome.block_hash).await);
+                    }
+                }
+                (
+                    TxExecutionStatus::Included | TxExecutionStatus::IncludedFinal,
+                    RpcSendTransactionOutcome::Executed(outcome),
+                ) => {
+                    // The receipts may have been executed by the time the node responds.
+                    assert_eq!(outcome.transaction_outcome.id, transaction.get_hash());
+                    if wait_until == TxExecutionStatus::IncludedFinal {
+                        assert!(
+                            is_final_block(&client, outcome.transaction_outcome.block_hash).await
+                        );
+                    }
+                }
+                (_, RpcSendTransactionOutcome::Executed(outcome)) => {
+                    assert_eq!(outcome.transaction_outcome.id, transaction.get_hash());
+                    assert_matches!(outcome.status, FinalExecutionStatus::SuccessValue(_));
+                    if wait_until != TxExecutionStatus::ExecutedOptimistic {
+                        assert!(
+                            is_final_block(&client, outcome.transaction_outcome.block_hash).await
+                        );
+                    }
+                    if wait_until == TxExecutionStatus::Final {
+                        for receipt_outcome in &outcome.receipts_outcome {
+                            assert!(is_final_block(&client, receipt_outcome.block_hash).await);
+                        }
+                    }
+                }
+                (wait_until, outcome) => {
+                    panic!("unexpected outcome {:?} for {:?}", outcome, wait_until)
+                }
+            }
+            last_transaction = Some(bytes);
+        }
+
+        // Sending a transaction again doesn't execute it twice but returns its outcome.
+        let response = client
+            .send_tx(to_base64(&last_transaction.unwrap()), TxExecutionStatus::Final)
+            .await
+            .unwrap();
+        assert_matches!(response.outcome, RpcSendTransactionOutcome::Executed(_));
+
+        System::current().stop();
+    });
 }
 
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
-fn test_get_execution_outcome_tx_failure() {
-    test_get_execution_outcome(false);
+fn test_send_tx_wait_until_timeout() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(1000)
+        .set_genesis_height(0)
+        .set_rpc_polling_timeout(Duration::from_millis(1));
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, clients| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let genesis_hash = *genesis_block(&genesis).hash();
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "near.0".parse().unwrap(),
+            "near.1".parse().unwrap(),
+            &signer,
+            10000,
+            genesis_hash,
+        );
+        let bytes = transaction.try_to_vec().unwrap();
+        let error = client.send_tx(to_base64(&bytes), TxExecutionStatus::Final).await.unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("TIMEOUT_ERROR"));
+
+        // The transaction is executed regardless.
+        let view_client = clients[0].1.clone();
+        loop {
+            let tx_status = view_client
+                .send(TxStatus {
+                    tx_hash: transaction.get_hash(),
+                    signer_account_id: "near.0".parse().unwrap(),
+                    fetch_receipt: false,
+                })
+                .await
+                .unwrap();
+            if let Ok(Some(_)) = tx_status {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        System::current().stop();
+    });
+}
+
+/// Waits until the final block of the node is at least at `height`.
+async fn wait_for_final_block(client: &JsonRpcClient, height: BlockHeight) -> BlockView {
+    loop {
+        match client.block(BlockReference::Finality(Finality::Final)).await {
+            Ok(block) if block.header.height >= height => return block,
+            _ => sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+}
+
+/// Next JSON message of a WebSocket connection.
+async fn next_ws_json(
+    frames: &mut (impl Stream<Item = Result<awc::ws::Frame, awc::error::WsProtocolError>> + Unpin),
+) -> serde_json::Value {
+    loop {
+        if let awc::ws::Frame::Text(text) = frames.next().await.unwrap().unwrap() {
+            return serde_json::from_slice(&text).unwrap();
+        }
+    }
+}
+
+/// Subscribes to each of `methods` over the WebSocket connection, returns the methods by the ids
+/// of their subscriptions.
+async fn subscribe_ws<'a>(
+    connection: &mut (impl Stream<Item = Result<awc::ws::Frame, awc::error::WsProtocolError>>
+              + Sink<awc::ws::Message, Error = awc::error::WsProtocolError>
+              + Unpin),
+    methods: &[&'a str],
+) -> HashMap<u64, &'a str> {
+    let mut subscriptions = HashMap::new();
+    for &method in methods {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": method,
+            "method": method,
+            "params": [],
+        });
+        connection.send(awc::ws::Message::Text(request.to_string().into())).await.unwrap();
+        let response = next_ws_json(connection).await;
+        assert_eq!(response["id"], serde_json::json!(method));
+        subscriptions.insert(response["result"].as_u64().unwrap(), method);
+    }
+    subscriptions
+}
+
+/// Checks that the heads pushed over WebSocket are in increasing height order and that none was
+/// skipped: each head builds on the previous one, or on a block not above it after a switch to
+/// another fork. Returns the number of such switches.
+async fn check_ws_heads(client: &JsonRpcClient, heads: &[BlockView]) -> usize {
+    let mut fork_switches = 0;
+    for pair in heads.windows(2) {
+        assert!(pair[0].header.height < pair[1].header.height);
+        if pair[1].header.prev_hash != pair[0].header.hash {
+            let prev = client.block_by_id(BlockId::Hash(pair[1].header.prev_hash)).await.unwrap();
+            assert!(prev.header.height <= pair[0].header.height);
+            fork_switches += 1;
+        }
+    }
+    fork_switches
 }
 
+/// Heads are pushed over WebSocket in increasing height order without duplicates nor skipped
+/// heads, whichever fork the two validators build on, final heads are on the canonical chain,
+/// and a light client block follows the final head into the next epoch.
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
-fn test_protocol_config_rpc() {
+fn test_subscriptions_ws() {
     init_integration_logger();
 
     let cluster = NodeCluster::default()
         .set_num_shards(1)
-        .set_num_validator_seats(1)
+        .set_num_validator_seats(2)
         .set_num_lightclients(0)
         .set_epoch_length(10)
         .set_genesis_height(0);
 
     cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let (_, mut connection) =
+            awc::Client::new().ws(format!("ws://{}/ws", rpc_addrs[0])).connect().await.unwrap();
+        let subscriptions = subscribe_ws(
+            &mut connection,
+            &["subscribe_new_heads", "subscribe_final_heads", "subscribe_light_client_blocks"],
+        )
+        .await;
+
+        let mut new_heads: Vec<BlockView> = vec![];
+        let mut final_heads: Vec<BlockView> = vec![];
+        let mut light_client_blocks: Vec<LightClientBlockView> = vec![];
+        while light_client_blocks.is_empty() || final_heads.len() < 15 {
+            let notification = next_ws_json(&mut connection).await;
+            assert_eq!(notification["method"], serde_json::json!("subscription"));
+            let params = &notification["params"];
+            assert_eq!(params.get("gap"), None);
+            let result = params["result"].clone();
+            match subscriptions[&params["subscription"].as_u64().unwrap()] {
+                "subscribe_new_heads" => new_heads.push(serde_json::from_value(result).unwrap()),
+                "subscribe_final_heads" => {
+                    final_heads.push(serde_json::from_value(result).unwrap())
+                }
+                _ => light_client_blocks.push(serde_json::from_value(result).unwrap()),
+            }
+        }
+
         let client = new_client(&format!("http://{}", rpc_addrs[0]));
-        let config_response = client
-            .EXPERIMENTAL_protocol_config(
-                near_jsonrpc_primitives::types::config::RpcProtocolConfigRequest {
-                    block_reference: near_primitives::types::BlockReference::Finality(
-                        Finality::None,
-                    ),
-                },
-            )
-            .await
-            .unwrap();
+        check_ws_heads(&client, &new_heads).await;
+        assert_eq!(check_ws_heads(&client, &final_heads).await, 0);
+        for head in &final_heads {
+            let block = client.block_by_id(BlockId::Height(head.header.height)).await.unwrap();
+            assert_eq!(block.header.hash, head.header.hash);
+        }
+        let light_client_block = &light_client_blocks[0];
+        assert!(light_client_block.next_bps.is_some());
+        assert!(final_heads
+            .iter()
+            .any(|head| head.header.epoch_id == light_client_block.inner_lite.epoch_id));
 
-        let runtime_config_store = RuntimeConfigStore::new(None);
-        let intial_runtime_config = runtime_config_store.get_config(ProtocolVersion::MIN);
-        let latest_runtime_config = runtime_config_store.get_config(ProtocolVersion::MAX);
-        assert_ne!(
-            config_response.config_view.runtime_config.storage_amount_per_byte,
-            intial_runtime_config.storage_amount_per_byte
-        );
-        assert_eq!(
-            config_response.config_view.runtime_config,
-            latest_runtime_config.as_ref().clone()
-        );
+        // Unknown subscriptions can't be removed, known ones only once.
+        let id = *subscriptions.keys().next().unwrap();
+        for (id, removed) in [(id + 100, false), (id, true), (id, false)] {
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "unsubscribe",
+                "params": [id],
+            });
+            connection.send(awc::ws::Message::Text(request.to_string().into())).await.unwrap();
+            let response = loop {
+                let message = next_ws_json(&mut connection).await;
+                if message.get("id").is_some() {
+                    break message;
+                }
+            };
+            assert_eq!(response["result"], serde_json::json!(removed));
+        }
         System::current().stop();
     });
 }
 
+/// Heads stay in increasing height order without duplicates nor skipped heads across switches to
+/// another fork, forced by the other validator producing bursts of blocks without waiting for the
+/// blocks of the first one.
+#[cfg(feature = "test_features")]
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
-fn test_query_rpc_account_view_must_succeed() {
+fn test_subscriptions_ws_fork() {
     init_integration_logger();
 
     let cluster = NodeCluster::default()
         .set_num_shards(1)
-        .set_num_validator_seats(1)
+        .set_num_validator_seats(2)
         .set_num_lightclients(0)
-        .set_epoch_length(10)
+        .set_epoch_length(1000)
         .set_genesis_height(0);
 
     cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let (_, mut connection) =
+            awc::Client::new().ws(format!("ws://{}/ws", rpc_addrs[0])).connect().await.unwrap();
+        let subscriptions =
+            subscribe_ws(&mut connection, &["subscribe_new_heads", "subscribe_final_heads"]).await;
         let client = new_client(&format!("http://{}", rpc_addrs[0]));
-        let query_response = client
-            .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
-                block_reference: near_primitives::types::BlockReference::Finality(Finality::Final),
-                request: near_primitives::views::QueryRequest::ViewAccount {
-                    account_id: "near.0".parse().unwrap(),
+        let adversary = new_client(&format!("http://{}", rpc_addrs[1]));
+        adversary.call("adv_disable_doomslug", serde_json::json!([])).await.unwrap();
+
+        let mut new_heads: Vec<BlockView> = vec![];
+        let mut final_heads: Vec<BlockView> = vec![];
+        let mut fork_switches = 0;
+        while fork_switches == 0 || final_heads.len() < 5 {
+            let notification = next_ws_json(&mut connection).await;
+            let params = &notification["params"];
+            assert_eq!(params.get("gap"), None);
+            let head = serde_json::from_value(params["result"].clone()).unwrap();
+            if subscriptions[&params["subscription"].as_u64().unwrap()] == "subscribe_final_heads" {
+                final_heads.push(head);
+                continue;
+            }
+            new_heads.push(head);
+            if fork_switches == 0 && new_heads.len() % 5 == 0 {
+                fork_switches = check_ws_heads(&client, &new_heads).await;
+                assert!(new_heads.len() < 500, "no switch to another fork");
+                adversary.call("adv_produce_blocks", serde_json::json!([5, true])).await.unwrap();
+            }
+        }
+        assert!(check_ws_heads(&client, &new_heads).await > 0);
+        assert_eq!(check_ws_heads(&client, &final_heads).await, 0);
+        System::current().stop();
+    });
+}
+
+/// Light client proofs can be built against a light client head several epochs behind the head of
+/// the node, as long as the head is after the block of the outcome.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_light_client_proof_with_old_head() {
+    init_integration_logger();
+
+    let epoch_length = 5;
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(epoch_length)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let genesis_hash = *genesis_block(&genesis).hash();
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "near.0".parse().unwrap(),
+            "near.0".parse().unwrap(),
+            &signer,
+            10000,
+            genesis_hash,
+        );
+        let bytes = transaction.try_to_vec().unwrap();
+        let outcome = loop {
+            match client.broadcast_tx_commit(to_base64(&bytes)).await {
+                Ok(outcome) => break outcome,
+                Err(_) => sleep(std::time::Duration::from_millis(100)).await,
+            }
+        };
+        let outcome_block = client
+            .block(BlockReference::BlockId(BlockId::Hash(outcome.transaction_outcome.block_hash)))
+            .await
+            .unwrap();
+
+        // The light client head is two epochs after the outcome, and the node moves on by
+        // another epoch before the proof is requested.
+        let light_client_head =
+            wait_for_final_block(&client, outcome_block.header.height + 2 * epoch_length + 1).await;
+        wait_for_final_block(&client, light_client_head.header.height + epoch_length).await;
+        let response = client
+            .light_client_proof(RpcLightClientExecutionProofRequest {
+                id: TransactionOrReceiptId::Transaction {
+                    transaction_hash: outcome.transaction_outcome.id,
+                    sender_id: "near.0".parse().unwrap(),
                 },
+                light_client_head: light_client_head.header.hash,
+                encoding: Default::default(),
             })
             .await
             .unwrap();
-        let account =
-            if let near_jsonrpc_primitives::types::query::QueryResponseKind::ViewAccount(account) =
-                query_response.kind
-            {
-                account
-            } else {
-                panic!(
-                    "expected a account view result, but received something else: {:?}",
-                    query_response.kind
-                );
-            };
-        assert_matches!(account, near_primitives::views::AccountView { .. });
+
+        // The outcome is in the outcome root of the block of the proof.
+        let block_header_lite = response.block_header_lite;
+        let mut outcome_with_id_to_hash = vec![response.outcome_proof.id];
+        outcome_with_id_to_hash.extend(outcome_view_to_hashes(&response.outcome_proof.outcome));
+        let chunk_outcome_root = compute_root_from_path_and_item(
+            &response.outcome_proof.proof,
+            &outcome_with_id_to_hash,
+        );
+        assert!(verify_path(
+            block_header_lite.inner_lite.outcome_root,
+            &response.outcome_root_proof,
+            &chunk_outcome_root
+        ));
+
+        // That block is in the block merkle tree of the light client head.
+        assert!(
+            block_header_lite.inner_lite.height + 2 * epoch_length
+                <= light_client_head.header.height
+        );
+        let inner_lite = BlockHeaderInnerLite::from(block_header_lite.inner_lite.clone());
+        let block_hash = combine_hash(
+            &combine_hash(
+                &hash(&inner_lite.try_to_vec().unwrap()),
+                &block_header_lite.inner_rest_hash,
+            ),
+            &block_header_lite.prev_block_hash,
+        );
+        assert_eq!(block_hash, response.outcome_proof.block_hash);
+        assert!(verify_hash(
+            light_client_head.header.block_merkle_root,
+            &response.block_proof,
+            block_hash
+        ));
         System::current().stop();
     });
 }
 
+/// The light client blocks of a localnet, followed from genesis over several epochs, are valid
+/// successors of each other for the light client validation of the runtime, and stop being valid
+/// once their approvals are truncated or the stakes of their block producers overflow. The
+/// signatures don't cover the stakes, so the latter only fail on the stake arithmetic.
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
-fn test_query_rpc_account_view_account_doesnt_exist_must_return_error() {
+fn test_validate_light_client_blocks_rpc() {
     init_integration_logger();
 
     let cluster = NodeCluster::default()
         .set_num_shards(1)
-        .set_num_validator_seats(1)
+        .set_num_validator_seats(3)
         .set_num_lightclients(0)
-        .set_epoch_length(10)
+        .set_epoch_length(5)
         .set_genesis_height(0);
 
-    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
         let client = new_client(&format!("http://{}", rpc_addrs[0]));
-        let error_message = loop {
-            let query_response = client
-                .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
-                    block_reference: near_primitives::types::BlockReference::Finality(Finality::Final),
-                    request: near_primitives::views::QueryRequest::ViewAccount {
-                        account_id: "accountdoesntexist.0".parse().unwrap(),
-                    },
-                })
+        let genesis_block = genesis_block(&genesis);
+        let mut head_hash = *genesis_block.hash();
+        let mut head = LightClientBlockLiteView::from(genesis_block.header().clone());
+        let mut next_bps: Option<Vec<ValidatorStakeView>> = None;
+        let mut epochs = vec![head.inner_lite.epoch_id];
+        while epochs.len() < 4 {
+            let block = match client
+                .call("next_light_client_block", serde_json::json!([head_hash]))
+                .await
+            {
+                Ok(block) if block.as_object().map_or(false, |fields| !fields.is_empty()) => {
+                    serde_json::from_value::<LightClientBlockView>(block).unwrap()
+                }
+                _ => {
+                    sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let mut current_bps = client
+                .EXPERIMENTAL_validators_ordered(RpcValidatorsOrderedRequest {
+                    block_id: Some(BlockId::Height(block.inner_lite.height)),
+                })
+                .await
+                .unwrap();
+            // Block producers entering a new epoch are those announced in the epoch before.
+            if block.inner_lite.epoch_id == head.inner_lite.next_epoch_id {
+                if let Some(next_bps) = &next_bps {
+                    assert_eq!(&current_bps, next_bps);
+                }
+            }
+            let head_bytes = head.try_to_vec().unwrap();
+            let validate = |current_bps: &Vec<ValidatorStakeView>, block: &LightClientBlockView| {
+                validate_light_client_block(
+                    &current_bps.try_to_vec().unwrap(),
+                    &head_bytes,
+                    &block.try_to_vec().unwrap(),
+                )
+            };
+            assert!(validate(&current_bps, &block));
+
+            let first_approval =
+                block.approvals_after_next.iter().position(Option::is_some).unwrap();
+            let mut truncated = block.clone();
+            truncated.approvals_after_next.truncate(first_approval + 1);
+            assert!(!validate(&current_bps, &truncated));
+
+            for bp in current_bps.iter_mut() {
+                let ValidatorStakeView::V1(bp) = bp;
+                bp.stake = Balance::MAX;
+            }
+            assert!(!validate(&current_bps, &block));
+
+            let inner_lite = BlockHeaderInnerLite::from(block.inner_lite.clone());
+            head_hash = combine_hash(
+                &combine_hash(&hash(&inner_lite.try_to_vec().unwrap()), &block.inner_rest_hash),
+                &block.prev_block_hash,
+            );
+            if block.inner_lite.epoch_id != *epochs.last().unwrap() {
+                epochs.push(block.inner_lite.epoch_id);
+            }
+            next_bps = block.next_bps.clone();
+            head = LightClientBlockLiteView {
+                prev_block_hash: block.prev_block_hash,
+                inner_rest_hash: block.inner_rest_hash,
+                inner_lite: block.inner_lite,
+            };
+        }
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_block_merkle_proof_rpc() {
+    init_integration_logger();
+
+    let epoch_length = 5;
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(epoch_length)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let head = wait_for_final_block(&client, 2 * epoch_length + 2).await;
+        let request = |block_hash, head_block_hash| RpcBlockMerkleProofRequest {
+            block_hash,
+            head_block_hash,
+            encoding: Default::default(),
+        };
+
+        // Blocks of all epochs up to the head, starting with genesis, are in the root of the head.
+        let mut epoch_ids = vec![];
+        for height in 0..head.header.height {
+            let block = match client.block(BlockReference::BlockId(BlockId::Height(height))).await {
+                Ok(block) => block,
+                Err(_) => continue,
+            };
+            let response = client
+                .EXPERIMENTAL_block_merkle_proof(request(block.header.hash, head.header.hash))
+                .await
+                .unwrap();
+            assert!(verify_hash(
+                head.header.block_merkle_root,
+                &response.block_proof,
+                block.header.hash
+            ));
+            if !epoch_ids.contains(&block.header.epoch_id) {
+                epoch_ids.push(block.header.epoch_id);
+            }
+        }
+        assert!(epoch_ids.len() >= 2);
+
+        let response = client
+            .EXPERIMENTAL_block_merkle_proof(request(head.header.hash, head.header.hash))
+            .await
+            .unwrap();
+        assert!(response.block_proof.is_empty());
+
+        let genesis = client.block(BlockReference::BlockId(BlockId::Height(0))).await.unwrap();
+        let error = client
+            .EXPERIMENTAL_block_merkle_proof(request(head.header.hash, genesis.header.hash))
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("BLOCK_AFTER_HEAD"));
+
+        let error = client
+            .EXPERIMENTAL_block_merkle_proof(request(hash(b"unknown"), head.header.hash))
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_BLOCK"));
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_get_execution_outcome_tx_success() {
+    test_get_execution_outcome(true);
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_get_execution_outcome_tx_failure() {
+    test_get_execution_outcome(false);
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_tx_status_with_proofs_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(1000)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let genesis_hash = *genesis_block(&genesis).hash();
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "near.0".parse().unwrap(),
+            "near.1".parse().unwrap(),
+            &signer,
+            10000,
+            genesis_hash,
+        );
+        let bytes = transaction.try_to_vec().unwrap();
+        let outcome = client.broadcast_tx_commit(to_base64(&bytes)).await.unwrap();
+        let last_block = client
+            .block(BlockReference::BlockId(BlockId::Hash(
+                outcome.receipts_outcome.last().unwrap().block_hash,
+            )))
+            .await
+            .unwrap();
+        // Wait for the chunks following all the outcomes.
+        wait_for_final_block(&client, last_block.header.height + 2).await;
+
+        let response = client
+            .call("tx", serde_json::json!([outcome.transaction_outcome.id, "near.0", true]))
+            .await
+            .unwrap();
+        let proofs: Vec<OutcomeRootProofView> =
+            serde_json::from_value(response["outcome_root_proofs"].clone()).unwrap();
+        let outcomes: Vec<_> = std::iter::once(&outcome.transaction_outcome)
+            .chain(&outcome.receipts_outcome)
+            .collect();
+        assert_eq!(proofs.len(), outcomes.len());
+        for (outcome, proof) in outcomes.into_iter().zip(proofs) {
+            assert_eq!(proof.id, outcome.id);
+            let block = client
+                .block(BlockReference::BlockId(BlockId::Hash(proof.block_hash)))
+                .await
+                .unwrap();
+            let outcome_block = client
+                .block(BlockReference::BlockId(BlockId::Hash(outcome.block_hash)))
+                .await
+                .unwrap();
+            assert!(block.header.height > outcome_block.header.height);
+            let mut outcome_with_id_to_hash = vec![outcome.id];
+            outcome_with_id_to_hash.extend(outcome_view_to_hashes(&outcome.outcome));
+            let chunk_outcome_root =
+                compute_root_from_path_and_item(&outcome.proof, &outcome_with_id_to_hash);
+            assert!(verify_path(
+                block.header.outcome_root,
+                &proof.outcome_root_proof,
+                &chunk_outcome_root
+            ));
+        }
+
+        // Proofs are only included on request.
+        let response = client
+            .call("tx", serde_json::json!([outcome.transaction_outcome.id, "near.0"]))
+            .await
+            .unwrap();
+        assert!(response.get("outcome_root_proofs").is_none());
+        assert_eq!(
+            response["transaction_outcome"]["id"],
+            serde_json::json!(outcome.transaction_outcome.id)
+        );
+
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_protocol_config_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let config_response = client
+            .EXPERIMENTAL_protocol_config(
+                near_jsonrpc_primitives::types::config::RpcProtocolConfigRequest {
+                    protocol_config_reference: ProtocolConfigReference::BlockReference(
+                        near_primitives::types::BlockReference::Finality(Finality::None),
+                    ),
+                },
+            )
+            .await
+            .unwrap();
+
+        let runtime_config_store = RuntimeConfigStore::new(None);
+        let intial_runtime_config = runtime_config_store.get_config(ProtocolVersion::MIN);
+        let latest_runtime_config = runtime_config_store.get_config(ProtocolVersion::MAX);
+        assert_ne!(
+            config_response.config_view.runtime_config.storage_amount_per_byte,
+            intial_runtime_config.storage_amount_per_byte
+        );
+        assert_eq!(
+            config_response.config_view.runtime_config,
+            latest_runtime_config.as_ref().clone()
+        );
+        System::current().stop();
+    });
+}
+
+/// Nodes upgrading to the fork's protocol version mid-run report the config of every epoch, with
+/// the fork parameters taking effect only from the upgrade on.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_protocol_config_at_epoch_rpc() {
+    init_integration_logger();
+
+    let fork_parameters: ParameterTableOverlay = Parameter::fork_ext_costs()
+        .map(|param| format!("{param}: 1_000\n"))
+        .collect::<String>()
+        .parse()
+        .unwrap();
+    let old_protocol_version = FORK_PROTOCOL_VERSION - 1;
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(5)
+        .set_genesis_height(0)
+        .set_genesis_protocol_version(old_protocol_version)
+        .set_fork_parameters(fork_parameters.clone());
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let new_block = loop {
+            let block = client.block(BlockReference::Finality(Finality::Final)).await.unwrap();
+            let config = client
+                .EXPERIMENTAL_protocol_config(RpcProtocolConfigRequest {
+                    protocol_config_reference: ProtocolConfigReference::BlockReference(
+                        BlockReference::BlockId(BlockId::Hash(block.header.hash)),
+                    ),
+                })
+                .await
+                .unwrap()
+                .config_view;
+            if config.protocol_version >= FORK_PROTOCOL_VERSION {
+                break block;
+            }
+            sleep(Duration::from_millis(500)).await;
+        };
+        let genesis_block = client.block_by_id(BlockId::Height(0)).await.unwrap();
+
+        let old_config = client
+            .EXPERIMENTAL_protocol_config(RpcProtocolConfigRequest {
+                protocol_config_reference: ProtocolConfigReference::EpochId {
+                    epoch_id: EpochId(genesis_block.header.epoch_id),
+                },
+            })
+            .await
+            .unwrap()
+            .config_view;
+        let new_config = client
+            .EXPERIMENTAL_protocol_config(RpcProtocolConfigRequest {
+                protocol_config_reference: ProtocolConfigReference::EpochId {
+                    epoch_id: EpochId(new_block.header.epoch_id),
+                },
+            })
+            .await
+            .unwrap()
+            .config_view;
+        assert_eq!(old_config.protocol_version, old_protocol_version);
+        assert!(new_config.protocol_version >= FORK_PROTOCOL_VERSION);
+
+        let runtime_config_store = RuntimeConfigStore::new(None);
+        let fork_runtime_config_store =
+            RuntimeConfigStore::with_fork_parameters(None, Some(&fork_parameters));
+        assert_eq!(
+            &old_config.runtime_config,
+            runtime_config_store.get_config(old_protocol_version).as_ref()
+        );
+        assert_eq!(
+            &new_config.runtime_config,
+            fork_runtime_config_store.get_config(new_config.protocol_version).as_ref()
+        );
+        let (old_ext_costs, new_ext_costs) = (
+            &old_config.runtime_config.wasm_config.ext_costs,
+            &new_config.runtime_config.wasm_config.ext_costs,
+        );
+        assert_ne!(old_ext_costs.verify_ics23_proof_base, 1_000);
+        assert_eq!(new_ext_costs.verify_ics23_proof_base, 1_000);
+        assert_eq!(old_ext_costs.storage_read_base, new_ext_costs.storage_read_base);
+
+        let error = client
+            .EXPERIMENTAL_protocol_config(RpcProtocolConfigRequest {
+                protocol_config_reference: ProtocolConfigReference::EpochId {
+                    epoch_id: EpochId(hash(b"unknown")),
+                },
+            })
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_EPOCH"));
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_query_rpc_account_view_must_succeed() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let query_response = client
+            .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
+                block_reference: near_primitives::types::BlockReference::Finality(Finality::Final),
+                request: near_primitives::views::QueryRequest::ViewAccount {
+                    account_id: "near.0".parse().unwrap(),
+                    include_proof: false,
+                },
+                encoding: Default::default(),
+            })
+            .await
+            .unwrap();
+        let account =
+            if let near_jsonrpc_primitives::types::query::QueryResponseKind::ViewAccount(account) =
+                query_response.kind
+            {
+                account
+            } else {
+                panic!(
+                    "expected a account view result, but received something else: {:?}",
+                    query_response.kind
+                );
+            };
+        assert_matches!(account, near_primitives::views::AccountView { .. });
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_query_rpc_account_view_account_doesnt_exist_must_return_error() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let error_message = loop {
+            let query_response = client
+                .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
+                    block_reference: near_primitives::types::BlockReference::Finality(Finality::Final),
+                    request: near_primitives::views::QueryRequest::ViewAccount {
+                        account_id: "accountdoesntexist.0".parse().unwrap(), include_proof: false,
+                    },
+                    encoding: Default::default(),
+                })
                 .await;
 
             break match query_response {
@@ -357,6 +1342,497 @@ fn test_query_rpc_account_view_account_doesnt_exist_must_return_error() {
     });
 }
 
+/// Queries of blocks whose data a node garbage collected fail with an error telling from which
+/// height the node keeps data, unless the node forwards them to an archival node.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_query_garbage_collected_block_rpc() {
+    init_integration_logger();
+
+    // Node 1 forwards the queries of garbage collected data to the archival node 2, the validator
+    // node 0 doesn't.
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_nodes(3)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(5)
+        .set_genesis_height(0)
+        .set_archival_fallback(true);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let request = || near_jsonrpc_primitives::types::query::RpcQueryRequest {
+            block_reference: BlockReference::BlockId(BlockId::Height(1)),
+            request: near_primitives::views::QueryRequest::ViewAccount {
+                account_id: "near.0".parse().unwrap(),
+                include_proof: false,
+            },
+            encoding: Default::default(),
+        };
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let error = loop {
+            match client.query(request()).await {
+                Ok(_) => sleep(std::time::Duration::from_millis(500)).await,
+                Err(err) => break serde_json::to_value(err).unwrap(),
+            }
+        };
+        assert_eq!(error["cause"]["name"], serde_json::json!("GARBAGE_COLLECTED_BLOCK"));
+        assert_eq!(error["cause"]["info"]["block_height"], serde_json::json!(1));
+        assert!(error["cause"]["info"]["gc_horizon"].as_u64().unwrap() > 1);
+
+        // Once node 1 garbage collected the block too, its data comes from the archival node.
+        let client = new_client(&format!("http://{}", rpc_addrs[1]));
+        while client.block_by_id(BlockId::Height(1)).await.is_ok() {
+            sleep(std::time::Duration::from_millis(500)).await;
+        }
+        let query_response = client.query(request()).await.unwrap();
+        assert_eq!(query_response.block_height, 1);
+        assert_matches!(
+            query_response.kind,
+            near_jsonrpc_primitives::types::query::QueryResponseKind::ViewAccount(_)
+        );
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_state_proof_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let account_id: AccountId = "near.0".parse().unwrap();
+        let request = |block_reference| RpcStateProofRequest {
+            block_reference,
+            account_id: account_id.clone(),
+            key: b"missing".to_vec(),
+            encoding: Default::default(),
+        };
+        let response = loop {
+            match client
+                .EXPERIMENTAL_state_proof(request(BlockReference::Finality(Finality::Final)))
+                .await
+            {
+                Ok(response) => break response,
+                Err(err) => {
+                    let error = serde_json::to_value(err).unwrap();
+                    assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_BLOCK"));
+                    println!("No blocks are produced yet, retry.");
+                    sleep(std::time::Duration::from_millis(100)).await;
+                }
+            }
+        };
+        let state_proof = response.state_proof;
+        assert_eq!(state_proof.value, None);
+        assert_eq!(state_proof.shard_id, 0);
+        let proof: Vec<Vec<u8>> =
+            state_proof.proof.iter().map(|node| from_base64(node).unwrap()).collect();
+        let key = contract_data_key(&account_id, b"missing");
+        assert_eq!(verify_state_proof(&proof, &state_proof.state_root, &key, None), Ok(()));
+        assert_eq!(verify_non_existence(&state_proof.state_root, &key, &proof), Ok(()));
+        assert!(
+            verify_state_proof(&proof, &state_proof.state_root, &key, Some(&b"1"[..])).is_err()
+        );
+
+        // The chunk identifies the shard in the block.
+        let block = client
+            .block(BlockReference::BlockId(BlockId::Hash(response.block_hash)))
+            .await
+            .unwrap();
+        assert_eq!(block.header.height, response.block_height);
+        assert_eq!(block.chunks[0].chunk_hash, state_proof.chunk_hash.0);
+
+        let error = client
+            .EXPERIMENTAL_state_proof(request(BlockReference::BlockId(BlockId::Height(
+                response.block_height + 1000,
+            ))))
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_BLOCK"));
+        System::current().stop();
+    });
+}
+
+/// A state proof at a block verifies up to the header of the next block: the state root it is
+/// against is the one the chunk of the next block is applied to, whose header is in the chunk
+/// headers root of that block.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_chunk_state_root_proof_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let final_block = wait_for_final_block(&client, 3).await;
+        let height = final_block.header.height - 1;
+
+        let account_id: AccountId = "near.0".parse().unwrap();
+        let state_proof = client
+            .EXPERIMENTAL_state_proof(RpcStateProofRequest {
+                block_reference: BlockReference::BlockId(BlockId::Height(height)),
+                account_id: account_id.clone(),
+                key: b"missing".to_vec(),
+                encoding: Default::default(),
+            })
+            .await
+            .unwrap()
+            .state_proof;
+        let proof: Vec<Vec<u8>> =
+            state_proof.proof.iter().map(|node| from_base64(node).unwrap()).collect();
+        let key = contract_data_key(&account_id, b"missing");
+        assert_eq!(verify_state_proof(&proof, &state_proof.state_root, &key, None), Ok(()));
+
+        let response = client
+            .EXPERIMENTAL_chunk_state_root_proof(RpcChunkStateRootProofRequest {
+                chunk_reference: ChunkReference::BlockShardId {
+                    block_id: BlockId::Height(height + 1),
+                    shard_id: 0,
+                },
+            })
+            .await
+            .unwrap();
+        let chunk_proof = response.proof;
+        let block = client
+            .block(BlockReference::BlockId(BlockId::Hash(chunk_proof.block_hash)))
+            .await
+            .unwrap();
+        assert_eq!(block.header.height, height + 1);
+        let block_header = BlockHeader::from(block.header.clone());
+        assert_eq!(*block_header.hash(), chunk_proof.block_hash);
+
+        let chunk_header = chunk_proof.verify(block_header.chunk_headers_root()).unwrap();
+        assert_eq!(chunk_header.shard_id(), 0);
+        assert_eq!(chunk_header.height_included(), height + 1);
+        assert_eq!(chunk_header.prev_state_root(), state_proof.state_root);
+        assert_eq!(chunk_header.chunk_hash().0, chunk_proof.chunk_header.chunk_hash);
+        assert_eq!(chunk_proof.verify(&CryptoHash::default()), None);
+
+        // The chunk can be referred to by its hash too.
+        let by_hash = client
+            .EXPERIMENTAL_chunk_state_root_proof(RpcChunkStateRootProofRequest {
+                chunk_reference: ChunkReference::ChunkHash {
+                    chunk_id: chunk_proof.chunk_header.chunk_hash,
+                },
+            })
+            .await
+            .unwrap()
+            .proof;
+        assert_eq!(by_hash.block_hash, chunk_proof.block_hash);
+        assert_eq!(by_hash.raw_chunk_header, chunk_proof.raw_chunk_header);
+        assert_eq!(by_hash.chunk_headers_proof, chunk_proof.chunk_headers_proof);
+
+        let error = client
+            .EXPERIMENTAL_chunk_state_root_proof(RpcChunkStateRootProofRequest {
+                chunk_reference: ChunkReference::BlockShardId {
+                    block_id: BlockId::Height(height + 1),
+                    shard_id: 1,
+                },
+            })
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("INVALID_SHARD_ID"));
+        System::current().stop();
+    });
+}
+
+/// The trie key a state change is about and the value it sets, as stored in the trie.
+fn state_change_trie_key_and_value(value: &StateChangeValueView) -> (Vec<u8>, Option<Vec<u8>>) {
+    match value {
+        StateChangeValueView::AccountUpdate { account_id, account } => (
+            TrieKey::Account { account_id: account_id.clone() }.to_vec(),
+            Some(Account::from(account).try_to_vec().unwrap()),
+        ),
+        StateChangeValueView::AccountDeletion { account_id } => {
+            (TrieKey::Account { account_id: account_id.clone() }.to_vec(), None)
+        }
+        StateChangeValueView::AccessKeyUpdate { account_id, public_key, access_key } => (
+            TrieKey::AccessKey { account_id: account_id.clone(), public_key: public_key.clone() }
+                .to_vec(),
+            Some(AccessKey::from(access_key.clone()).try_to_vec().unwrap()),
+        ),
+        StateChangeValueView::AccessKeyDeletion { account_id, public_key } => (
+            TrieKey::AccessKey { account_id: account_id.clone(), public_key: public_key.clone() }
+                .to_vec(),
+            None,
+        ),
+        StateChangeValueView::DataUpdate { account_id, key, value } => {
+            (contract_data_key(account_id, key.as_ref()), Some(value.as_ref().to_vec()))
+        }
+        StateChangeValueView::DataDeletion { account_id, key } => {
+            (contract_data_key(account_id, key.as_ref()), None)
+        }
+        StateChangeValueView::ContractCodeUpdate { .. }
+        | StateChangeValueView::ContractCodeDeletion { .. } => {
+            panic!("Contract code changes have no proofs")
+        }
+    }
+}
+
+/// The changes of a block come with proofs of the values they set against the state root of
+/// their shard after the block.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_changes_with_proof_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let account_id: AccountId = "near.0".parse().unwrap();
+        let signer = InMemorySigner::from_seed(account_id.clone(), KeyType::ED25519, "near.0");
+        let mut actions = vec![Action::DeployContract(DeployContractAction {
+            code: near_test_contracts::rs_contract().to_vec(),
+        })];
+        for key in 1u64..=3 {
+            actions.push(Action::FunctionCall(FunctionCallAction {
+                method_name: "write_key_value".to_string(),
+                args: [key.to_le_bytes(), (key * 10).to_le_bytes()].concat(),
+                gas: 50_000_000_000_000,
+                deposit: 0,
+            }));
+        }
+        let transaction = SignedTransaction::from_actions(
+            1,
+            account_id.clone(),
+            account_id.clone(),
+            &signer,
+            actions,
+            *genesis_block(&genesis).hash(),
+        );
+        let outcome = loop {
+            match client.broadcast_tx_commit(to_base64(&transaction.try_to_vec().unwrap())).await {
+                Ok(outcome) => break outcome,
+                Err(_) => sleep(std::time::Duration::from_millis(100)).await,
+            }
+        };
+        assert_matches!(outcome.status, FinalExecutionStatus::SuccessValue(_));
+        // The receipt to the signer itself is applied in the block of the transaction.
+        let block_hash = outcome.receipts_outcome[0].block_hash;
+
+        let request = |state_changes_request, cursor| RpcStateChangesInBlockByTypeRequest {
+            block_reference: BlockReference::BlockId(BlockId::Hash(block_hash)),
+            state_changes_request,
+            include_proof: true,
+            cursor,
+        };
+        let data_changes = || StateChangesRequestView::DataChanges {
+            account_ids: vec![account_id.clone()],
+            key_prefix: b"".to_vec().into(),
+        };
+        let block = client.block(BlockReference::BlockId(BlockId::Hash(block_hash))).await.unwrap();
+        for state_changes_request in [
+            data_changes(),
+            StateChangesRequestView::AccountChanges { account_ids: vec![account_id.clone()] },
+            StateChangesRequestView::AllAccessKeyChanges { account_ids: vec![account_id.clone()] },
+        ] {
+            let response =
+                client.EXPERIMENTAL_changes(request(state_changes_request, None)).await.unwrap();
+            assert_eq!(response.next_cursor, None);
+            let proofs = response.proofs.unwrap();
+            assert!(!proofs.is_empty());
+            for state_change_proof in proofs {
+                assert_eq!(state_change_proof.shard_id, 0);
+                assert_eq!(block.chunks[0].chunk_hash, state_change_proof.chunk_hash.0);
+                let change = &response.changes[state_change_proof.change_index as usize];
+                let (key, value) = state_change_trie_key_and_value(&change.value);
+                let proof: Vec<Vec<u8>> = state_change_proof
+                    .proof
+                    .iter()
+                    .map(|node| from_base64(node).unwrap())
+                    .collect();
+                assert_eq!(
+                    verify_state_proof(
+                        &proof,
+                        &state_change_proof.state_root,
+                        &key,
+                        value.as_deref()
+                    ),
+                    Ok(())
+                );
+            }
+        }
+
+        // Every key is written once, so each data change has a proof.
+        let response = client.EXPERIMENTAL_changes(request(data_changes(), None)).await.unwrap();
+        assert_eq!(response.changes.len(), 3);
+        assert_eq!(response.proofs.unwrap().len(), 3);
+        let response = client.EXPERIMENTAL_changes(request(data_changes(), Some(1))).await.unwrap();
+        assert_eq!(response.changes.len(), 2);
+        let change_indexes: Vec<u64> =
+            response.proofs.unwrap().iter().map(|proof| proof.change_index).collect();
+        assert_eq!(change_indexes, vec![0, 1]);
+
+        // Without `include_proof` the response is as before.
+        let mut without_proof = request(data_changes(), None);
+        without_proof.include_proof = false;
+        let response = client.EXPERIMENTAL_changes(without_proof).await.unwrap();
+        assert_eq!(response.changes.len(), 3);
+        assert!(response.proofs.is_none());
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_trie_node_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_nodes(2)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0)
+        .set_enable_debug_rpc(true);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let validator = new_client(&format!("http://{}", rpc_addrs[0]));
+        let client = new_client(&format!("http://{}", rpc_addrs[1]));
+        let state_root = loop {
+            match client.block(BlockReference::Finality(Finality::Final)).await {
+                Ok(block) => break block.chunks[0].prev_state_root,
+                Err(_) => {
+                    println!("The node is not ready yet, retry.");
+                    sleep(std::time::Duration::from_millis(100)).await;
+                }
+            }
+        };
+
+        // The root node decodes to the node stored under the state root.
+        let response = client
+            .EXPERIMENTAL_trie_node(RpcTrieNodeRequest { shard_id: 0, hash: state_root })
+            .await
+            .unwrap();
+        let trie_node = response.trie_node;
+        assert_eq!(trie_node.hash, state_root);
+        assert_eq!(hash(&trie_node.raw), state_root);
+        let raw_node = RawTrieNodeWithSize::decode(&trie_node.raw).unwrap();
+        let node = trie_node.node.unwrap();
+        assert_eq!(node.memory_usage, raw_node.memory_usage);
+        let child = match (node.kind, raw_node.node) {
+            (TrieNodeKindView::Extension { child, .. }, RawTrieNode::Extension(_, raw_child)) => {
+                assert_eq!(child, raw_child);
+                child
+            }
+            (TrieNodeKindView::Branch { children, .. }, RawTrieNode::Branch(raw_children, _)) => {
+                assert_eq!(children, raw_children.to_vec());
+                children.into_iter().flatten().next().unwrap()
+            }
+            (kind, raw_node) => panic!("Unexpected root node {:?} decoded as {:?}", raw_node, kind),
+        };
+
+        // Children of the root are stored under their hashes as well.
+        let response = client
+            .EXPERIMENTAL_trie_node(RpcTrieNodeRequest { shard_id: 0, hash: child })
+            .await
+            .unwrap();
+        assert_eq!(hash(&response.trie_node.raw), child);
+        assert!(response.trie_node.node.unwrap().memory_usage < node.memory_usage);
+
+        let error = client
+            .EXPERIMENTAL_trie_node(RpcTrieNodeRequest { shard_id: 1, hash: state_root })
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("UNKNOWN_SHARD"));
+
+        // Validators refuse to serve trie nodes.
+        let error = validator
+            .EXPERIMENTAL_trie_node(RpcTrieNodeRequest { shard_id: 0, hash: state_root })
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("VALIDATOR_NODE"));
+        System::current().stop();
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_raw_trie_key_rpc() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0)
+        .set_enable_debug_rpc(true);
+
+    cluster.exec_until_stop(|_, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let trie_key = TrieKey::AccessKey {
+            account_id: "near.0".parse().unwrap(),
+            public_key: signer.public_key.clone(),
+        };
+        let response = client
+            .EXPERIMENTAL_raw_trie_key(RpcRawTrieKeyRequest {
+                trie_key: TrieKeyView::AccessKey {
+                    account_id: "near.0".parse().unwrap(),
+                    public_key: signer.public_key,
+                },
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.raw_key, trie_key.to_vec());
+        assert_eq!(response.column, 2);
+
+        let response = client
+            .call(
+                "EXPERIMENTAL_raw_trie_key",
+                serde_json::json!({
+                    "key_type": "contract_data",
+                    "account_id": "near.0",
+                    "key_base64": "AQI=",
+                }),
+            )
+            .await
+            .unwrap();
+        let trie_key =
+            TrieKey::ContractData { account_id: "near.0".parse().unwrap(), key: vec![1, 2] };
+        assert_eq!(response["raw_key_base64"], serde_json::json!(to_base64(&trie_key.to_vec())));
+        assert_eq!(response["column"], serde_json::json!(9));
+
+        let error = client
+            .call(
+                "EXPERIMENTAL_raw_trie_key",
+                serde_json::json!({"key_type": "account", "account_id": "Invalid.Account"}),
+            )
+            .await
+            .unwrap_err();
+        let error = serde_json::to_value(error).unwrap();
+        assert_eq!(error["cause"]["name"], serde_json::json!("PARSE_ERROR"));
+        System::current().stop();
+    });
+}
+
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
 fn test_tx_not_enough_balance_must_return_error() {
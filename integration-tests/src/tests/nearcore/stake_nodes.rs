@@ -262,6 +262,7 @@ fn test_validator_kickout() {
                                                         account_id: test_nodes[i as usize]
                                                             .account_id
                                                             .clone(),
+                                                        include_proof: false,
                                                     },
                                                 ))
                                                 .then(move |res| {
@@ -292,6 +293,7 @@ fn test_validator_kickout() {
                                                         account_id: test_nodes[i as usize]
                                                             .account_id
                                                             .clone(),
+                                                        include_proof: false,
                                                     },
                                                 ))
                                                 .then(move |res| {
@@ -440,6 +442,7 @@ fn test_validator_join() {
                                                 BlockReference::latest(),
                                                 QueryRequest::ViewAccount {
                                                     account_id: test_nodes[1].account_id.clone(),
+                                                    include_proof: false,
                                                 },
                                             ))
                                             .then(move |res| match res.unwrap().unwrap().kind {
@@ -459,6 +462,7 @@ fn test_validator_join() {
                                                 BlockReference::latest(),
                                                 QueryRequest::ViewAccount {
                                                     account_id: test_nodes[2].account_id.clone(),
+                                                    include_proof: false,
                                                 },
                                             ))
                                             .then(move |res| match res.unwrap().unwrap().kind {
@@ -38,7 +38,7 @@ fn add_blocks(
     let mut prev = &blocks[blocks.len() - 1];
     let mut block_merkle_tree = PartialMerkleTree::default();
     for block in blocks.iter() {
-        block_merkle_tree.insert(*block.hash());
+        block_merkle_tree.append(*block.hash());
     }
     for _ in 0..num {
         let epoch_id = match prev.header().height() + 1 {
@@ -86,7 +86,7 @@ fn add_blocks(
             block_merkle_tree.root(),
             None,
         );
-        block_merkle_tree.insert(*block.hash());
+        block_merkle_tree.append(*block.hash());
         let _ = client.do_send(NetworkClientMessages::Block(
             block.clone(),
             PeerInfo::random().id,
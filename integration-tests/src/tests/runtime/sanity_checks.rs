@@ -9,7 +9,7 @@ use near_primitives::views::{
     CostGasUsed, ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionStatus,
 };
 use nearcore::config::GenesisExt;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::mem::size_of;
 use testlib::runtime_utils::{add_test_contract, alice_account, bob_account};
 
@@ -326,3 +326,54 @@ fn contract_sanity_check_used_gas() -> Vec<u8> {
     )
     .unwrap()
 }
+
+/// Calls host function `verify_near_state_proof` with a proof of one node and
+/// verifies that the gas profile of the receipt attributes its gas to the
+/// state proof costs.
+#[test]
+fn test_gas_profile_state_proof_costs() {
+    // The proof at offset 32 is the borsh encoding of a single empty node.
+    let contract = near_test_contracts::wat_contract(
+        r#"
+          (module
+            (import "env" "verify_near_state_proof"
+              (func $verify_near_state_proof
+                (param i64 i64 i64 i64 i64 i64 i64 i64 i64 i64)))
+            (memory 1)
+            (data (i32.const 32) "\01\00\00\00\00\00\00\00")
+            (data (i32.const 64) "alice.near")
+            (data (i32.const 80) "key")
+            (func (export "main")
+              (call $verify_near_state_proof
+                (i64.const 0) (i64.const 8) (i64.const 32) (i64.const 10) (i64.const 64)
+                (i64.const 3) (i64.const 80) (i64.const 0) (i64.const 0) (i64.const 0)))
+          )
+        "#,
+    );
+    let node = setup_runtime_node_with_contract(&contract);
+    let res = node
+        .user()
+        .function_call(alice_account(), test_contract_account(), "main", vec![], MAX_GAS, 0)
+        .unwrap();
+    assert_eq!(res.status, FinalExecutionStatus::SuccessValue(to_base64(&[])));
+
+    let gas_profile = res.receipts_outcome[0].outcome.metadata.gas_profile.as_ref().unwrap();
+    let gas_used_by_cost: HashMap<&str, u64> = gas_profile
+        .iter()
+        .filter(|cost| cost.cost_category == "WASM_HOST_COST")
+        .map(|cost| (cost.cost.as_str(), cost.gas_used))
+        .collect();
+    let ext_costs = node.client.read().unwrap().runtime_config.wasm_config.ext_costs.clone();
+    assert_eq!(
+        gas_used_by_cost.get("VERIFY_NEAR_STATE_PROOF_BASE"),
+        Some(&ext_costs.verify_near_state_proof_base)
+    );
+    assert_eq!(
+        gas_used_by_cost.get("VERIFY_NEAR_STATE_PROOF_BYTE"),
+        Some(&(8 * ext_costs.verify_near_state_proof_byte))
+    );
+    assert_eq!(
+        gas_used_by_cost.get("VERIFY_NEAR_STATE_PROOF_NODE"),
+        Some(&ext_costs.verify_near_state_proof_node)
+    );
+}
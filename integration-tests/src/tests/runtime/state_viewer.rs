@@ -1,19 +1,35 @@
 use crate::runtime_utils::{get_runtime_and_trie, get_test_trie_viewer, TEST_SHARD_UID};
+use borsh::BorshSerialize;
+use near_crypto::{KeyType, PublicKey};
 use near_primitives::{
     account::Account,
     hash::hash as sha256,
     hash::CryptoHash,
-    views::{StateItem, ViewApplyState},
+    runtime::fees::RuntimeFeesConfig,
+    serialize::{from_base64, to_base64},
+    views::{ElidedValueView, StateItem, ViewApplyState, ViewStateProofKind, ViewStateResult},
 };
 use near_primitives::{
     test_utils::MockEpochInfoProvider,
-    trie_key::TrieKey,
-    types::{EpochId, StateChangeCause},
+    trie_key::{trie_key_parsers, TrieKey},
+    types::{AccountId, EpochId, Gas, StateChangeCause},
     version::PROTOCOL_VERSION,
 };
-use near_store::set_account;
+use near_store::flat_contract_data::FlatContractData;
+use near_store::proof_verifier::{
+    contract_data_key, verify_non_existence, verify_state_proof, verify_view_state_item,
+    verify_view_state_result, ProofError,
+};
+use near_store::{set_account, ShardTries, WrappedTrieChanges};
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::mocks::mock_memory::MockedMemory;
+use near_vm_logic::{
+    verify_prefix_absence, verify_state_proof_value_hash, VMConfig, VMContext, VMLogic,
+};
 use node_runtime::state_viewer::errors;
 use node_runtime::state_viewer::*;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
 use testlib::runtime_utils::{alice_account, encode_int};
 
 #[test]
@@ -27,6 +43,7 @@ fn test_view_call() {
         block_hash: CryptoHash::default(),
         epoch_id: EpochId::default(),
         epoch_height: 0,
+        chain_id: "test-chain".to_string(),
         block_timestamp: 1,
         current_protocol_version: PROTOCOL_VERSION,
         cache: None,
@@ -37,11 +54,13 @@ fn test_view_call() {
         &"test.contract".parse().unwrap(),
         "run_test",
         &[],
+        None,
+        false,
         &mut logs,
         &MockEpochInfoProvider::default(),
     );
 
-    assert_eq!(result.unwrap(), encode_int(10));
+    assert_eq!(result.unwrap().0, encode_int(10));
 }
 
 #[test]
@@ -55,6 +74,7 @@ fn test_view_call_try_changing_storage() {
         block_hash: CryptoHash::default(),
         epoch_id: EpochId::default(),
         epoch_height: 0,
+        chain_id: "test-chain".to_string(),
         block_timestamp: 1,
         current_protocol_version: PROTOCOL_VERSION,
         cache: None,
@@ -65,6 +85,8 @@ fn test_view_call_try_changing_storage() {
         &"test.contract".parse().unwrap(),
         "run_test_with_storage_change",
         &[],
+        None,
+        false,
         &mut logs,
         &MockEpochInfoProvider::default(),
     );
@@ -87,6 +109,7 @@ fn test_view_call_with_args() {
         block_hash: CryptoHash::default(),
         epoch_id: EpochId::default(),
         epoch_height: 0,
+        chain_id: "test-chain".to_string(),
         block_timestamp: 1,
         current_protocol_version: PROTOCOL_VERSION,
         cache: None,
@@ -97,10 +120,165 @@ fn test_view_call_with_args() {
         &"test.contract".parse().unwrap(),
         "sum_with_input",
         &args,
+        None,
+        false,
         &mut logs,
         &MockEpochInfoProvider::default(),
     );
-    assert_eq!(view_call_result.unwrap(), 3u64.to_le_bytes().to_vec());
+    assert_eq!(view_call_result.unwrap().0, 3u64.to_le_bytes().to_vec());
+}
+
+#[test]
+fn test_view_call_max_gas_burnt_view() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let call = |viewer: &TrieViewer, max_gas: Option<Gas>| {
+        let view_state = ViewApplyState {
+            block_height: 1,
+            prev_block_hash: CryptoHash::default(),
+            block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            epoch_height: 0,
+            chain_id: "test-chain".to_string(),
+            block_timestamp: 1,
+            current_protocol_version: PROTOCOL_VERSION,
+            cache: None,
+        };
+        viewer.call_function(
+            tries.new_trie_update(TEST_SHARD_UID, root),
+            view_state,
+            &"test.contract".parse().unwrap(),
+            "fibonacci",
+            &[20],
+            max_gas,
+            false,
+            &mut vec![],
+            &MockEpochInfoProvider::default(),
+        )
+    };
+
+    // The limit of view calls is independent from `max_gas_burnt` of the
+    // runtime config used for transactions.
+    let low_limit_viewer = TrieViewer::new(None, Some(1_000_000_000), None, None);
+    let err = call(&low_limit_viewer, None).unwrap_err().to_string();
+    assert!(err.contains("GasLimitExceeded"), "unexpected error: {}", err);
+    assert!(err.contains("with a limit of 1000000000"), "unexpected error: {}", err);
+    let (result, _) = call(&TrieViewer::new(None, None, None, None), None).unwrap();
+    assert_eq!(result, 6765u64.to_le_bytes().to_vec());
+
+    // A query can raise the limit of the node for itself.
+    assert_eq!(call(&low_limit_viewer, Some(300_000_000_000_000)).unwrap().0, result);
+    let err = call(&TrieViewer::default(), Some(1_000_000_000)).unwrap_err().to_string();
+    assert!(err.contains("with a limit of 1000000000"), "unexpected error: {}", err);
+}
+
+#[test]
+fn test_view_call_max_number_logs_view() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let call = |viewer: &TrieViewer, logs: &mut Vec<String>| {
+        let view_state = ViewApplyState {
+            block_height: 1,
+            prev_block_hash: CryptoHash::default(),
+            block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            epoch_height: 0,
+            chain_id: "test-chain".to_string(),
+            block_timestamp: 1,
+            current_protocol_version: PROTOCOL_VERSION,
+            cache: None,
+        };
+        viewer.call_function(
+            tries.new_trie_update(TEST_SHARD_UID, root),
+            view_state,
+            &"test.contract".parse().unwrap(),
+            "log_something",
+            &[],
+            None,
+            false,
+            logs,
+            &MockEpochInfoProvider::default(),
+        )
+    };
+
+    let err = call(&TrieViewer::new(None, None, Some(0), None), &mut vec![]).unwrap_err();
+    assert!(err.to_string().contains("NumberOfLogsExceeded"), "unexpected error: {}", err);
+    let mut logs = vec![];
+    call(&TrieViewer::new(None, None, None, None), &mut logs).unwrap();
+    assert_eq!(logs, vec!["hello".to_string()]);
+}
+
+#[test]
+fn test_view_call_witness() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let viewer = TrieViewer::default();
+    let contract_id: AccountId = "test.contract".parse().unwrap();
+    let view_state = || ViewApplyState {
+        block_height: 1,
+        prev_block_hash: CryptoHash::default(),
+        block_hash: CryptoHash::default(),
+        epoch_id: EpochId::default(),
+        epoch_height: 0,
+        chain_id: "test-chain".to_string(),
+        block_timestamp: 1,
+        current_protocol_version: PROTOCOL_VERSION,
+        cache: None,
+    };
+    let args = [&1u64.to_le_bytes()[..], &2u64.to_le_bytes()[..]].concat();
+    let verify = |witness: &[Arc<[u8]>], expected_result: &[u8]| {
+        viewer.verify_call_function_witness(
+            root,
+            witness,
+            view_state(),
+            &contract_id,
+            "sum_with_input",
+            &args,
+            None,
+            &MockEpochInfoProvider::default(),
+            expected_result,
+        )
+    };
+
+    let (result, witness) = viewer
+        .call_function(
+            tries.new_trie_update(TEST_SHARD_UID, root),
+            view_state(),
+            &contract_id,
+            "sum_with_input",
+            &args,
+            None,
+            true,
+            &mut vec![],
+            &MockEpochInfoProvider::default(),
+        )
+        .unwrap();
+    assert_eq!(result, 3u64.to_le_bytes().to_vec());
+    assert!(verify(&witness, &result));
+    assert!(!verify(&witness, &4u64.to_le_bytes()));
+    // Any tampered or missing node is detected, including the one holding the code.
+    for i in 0..witness.len() {
+        let mut tampered = witness.clone();
+        let mut node = tampered[i].to_vec();
+        *node.last_mut().unwrap() ^= 1;
+        tampered[i] = Arc::from(node);
+        assert!(!verify(&tampered, &result));
+        let mut missing = witness.clone();
+        missing.remove(i);
+        assert!(!verify(&missing, &result));
+    }
+
+    let (_, witness) = viewer
+        .call_function(
+            tries.new_trie_update(TEST_SHARD_UID, root),
+            view_state(),
+            &contract_id,
+            "sum_with_input",
+            &args,
+            None,
+            false,
+            &mut vec![],
+            &MockEpochInfoProvider::default(),
+        )
+        .unwrap();
+    assert!(witness.is_empty());
 }
 
 #[test]
@@ -131,22 +309,859 @@ fn test_view_state() {
 
     let state_update = tries.new_trie_update(shard_uid, new_root);
     let trie_viewer = TrieViewer::default();
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"").unwrap();
-    assert_eq!(result.proof, Vec::<String>::new());
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, None, None, true)
+        .unwrap();
+    assert!(verify_view_state_result(&new_root, &alice_account(), b"", None, &result).is_ok());
+    // The proof is made of the nodes a second pass over a trie recording its reads visits,
+    // without the values read.
+    for prefix in [&b""[..], b"test", b"test123", b"xyz"] {
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(&alice_account(), prefix);
+        let trie = state_update.trie.recording_reads();
+        let mut iter = trie.iter(&new_root).unwrap();
+        iter.seek(&query).unwrap();
+        let mut value_hashes = HashSet::new();
+        for item in iter {
+            let (key, value) = item.unwrap();
+            value_hashes.insert(sha256(&value));
+            if !key.starts_with(&query) {
+                break;
+            }
+        }
+        let recorded: Vec<String> = trie
+            .recorded_storage()
+            .unwrap()
+            .nodes
+            .0
+            .iter()
+            .filter(|node| !value_hashes.contains(&sha256(node)))
+            .map(to_base64)
+            .collect();
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), prefix, None, None, None, true)
+            .unwrap();
+        assert_eq!(result.proof, recorded);
+    }
     assert_eq!(
-        result.values,
+        without_proof_node_indices(&result.values),
         [
-            StateItem { key: "dGVzdDEyMw==".to_string(), value: "MTIz".to_string(), proof: vec![] },
-            StateItem { key: "dGVzdDMyMQ==".to_string(), value: "MzIx".to_string(), proof: vec![] }
+            StateItem {
+                key: "dGVzdDEyMw==".to_string(),
+                value: "MTIz".to_string(),
+                proof: vec![],
+                elided_value: None,
+                proof_node_indices: vec![],
+            },
+            StateItem {
+                key: "dGVzdDMyMQ==".to_string(),
+                value: "MzIx".to_string(),
+                proof: vec![],
+                elided_value: None,
+                proof_node_indices: vec![],
+            }
         ]
     );
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"xyz").unwrap();
+    // Each item verifies on its own with the nodes it refers to, the path to it from the root.
+    let item_nodes = |item: &StateItem| -> Vec<Vec<u8>> {
+        item.proof_node_indices
+            .iter()
+            .map(|index| from_base64(&result.proof[*index as usize]).unwrap())
+            .collect()
+    };
+    for item in &result.values {
+        let nodes = item_nodes(item);
+        assert_eq!(sha256(&nodes[0]), new_root);
+        assert!(nodes.len() < result.proof.len());
+        assert_eq!(verify_view_state_item(&new_root, &alice_account(), item, &nodes), Ok(()));
+        let mut tampered = item.clone();
+        tampered.value = to_base64(b"124");
+        assert_eq!(
+            verify_view_state_item(&new_root, &alice_account(), &tampered, &nodes),
+            Err(ProofError::ValueMismatch)
+        );
+    }
+    let err = verify_view_state_item(
+        &new_root,
+        &alice_account(),
+        &result.values[1],
+        &item_nodes(&result.values[0]),
+    )
+    .unwrap_err();
+    assert!(err.is_malformed(), "{:?}", err);
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"xyz", None, None, None, true)
+        .unwrap();
     assert_eq!(result.values, []);
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"test123").unwrap();
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test123", None, None, None, true)
+        .unwrap();
+    assert_eq!(
+        without_proof_node_indices(&result.values),
+        [StateItem {
+            key: "dGVzdDEyMw==".to_string(),
+            value: "MTIz".to_string(),
+            proof: vec![],
+            elided_value: None,
+            proof_node_indices: vec![],
+        }]
+    );
+}
+
+fn without_proof_node_indices(items: &[StateItem]) -> Vec<StateItem> {
+    items.iter().map(|item| StateItem { proof_node_indices: vec![], ..item.clone() }).collect()
+}
+
+#[test]
+fn test_view_account_with_proof() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    let trie_viewer = TrieViewer::default();
+
+    let (account, proof) =
+        trie_viewer.view_account_with_proof(&state_update, &alice_account()).unwrap();
+    let account = account.unwrap();
+    assert_eq!(account, trie_viewer.view_account(&state_update, &alice_account()).unwrap());
+    let proof: Vec<Vec<u8>> = proof.iter().map(|node| node.to_vec()).collect();
+    let key = TrieKey::Account { account_id: alice_account() }.to_vec();
+    let value = account.try_to_vec().unwrap();
+    assert!(verify_state_proof(&proof, &root, &key, Some(&value)).is_ok());
+    let mut richer = account.clone();
+    richer.set_amount(account.amount() + 1);
+    assert_eq!(
+        verify_state_proof(&proof, &root, &key, Some(&richer.try_to_vec().unwrap())),
+        Err(ProofError::ValueMismatch)
+    );
+    assert_eq!(verify_state_proof(&proof, &root, &key, None), Err(ProofError::UnexpectedValue));
+    // Tampering with any node on the path makes the proof fail. The value
+    // itself isn't part of the proof.
+    assert!(!proof.contains(&value));
+    for i in 0..proof.len() {
+        let mut tampered = proof.clone();
+        *tampered[i].last_mut().unwrap() ^= 1;
+        let err = verify_state_proof(&tampered, &root, &key, Some(&value)).unwrap_err();
+        assert!(err.is_malformed(), "{:?}", err);
+    }
+
+    let missing_account: AccountId = "missing.near".parse().unwrap();
+    let (account, proof) =
+        trie_viewer.view_account_with_proof(&state_update, &missing_account).unwrap();
+    assert_eq!(account, None);
+    let proof: Vec<Vec<u8>> = proof.iter().map(|node| node.to_vec()).collect();
+    let key = TrieKey::Account { account_id: missing_account }.to_vec();
+    assert!(verify_state_proof(&proof, &root, &key, None).is_ok());
+    assert!(verify_state_proof(&proof, &root, &key, Some(&value)).is_err());
+}
+
+#[test]
+fn test_view_access_keys_with_proof() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    let trie_viewer = TrieViewer::default();
+    let access_keys = trie_viewer.view_access_keys(&state_update, &alice_account()).unwrap();
+    assert!(!access_keys.is_empty());
+    let trie_key = |public_key: &PublicKey| {
+        TrieKey::AccessKey { account_id: alice_account(), public_key: public_key.clone() }.to_vec()
+    };
+    let missing_key = PublicKey::from_seed(KeyType::ED25519, "missing");
+
+    for (public_key, access_key) in &access_keys {
+        let (found, proof) = trie_viewer
+            .view_access_key_with_proof(&state_update, &alice_account(), public_key)
+            .unwrap();
+        assert_eq!(found.as_ref(), Some(access_key));
+        let proof: Vec<Vec<u8>> = proof.iter().map(|node| node.to_vec()).collect();
+        let value = access_key.try_to_vec().unwrap();
+        assert!(verify_state_proof(&proof, &root, &trie_key(public_key), Some(&value)).is_ok());
+        assert!(verify_state_proof(&proof, &root, &trie_key(public_key), None).is_err());
+    }
+    let (found, proof) = trie_viewer
+        .view_access_key_with_proof(&state_update, &alice_account(), &missing_key)
+        .unwrap();
+    assert_eq!(found, None);
+    let proof: Vec<Vec<u8>> = proof.iter().map(|node| node.to_vec()).collect();
+    let value = access_keys[0].1.try_to_vec().unwrap();
+    assert!(verify_state_proof(&proof, &root, &trie_key(&missing_key), None).is_ok());
+    assert!(verify_state_proof(&proof, &root, &trie_key(&missing_key), Some(&value)).is_err());
+
+    let (keys, proof) =
+        trie_viewer.view_access_keys_with_proof(&state_update, &alice_account()).unwrap();
+    assert_eq!(keys, access_keys);
+    let proof: Vec<Vec<u8>> = proof.iter().map(|node| node.to_vec()).collect();
+    for (public_key, access_key) in &keys {
+        let value = access_key.try_to_vec().unwrap();
+        assert!(verify_state_proof(&proof, &root, &trie_key(public_key), Some(&value)).is_ok());
+    }
+    // The scan covers all the keys of the account, so it proves the absence of any other key.
+    assert!(verify_state_proof(&proof, &root, &trie_key(&missing_key), None).is_ok());
+    assert!(verify_state_proof(&proof, &root, &trie_key(&missing_key), Some(&value)).is_err());
+}
+
+#[test]
+fn test_view_contract_code_with_proof() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    let trie_viewer = TrieViewer::default();
+    let contract_id: AccountId = "test.contract".parse().unwrap();
+    let account_key = TrieKey::Account { account_id: contract_id.clone() }.to_vec();
+    let code_key = TrieKey::ContractCode { account_id: contract_id.clone() }.to_vec();
+
+    let (account, code, proof) =
+        trie_viewer.view_contract_code_with_proof(&state_update, &contract_id, true).unwrap();
+    let code = code.unwrap().into_code();
+    assert_eq!(
+        code,
+        trie_viewer.view_contract_code(&state_update, &contract_id).unwrap().into_code()
+    );
+    let proof: Vec<Vec<u8>> = proof.iter().map(|node| node.to_vec()).collect();
+    assert!(!proof.contains(&code));
+    assert!(verify_state_proof(&proof, &root, &account_key, Some(&account.try_to_vec().unwrap()))
+        .is_ok());
+    assert!(verify_state_proof(&proof, &root, &code_key, Some(&code)).is_ok());
+    assert_eq!(sha256(&code), account.code_hash());
+    let mut tampered_code = code.clone();
+    tampered_code[0] ^= 1;
+    assert!(verify_state_proof(&proof, &root, &code_key, Some(&tampered_code)).is_err());
+
+    // Without the code only the account record, and so the code hash, is proven.
+    let (hash_only_account, no_code, hash_proof) =
+        trie_viewer.view_contract_code_with_proof(&state_update, &contract_id, false).unwrap();
+    assert_eq!(hash_only_account, account);
+    assert_eq!(no_code, None);
+    let hash_proof: Vec<Vec<u8>> = hash_proof.iter().map(|node| node.to_vec()).collect();
+    assert!(hash_proof.len() <= proof.len());
+    assert!(verify_state_proof(
+        &hash_proof,
+        &root,
+        &account_key,
+        Some(&account.try_to_vec().unwrap())
+    )
+    .is_ok());
+
+    let missing_account: AccountId = "missing.near".parse().unwrap();
+    assert!(matches!(
+        trie_viewer.view_contract_code_with_proof(&state_update, &missing_account, true),
+        Err(errors::ViewContractCodeError::AccountDoesNotExist { .. })
+    ));
+}
+
+#[test]
+fn test_view_state_absence_proof() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    for (account_id, key, value) in [
+        (alice_account(), b"test123".as_slice(), b"123".as_slice()),
+        (alice_account(), b"test321", b"321"),
+        ("alina".parse().unwrap(), b"qqq", b"321"),
+        ("alex".parse().unwrap(), b"qqq", b"321"),
+    ] {
+        state_update.set(TrieKey::ContractData { account_id, key: key.to_vec() }, value.to_vec());
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    let trie_viewer = TrieViewer::default();
+    let raw_prefix = |prefix: &[u8]| {
+        trie_key_parsers::get_raw_prefix_for_contract_data(&alice_account(), prefix)
+    };
+
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", None, None, None, true)
+        .unwrap();
+    assert_eq!(result.values.len(), 2);
+    assert_eq!(result.proof_kind, Some(ViewStateProofKind::Range));
+    assert!(verify_view_state_result(&new_root, &alice_account(), b"test", None, &result).is_ok());
+
+    for prefix in [b"xyz".as_slice(), b"test4", b"test1234", b"tesu"] {
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), prefix, None, None, None, true)
+            .unwrap();
+        assert_eq!(result.values, []);
+        assert_eq!(result.proof_kind, Some(ViewStateProofKind::NonExistence));
+        let proof: Vec<Vec<u8>> =
+            result.proof.iter().map(|node| from_base64(node).unwrap()).collect();
+        assert!(!proof.is_empty());
+        assert!(verify_prefix_absence(&new_root, &proof, &raw_prefix(prefix)));
+        assert_eq!(verify_non_existence(&new_root, &raw_prefix(prefix), &proof), Ok(()));
+
+        // The proof doesn't hold for another root or for a prefix with data.
+        assert!(!verify_prefix_absence(&root, &proof, &raw_prefix(prefix)));
+        assert!(!verify_prefix_absence(&new_root, &proof, &raw_prefix(b"test")));
+        // Tampering with or dropping the root node makes it fail.
+        let root_node = proof.iter().position(|node| sha256(node) == new_root).unwrap();
+        let mut tampered = proof.clone();
+        *tampered[root_node].last_mut().unwrap() ^= 1;
+        assert!(!verify_prefix_absence(&new_root, &tampered, &raw_prefix(prefix)));
+        let mut truncated = proof.clone();
+        truncated.remove(root_node);
+        assert!(!verify_prefix_absence(&new_root, &truncated, &raw_prefix(prefix)));
+        assert!(
+            verify_view_state_result(&new_root, &alice_account(), prefix, None, &result).is_ok()
+        );
+
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), prefix, None, None, None, false)
+            .unwrap();
+        assert_eq!(result.values, []);
+        assert_eq!(result.proof, Vec::<String>::new());
+        assert_eq!(result.proof_kind, None);
+    }
+}
+
+#[test]
+fn test_prove_contract_data() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    for (key, value) in [(b"test123".as_slice(), b"123".as_slice()), (b"test321", b"321")] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            value.to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    let trie_viewer = TrieViewer::default();
+    let (value, proof) =
+        trie_viewer.prove_contract_data(&state_update, &alice_account(), b"test123").unwrap();
+    assert_eq!(value.as_deref(), Some(b"123".as_slice()));
+    // The proof is the path to the key, root first.
+    assert_eq!(sha256(&proof[0]), new_root);
+    let key = contract_data_key(&alice_account(), b"test123");
+    assert_eq!(verify_state_proof(&proof, &new_root, &key, value.as_deref()), Ok(()));
+    assert!(verify_state_proof(&proof, &root, &key, value.as_deref()).is_err());
+    assert_eq!(
+        verify_state_proof(&proof, &new_root, &key, Some(b"321")),
+        Err(ProofError::ValueMismatch)
+    );
+
+    // Absent keys are proven absent, also for accounts which don't exist.
+    for (account_id, data_key) in [
+        (alice_account(), b"test".as_slice()),
+        (alice_account(), b"test1234"),
+        ("nobody".parse().unwrap(), b"test123"),
+    ] {
+        let (value, proof) =
+            trie_viewer.prove_contract_data(&state_update, &account_id, data_key).unwrap();
+        assert_eq!(value, None);
+        let key = contract_data_key(&account_id, data_key);
+        assert_eq!(verify_state_proof(&proof, &new_root, &key, None), Ok(()));
+        assert_eq!(verify_non_existence(&new_root, &key, &proof), Ok(()));
+        assert_eq!(
+            verify_state_proof(&proof, &new_root, &key, Some(b"123")),
+            Err(ProofError::KeyAbsent)
+        );
+    }
+}
+
+#[test]
+fn test_view_state_completeness() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    for (account_id, key, value) in [
+        (alice_account(), b"test123".as_slice(), b"123".as_slice()),
+        (alice_account(), b"test321", b"321"),
+        (alice_account(), b"test4", b"4"),
+        ("alina".parse().unwrap(), b"qqq", b"321"),
+        ("alex".parse().unwrap(), b"qqq", b"321"),
+    ] {
+        state_update.set(TrieKey::ContractData { account_id, key: key.to_vec() }, value.to_vec());
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    let trie_viewer = TrieViewer::default();
+    let verify = |prefix: &[u8], from_key: Option<&[u8]>, result: &ViewStateResult| {
+        verify_view_state_result(&new_root, &alice_account(), prefix, from_key, result)
+    };
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", None, None, None, true)
+        .unwrap();
+    assert_eq!(result.values.len(), 3);
+    assert_eq!(verify(b"test", None, &result), Ok(()));
+    assert!(verify_view_state_result(&root, &alice_account(), b"test", None, &result).is_err());
+
+    // Leaving an item out, adding one or changing a value is caught.
+    let mut incomplete = result.clone();
+    incomplete.values.remove(1);
+    assert_eq!(
+        verify(b"test", None, &incomplete),
+        Err(ProofError::MissingItem(b"test321".to_vec()))
+    );
+    let mut incomplete = result.clone();
+    incomplete.values.pop();
+    assert_eq!(verify(b"test", None, &incomplete), Err(ProofError::MissingItem(b"test4".to_vec())));
+    let mut extended = result.clone();
+    extended.values.insert(
+        1,
+        StateItem {
+            key: to_base64(b"test2"),
+            value: to_base64(b"2"),
+            proof: vec![],
+            elided_value: None,
+            proof_node_indices: vec![],
+        },
+    );
+    assert_eq!(
+        verify(b"test", None, &extended),
+        Err(ProofError::UnexpectedItem(b"test2".to_vec()))
+    );
+    let mut tampered = result.clone();
+    tampered.values[0].value = to_base64(b"124");
+    assert_eq!(verify(b"test", None, &tampered), Err(ProofError::ValueMismatch));
+    // The nodes of a narrower range don't cover the items outside of it.
+    let mut narrowed = result.clone();
+    narrowed.proof = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test4", None, None, None, true)
+        .unwrap()
+        .proof;
+    let err = verify(b"test", None, &narrowed);
+    assert!(matches!(err, Err(ProofError::MissingNode(_))), "{:?}", err);
+
+    // A truncated page only covers the range up to its last item, after `from_key`.
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", None, Some(1), None, true)
+        .unwrap();
+    assert_eq!(page.next_key, Some(to_base64(b"test123")));
+    assert_eq!(verify(b"test", None, &page), Ok(()));
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", Some(b"test123"), Some(1), None, true)
+        .unwrap();
+    assert_eq!(page.values, result.values[1..2]);
+    assert_eq!(verify(b"test", Some(b"test123"), &page), Ok(()));
+    assert_eq!(verify(b"test", None, &page), Err(ProofError::MissingItem(b"test123".to_vec())));
+    let mut skipped = page.clone();
+    skipped.next_key = None;
+    assert_eq!(
+        verify(b"test", Some(b"test123"), &skipped),
+        Err(ProofError::MissingItem(b"test4".to_vec()))
+    );
+
+    // Elided values are checked against their hash and length.
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", None, None, Some(1), true)
+        .unwrap();
+    assert!(result.values[0].elided_value.is_some());
+    assert!(!result.proof.contains(&to_base64(b"123")));
+    assert_eq!(verify(b"test", None, &result), Ok(()));
+}
+
+#[test]
+fn test_iter_state() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    let mut expected = BTreeMap::new();
+    for i in 0..5_000u32 {
+        let key = format!("key{}", i).into_bytes();
+        let value = i.to_le_bytes().to_vec();
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.clone() },
+            value.clone(),
+        );
+        expected.insert(key, value);
+    }
+    state_update.set(
+        TrieKey::ContractData { account_id: "alina".parse().unwrap(), key: b"key".to_vec() },
+        b"321".to_vec(),
+    );
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    let trie_viewer = TrieViewer::default();
+    let expected_items = |prefix: &[u8], from_key: Option<&[u8]>| {
+        expected
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .filter(|(key, _)| from_key.map_or(true, |from_key| key.as_slice() > from_key))
+            .map(|(key, value)| StateItem {
+                key: to_base64(key),
+                value: to_base64(value),
+                proof: vec![],
+                elided_value: None,
+                proof_node_indices: vec![],
+            })
+            .collect::<Vec<_>>()
+    };
+    let items = trie_viewer
+        .iter_state(&state_update, &alice_account(), b"")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(items.len(), 5_000);
+    assert_eq!(items, expected_items(b"", None));
+    let full = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, None, None, false)
+        .unwrap();
+    assert_eq!(items, full.values);
+
+    let items = trie_viewer
+        .iter_state(&state_update, &alice_account(), b"key49")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(items.len(), 111);
+    assert_eq!(items, expected_items(b"key49", None));
+    for (prefix, from_key) in
+        [(b"".as_slice(), b"key2500".as_slice()), (b"key49", b"key4950"), (b"key49", b"key")]
+    {
+        let items = trie_viewer
+            .iter_state_after(&state_update, &alice_account(), prefix, Some(from_key))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(items, expected_items(prefix, Some(from_key)));
+    }
+
+    // Changes not committed to the trie yet are part of the state.
+    let mut state_update = tries.new_trie_update(shard_uid, new_root);
+    state_update.set(
+        TrieKey::ContractData { account_id: alice_account(), key: b"key49x".to_vec() },
+        b"x".to_vec(),
+    );
+    state_update
+        .remove(TrieKey::ContractData { account_id: alice_account(), key: b"key490".to_vec() });
+    let keys = trie_viewer
+        .iter_state(&state_update, &alice_account(), b"key49")
+        .unwrap()
+        .map(|item| from_base64(&item.unwrap().key).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(keys.len(), 111);
+    assert!(!keys.contains(&b"key490".to_vec()));
+    assert_eq!(keys.last().unwrap(), b"key49x");
+
+    let result = trie_viewer.iter_state(&state_update, &"missing.near".parse().unwrap(), b"");
+    assert!(matches!(result, Err(errors::ViewStateError::AccountDoesNotExist { .. })));
+}
+
+#[test]
+fn test_view_state_paginated() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    for key in [b"test1".as_slice(), b"test2", b"test3", b"test4", b"test5"] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            b"123".to_vec(),
+        );
+    }
+    state_update.set(
+        TrieKey::ContractData { account_id: "alina".parse().unwrap(), key: b"test6".to_vec() },
+        b"321".to_vec(),
+    );
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    let trie_viewer = TrieViewer::default();
+    let full = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, None, None, true)
+        .unwrap();
+    assert_eq!(full.next_key, None);
+
+    let mut values = vec![];
+    let mut pages = 0;
+    let mut from_key = None;
+    loop {
+        let page = trie_viewer
+            .view_state(
+                &state_update,
+                &alice_account(),
+                b"",
+                from_key.as_deref(),
+                Some(2),
+                None,
+                true,
+            )
+            .unwrap();
+        assert!(page.values.len() <= 2);
+        values.extend(page.values);
+        pages += 1;
+        match page.next_key {
+            Some(next_key) => from_key = Some(from_base64(&next_key).unwrap()),
+            None => break,
+        }
+    }
+    assert_eq!(pages, 3);
+    assert_eq!(values, full.values);
+
+    // Iteration resumes strictly after `from_key` and stays within the prefix.
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", Some(b"test45"), Some(10), None, true)
+        .unwrap();
+    assert_eq!(page.values, full.values[4..]);
+    assert_eq!(page.next_key, None);
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test3", Some(b"test"), None, None, true)
+        .unwrap();
+    assert_eq!(page.values, full.values[2..3]);
+}
+
+#[test]
+fn test_view_state_paginated_size_limit() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    set_account(
+        &mut state_update,
+        alice_account(),
+        &Account::new(0, 0, CryptoHash::default(), 50_001),
+    );
+    for key in [b"test1".as_slice(), b"test2", b"test3"] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            b"123".to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    // Each item takes 8 bytes, so a page of at most 20 bytes holds two of them.
+    let trie_viewer = TrieViewer::new(Some(20), None, None, None);
+    let result =
+        trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, None, true);
+    assert!(matches!(result, Err(errors::ViewStateError::AccountStateTooLarge { .. })));
+
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, Some(10), None, true)
+        .unwrap();
+    assert_eq!(page.values.len(), 2);
+    assert_eq!(page.next_key, Some(to_base64(b"test2")));
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", Some(b"test2"), Some(10), None, true)
+        .unwrap();
+    assert_eq!(page.values.len(), 1);
+    assert_eq!(page.next_key, None);
+
+    // A single item larger than the limit is still returned.
+    let trie_viewer = TrieViewer::new(Some(1), None, None, None);
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, Some(10), None, true)
+        .unwrap();
+    assert_eq!(page.values.len(), 1);
+    assert_eq!(page.next_key, Some(to_base64(b"test1")));
+}
+
+#[test]
+fn test_view_state_elided_values() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    let big_value = vec![7u8; 1000];
+    for (key, value) in [(b"big".as_slice(), big_value.as_slice()), (b"small", b"123")] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            value.to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+    let trie_viewer = TrieViewer::default();
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, None, Some(100), true)
+        .unwrap();
     assert_eq!(
-        result.values,
-        [StateItem { key: "dGVzdDEyMw==".to_string(), value: "MTIz".to_string(), proof: vec![] }]
+        without_proof_node_indices(&result.values),
+        [
+            StateItem {
+                key: to_base64(b"big"),
+                value: String::new(),
+                proof: vec![],
+                elided_value: Some(ElidedValueView { hash: sha256(&big_value), length: 1000 }),
+                proof_node_indices: vec![],
+            },
+            StateItem {
+                key: to_base64(b"small"),
+                value: to_base64(b"123"),
+                proof: vec![],
+                elided_value: None,
+                proof_node_indices: vec![],
+            },
+        ]
+    );
+
+    // Both forms of items verify against the root with the nodes on the path to their key.
+    let proof_of = |key: &[u8]| {
+        let trie = state_update.trie.recording_reads();
+        trie.get_ref(&new_root, key).unwrap().unwrap();
+        trie.recorded_storage().unwrap().nodes.0
+    };
+    for item in &result.values {
+        let key = contract_data_key(&alice_account(), &from_base64(&item.key).unwrap());
+        let proof = proof_of(&key);
+        // The nodes the item refers to are the ones on the path to its key.
+        let item_nodes: Vec<Vec<u8>> = item
+            .proof_node_indices
+            .iter()
+            .map(|index| from_base64(&result.proof[*index as usize]).unwrap())
+            .collect();
+        assert_eq!(item_nodes.iter().collect::<HashSet<_>>(), proof.iter().collect::<HashSet<_>>());
+        assert_eq!(verify_view_state_item(&new_root, &alice_account(), item, &item_nodes), Ok(()));
+        match &item.elided_value {
+            Some(elided) => {
+                assert!(verify_state_proof_value_hash(
+                    &new_root,
+                    &proof,
+                    &key,
+                    elided.length,
+                    &elided.hash
+                ));
+                assert!(!verify_state_proof_value_hash(
+                    &new_root,
+                    &proof,
+                    &key,
+                    elided.length,
+                    &sha256(b"123")
+                ));
+                assert!(!verify_state_proof_value_hash(
+                    &new_root,
+                    &proof,
+                    &key,
+                    elided.length + 1,
+                    &elided.hash
+                ));
+            }
+            None => {
+                let value = from_base64(&item.value).unwrap();
+                assert!(verify_state_proof(&proof, &new_root, &key, Some(&value)).is_ok());
+            }
+        }
+    }
+
+    // Without `max_value_size` all values are inline.
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, None, None, true)
+        .unwrap();
+    assert_eq!(result.values[0].value, to_base64(&big_value));
+    assert!(result.values.iter().all(|item| item.elided_value.is_none()));
+}
+
+/// Verifies a proof recorded while reading `key` from `alice_account()`'s
+/// contract data with the `verify_near_state_proof` host function.
+fn verify_state_proof_in_vm(root: &CryptoHash, proof: &[Vec<u8>], key: &[u8], value: &[u8]) -> u8 {
+    let context = VMContext {
+        current_account_id: alice_account(),
+        signer_account_id: alice_account(),
+        signer_account_pk: vec![],
+        predecessor_account_id: alice_account(),
+        input: vec![],
+        block_index: 1,
+        block_timestamp: 1,
+        epoch_height: 0,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId::default(),
+        account_balance: 0,
+        account_locked_balance: 0,
+        storage_usage: 0,
+        attached_deposit: 0,
+        prepaid_gas: 10u64.pow(14),
+        gas_price: 100_000_000,
+        random_seed: vec![],
+        view_config: None,
+        output_data_receivers: vec![],
+        input_data_ids: vec![],
+    };
+    let mut ext = MockedExternal::default();
+    let config = VMConfig::test();
+    let fees_config = RuntimeFeesConfig::test();
+    let mut memory = MockedMemory::default();
+    let mut logic = VMLogic::new_with_protocol_version(
+        &mut ext,
+        context,
+        &config,
+        &fees_config,
+        &[],
+        &mut memory,
+        PROTOCOL_VERSION,
     );
+
+    let proof = proof.try_to_vec().unwrap();
+    let account_id = alice_account();
+    logic
+        .verify_near_state_proof(
+            root.as_ref().as_ptr() as _,
+            proof.len() as _,
+            proof.as_ptr() as _,
+            account_id.len() as _,
+            account_id.as_ptr() as _,
+            key.len() as _,
+            key.as_ptr() as _,
+            value.len() as _,
+            value.as_ptr() as _,
+            0,
+        )
+        .unwrap();
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _).unwrap();
+    res[0]
+}
+
+#[test]
+fn test_view_state_proof_verifies_in_vm() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    for (account_id, key, value) in [
+        (alice_account(), b"test123".as_slice(), b"123".as_slice()),
+        (alice_account(), b"test321", b"321"),
+        ("alina".parse().unwrap(), b"qqq", b"321"),
+        ("alex".parse().unwrap(), b"qqq", b"321"),
+    ] {
+        state_update.set(TrieKey::ContractData { account_id, key: key.to_vec() }, value.to_vec());
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let record_proof = |key: &[u8]| {
+        let trie = tries.get_trie_for_shard(shard_uid).recording_reads();
+        let trie_key = TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() };
+        let value = trie.get(&new_root, &trie_key.to_vec()).unwrap();
+        (value, trie.recorded_storage().unwrap().nodes.0)
+    };
+
+    for key in [b"test123".as_slice(), b"test321"] {
+        let (value, proof) = record_proof(key);
+        let value = value.unwrap();
+        assert_eq!(verify_state_proof_in_vm(&new_root, &proof, key, &value), 1);
+        assert_eq!(verify_state_proof_in_vm(&new_root, &proof, key, b"xyz"), 0);
+        assert_eq!(verify_state_proof_in_vm(&new_root, &proof, key, &[]), 0);
+        assert_eq!(verify_state_proof_in_vm(&root, &proof, key, &value), 0);
+    }
+
+    for key in [b"test".as_slice(), b"test1234", b"xyz", b"qqq"] {
+        let (value, proof) = record_proof(key);
+        assert_eq!(value, None);
+        assert_eq!(verify_state_proof_in_vm(&new_root, &proof, key, &[]), 1);
+        assert_eq!(verify_state_proof_in_vm(&new_root, &proof, key, b"123"), 0);
+        // A proof of absence for one key doesn't prove absence of a key
+        // stored elsewhere in the trie.
+        assert_eq!(verify_state_proof_in_vm(&new_root, &proof, b"test123", &[]), 0);
+    }
 }
 
 #[test]
@@ -158,9 +1173,98 @@ fn test_view_state_too_large() {
         alice_account(),
         &Account::new(0, 0, CryptoHash::default(), 50_001),
     );
-    let trie_viewer = TrieViewer::new(Some(50_000), None);
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"");
-    assert!(matches!(result, Err(errors::ViewStateError::AccountStateTooLarge { .. })));
+    // Each item takes 20_005 bytes, so two of them fit in the limit.
+    for key in [b"test1".as_slice(), b"test2", b"test3"] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            vec![0; 20_000],
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, TEST_SHARD_UID);
+    db_changes.commit().unwrap();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, new_root);
+
+    let trie_viewer = TrieViewer::new(Some(50_000), None, None, None);
+    let result =
+        trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, None, true);
+    assert!(matches!(
+        result,
+        Err(errors::ViewStateError::AccountStateTooLarge {
+            state_size: 50_001,
+            state_size_limit: 50_000,
+            ..
+        })
+    ));
+
+    // With partial results, the first page is returned instead.
+    let trie_viewer = trie_viewer.with_partial_results(true);
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", None, None, None, true)
+        .unwrap();
+    assert_eq!(page.values.len(), 2);
+    assert!(page.truncated);
+    assert_eq!(page.account_state_size, Some(50_001));
+    assert_eq!(page.next_key, Some(to_base64(b"test2")));
+    assert!(verify_view_state_result(&new_root, &alice_account(), b"", None, &page).is_ok());
+
+    // The next key resumes the state with the pagination API.
+    let from_key = from_base64(page.next_key.as_ref().unwrap()).unwrap();
+    let page = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", Some(&from_key), None, None, true)
+        .unwrap();
+    assert_eq!(page.values.len(), 1);
+    assert_eq!(page.values[0].key, to_base64(b"test3"));
+    assert!(!page.truncated);
+    assert_eq!(page.account_state_size, None);
+    assert_eq!(page.next_key, None);
+}
+
+#[test]
+fn test_view_state_requested_state_size_limit() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let mut state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    set_account(
+        &mut state_update,
+        alice_account(),
+        &Account::new(0, 0, CryptoHash::default(), 50_001),
+    );
+    let view = |trie_viewer: &TrieViewer, requested: Option<u64>| {
+        trie_viewer.with_requested_state_size_limit(requested).view_state(
+            &state_update,
+            &alice_account(),
+            b"",
+            None,
+            None,
+            None,
+            false,
+        )
+    };
+    let effective_limit = |result: Result<ViewStateResult, errors::ViewStateError>| match result {
+        Err(errors::ViewStateError::AccountStateTooLarge {
+            state_size, state_size_limit, ..
+        }) => {
+            assert_eq!(state_size, 50_001);
+            Some(state_size_limit)
+        }
+        Ok(_) => None,
+        Err(err) => panic!("unexpected error: {}", err),
+    };
+
+    let mut trie_viewer = TrieViewer::new(Some(50_000), None, None, None);
+    // Without overrides, the limit can be lowered but raises are denied.
+    assert_eq!(effective_limit(view(&trie_viewer, None)), Some(50_000));
+    assert_eq!(effective_limit(view(&trie_viewer, Some(100))), Some(100));
+    assert_eq!(effective_limit(view(&trie_viewer, Some(60_000))), Some(50_000));
+
+    // With overrides, raises are allowed up to the ceiling.
+    trie_viewer.allow_state_size_override(50_500);
+    assert_eq!(effective_limit(view(&trie_viewer, Some(100))), Some(100));
+    assert_eq!(effective_limit(view(&trie_viewer, Some(60_000))), Some(50_500));
+    trie_viewer.allow_state_size_override(1_000_000);
+    assert_eq!(effective_limit(view(&trie_viewer, Some(60_000))), None);
+    assert_eq!(effective_limit(view(&trie_viewer, None)), Some(50_000));
 }
 
 #[test]
@@ -174,8 +1278,9 @@ fn test_view_state_with_large_contract() {
         &Account::new(0, 0, sha256(&contract_code), 50_001),
     );
     state_update.set(TrieKey::ContractCode { account_id: alice_account() }, contract_code);
-    let trie_viewer = TrieViewer::new(Some(50_000), None);
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"");
+    let trie_viewer = TrieViewer::new(Some(50_000), None, None, None);
+    let result =
+        trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, None, true);
     assert!(result.is_ok());
 }
 
@@ -188,6 +1293,7 @@ fn test_log_when_panic() {
         block_hash: CryptoHash::default(),
         epoch_id: EpochId::default(),
         epoch_height: 0,
+        chain_id: "test-chain".to_string(),
         block_timestamp: 1,
         current_protocol_version: PROTOCOL_VERSION,
         cache: None,
@@ -200,6 +1306,8 @@ fn test_log_when_panic() {
             &"test.contract".parse().unwrap(),
             "panic_after_logging",
             &[],
+            None,
+            false,
             &mut logs,
             &MockEpochInfoProvider::default(),
         )
@@ -207,3 +1315,106 @@ fn test_log_when_panic() {
 
     assert_eq!(logs, vec!["hello".to_string()]);
 }
+
+/// Asserts that `view_state` reads the same pages of the state of alice from the flat index of
+/// contract data as from the trie.
+fn assert_view_state_flat_matches_trie(
+    tries: &ShardTries,
+    flat_contract_data: &FlatContractData,
+    root: CryptoHash,
+) {
+    let state_update = tries.new_trie_update_view(TEST_SHARD_UID, root);
+    let trie_viewer = TrieViewer::new(Some(20), None, None, None);
+    let queries: [(&[u8], Option<&[u8]>, Option<u64>, Option<u64>); 6] = [
+        (b"", None, Some(100), None),
+        (b"test", None, Some(2), None),
+        (b"test", Some(b"test2"), Some(2), None),
+        (b"test1", None, Some(100), None),
+        (b"", None, Some(100), Some(3)),
+        (b"missing", None, Some(100), None),
+    ];
+    for (prefix, from_key, limit, max_value_size) in queries {
+        let trie_result = trie_viewer
+            .view_state(
+                &state_update,
+                &alice_account(),
+                prefix,
+                from_key,
+                limit,
+                max_value_size,
+                false,
+            )
+            .unwrap();
+        let flat_result = trie_viewer
+            .view_state_flat(
+                &state_update,
+                flat_contract_data,
+                &alice_account(),
+                prefix,
+                from_key,
+                limit,
+                max_value_size,
+            )
+            .unwrap();
+        assert_eq!(flat_result, trie_result);
+    }
+}
+
+#[test]
+fn test_view_state_flat() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    for (key, value) in [
+        (b"test1".as_slice(), b"1".as_slice()),
+        (b"test12", b"12"),
+        (b"test2", b"123"),
+        (b"test3", b"1234"),
+        (b"other", b"12345"),
+    ] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            value.to_vec(),
+        );
+    }
+    state_update.set(
+        TrieKey::ContractData { account_id: "alice.near0".parse().unwrap(), key: b"test".to_vec() },
+        b"321".to_vec(),
+    );
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, root) = tries.apply_all(&trie_changes, shard_uid);
+    db_changes.commit().unwrap();
+
+    let flat_contract_data = FlatContractData::new(tries.get_store(), shard_uid);
+    let block_hash = sha256(b"block");
+    flat_contract_data.build(&tries.get_trie_for_shard(shard_uid), &block_hash, &root).unwrap();
+    assert_view_state_flat_matches_trie(&tries, &flat_contract_data, root);
+
+    // The index follows the changes applied on top of its state.
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    state_update
+        .remove(TrieKey::ContractData { account_id: alice_account(), key: b"test2".to_vec() });
+    for (key, value) in [(b"test3".as_slice(), b"4321".as_slice()), (b"test4", b"5")] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            value.to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let (trie_changes, state_changes) = state_update.finalize().unwrap();
+    let new_root = trie_changes.new_root;
+    let wrapped_trie_changes = WrappedTrieChanges::new(
+        tries.clone(),
+        shard_uid,
+        trie_changes,
+        state_changes,
+        sha256(b"next block"),
+    );
+    let mut store_update = tries.get_store().store_update();
+    wrapped_trie_changes.insertions_into(&mut store_update);
+    wrapped_trie_changes.flat_contract_data_into(&mut store_update).unwrap();
+    store_update.commit().unwrap();
+    assert_eq!(flat_contract_data.get_head().unwrap().unwrap().state_root, new_root);
+    assert_view_state_flat_matches_trie(&tries, &flat_contract_data, new_root);
+}
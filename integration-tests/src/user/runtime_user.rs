@@ -142,6 +142,7 @@ impl RuntimeUser {
             block_hash: Default::default(),
             block_timestamp: 0,
             epoch_height: 0,
+            chain_id: "test-chain".to_string(),
             gas_price: MIN_GAS_PRICE,
             gas_limit: None,
             random_seed: Default::default(),
@@ -233,7 +234,7 @@ impl User for RuntimeUser {
     fn view_state(&self, account_id: &AccountId, prefix: &[u8]) -> Result<ViewStateResult, String> {
         let state_update = self.client.read().expect(POISONED_LOCK_ERR).get_state_update();
         self.trie_viewer
-            .view_state(&state_update, account_id, prefix)
+            .view_state(&state_update, account_id, prefix, None, None, None, true)
             .map_err(|err| err.to_string())
     }
 
@@ -253,6 +254,7 @@ impl User for RuntimeUser {
             block_hash: apply_state.block_hash,
             epoch_id: apply_state.epoch_id,
             epoch_height: apply_state.epoch_height,
+            chain_id: apply_state.chain_id,
             block_timestamp: apply_state.block_timestamp,
             current_protocol_version: PROTOCOL_VERSION,
             cache: apply_state.cache,
@@ -265,10 +267,13 @@ impl User for RuntimeUser {
                 account_id,
                 method_name,
                 args,
+                None,
+                false,
                 &mut result.logs,
                 &self.epoch_info_provider,
             )
-            .map_err(|err| err.to_string())?;
+            .map_err(|err| err.to_string())?
+            .0;
         Ok(result)
     }
 
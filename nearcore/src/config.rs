@@ -25,6 +25,7 @@ use near_network::test_utils::open_port;
 use near_network_primitives::types::NetworkConfig;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::hash::CryptoHash;
+use near_primitives::runtime::parameter_table::ParameterTableOverlay;
 #[cfg(test)]
 use near_primitives::shard_layout::account_id_to_shard_id;
 use near_primitives::shard_layout::ShardLayout;
@@ -199,6 +200,10 @@ fn default_trie_viewer_state_size_limit() -> Option<u64> {
     Some(50_000)
 }
 
+fn default_trie_viewer_state_size_ceiling() -> u64 {
+    5_000_000
+}
+
 fn default_use_checkpoints_for_db_migration() -> bool {
     true
 }
@@ -314,9 +319,25 @@ pub struct Config {
     pub view_client_throttle_period: Duration,
     #[serde(default = "default_trie_viewer_state_size_limit")]
     pub trie_viewer_state_size_limit: Option<u64>,
+    /// Whether view_state queries may raise `trie_viewer_state_size_limit`, up to
+    /// `trie_viewer_state_size_ceiling`. They may always lower it.
+    #[serde(default)]
+    pub allow_state_size_override: bool,
+    #[serde(default = "default_trie_viewer_state_size_ceiling")]
+    pub trie_viewer_state_size_ceiling: u64,
     /// If set, overrides value in genesis configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_gas_burnt_view: Option<Gas>,
+    /// If set, overrides the default log limits of view methods.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_number_logs_view: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_total_log_length_view: Option<u64>,
+    /// Keep a flat index of the contract data at the head to speed up `view_state` queries.
+    /// When it is missing or out of date, the index is brought to the head in the background,
+    /// and it is dropped when the node starts with it disabled.
+    #[serde(default)]
+    pub flat_contract_data_index: bool,
     /// Checkpoints let the user recover from interrupted DB migrations.
     #[serde(default = "default_use_checkpoints_for_db_migration")]
     pub use_db_migration_snapshot: bool,
@@ -328,6 +349,15 @@ pub struct Config {
     pub db_migration_snapshot_path: Option<PathBuf>,
     /// Different parameters to configure/optimize underlying storage.
     pub store: near_store::StoreConfig,
+    /// File overriding the gas costs of this fork's host functions, relative
+    /// to the home directory. The file uses the runtime parameters format and
+    /// has to define every fork cost.
+    ///
+    /// The costs are part of consensus: every node of the network must use the
+    /// same file, or the nodes compute different gas and burnt amounts and
+    /// fork the chain. Hence the file is rejected on mainnet and testnet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fork_parameters_file: Option<String>,
 }
 
 impl Default for Config {
@@ -353,10 +383,16 @@ impl Default for Config {
             view_client_threads: default_view_client_threads(),
             view_client_throttle_period: default_view_client_throttle_period(),
             trie_viewer_state_size_limit: default_trie_viewer_state_size_limit(),
+            allow_state_size_override: false,
+            trie_viewer_state_size_ceiling: default_trie_viewer_state_size_ceiling(),
             max_gas_burnt_view: None,
+            max_number_logs_view: None,
+            max_total_log_length_view: None,
+            flat_contract_data_index: false,
             db_migration_snapshot_path: None,
             use_db_migration_snapshot: true,
             store: near_store::StoreConfig::default(),
+            fork_parameters_file: None,
         }
     }
 }
@@ -515,6 +551,8 @@ pub struct NearConfig {
     pub telemetry_config: TelemetryConfig,
     pub genesis: Genesis,
     pub validator_signer: Option<Arc<dyn ValidatorSigner>>,
+    /// Gas costs loaded from `config.fork_parameters_file`.
+    pub fork_parameters: Option<ParameterTableOverlay>,
 }
 
 impl NearConfig {
@@ -569,7 +607,12 @@ impl NearConfig {
                 epoch_sync_enabled: config.epoch_sync_enabled,
                 view_client_throttle_period: config.view_client_throttle_period,
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
+                allow_state_size_override: config.allow_state_size_override,
+                trie_viewer_state_size_ceiling: config.trie_viewer_state_size_ceiling,
                 max_gas_burnt_view: config.max_gas_burnt_view,
+                max_number_logs_view: config.max_number_logs_view,
+                max_total_log_length_view: config.max_total_log_length_view,
+                flat_contract_data_index: config.flat_contract_data_index,
                 enable_statistics_export: config.store.enable_statistics_export,
             },
             network_config: NetworkConfig::new(
@@ -585,6 +628,7 @@ impl NearConfig {
             rosetta_rpc_config: config.rosetta_rpc,
             genesis,
             validator_signer,
+            fork_parameters: None,
         }
     }
 
@@ -1210,8 +1254,9 @@ pub fn load_config(
         format!("Failed reading node key file from {}", node_key_path.display())
     })?;
 
+    let fork_parameters_file = config.fork_parameters_file.clone();
     let genesis_records_file = config.genesis_records_file.clone();
-    Ok(NearConfig::new(
+    let mut near_config = NearConfig::new(
         config,
         match genesis_records_file {
             Some(genesis_records_file) => Genesis::from_files(
@@ -1223,7 +1268,24 @@ pub fn load_config(
         },
         network_signer.into(),
         validator_signer,
-    ))
+    );
+    if let Some(fork_parameters_file) = fork_parameters_file {
+        let chain_id = &near_config.genesis.config.chain_id;
+        anyhow::ensure!(
+            !matches!(chain_id.as_str(), "mainnet" | "testnet"),
+            "fork_parameters_file can't be used on {chain_id}, \
+             every node of the network has to use the same gas costs"
+        );
+        let path = dir.join(fork_parameters_file);
+        let data = fs::read_to_string(&path).with_context(|| {
+            format!("Failed reading fork parameters file from {}", path.display())
+        })?;
+        let fork_parameters = data
+            .parse::<ParameterTableOverlay>()
+            .with_context(|| format!("Failed parsing fork parameters file {}", path.display()))?;
+        near_config.fork_parameters = Some(fork_parameters);
+    }
+    Ok(near_config)
 }
 
 pub fn load_test_config(seed: &str, port: u16, genesis: Genesis) -> NearConfig {
@@ -1298,6 +1360,82 @@ fn test_init_config_localnet() {
     );
 }
 
+/// Tests that a malformed fork parameters file fails loading the config.
+#[test]
+fn test_load_config_invalid_fork_parameters() {
+    let temp_dir = tempdir().unwrap();
+    init_configs(
+        &temp_dir.path(),
+        Some("localnet"),
+        None,
+        Some("seed1"),
+        1,
+        false,
+        None,
+        false,
+        None,
+        false,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    let config_path = temp_dir.path().join(CONFIG_FILENAME);
+    let mut config = Config::from_file(&config_path).unwrap();
+    config.fork_parameters_file = Some("fork_parameters.txt".to_string());
+    config.write_to_file(&config_path).unwrap();
+
+    let err = load_config(temp_dir.path(), GenesisValidationMode::UnsafeFast).unwrap_err();
+    assert!(format!("{err:#}").contains("Failed reading fork parameters file"), "{err:#}");
+
+    fs::write(temp_dir.path().join("fork_parameters.txt"), "wasm_regular_op_cost: 100").unwrap();
+    let err = load_config(temp_dir.path(), GenesisValidationMode::UnsafeFast).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("`wasm_regular_op_cost` is not a fork parameter"),
+        "{err:#}"
+    );
+}
+
+/// Tests that fork parameters are rejected on mainnet and testnet.
+#[test]
+fn test_load_config_fork_parameters_on_public_chain() {
+    let temp_dir = tempdir().unwrap();
+    init_configs(
+        &temp_dir.path(),
+        Some("localnet"),
+        None,
+        Some("seed1"),
+        1,
+        false,
+        None,
+        false,
+        None,
+        false,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    let config_path = temp_dir.path().join(CONFIG_FILENAME);
+    let mut config = Config::from_file(&config_path).unwrap();
+    config.fork_parameters_file = Some("fork_parameters.txt".to_string());
+    config.write_to_file(&config_path).unwrap();
+
+    let genesis_path = temp_dir.path().join(&config.genesis_file);
+    let mut genesis: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&genesis_path).unwrap()).unwrap();
+    for chain_id in ["mainnet", "testnet"] {
+        genesis["chain_id"] = chain_id.into();
+        fs::write(&genesis_path, serde_json::to_string(&genesis).unwrap()).unwrap();
+        let err = load_config(temp_dir.path(), GenesisValidationMode::UnsafeFast).unwrap_err();
+        assert!(
+            format!("{err:#}")
+                .contains(&format!("fork_parameters_file can't be used on {chain_id}")),
+            "{err:#}"
+        );
+    }
+}
+
 /// Tests that loading a config.json file works and results in values being
 /// correctly parsed and defaults being applied correctly applied.
 #[test]
@@ -1318,9 +1456,19 @@ fn test_config_from_file() {
         // values is probably not worth it but there may be some other defaults
         // we want to ensure that they happen.
         let want_gc = if has_gc {
-            GCConfig { gc_blocks_limit: 42, gc_fork_clean_step: 420, gc_num_epochs_to_keep: 24 }
+            GCConfig {
+                gc_blocks_limit: 42,
+                gc_fork_clean_step: 420,
+                gc_num_epochs_to_keep: 24,
+                ..GCConfig::default()
+            }
         } else {
-            GCConfig { gc_blocks_limit: 2, gc_fork_clean_step: 100, gc_num_epochs_to_keep: 5 }
+            GCConfig {
+                gc_blocks_limit: 2,
+                gc_fork_clean_step: 100,
+                gc_num_epochs_to_keep: 5,
+                ..GCConfig::default()
+            }
         };
         assert_eq!(want_gc, config.gc);
 
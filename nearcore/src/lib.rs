@@ -1,5 +1,5 @@
 pub use crate::config::{init_configs, load_config, load_test_config, NearConfig, NEAR_BASE};
-use crate::migrations::migrate_30_to_31;
+use crate::migrations::{migrate_30_to_31, migrate_31_to_32};
 pub use crate::runtime::NightshadeRuntime;
 pub use crate::shard_tracker::TrackedConfig;
 use actix::{Actor, Addr, Arbiter};
@@ -10,6 +10,7 @@ use near_chain::ChainGenesis;
 use near_client::{start_client, start_view_client, ClientActor, ViewClientActor};
 use near_network::test_utils::NetworkRecipient;
 use near_network::PeerManagerActor;
+use near_primitives::types::AccountId;
 use near_primitives::version::DbVersion;
 #[cfg(feature = "rosetta_rpc")]
 use near_rosetta_rpc::start_rosetta_rpc;
@@ -22,7 +23,7 @@ use near_telemetry::TelemetryActor;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::oneshot;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 
 pub mod append_only_map;
 pub mod config;
@@ -165,6 +166,11 @@ fn apply_store_migrations_if_exists(
         info!(target: "near", "Migrate DB from version 30 to 31");
         migrate_30_to_31(store_opener, &near_config);
     }
+    if db_version <= 31 {
+        // version 31 => 32: add the events emitted by contracts to the execution outcomes
+        info!(target: "near", "Migrate DB from version 31 to 32");
+        migrate_31_to_32(store_opener);
+    }
 
     if cfg!(feature = "nightly") || cfg!(feature = "nightly_protocol") {
         let store = store_opener.open();
@@ -486,3 +492,80 @@ pub fn recompress_storage(home_dir: &Path, opts: RecompressOpts) -> anyhow::Resu
     info!(target: "recompress", dest = %dst_path.display(), "Database recompressed");
     Ok(())
 }
+
+/// Compiles the contracts deployed to `account_ids` as of the chain head and
+/// stores them in the compiled contract cache, so that the first calls to them
+/// after a restart don’t have to wait for compilation.
+///
+/// Contracts which are already in the cache are skipped, so an interrupted
+/// run can simply be started again.
+pub fn precompile_contracts(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    account_ids: &[AccountId],
+) -> anyhow::Result<()> {
+    use near_chain::{ChainStore, ChainStoreAccess, RuntimeAdapter};
+    use near_store::StoreCompiledContractCache;
+    use near_vm_runner::internal::VMKind;
+    use node_runtime::adapter::ViewRuntimeAdapter;
+
+    let store = Store::opener(home_dir, &near_config.config.store).open();
+    let chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    let head = chain_store.head().context("reading chain head")?;
+    let block = chain_store.get_block(&head.last_block_hash)?;
+    let runtime = NightshadeRuntime::from_config(home_dir, store.clone(), near_config);
+    let protocol_version = runtime.get_epoch_protocol_version(&head.epoch_id)?;
+    let vm_config = runtime.get_protocol_config(&head.epoch_id)?.runtime_config.wasm_config;
+    let vm_kind = VMKind::for_protocol_version(protocol_version);
+
+    let mut failed = 0;
+    let mut contracts = Vec::new();
+    for account_id in account_ids {
+        let shard_id = runtime.account_id_to_shard_id(account_id, &head.epoch_id)?;
+        let shard_uid = runtime.shard_id_to_uid(shard_id, &head.epoch_id)?;
+        let state_root = block.chunks()[shard_id as usize].prev_state_root();
+        match runtime.view_contract_code(&shard_uid, state_root, account_id) {
+            Ok(code) => contracts.push((account_id, code)),
+            Err(err) => {
+                error!(target: "precompile", %account_id, %err, "Failed reading contract");
+                failed += 1;
+            }
+        }
+    }
+
+    info!(target: "precompile", num_contracts = contracts.len(), ?vm_kind, "Precompiling contracts");
+    let (account_ids, codes): (Vec<_>, Vec<_>) = contracts.into_iter().unzip();
+    let cache = StoreCompiledContractCache { store };
+    let reports = near_vm_runner::precompile_contracts(codes, vm_kind, &vm_config, &cache);
+    for (account_id, report) in account_ids.into_iter().zip(reports) {
+        let code_hash = report.code_hash;
+        let duration = report.duration;
+        match report.result {
+            Ok(Ok(result)) => {
+                info!(target: "precompile", %account_id, %code_hash, ?duration, ?result, "Done");
+            }
+            // The error is cached as well, calls to the contract fail without compiling it.
+            Ok(Err(err)) => {
+                warn!(
+                    target: "precompile",
+                    %account_id, %code_hash, ?duration, ?err,
+                    "Contract does not compile"
+                );
+            }
+            Err(err) => {
+                error!(
+                    target: "precompile",
+                    %account_id, %code_hash, ?err,
+                    "Failed caching contract"
+                );
+                failed += 1;
+            }
+        }
+    }
+    anyhow::ensure!(failed == 0, "failed precompiling {} contracts", failed);
+    Ok(())
+}
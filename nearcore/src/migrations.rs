@@ -1,7 +1,14 @@
+use borsh::BorshDeserialize;
 use near_chain::{ChainStore, ChainStoreAccess};
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::MerklePath;
 use near_primitives::receipt::ReceiptResult;
 use near_primitives::runtime::migration_data::MigrationData;
-use near_primitives::types::Gas;
+use near_primitives::transaction::{
+    ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionOutcomeWithIdAndProof,
+    ExecutionStatus, LogEntry,
+};
+use near_primitives::types::{AccountId, Balance, Gas};
 use near_primitives::utils::index_to_bytes;
 use near_store::migrations::{set_store_version, BatchedStoreUpdate};
 use near_store::DBCol;
@@ -37,6 +44,69 @@ pub fn migrate_30_to_31(store_opener: &near_store::StoreOpener, near_config: &cr
     set_store_version(&store, 31);
 }
 
+/// Add the events emitted by contracts to the stored execution outcomes, which have none for the
+/// outcomes stored before.
+pub fn migrate_31_to_32(store_opener: &near_store::StoreOpener) {
+    #[derive(BorshDeserialize)]
+    struct OldExecutionOutcome {
+        logs: Vec<LogEntry>,
+        receipt_ids: Vec<CryptoHash>,
+        gas_burnt: Gas,
+        tokens_burnt: Balance,
+        executor_id: AccountId,
+        status: ExecutionStatus,
+        metadata: ExecutionMetadata,
+    }
+    #[derive(BorshDeserialize)]
+    struct OldExecutionOutcomeWithId {
+        id: CryptoHash,
+        outcome: OldExecutionOutcome,
+    }
+    #[derive(BorshDeserialize)]
+    struct OldExecutionOutcomeWithIdAndProof {
+        proof: MerklePath,
+        block_hash: CryptoHash,
+        outcome_with_id: OldExecutionOutcomeWithId,
+    }
+
+    let store = store_opener.open();
+    let col = DBCol::TransactionResult;
+    let keys: Vec<_> = store.iter(col).map(Result::unwrap).map(|(key, _)| key).collect();
+    let mut store_update = BatchedStoreUpdate::new(&store, 10_000_000);
+    for key in keys {
+        let outcomes: Vec<OldExecutionOutcomeWithIdAndProof> =
+            store.get_ser(col, key.as_ref()).unwrap().unwrap();
+        let outcomes: Vec<_> = outcomes
+            .into_iter()
+            .map(|outcome| {
+                let OldExecutionOutcomeWithIdAndProof { proof, block_hash, outcome_with_id } =
+                    outcome;
+                let OldExecutionOutcomeWithId { id, outcome } = outcome_with_id;
+                ExecutionOutcomeWithIdAndProof {
+                    proof,
+                    block_hash,
+                    outcome_with_id: ExecutionOutcomeWithId {
+                        id,
+                        outcome: ExecutionOutcome {
+                            logs: outcome.logs,
+                            receipt_ids: outcome.receipt_ids,
+                            gas_burnt: outcome.gas_burnt,
+                            tokens_burnt: outcome.tokens_burnt,
+                            executor_id: outcome.executor_id,
+                            status: outcome.status,
+                            metadata: outcome.metadata,
+                            events: vec![],
+                        },
+                    },
+                }
+            })
+            .collect();
+        store_update.set_ser(col, key.as_ref(), &outcomes).expect("BorshSerialize should not fail");
+    }
+    store_update.finish().expect("Failed to migrate");
+    set_store_version(&store, 32);
+}
+
 /// In test runs reads and writes here used 442 TGas, but in test on live net migration take
 /// between 4 and 4.5s. We do not want to process any receipts in this block
 const GAS_USED_FOR_STORAGE_USAGE_DELTA_MIGRATION: Gas = 1_000_000_000_000_000;
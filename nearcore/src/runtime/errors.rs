@@ -79,7 +79,15 @@ impl QueryError {
             } => Self::UnknownAccount { requested_account_id, block_height, block_hash },
             node_runtime::state_viewer::errors::ViewStateError::AccountStateTooLarge {
                 requested_account_id,
-            } => Self::TooLargeContractState { requested_account_id, block_height, block_hash },
+                state_size,
+                state_size_limit,
+            } => Self::TooLargeContractState {
+                requested_account_id,
+                state_size,
+                state_size_limit,
+                block_height,
+                block_hash,
+            },
         }
     }
 
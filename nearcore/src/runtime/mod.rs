@@ -28,7 +28,9 @@ use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::receipt::Receipt;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
+use near_primitives::runtime::parameter_table::ParameterTableOverlay;
 use near_primitives::sandbox_state_patch::SandboxStatePatch;
+use near_primitives::serialize::to_base64;
 use near_primitives::shard_layout::{
     account_id_to_shard_id, account_id_to_shard_uid, ShardLayout, ShardUId,
 };
@@ -37,6 +39,7 @@ use near_primitives::state_part::PartId;
 use near_primitives::state_record::{state_record_to_account_id, StateRecord};
 use near_primitives::syncing::{get_num_state_parts, STATE_PART_MEMORY_LIMIT};
 use near_primitives::transaction::SignedTransaction;
+use near_primitives::trie_key::{col, TrieKey};
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
     AccountId, ApprovalStake, Balance, BlockHeight, CompiledContractCache, EpochHeight, EpochId,
@@ -45,14 +48,16 @@ use near_primitives::types::{
 };
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::{
-    AccessKeyInfoView, CallResult, EpochValidatorInfo, QueryRequest, QueryResponse,
+    AccessKeyInfoView, AccessKeyListWithProof, AccessKeyWithProofView, AccountWithProofView,
+    CallResult, ContractCodeWithProofView, EpochValidatorInfo, QueryRequest, QueryResponse,
     QueryResponseKind, ViewApplyState, ViewStateResult,
 };
+use near_store::flat_contract_data::{FlatContractData, FlatContractDataHead};
 use near_store::split_state::get_delayed_receipts;
 use near_store::{
     get_genesis_hash, get_genesis_state_roots, set_genesis_hash, set_genesis_state_roots,
-    ApplyStatePartResult, DBCol, PartialStorage, ShardTries, Store, StoreCompiledContractCache,
-    StoreUpdate, Trie, TrieCacheFactory, WrappedTrieChanges,
+    verify_state_part, ApplyStatePartResult, DBCol, KeyForStateChanges, PartialStorage, ShardTries,
+    Store, StoreCompiledContractCache, StoreUpdate, Trie, TrieCacheFactory, WrappedTrieChanges,
 };
 use near_vm_runner::precompile_contract;
 use node_runtime::adapter::ViewRuntimeAdapter;
@@ -67,7 +72,7 @@ use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
@@ -106,6 +111,23 @@ impl EpochInfoProvider for SafeEpochManager {
         Ok(epoch_info.get_validator_id(account_id).map(|id| epoch_info.validator_stake(*id)))
     }
 
+    fn validator_public_key(
+        &self,
+        epoch_id: &EpochId,
+        last_block_hash: &CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<Option<PublicKey>, EpochError> {
+        let epoch_manager = self.read();
+        let last_block_info = epoch_manager.get_block_info(last_block_hash)?;
+        if last_block_info.slashed().contains_key(account_id) {
+            return Ok(None);
+        }
+        let epoch_info = epoch_manager.get_epoch_info(epoch_id)?;
+        Ok(epoch_info
+            .get_validator_id(account_id)
+            .map(|id| epoch_info.get_validator(*id).public_key().clone()))
+    }
+
     fn validator_total_stake(
         &self,
         epoch_id: &EpochId,
@@ -125,6 +147,27 @@ impl EpochInfoProvider for SafeEpochManager {
         let epoch_manager = self.read();
         epoch_manager.minimum_stake(prev_block_hash)
     }
+
+    fn block_hash_by_height(
+        &self,
+        last_block_hash: &CryptoHash,
+        height: BlockHeight,
+    ) -> Result<Option<CryptoHash>, EpochError> {
+        let epoch_manager = self.read();
+        let mut block_hash = *last_block_hash;
+        loop {
+            let block_info = epoch_manager.get_block_info(&block_hash)?;
+            match block_info.height().cmp(&height) {
+                Ordering::Equal => return Ok(Some(block_hash)),
+                Ordering::Less => return Ok(None),
+                // Genesis has no previous block to walk back to.
+                Ordering::Greater if block_info.prev_hash() == &CryptoHash::default() => {
+                    return Ok(None)
+                }
+                Ordering::Greater => block_hash = *block_info.prev_hash(),
+            }
+        }
+    }
 }
 
 /// Defines Nightshade state transition and validator rotation.
@@ -142,21 +185,37 @@ pub struct NightshadeRuntime {
     genesis_state_roots: Vec<StateRoot>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    /// Whether to keep the flat index of contract data, see `update_flat_contract_data`.
+    flat_contract_data_index: bool,
+    /// Shards whose flat index of contract data is being updated in the background.
+    flat_contract_data_updates: Arc<Mutex<HashSet<ShardUId>>>,
 }
 
 impl NightshadeRuntime {
     pub fn from_config(home_dir: &Path, store: Store, config: &NearConfig) -> Self {
-        Self::new(
+        let mut runtime = Self::new(
             home_dir,
             store,
             &config.genesis,
             TrackedConfig::from_config(&config.client_config),
             config.client_config.trie_viewer_state_size_limit,
             config.client_config.max_gas_burnt_view,
-            None,
+            config.client_config.max_number_logs_view,
+            config.client_config.max_total_log_length_view,
+            Some(NightshadeRuntime::create_runtime_config_store(
+                &config.genesis.config.chain_id,
+                config.fork_parameters.as_ref(),
+            )),
             config.config.gc.gc_num_epochs_to_keep(),
             config.config.store.trie_cache_capacities.clone(),
-        )
+            config.client_config.flat_contract_data_index,
+        );
+        if config.client_config.allow_state_size_override {
+            runtime
+                .trie_viewer
+                .allow_state_size_override(config.client_config.trie_viewer_state_size_ceiling);
+        }
+        runtime
     }
 
     fn new(
@@ -166,17 +225,25 @@ impl NightshadeRuntime {
         tracked_config: TrackedConfig,
         trie_viewer_state_size_limit: Option<u64>,
         max_gas_burnt_view: Option<Gas>,
+        max_number_logs_view: Option<u64>,
+        max_total_log_length_view: Option<u64>,
         runtime_config_store: Option<RuntimeConfigStore>,
         gc_num_epochs_to_keep: u64,
         trie_cache_capacities: Vec<(ShardUId, usize)>,
+        flat_contract_data_index: bool,
     ) -> Self {
         let runtime_config_store = match runtime_config_store {
             Some(store) => store,
-            None => NightshadeRuntime::create_runtime_config_store(&genesis.config.chain_id),
+            None => NightshadeRuntime::create_runtime_config_store(&genesis.config.chain_id, None),
         };
 
         let runtime = Runtime::new();
-        let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, max_gas_burnt_view);
+        let trie_viewer = TrieViewer::new(
+            trie_viewer_state_size_limit,
+            max_gas_burnt_view,
+            max_number_logs_view,
+            max_total_log_length_view,
+        );
         let genesis_config = genesis.config.clone();
         assert_eq!(
             genesis_config.shard_layout.num_shards(),
@@ -193,6 +260,10 @@ impl NightshadeRuntime {
             genesis.config.num_block_producer_seats_per_shard.len() as NumShards,
         );
         let tries = ShardTries::new(store.clone(), trie_cache_factory);
+        if !flat_contract_data_index {
+            // An index left from a run with it enabled would go stale.
+            FlatContractData::delete_all(&store).expect("Failed to delete flat contract data");
+        }
         let epoch_manager = Arc::new(RwLock::new(
             EpochManager::new_from_genesis_config(store.clone(), &genesis_config)
                 .expect("Failed to start Epoch Manager"),
@@ -210,6 +281,8 @@ impl NightshadeRuntime {
             genesis_state_roots: state_roots,
             migration_data: Arc::new(load_migration_data(&genesis.config.chain_id)),
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            flat_contract_data_index,
+            flat_contract_data_updates: Default::default(),
         }
     }
 
@@ -227,9 +300,12 @@ impl NightshadeRuntime {
             tracked_config,
             None,
             None,
+            None,
+            None,
             Some(runtime_config_store),
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             Default::default(),
+            false,
         )
     }
 
@@ -254,13 +330,20 @@ impl NightshadeRuntime {
     /// first protocol versions.
     /// For testnet, runtime config for genesis block was (incorrectly) different, that's why we
     /// need to override it specifically to preserve compatibility.
-    fn create_runtime_config_store(chain_id: &str) -> RuntimeConfigStore {
+    /// `fork_parameters` override the costs of this fork's host functions.
+    fn create_runtime_config_store(
+        chain_id: &str,
+        fork_parameters: Option<&ParameterTableOverlay>,
+    ) -> RuntimeConfigStore {
         match chain_id {
             "testnet" => {
                 let genesis_runtime_config = RuntimeConfig::initial_testnet_config();
-                RuntimeConfigStore::new(Some(&genesis_runtime_config))
+                RuntimeConfigStore::with_fork_parameters(
+                    Some(&genesis_runtime_config),
+                    fork_parameters,
+                )
             }
-            _ => RuntimeConfigStore::new(None),
+            _ => RuntimeConfigStore::with_fork_parameters(None, fork_parameters),
         }
     }
 
@@ -309,8 +392,10 @@ impl NightshadeRuntime {
         );
         let tries = ShardTries::new(store, trie_cache_factory);
         let runtime = Runtime::new();
+        // Fork parameters only override gas costs, which don't matter for the
+        // genesis state.
         let runtime_config_store =
-            NightshadeRuntime::create_runtime_config_store(&genesis.config.chain_id);
+            NightshadeRuntime::create_runtime_config_store(&genesis.config.chain_id, None);
         let runtime_config = runtime_config_store.get_config(genesis.config.protocol_version);
 
         for shard_id in 0..num_shards {
@@ -536,6 +621,7 @@ impl NightshadeRuntime {
             block_hash: *block_hash,
             epoch_id,
             epoch_height,
+            chain_id: self.genesis_config.chain_id.clone(),
             gas_price,
             block_timestamp,
             gas_limit: Some(gas_limit),
@@ -703,6 +789,70 @@ pub fn state_record_to_shard_id(state_record: &StateRecord, shard_layout: &Shard
     account_id_to_shard_id(state_record_to_account_id(state_record), shard_layout)
 }
 
+/// The contract data keys changed by the blocks from the common ancestor of `from` and `to` up
+/// to either of them, according to their state changes. `None` if some of these blocks are
+/// garbage collected: GC keeps the headers but deletes the blocks along with their state
+/// changes, so a block missing from `DBCol::Block` has no record of its changes.
+fn get_contract_data_keys_changed_between(
+    store: &Store,
+    from: &CryptoHash,
+    to: &CryptoHash,
+) -> Result<Option<Vec<Vec<u8>>>, Error> {
+    let get_header = |block_hash: &CryptoHash| -> Result<Option<BlockHeader>, Error> {
+        Ok(store.get_ser(DBCol::BlockHeader, block_hash.as_ref())?)
+    };
+    let (mut from, mut to) = match (get_header(from)?, get_header(to)?) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Ok(None),
+    };
+    let mut trie_keys = HashSet::new();
+    while from.hash() != to.hash() {
+        let header = if from.height() >= to.height() { &mut from } else { &mut to };
+        if !store.exists(DBCol::Block, header.hash().as_ref())? {
+            return Ok(None);
+        }
+        let key = KeyForStateChanges::from_raw_key(header.hash(), col::CONTRACT_DATA);
+        for change in key.find_iter(store) {
+            trie_keys.insert(change?.trie_key.to_vec());
+        }
+        *header = match get_header(header.prev_hash())? {
+            Some(prev_header) => prev_header,
+            None => return Ok(None),
+        };
+    }
+    Ok(Some(trie_keys.into_iter().collect()))
+}
+
+/// Moves the flat contract data of `shard_uid`, whose head was `old_head`, to `state_root`, the
+/// state after `block_hash`. Only the keys changed on the way are read from `trie` if these are
+/// known, otherwise the index is rebuilt.
+fn move_flat_contract_data(
+    store: &Store,
+    trie: &Trie,
+    shard_uid: ShardUId,
+    old_head: Option<FlatContractDataHead>,
+    block_hash: &CryptoHash,
+    state_root: &StateRoot,
+) -> Result<(), Error> {
+    let trie_keys = match old_head {
+        Some(old_head) => {
+            get_contract_data_keys_changed_between(store, &old_head.block_hash, block_hash)?
+        }
+        None => None,
+    };
+    let flat_contract_data = FlatContractData::new(store.clone(), shard_uid);
+    match trie_keys {
+        Some(trie_keys) => {
+            flat_contract_data.update_entries(trie, block_hash, state_root, &trie_keys)?
+        }
+        None => {
+            info!(target: "runtime", "Building flat contract data of shard {:?} at block {}", shard_uid, block_hash);
+            flat_contract_data.build(trie, block_hash, state_root)?
+        }
+    }
+    Ok(())
+}
+
 impl RuntimeAdapter for NightshadeRuntime {
     fn genesis_state(&self) -> (Store, Vec<StateRoot>) {
         (self.store.clone(), self.genesis_state_roots.clone())
@@ -1049,6 +1199,15 @@ impl RuntimeAdapter for NightshadeRuntime {
         Ok(epoch_manager.get_all_block_producers_ordered(epoch_id, last_known_block_hash)?.to_vec())
     }
 
+    fn get_bp_hash_inputs(
+        &self,
+        epoch_id: &EpochId,
+        prev_epoch_id: &EpochId,
+    ) -> Result<(Vec<ValidatorStake>, ProtocolVersion), Error> {
+        let epoch_manager = self.epoch_manager.read();
+        Ok(epoch_manager.get_bp_hash_inputs(epoch_id, prev_epoch_id)?)
+    }
+
     fn get_epoch_block_approvers_ordered(
         &self,
         parent_hash: &CryptoHash,
@@ -1509,7 +1668,7 @@ impl RuntimeAdapter for NightshadeRuntime {
         request: &QueryRequest,
     ) -> Result<QueryResponse, near_chain::near_chain_primitives::error::QueryError> {
         match request {
-            QueryRequest::ViewAccount { account_id } => {
+            QueryRequest::ViewAccount { account_id, include_proof: false } => {
                 let account = self
                     .view_account(&shard_uid, *state_root, account_id)
                     .map_err(|err| {
@@ -1525,7 +1684,33 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::ViewCode { account_id } => {
+            QueryRequest::ViewAccount { account_id, include_proof: true } => {
+                let (account, proof) = self
+                    .view_account_with_proof(&shard_uid, *state_root, account_id)
+                    .map_err(|err| {
+                    near_chain::near_chain_primitives::error::QueryError::from_view_account_error(
+                        err,
+                        block_height,
+                        *block_hash,
+                    )
+                })?;
+                let account = account.ok_or_else(|| {
+                    near_chain::near_chain_primitives::error::QueryError::UnknownAccount {
+                        requested_account_id: account_id.clone(),
+                        block_height,
+                        block_hash: *block_hash,
+                    }
+                })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewAccountWithProof(AccountWithProofView {
+                        account: account.into(),
+                        proof: proof.iter().map(to_base64).collect(),
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
+            QueryRequest::ViewCode { account_id, include_proof: false, .. } => {
                 let contract_code = self
                     .view_contract_code(&shard_uid,  *state_root, account_id)
                     .map_err(|err| near_chain::near_chain_primitives::error::QueryError::from_view_contract_code_error(err, block_height, *block_hash))?;
@@ -1535,7 +1720,22 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::CallFunction { account_id, method_name, args } => {
+            QueryRequest::ViewCode { account_id, include_proof: true, include_code } => {
+                let (account, code, proof) = self
+                    .view_contract_code_with_proof(&shard_uid, *state_root, account_id, *include_code)
+                    .map_err(|err| near_chain::near_chain_primitives::error::QueryError::from_view_contract_code_error(err, block_height, *block_hash))?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewCodeWithProof(ContractCodeWithProofView {
+                        code: code.map(|code| code.into_code()),
+                        hash: account.code_hash(),
+                        account: account.into(),
+                        proof: proof.iter().map(to_base64).collect(),
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
+            QueryRequest::CallFunction { account_id, method_name, args, max_gas } => {
                 let mut logs = vec![];
                 let (epoch_height, current_protocol_version) = {
                     let epoch_manager = self.epoch_manager.read();
@@ -1562,6 +1762,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         account_id,
                         method_name,
                         args.as_ref(),
+                        *max_gas,
                         &mut logs,
                         &self.epoch_manager,
                         current_protocol_version,
@@ -1576,9 +1777,29 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::ViewState { account_id, prefix } => {
+            QueryRequest::ViewState {
+                account_id,
+                prefix,
+                from_key,
+                limit,
+                max_value_size,
+                state_size_limit,
+                include_proof,
+                allow_partial,
+            } => {
                 let view_state_result = self
-                    .view_state(&shard_uid, *state_root, account_id, prefix.as_ref())
+                    .view_state(
+                        &shard_uid,
+                        *state_root,
+                        account_id,
+                        prefix.as_ref(),
+                        from_key.as_deref(),
+                        *limit,
+                        *max_value_size,
+                        *state_size_limit,
+                        *include_proof,
+                        *allow_partial,
+                    )
                     .map_err(|err| {
                         near_chain::near_chain_primitives::error::QueryError::from_view_state_error(
                             err,
@@ -1592,7 +1813,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::ViewAccessKeyList { account_id } => {
+            QueryRequest::ViewAccessKeyList { account_id, include_proof: false } => {
                 let access_key_list =
                     self.view_access_keys(&shard_uid, *state_root, account_id).map_err(|err| {
                         near_chain::near_chain_primitives::error::QueryError::from_view_access_key_error(
@@ -1615,7 +1836,32 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::ViewAccessKey { account_id, public_key } => {
+            QueryRequest::ViewAccessKeyList { account_id, include_proof: true } => {
+                let (access_key_list, proof) = self
+                    .view_access_keys_with_proof(&shard_uid, *state_root, account_id)
+                    .map_err(|err| {
+                        near_chain::near_chain_primitives::error::QueryError::from_view_access_key_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::AccessKeyListWithProof(AccessKeyListWithProof {
+                        keys: access_key_list
+                            .into_iter()
+                            .map(|(public_key, access_key)| AccessKeyInfoView {
+                                public_key,
+                                access_key: access_key.into(),
+                            })
+                            .collect(),
+                        proof: proof.iter().map(to_base64).collect(),
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
+            QueryRequest::ViewAccessKey { account_id, public_key, include_proof: false } => {
                 let access_key = self
                     .view_access_key(&shard_uid, *state_root, account_id, public_key)
                     .map_err(|err| {
@@ -1631,9 +1877,135 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
+            QueryRequest::ViewAccessKey { account_id, public_key, include_proof: true } => {
+                let (access_key, proof) = self
+                    .view_access_key_with_proof(&shard_uid, *state_root, account_id, public_key)
+                    .map_err(|err| {
+                        near_chain::near_chain_primitives::error::QueryError::from_view_access_key_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?;
+                let access_key = access_key.ok_or_else(|| {
+                    near_chain::near_chain_primitives::error::QueryError::UnknownAccessKey {
+                        public_key: public_key.clone(),
+                        block_height,
+                        block_hash: *block_hash,
+                    }
+                })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::AccessKeyWithProof(AccessKeyWithProofView {
+                        access_key: access_key.into(),
+                        proof: proof.iter().map(to_base64).collect(),
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
         }
     }
 
+    fn view_state_batch(
+        &self,
+        shard_uid: ShardUId,
+        state_root: &StateRoot,
+        block_height: BlockHeight,
+        block_hash: &CryptoHash,
+        account_id: &AccountId,
+        prefix: &[u8],
+        from_key: Option<&[u8]>,
+        max_items: u64,
+    ) -> Result<QueryResponse, near_chain::near_chain_primitives::error::QueryError> {
+        let view_state_result = ViewRuntimeAdapter::view_state_batch(
+            self,
+            &shard_uid,
+            *state_root,
+            account_id,
+            prefix,
+            from_key,
+            max_items,
+        )
+        .map_err(|err| {
+            near_chain::near_chain_primitives::error::QueryError::from_view_state_error(
+                err,
+                block_height,
+                *block_hash,
+            )
+        })?;
+        Ok(QueryResponse {
+            kind: QueryResponseKind::ViewState(view_state_result),
+            block_height,
+            block_hash: *block_hash,
+        })
+    }
+
+    fn prove_trie_key(
+        &self,
+        shard_uid: ShardUId,
+        state_root: &StateRoot,
+        block_height: BlockHeight,
+        block_hash: &CryptoHash,
+        key: &TrieKey,
+    ) -> Result<
+        (Option<Vec<u8>>, Vec<Arc<[u8]>>),
+        near_chain::near_chain_primitives::error::QueryError,
+    > {
+        ViewRuntimeAdapter::prove_trie_key(self, &shard_uid, *state_root, key).map_err(|err| {
+            near_chain::near_chain_primitives::error::QueryError::from_view_state_error(
+                err,
+                block_height,
+                *block_hash,
+            )
+        })
+    }
+
+    fn update_flat_contract_data(
+        &self,
+        shard_uid: ShardUId,
+        block_hash: &CryptoHash,
+        state_root: &StateRoot,
+    ) -> Result<(), Error> {
+        if !self.flat_contract_data_index {
+            return Ok(());
+        }
+        let mut updates = self.flat_contract_data_updates.lock().unwrap();
+        if updates.contains(&shard_uid) {
+            // The next call brings the index to the blocks applied in the meantime.
+            return Ok(());
+        }
+        let flat_contract_data = FlatContractData::new(self.store.clone(), shard_uid);
+        let old_head = flat_contract_data.get_head()?;
+        if old_head.as_ref().map_or(false, |head| head.state_root == *state_root) {
+            return Ok(());
+        }
+        // Until the update completes, the index is neither used nor moved by applied chunks.
+        flat_contract_data.reset_head()?;
+        updates.insert(shard_uid);
+        drop(updates);
+
+        let store = self.store.clone();
+        let tries = self.tries.clone();
+        let updates = self.flat_contract_data_updates.clone();
+        let (block_hash, state_root) = (*block_hash, *state_root);
+        std::thread::spawn(move || {
+            let trie = tries.get_view_trie_for_shard(shard_uid);
+            let result = move_flat_contract_data(
+                &store,
+                &trie,
+                shard_uid,
+                old_head,
+                &block_hash,
+                &state_root,
+            );
+            if let Err(err) = result {
+                error!(target: "runtime", "Can't update flat contract data of shard {:?} to block {}, {:?}", shard_uid, block_hash, err);
+            }
+            updates.lock().unwrap().remove(&shard_uid);
+        });
+        Ok(())
+    }
+
     fn get_validator_info(
         &self,
         epoch_id: ValidatorInfoIdentifier,
@@ -1671,17 +2043,7 @@ impl RuntimeAdapter for NightshadeRuntime {
     }
 
     fn validate_state_part(&self, state_root: &StateRoot, part_id: PartId, data: &Vec<u8>) -> bool {
-        match BorshDeserialize::try_from_slice(data) {
-            Ok(trie_nodes) => {
-                match Trie::validate_trie_nodes_for_part(state_root, part_id, trie_nodes) {
-                    Ok(_) => true,
-                    // Storage error should not happen
-                    Err(_) => false,
-                }
-            }
-            // Deserialization error means we've got the data from malicious peer
-            Err(_) => false,
-        }
+        verify_state_part(state_root, part_id.idx, part_id.total, data)
     }
 
     fn apply_update_to_split_states(
@@ -1920,6 +2282,19 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         self.trie_viewer.view_account(&state_update, account_id)
     }
 
+    fn view_account_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+    ) -> Result<
+        (Option<Account>, Vec<Arc<[u8]>>),
+        node_runtime::state_viewer::errors::ViewAccountError,
+    > {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.view_account_with_proof(&state_update, account_id)
+    }
+
     fn view_contract_code(
         &self,
         shard_uid: &ShardUId,
@@ -1930,6 +2305,20 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         self.trie_viewer.view_contract_code(&state_update, account_id)
     }
 
+    fn view_contract_code_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        include_code: bool,
+    ) -> Result<
+        (Account, Option<ContractCode>, Vec<Arc<[u8]>>),
+        node_runtime::state_viewer::errors::ViewContractCodeError,
+    > {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.view_contract_code_with_proof(&state_update, account_id, include_code)
+    }
+
     fn call_function(
         &self,
         shard_uid: &ShardUId,
@@ -1943,6 +2332,7 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        max_gas: Option<Gas>,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
@@ -1954,19 +2344,24 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             block_hash: *block_hash,
             epoch_id: epoch_id.clone(),
             epoch_height,
+            chain_id: self.genesis_config.chain_id.clone(),
             block_timestamp,
             current_protocol_version,
             cache: Some(Arc::new(StoreCompiledContractCache { store: self.tries.get_store() })),
         };
-        self.trie_viewer.call_function(
-            state_update,
-            view_state,
-            contract_id,
-            method_name,
-            args,
-            logs,
-            epoch_info_provider,
-        )
+        self.trie_viewer
+            .call_function(
+                state_update,
+                view_state,
+                contract_id,
+                method_name,
+                args,
+                max_gas,
+                false,
+                logs,
+                epoch_info_provider,
+            )
+            .map(|(result, _)| result)
     }
 
     fn view_access_key(
@@ -1991,15 +2386,121 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         self.trie_viewer.view_access_keys(&state_update, account_id)
     }
 
+    fn view_access_key_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        public_key: &PublicKey,
+    ) -> Result<
+        (Option<AccessKey>, Vec<Arc<[u8]>>),
+        node_runtime::state_viewer::errors::ViewAccessKeyError,
+    > {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.view_access_key_with_proof(&state_update, account_id, public_key)
+    }
+
+    fn view_access_keys_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+    ) -> Result<
+        (Vec<(PublicKey, AccessKey)>, Vec<Arc<[u8]>>),
+        node_runtime::state_viewer::errors::ViewAccessKeyError,
+    > {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.view_access_keys_with_proof(&state_update, account_id)
+    }
+
     fn view_state(
         &self,
         shard_uid: &ShardUId,
         state_root: MerkleHash,
         account_id: &AccountId,
         prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
+        max_value_size: Option<u64>,
+        state_size_limit: Option<u64>,
+        include_proof: bool,
+        allow_partial: bool,
+    ) -> Result<ViewStateResult, node_runtime::state_viewer::errors::ViewStateError> {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        let trie_viewer = self
+            .trie_viewer
+            .with_requested_state_size_limit(state_size_limit)
+            .with_partial_results(allow_partial);
+        if self.flat_contract_data_index && !include_proof {
+            let flat_contract_data = FlatContractData::new(self.store.clone(), *shard_uid);
+            let is_at_state_root = || {
+                flat_contract_data
+                    .get_head()
+                    .map_or(false, |head| head.map_or(false, |head| head.state_root == state_root))
+            };
+            if is_at_state_root() {
+                let result = trie_viewer.view_state_flat(
+                    &state_update,
+                    &flat_contract_data,
+                    account_id,
+                    prefix,
+                    from_key,
+                    limit,
+                    max_value_size,
+                );
+                // The index may have moved to the next block while it was read.
+                if is_at_state_root() {
+                    return result;
+                }
+            }
+        }
+        trie_viewer.view_state(
+            &state_update,
+            account_id,
+            prefix,
+            from_key,
+            limit,
+            max_value_size,
+            include_proof,
+        )
+    }
+
+    fn view_state_batch(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        prefix: &[u8],
+        from_key: Option<&[u8]>,
+        max_items: u64,
     ) -> Result<ViewStateResult, node_runtime::state_viewer::errors::ViewStateError> {
         let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
-        self.trie_viewer.view_state(&state_update, account_id, prefix)
+        let mut items =
+            self.trie_viewer.iter_state_after(&state_update, account_id, prefix, from_key)?;
+        let values = items.by_ref().take(max_items as usize).collect::<Result<Vec<_>, _>>()?;
+        let next_key = match items.next() {
+            Some(_) => values.last().map(|item| item.key.clone()),
+            None => None,
+        };
+        Ok(ViewStateResult {
+            values,
+            proof: vec![],
+            next_key,
+            proof_kind: None,
+            truncated: false,
+            account_state_size: None,
+        })
+    }
+
+    fn prove_trie_key(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        key: &TrieKey,
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), node_runtime::state_viewer::errors::ViewStateError>
+    {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.prove_trie_key(&state_update, key)
     }
 }
 
@@ -2009,13 +2510,17 @@ mod test {
 
     use num_rational::Ratio;
 
+    use near_chain::Chain;
     use near_chain_configs::DEFAULT_GC_NUM_EPOCHS_TO_KEEP;
     use near_crypto::{InMemorySigner, KeyType, Signer};
     use near_logger_utils::init_test_logger;
     use near_primitives::block::Tip;
     use near_primitives::challenge::SlashedValidator;
     use near_primitives::transaction::{Action, DeleteAccountAction, StakeAction};
-    use near_primitives::types::{BlockHeightDelta, Nonce, ValidatorId, ValidatorKickoutReason};
+    use near_primitives::types::{
+        BlockHeightDelta, Nonce, RawStateChange, RawStateChangesWithTrieKey, ValidatorId,
+        ValidatorKickoutReason,
+    };
     use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
     use near_primitives::views::{
         AccountView, CurrentEpochValidatorInfo, NextEpochValidatorInfo, ValidatorKickoutView,
@@ -2179,9 +2684,12 @@ mod test {
                 tracked_config,
                 None,
                 None,
+                None,
+                None,
                 Some(RuntimeConfigStore::free()),
                 DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
                 Default::default(),
+                false,
             );
             let (_store, state_roots) = runtime.genesis_state();
             let genesis_hash = hash(&vec![0]);
@@ -2850,6 +3358,14 @@ mod test {
             .runtime
             .get_validator_info(ValidatorInfoIdentifier::BlockHash(env.head.last_block_hash))
             .unwrap();
+        let prev_epoch_id = env
+            .runtime
+            .epoch_manager
+            .0
+            .read()
+            .unwrap()
+            .get_prev_epoch_id(&env.head.last_block_hash)
+            .unwrap();
         assert_eq!(
             response,
             EpochValidatorInfo {
@@ -2866,6 +3382,20 @@ mod test {
                 prev_epoch_kickout: Default::default(),
                 epoch_start_height: 1,
                 epoch_height: 1,
+                current_bp_hash: Chain::compute_bp_hash(
+                    &env.runtime,
+                    env.head.epoch_id.clone(),
+                    prev_epoch_id,
+                    &env.head.last_block_hash,
+                )
+                .unwrap(),
+                next_bp_hash: Chain::compute_bp_hash(
+                    &env.runtime,
+                    env.head.next_epoch_id.clone(),
+                    env.head.epoch_id.clone(),
+                    &env.head.last_block_hash,
+                )
+                .unwrap(),
             }
         );
         expected_blocks = [0, 0];
@@ -3482,4 +4012,140 @@ mod test {
         assert_eq!(env.last_proposals.len(), 1);
         assert_eq!(env.last_proposals[0].stake(), 0);
     }
+
+    #[test]
+    fn test_move_flat_contract_data_after_gc() {
+        let tries = near_store::test_utils::create_tries();
+        let store = tries.get_store();
+        let shard_uid = ShardUId::single_shard();
+        let trie = tries.get_trie_for_shard(shard_uid);
+        let contract_data_key = |key: &[u8]| TrieKey::ContractData {
+            account_id: "alice".parse().unwrap(),
+            key: key.to_vec(),
+        };
+        let changes = vec![
+            (contract_data_key(b"a"), Some(b"1".to_vec())),
+            (contract_data_key(b"b"), Some(b"2".to_vec())),
+            (contract_data_key(b"c"), Some(b"3".to_vec())),
+        ];
+        let mut state_root = near_store::test_utils::test_populate_trie(
+            &tries,
+            &Trie::empty_root(),
+            shard_uid,
+            changes.into_iter().map(|(key, value)| (key.to_vec(), value)).collect(),
+        );
+
+        // Each block after genesis changes one key.
+        let signer =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let chunks = near_primitives::block::genesis_chunks(
+            vec![state_root],
+            1,
+            1000,
+            0,
+            near_primitives::version::PROTOCOL_VERSION,
+        );
+        let mut blocks = vec![near_chain::Block::genesis(
+            near_primitives::version::PROTOCOL_VERSION,
+            chunks.into_iter().map(|chunk| chunk.take_header()).collect(),
+            near_primitives::time::Clock::utc(),
+            0,
+            1000,
+            1000,
+            CryptoHash::default(),
+        )];
+        let mut state_roots = vec![state_root];
+        let block_changes = vec![
+            (contract_data_key(b"a"), Some(b"4".to_vec())),
+            (contract_data_key(b"b"), None),
+            (contract_data_key(b"c"), Some(b"5".to_vec())),
+        ];
+        let mut store_update = store.store_update();
+        for (trie_key, value) in block_changes {
+            let block = near_chain::Block::empty(blocks.last().unwrap(), &signer);
+            state_root = near_store::test_utils::test_populate_trie(
+                &tries,
+                &state_root,
+                shard_uid,
+                vec![(trie_key.to_vec(), value.clone())],
+            );
+            let state_changes = RawStateChangesWithTrieKey {
+                trie_key: trie_key.clone(),
+                changes: vec![RawStateChange {
+                    cause: StateChangeCause::InitialState,
+                    data: value,
+                }],
+            };
+            store_update.set(
+                DBCol::StateChanges,
+                KeyForStateChanges::from_trie_key(block.hash(), &trie_key).as_ref(),
+                &state_changes.try_to_vec().unwrap(),
+            );
+            blocks.push(block);
+            state_roots.push(state_root);
+        }
+        for block in &blocks {
+            store_update
+                .set_ser(DBCol::BlockHeader, block.hash().as_ref(), block.header())
+                .unwrap();
+            store_update.set_ser(DBCol::Block, block.hash().as_ref(), block).unwrap();
+        }
+        store_update.commit().unwrap();
+
+        let flat_contract_data = FlatContractData::new(store.clone(), shard_uid);
+        let get_entries = || -> Vec<_> {
+            store
+                .iter_prefix(DBCol::FlatContractData, &shard_uid.to_bytes())
+                .map(Result::unwrap)
+                .collect()
+        };
+        let old_head =
+            FlatContractDataHead { block_hash: *blocks[1].hash(), state_root: state_roots[1] };
+        flat_contract_data.build(&trie, &old_head.block_hash, &old_head.state_root).unwrap();
+        assert_eq!(
+            get_contract_data_keys_changed_between(&store, blocks[1].hash(), blocks[3].hash())
+                .unwrap()
+                .map(|trie_keys| trie_keys.len()),
+            Some(2)
+        );
+
+        // GC deletes the blocks and their state changes, but keeps the headers.
+        let mut store_update = store.store_update();
+        for block in &blocks[1..3] {
+            store_update.delete(DBCol::Block, block.hash().as_ref());
+            for item in store.iter_prefix(
+                DBCol::StateChanges,
+                KeyForStateChanges::for_block(block.hash()).as_ref(),
+            ) {
+                store_update.delete(DBCol::StateChanges, &item.unwrap().0);
+            }
+        }
+        store_update.commit().unwrap();
+        assert_eq!(
+            get_contract_data_keys_changed_between(&store, blocks[1].hash(), blocks[3].hash())
+                .unwrap(),
+            None
+        );
+
+        move_flat_contract_data(
+            &store,
+            &trie,
+            shard_uid,
+            Some(old_head),
+            blocks[3].hash(),
+            &state_roots[3],
+        )
+        .unwrap();
+        assert_eq!(
+            flat_contract_data.get_head().unwrap(),
+            Some(FlatContractDataHead {
+                block_hash: *blocks[3].hash(),
+                state_root: state_roots[3]
+            })
+        );
+        let entries = get_entries();
+        flat_contract_data.build(&trie, blocks[3].hash(), &state_roots[3]).unwrap();
+        assert_eq!(entries, get_entries());
+        assert_eq!(entries.len(), 2);
+    }
 }
@@ -5,7 +5,7 @@ use near_chain_configs::GenesisValidationMode;
 use near_o11y::{
     default_subscriber, BuildEnvFilterError, DefaultSubscriberGuard, EnvFilterBuilder,
 };
-use near_primitives::types::{Gas, NumSeats, NumShards};
+use near_primitives::types::{AccountId, Gas, NumSeats, NumShards};
 use near_state_viewer::StateViewerSubCommand;
 use near_store::db::{Mode, RocksDB};
 use std::cell::Cell;
@@ -77,6 +77,9 @@ impl NeardCmd {
             NeardSubCommand::RecompressStorage(cmd) => {
                 cmd.run(&home_dir);
             }
+            NeardSubCommand::Precompile(cmd) => {
+                cmd.run(&home_dir, genesis_validation);
+            }
         };
         Ok(())
     }
@@ -186,6 +189,13 @@ pub(super) enum NeardSubCommand {
     /// tool, it is planned to be removed by the end of 2022.
     #[clap(alias = "recompress_storage")]
     RecompressStorage(RecompressStorageSubCommand),
+    /// Compiles the contracts of the given accounts into the compiled contract
+    /// cache, so that the first calls to them after a restart don’t have to
+    /// wait for compilation.
+    ///
+    /// Contracts which are already cached are skipped, so an interrupted run
+    /// can simply be started again.  The node must not be running.
+    Precompile(PrecompileCmd),
 }
 
 #[derive(Parser)]
@@ -556,6 +566,26 @@ impl RecompressStorageSubCommand {
     }
 }
 
+#[derive(Args)]
+#[clap(arg_required_else_help = true)]
+pub(super) struct PrecompileCmd {
+    /// Comma separated accounts whose contracts to compile.
+    #[clap(long, use_value_delimiter = true, required = true)]
+    account_ids: Vec<AccountId>,
+}
+
+impl PrecompileCmd {
+    pub(super) fn run(self, home_dir: &Path, genesis_validation: GenesisValidationMode) {
+        let near_config = nearcore::config::load_config(&home_dir, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        if let Err(err) = nearcore::precompile_contracts(home_dir, &near_config, &self.account_ids)
+        {
+            error!("{:#}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -584,4 +614,19 @@ mod tests {
         ])
         .is_err());
     }
+
+    #[test]
+    fn precompile_account_ids() {
+        let cmd =
+            NeardCmd::parse_from(&["test", "precompile", "--account-ids=alice.near,bob.near"]);
+        if let NeardSubCommand::Precompile(scmd) = cmd.subcmd {
+            assert_eq!(
+                scmd.account_ids,
+                vec!["alice.near".parse::<AccountId>().unwrap(), "bob.near".parse().unwrap()]
+            );
+        } else {
+            panic!("incorrect subcommand");
+        }
+        assert!(NeardCmd::try_parse_from(&["test", "precompile"]).is_err());
+    }
 }
@@ -0,0 +1,92 @@
+#!/usr/bin/env python3
+"""Runs chainsync-loadtest in the state-sync mode against a localnet node.
+
+Spins up a single node with a single shard and waits until a few epochs pass.
+Then runs chainsync-loadtest, which connects to the node, fetches the state
+header and all the state parts of shard 0 at the start of the latest epoch and
+validates the parts against the state root.  The test checks that the whole
+part set has been fetched and validated.
+
+The test runs the chainsync-loadtest binary next to neard, and builds it with
+the same profile if it isn't there:
+
+    cargo build -p chainsync-loadtest
+"""
+
+import json
+import os
+import pathlib
+import re
+import shutil
+import subprocess
+import sys
+
+sys.path.append(str(pathlib.Path(__file__).resolve().parents[2] / 'lib'))
+
+from configured_logger import logger
+import cluster
+import utils
+
+EPOCH_LENGTH = 10
+LOADTEST_PORT = 24567 + 100
+
+near_root = pathlib.Path(cluster.load_config()['near_root'])
+loadtest = near_root / 'chainsync-loadtest'
+if not loadtest.exists():
+    logger.info(f'{loadtest} not found, building it')
+    cmd = ['cargo', 'build', '-p', 'chainsync-loadtest']
+    if near_root.name == 'release':
+        cmd.append('--release')
+    subprocess.check_call(cmd,
+                          cwd=pathlib.Path(__file__).resolve().parents[3],
+                          env=dict(os.environ,
+                                   CARGO_TARGET_DIR=str(near_root.parent)))
+
+node = cluster.start_cluster(1, 0, 1, None, [['epoch_length', EPOCH_LENGTH]],
+                             {})[0]
+utils.wait_for_blocks(node, target=3 * EPOCH_LENGTH)
+
+validators = node.get_validators()['result']
+epoch_start = node.get_block(validators['epoch_start_height'])['result']
+sync_hash = epoch_start['header']['hash']
+genesis_hash = node.get_block(0)['result']['header']['hash']
+logger.info(f'sync_hash = {sync_hash}, genesis_hash = {genesis_hash}')
+
+# The loadtest uses the node's configs, but has to listen on its own port and
+# connect to the node.
+home_dir = utils.get_near_tempdir('chainsync_loadtest', clean=True)
+for name in ('config.json', 'genesis.json'):
+    shutil.copy(os.path.join(node.node_dir, name), home_dir / name)
+cluster.apply_config_changes(
+    home_dir, {
+        'network': {
+            'addr': f'0.0.0.0:{LOADTEST_PORT}',
+            'boot_nodes': node.addr_with_pk(),
+        },
+        'consensus': {
+            'min_num_peers': 1
+        },
+    })
+
+with open(home_dir / 'genesis.json') as f:
+    chain_id = json.load(f)['chain_id']
+cmd = (str(loadtest), '--chain-id', chain_id, '--home-dir', str(home_dir),
+       '--genesis-hash', genesis_hash, '--mode', 'state-sync', '--sync-hash',
+       sync_hash, '--shard-id', '0')
+logger.info(' '.join(cmd))
+res = subprocess.run(cmd,
+                     stdout=subprocess.PIPE,
+                     stderr=subprocess.STDOUT,
+                     text=True,
+                     timeout=300)
+logger.info(res.stdout)
+assert res.returncode == 0, f'chainsync-loadtest failed: {res.returncode}'
+
+num_parts = re.search(r'STATE SYNC shard 0: state_root = \S+, (\d+) parts',
+                      res.stdout)
+fetched = re.search(r'fetched (\d+) valid state parts', res.stdout)
+assert num_parts and fetched, 'missing state sync summary'
+assert int(num_parts.group(1)) > 0
+assert num_parts.group(1) == fetched.group(1), (num_parts.group(1),
+                                                fetched.group(1))
+logger.info(f'fetched and validated {fetched.group(1)} state parts')
@@ -40,6 +40,14 @@ pub enum FunctionCallError {
     _EVMError,
     /// Non-deterministic error.
     Nondeterministic(String),
+    /// The contract imports a host function that exists but isn't available at the protocol
+    /// version it is executed with. `link_error` is the error reported by the VM, which is what
+    /// outcomes of older protocol versions record.
+    UnsupportedHostFunction {
+        name: String,
+        protocol_version: u32,
+        link_error: String,
+    },
 }
 
 /// Serializable version of `FunctionCallError`. Must never reorder/remove elements, can only
@@ -65,6 +73,10 @@ pub enum FunctionCallErrorSer {
     // error borsh serialized at correct index
     _EVMError,
     ExecutionError(String),
+    UnsupportedHostFunction {
+        name: String,
+        protocol_version: u32,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -224,6 +236,33 @@ pub enum HostError {
     /// Invalid input to alt_bn128 familiy of functions (e.g., point which isn't
     /// on the curve).
     AltBn128InvalidInput { msg: String },
+    /// The serialized ICS-23 proof exceeded the limit.
+    Ics23ProofTooLarge { size: u64, limit: u64 },
+    /// `spec_id` does not correspond to a known ICS-23 proof spec.
+    Ics23InvalidSpecId { spec_id: u64 },
+    /// Number of trie nodes in a NEAR state proof exceeded the limit.
+    NumberStateProofNodesExceeded { number_of_nodes: u64, limit: u64 },
+    /// The merkle path of an execution outcome proof exceeded the limit.
+    OutcomeProofTooLong { length: u64, limit: u64 },
+    /// Invalid input to `verify_mmr_proof` (e.g., root which isn't 32 bytes).
+    MmrInvalidInput { msg: String },
+    /// Invalid input to `verify_beefy_commitment` (e.g., truncated signature).
+    BeefyInvalidInput { msg: String },
+    /// The serialized NEAR state proof exceeded the limit.
+    StateProofTooLarge { size: u64, limit: u64 },
+    /// `storage_read_with_proof` was called outside of a view call while
+    /// `allow_storage_proofs_in_transactions` is disabled.
+    StorageProofsDisabled,
+    /// The key was modified since the state root, so its value has no proof.
+    StorageProofUnavailable,
+    /// The total number of events will exceed the limit.
+    NumberOfEventsExceeded { limit: u64 },
+    /// The total event length exceeded the limit.
+    TotalEventLengthExceeded { length: u64, limit: u64 },
+    /// Number of validators in a Tendermint validator set exceeded the limit.
+    NumberTendermintValidatorsExceeded { number_of_validators: u64, limit: u64 },
+    /// Invalid input to `verify_tendermint_commit` (e.g., trust threshold below 1/3).
+    TendermintInvalidInput { msg: String },
 }
 
 #[derive(Debug, PartialEq)]
@@ -315,6 +354,11 @@ impl fmt::Display for FunctionCallError {
             FunctionCallError::Nondeterministic(msg) => {
                 write!(f, "Nondeterministic error during contract execution: {}", msg)
             }
+            FunctionCallError::UnsupportedHostFunction { name, protocol_version, .. } => write!(
+                f,
+                "Host function {} is not available at protocol version {}",
+                name, protocol_version
+            ),
             FunctionCallError::_EVMError => unreachable!(),
         }
     }
@@ -421,6 +465,19 @@ impl std::fmt::Display for HostError {
             Deprecated {method_name}=> write!(f, "Attempted to call deprecated host function {}", method_name),
             AltBn128InvalidInput { msg } => write!(f, "AltBn128 invalid input: {}", msg),
             ECRecoverError { msg } => write!(f, "ECDSA recover error: {}", msg),
+            Ics23ProofTooLarge { size, limit } => write!(f, "The size of an ICS-23 proof {} exceeds the limit {}", size, limit),
+            Ics23InvalidSpecId { spec_id } => write!(f, "Unknown ICS-23 proof spec id: {}", spec_id),
+            NumberStateProofNodesExceeded { number_of_nodes, limit } => write!(f, "The number of nodes in a state proof {} exceeds the limit {}", number_of_nodes, limit),
+            OutcomeProofTooLong { length, limit } => write!(f, "The length of an outcome proof {} exceeds the limit {}", length, limit),
+            MmrInvalidInput { msg } => write!(f, "MMR invalid input: {}", msg),
+            BeefyInvalidInput { msg } => write!(f, "BEEFY invalid input: {}", msg),
+            StateProofTooLarge { size, limit } => write!(f, "The size of a state proof {} exceeds the limit {}", size, limit),
+            StorageProofsDisabled => write!(f, "Storage proofs are only available in view calls"),
+            StorageProofUnavailable => write!(f, "Storage proofs are not available for keys modified since the state root"),
+            NumberOfEventsExceeded { limit } => write!(f, "The number of events will exceed the limit {}", limit),
+            TotalEventLengthExceeded { length, limit } => write!(f, "The length of an event {} exceeds the limit {}", length, limit),
+            NumberTendermintValidatorsExceeded { number_of_validators, limit } => write!(f, "The number of validators in a Tendermint validator set {} exceeds the limit {}", number_of_validators, limit),
+            TendermintInvalidInput { msg } => write!(f, "Tendermint invalid input: {}", msg),
         }
     }
 }
@@ -0,0 +1,87 @@
+use ics23::{commitment_proof, CommitmentProof, ExistenceProof, HostFunctionsManager, ProofSpec};
+use near_vm_errors::{HostError, VMLogicError};
+use prost::Message;
+
+/// Proof specs that can be selected by `spec_id` in `verify_ics23_proof`.
+const IAVL_SPEC_ID: u64 = 0;
+const TENDERMINT_SPEC_ID: u64 = 1;
+const SMT_SPEC_ID: u64 = 2;
+
+pub(crate) struct InvalidSpecId {
+    pub(crate) spec_id: u64,
+}
+
+impl From<InvalidSpecId> for VMLogicError {
+    fn from(err: InvalidSpecId) -> Self {
+        HostError::Ics23InvalidSpecId { spec_id: err.spec_id }.into()
+    }
+}
+
+pub(crate) fn proof_spec(spec_id: u64) -> Result<ProofSpec, InvalidSpecId> {
+    match spec_id {
+        IAVL_SPEC_ID => Ok(ics23::iavl_spec()),
+        TENDERMINT_SPEC_ID => Ok(ics23::tendermint_spec()),
+        SMT_SPEC_ID => Ok(ics23::smt_spec()),
+        _ => Err(InvalidSpecId { spec_id }),
+    }
+}
+
+/// Decodes a protobuf encoded `CommitmentProof`, expanding compressed batch
+/// proofs so that the number of inner ops reflects the actual hashing work.
+///
+/// Returns `None` if the proof can't be decoded, such a proof is never valid.
+pub(crate) fn decode_proof(data: &[u8]) -> Option<CommitmentProof> {
+    let proof = CommitmentProof::decode(data).ok()?;
+    if ics23::is_compressed(&proof) {
+        ics23::decompress(&proof).ok()
+    } else {
+        Some(proof)
+    }
+}
+
+/// Number of inner ops which have to be applied to verify every existence
+/// proof contained in `proof`.
+pub(crate) fn num_inner_ops(proof: &CommitmentProof) -> u64 {
+    fn exist(proof: &ExistenceProof) -> u64 {
+        proof.path.len() as u64
+    }
+    fn nonexist(left: &Option<ExistenceProof>, right: &Option<ExistenceProof>) -> u64 {
+        left.iter().chain(right.iter()).map(exist).sum()
+    }
+
+    match &proof.proof {
+        Some(commitment_proof::Proof::Exist(proof)) => exist(proof),
+        Some(commitment_proof::Proof::Nonexist(proof)) => nonexist(&proof.left, &proof.right),
+        Some(commitment_proof::Proof::Batch(batch)) => batch
+            .entries
+            .iter()
+            .map(|entry| match &entry.proof {
+                Some(ics23::batch_entry::Proof::Exist(proof)) => exist(proof),
+                Some(ics23::batch_entry::Proof::Nonexist(proof)) => {
+                    nonexist(&proof.left, &proof.right)
+                }
+                None => 0,
+            })
+            .sum(),
+        // Compressed proofs are expanded by `decode_proof`.
+        Some(commitment_proof::Proof::Compressed(_)) | None => 0,
+    }
+}
+
+/// Verifies that `key` maps to `value` under `root`, or that `key` is absent
+/// if `value` is `None`.
+pub(crate) fn verify(
+    proof: &CommitmentProof,
+    spec: &ProofSpec,
+    root: &[u8],
+    key: &[u8],
+    value: Option<&[u8]>,
+) -> bool {
+    let root = root.to_vec();
+    match value {
+        Some(value) => {
+            ics23::verify_membership::<HostFunctionsManager>(proof, spec, &root, key, value)
+        }
+        None => ics23::verify_non_membership::<HostFunctionsManager>(proof, spec, &root, key),
+    }
+}
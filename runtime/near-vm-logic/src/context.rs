@@ -1,5 +1,7 @@
 use crate::types::PublicKey;
+use near_primitives::types::EpochId;
 use near_primitives_core::config::ViewConfig;
+use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::serialize::u64_dec_format;
 use near_primitives_core::types::{
     AccountId, Balance, BlockHeight, EpochHeight, Gas, StorageUsage,
@@ -35,6 +37,10 @@ pub struct VMContext {
     pub block_timestamp: u64,
     /// The current epoch height.
     pub epoch_height: EpochHeight,
+    /// The id of the current epoch.
+    pub epoch_id: EpochId,
+    /// The chain id from the genesis config.
+    pub chain_id: String,
 
     /// The balance attached to the given account. Excludes the `attached_deposit` that was
     /// attached to the transaction.
@@ -51,6 +57,9 @@ pub struct VMContext {
     pub attached_deposit: Balance,
     /// The gas attached to the call that can be used to pay for the gas fees.
     pub prepaid_gas: Gas,
+    /// The gas price of the current block.
+    #[serde(with = "crate::serde_with::u128_dec_format_compatible")]
+    pub gas_price: Balance,
     #[serde(with = "crate::serde_with::bytes_as_base58")]
     /// Initial seed for randomness
     pub random_seed: Vec<u8>,
@@ -61,6 +70,9 @@ pub struct VMContext {
     /// How many `DataReceipt`'s should receive this execution result. This should be empty if
     /// this function call is a part of a batch and it is not the last action.
     pub output_data_receivers: Vec<AccountId>,
+    /// Ids of the data received by this execution, in the order of the promise results.
+    #[serde(default)]
+    pub input_data_ids: Vec<CryptoHash>,
 }
 
 impl VMContext {
@@ -1,8 +1,9 @@
 //! External dependencies of the near-vm-logic.
 
+use near_crypto::PublicKey;
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::TrieNodesCount;
-use near_primitives_core::types::{AccountId, Balance};
+use near_primitives_core::types::{AccountId, Balance, BlockHeight};
 use near_vm_errors::VMLogicError;
 
 /// An abstraction over the memory of the smart contract.
@@ -163,6 +164,72 @@ pub trait External {
     /// ```
     fn storage_has_key(&mut self, key: &[u8]) -> Result<bool>;
 
+    /// Creates an iterator over the keys with the given `prefix` in the storage trie associated
+    /// with the current account, in lexicographic order. Returns the id of the iterator.
+    ///
+    /// The iterator may assume the storage isn't modified while it's in use, `VMLogic` rejects
+    /// iterators which were created before a write or removal.
+    ///
+    /// # Example
+    /// ```
+    /// # use near_vm_logic::mocks::mock_external::MockedExternal;
+    /// # use near_vm_logic::External;
+    ///
+    /// # let mut external = MockedExternal::new();
+    /// external.storage_set(b"key2", b"value2").unwrap();
+    /// external.storage_set(b"key1", b"value1").unwrap();
+    /// external.storage_set(b"other", b"value").unwrap();
+    /// let iterator = external.storage_iter(b"key").unwrap();
+    /// assert_eq!(
+    ///     external.storage_iter_next(iterator),
+    ///     Ok(Some((b"key1".to_vec(), b"value1".to_vec())))
+    /// );
+    /// assert_eq!(
+    ///     external.storage_iter_next(iterator),
+    ///     Ok(Some((b"key2".to_vec(), b"value2".to_vec())))
+    /// );
+    /// assert_eq!(external.storage_iter_next(iterator), Ok(None));
+    /// ```
+    fn storage_iter(&mut self, prefix: &[u8]) -> Result<u64>;
+
+    /// Creates an iterator over the keys `start <= key < end` in the storage trie associated with
+    /// the current account, in lexicographic order. Returns the id of the iterator.
+    ///
+    /// Same as `storage_iter` otherwise, the iterator is empty unless `start < end`.
+    fn storage_iter_range(&mut self, start: &[u8], end: &[u8]) -> Result<u64>;
+
+    /// Advances the iterator with the given id, returning the next key and its value or `None`
+    /// once the iterator is exhausted.
+    ///
+    /// # Errors
+    ///
+    /// This function could return [`VMError::ExternalError`], including when there is no iterator
+    /// with the given id.
+    fn storage_iter_next(&mut self, iterator_idx: u64) -> Result<Option<(Vec<u8>, Vec<u8>)>>;
+
+    /// Returns the trie nodes proving the value of `key`, or its absence, in the state root the
+    /// storage was loaded from. The nodes include the value itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HostError::StorageProofUnavailable`] if the key was modified since, as there is
+    /// no state root to prove its current value against, or if the storage can't record the
+    /// nodes it reads.
+    ///
+    /// # Example
+    /// ```
+    /// # use near_vm_logic::mocks::mock_external::MockedExternal;
+    /// # use near_vm_logic::External;
+    ///
+    /// # let mut external = MockedExternal::new();
+    /// external.storage_set(b"key", b"value").unwrap();
+    /// assert_eq!(external.storage_proof(b"key"), Ok(vec![b"value".to_vec()]));
+    /// assert_eq!(external.storage_proof(b"no_value_key"), Ok(vec![]));
+    /// ```
+    ///
+    /// [`HostError::StorageProofUnavailable`]: near_vm_errors::HostError::StorageProofUnavailable
+    fn storage_proof(&self, key: &[u8]) -> Result<Vec<Vec<u8>>>;
+
     fn generate_data_id(&mut self) -> CryptoHash;
 
     /// Returns amount of touched trie nodes by storage operations
@@ -172,6 +239,26 @@ pub trait External {
     /// If the account is not a validator, returns `None`.
     fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>>;
 
+    /// Returns the public key of the given account in the current epoch.
+    /// If the account is not a validator, returns `None`.
+    fn validator_public_key(&self, account_id: &AccountId) -> Result<Option<PublicKey>>;
+
     /// Returns total stake of validators in the current epoch.
     fn validator_total_stake(&self) -> Result<Balance>;
+
+    /// Returns the hash of the block at the given height among the ancestors of the current
+    /// block. If the height was skipped, returns `None`.
+    ///
+    /// # Example
+    /// ```
+    /// # use near_primitives::hash::CryptoHash;
+    /// # use near_vm_logic::mocks::mock_external::MockedExternal;
+    /// # use near_vm_logic::External;
+    ///
+    /// # let mut external = MockedExternal::new();
+    /// external.block_hashes.insert(10, CryptoHash::hash_bytes(b"block"));
+    /// assert_eq!(external.get_block_hash(10), Ok(Some(CryptoHash::hash_bytes(b"block"))));
+    /// assert_eq!(external.get_block_hash(11), Ok(None));
+    /// ```
+    fn get_block_hash(&self, block_height: BlockHeight) -> Result<Option<CryptoHash>>;
 }
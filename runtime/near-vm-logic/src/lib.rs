@@ -2,13 +2,18 @@
 
 mod alt_bn128;
 mod array_utils;
+mod commitment_proof;
 mod context;
 mod dependencies;
 pub mod gas_counter;
+mod light_client;
 mod logic;
+mod mmr;
 pub mod mocks;
 pub(crate) mod receipt_manager;
 pub mod serde_with;
+mod state_proof;
+mod tendermint;
 #[cfg(test)]
 mod tests;
 pub mod types;
@@ -16,12 +21,14 @@ mod utils;
 
 pub use context::VMContext;
 pub use dependencies::{External, MemoryLike, ValuePtr};
+pub use light_client::validate_light_client_block;
 pub use logic::{VMLogic, VMOutcome};
 pub use near_primitives_core::config::*;
 pub use near_primitives_core::profile;
 pub use near_primitives_core::types::ProtocolVersion;
 pub use near_vm_errors::{HostError, VMLogicError};
 pub use receipt_manager::ReceiptMetadata;
+pub use state_proof::{verify_prefix_absence, verify_state_proof, verify_state_proof_value_hash};
 pub use types::ReturnData;
 
 pub use gas_counter::with_ext_cost_counter;
@@ -0,0 +1,108 @@
+//! Validation of NEAR light client blocks, following the update rule of the
+//! NEAR light client specification.
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::block_header::{Approval, ApprovalInner, BlockHeaderInnerLite};
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::merkle::combine_hash;
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{LightClientBlockLiteView, LightClientBlockView};
+
+pub(crate) struct Inputs {
+    pub(crate) current_bps: Vec<ValidatorStakeView>,
+    pub(crate) head: LightClientBlockLiteView,
+    pub(crate) block: LightClientBlockView,
+}
+
+/// Decodes the borsh encoded block producers of the epoch of `block`, the
+/// current light client head and the new block.
+pub(crate) fn decode(current_bps: &[u8], head: &[u8], block: &[u8]) -> Option<Inputs> {
+    Some(Inputs {
+        current_bps: Vec::try_from_slice(current_bps).ok()?,
+        head: LightClientBlockLiteView::try_from_slice(head).ok()?,
+        block: LightClientBlockView::try_from_slice(block).ok()?,
+    })
+}
+
+/// Number of signatures `validate` checks at most, one per approval present
+/// for a known block producer.
+pub(crate) fn num_signatures(inputs: &Inputs) -> u64 {
+    let approvals = inputs.block.approvals_after_next.iter().take(inputs.current_bps.len());
+    approvals.filter(|approval| approval.is_some()).count() as u64
+}
+
+/// Same as `BlockHeader::compute_hash`, given the hash of the inner rest part.
+fn block_hash(block: &LightClientBlockView) -> CryptoHash {
+    let inner_lite: BlockHeaderInnerLite = block.inner_lite.clone().into();
+    let inner_hash = combine_hash(&hash(&inner_lite.try_to_vec().unwrap()), &block.inner_rest_hash);
+    combine_hash(&inner_hash, &block.prev_block_hash)
+}
+
+/// Checks whether the borsh encoded `block` is a valid successor of `head`,
+/// given the block producers of its epoch; inputs which fail to decode are
+/// invalid.
+pub fn validate_light_client_block(current_bps: &[u8], head: &[u8], block: &[u8]) -> bool {
+    decode(current_bps, head, block).map_or(false, |inputs| validate(&inputs))
+}
+
+/// Checks whether `block` is a valid successor of `head`.
+pub(crate) fn validate(inputs: &Inputs) -> bool {
+    let Inputs { current_bps, head, block } = inputs;
+
+    if block.inner_lite.height <= head.inner_lite.height {
+        return false;
+    }
+    let epoch_id = &block.inner_lite.epoch_id;
+    if epoch_id != &head.inner_lite.epoch_id && epoch_id != &head.inner_lite.next_epoch_id {
+        return false;
+    }
+    if epoch_id == &head.inner_lite.next_epoch_id && block.next_bps.is_none() {
+        return false;
+    }
+
+    // There is one approval slot per block producer of the epoch, followed by
+    // the slots of the approvers of the next epoch at the end of an epoch,
+    // which are ignored. Fewer slots would leave the stake of the missing
+    // block producers out of the total.
+    if block.approvals_after_next.len() < current_bps.len() {
+        return false;
+    }
+    let approval_height = match block.inner_lite.height.checked_add(2) {
+        Some(height) => height,
+        None => return false,
+    };
+    let next_block_hash = combine_hash(&block.next_block_inner_hash, &block_hash(block));
+    let approval_message =
+        Approval::get_data_for_sig(&ApprovalInner::Endorsement(next_block_hash), approval_height);
+
+    // The stakes come from the caller, so the sums may overflow, in which
+    // case the block is invalid.
+    let mut total_stake = 0u128;
+    let mut approved_stake = 0u128;
+    for (approval, bp) in block.approvals_after_next.iter().zip(current_bps) {
+        let ValidatorStakeView::V1(bp) = bp;
+        total_stake = match total_stake.checked_add(bp.stake) {
+            Some(stake) => stake,
+            None => return false,
+        };
+        let signature = match approval {
+            Some(signature) => signature,
+            None => continue,
+        };
+        approved_stake = match approved_stake.checked_add(bp.stake) {
+            Some(stake) => stake,
+            None => return false,
+        };
+        if !signature.verify(&approval_message, &bp.public_key) {
+            return false;
+        }
+    }
+    match total_stake.checked_mul(2) {
+        Some(threshold) if approved_stake > threshold / 3 => {}
+        _ => return false,
+    }
+
+    match &block.next_bps {
+        Some(next_bps) => CryptoHash::hash_borsh(next_bps) == block.inner_lite.next_bp_hash,
+        None => true,
+    }
+}
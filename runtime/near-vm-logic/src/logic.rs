@@ -1,3 +1,4 @@
+use crate::array_utils::ArrayChunks;
 use crate::context::VMContext;
 use crate::dependencies::{External, MemoryLike};
 use crate::gas_counter::{FastGasCounter, GasCounter};
@@ -5,10 +6,13 @@ use crate::receipt_manager::ReceiptManager;
 use crate::types::{PromiseIndex, PromiseResult, ReceiptIndex, ReturnData};
 use crate::utils::split_method_names;
 use crate::{ReceiptMetadata, ValuePtr};
+use borsh::{BorshDeserialize, BorshSerialize};
 use byteorder::ByteOrder;
 use near_crypto::Secp256K1Signature;
 use near_primitives::checked_feature;
-use near_primitives::config::ViewConfig;
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::{verify_hash, MerklePath};
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::version::is_implicit_account_creation_enabled;
 use near_primitives_core::config::ExtCosts::*;
 use near_primitives_core::config::{ActionCosts, ExtCosts, VMConfig};
@@ -17,12 +21,12 @@ use near_primitives_core::runtime::fees::{
     transfer_exec_fee, transfer_send_fee, RuntimeFeesConfig,
 };
 use near_primitives_core::types::{
-    AccountId, Balance, EpochHeight, Gas, ProtocolVersion, StorageUsage,
+    AccountId, Balance, BlockHeight, EpochHeight, Gas, ProtocolVersion, StorageUsage,
 };
 use near_primitives_core::types::{GasDistribution, GasWeight};
 use near_vm_errors::{HostError, VMLogicError};
 use near_vm_errors::{InconsistentStateError, VMError};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem::size_of;
 
 pub type Result<T> = ::std::result::Result<T, VMLogicError>;
@@ -56,14 +60,27 @@ pub struct VMLogic<'a> {
     return_data: ReturnData,
     /// Logs written by the runtime.
     logs: Vec<String>,
+    /// Events emitted by the contract, as pairs of standard and data.
+    events: Vec<(String, Vec<u8>)>,
     /// Registers can be used by the guest to store blobs of data without moving them across
     /// host-guest boundary.
     registers: HashMap<u64, Vec<u8>>,
 
+    /// Iterators that were created and can still be advanced.
+    valid_iterators: HashSet<u64>,
+    /// Iterators that were invalidated by a write or removal after their creation.
+    invalid_iterators: HashSet<u64>,
+
     /// The DAG of promises, indexed by promise id.
     promises: Vec<Promise>,
     /// Tracks the total log length. The sum of length of all logs.
     total_log_length: u64,
+    /// Max number of logs, which is higher for view calls.
+    max_number_logs: u64,
+    /// Max total log length, which is higher for view calls.
+    max_total_log_length: u64,
+    /// Tracks the total event length. The sum of length of all event standards and data.
+    total_event_length: u64,
 
     /// Current protocol version that is used for the function call.
     current_protocol_version: ProtocolVersion,
@@ -115,9 +132,17 @@ impl<'a> VMLogic<'a> {
         // Overflow should be checked before calling VMLogic.
         let current_account_balance = context.account_balance + context.attached_deposit;
         let current_storage_usage = context.storage_usage;
-        let max_gas_burnt = match context.view_config {
-            Some(ViewConfig { max_gas_burnt: max_gas_burnt_view }) => max_gas_burnt_view,
-            None => config.limit_config.max_gas_burnt,
+        let (max_gas_burnt, max_number_logs, max_total_log_length) = match &context.view_config {
+            Some(view_config) => (
+                view_config.max_gas_burnt,
+                view_config.max_number_logs(&config.limit_config),
+                view_config.max_total_log_length(&config.limit_config),
+            ),
+            None => (
+                config.limit_config.max_gas_burnt,
+                config.limit_config.max_number_logs,
+                config.limit_config.max_total_log_length,
+            ),
         };
 
         let current_account_locked_balance = context.account_locked_balance;
@@ -141,9 +166,15 @@ impl<'a> VMLogic<'a> {
             gas_counter,
             return_data: ReturnData::None,
             logs: vec![],
+            events: vec![],
             registers: HashMap::new(),
+            valid_iterators: HashSet::new(),
+            invalid_iterators: HashSet::new(),
             promises: vec![],
             total_log_length: 0,
+            max_number_logs,
+            max_total_log_length,
+            total_event_length: 0,
             current_protocol_version,
             receipt_manager: ReceiptManager::default(),
         }
@@ -154,6 +185,11 @@ impl<'a> VMLogic<'a> {
         &self.logs
     }
 
+    /// Returns reference to events that have been emitted so far.
+    pub fn events(&self) -> &[(String, Vec<u8>)] {
+        &self.events
+    }
+
     /// Returns receipt metadata for created receipts
     pub fn action_receipts(&self) -> &[(AccountId, ReceiptMetadata)] {
         &self.receipt_manager.action_receipts
@@ -355,16 +391,19 @@ impl<'a> VMLogic<'a> {
     ///
     /// For nul-terminated string:
     /// `(read_memory_base + read_memory_byte) * num_bytes + utf8_decoding_base + utf8_decoding_byte * num_bytes`
+    ///
+    /// The scan of a nul-terminated string stops after `max_total_log_length - total_log_length + 1`
+    /// bytes. Since `NulTerminatedUtf8DecodingCost`, `utf8_decoding_byte` is also paid for the
+    /// scanned bytes when the scan ends with `TotalLogLengthExceeded`.
     fn get_utf8_string(&mut self, len: u64, ptr: u64) -> Result<String> {
         self.gas_counter.pay_base(utf8_decoding_base)?;
         let mut buf;
-        let max_len =
-            self.config.limit_config.max_total_log_length.saturating_sub(self.total_log_length);
+        let max_len = self.max_total_log_length.saturating_sub(self.total_log_length);
         if len != u64::MAX {
             if len > max_len {
                 return Err(HostError::TotalLogLengthExceeded {
                     length: self.total_log_length.saturating_add(len),
-                    limit: self.config.limit_config.max_total_log_length,
+                    limit: self.max_total_log_length,
                 }
                 .into());
             }
@@ -378,9 +417,16 @@ impl<'a> VMLogic<'a> {
                     break;
                 }
                 if i == max_len {
+                    if checked_feature!(
+                        "stable",
+                        NulTerminatedUtf8DecodingCost,
+                        self.current_protocol_version
+                    ) {
+                        self.gas_counter.pay_per(utf8_decoding_byte, max_len.saturating_add(1))?;
+                    }
                     return Err(HostError::TotalLogLengthExceeded {
                         length: self.total_log_length.saturating_add(max_len).saturating_add(1),
-                        limit: self.config.limit_config.max_total_log_length,
+                        limit: self.max_total_log_length,
                     }
                     .into());
                 }
@@ -409,7 +455,9 @@ impl<'a> VMLogic<'a> {
     /// * If string extends outside the memory of the guest with `MemoryAccessViolation`;
     /// * If string is not UTF-16 returns `BadUtf16`.
     /// * If number of bytes read + `total_log_length` exceeds the `max_total_log_length` returns
-    ///   `TotalLogLengthExceeded`.
+    ///   `TotalLogLengthExceeded`. Since `Utf16LogUtf8Length` the number of code units read is
+    ///   used instead, as each of them takes at least one byte once encoded as UTF-8. The exact
+    ///   UTF-8 length is then checked when the message is pushed to the logs.
     ///
     /// # Cost
     ///
@@ -421,17 +469,24 @@ impl<'a> VMLogic<'a> {
     fn get_utf16_string(&mut self, len: u64, ptr: u64) -> Result<String> {
         self.gas_counter.pay_base(utf16_decoding_base)?;
         let mut u16_buffer;
-        let max_len =
-            self.config.limit_config.max_total_log_length.saturating_sub(self.total_log_length);
+        let max_len = self.max_total_log_length.saturating_sub(self.total_log_length);
+        // Number of bytes each code unit counts for against `max_total_log_length`.
+        let unit_len =
+            if checked_feature!("stable", Utf16LogUtf8Length, self.current_protocol_version) {
+                1
+            } else {
+                size_of::<u16>() as u64
+            };
         if len != u64::MAX {
             let input = self.memory_get_vec(ptr, len)?;
             if len % 2 != 0 {
                 return Err(HostError::BadUTF16.into());
             }
-            if len > max_len {
+            let counted_len = len / size_of::<u16>() as u64 * unit_len;
+            if counted_len > max_len {
                 return Err(HostError::TotalLogLengthExceeded {
-                    length: self.total_log_length.saturating_add(len),
-                    limit: self.config.limit_config.max_total_log_length,
+                    length: self.total_log_length.saturating_add(counted_len),
+                    limit: self.max_total_log_length,
                 }
                 .into());
             }
@@ -439,7 +494,7 @@ impl<'a> VMLogic<'a> {
             byteorder::LittleEndian::read_u16_into(&input, &mut u16_buffer);
         } else {
             u16_buffer = vec![];
-            let limit = max_len / size_of::<u16>() as u64;
+            let limit = max_len / unit_len;
             // Takes 2 bytes each iter
             for i in 0..=limit {
                 // self.try_fit_mem will check for u64 overflow on the first iteration (i == 0)
@@ -452,9 +507,9 @@ impl<'a> VMLogic<'a> {
                     return Err(HostError::TotalLogLengthExceeded {
                         length: self
                             .total_log_length
-                            .saturating_add(i * size_of::<u16>() as u64)
-                            .saturating_add(size_of::<u16>() as u64),
-                        limit: self.config.limit_config.max_total_log_length,
+                            .saturating_add(i * unit_len)
+                            .saturating_add(unit_len),
+                        limit: self.max_total_log_length,
                     }
                     .into());
                 }
@@ -472,9 +527,20 @@ impl<'a> VMLogic<'a> {
 
     /// Checks that the current log number didn't reach the limit yet, so we can add a new message.
     fn check_can_add_a_log_message(&self) -> Result<()> {
-        if self.logs.len() as u64 >= self.config.limit_config.max_number_logs {
-            Err(HostError::NumberOfLogsExceeded { limit: self.config.limit_config.max_number_logs }
-                .into())
+        if self.logs.len() as u64 >= self.max_number_logs {
+            Err(HostError::NumberOfLogsExceeded { limit: self.max_number_logs }.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that the current event number didn't reach the limit yet, so we can add a new event.
+    fn check_can_add_an_event(&self) -> Result<()> {
+        if self.events.len() as u64 >= self.config.limit_config.max_number_events {
+            Err(HostError::NumberOfEventsExceeded {
+                limit: self.config.limit_config.max_number_events,
+            }
+            .into())
         } else {
             Ok(())
         }
@@ -501,10 +567,10 @@ impl<'a> VMLogic<'a> {
     fn checked_push_log(&mut self, message: String) -> Result<()> {
         // The size of logged data can't be too large. No overflow.
         self.total_log_length += message.len() as u64;
-        if self.total_log_length > self.config.limit_config.max_total_log_length {
+        if self.total_log_length > self.max_total_log_length {
             return Err(HostError::TotalLogLengthExceeded {
                 length: self.total_log_length,
-                limit: self.config.limit_config.max_total_log_length,
+                limit: self.max_total_log_length,
             }
             .into());
         }
@@ -512,6 +578,20 @@ impl<'a> VMLogic<'a> {
         Ok(())
     }
 
+    fn checked_push_event(&mut self, standard: String, data: Vec<u8>) -> Result<()> {
+        // The size of emitted events can't be too large. No overflow.
+        self.total_event_length += (standard.len() + data.len()) as u64;
+        if self.total_event_length > self.config.limit_config.max_total_event_length {
+            return Err(HostError::TotalEventLengthExceeded {
+                length: self.total_event_length,
+                limit: self.config.limit_config.max_total_event_length,
+            }
+            .into());
+        }
+        self.events.push((standard, data));
+        Ok(())
+    }
+
     // ###############
     // # Context API #
     // ###############
@@ -657,6 +737,52 @@ impl<'a> VMLogic<'a> {
         Ok(self.context.epoch_height)
     }
 
+    /// Writes the 32-byte id of the current epoch into the register.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * 32`
+    pub fn epoch_id(&mut self, register_id: u64) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        self.internal_write_register(register_id, self.context.epoch_id.0.as_bytes().to_vec())
+    }
+
+    /// Writes the UTF-8 encoded chain id of the genesis config into the register.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes`
+    pub fn chain_id(&mut self, register_id: u64) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        self.internal_write_register(register_id, self.context.chain_id.as_bytes().to_vec())
+    }
+
+    /// Looks up the hash of the block at the given height. If the block is one of the last
+    /// `max_block_hash_lookback` blocks before the current one, writes its 32-byte hash into the
+    /// `register_id` and returns `1`. Otherwise returns `0`, which includes skipped heights and
+    /// the current height, as the hash of the current block isn't known during its execution.
+    ///
+    /// # Cost
+    ///
+    /// `base + block_hash_base + write_register_base + write_register_byte * 32`
+    pub fn block_hash(&mut self, block_height: BlockHeight, register_id: u64) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        self.gas_counter.pay_base(block_hash_base)?;
+        let current_height = self.context.block_index;
+        if block_height >= current_height
+            || current_height - block_height > self.config.limit_config.max_block_hash_lookback
+        {
+            return Ok(0);
+        }
+        match self.ext.get_block_hash(block_height)? {
+            Some(hash) => {
+                self.internal_write_register(register_id, hash.as_bytes().to_vec())?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
     /// Get the stake of an account, if the account is currently a validator. Otherwise returns 0.
     /// writes the value into the` u128` variable pointed by `stake_ptr`.
     ///
@@ -676,6 +802,34 @@ impl<'a> VMLogic<'a> {
         self.memory_set_u128(stake_ptr, balance)
     }
 
+    /// Get the public key of an account, if the account is currently a validator.
+    /// * If the account is a validator writes the borsh serialized `PublicKey` into the
+    ///   `register_id`. Returns `1`;
+    /// * Otherwise does not modify the register. Returns `0`.
+    ///
+    /// # Cost
+    ///
+    /// `base + utf8_decoding_base + utf8_decoding_byte * account_id_len + validator_stake_base +
+    /// write_register_base + write_register_byte * num_bytes`
+    pub fn validator_public_key(
+        &mut self,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        register_id: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
+        self.gas_counter.pay_base(validator_stake_base)?;
+        match self.ext.validator_public_key(&account_id)? {
+            Some(public_key) => {
+                let public_key = public_key.try_to_vec().expect("Failed to serialize");
+                self.internal_write_register(register_id, public_key)?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
     /// Get the total validator stake of the current epoch.
     /// Write the u128 value into `stake_ptr`.
     /// writes the value into the` u128` variable pointed by `stake_ptr`.
@@ -788,6 +942,16 @@ impl<'a> VMLogic<'a> {
         Ok(self.gas_counter.used_gas())
     }
 
+    /// Writes the gas price of the current block into the register, as a little-endian `u128`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * 16`
+    pub fn gas_price(&mut self, register_id: u64) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        self.internal_write_register(register_id, self.context.gas_price.to_le_bytes().to_vec())
+    }
+
     // ############
     // # Math API #
     // ############
@@ -918,6 +1082,591 @@ impl<'a> VMLogic<'a> {
         Ok(res as u64)
     }
 
+    /// Verifies an ICS-23 `CommitmentProof` against a commitment root and
+    /// writes `1` into the register if the proof is valid, `0` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec_id` - proof spec to verify against: `0` for IAVL, `1` for
+    ///   Tendermint and `2` for sparse merkle trees.
+    /// * `root` - commitment root the proof is verified against.
+    /// * `proof` - protobuf encoded `CommitmentProof`, compressed batch proofs
+    ///   are accepted.
+    /// * `key` - key the proof is about.
+    /// * `value` - value expected under `key`. If `value_len` is `0` the proof
+    ///   is verified as a proof of non-existence of `key`.
+    ///
+    /// # Errors
+    ///
+    /// If `root_len + root_ptr`, `proof_len + proof_ptr`, `key_len + key_ptr`
+    /// or `value_len + value_ptr` points outside the memory or the registers
+    /// use more memory than the limit, the function returns
+    /// `MemoryAccessViolation`.
+    ///
+    /// If `spec_id` is not a known proof spec, the function returns
+    /// `Ics23InvalidSpecId`.
+    ///
+    /// If the proof is longer than `max_ics23_proof_size`, the function
+    /// returns `Ics23ProofTooLarge`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  verify_ics23_proof_base + verify_ics23_proof_byte * num_proof_bytes +
+    ///  verify_ics23_proof_inner_op * num_inner_ops`
+    pub fn verify_ics23_proof(
+        &mut self,
+        spec_id: u64,
+        root_len: u64,
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(verify_ics23_proof_base)?;
+        let spec = crate::commitment_proof::proof_spec(spec_id)?;
+        let root = self.get_vec_from_memory_or_register(root_ptr, root_len)?;
+        let key = self.get_vec_from_memory_or_register(key_ptr, key_len)?;
+        let value = if value_len == 0 {
+            None
+        } else {
+            Some(self.get_vec_from_memory_or_register(value_ptr, value_len)?)
+        };
+        let proof = self.get_vec_from_memory_or_register(proof_ptr, proof_len)?;
+
+        let limit = self.config.limit_config.max_ics23_proof_size;
+        if proof.len() as u64 > limit {
+            return Err(HostError::Ics23ProofTooLarge { size: proof.len() as u64, limit }.into());
+        }
+        self.gas_counter.pay_per(verify_ics23_proof_byte, proof.len() as u64)?;
+
+        let valid = match crate::commitment_proof::decode_proof(&proof) {
+            Some(proof) => {
+                let inner_ops = crate::commitment_proof::num_inner_ops(&proof);
+                self.gas_counter.pay_per(verify_ics23_proof_inner_op, inner_ops)?;
+                crate::commitment_proof::verify(&proof, &spec, &root, &key, value.as_deref())
+            }
+            None => false,
+        };
+
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
+    /// Verifies a proof of NEAR state, as stored by the trie, and writes `1`
+    /// into the register if the proof is valid, `0` otherwise.
+    ///
+    /// Use `verify_near_state_proof_status` to learn why a proof is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - 32 byte state root the proof is verified against.
+    /// * `proof` - borsh encoded `Vec<Vec<u8>>` of serialized trie nodes. The
+    ///   nodes may come in any order.
+    /// * `account_id` - account which owns the contract data.
+    /// * `key` - key of the contract data the proof is about.
+    /// * `value` - value expected under `key`. If `value_len` is `0` the proof
+    ///   is verified as a proof of absence of `key`.
+    ///
+    /// # Errors
+    ///
+    /// If `root_ptr + 32`, `proof_len + proof_ptr`, `account_id_len +
+    /// account_id_ptr`, `key_len + key_ptr` or `value_len + value_ptr` points
+    /// outside the memory or the registers use more memory than the limit,
+    /// the function returns `MemoryAccessViolation`.
+    ///
+    /// If `account_id` is not valid UTF-8, the function returns `BadUTF8`.
+    ///
+    /// If the proof is longer than `max_state_proof_size`, the function
+    /// returns `StateProofTooLarge`.
+    ///
+    /// If the proof contains more than `max_number_state_proof_nodes` nodes,
+    /// the function returns `NumberStateProofNodesExceeded`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  utf8_decoding_base + utf8_decoding_byte * num_account_id_bytes +
+    ///  verify_near_state_proof_base + verify_near_state_proof_byte * num_proof_bytes +
+    ///  verify_near_state_proof_node * num_nodes`
+    pub fn verify_near_state_proof(
+        &mut self,
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        let status = self.near_state_proof_status(
+            root_ptr,
+            proof_len,
+            proof_ptr,
+            account_id_len,
+            account_id_ptr,
+            key_len,
+            key_ptr,
+            value_len,
+            value_ptr,
+        )?;
+        let valid = status == crate::state_proof::ProofStatus::Verified;
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
+    /// Verifies a proof of NEAR state, as stored by the trie, and returns
+    /// why it failed to verify if it did:
+    ///
+    /// * `0` - the proof is valid.
+    /// * `1` - `key` is present with another value, or is present while the
+    ///   proof was verified as a proof of absence.
+    /// * `2` - `key` is absent while a value was expected.
+    /// * `3` - the proof fails to decode or lacks a node on the path to `key`.
+    /// * `4` - none of the nodes in the proof hashes to `root`.
+    ///
+    /// Takes the same arguments as `verify_near_state_proof`, without the
+    /// register.
+    ///
+    /// # Errors
+    ///
+    /// Same as `verify_near_state_proof`, only invalid arguments are errors.
+    ///
+    /// # Cost
+    ///
+    /// `base + utf8_decoding_base + utf8_decoding_byte * num_account_id_bytes +
+    ///  verify_near_state_proof_base + verify_near_state_proof_byte * num_proof_bytes +
+    ///  verify_near_state_proof_node * num_nodes`
+    pub fn verify_near_state_proof_status(
+        &mut self,
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+    ) -> Result<u64> {
+        let status = self.near_state_proof_status(
+            root_ptr,
+            proof_len,
+            proof_ptr,
+            account_id_len,
+            account_id_ptr,
+            key_len,
+            key_ptr,
+            value_len,
+            value_ptr,
+        )?;
+        Ok(status as u64)
+    }
+
+    fn near_state_proof_status(
+        &mut self,
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+    ) -> Result<crate::state_proof::ProofStatus> {
+        self.gas_counter.pay_base(verify_near_state_proof_base)?;
+        let mut root = [0u8; 32];
+        self.memory_get_into(root_ptr, &mut root)?;
+        let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
+        let key = self.get_vec_from_memory_or_register(key_ptr, key_len)?;
+        let value = if value_len == 0 {
+            None
+        } else {
+            Some(self.get_vec_from_memory_or_register(value_ptr, value_len)?)
+        };
+        let proof = self.get_vec_from_memory_or_register(proof_ptr, proof_len)?;
+
+        let limit = self.config.limit_config.max_state_proof_size;
+        if proof.len() as u64 > limit {
+            return Err(HostError::StateProofTooLarge { size: proof.len() as u64, limit }.into());
+        }
+        self.gas_counter.pay_per(verify_near_state_proof_byte, proof.len() as u64)?;
+
+        // The number of nodes is the borsh length prefix, so it's limited and
+        // paid for before any node gets decoded.
+        let number_of_nodes = match proof.get(..4) {
+            Some(len) => u32::from_le_bytes(len.try_into().unwrap()) as u64,
+            None => 0,
+        };
+        let limit = self.config.limit_config.max_number_state_proof_nodes;
+        if number_of_nodes > limit {
+            return Err(HostError::NumberStateProofNodesExceeded { number_of_nodes, limit }.into());
+        }
+        self.gas_counter.pay_per(verify_near_state_proof_node, number_of_nodes)?;
+
+        let status = match Vec::<Vec<u8>>::try_from_slice(&proof) {
+            Ok(nodes) => {
+                let key = TrieKey::ContractData { account_id, key }.to_vec();
+                crate::state_proof::verify(&CryptoHash(root), &nodes, &key, value.as_deref())
+            }
+            Err(_) => crate::state_proof::ProofStatus::MalformedProof,
+        };
+        Ok(status)
+    }
+
+    /// Verifies that an execution outcome is included in a block's outcome
+    /// root and writes `1` into the register if the proof is valid, `0`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `outcome_hash` - 32 byte hash of the merkle leaf, i.e. the borsh
+    ///   hash of `ExecutionOutcomeWithId::to_hashes`.
+    /// * `proof` - borsh encoded `MerklePath` from the leaf to the root.
+    /// * `block_outcome_root` - 32 byte outcome root of the block.
+    ///
+    /// # Errors
+    ///
+    /// If `outcome_hash_ptr + 32`, `proof_len + proof_ptr` or
+    /// `block_outcome_root_ptr + 32` points outside the memory or the
+    /// registers use more memory than the limit, the function returns
+    /// `MemoryAccessViolation`.
+    ///
+    /// If the merkle path is longer than `max_outcome_proof_length`, the
+    /// function returns `OutcomeProofTooLong`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  verify_outcome_proof_base + verify_outcome_proof_item * num_items`
+    pub fn verify_outcome_proof(
+        &mut self,
+        outcome_hash_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        block_outcome_root_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(verify_outcome_proof_base)?;
+        let mut outcome_hash = [0u8; 32];
+        self.memory_get_into(outcome_hash_ptr, &mut outcome_hash)?;
+        let proof = self.get_vec_from_memory_or_register(proof_ptr, proof_len)?;
+        let mut block_outcome_root = [0u8; 32];
+        self.memory_get_into(block_outcome_root_ptr, &mut block_outcome_root)?;
+
+        let valid = match MerklePath::try_from_slice(&proof) {
+            Ok(path) => {
+                let length = path.len() as u64;
+                let limit = self.config.limit_config.max_outcome_proof_length;
+                if length > limit {
+                    return Err(HostError::OutcomeProofTooLong { length, limit }.into());
+                }
+                self.gas_counter.pay_per(verify_outcome_proof_item, length)?;
+                verify_hash(CryptoHash(block_outcome_root), &path, CryptoHash(outcome_hash))
+            }
+            Err(_) => false,
+        };
+
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
+    /// Validates a NEAR light client block against the current light client
+    /// head and writes `1` into the register if the block is a valid update,
+    /// `0` otherwise.
+    ///
+    /// The block is valid if it is higher than the head, belongs to the epoch
+    /// of the head or the next one (carrying `next_bps` in the latter case),
+    /// more than 2/3 of the stake of `current_bps` signed its
+    /// `approvals_after_next` and `next_bps`, if present, hashes to
+    /// `next_bp_hash`.
+    ///
+    /// # Arguments
+    ///
+    /// * `current_bps` - borsh encoded `Vec<ValidatorStakeView>`, block
+    ///   producers of the epoch of the new block.
+    /// * `head` - borsh encoded `LightClientBlockLiteView` of the head.
+    /// * `new_block` - borsh encoded `LightClientBlockView`.
+    ///
+    /// # Errors
+    ///
+    /// If `current_bps_len + current_bps_ptr`, `head_len + head_ptr` or
+    /// `new_block_len + new_block_ptr` points outside the memory or the
+    /// registers use more memory than the limit, the function returns
+    /// `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  validate_light_client_block_base +
+    ///  validate_light_client_block_byte * num_input_bytes +
+    ///  validate_light_client_block_signature * num_signatures`
+    pub fn validate_light_client_block(
+        &mut self,
+        current_bps_len: u64,
+        current_bps_ptr: u64,
+        head_len: u64,
+        head_ptr: u64,
+        new_block_len: u64,
+        new_block_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(validate_light_client_block_base)?;
+        let current_bps = self.get_vec_from_memory_or_register(current_bps_ptr, current_bps_len)?;
+        let head = self.get_vec_from_memory_or_register(head_ptr, head_len)?;
+        let new_block = self.get_vec_from_memory_or_register(new_block_ptr, new_block_len)?;
+        let num_bytes = (current_bps.len() + head.len() + new_block.len()) as u64;
+        self.gas_counter.pay_per(validate_light_client_block_byte, num_bytes)?;
+
+        let valid = match crate::light_client::decode(&current_bps, &head, &new_block) {
+            Some(inputs) => {
+                let num_signatures = crate::light_client::num_signatures(&inputs);
+                self.gas_counter.pay_per(validate_light_client_block_signature, num_signatures)?;
+                crate::light_client::validate(&inputs)
+            }
+            None => false,
+        };
+
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
+    /// Verifies an inclusion proof of a leaf in a merkle mountain range with
+    /// keccak256 as the node hash and writes `1` into the register if the
+    /// proof is valid, `0` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - 32 byte root of the MMR, its peaks bagged right to left.
+    /// * `leaf` - leaf data, the MMR stores its keccak256 hash.
+    /// * `proof` - concatenated 32 byte proof items, in the order produced by
+    ///   `ckb-merkle-mountain-range`.
+    /// * `leaf_index` - index of the leaf among all leaves.
+    /// * `leaf_count` - number of leaves in the MMR.
+    ///
+    /// # Errors
+    ///
+    /// If `root_len + root_ptr`, `leaf_len + leaf_ptr` or `proof_len +
+    /// proof_ptr` points outside the memory or the registers use more memory
+    /// than the limit, the function returns `MemoryAccessViolation`.
+    ///
+    /// If the root is not 32 bytes or the proof length is not a multiple of
+    /// 32, the function returns `MmrInvalidInput`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  verify_mmr_proof_base + verify_mmr_proof_byte * num_leaf_bytes +
+    ///  verify_mmr_proof_item * num_items`
+    pub fn verify_mmr_proof(
+        &mut self,
+        root_len: u64,
+        root_ptr: u64,
+        leaf_len: u64,
+        leaf_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        leaf_index: u64,
+        leaf_count: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(verify_mmr_proof_base)?;
+        let root = self.get_vec_from_memory_or_register(root_ptr, root_len)?;
+        let root: [u8; crate::mmr::HASH_SIZE] =
+            root.as_slice().try_into().map_err(|_| HostError::MmrInvalidInput {
+                msg: format!("The length of the root: {}, must be 32 bytes", root.len()),
+            })?;
+        let leaf = self.get_vec_from_memory_or_register(leaf_ptr, leaf_len)?;
+        self.gas_counter.pay_per(verify_mmr_proof_byte, leaf.len() as u64)?;
+        let proof = self.get_vec_from_memory_or_register(proof_ptr, proof_len)?;
+        let proof: Vec<[u8; crate::mmr::HASH_SIZE]> = ArrayChunks::new(&proof)
+            .map_err(|()| HostError::MmrInvalidInput {
+                msg: format!("The length of the proof: {}, is not a multiple of 32", proof.len()),
+            })?
+            .copied()
+            .collect();
+        self.gas_counter.pay_per(verify_mmr_proof_item, proof.len() as u64)?;
+
+        let valid = crate::mmr::verify_proof(&root, &leaf, &proof, leaf_index, leaf_count);
+
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
+    /// Verifies that more than 2/3 of a BEEFY authority set signed a
+    /// commitment and writes `1` into the register if so, `0` otherwise.
+    /// Any invalid signature makes the commitment invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `authorities` - concatenated 64 byte secp256k1 public keys of the
+    ///   authority set, in the format returned by `ecrecover`.
+    /// * `commitment` - encoded commitment, the authorities sign its keccak256
+    ///   hash.
+    /// * `signatures` - concatenated 69 byte entries, each the index of the
+    ///   authority as u32 little endian followed by a 65 byte recoverable
+    ///   signature. Each authority may sign at most once and signatures with
+    ///   the high `s` value are rejected.
+    ///
+    /// # Errors
+    ///
+    /// If `authorities_len + authorities_ptr`, `commitment_len +
+    /// commitment_ptr` or `signatures_len + signatures_ptr` points outside the
+    /// memory or the registers use more memory than the limit, the function
+    /// returns `MemoryAccessViolation`.
+    ///
+    /// If the length of `authorities` is not a multiple of 64 or the length of
+    /// `signatures` is not a multiple of 69, the function returns
+    /// `BeefyInvalidInput`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  verify_beefy_commitment_base +
+    ///  verify_beefy_commitment_byte * num_commitment_bytes +
+    ///  verify_beefy_commitment_signature * num_signatures`
+    pub fn verify_beefy_commitment(
+        &mut self,
+        authorities_len: u64,
+        authorities_ptr: u64,
+        commitment_len: u64,
+        commitment_ptr: u64,
+        signatures_len: u64,
+        signatures_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(verify_beefy_commitment_base)?;
+        let authorities = self.get_vec_from_memory_or_register(authorities_ptr, authorities_len)?;
+        let authorities: Vec<[u8; crate::mmr::PUBLIC_KEY_SIZE]> = ArrayChunks::new(&authorities)
+            .map_err(|()| HostError::BeefyInvalidInput {
+                msg: format!(
+                    "The length of the authorities: {}, is not a multiple of 64",
+                    authorities.len()
+                ),
+            })?
+            .copied()
+            .collect();
+        let commitment = self.get_vec_from_memory_or_register(commitment_ptr, commitment_len)?;
+        self.gas_counter.pay_per(verify_beefy_commitment_byte, commitment.len() as u64)?;
+        let signatures = self.get_vec_from_memory_or_register(signatures_ptr, signatures_len)?;
+        let signatures: Vec<[u8; crate::mmr::BEEFY_SIGNATURE_SIZE]> = ArrayChunks::new(&signatures)
+            .map_err(|()| HostError::BeefyInvalidInput {
+                msg: format!(
+                    "The length of the signatures: {}, is not a multiple of 69",
+                    signatures.len()
+                ),
+            })?
+            .copied()
+            .collect();
+        self.gas_counter.pay_per(verify_beefy_commitment_signature, signatures.len() as u64)?;
+
+        let valid = crate::mmr::verify_beefy_commitment(&authorities, &commitment, &signatures);
+
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
+    /// Verifies that validators holding more than the trust threshold of the
+    /// voting power of a trusted validator set signed a Tendermint commit, as
+    /// done by the ICS-07 Tendermint light client. Writes `1` into the
+    /// register if the threshold is met, `0` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `validators` - protobuf encoded `tendermint.types.ValidatorSet`
+    ///   trusted by the light client. Only ed25519 keys are supported.
+    /// * `commit` - protobuf encoded `tendermint.types.Commit`. Only
+    ///   signatures for the committed block of validators in `validators`
+    ///   are counted, a commit with an invalid signature or a validator
+    ///   signing twice is invalid.
+    /// * `chain_id` - chain id included in the canonical sign bytes.
+    /// * `trust_threshold_num / trust_threshold_den` - fraction of the total
+    ///   voting power which has to be exceeded.
+    ///
+    /// # Errors
+    ///
+    /// If `validators_len + validators_ptr`, `commit_len + commit_ptr` or
+    /// `chain_id_len + chain_id_ptr` points outside the memory or the
+    /// registers use more memory than the limit, the function returns
+    /// `MemoryAccessViolation`.
+    ///
+    /// If `chain_id` is not valid UTF-8, the function returns `BadUTF8`.
+    ///
+    /// If the trust threshold is not within `[1/3, 1]`, the function returns
+    /// `TendermintInvalidInput`.
+    ///
+    /// If the validator set holds more than `max_number_tendermint_validators`
+    /// validators, the function returns `NumberTendermintValidatorsExceeded`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes +
+    ///  verify_tendermint_commit_base +
+    ///  verify_tendermint_commit_byte * (num_validators_bytes + num_commit_bytes) +
+    ///  verify_tendermint_commit_signature * num_verified_signatures`
+    pub fn verify_tendermint_commit(
+        &mut self,
+        validators_len: u64,
+        validators_ptr: u64,
+        commit_len: u64,
+        commit_ptr: u64,
+        chain_id_len: u64,
+        chain_id_ptr: u64,
+        trust_threshold_num: u64,
+        trust_threshold_den: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(verify_tendermint_commit_base)?;
+        let trust_threshold = crate::tendermint::TrustThreshold {
+            numerator: trust_threshold_num,
+            denominator: trust_threshold_den,
+        };
+        if !trust_threshold.is_valid() {
+            return Err(HostError::TendermintInvalidInput {
+                msg: format!(
+                    "The trust threshold: {}/{}, is not within [1/3, 1]",
+                    trust_threshold_num, trust_threshold_den
+                ),
+            }
+            .into());
+        }
+        let validators = self.get_vec_from_memory_or_register(validators_ptr, validators_len)?;
+        let commit = self.get_vec_from_memory_or_register(commit_ptr, commit_len)?;
+        self.gas_counter
+            .pay_per(verify_tendermint_commit_byte, (validators.len() + commit.len()) as u64)?;
+        let chain_id = self.get_vec_from_memory_or_register(chain_id_ptr, chain_id_len)?;
+        let chain_id = String::from_utf8(chain_id).map_err(|_| HostError::BadUTF8)?;
+
+        let validators = crate::tendermint::decode_validator_set(&validators);
+        if let Some(validators) = &validators {
+            let limit = self.config.limit_config.max_number_tendermint_validators;
+            if validators.validators.len() as u64 > limit {
+                return Err(HostError::NumberTendermintValidatorsExceeded {
+                    number_of_validators: validators.validators.len() as u64,
+                    limit,
+                }
+                .into());
+            }
+        }
+        let valid = match (validators, crate::tendermint::decode_commit(&commit)) {
+            (Some(validators), Some(commit)) => {
+                let gas_counter = &mut self.gas_counter;
+                crate::tendermint::verify_commit(
+                    &validators,
+                    &commit,
+                    &chain_id,
+                    &trust_threshold,
+                    || gas_counter.pay_base(verify_tendermint_commit_signature),
+                )?
+            }
+            _ => false,
+        };
+
+        self.internal_write_register(register_id, vec![valid as u8])
+    }
+
     /// Writes random seed into the register.
     ///
     /// # Errors
@@ -1038,6 +1787,8 @@ impl<'a> VMLogic<'a> {
     /// 0 - No extra checks.
     /// 1 - Rejecting upper range.
     ///
+    /// The upper range is always rejected if `force_ecrecover_malleability_check` is set.
+    ///
     /// # Errors
     ///
     /// * If `hash_ptr`, `r_ptr`, or `s_ptr` point outside the memory or the registers use more
@@ -1107,7 +1858,9 @@ impl<'a> VMLogic<'a> {
             }));
         }
 
-        if !signature.check_signature_values(malleability_flag != 0) {
+        let reject_upper =
+            malleability_flag != 0 || self.config.limit_config.force_ecrecover_malleability_check;
+        if !signature.check_signature_values(reject_upper) {
             return Ok(false as u64);
         }
 
@@ -2054,6 +2807,71 @@ impl<'a> VMLogic<'a> {
         }
     }
 
+    /// If the current function is invoked by a callback this function returns the length in bytes
+    /// of the blob of promise result `result_idx`, without copying it into a register.
+    ///
+    /// # Returns
+    ///
+    /// * If promise result is complete and successful returns the length of its blob;
+    /// * If promise result is complete and failed or incomplete returns `0`.
+    ///
+    /// # Errors
+    ///
+    /// * If `result_idx` does not correspond to an existing result returns `InvalidPromiseResultIndex`;
+    /// * If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Cost
+    ///
+    /// `base`
+    pub fn promise_result_len(&mut self, result_idx: u64) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_result_len".to_string(),
+            }
+            .into());
+        }
+        match self
+            .promise_results
+            .get(result_idx as usize)
+            .ok_or(HostError::InvalidPromiseResultIndex { result_idx })?
+        {
+            PromiseResult::Successful(data) => Ok(data.len() as u64),
+            PromiseResult::NotReady | PromiseResult::Failed => Ok(0),
+        }
+    }
+
+    /// If the current function is invoked by a callback this function writes the 32-byte id of
+    /// the data receipt that delivered promise result `result_idx` into the register.
+    ///
+    /// # Errors
+    ///
+    /// * If `result_idx` does not correspond to an existing result returns `InvalidPromiseResultIndex`;
+    /// * If the registers exceed the memory limit returns `MemoryAccessViolation`;
+    /// * If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * 32`
+    pub fn promise_result_receipt_id(&mut self, result_idx: u64, register_id: u64) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_result_receipt_id".to_string(),
+            }
+            .into());
+        }
+        if result_idx as usize >= self.promise_results.len() {
+            return Err(HostError::InvalidPromiseResultIndex { result_idx }.into());
+        }
+        let data_id = *self
+            .context
+            .input_data_ids
+            .get(result_idx as usize)
+            .ok_or(HostError::InvalidPromiseResultIndex { result_idx })?;
+        self.internal_write_register(register_id, data_id.as_ref().to_vec())
+    }
+
     /// When promise `promise_idx` finishes executing its result is considered to be the result of
     /// the current function.
     ///
@@ -2104,7 +2922,6 @@ impl<'a> VMLogic<'a> {
     pub fn value_return(&mut self, value_len: u64, value_ptr: u64) -> Result<()> {
         self.gas_counter.pay_base(base)?;
         let return_val = self.get_vec_from_memory_or_register(value_ptr, value_len)?;
-        let mut burn_gas: Gas = 0;
         let num_bytes = return_val.len() as u64;
         if num_bytes > self.config.limit_config.max_length_returned_data {
             return Err(HostError::ReturnedValueLengthExceeded {
@@ -2113,6 +2930,53 @@ impl<'a> VMLogic<'a> {
             }
             .into());
         }
+        self.pay_for_returned_bytes(num_bytes)?;
+        self.return_data = ReturnData::Value(return_val);
+        Ok(())
+    }
+
+    /// Appends the blob of data to the return value of the contract. If the return value isn't a
+    /// blob of data yet, e.g. it was set with `promise_return`, it is replaced by this blob.
+    ///
+    /// Appending the parts of a value one by one results in the same return value as passing
+    /// the whole value to `value_return`.
+    ///
+    /// # Errors
+    ///
+    /// * If `value_len + value_ptr` exceeds the memory container or points to an unused register it
+    ///   returns `MemoryAccessViolation`.
+    /// * if the total length of the returned data exceeds `max_length_returned_data` returns
+    ///   `ReturnedValueLengthExceeded`.
+    ///
+    /// # Cost
+    /// `base + cost of reading the appended data from memory or register + dispatch&exec cost per byte of the appended data * num data receivers`
+    pub fn value_return_append(&mut self, value_len: u64, value_ptr: u64) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        let value = self.get_vec_from_memory_or_register(value_ptr, value_len)?;
+        let num_bytes = value.len() as u64;
+        let current_len = match &self.return_data {
+            ReturnData::Value(return_val) => return_val.len() as u64,
+            ReturnData::ReceiptIndex(_) | ReturnData::None => 0,
+        };
+        let length = current_len.saturating_add(num_bytes);
+        if length > self.config.limit_config.max_length_returned_data {
+            return Err(HostError::ReturnedValueLengthExceeded {
+                length,
+                limit: self.config.limit_config.max_length_returned_data,
+            }
+            .into());
+        }
+        self.pay_for_returned_bytes(num_bytes)?;
+        match &mut self.return_data {
+            ReturnData::Value(return_val) => return_val.extend_from_slice(&value),
+            return_data => *return_data = ReturnData::Value(value),
+        }
+        Ok(())
+    }
+
+    /// Pays for sending and executing `num_bytes` of returned data to every data receiver.
+    fn pay_for_returned_bytes(&mut self, num_bytes: u64) -> Result<()> {
+        let mut burn_gas: Gas = 0;
         let data_cfg = &self.fees_config.data_receipt_creation_config;
         for data_receiver in &self.context.output_data_receivers {
             let sir = data_receiver == &self.context.current_account_id;
@@ -2135,9 +2999,7 @@ impl<'a> VMLogic<'a> {
                 )
                 .ok_or(HostError::IntegerOverflow)?;
         }
-        self.gas_counter.pay_action_accumulated(burn_gas, burn_gas, ActionCosts::value_return)?;
-        self.return_data = ReturnData::Value(return_val);
-        Ok(())
+        self.gas_counter.pay_action_accumulated(burn_gas, burn_gas, ActionCosts::value_return)
     }
 
     /// Terminates the execution of the program with panic `GuestPanic`.
@@ -2252,6 +3114,42 @@ impl<'a> VMLogic<'a> {
         Err(HostError::GuestPanic { panic_msg: message }.into())
     }
 
+    /// Emits an event with the UTF-8 encoded `standard` and arbitrary `data`. Events are
+    /// recorded in the outcome separately from logs.
+    ///
+    /// # Errors
+    ///
+    /// * If `standard` or `data` extend outside the memory of the guest or point to an unused
+    ///   register returns `MemoryAccessViolation` or `InvalidRegisterId` respectively;
+    /// * If `standard` is not UTF-8 returns `BadUtf8`;
+    /// * If the total number of events will exceed the `max_number_events` returns
+    ///   `NumberOfEventsExceeded`;
+    /// * If the length of `standard` and `data` + `total_event_length` exceeds the
+    ///   `max_total_event_length` returns `TotalEventLengthExceeded`.
+    ///
+    /// # Cost
+    ///
+    /// `base + event_base + event_byte * num_bytes + utf8 decoding cost + cost of reading
+    /// standard and data from memory or register`
+    pub fn emit_event(
+        &mut self,
+        standard_len: u64,
+        standard_ptr: u64,
+        data_len: u64,
+        data_ptr: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        self.check_can_add_an_event()?;
+        let standard = self.get_vec_from_memory_or_register(standard_ptr, standard_len)?;
+        self.gas_counter.pay_base(utf8_decoding_base)?;
+        self.gas_counter.pay_per(utf8_decoding_byte, standard.len() as u64)?;
+        let standard = String::from_utf8(standard).map_err(|_| HostError::BadUTF8)?;
+        let data = self.get_vec_from_memory_or_register(data_ptr, data_len)?;
+        self.gas_counter.pay_base(event_base)?;
+        self.gas_counter.pay_per(event_byte, (standard.len() + data.len()) as u64)?;
+        self.checked_push_event(standard, data)
+    }
+
     // ###############
     // # Storage API #
     // ###############
@@ -2357,6 +3255,7 @@ impl<'a> VMLogic<'a> {
 
         self.gas_counter.add_trie_fees(&nodes_delta)?;
         self.ext.storage_set(&key, &value)?;
+        self.invalidate_iterators();
         let storage_config = &self.fees_config.storage_usage_config;
         match evicted {
             Some(old_value) => {
@@ -2453,6 +3352,113 @@ impl<'a> VMLogic<'a> {
         }
     }
 
+    /// Reads the value stored under the given key along with the trie nodes proving it against
+    /// the state root.
+    /// * If key is used copies the content of the value into the `value_register_id`, even if the
+    ///   content is zero bytes. Returns `1`;
+    /// * If key is not present then does not modify the value register. Returns `0`;
+    ///
+    /// In both cases the proof is written into `proof_register_id` as the borsh serialized
+    /// `Vec<Vec<u8>>` of trie nodes, the same format `verify_near_state_proof` takes.
+    ///
+    /// # Errors
+    ///
+    /// * If `key_len + key_ptr` exceeds the memory container or points to an unused register it
+    ///   returns `MemoryAccessViolation`;
+    /// * If `value_register_id == proof_register_id` returns `MemoryAccessViolation`;
+    /// * If the length of the key exceeds `max_length_storage_key` returns `KeyLengthExceeded`;
+    /// * If called outside of a view call while `allow_storage_proofs_in_transactions` is
+    ///   disabled returns `StorageProofsDisabled`;
+    /// * If the key was modified by this or a previous receipt since the state root returns
+    ///   `StorageProofUnavailable`.
+    ///
+    /// # Cost
+    ///
+    /// The cost of `storage_read` + `storage_read_proof_byte * num_proof_bytes + cost to write
+    /// the proof into register`.
+    pub fn storage_read_with_proof(
+        &mut self,
+        key_len: u64,
+        key_ptr: u64,
+        value_register_id: u64,
+        proof_register_id: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        if !self.context.is_view() && !self.config.limit_config.allow_storage_proofs_in_transactions
+        {
+            return Err(HostError::StorageProofsDisabled.into());
+        }
+        if value_register_id == proof_register_id {
+            return Err(HostError::MemoryAccessViolation.into());
+        }
+        self.gas_counter.pay_base(storage_read_base)?;
+        let key = self.get_vec_from_memory_or_register(key_ptr, key_len)?;
+        if key.len() as u64 > self.config.limit_config.max_length_storage_key {
+            return Err(HostError::KeyLengthExceeded {
+                length: key.len() as u64,
+                limit: self.config.limit_config.max_length_storage_key,
+            }
+            .into());
+        }
+        self.gas_counter.pay_per(storage_read_key_byte, key.len() as u64)?;
+        let nodes_before = self.ext.get_trie_nodes_count();
+        let read = self.ext.storage_get(&key);
+        let nodes_delta = self.ext.get_trie_nodes_count() - nodes_before;
+        self.gas_counter.add_trie_fees(&nodes_delta)?;
+        let read = Self::deref_value(&mut self.gas_counter, storage_read_value_byte, read?)?;
+
+        let proof = self.ext.storage_proof(&key)?.try_to_vec().expect("Failed to serialize");
+        self.gas_counter.pay_per(storage_read_proof_byte, proof.len() as u64)?;
+        self.internal_write_register(proof_register_id, proof)?;
+        match read {
+            Some(value) => {
+                self.internal_write_register(value_register_id, value)?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Writes into the `register_id` the raw trie key prefix under which the contract data of
+    /// `account_id` starting with `key` is stored, as used by the state proofs.
+    ///
+    /// # Errors
+    ///
+    /// * If `account_id_len + account_id_ptr` or `key_len + key_ptr` exceeds the memory container
+    ///   or points to an unused register it returns `MemoryAccessViolation`;
+    /// * If account is not UTF-8 encoded then returns `BadUtf8`;
+    /// * If account is not valid then returns `InvalidAccountId`;
+    /// * If the length of the key exceeds `max_length_storage_key` returns `KeyLengthExceeded`.
+    ///
+    /// # Cost
+    ///
+    /// `base + utf8_decoding_base + utf8_decoding_byte * account_id_len + write_register_base +
+    /// write_register_byte * num_bytes + cost of reading account id and key`
+    pub fn contract_data_trie_prefix(
+        &mut self,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
+        if AccountId::validate(account_id.as_ref()).is_err() {
+            return Err(HostError::InvalidAccountId.into());
+        }
+        let key = self.get_vec_from_memory_or_register(key_ptr, key_len)?;
+        if key.len() as u64 > self.config.limit_config.max_length_storage_key {
+            return Err(HostError::KeyLengthExceeded {
+                length: key.len() as u64,
+                limit: self.config.limit_config.max_length_storage_key,
+            }
+            .into());
+        }
+        let prefix = trie_key_parsers::get_raw_prefix_for_contract_data(&account_id, &key);
+        self.internal_write_register(register_id, prefix)
+    }
+
     /// Removes the value stored under the given key.
     /// * If key is used, removes the key-value from the trie and copies the content of the value
     ///   into the `register_id`, even if the content is zero bytes. Returns `1`;
@@ -2495,6 +3501,7 @@ impl<'a> VMLogic<'a> {
             Self::deref_value(&mut self.gas_counter, storage_remove_ret_value_byte, removed_ptr)?;
 
         self.ext.storage_remove(&key)?;
+        self.invalidate_iterators();
         let nodes_delta = self.ext.get_trie_nodes_count() - nodes_before;
 
         near_o11y::io_trace!(
@@ -2581,7 +3588,6 @@ impl<'a> VMLogic<'a> {
         Ok(())
     }
 
-    /// DEPRECATED
     /// Creates an iterator object inside the host. Returns the identifier that uniquely
     /// differentiates the given iterator from other iterators that can be simultaneously created.
     /// * It iterates over the keys that have the provided prefix. The order of iteration is defined
@@ -2593,18 +3599,31 @@ impl<'a> VMLogic<'a> {
     /// * If `prefix_len + prefix_ptr` exceeds the memory container it returns
     ///   `MemoryAccessViolation`.
     /// * If the length of the prefix exceeds `max_length_storage_key` returns `KeyLengthExceeded`.
+    /// * Before the `StorageIterators` protocol feature returns `Deprecated`.
     ///
     /// # Cost
     ///
-    /// `base + storage_iter_create_prefix_base + storage_iter_create_key_byte * num_prefix_bytes
-    ///  cost of reading the prefix`.
-    pub fn storage_iter_prefix(&mut self, _prefix_len: u64, _prefix_ptr: u64) -> Result<u64> {
-        Err(VMLogicError::HostError(HostError::Deprecated {
-            method_name: "storage_iter_prefix".to_string(),
-        }))
+    /// `base + storage_iter_create_prefix_base + storage_iter_create_prefix_byte * num_prefix_bytes
+    ///  + cost of reading the prefix + cost of touched trie nodes`.
+    pub fn storage_iter_prefix(&mut self, prefix_len: u64, prefix_ptr: u64) -> Result<u64> {
+        if !checked_feature!("stable", StorageIterators, self.current_protocol_version) {
+            return Err(VMLogicError::HostError(HostError::Deprecated {
+                method_name: "storage_iter_prefix".to_string(),
+            }));
+        }
+        self.gas_counter.pay_base(base)?;
+        self.gas_counter.pay_base(storage_iter_create_prefix_base)?;
+        let prefix = self.get_storage_key(prefix_ptr, prefix_len)?;
+        self.gas_counter.pay_per(storage_iter_create_prefix_byte, prefix.len() as u64)?;
+        let nodes_before = self.ext.get_trie_nodes_count();
+        let iterator_index = self.ext.storage_iter(&prefix);
+        let nodes_delta = self.ext.get_trie_nodes_count() - nodes_before;
+        self.gas_counter.add_trie_fees(&nodes_delta)?;
+        let iterator_index = iterator_index?;
+        self.valid_iterators.insert(iterator_index);
+        Ok(iterator_index)
     }
 
-    /// DEPRECATED
     /// Iterates over all key-values such that keys are between `start` and `end`, where `start` is
     /// inclusive and `end` is exclusive. Unless lexicographically `start < end`, it creates an
     /// empty iterator. Note, this definition allows for `start` or `end` keys to not actually exist
@@ -2616,24 +3635,40 @@ impl<'a> VMLogic<'a> {
     ///   an unused register it returns `MemoryAccessViolation`.
     /// * If the length of the `start` exceeds `max_length_storage_key` returns `KeyLengthExceeded`.
     /// * If the length of the `end` exceeds `max_length_storage_key` returns `KeyLengthExceeded`.
+    /// * Before the `StorageIterators` protocol feature returns `Deprecated`.
     ///
     /// # Cost
     ///
     /// `base + storage_iter_create_range_base + storage_iter_create_from_byte * num_from_bytes
-    ///  + storage_iter_create_to_byte * num_to_bytes + reading from prefix + reading to prefix`.
+    ///  + storage_iter_create_to_byte * num_to_bytes + reading from prefix + reading to prefix
+    ///  + cost of touched trie nodes`.
     pub fn storage_iter_range(
         &mut self,
-        _start_len: u64,
-        _start_ptr: u64,
-        _end_len: u64,
-        _end_ptr: u64,
+        start_len: u64,
+        start_ptr: u64,
+        end_len: u64,
+        end_ptr: u64,
     ) -> Result<u64> {
-        Err(VMLogicError::HostError(HostError::Deprecated {
-            method_name: "storage_iter_range".to_string(),
-        }))
+        if !checked_feature!("stable", StorageIterators, self.current_protocol_version) {
+            return Err(VMLogicError::HostError(HostError::Deprecated {
+                method_name: "storage_iter_range".to_string(),
+            }));
+        }
+        self.gas_counter.pay_base(base)?;
+        self.gas_counter.pay_base(storage_iter_create_range_base)?;
+        let start = self.get_storage_key(start_ptr, start_len)?;
+        let end = self.get_storage_key(end_ptr, end_len)?;
+        self.gas_counter.pay_per(storage_iter_create_from_byte, start.len() as u64)?;
+        self.gas_counter.pay_per(storage_iter_create_to_byte, end.len() as u64)?;
+        let nodes_before = self.ext.get_trie_nodes_count();
+        let iterator_index = self.ext.storage_iter_range(&start, &end);
+        let nodes_delta = self.ext.get_trie_nodes_count() - nodes_before;
+        self.gas_counter.add_trie_fees(&nodes_delta)?;
+        let iterator_index = iterator_index?;
+        self.valid_iterators.insert(iterator_index);
+        Ok(iterator_index)
     }
 
-    /// DEPRECATED
     /// Advances iterator and saves the next key and value in the register.
     /// * If iterator is not empty (after calling next it points to a key-value), copies the key
     ///   into `key_register_id` and value into `value_register_id` and returns `1`;
@@ -2644,32 +3679,71 @@ impl<'a> VMLogic<'a> {
     ///
     /// * If `key_register_id == value_register_id` returns `MemoryAccessViolation`;
     /// * If the registers exceed the memory limit returns `MemoryAccessViolation`;
-    /// * If `iterator_id` does not correspond to an existing iterator returns `InvalidIteratorId`;
-    /// * If between the creation of the iterator and calling `storage_iter_next` the range over
-    ///   which it iterates was modified returns `IteratorWasInvalidated`. Specifically, if
-    ///   `storage_write` or `storage_remove` was invoked on the key key such that:
-    ///   * in case of `storage_iter_prefix`. `key` has the given prefix and:
-    ///     * Iterator was not called next yet.
-    ///     * `next` was already called on the iterator and it is currently pointing at the `key`
-    ///       `curr` such that `curr <= key`.
-    ///   * in case of `storage_iter_range`. `start<=key<end` and:
-    ///     * Iterator was not called `next` yet.
-    ///     * `next` was already called on the iterator and it is currently pointing at the key
-    ///       `curr` such that `curr<=key<end`.
+    /// * If `iterator_id` does not correspond to an existing iterator returns `InvalidIteratorIndex`;
+    /// * If `storage_write` or `storage_remove` was called between the creation of the iterator
+    ///   and calling `storage_iter_next` returns `IteratorWasInvalidated`, regardless of the key
+    ///   which was written or removed.
+    /// * Before the `StorageIterators` protocol feature returns `Deprecated`.
     ///
     /// # Cost
     ///
     /// `base + storage_iter_next_base + storage_iter_next_key_byte * num_key_bytes + storage_iter_next_value_byte * num_value_bytes
-    ///  + writing key to register + writing value to register`.
+    ///  + writing key to register + writing value to register + cost of touched trie nodes`.
     pub fn storage_iter_next(
         &mut self,
-        _iterator_id: u64,
-        _key_register_id: u64,
-        _value_register_id: u64,
+        iterator_id: u64,
+        key_register_id: u64,
+        value_register_id: u64,
     ) -> Result<u64> {
-        Err(VMLogicError::HostError(HostError::Deprecated {
-            method_name: "storage_iter_next".to_string(),
-        }))
+        if !checked_feature!("stable", StorageIterators, self.current_protocol_version) {
+            return Err(VMLogicError::HostError(HostError::Deprecated {
+                method_name: "storage_iter_next".to_string(),
+            }));
+        }
+        self.gas_counter.pay_base(base)?;
+        self.gas_counter.pay_base(storage_iter_next_base)?;
+        if self.invalid_iterators.contains(&iterator_id) {
+            return Err(HostError::IteratorWasInvalidated { iterator_index: iterator_id }.into());
+        } else if !self.valid_iterators.contains(&iterator_id) {
+            return Err(HostError::InvalidIteratorIndex { iterator_index: iterator_id }.into());
+        }
+        if key_register_id == value_register_id {
+            return Err(HostError::MemoryAccessViolation.into());
+        }
+
+        let nodes_before = self.ext.get_trie_nodes_count();
+        let next = self.ext.storage_iter_next(iterator_id);
+        let nodes_delta = self.ext.get_trie_nodes_count() - nodes_before;
+        self.gas_counter.add_trie_fees(&nodes_delta)?;
+        match next? {
+            Some((key, value)) => {
+                self.gas_counter.pay_per(storage_iter_next_key_byte, key.len() as u64)?;
+                self.gas_counter.pay_per(storage_iter_next_value_byte, value.len() as u64)?;
+                self.internal_write_register(key_register_id, key)?;
+                self.internal_write_register(value_register_id, value)?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Reads a key or key prefix for one of the storage iterators, checking it against
+    /// `max_length_storage_key`.
+    fn get_storage_key(&mut self, ptr: u64, len: u64) -> Result<Vec<u8>> {
+        let key = self.get_vec_from_memory_or_register(ptr, len)?;
+        if key.len() as u64 > self.config.limit_config.max_length_storage_key {
+            return Err(HostError::KeyLengthExceeded {
+                length: key.len() as u64,
+                limit: self.config.limit_config.max_length_storage_key,
+            }
+            .into());
+        }
+        Ok(key)
+    }
+
+    /// Any write or removal can change what the created iterators return next.
+    fn invalidate_iterators(&mut self) {
+        self.invalid_iterators.extend(self.valid_iterators.drain());
     }
 
     /// Computes the outcome of the execution.
@@ -2702,6 +3776,7 @@ impl<'a> VMLogic<'a> {
             burnt_gas,
             used_gas,
             logs: self.logs,
+            events: self.events,
             profile,
             action_receipts: self.receipt_manager.action_receipts,
         }
@@ -2796,6 +3871,8 @@ pub struct VMOutcome {
     pub burnt_gas: Gas,
     pub used_gas: Gas,
     pub logs: Vec<String>,
+    /// Events emitted by the contract, as pairs of standard and data.
+    pub events: Vec<(String, Vec<u8>)>,
     /// Data collected from making a contract call
     pub profile: ProfileData,
     pub action_receipts: Vec<(AccountId, ReceiptMetadata)>,
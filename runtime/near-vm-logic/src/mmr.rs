@@ -0,0 +1,185 @@
+//! Verification of merkle mountain range inclusion proofs and BEEFY
+//! commitments, as used by Polkadot's BEEFY light client protocol.
+//!
+//! The MMR layout and proof format are those of the `ckb-merkle-mountain-range`
+//! crate, which Substrate's MMR pallet is built on, with keccak256 as the node
+//! hash.
+use near_crypto::Secp256K1Signature;
+
+pub(crate) const HASH_SIZE: usize = 32;
+pub(crate) const PUBLIC_KEY_SIZE: usize = 64;
+/// Authority index as u32 little endian followed by a recoverable signature.
+pub(crate) const BEEFY_SIGNATURE_SIZE: usize = 4 + 65;
+
+type Hash = [u8; HASH_SIZE];
+
+pub(crate) fn keccak256(data: &[u8]) -> Hash {
+    use sha3::Digest;
+    sha3::Keccak256::digest(data).into()
+}
+
+fn merge(left: &Hash, right: &Hash) -> Hash {
+    keccak256(&[left.as_slice(), right.as_slice()].concat())
+}
+
+/// Height of the node at `pos` in its tree, leaves are at height 0.
+fn pos_height_in_tree(mut pos: u64) -> u32 {
+    pos += 1;
+    let all_ones = |num: u64| num != 0 && num.count_zeros() == num.leading_zeros();
+    let jump_left = |pos: u64| {
+        let bit_length = 64 - pos.leading_zeros();
+        let most_significant_bits = 1 << (bit_length - 1);
+        pos - (most_significant_bits - 1)
+    };
+    while !all_ones(pos) {
+        pos = jump_left(pos)
+    }
+    64 - pos.leading_zeros() - 1
+}
+
+fn leaf_index_to_pos(index: u64) -> u64 {
+    // Each tree of `2^h` leaves to the left of the leaf adds `2^h - 1` inner
+    // nodes.
+    2 * index - u64::from(index.count_ones())
+}
+
+fn leaf_count_to_mmr_size(leaf_count: u64) -> u64 {
+    2 * leaf_count - u64::from(leaf_count.count_ones())
+}
+
+/// Positions of the peaks of an MMR of `mmr_size` nodes, left to right.
+fn get_peaks(mmr_size: u64) -> Vec<u64> {
+    let mut peaks = Vec::new();
+    let mut offset = 0;
+    let mut remaining = mmr_size;
+    while remaining > 0 {
+        // The largest perfect tree fitting in the remaining nodes.
+        let height = 63 - (remaining + 1).leading_zeros();
+        let size = (1u64 << height) - 1;
+        offset += size;
+        peaks.push(offset - 1);
+        remaining -= size;
+    }
+    peaks
+}
+
+/// Verifies that `leaf` is the `leaf_index`-th leaf of the MMR with
+/// `leaf_count` leaves and the given `root`.
+///
+/// `proof` holds the sibling hashes from the leaf up to its peak, then the
+/// peaks to the left of it, then the bagged peaks to the right of it.
+pub(crate) fn verify_proof(
+    root: &Hash,
+    leaf: &[u8],
+    proof: &[Hash],
+    leaf_index: u64,
+    leaf_count: u64,
+) -> bool {
+    if leaf_index >= leaf_count || leaf_count > u64::MAX / 2 {
+        return false;
+    }
+    let mmr_size = leaf_count_to_mmr_size(leaf_count);
+    let leaf_pos = leaf_index_to_pos(leaf_index);
+    let mut proof = proof.iter();
+
+    let mut peak_hashes = Vec::new();
+    let mut leaf = Some((leaf_pos, keccak256(leaf)));
+    for peak_pos in get_peaks(mmr_size) {
+        let peak_hash = match leaf {
+            Some((pos, hash)) if pos <= peak_pos => {
+                leaf = None;
+                match peak_root(pos, hash, peak_pos, &mut proof) {
+                    Some(hash) => hash,
+                    None => return false,
+                }
+            }
+            // Leaf is under a peak further right.
+            Some(_) => match proof.next() {
+                Some(hash) => *hash,
+                None => return false,
+            },
+            // Peaks to the right of the leaf are bagged into one hash.
+            None => match proof.next() {
+                Some(hash) => *hash,
+                None => break,
+            },
+        };
+        peak_hashes.push(peak_hash);
+    }
+    if let Some(rhs_peaks) = proof.next() {
+        peak_hashes.push(*rhs_peaks);
+    }
+    if proof.next().is_some() {
+        return false;
+    }
+
+    let mut bagged = match peak_hashes.pop() {
+        Some(hash) => hash,
+        None => return false,
+    };
+    while let Some(left) = peak_hashes.pop() {
+        bagged = merge(&bagged, &left);
+    }
+    bagged == *root
+}
+
+fn peak_root<'a>(
+    mut pos: u64,
+    mut hash: Hash,
+    peak_pos: u64,
+    proof: &mut impl Iterator<Item = &'a Hash>,
+) -> Option<Hash> {
+    let mut height = 0;
+    while pos < peak_pos {
+        let sibling = proof.next()?;
+        if pos_height_in_tree(pos + 1) > height {
+            // `pos` is a right child, its parent follows it.
+            hash = merge(sibling, &hash);
+            pos += 1;
+        } else {
+            hash = merge(&hash, sibling);
+            pos += 2 << height;
+        }
+        height += 1;
+    }
+    (pos == peak_pos).then(|| hash)
+}
+
+/// Checks that more than 2/3 of `authorities` signed `commitment` and that
+/// all provided signatures are valid.
+///
+/// `signatures` are `BEEFY_SIGNATURE_SIZE` byte entries over the keccak256
+/// hash of `commitment`, each authority may sign at most once.
+pub(crate) fn verify_beefy_commitment(
+    authorities: &[[u8; PUBLIC_KEY_SIZE]],
+    commitment: &[u8],
+    signatures: &[[u8; BEEFY_SIGNATURE_SIZE]],
+) -> bool {
+    let message = keccak256(commitment);
+    let mut signed = vec![false; authorities.len()];
+    for entry in signatures {
+        let (index, signature) = entry.split_at(4);
+        let index = u32::from_le_bytes(index.try_into().unwrap()) as usize;
+        let authority = match authorities.get(index) {
+            Some(authority) if !signed[index] => authority,
+            _ => return false,
+        };
+        let mut signature = <[u8; 65]>::try_from(signature).unwrap();
+        // Ethereum style recovery ids.
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        if signature[64] > 3 {
+            return false;
+        }
+        let signature = Secp256K1Signature::from(signature);
+        if !signature.check_signature_values(true) {
+            return false;
+        }
+        match signature.recover(message) {
+            Ok(public_key) if public_key.as_ref() == authority.as_slice() => signed[index] = true,
+            _ => return false,
+        }
+    }
+    signatures.len() * 3 > authorities.len() * 2
+}
@@ -1,7 +1,7 @@
 use crate::{External, ValuePtr};
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::types::TrieNodesCount;
-use near_primitives_core::types::{AccountId, Balance, Gas};
+use near_primitives_core::types::{AccountId, Balance, BlockHeight, Gas};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -10,7 +10,12 @@ use std::collections::HashMap;
 pub struct MockedExternal {
     pub fake_trie: HashMap<Vec<u8>, Vec<u8>>,
     pub validators: HashMap<AccountId, Balance>,
+    pub validator_public_keys: HashMap<AccountId, near_crypto::PublicKey>,
+    pub block_hashes: HashMap<BlockHeight, CryptoHash>,
     data_count: u64,
+    /// Iterators hold a sorted copy of the key-values they iterate over.
+    iterators: HashMap<u64, std::vec::IntoIter<(Vec<u8>, Vec<u8>)>>,
+    next_iterator_index: u64,
 }
 
 pub struct MockedValuePtr {
@@ -40,10 +45,25 @@ impl MockedExternal {
     pub fn new() -> Self {
         Self::default()
     }
+
+    fn create_iterator(&mut self, filter: impl Fn(&[u8]) -> bool) -> u64 {
+        let mut key_values: Vec<_> = self
+            .fake_trie
+            .iter()
+            .filter(|(key, _)| filter(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        key_values.sort();
+        let iterator_index = self.next_iterator_index;
+        self.next_iterator_index += 1;
+        self.iterators.insert(iterator_index, key_values.into_iter());
+        iterator_index
+    }
 }
 
 use crate::dependencies::Result;
 use crate::types::PublicKey;
+use near_vm_errors::HostError;
 
 impl External for MockedExternal {
     fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
@@ -72,6 +92,26 @@ impl External for MockedExternal {
         Ok(self.fake_trie.contains_key(key))
     }
 
+    fn storage_iter(&mut self, prefix: &[u8]) -> Result<u64> {
+        Ok(self.create_iterator(|key| key.starts_with(prefix)))
+    }
+
+    fn storage_iter_range(&mut self, start: &[u8], end: &[u8]) -> Result<u64> {
+        Ok(self.create_iterator(|key| start <= key && key < end))
+    }
+
+    fn storage_iter_next(&mut self, iterator_idx: u64) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match self.iterators.get_mut(&iterator_idx) {
+            Some(iterator) => Ok(iterator.next()),
+            None => Err(HostError::InvalidIteratorIndex { iterator_index: iterator_idx }.into()),
+        }
+    }
+
+    fn storage_proof(&self, key: &[u8]) -> Result<Vec<Vec<u8>>> {
+        // There are no trie nodes to prove the value with, the mocked proof is the value itself.
+        Ok(self.fake_trie.get(key).cloned().into_iter().collect())
+    }
+
     fn generate_data_id(&mut self) -> CryptoHash {
         // Generates some hash for the data ID to receive data. This hash should not be functionally
         // used in any mocked contexts.
@@ -88,9 +128,20 @@ impl External for MockedExternal {
         Ok(self.validators.get(account_id).cloned())
     }
 
+    fn validator_public_key(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<Option<near_crypto::PublicKey>> {
+        Ok(self.validator_public_keys.get(account_id).cloned())
+    }
+
     fn validator_total_stake(&self) -> Result<Balance> {
         Ok(self.validators.values().sum())
     }
+
+    fn get_block_hash(&self, block_height: BlockHeight) -> Result<Option<CryptoHash>> {
+        Ok(self.block_hashes.get(&block_height).cloned())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
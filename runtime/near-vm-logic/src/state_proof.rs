@@ -0,0 +1,173 @@
+//! Verification of state proofs made of `RawTrieNodeWithSize` nodes, as
+//! stored in the trie by `near-store`.
+//!
+//! Nodes are decoded with `near_primitives::trie_encoding`, the decoder the
+//! trie itself uses.
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::trie_encoding::{
+    decode_nibbles, key_to_nibbles, step, RawTrieNode, RawTrieNodeWithSize, Step,
+};
+use std::collections::HashMap;
+
+/// Length and hash of a value stored in the trie.
+type ValueRef = (u32, CryptoHash);
+
+/// Outcome of verifying a state proof, as returned to contracts by
+/// `verify_near_state_proof_status`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProofStatus {
+    Verified = 0,
+    /// The key is present with another value than the expected one, or is
+    /// present while expected to be absent.
+    ValueMismatch = 1,
+    /// The key is absent while a value was expected.
+    KeyAbsent = 2,
+    /// The proof fails to decode or lacks a node on the path to the key.
+    MalformedProof = 3,
+    /// None of the nodes in the proof hashes to the root.
+    RootMismatch = 4,
+}
+
+/// Looks up the value stored under `key`, `None` if `key` is absent.
+fn lookup(
+    root: &CryptoHash,
+    nodes: &[Vec<u8>],
+    key: &[u8],
+) -> Result<Option<ValueRef>, ProofStatus> {
+    if *root == CryptoHash::default() {
+        return Ok(None);
+    }
+    let nodes: HashMap<CryptoHash, &[u8]> =
+        nodes.iter().map(|node| (hash(node), node.as_slice())).collect();
+
+    let key = key_to_nibbles(key);
+    let mut key = key.as_slice();
+    let mut node_hash = *root;
+    loop {
+        let node = match nodes.get(&node_hash) {
+            Some(node) => node,
+            None if node_hash == *root => return Err(ProofStatus::RootMismatch),
+            None => return Err(ProofStatus::MalformedProof),
+        };
+        match step(node, key).map_err(|_| ProofStatus::MalformedProof)? {
+            Step::End(stored) => return Ok(stored),
+            Step::Child(child, rest) => {
+                key = rest;
+                node_hash = child;
+            }
+        }
+    }
+}
+
+/// Verifies that `key` maps to `value` in the trie with the given `root`, or
+/// that `key` is absent if `value` is `None`.
+///
+/// `nodes` may contain the nodes in any order as well as unrelated nodes and
+/// values, as recorded by `Trie::recording_reads`. A proof which is missing
+/// a node on the path to `key` or contains malformed nodes is invalid.
+pub(crate) fn verify(
+    root: &CryptoHash,
+    nodes: &[Vec<u8>],
+    key: &[u8],
+    value: Option<&[u8]>,
+) -> ProofStatus {
+    let stored = match lookup(root, nodes, key) {
+        Ok(stored) => stored,
+        Err(status) => return status,
+    };
+    match (stored, value) {
+        (None, None) => ProofStatus::Verified,
+        (None, Some(_)) => ProofStatus::KeyAbsent,
+        (Some((length, value_hash)), Some(value))
+            if length as usize == value.len() && value_hash == hash(value) =>
+        {
+            ProofStatus::Verified
+        }
+        (Some(_), _) => ProofStatus::ValueMismatch,
+    }
+}
+
+/// Verifies that the raw trie `key` maps to `value` in the trie with the given
+/// `root`, or that `key` is absent if `value` is `None`. Works for any trie
+/// key, e.g. for the proof of an account record.
+pub fn verify_state_proof(
+    root: &CryptoHash,
+    nodes: &[Vec<u8>],
+    key: &[u8],
+    value: Option<&[u8]>,
+) -> bool {
+    verify(root, nodes, key, value) == ProofStatus::Verified
+}
+
+/// Verifies that the raw trie `key` maps to a value with the given length and
+/// hash in the trie with the given `root`, e.g. for a value elided from a
+/// `view_state` result.
+pub fn verify_state_proof_value_hash(
+    root: &CryptoHash,
+    nodes: &[Vec<u8>],
+    key: &[u8],
+    length: u64,
+    value_hash: &CryptoHash,
+) -> bool {
+    matches!(
+        lookup(root, nodes, key),
+        Ok(Some((stored_length, stored_hash)))
+            if u64::from(stored_length) == length && stored_hash == *value_hash
+    )
+}
+
+/// Verifies that no key in the trie with the given `root` starts with `prefix`,
+/// e.g. that an account has no contract data under a prefix.
+///
+/// `nodes` are the nodes on the path to `prefix` up to where the trie diverges
+/// from it, such as the proof of an empty `view_state` result. As in `verify`,
+/// they may come in any order and may include unrelated nodes.
+pub fn verify_prefix_absence(root: &CryptoHash, nodes: &[Vec<u8>], prefix: &[u8]) -> bool {
+    if *root == CryptoHash::default() {
+        return true;
+    }
+    let nodes: HashMap<CryptoHash, &[u8]> =
+        nodes.iter().map(|node| (hash(node), node.as_slice())).collect();
+
+    let prefix = key_to_nibbles(prefix);
+    let mut prefix = prefix.as_slice();
+    let mut node_hash = *root;
+    loop {
+        let node = match nodes.get(&node_hash).map(|node| RawTrieNodeWithSize::decode(node)) {
+            Some(Ok(node)) => node.node,
+            _ => return false,
+        };
+        match node {
+            RawTrieNode::Leaf(leaf_key, _, _) => {
+                return match decode_nibbles(&leaf_key) {
+                    Ok((leaf_key, _)) => !leaf_key.starts_with(prefix),
+                    Err(_) => false,
+                };
+            }
+            RawTrieNode::Extension(extension_key, child) => {
+                let extension_key = match decode_nibbles(&extension_key) {
+                    Ok((extension_key, _)) => extension_key,
+                    Err(_) => return false,
+                };
+                match prefix.strip_prefix(extension_key.as_slice()) {
+                    Some(rest) => {
+                        prefix = rest;
+                        node_hash = child;
+                    }
+                    None => return !extension_key.starts_with(prefix),
+                }
+            }
+            RawTrieNode::Branch(children, _) => match prefix.split_first() {
+                // Every key below the branch starts with the prefix.
+                None => return false,
+                Some((nibble, rest)) => match children[*nibble as usize] {
+                    Some(child) => {
+                        prefix = rest;
+                        node_hash = child;
+                    }
+                    None => return true,
+                },
+            },
+        }
+    }
+}
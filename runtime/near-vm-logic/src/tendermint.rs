@@ -0,0 +1,251 @@
+//! Verification of Tendermint commits against a trusted validator set, as
+//! done by the ICS-07 Tendermint light client.
+//!
+//! The protobuf messages mirror those of the `tendermint.types` and
+//! `tendermint.crypto` packages, keeping only the fields needed to verify a
+//! commit. Unknown fields are skipped when decoding.
+use near_crypto::{ED25519PublicKey, KeyType, PublicKey, Signature};
+use near_vm_errors::VMLogicError;
+use prost::Message;
+use std::collections::{HashMap, HashSet};
+
+/// Length of a validator address, the truncated sha256 of its public key.
+const ADDRESS_SIZE: usize = 20;
+/// `SignedMsgType::SIGNED_MSG_TYPE_PRECOMMIT`.
+const PRECOMMIT_TYPE: i32 = 2;
+/// `BlockIDFlag::BLOCK_ID_FLAG_COMMIT`, signatures for nil or absent
+/// validators don't count towards the voting power of a commit.
+const BLOCK_ID_FLAG_COMMIT: i32 = 2;
+/// Tendermint's `MaxTotalVotingPower`, which also keeps the threshold
+/// arithmetic below from overflowing.
+const MAX_TOTAL_VOTING_POWER: u128 = (i64::MAX / 8) as u128;
+
+type Address = [u8; ADDRESS_SIZE];
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ValidatorSet {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) validators: Vec<Validator>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Validator {
+    #[prost(message, optional, tag = "2")]
+    pub_key: Option<ValidatorPublicKey>,
+    #[prost(int64, tag = "3")]
+    voting_power: i64,
+}
+
+/// `tendermint.crypto.PublicKey`, a oneof of which only ed25519 keys are
+/// supported.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ValidatorPublicKey {
+    #[prost(bytes = "vec", optional, tag = "1")]
+    ed25519: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Commit {
+    #[prost(int64, tag = "1")]
+    height: i64,
+    #[prost(int32, tag = "2")]
+    round: i32,
+    #[prost(message, optional, tag = "3")]
+    block_id: Option<BlockId>,
+    #[prost(message, repeated, tag = "4")]
+    signatures: Vec<CommitSig>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BlockId {
+    #[prost(bytes = "vec", tag = "1")]
+    hash: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    part_set_header: Option<PartSetHeader>,
+}
+
+/// Encodes the same as `CanonicalPartSetHeader`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct PartSetHeader {
+    #[prost(uint32, tag = "1")]
+    total: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct CommitSig {
+    #[prost(int32, tag = "1")]
+    block_id_flag: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    validator_address: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    timestamp: Option<Timestamp>,
+    #[prost(bytes = "vec", tag = "4")]
+    signature: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Timestamp {
+    #[prost(int64, tag = "1")]
+    seconds: i64,
+    #[prost(int32, tag = "2")]
+    nanos: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct CanonicalVote {
+    #[prost(int32, tag = "1")]
+    r#type: i32,
+    #[prost(sfixed64, tag = "2")]
+    height: i64,
+    #[prost(sfixed64, tag = "3")]
+    round: i64,
+    #[prost(message, optional, tag = "4")]
+    block_id: Option<CanonicalBlockId>,
+    #[prost(message, optional, tag = "5")]
+    timestamp: Option<Timestamp>,
+    #[prost(string, tag = "6")]
+    chain_id: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct CanonicalBlockId {
+    #[prost(bytes = "vec", tag = "1")]
+    hash: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    part_set_header: Option<PartSetHeader>,
+}
+
+/// Trust threshold `numerator / denominator` of the voting power that has to
+/// sign a commit.
+pub(crate) struct TrustThreshold {
+    pub(crate) numerator: u64,
+    pub(crate) denominator: u64,
+}
+
+impl TrustThreshold {
+    /// Tendermint only accepts thresholds in `[1/3, 1]`.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.denominator != 0
+            && self.numerator <= self.denominator
+            && u128::from(self.numerator) * 3 >= u128::from(self.denominator)
+    }
+}
+
+/// Decodes a protobuf encoded `ValidatorSet`.
+///
+/// Returns `None` if the set can't be decoded, such a set never verifies a
+/// commit.
+pub(crate) fn decode_validator_set(data: &[u8]) -> Option<ValidatorSet> {
+    ValidatorSet::decode(data).ok()
+}
+
+/// Decodes a protobuf encoded `Commit`.
+///
+/// Returns `None` if the commit can't be decoded, such a commit is never
+/// valid.
+pub(crate) fn decode_commit(data: &[u8]) -> Option<Commit> {
+    Commit::decode(data).ok()
+}
+
+fn address(public_key: &[u8]) -> Address {
+    use sha2::Digest;
+    let hash = sha2::Sha256::digest(public_key);
+    let mut address = [0u8; ADDRESS_SIZE];
+    address.copy_from_slice(&hash[..ADDRESS_SIZE]);
+    address
+}
+
+/// Length prefixed `CanonicalVote` signed by the validator of `sig`.
+fn sign_bytes(commit: &Commit, sig: &CommitSig, chain_id: &str) -> Vec<u8> {
+    let block_id = commit.block_id.as_ref().map(|block_id| CanonicalBlockId {
+        hash: block_id.hash.clone(),
+        part_set_header: Some(block_id.part_set_header.clone().unwrap_or_default()),
+    });
+    CanonicalVote {
+        r#type: PRECOMMIT_TYPE,
+        height: commit.height,
+        round: i64::from(commit.round),
+        block_id,
+        timestamp: sig.timestamp.clone(),
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+/// Verifies that validators of `validator_set` holding more than
+/// `trust_threshold` of its voting power signed `commit`, as done by
+/// `VerifyCommitLightTrusting`.
+///
+/// Signatures of validators missing from the set are skipped, the commit is
+/// invalid if any checked signature is invalid or a validator signed twice.
+/// `pay_signature` is called before each signature verification and
+/// verification stops as soon as the threshold is reached.
+pub(crate) fn verify_commit(
+    validator_set: &ValidatorSet,
+    commit: &Commit,
+    chain_id: &str,
+    trust_threshold: &TrustThreshold,
+    mut pay_signature: impl FnMut() -> Result<(), VMLogicError>,
+) -> Result<bool, VMLogicError> {
+    let mut total_voting_power: u128 = 0;
+    let mut validators: HashMap<Address, (ED25519PublicKey, u128)> = HashMap::new();
+    for validator in validator_set.validators.iter() {
+        let voting_power = match u128::try_from(validator.voting_power) {
+            Ok(voting_power) => voting_power,
+            Err(_) => return Ok(false),
+        };
+        total_voting_power += voting_power;
+        if total_voting_power > MAX_TOTAL_VOTING_POWER {
+            return Ok(false);
+        }
+        let public_key = match validator.pub_key.as_ref().and_then(|key| key.ed25519.as_ref()) {
+            Some(public_key) => public_key,
+            // Validators with other keys still count towards the total.
+            None => continue,
+        };
+        let public_key = match ED25519PublicKey::try_from(public_key.as_slice()) {
+            Ok(public_key) => public_key,
+            Err(_) => return Ok(false),
+        };
+        if validators.insert(address(&public_key.0), (public_key, voting_power)).is_some() {
+            return Ok(false);
+        }
+    }
+    let needed = total_voting_power * u128::from(trust_threshold.numerator);
+    let denominator = u128::from(trust_threshold.denominator);
+
+    let mut tallied_voting_power: u128 = 0;
+    let mut seen: HashSet<Address> = HashSet::new();
+    for sig in commit.signatures.iter() {
+        if sig.block_id_flag != BLOCK_ID_FLAG_COMMIT {
+            continue;
+        }
+        let address: Address = match sig.validator_address.as_slice().try_into() {
+            Ok(address) => address,
+            Err(_) => return Ok(false),
+        };
+        let (public_key, voting_power) = match validators.get(&address) {
+            Some(validator) => validator,
+            None => continue,
+        };
+        if !seen.insert(address) {
+            return Ok(false);
+        }
+        pay_signature()?;
+        let signature = match Signature::from_parts(KeyType::ED25519, &sig.signature) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+        let sign_bytes = sign_bytes(commit, sig, chain_id);
+        if !signature.verify(&sign_bytes, &PublicKey::ED25519(public_key.clone())) {
+            return Ok(false);
+        }
+        tallied_voting_power += voting_power;
+        if tallied_voting_power * denominator > needed {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
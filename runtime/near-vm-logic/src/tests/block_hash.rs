@@ -0,0 +1,60 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts};
+use near_primitives::hash::CryptoHash;
+
+#[test]
+fn test_block_hash() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let lookback = logic_builder.config.limit_config.max_block_hash_lookback;
+    let current_height = 1000;
+    let block_hash = |height: u64| CryptoHash::hash_bytes(&height.to_le_bytes());
+    for height in current_height - lookback - 1..=current_height {
+        logic_builder.ext.block_hashes.insert(height, block_hash(height));
+    }
+    // The block at this height was skipped.
+    logic_builder.ext.block_hashes.remove(&(current_height - 2));
+    let mut context = get_context(vec![], false);
+    context.block_index = current_height;
+    let mut logic = logic_builder.build(context);
+
+    assert_eq!(logic.block_hash(current_height - 1, 0), Ok(1));
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::block_hash_base: 1,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 32,
+    });
+    let res = [0u8; 32];
+    logic.read_register(0, res.as_ptr() as _).unwrap();
+    assert_eq!(res, block_hash(current_height - 1).0);
+
+    assert_eq!(logic.block_hash(current_height - lookback, 1), Ok(1));
+    logic.read_register(1, res.as_ptr() as _).unwrap();
+    assert_eq!(res, block_hash(current_height - lookback).0);
+
+    assert_eq!(logic.block_hash(current_height - 2, 2), Ok(0));
+    assert_eq!(logic.register_len(2), Ok(u64::MAX));
+}
+
+#[test]
+fn test_block_hash_out_of_window() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let lookback = logic_builder.config.limit_config.max_block_hash_lookback;
+    let current_height = 1000;
+    let block_hash = |height: u64| CryptoHash::hash_bytes(&height.to_le_bytes());
+    for height in 0..=current_height + 1 {
+        logic_builder.ext.block_hashes.insert(height, block_hash(height));
+    }
+    let mut context = get_context(vec![], true);
+    context.block_index = current_height;
+    let mut logic = logic_builder.build(context);
+
+    // The hash of the current block isn't known while it is being executed.
+    assert_eq!(logic.block_hash(current_height, 0), Ok(0));
+    assert_eq!(logic.block_hash(current_height + 1, 0), Ok(0));
+    assert_eq!(logic.block_hash(current_height - lookback - 1, 0), Ok(0));
+    assert_eq!(logic.block_hash(0, 0), Ok(0));
+    assert_eq!(logic.register_len(0), Ok(u64::MAX));
+}
@@ -0,0 +1,142 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vectors::load_vectors;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts, VMLogic};
+use near_vm_errors::{HostError, VMLogicError};
+use serde::Deserialize;
+
+/// Proofs over a four leaf tree, in the format of the ics23 `testdata`
+/// vectors. An empty `value` denotes a non-existence proof.
+#[derive(Deserialize)]
+struct Ics23Test {
+    name: String,
+    spec_id: u64,
+    #[serde(with = "hex::serde")]
+    root: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    proof: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    key: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    value: Vec<u8>,
+}
+
+fn ics23_tests() -> Vec<Ics23Test> {
+    load_vectors("ics23-tests.json")
+}
+
+fn verify(
+    logic: &mut VMLogic,
+    spec_id: u64,
+    root: &[u8],
+    proof: &[u8],
+    key: &[u8],
+    value: &[u8],
+) -> Result<u8, VMLogicError> {
+    logic.verify_ics23_proof(
+        spec_id,
+        root.len() as _,
+        root.as_ptr() as _,
+        proof.len() as _,
+        proof.as_ptr() as _,
+        key.len() as _,
+        key.as_ptr() as _,
+        value.len() as _,
+        value.as_ptr() as _,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_verify_ics23_proof() {
+    for Ics23Test { name, spec_id, root, proof, key, value } in ics23_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+
+        assert_eq!(verify(&mut logic, spec_id, &root, &proof, &key, &value), Ok(1), "{name}");
+
+        // Every existence proof in the fixtures is two levels deep.
+        let exist_proofs = if name.ends_with("nonexist_middle") { 2 } else { 1 };
+        let inputs = if value.is_empty() { 3 } else { 4 };
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: inputs,
+            ExtCosts::read_memory_byte: (root.len() + proof.len() + key.len() + value.len()) as u64,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 1,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 1,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 1,
+            ExtCosts::verify_ics23_proof_base: 1,
+            ExtCosts::verify_ics23_proof_byte: proof.len() as u64,
+            ExtCosts::verify_ics23_proof_inner_op: 2 * exist_proofs,
+        });
+        reset_costs_counter();
+    }
+}
+
+#[test]
+fn test_verify_ics23_proof_invalid() {
+    for Ics23Test { name, spec_id, root, proof, key, value } in ics23_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+
+        let mut bad_root = root.clone();
+        bad_root[0] ^= 1;
+        assert_eq!(verify(&mut logic, spec_id, &bad_root, &proof, &key, &value), Ok(0), "{name}");
+
+        // Leaf ops of the Tendermint and SMT specs encode lengths differently.
+        let other_spec = if spec_id == 2 { 1 } else { 2 };
+        assert_eq!(verify(&mut logic, other_spec, &root, &proof, &key, &value), Ok(0), "{name}");
+
+        if value.is_empty() {
+            // A non-existence proof doesn't prove existence of any value.
+            assert_eq!(verify(&mut logic, spec_id, &root, &proof, &key, b"x"), Ok(0), "{name}");
+        } else {
+            assert_eq!(verify(&mut logic, spec_id, &root, &proof, &key, b"x"), Ok(0), "{name}");
+            assert_eq!(verify(&mut logic, spec_id, &root, &proof, &key, &[]), Ok(0), "{name}");
+        }
+
+        // Truncated proofs fail to decode, which is not an error.
+        let truncated = &proof[..proof.len() / 2];
+        assert_eq!(verify(&mut logic, spec_id, &root, truncated, &key, &value), Ok(0), "{name}");
+    }
+}
+
+#[test]
+fn test_verify_ics23_proof_invalid_spec() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let test = &ics23_tests()[0];
+    assert_eq!(
+        verify(&mut logic, 3, &test.root, &test.proof, &test.key, &test.value),
+        Err(HostError::Ics23InvalidSpecId { spec_id: 3 }.into())
+    );
+}
+
+#[test]
+fn test_verify_ics23_proof_size_limit() {
+    let test = &ics23_tests()[0];
+    let limit = test.proof.len() as u64;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_ics23_proof_size = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, test.spec_id, &test.root, &test.proof, &test.key, &test.value),
+        Ok(1)
+    );
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_ics23_proof_size = limit - 1;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, test.spec_id, &test.root, &test.proof, &test.key, &test.value),
+        Err(HostError::Ics23ProofTooLarge { size: limit, limit: limit - 1 }.into())
+    );
+}
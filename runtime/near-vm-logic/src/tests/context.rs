@@ -1,5 +1,7 @@
 use crate::tests::vm_logic_builder::VMLogicBuilder;
 use crate::VMContext;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::EpochId;
 
 pub fn create_context() -> VMContext {
     VMContext {
@@ -11,14 +13,18 @@ pub fn create_context() -> VMContext {
         block_index: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId(CryptoHash::hash_bytes(b"epoch")),
         account_balance: 2u128,
         account_locked_balance: 1u128,
         storage_usage: 12,
         attached_deposit: 2u128,
         prepaid_gas: 10_u64.pow(14),
+        gas_price: 100_000_000,
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        input_data_ids: vec![],
     }
 }
 
@@ -88,6 +94,10 @@ decl_test_bytes!(test_random_seed, random_seed, create_context().random_seed.as_
 
 decl_test_bytes!(test_input, input, create_context().input.as_slice());
 
+decl_test_bytes!(test_epoch_id, epoch_id, create_context().epoch_id.0.as_bytes());
+decl_test_bytes!(test_chain_id, chain_id, create_context().chain_id.as_bytes());
+decl_test_bytes!(test_gas_price, gas_price, &create_context().gas_price.to_le_bytes());
+
 decl_test_u64!(test_block_index, block_index, create_context().block_index);
 decl_test_u64!(test_block_timestamp, block_timestamp, create_context().block_timestamp);
 decl_test_u64!(test_storage_usage, storage_usage, create_context().storage_usage);
@@ -1,4 +1,5 @@
 use crate::{VMContext, VMLimitConfig};
+use near_primitives::types::EpochId;
 use near_primitives_core::config::ViewConfig;
 
 pub fn get_context(input: Vec<u8>, is_view: bool) -> VMContext {
@@ -11,16 +12,24 @@ pub fn get_context(input: Vec<u8>, is_view: bool) -> VMContext {
         block_index: 0,
         block_timestamp: 0,
         epoch_height: 0,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId::default(),
         account_balance: 100,
         storage_usage: 0,
         account_locked_balance: 0,
         attached_deposit: 10,
         prepaid_gas: 10_u64.pow(14),
+        gas_price: 100_000_000,
         random_seed: vec![],
         view_config: match is_view {
-            true => Some(ViewConfig { max_gas_burnt: VMLimitConfig::test().max_gas_burnt }),
+            true => Some(ViewConfig {
+                max_gas_burnt: VMLimitConfig::test().max_gas_burnt,
+                max_number_logs: None,
+                max_total_log_length: None,
+            }),
             false => None,
         },
         output_data_receivers: vec![],
+        input_data_ids: vec![],
     }
 }
@@ -3,8 +3,10 @@ use crate::tests::fixtures::get_context;
 use crate::tests::helpers::*;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
 use crate::types::Gas;
-use crate::{VMConfig, VMLogic};
+use crate::{ExtCosts, VMConfig, VMLogic};
 use near_primitives::transaction::{Action, FunctionCallAction};
+use near_primitives_core::profile::Cost;
+use near_vm_errors::HostError;
 
 #[test]
 fn test_dont_burn_gas_when_exceeding_attached_gas_limit() {
@@ -199,7 +201,32 @@ fn function_call_weight_basic_cases_test() {
     ]);
 
     // Weights with one zero and one non-zero
-    function_call_weight_check(&[(0, 0, 0), (0, 1, 10_000_000_000)])
+    function_call_weight_check(&[(0, 0, 0), (0, 1, 10_000_000_000)]);
+
+    // Static gas using up all of the prepaid gas leaves nothing to distribute
+    function_call_weight_check(&[
+        (4_000_000_000, 1, 4_000_000_000),
+        (6_000_000_000, 1, 6_000_000_000),
+    ])
+}
+
+#[test]
+fn function_call_weight_static_gas_exceeds_prepaid_gas() {
+    let gas_limit = 10_000_000_000;
+
+    let mut logic_builder = VMLogicBuilder::free().max_gas_burnt(gas_limit);
+    let mut logic = logic_builder.build_with_prepaid_gas(gas_limit);
+
+    let index = promise_batch_create(&mut logic, "rick.test").expect("should create a promise");
+    promise_batch_action_function_call_weight(&mut logic, index, 0, gas_limit / 2, 1)
+        .expect("batch action function call should succeed");
+
+    // Weights only distribute unused gas, the static gas still has to fit into the prepaid gas.
+    let index = promise_batch_create(&mut logic, "rick.test").expect("should create a promise");
+    assert_eq!(
+        promise_batch_action_function_call_weight(&mut logic, index, 0, gas_limit / 2 + 1, 1),
+        Err(HostError::GasExceeded.into())
+    );
 }
 
 #[test]
@@ -219,6 +246,36 @@ fn function_call_no_weight_refund() {
     assert!(outcome.used_gas < gas_limit);
 }
 
+#[test]
+fn test_profile_host_function_costs() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let ext_costs = logic_builder.config.ext_costs.clone();
+    let regular_op_cost = logic_builder.config.regular_op_cost as u64;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let data = b"profiled";
+    let len = data.len() as u64;
+    logic.sha256(len, data.as_ptr() as _, 0).expect("sha256 should succeed");
+    logic.log_utf8(len, data.as_ptr() as _).expect("log should succeed");
+    logic.gas(100).expect("gas should not be exceeded");
+    let outcome = logic.compute_outcome_and_distribute_gas();
+
+    let profile = &outcome.profile;
+    assert_eq!(profile.get_ext_cost(ExtCosts::sha256_base), ext_costs.sha256_base);
+    assert_eq!(profile.get_ext_cost(ExtCosts::sha256_byte), ext_costs.sha256_byte * len);
+    assert_eq!(profile.get_ext_cost(ExtCosts::log_base), ext_costs.log_base);
+    assert_eq!(profile.get_ext_cost(ExtCosts::log_byte), ext_costs.log_byte * len);
+    assert_eq!(profile.get_ext_cost(ExtCosts::read_memory_base), 2 * ext_costs.read_memory_base);
+    assert_eq!(
+        profile.get_ext_cost(ExtCosts::read_memory_byte),
+        2 * ext_costs.read_memory_byte * len
+    );
+    assert_eq!(profile.get_ext_cost(ExtCosts::storage_write_base), 0);
+    assert_eq!(profile[Cost::WasmInstruction], 100 * regular_op_cost);
+    assert_eq!(profile.action_gas(), 0);
+    assert_eq!(profile.host_gas() + profile[Cost::WasmInstruction], outcome.burnt_gas);
+}
+
 impl VMLogicBuilder {
     fn max_gas_burnt(mut self, max_gas_burnt: Gas) -> Self {
         self.config.limit_config.max_gas_burnt = max_gas_burnt;
@@ -1,11 +1,16 @@
 use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts, External, VMLogic};
+use near_primitives::version::ProtocolFeature;
 use near_vm_errors::{HostError, VMLogicError};
 
 #[test]
 fn test_iterator_deprecated() {
     let context = get_context(vec![], false);
     let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.current_protocol_version =
+        ProtocolFeature::StorageIterators.protocol_version() - 1;
     let mut logic = logic_builder.build(context);
     assert_eq!(
         Err(VMLogicError::HostError(HostError::Deprecated {
@@ -26,3 +31,167 @@ fn test_iterator_deprecated() {
         logic.storage_iter_next(0, 0, 1)
     );
 }
+
+fn logic_builder_with_storage() -> VMLogicBuilder {
+    let mut logic_builder = VMLogicBuilder::default();
+    for (key, value) in [("aa", "1"), ("ab", "2"), ("b", "3"), ("ba", ""), ("c", "5")] {
+        logic_builder.ext.storage_set(key.as_bytes(), value.as_bytes()).unwrap();
+    }
+    logic_builder
+}
+
+fn read_register(logic: &mut VMLogic, register_id: u64) -> Vec<u8> {
+    let len = logic.register_len(register_id).unwrap();
+    let mut buffer = vec![0u8; len as usize];
+    logic.read_register(register_id, buffer.as_mut_ptr() as _).unwrap();
+    buffer
+}
+
+/// Drains the iterator, returning the keys and values as strings.
+fn collect(logic: &mut VMLogic, iterator_id: u64) -> Vec<(String, String)> {
+    let mut key_values = vec![];
+    while logic.storage_iter_next(iterator_id, 1, 2).unwrap() == 1 {
+        let key = String::from_utf8(read_register(logic, 1)).unwrap();
+        let value = String::from_utf8(read_register(logic, 2)).unwrap();
+        key_values.push((key, value));
+    }
+    // Exhausted iterators stay exhausted.
+    assert_eq!(logic.storage_iter_next(iterator_id, 1, 2), Ok(0));
+    key_values
+}
+
+fn key_values(key_values: &[(&str, &str)]) -> Vec<(String, String)> {
+    key_values.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn test_iterator_prefix() {
+    let mut logic_builder = logic_builder_with_storage();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let prefix = b"a";
+    let iterator_id = logic.storage_iter_prefix(prefix.len() as _, prefix.as_ptr() as _).unwrap();
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 1,
+        ExtCosts::read_memory_byte: prefix.len() as u64,
+        ExtCosts::storage_iter_create_prefix_base: 1,
+        ExtCosts::storage_iter_create_prefix_byte: prefix.len() as u64,
+        ExtCosts::touching_trie_node: 0,
+        ExtCosts::read_cached_trie_node: 0,
+    });
+
+    assert_eq!(logic.storage_iter_next(iterator_id, 1, 2), Ok(1));
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::storage_iter_next_base: 1,
+        ExtCosts::storage_iter_next_key_byte: 2,
+        ExtCosts::storage_iter_next_value_byte: 1,
+        ExtCosts::write_register_base: 2,
+        ExtCosts::write_register_byte: 3,
+        ExtCosts::touching_trie_node: 0,
+        ExtCosts::read_cached_trie_node: 0,
+    });
+    assert_eq!(read_register(&mut logic, 1), b"aa");
+    assert_eq!(read_register(&mut logic, 2), b"1");
+    assert_eq!(collect(&mut logic, iterator_id), key_values(&[("ab", "2")]));
+
+    // An empty prefix iterates over all keys, including ones with empty values.
+    let iterator_id = logic.storage_iter_prefix(0, b"".as_ptr() as _).unwrap();
+    assert_eq!(
+        collect(&mut logic, iterator_id),
+        key_values(&[("aa", "1"), ("ab", "2"), ("b", "3"), ("ba", ""), ("c", "5")])
+    );
+
+    let prefix = b"d";
+    let iterator_id = logic.storage_iter_prefix(prefix.len() as _, prefix.as_ptr() as _).unwrap();
+    assert_eq!(collect(&mut logic, iterator_id), vec![]);
+}
+
+#[test]
+fn test_iterator_range() {
+    let mut logic_builder = logic_builder_with_storage();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let mut range = |start: &[u8], end: &[u8]| {
+        let iterator_id = logic
+            .storage_iter_range(
+                start.len() as _,
+                start.as_ptr() as _,
+                end.len() as _,
+                end.as_ptr() as _,
+            )
+            .unwrap();
+        collect(&mut logic, iterator_id)
+    };
+
+    assert_eq!(range(b"ab", b"c"), key_values(&[("ab", "2"), ("b", "3"), ("ba", "")]));
+    assert_eq!(range(b"", b"b"), key_values(&[("aa", "1"), ("ab", "2")]));
+    assert_eq!(range(b"b", b"z"), key_values(&[("b", "3"), ("ba", ""), ("c", "5")]));
+
+    // Empty ranges.
+    assert_eq!(range(b"b", b"b"), vec![]);
+    assert_eq!(range(b"c", b"a"), vec![]);
+    assert_eq!(range(b"bb", b"c"), vec![]);
+}
+
+#[test]
+fn test_iterator_invalidation() {
+    let mut logic_builder = logic_builder_with_storage();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let prefix = b"a";
+    let first = logic.storage_iter_prefix(prefix.len() as _, prefix.as_ptr() as _).unwrap();
+    let second = logic.storage_iter_prefix(prefix.len() as _, prefix.as_ptr() as _).unwrap();
+    assert_eq!(logic.storage_iter_next(first, 1, 2), Ok(1));
+
+    // Writing any key, even outside of the iterated keys, invalidates all iterators.
+    let (key, value) = (b"z", b"26");
+    logic
+        .storage_write(key.len() as _, key.as_ptr() as _, value.len() as _, value.as_ptr() as _, 0)
+        .unwrap();
+    for iterator_id in [first, second] {
+        assert_eq!(
+            logic.storage_iter_next(iterator_id, 1, 2),
+            Err(HostError::IteratorWasInvalidated { iterator_index: iterator_id }.into())
+        );
+    }
+
+    // Iterators created afterwards see the write.
+    let iterator_id = logic.storage_iter_prefix(0, b"".as_ptr() as _).unwrap();
+    assert_eq!(logic.storage_iter_next(iterator_id, 1, 2), Ok(1));
+    logic.storage_remove(key.len() as _, key.as_ptr() as _, 0).unwrap();
+    assert_eq!(
+        logic.storage_iter_next(iterator_id, 1, 2),
+        Err(HostError::IteratorWasInvalidated { iterator_index: iterator_id }.into())
+    );
+
+    let iterator_id = logic.storage_iter_prefix(1, b"z".as_ptr() as _).unwrap();
+    assert_eq!(collect(&mut logic, iterator_id), vec![]);
+}
+
+#[test]
+fn test_iterator_invalid_arguments() {
+    let mut logic_builder = logic_builder_with_storage();
+    logic_builder.config.limit_config.max_length_storage_key = 2;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    assert_eq!(
+        logic.storage_iter_next(0, 1, 2),
+        Err(HostError::InvalidIteratorIndex { iterator_index: 0 }.into())
+    );
+    let iterator_id = logic.storage_iter_prefix(0, b"".as_ptr() as _).unwrap();
+    assert_eq!(
+        logic.storage_iter_next(iterator_id, 1, 1),
+        Err(HostError::MemoryAccessViolation.into())
+    );
+
+    let key = b"abc";
+    assert_eq!(
+        logic.storage_iter_prefix(key.len() as _, key.as_ptr() as _),
+        Err(HostError::KeyLengthExceeded { length: 3, limit: 2 }.into())
+    );
+    assert_eq!(
+        logic.storage_iter_range(1, key.as_ptr() as _, key.len() as _, key.as_ptr() as _),
+        Err(HostError::KeyLengthExceeded { length: 3, limit: 2 }.into())
+    );
+}
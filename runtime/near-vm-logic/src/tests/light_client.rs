@@ -0,0 +1,331 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts, VMLogic};
+use borsh::BorshSerialize;
+use near_crypto::{InMemorySigner, KeyType, Signer};
+use near_primitives::block_header::{Approval, ApprovalInner, BlockHeader, BlockHeaderInnerLite};
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{Balance, BlockHeight};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{
+    BlockHeaderInnerLiteView, LightClientBlockLiteView, LightClientBlockView,
+};
+use near_vm_errors::VMLogicError;
+
+const HEAD_HEIGHT: BlockHeight = 10;
+
+fn epoch_id() -> CryptoHash {
+    hash(b"epoch")
+}
+
+fn next_epoch_id() -> CryptoHash {
+    hash(b"next epoch")
+}
+
+fn block_producers(stakes: &[Balance]) -> (Vec<InMemorySigner>, Vec<ValidatorStakeView>) {
+    stakes
+        .iter()
+        .enumerate()
+        .map(|(i, stake)| {
+            let account_id = format!("bp{i}.near");
+            let signer = InMemorySigner::from_seed(
+                account_id.parse().unwrap(),
+                KeyType::ED25519,
+                &account_id,
+            );
+            let bp = ValidatorStake::new(signer.account_id.clone(), signer.public_key(), *stake);
+            (signer, bp.into())
+        })
+        .unzip()
+}
+
+fn inner_lite(
+    height: BlockHeight,
+    epoch_id: CryptoHash,
+    next_epoch_id: CryptoHash,
+    next_bps: Option<&[ValidatorStakeView]>,
+) -> BlockHeaderInnerLiteView {
+    let next_bp_hash = next_bps
+        .map(|bps| {
+            let bps: Vec<ValidatorStake> =
+                bps.iter().map(|bp| bp.clone().into_validator_stake()).collect();
+            CryptoHash::hash_borsh(&bps)
+        })
+        .unwrap_or_default();
+    BlockHeaderInnerLiteView {
+        height,
+        epoch_id,
+        next_epoch_id,
+        prev_state_root: hash(b"state"),
+        outcome_root: hash(b"outcome"),
+        timestamp: height,
+        timestamp_nanosec: height,
+        next_bp_hash,
+        block_merkle_root: hash(b"block merkle"),
+    }
+}
+
+fn head() -> LightClientBlockLiteView {
+    LightClientBlockLiteView {
+        prev_block_hash: hash(b"head prev"),
+        inner_rest_hash: hash(b"head rest"),
+        inner_lite: inner_lite(HEAD_HEIGHT, epoch_id(), next_epoch_id(), None),
+    }
+}
+
+/// Builds a block at `height` approved by the `signers` for which `signed`
+/// is set, hashing it the same way the chain does.
+fn new_block(
+    signers: &[InMemorySigner],
+    signed: &[bool],
+    height: BlockHeight,
+    inner_lite: BlockHeaderInnerLiteView,
+    next_bps: Option<Vec<ValidatorStakeView>>,
+) -> LightClientBlockView {
+    let prev_block_hash = hash(b"prev");
+    let inner_lite_bytes = BlockHeaderInnerLite::from(inner_lite.clone()).try_to_vec().unwrap();
+    let block_hash = BlockHeader::compute_hash(prev_block_hash, &inner_lite_bytes, b"rest");
+    let next_block_hash = BlockHeader::compute_hash(block_hash, b"next lite", b"next rest");
+    let approval =
+        Approval::get_data_for_sig(&ApprovalInner::Endorsement(next_block_hash), height + 2);
+    let approvals_after_next = signers
+        .iter()
+        .zip(signed)
+        .map(|(signer, signed)| signed.then(|| signer.sign(&approval)))
+        .collect();
+    LightClientBlockView {
+        prev_block_hash,
+        next_block_inner_hash: BlockHeader::compute_inner_hash(b"next lite", b"next rest"),
+        inner_lite,
+        inner_rest_hash: hash(b"rest"),
+        next_bps,
+        approvals_after_next,
+    }
+}
+
+fn validate(
+    logic: &mut VMLogic,
+    current_bps: &[ValidatorStakeView],
+    head: &LightClientBlockLiteView,
+    block: &LightClientBlockView,
+) -> Result<u8, VMLogicError> {
+    let current_bps = current_bps.try_to_vec().unwrap();
+    let head = head.try_to_vec().unwrap();
+    let block = block.try_to_vec().unwrap();
+    logic.validate_light_client_block(
+        current_bps.len() as _,
+        current_bps.as_ptr() as _,
+        head.len() as _,
+        head.as_ptr() as _,
+        block.len() as _,
+        block.as_ptr() as _,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_validate_light_client_block() {
+    let (signers, bps) = block_producers(&[10, 20, 30, 40]);
+    let block = new_block(
+        &signers,
+        &[false, true, true, true],
+        HEAD_HEIGHT + 1,
+        inner_lite(HEAD_HEIGHT + 1, epoch_id(), next_epoch_id(), None),
+        None,
+    );
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(1));
+
+    let num_bytes = bps.try_to_vec().unwrap().len()
+        + head().try_to_vec().unwrap().len()
+        + block.try_to_vec().unwrap().len();
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 3,
+        ExtCosts::read_memory_byte: num_bytes as u64,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 1,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 1,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 1,
+        ExtCosts::validate_light_client_block_base: 1,
+        ExtCosts::validate_light_client_block_byte: num_bytes as u64,
+        ExtCosts::validate_light_client_block_signature: 3,
+    });
+}
+
+#[test]
+fn test_validate_light_client_block_next_epoch() {
+    let (signers, bps) = block_producers(&[10, 20, 30, 40]);
+    let (_, next_bps) = block_producers(&[1, 2, 3]);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let block = new_block(
+        &signers,
+        &[true; 4],
+        HEAD_HEIGHT + 5,
+        inner_lite(HEAD_HEIGHT + 5, next_epoch_id(), hash(b"after next"), Some(&next_bps)),
+        Some(next_bps.clone()),
+    );
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(1));
+
+    // Entering the next epoch requires the block producers of the epoch after.
+    let block = new_block(
+        &signers,
+        &[true; 4],
+        HEAD_HEIGHT + 5,
+        inner_lite(HEAD_HEIGHT + 5, next_epoch_id(), hash(b"after next"), Some(&next_bps)),
+        None,
+    );
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+
+    // `next_bps` must match `next_bp_hash`.
+    let block = new_block(
+        &signers,
+        &[true; 4],
+        HEAD_HEIGHT + 5,
+        inner_lite(HEAD_HEIGHT + 5, next_epoch_id(), hash(b"after next"), Some(&next_bps)),
+        Some(next_bps[1..].to_vec()),
+    );
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+}
+
+#[test]
+fn test_validate_light_client_block_invalid() {
+    let (signers, bps) = block_producers(&[10, 20, 30, 40]);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let valid_block = |signed: &[bool], height| {
+        new_block(
+            &signers,
+            signed,
+            height,
+            inner_lite(height, epoch_id(), next_epoch_id(), None),
+            None,
+        )
+    };
+
+    // Exactly 2/3 of the stake is not enough.
+    let (equal_signers, equal_bps) = block_producers(&[10, 10, 10]);
+    let block = new_block(
+        &equal_signers,
+        &[true, true, false],
+        HEAD_HEIGHT + 1,
+        inner_lite(HEAD_HEIGHT + 1, epoch_id(), next_epoch_id(), None),
+        None,
+    );
+    assert_eq!(validate(&mut logic, &equal_bps, &head(), &block), Ok(0));
+
+    // Not higher than the head.
+    let block = valid_block(&[true; 4], HEAD_HEIGHT);
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+
+    // Unknown epoch.
+    let block = new_block(
+        &signers,
+        &[true; 4],
+        HEAD_HEIGHT + 1,
+        inner_lite(HEAD_HEIGHT + 1, hash(b"other epoch"), next_epoch_id(), None),
+        None,
+    );
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+
+    // Signature over another block.
+    let mut block = valid_block(&[true; 4], HEAD_HEIGHT + 1);
+    block.approvals_after_next[0] =
+        valid_block(&[true; 4], HEAD_HEIGHT + 2).approvals_after_next[0].clone();
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+
+    // Approvals by other block producers.
+    let (_, other_bps) = block_producers(&[40, 30, 20, 10]);
+    let block = valid_block(&[true; 4], HEAD_HEIGHT + 1);
+    let other_bps: Vec<_> = other_bps.into_iter().rev().collect();
+    assert_eq!(validate(&mut logic, &other_bps, &head(), &block), Ok(0));
+
+    // Inputs which fail to decode are not an error.
+    let block = valid_block(&[true; 4], HEAD_HEIGHT + 1);
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(1));
+    let bps = bps.try_to_vec().unwrap();
+    let bps = &bps[..bps.len() - 1];
+    let head = head().try_to_vec().unwrap();
+    let block = block.try_to_vec().unwrap();
+    logic
+        .validate_light_client_block(
+            bps.len() as _,
+            bps.as_ptr() as _,
+            head.len() as _,
+            head.as_ptr() as _,
+            block.len() as _,
+            block.as_ptr() as _,
+            0,
+        )
+        .unwrap();
+    let mut res = [1u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _).unwrap();
+    assert_eq!(res[0], 0);
+}
+
+#[test]
+fn test_validate_light_client_block_truncated_approvals() {
+    let (signers, bps) = block_producers(&[70, 10, 10, 10]);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let block = new_block(
+        &signers,
+        &[true; 4],
+        HEAD_HEIGHT + 1,
+        inner_lite(HEAD_HEIGHT + 1, epoch_id(), next_epoch_id(), None),
+        None,
+    );
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(1));
+
+    // The approval of the first block producer alone is more than 2/3 of the
+    // stake of the approvals present, but not of the stake of the epoch.
+    let mut truncated = block.clone();
+    truncated.approvals_after_next.truncate(1);
+    assert_eq!(validate(&mut logic, &bps, &head(), &truncated), Ok(0));
+
+    // The approvals of the block producers of the next epoch follow those of
+    // the current one at the end of an epoch, and are ignored.
+    let (next_signers, _) = block_producers(&[1]);
+    let mut extended = block;
+    extended.approvals_after_next.push(Some(next_signers[0].sign(b"unrelated")));
+    assert_eq!(validate(&mut logic, &bps, &head(), &extended), Ok(1));
+}
+
+#[test]
+fn test_validate_light_client_block_stake_overflow() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    for stakes in [&[Balance::MAX, Balance::MAX, 1][..], &[Balance::MAX / 2 + 1, 1][..]] {
+        let (signers, bps) = block_producers(stakes);
+        let block = new_block(
+            &signers,
+            &vec![true; stakes.len()],
+            HEAD_HEIGHT + 1,
+            inner_lite(HEAD_HEIGHT + 1, epoch_id(), next_epoch_id(), None),
+            None,
+        );
+        assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+    }
+
+    // The height of the approvals overflows.
+    let (signers, bps) = block_producers(&[10, 20, 30, 40]);
+    let mut block = new_block(
+        &signers,
+        &[true; 4],
+        HEAD_HEIGHT + 1,
+        inner_lite(HEAD_HEIGHT + 1, epoch_id(), next_epoch_id(), None),
+        None,
+    );
+    block.inner_lite.height = BlockHeight::MAX;
+    assert_eq!(validate(&mut logic, &bps, &head(), &block), Ok(0));
+}
@@ -1,12 +1,15 @@
 use crate::tests::fixtures::get_context;
 use crate::tests::helpers::*;
+use crate::tests::vectors::{deserialize_option_hex, load_vectors};
 use crate::tests::vm_logic_builder::VMLogicBuilder;
-use crate::{map, ExtCosts};
-use hex::FromHex;
+use crate::{map, ExtCosts, ReturnData, VIEW_LOG_LIMITS_MULTIPLIER};
+use borsh::BorshSerialize;
+use near_crypto::{KeyType, PublicKey};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::EpochId;
+use near_primitives::version::ProtocolFeature;
 use near_vm_errors::HostError;
-use serde::{de::Error, Deserialize, Deserializer};
-use serde_json::from_slice;
-use std::{fmt::Display, fs};
+use serde::Deserialize;
 
 #[test]
 fn test_valid_utf8() {
@@ -153,6 +156,132 @@ fn test_log_number_limit() {
     assert_eq!(outcome.logs.len() as u64, max_number_logs);
 }
 
+#[test]
+fn test_log_limits_view() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let string_bytes = "blabla".as_bytes().to_vec();
+    let len = string_bytes.len() as u64;
+    let max_number_logs = 3;
+    logic_builder.config.limit_config.max_number_logs = max_number_logs;
+    logic_builder.config.limit_config.max_total_log_length = len * max_number_logs * 2;
+
+    // Logs as much as the limits allow, returns the number of logs written and the error.
+    let mut log_until_limit = |context| {
+        let mut logic = logic_builder.build(context);
+        loop {
+            if let Err(err) = logic.log_utf8(len, string_bytes.as_ptr() as _) {
+                return (logic.logs().len() as u64, err);
+            }
+        }
+    };
+
+    assert_eq!(
+        log_until_limit(get_context(vec![], false)),
+        (max_number_logs, HostError::NumberOfLogsExceeded { limit: max_number_logs }.into())
+    );
+    let view_limit = max_number_logs * VIEW_LOG_LIMITS_MULTIPLIER;
+    assert_eq!(
+        log_until_limit(get_context(vec![], true)),
+        (view_limit, HostError::NumberOfLogsExceeded { limit: view_limit }.into())
+    );
+
+    let mut context = get_context(vec![], true);
+    let view_config = context.view_config.as_mut().unwrap();
+    view_config.max_number_logs = Some(5);
+    assert_eq!(log_until_limit(context), (5, HostError::NumberOfLogsExceeded { limit: 5 }.into()));
+
+    let mut context = get_context(vec![], true);
+    let view_config = context.view_config.as_mut().unwrap();
+    view_config.max_total_log_length = Some(len);
+    assert_eq!(
+        log_until_limit(context),
+        (1, HostError::TotalLogLengthExceeded { length: len * 2, limit: len }.into())
+    );
+}
+
+#[test]
+fn test_event_total_length_limit() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let standard = "nep171".as_bytes().to_vec();
+    let data = "j ñ r'ø qò$`5 y'5 øò{%÷ `Võ%".as_bytes().to_vec();
+    let num_events = 10;
+    let limit = (standard.len() + data.len()) as u64 * num_events - 1;
+    logic_builder.config.limit_config.max_total_event_length = limit;
+    logic_builder.config.limit_config.max_number_events = num_events;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    for _ in 0..num_events - 1 {
+        logic
+            .emit_event(
+                standard.len() as _,
+                standard.as_ptr() as _,
+                data.len() as _,
+                data.as_ptr() as _,
+            )
+            .expect("total is still under the limit");
+    }
+    assert_eq!(
+        logic.emit_event(
+            standard.len() as _,
+            standard.as_ptr() as _,
+            data.len() as _,
+            data.as_ptr() as _
+        ),
+        Err(HostError::TotalEventLengthExceeded { length: limit + 1, limit }.into())
+    );
+
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert_eq!(outcome.events.len() as u64, num_events - 1);
+    assert!(outcome.logs.is_empty());
+}
+
+#[test]
+fn test_event_number_limit() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let standard = "nep171".as_bytes().to_vec();
+    let data = vec![1u8, 2, 3, 4];
+    let max_number_events = 3;
+    let len = (standard.len() + data.len()) as u64;
+    logic_builder.config.limit_config.max_total_event_length = (len + 1) * (max_number_events + 1);
+    logic_builder.config.limit_config.max_number_events = max_number_events;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    for _ in 0..max_number_events {
+        logic
+            .emit_event(
+                standard.len() as _,
+                standard.as_ptr() as _,
+                data.len() as _,
+                data.as_ptr() as _,
+            )
+            .expect("Valid event under the event number limit");
+    }
+    assert_eq!(
+        logic.emit_event(
+            standard.len() as _,
+            standard.as_ptr() as _,
+            data.len() as _,
+            data.as_ptr() as _
+        ),
+        Err(HostError::NumberOfEventsExceeded { limit: max_number_events }.into())
+    );
+
+    assert_costs(map! {
+        ExtCosts::base: max_number_events + 1,
+        ExtCosts::event_base: max_number_events,
+        ExtCosts::event_byte: len * max_number_events,
+        ExtCosts::read_memory_base: 2 * max_number_events,
+        ExtCosts::read_memory_byte: len * max_number_events,
+        ExtCosts::utf8_decoding_base: max_number_events,
+        ExtCosts::utf8_decoding_byte: standard.len() as u64 * max_number_events,
+    });
+
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert_eq!(
+        outcome.events,
+        vec![("nep171".to_string(), data.clone()); max_number_events as usize]
+    );
+}
+
 #[test]
 fn test_log_utf16_number_limit() {
     let mut logic_builder = VMLogicBuilder::default();
@@ -210,10 +339,11 @@ fn test_log_total_length_limit_mixed() {
     let num_logs_each = 10;
     let limit = utf8_bytes.len() as u64 * num_logs_each
         + string.as_bytes().len() as u64 * num_logs_each
+        + string.as_bytes().len() as u64
         + final_bytes.len() as u64
         - 1;
     logic_builder.config.limit_config.max_total_log_length = limit;
-    logic_builder.config.limit_config.max_number_logs = num_logs_each * 2 + 1;
+    logic_builder.config.limit_config.max_number_logs = num_logs_each * 2 + 2;
     let mut logic = logic_builder.build(get_context(vec![], false));
 
     for _ in 0..num_logs_each {
@@ -225,13 +355,51 @@ fn test_log_total_length_limit_mixed() {
             .log_utf8(utf8_bytes.len() as _, utf8_bytes.as_ptr() as _)
             .expect("total is still under the limit");
     }
+    // The UTF-16 log is longer than what is left of the limit, but its UTF-8 length isn't.
+    logic
+        .log_utf16(utf16_bytes.len() as _, utf16_bytes.as_ptr() as _)
+        .expect("UTF-8 length of the log is under the limit");
     assert_eq!(
         logic.log_utf8(final_bytes.len() as _, final_bytes.as_ptr() as _),
         Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into())
     );
 
     let outcome = logic.compute_outcome_and_distribute_gas();
-    assert_eq!(outcome.logs.len() as u64, num_logs_each * 2);
+    assert_eq!(outcome.logs.len() as u64, num_logs_each * 2 + 1);
+}
+
+#[test]
+fn test_log_utf16_surrogate_pairs_total_length() {
+    let string = "a😀b😀";
+    let mut utf16_bytes: Vec<u8> = vec![];
+    for u16_ in string.encode_utf16() {
+        utf16_bytes.push(u16_ as u8);
+        utf16_bytes.push((u16_ >> 8) as u8);
+    }
+    let final_bytes = "a".as_bytes().to_vec();
+    let limit = string.len() as u64;
+    assert!(limit < utf16_bytes.len() as u64);
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_total_log_length = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    logic
+        .log_utf16(utf16_bytes.len() as _, utf16_bytes.as_ptr() as _)
+        .expect("UTF-8 length of the log is exactly the limit");
+    assert_eq!(
+        logic.log_utf8(final_bytes.len() as _, final_bytes.as_ptr() as _),
+        Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into())
+    );
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert_eq!(outcome.logs, vec![string.to_string()]);
+
+    logic_builder.current_protocol_version =
+        ProtocolFeature::Utf16LogUtf8Length.protocol_version() - 1;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        logic.log_utf16(utf16_bytes.len() as _, utf16_bytes.as_ptr() as _),
+        Err(HostError::TotalLogLengthExceeded { length: utf16_bytes.len() as _, limit }.into())
+    );
 }
 
 #[test]
@@ -254,6 +422,7 @@ fn test_log_utf8_max_limit_null_terminated() {
         ExtCosts::read_memory_base: len - 1 ,
         ExtCosts::read_memory_byte: len - 1,
         ExtCosts::utf8_decoding_base: 1,
+        ExtCosts::utf8_decoding_byte: len - 1,
     });
 
     let outcome = logic.compute_outcome_and_distribute_gas();
@@ -313,6 +482,37 @@ fn test_valid_log_utf16_max_log_len_not_even() {
         ExtCosts::log_byte: string.len() as u64 ,
     });
 
+    let string = "abcd";
+    let mut utf16_bytes: Vec<u8> = Vec::new();
+    for u16_ in string.encode_utf16() {
+        utf16_bytes.push(u16_ as u8);
+        utf16_bytes.push((u16_ >> 8) as u8);
+    }
+    utf16_bytes.extend_from_slice(&[0, 0]);
+    assert_eq!(
+        logic.log_utf16(u64::MAX, utf16_bytes.as_ptr() as _),
+        Err(HostError::TotalLogLengthExceeded {
+            length: 6,
+            limit: logic_builder.config.limit_config.max_total_log_length,
+        }
+        .into())
+    );
+
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 4,
+        ExtCosts::read_memory_byte: 4 * 2,
+        ExtCosts::utf16_decoding_base: 1,
+    });
+}
+
+#[test]
+fn test_valid_log_utf16_max_log_len_not_even_before_utf8_length() {
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.current_protocol_version =
+        ProtocolFeature::Utf16LogUtf8Length.protocol_version() - 1;
+    logic_builder.config.limit_config.max_total_log_length = 5;
+    let mut logic = logic_builder.build(get_context(vec![], false));
     let string = "abc";
     let mut utf16_bytes: Vec<u8> = Vec::new();
     for u16_ in string.encode_utf16() {
@@ -331,8 +531,8 @@ fn test_valid_log_utf16_max_log_len_not_even() {
 
     assert_costs(map! {
         ExtCosts::base: 1,
-        ExtCosts::read_memory_base: 2,
-        ExtCosts::read_memory_byte: 2 * 2,
+        ExtCosts::read_memory_base: 3,
+        ExtCosts::read_memory_byte: 3 * 2,
         ExtCosts::utf16_decoding_base: 1,
     });
 }
@@ -351,6 +551,45 @@ fn test_log_utf8_max_limit_null_terminated_fail() {
         ExtCosts::read_memory_base: logic_builder.config.limit_config.max_total_log_length + 1,
         ExtCosts::read_memory_byte: logic_builder.config.limit_config.max_total_log_length + 1,
         ExtCosts::utf8_decoding_base: 1,
+        ExtCosts::utf8_decoding_byte: logic_builder.config.limit_config.max_total_log_length + 1,
+    });
+}
+
+#[test]
+fn test_log_utf8_null_terminated_scan_limit() {
+    let mut logic_builder = VMLogicBuilder::default();
+    // No nul byte within the first kilobyte.
+    let string_bytes = vec![b'a'; 1024];
+    let limit = 10;
+    logic_builder.config.limit_config.max_total_log_length = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let res = logic.log_utf8(u64::MAX, string_bytes.as_ptr() as _);
+    assert_eq!(res, Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into()));
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: limit + 1,
+        ExtCosts::read_memory_byte: limit + 1,
+        ExtCosts::utf8_decoding_base: 1,
+        ExtCosts::utf8_decoding_byte: limit + 1,
+    });
+}
+
+#[test]
+fn test_log_utf8_null_terminated_scan_limit_before_decoding_cost() {
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.current_protocol_version =
+        ProtocolFeature::NulTerminatedUtf8DecodingCost.protocol_version() - 1;
+    let string_bytes = vec![b'a'; 1024];
+    let limit = 10;
+    logic_builder.config.limit_config.max_total_log_length = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let res = logic.log_utf8(u64::MAX, string_bytes.as_ptr() as _);
+    assert_eq!(res, Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into()));
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: limit + 1,
+        ExtCosts::read_memory_byte: limit + 1,
+        ExtCosts::utf8_decoding_base: 1,
     });
 }
 
@@ -430,132 +669,141 @@ fn test_valid_log_utf16_null_terminated_fail() {
     });
 }
 
+/// Digests of `input` computed independently of the host functions.
+#[derive(Deserialize)]
+struct HashTest {
+    name: String,
+    #[serde(with = "hex::serde")]
+    input: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    sha256: [u8; 32],
+    #[serde(with = "hex::serde")]
+    keccak256: [u8; 32],
+    #[serde(with = "hex::serde")]
+    keccak512: [u8; 64],
+    #[serde(with = "hex::serde")]
+    ripemd160: [u8; 20],
+    ripemd160_blocks: u64,
+}
+
+fn hash_tests() -> Vec<HashTest> {
+    load_vectors("hash-tests.json")
+}
+
 #[test]
 fn test_sha256() {
-    let mut logic_builder = VMLogicBuilder::default();
-    let mut logic = logic_builder.build(get_context(vec![], false));
-    let data = b"tesdsst";
-
-    logic.sha256(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let res = &vec![0u8; 32];
-    logic.read_register(0, res.as_ptr() as _).expect("OK");
-    assert_eq!(
-        res,
-        &[
-            18, 176, 115, 156, 45, 100, 241, 132, 180, 134, 77, 42, 105, 111, 199, 127, 118, 112,
-            92, 255, 88, 43, 83, 147, 122, 55, 26, 36, 42, 156, 160, 158,
-        ]
-    );
-    let len = data.len() as u64;
-    assert_costs(map! {
-        ExtCosts::base: 1,
-        ExtCosts::read_memory_base: 1,
-        ExtCosts::read_memory_byte: len,
-        ExtCosts::write_memory_base: 1,
-        ExtCosts::write_memory_byte: 32,
-        ExtCosts::read_register_base: 1,
-        ExtCosts::read_register_byte: 32,
-        ExtCosts::write_register_base: 1,
-        ExtCosts::write_register_byte: 32,
-        ExtCosts::sha256_base: 1,
-        ExtCosts::sha256_byte: len,
-    });
+    for test in hash_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let data = &test.input;
+
+        logic.sha256(data.len() as _, data.as_ptr() as _, 0).unwrap();
+        let res = [0u8; 32];
+        logic.read_register(0, res.as_ptr() as _).expect("OK");
+        assert_eq!(res, test.sha256, "{}", test.name);
+        let len = data.len() as u64;
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: 1,
+            ExtCosts::read_memory_byte: len,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 32,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 32,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 32,
+            ExtCosts::sha256_base: 1,
+            ExtCosts::sha256_byte: len,
+        });
+        reset_costs_counter();
+    }
 }
 
 #[test]
 fn test_keccak256() {
-    let mut logic_builder = VMLogicBuilder::default();
-    let mut logic = logic_builder.build(get_context(vec![], false));
-    let data = b"tesdsst";
-
-    logic.keccak256(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let res = &vec![0u8; 32];
-    logic.read_register(0, res.as_ptr() as _).expect("OK");
-    assert_eq!(
-        res.as_slice(),
-        &[
-            104, 110, 58, 122, 230, 181, 215, 145, 231, 229, 49, 162, 123, 167, 177, 58, 26, 142,
-            129, 173, 7, 37, 9, 26, 233, 115, 64, 102, 61, 85, 10, 159
-        ]
-    );
-    let len = data.len() as u64;
-    assert_costs(map! {
-        ExtCosts::base: 1,
-        ExtCosts::read_memory_base: 1,
-        ExtCosts::read_memory_byte: len,
-        ExtCosts::write_memory_base: 1,
-        ExtCosts::write_memory_byte: 32,
-        ExtCosts::read_register_base: 1,
-        ExtCosts::read_register_byte: 32,
-        ExtCosts::write_register_base: 1,
-        ExtCosts::write_register_byte: 32,
-        ExtCosts::keccak256_base: 1,
-        ExtCosts::keccak256_byte: len,
-    });
+    for test in hash_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let data = &test.input;
+
+        logic.keccak256(data.len() as _, data.as_ptr() as _, 0).unwrap();
+        let res = [0u8; 32];
+        logic.read_register(0, res.as_ptr() as _).expect("OK");
+        assert_eq!(res, test.keccak256, "{}", test.name);
+        let len = data.len() as u64;
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: 1,
+            ExtCosts::read_memory_byte: len,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 32,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 32,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 32,
+            ExtCosts::keccak256_base: 1,
+            ExtCosts::keccak256_byte: len,
+        });
+        reset_costs_counter();
+    }
 }
 
 #[test]
 fn test_keccak512() {
-    let mut logic_builder = VMLogicBuilder::default();
-    let mut logic = logic_builder.build(get_context(vec![], false));
-    let data = b"tesdsst";
-
-    logic.keccak512(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let res = &vec![0u8; 64];
-    logic.read_register(0, res.as_ptr() as _).expect("OK");
-    assert_eq!(
-        res,
-        &[
-            55, 134, 96, 137, 168, 122, 187, 95, 67, 76, 18, 122, 146, 11, 225, 106, 117, 194, 154,
-            157, 48, 160, 90, 146, 104, 209, 118, 126, 222, 230, 200, 125, 48, 73, 197, 236, 123,
-            173, 192, 197, 90, 153, 167, 121, 100, 88, 209, 240, 137, 86, 239, 41, 87, 128, 219,
-            249, 136, 203, 220, 109, 46, 168, 234, 190
-        ]
-        .to_vec()
-    );
-    let len = data.len() as u64;
-    assert_costs(map! {
-        ExtCosts::base: 1,
-        ExtCosts::read_memory_base: 1,
-        ExtCosts::read_memory_byte: len,
-        ExtCosts::write_memory_base: 1,
-        ExtCosts::write_memory_byte: 64,
-        ExtCosts::read_register_base: 1,
-        ExtCosts::read_register_byte: 64,
-        ExtCosts::write_register_base: 1,
-        ExtCosts::write_register_byte: 64,
-        ExtCosts::keccak512_base: 1,
-        ExtCosts::keccak512_byte: len,
-    });
+    for test in hash_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let data = &test.input;
+
+        logic.keccak512(data.len() as _, data.as_ptr() as _, 0).unwrap();
+        let res = [0u8; 64];
+        logic.read_register(0, res.as_ptr() as _).expect("OK");
+        assert_eq!(res, test.keccak512, "{}", test.name);
+        let len = data.len() as u64;
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: 1,
+            ExtCosts::read_memory_byte: len,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 64,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 64,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 64,
+            ExtCosts::keccak512_base: 1,
+            ExtCosts::keccak512_byte: len,
+        });
+        reset_costs_counter();
+    }
 }
 
 #[test]
 fn test_ripemd160() {
-    let mut logic_builder = VMLogicBuilder::default();
-    let mut logic = logic_builder.build(get_context(vec![], false));
-
-    let data = b"tesdsst";
-    logic.ripemd160(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let res = &vec![0u8; 20];
-    logic.read_register(0, res.as_ptr() as _).expect("OK");
-    assert_eq!(
-        res,
-        &[21, 102, 156, 115, 232, 3, 58, 215, 35, 84, 129, 30, 143, 86, 212, 104, 70, 97, 14, 225,]
-    );
-    let len = data.len() as u64;
-    assert_costs(map! {
-        ExtCosts::base: 1,
-        ExtCosts::read_memory_base: 1,
-        ExtCosts::read_memory_byte: len,
-        ExtCosts::write_memory_base: 1,
-        ExtCosts::write_memory_byte: 20,
-        ExtCosts::read_register_base: 1,
-        ExtCosts::read_register_byte: 20,
-        ExtCosts::write_register_base: 1,
-        ExtCosts::write_register_byte: 20,
-        ExtCosts::ripemd160_base: 1,
-        ExtCosts::ripemd160_block: 1,
-    });
+    for test in hash_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let data = &test.input;
+
+        logic.ripemd160(data.len() as _, data.as_ptr() as _, 0).unwrap();
+        let res = [0u8; 20];
+        logic.read_register(0, res.as_ptr() as _).expect("OK");
+        assert_eq!(res, test.ripemd160, "{}", test.name);
+        let len = data.len() as u64;
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: 1,
+            ExtCosts::read_memory_byte: len,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 20,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 20,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 20,
+            ExtCosts::ripemd160_base: 1,
+            ExtCosts::ripemd160_block: test.ripemd160_blocks,
+        });
+        reset_costs_counter();
+    }
 }
 
 #[derive(Deserialize)]
@@ -566,28 +814,18 @@ struct EcrecoverTest {
     #[serde(with = "hex::serde")]
     sig: [u8; 64],
     mc: bool,
+    /// Value of `force_ecrecover_malleability_check`.
+    #[serde(default)]
+    force_mc: bool,
     #[serde(deserialize_with = "deserialize_option_hex")]
     res: Option<[u8; 64]>,
 }
 
-fn deserialize_option_hex<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
-where
-    D: Deserializer<'de>,
-    T: FromHex,
-    <T as FromHex>::Error: Display,
-{
-    Deserialize::deserialize(deserializer)
-        .map(|v: Option<&str>| v.map(FromHex::from_hex).transpose().map_err(Error::custom))
-        .and_then(|v| v)
-}
-
 #[test]
 fn test_ecrecover() {
-    for EcrecoverTest { m, v, sig, mc, res } in
-        from_slice::<'_, Vec<_>>(fs::read("src/tests/ecrecover-tests.json").unwrap().as_slice())
-            .unwrap()
-    {
+    for EcrecoverTest { m, v, sig, mc, force_mc, res } in load_vectors("ecrecover-tests.json") {
         let mut logic_builder = VMLogicBuilder::default();
+        logic_builder.config.limit_config.force_ecrecover_malleability_check = force_mc;
         let mut logic = logic_builder.build(get_context(vec![], false));
 
         let b = logic
@@ -652,76 +890,75 @@ fn test_hash256_register() {
 
 #[test]
 fn test_key_length_limit() {
-    let mut logic_builder = VMLogicBuilder::default();
-    let mut key = "a".repeat(1024).as_bytes().to_vec();
-    let val = b"hello";
-    let limit = key.len() as u64;
-    logic_builder.config.limit_config.max_length_storage_key = limit;
-    let mut logic = logic_builder.build(get_context(vec![], false));
-    // Under the limit. Valid calls.
-    logic
-        .storage_has_key(key.len() as _, key.as_ptr() as _)
-        .expect("storage_has_key: key length is under the limit");
-    logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
-        .expect("storage_read: key length is under the limit");
-    logic
-        .storage_read(key.len() as _, key.as_ptr() as _, 0)
-        .expect("storage_read: key length is under the limit");
-    logic
-        .storage_remove(key.len() as _, key.as_ptr() as _, 0)
-        .expect("storage_remove: key length is under the limit");
-    // Over the limit. Invalid calls.
-    key.push(b'a');
-    assert_eq!(
-        logic.storage_has_key(key.len() as _, key.as_ptr() as _),
-        Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
-    );
-    assert_eq!(
-        logic.storage_write(
-            key.len() as _,
-            key.as_ptr() as _,
-            val.len() as _,
-            val.as_ptr() as _,
-            0
-        ),
-        Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
-    );
-    assert_eq!(
-        logic.storage_read(key.len() as _, key.as_ptr() as _, 0),
-        Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
-    );
-    assert_eq!(
-        logic.storage_remove(key.len() as _, key.as_ptr() as _, 0),
-        Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
-    );
+    for mut logic_builder in [VMLogicBuilder::default(), VMLogicBuilder::with_trie()] {
+        let mut key = "a".repeat(1024).as_bytes().to_vec();
+        let val = b"hello";
+        let limit = key.len() as u64;
+        logic_builder.config.limit_config.max_length_storage_key = limit;
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        // Under the limit. Valid calls.
+        logic
+            .storage_has_key(key.len() as _, key.as_ptr() as _)
+            .expect("storage_has_key: key length is under the limit");
+        logic
+            .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+            .expect("storage_read: key length is under the limit");
+        logic
+            .storage_read(key.len() as _, key.as_ptr() as _, 0)
+            .expect("storage_read: key length is under the limit");
+        logic
+            .storage_remove(key.len() as _, key.as_ptr() as _, 0)
+            .expect("storage_remove: key length is under the limit");
+        // Over the limit. Invalid calls.
+        key.push(b'a');
+        assert_eq!(
+            logic.storage_has_key(key.len() as _, key.as_ptr() as _),
+            Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
+        );
+        assert_eq!(
+            logic.storage_write(
+                key.len() as _,
+                key.as_ptr() as _,
+                val.len() as _,
+                val.as_ptr() as _,
+                0
+            ),
+            Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
+        );
+        assert_eq!(
+            logic.storage_read(key.len() as _, key.as_ptr() as _, 0),
+            Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
+        );
+        assert_eq!(
+            logic.storage_remove(key.len() as _, key.as_ptr() as _, 0),
+            Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
+        );
+    }
 }
 
 #[test]
 fn test_value_length_limit() {
-    let mut logic_builder = VMLogicBuilder::default();
-    let mut val = "a".repeat(1024).as_bytes().to_vec();
-    logic_builder.config.limit_config.max_length_storage_value = val.len() as u64;
-    let mut logic = logic_builder.build(get_context(vec![], false));
-    let key = b"hello";
-    logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
-        .expect("Value length is under the limit");
-    val.push(b'a');
-    assert_eq!(
-        logic.storage_write(
-            key.len() as _,
-            key.as_ptr() as _,
-            val.len() as _,
-            val.as_ptr() as _,
-            0
-        ),
-        Err(HostError::ValueLengthExceeded {
-            length: val.len() as u64,
-            limit: logic_builder.config.limit_config.max_length_storage_value
-        }
-        .into())
-    );
+    for mut logic_builder in [VMLogicBuilder::default(), VMLogicBuilder::with_trie()] {
+        let mut val = "a".repeat(1024).as_bytes().to_vec();
+        let limit = val.len() as u64;
+        logic_builder.config.limit_config.max_length_storage_value = limit;
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let key = b"hello";
+        logic
+            .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+            .expect("Value length is under the limit");
+        val.push(b'a');
+        assert_eq!(
+            logic.storage_write(
+                key.len() as _,
+                key.as_ptr() as _,
+                val.len() as _,
+                val.as_ptr() as _,
+                0
+            ),
+            Err(HostError::ValueLengthExceeded { length: val.len() as u64, limit }.into())
+        );
+    }
 }
 
 #[test]
@@ -832,6 +1069,66 @@ fn test_return_value_limit() {
     );
 }
 
+#[test]
+fn test_value_return_append_limit() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let val = "a".repeat(1024).as_bytes().to_vec();
+    logic_builder.config.limit_config.max_length_returned_data = val.len() as u64;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    logic
+        .value_return_append(1000, val.as_ptr() as _)
+        .expect("Returned value length is under the limit");
+    logic
+        .value_return_append(24, val.as_ptr() as _)
+        .expect("Returned value length is at the limit");
+    assert_eq!(
+        logic.value_return_append(1, val.as_ptr() as _),
+        Err(HostError::ReturnedValueLengthExceeded {
+            length: val.len() as u64 + 1,
+            limit: logic_builder.config.limit_config.max_length_returned_data
+        }
+        .into())
+    );
+
+    // `value_return` still replaces the return value.
+    logic.value_return(1, val.as_ptr() as _).expect("Returned value length is under the limit");
+    logic
+        .value_return_append(1, val.as_ptr() as _)
+        .expect("Returned value length is under the limit");
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert_eq!(outcome.return_data, ReturnData::Value(b"aa".to_vec()));
+}
+
+#[test]
+fn test_value_return_append_same_as_value_return() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let val = "abcdefghij".repeat(100).as_bytes().to_vec();
+    let mut context = get_context(vec![], false);
+    context.output_data_receivers =
+        vec![context.current_account_id.clone(), "bob.near".parse().unwrap()];
+
+    let mut logic = logic_builder.build(context.clone());
+    logic.value_return(val.len() as _, val.as_ptr() as _).unwrap();
+    let outcome = logic.compute_outcome_and_distribute_gas();
+
+    let parts = [0..10, 10..500, 500..val.len()];
+    let mut logic = logic_builder.build(context);
+    for part in parts.iter().cloned() {
+        let part = &val[part];
+        logic.value_return_append(part.len() as _, part.as_ptr() as _).unwrap();
+    }
+    let appended_outcome = logic.compute_outcome_and_distribute_gas();
+
+    assert_eq!(appended_outcome.return_data, outcome.return_data);
+    assert_eq!(appended_outcome.return_data, ReturnData::Value(val));
+    // Per-byte costs are the same, only the additional calls and memory reads are charged on
+    // top of `value_return`.
+    let ext_costs = &logic_builder.config.ext_costs;
+    let extra = (ext_costs.base + ext_costs.read_memory_base) * (parts.len() as u64 - 1);
+    assert_eq!(appended_outcome.burnt_gas, outcome.burnt_gas + extra);
+    assert_eq!(appended_outcome.used_gas, outcome.used_gas + extra);
+}
+
 #[test]
 fn test_contract_size_limit() {
     let mut logic_builder = VMLogicBuilder::default();
@@ -859,3 +1156,48 @@ fn test_contract_size_limit() {
         .into())
     );
 }
+
+#[test]
+fn test_epoch_height_and_id() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut context = get_context(vec![], false);
+    context.epoch_height = 7;
+    context.epoch_id = EpochId(CryptoHash::hash_bytes(b"epoch"));
+    let mut logic = logic_builder.build(context);
+
+    assert_eq!(logic.epoch_height(), Ok(7));
+    logic.epoch_id(0).unwrap();
+    assert_costs(map! {
+        ExtCosts::base: 2,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 32,
+    });
+    let res = [0u8; 32];
+    logic.read_register(0, res.as_ptr() as _).unwrap();
+    assert_eq!(res, CryptoHash::hash_bytes(b"epoch").0);
+}
+
+#[test]
+fn test_validator_public_key() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let public_key = PublicKey::from_seed(KeyType::ED25519, "alice.near");
+    logic_builder
+        .ext
+        .validator_public_keys
+        .insert("alice.near".parse().unwrap(), public_key.clone());
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let validator = b"alice.near";
+    assert_eq!(logic.validator_public_key(validator.len() as _, validator.as_ptr() as _, 0), Ok(1));
+    let expected = public_key.try_to_vec().unwrap();
+    let res = vec![0u8; expected.len()];
+    logic.read_register(0, res.as_ptr() as _).unwrap();
+    assert_eq!(res, expected);
+
+    let not_validator = b"bob.near";
+    assert_eq!(
+        logic.validator_public_key(not_validator.len() as _, not_validator.as_ptr() as _, 1),
+        Ok(0)
+    );
+    assert_eq!(logic.register_len(1), Ok(u64::MAX));
+}
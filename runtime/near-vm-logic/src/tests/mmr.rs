@@ -0,0 +1,279 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts, VMLogic};
+use borsh::BorshSerialize;
+use ckb_merkle_mountain_range::util::MemStore;
+use ckb_merkle_mountain_range::{leaf_index_to_pos, Merge, MMR};
+use near_crypto::{KeyType, SecretKey};
+use near_vm_errors::{HostError, VMLogicError};
+use sha3::Digest;
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    sha3::Keccak256::digest(data).into()
+}
+
+struct MergeKeccak;
+
+impl Merge for MergeKeccak {
+    type Item = [u8; 32];
+
+    fn merge(left: &[u8; 32], right: &[u8; 32]) -> ckb_merkle_mountain_range::Result<[u8; 32]> {
+        Ok(keccak256(&[left.as_slice(), right.as_slice()].concat()))
+    }
+}
+
+fn leaf(index: u64) -> Vec<u8> {
+    format!("leaf {index}").into_bytes()
+}
+
+/// Builds an MMR of `leaf_count` leaves and returns its root along with the
+/// proof of each leaf.
+fn mmr_proofs(leaf_count: u64) -> ([u8; 32], Vec<Vec<u8>>) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeKeccak, _>::new(0, &store);
+    for index in 0..leaf_count {
+        mmr.push(keccak256(&leaf(index))).unwrap();
+    }
+    let root = mmr.get_root().unwrap();
+    let proofs = (0..leaf_count)
+        .map(|index| {
+            let proof = mmr.gen_proof(vec![leaf_index_to_pos(index)]).unwrap();
+            proof.proof_items().concat()
+        })
+        .collect();
+    (root, proofs)
+}
+
+fn verify_mmr(
+    logic: &mut VMLogic,
+    root: &[u8],
+    leaf: &[u8],
+    proof: &[u8],
+    leaf_index: u64,
+    leaf_count: u64,
+) -> Result<u8, VMLogicError> {
+    logic.verify_mmr_proof(
+        root.len() as _,
+        root.as_ptr() as _,
+        leaf.len() as _,
+        leaf.as_ptr() as _,
+        proof.len() as _,
+        proof.as_ptr() as _,
+        leaf_index,
+        leaf_count,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_verify_mmr_proof() {
+    for leaf_count in [1, 2, 3, 7, 8, 11, 32] {
+        let (root, proofs) = mmr_proofs(leaf_count);
+        for (leaf_index, proof) in proofs.iter().enumerate() {
+            let leaf_index = leaf_index as u64;
+            let leaf = leaf(leaf_index);
+            let mut logic_builder = VMLogicBuilder::default();
+            let mut logic = logic_builder.build(get_context(vec![], false));
+
+            assert_eq!(
+                verify_mmr(&mut logic, &root, &leaf, proof, leaf_index, leaf_count),
+                Ok(1),
+                "{leaf_index} of {leaf_count}"
+            );
+            assert_costs(map! {
+                ExtCosts::base: 1,
+                ExtCosts::read_memory_base: 3,
+                ExtCosts::read_memory_byte: (32 + leaf.len() + proof.len()) as u64,
+                ExtCosts::write_memory_base: 1,
+                ExtCosts::write_memory_byte: 1,
+                ExtCosts::read_register_base: 1,
+                ExtCosts::read_register_byte: 1,
+                ExtCosts::write_register_base: 1,
+                ExtCosts::write_register_byte: 1,
+                ExtCosts::verify_mmr_proof_base: 1,
+                ExtCosts::verify_mmr_proof_byte: leaf.len() as u64,
+                ExtCosts::verify_mmr_proof_item: (proof.len() / 32) as u64,
+            });
+            reset_costs_counter();
+        }
+    }
+}
+
+#[test]
+fn test_verify_mmr_proof_invalid() {
+    let leaf_count = 11;
+    let (root, proofs) = mmr_proofs(leaf_count);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    for leaf_index in [0, 5, 10] {
+        let proof = &proofs[leaf_index as usize];
+        let leaf = leaf(leaf_index);
+        assert_eq!(verify_mmr(&mut logic, &root, &leaf, proof, leaf_index, leaf_count), Ok(1));
+
+        // Another leaf.
+        assert_eq!(verify_mmr(&mut logic, &root, b"leaf", proof, leaf_index, leaf_count), Ok(0));
+        // Another position.
+        let other_index = (leaf_index + 1) % leaf_count;
+        assert_eq!(verify_mmr(&mut logic, &root, &leaf, proof, other_index, leaf_count), Ok(0));
+        // Index out of range.
+        assert_eq!(verify_mmr(&mut logic, &root, &leaf, proof, leaf_count, leaf_count), Ok(0));
+        // Missing and extra proof items.
+        let truncated = &proof[..proof.len() - 32];
+        assert_eq!(verify_mmr(&mut logic, &root, &leaf, truncated, leaf_index, leaf_count), Ok(0));
+        let extended = [proof.as_slice(), &root].concat();
+        assert_eq!(verify_mmr(&mut logic, &root, &leaf, &extended, leaf_index, leaf_count), Ok(0));
+        // Another root.
+        let other_root = keccak256(&root);
+        assert_eq!(
+            verify_mmr(&mut logic, &other_root, &leaf, proof, leaf_index, leaf_count),
+            Ok(0)
+        );
+    }
+
+    // Another MMR size, in which the last leaf is no longer a peak.
+    assert_eq!(verify_mmr(&mut logic, &root, &leaf(10), &proofs[10], 10, leaf_count + 1), Ok(0));
+
+    assert_eq!(
+        verify_mmr(&mut logic, &root[1..], &leaf(0), &proofs[0], 0, leaf_count),
+        Err(HostError::MmrInvalidInput {
+            msg: "The length of the root: 31, must be 32 bytes".to_string()
+        }
+        .into())
+    );
+    let proof = &proofs[0][1..];
+    assert_eq!(
+        verify_mmr(&mut logic, &root, &leaf(0), proof, 0, leaf_count),
+        Err(HostError::MmrInvalidInput {
+            msg: format!("The length of the proof: {}, is not a multiple of 32", proof.len())
+        }
+        .into())
+    );
+}
+
+fn authorities(num_authorities: u64) -> (Vec<SecretKey>, Vec<u8>) {
+    let keys: Vec<_> = (0..num_authorities)
+        .map(|i| SecretKey::from_seed(KeyType::SECP256K1, &format!("authority {i}")))
+        .collect();
+    let public_keys = keys.iter().flat_map(|key| key.public_key().key_data().to_vec()).collect();
+    (keys, public_keys)
+}
+
+/// Signatures of `commitment` by the given authorities, with recovery ids in
+/// the Ethereum style if `eth_v` is set.
+fn sign(keys: &[SecretKey], signers: &[u32], commitment: &[u8], eth_v: bool) -> Vec<u8> {
+    let message = keccak256(commitment);
+    signers
+        .iter()
+        .flat_map(|&index| {
+            let mut signature = keys[index as usize].sign(&message).try_to_vec().unwrap();
+            // Drop the key type.
+            signature.remove(0);
+            if eth_v {
+                signature[64] += 27;
+            }
+            [index.to_le_bytes().as_slice(), &signature].concat()
+        })
+        .collect()
+}
+
+fn verify_beefy(
+    authorities: &[u8],
+    commitment: &[u8],
+    signatures: &[u8],
+) -> Result<u8, VMLogicError> {
+    // Each call gets its own logic as signatures quickly add up to the gas limit.
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    logic.verify_beefy_commitment(
+        authorities.len() as _,
+        authorities.as_ptr() as _,
+        commitment.len() as _,
+        commitment.as_ptr() as _,
+        signatures.len() as _,
+        signatures.as_ptr() as _,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_verify_beefy_commitment() {
+    let (keys, authorities) = authorities(4);
+    let commitment = b"payload, block number and validator set id";
+    let signatures = sign(&keys, &[0, 2, 3], commitment, false);
+
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(1));
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 3,
+        ExtCosts::read_memory_byte: (authorities.len() + commitment.len() + signatures.len()) as u64,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 1,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 1,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 1,
+        ExtCosts::verify_beefy_commitment_base: 1,
+        ExtCosts::verify_beefy_commitment_byte: commitment.len() as u64,
+        ExtCosts::verify_beefy_commitment_signature: 3,
+    });
+
+    let signatures = sign(&keys, &[3, 1, 0, 2], commitment, true);
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(1));
+}
+
+#[test]
+fn test_verify_beefy_commitment_invalid() {
+    let (keys, authorities) = authorities(6);
+    let commitment = b"commitment";
+
+    // Exactly 2/3 of the authorities is not enough.
+    let signatures = sign(&keys, &[0, 1, 2, 3], commitment, false);
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(0));
+    let signatures = sign(&keys, &[0, 1, 2, 3, 4], commitment, false);
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(1));
+
+    // Another commitment.
+    assert_eq!(verify_beefy(&authorities, b"other", &signatures), Ok(0));
+
+    // Duplicate signature.
+    let signatures = sign(&keys, &[0, 1, 2, 3, 3], commitment, false);
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(0));
+
+    // Signature attributed to another authority.
+    let mut signatures = sign(&keys, &[0, 1, 2, 3, 4], commitment, false);
+    signatures[0] = 5;
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(0));
+
+    // Unknown authority.
+    let mut signatures = sign(&keys, &[0, 1, 2, 3, 4], commitment, false);
+    signatures[0] = 6;
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(0));
+
+    // Invalid recovery id.
+    let mut signatures = sign(&keys, &[0, 1, 2, 3, 4], commitment, false);
+    signatures[68] = 4;
+    assert_eq!(verify_beefy(&authorities, commitment, &signatures), Ok(0));
+
+    assert_eq!(
+        verify_beefy(&authorities[1..], commitment, &signatures),
+        Err(HostError::BeefyInvalidInput {
+            msg: "The length of the authorities: 383, is not a multiple of 64".to_string()
+        }
+        .into())
+    );
+    assert_eq!(
+        verify_beefy(&authorities, commitment, &signatures[1..]),
+        Err(HostError::BeefyInvalidInput {
+            msg: "The length of the signatures: 344, is not a multiple of 69".to_string()
+        }
+        .into())
+    );
+}
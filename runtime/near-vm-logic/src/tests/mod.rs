@@ -1,13 +1,22 @@
 mod alt_bn128;
+mod block_hash;
+mod commitment_proof;
 mod context;
 mod fixtures;
 mod gas_counter;
 mod helpers;
 mod iterators;
+mod light_client;
 mod miscs;
+mod mmr;
+mod outcome_proof;
 mod promises;
 mod registers;
+mod state_proof;
 mod storage_read_write;
 mod storage_usage;
+mod tendermint;
+mod trie_external;
+mod vectors;
 mod view_method;
 mod vm_logic_builder;
@@ -0,0 +1,130 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts, VMLogic};
+use borsh::BorshSerialize;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::merkle::{merklize, Direction, MerklePath};
+use near_vm_errors::{HostError, VMLogicError};
+
+/// Leaves in the shape of `ExecutionOutcomeWithId::to_hashes`, merklized the
+/// same way chunk outcome roots are.
+fn outcome_hashes(num_outcomes: u8) -> Vec<Vec<CryptoHash>> {
+    (0..num_outcomes).map(|i| vec![hash(&[i]), hash(&[i, i])]).collect()
+}
+
+fn verify(
+    logic: &mut VMLogic,
+    outcome_hash: &CryptoHash,
+    proof: &MerklePath,
+    root: &CryptoHash,
+) -> Result<u8, VMLogicError> {
+    let proof = proof.try_to_vec().unwrap();
+    logic.verify_outcome_proof(
+        outcome_hash.as_ref().as_ptr() as _,
+        proof.len() as _,
+        proof.as_ptr() as _,
+        root.as_ref().as_ptr() as _,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_verify_outcome_proof() {
+    for num_outcomes in [1, 2, 5, 8] {
+        let outcomes = outcome_hashes(num_outcomes);
+        let (root, paths) = merklize(&outcomes);
+        for (outcome, path) in outcomes.iter().zip(paths) {
+            let mut logic_builder = VMLogicBuilder::default();
+            let mut logic = logic_builder.build(get_context(vec![], false));
+
+            let outcome_hash = CryptoHash::hash_borsh(outcome);
+            assert_eq!(verify(&mut logic, &outcome_hash, &path, &root), Ok(1));
+            assert_costs(map! {
+                ExtCosts::base: 1,
+                ExtCosts::read_memory_base: 3,
+                ExtCosts::read_memory_byte: 64 + path.try_to_vec().unwrap().len() as u64,
+                ExtCosts::write_memory_base: 1,
+                ExtCosts::write_memory_byte: 1,
+                ExtCosts::read_register_base: 1,
+                ExtCosts::read_register_byte: 1,
+                ExtCosts::write_register_base: 1,
+                ExtCosts::write_register_byte: 1,
+                ExtCosts::verify_outcome_proof_base: 1,
+                ExtCosts::verify_outcome_proof_item: path.len() as u64,
+            });
+            reset_costs_counter();
+        }
+    }
+}
+
+#[test]
+fn test_verify_outcome_proof_invalid() {
+    let outcomes = outcome_hashes(5);
+    let (root, paths) = merklize(&outcomes);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let outcome_hash = CryptoHash::hash_borsh(&outcomes[1]);
+    let path = &paths[1];
+    assert_eq!(verify(&mut logic, &outcome_hash, path, &root), Ok(1));
+
+    // Path of another outcome.
+    assert_eq!(verify(&mut logic, &outcome_hash, &paths[2], &root), Ok(0));
+    // Another root.
+    assert_eq!(verify(&mut logic, &outcome_hash, path, &hash(b"root")), Ok(0));
+    // Hash of the outcome itself rather than of its merkle leaf.
+    assert_eq!(verify(&mut logic, &outcomes[1][0], path, &root), Ok(0));
+
+    // Flipped direction.
+    let mut flipped = path.clone();
+    flipped[0].direction = match flipped[0].direction {
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+    };
+    assert_eq!(verify(&mut logic, &outcome_hash, &flipped, &root), Ok(0));
+
+    // Truncated path.
+    let truncated = path[..path.len() - 1].to_vec();
+    assert_eq!(verify(&mut logic, &outcome_hash, &truncated, &root), Ok(0));
+
+    // A proof which fails to decode is not an error.
+    let proof = path.try_to_vec().unwrap();
+    let proof = &proof[..proof.len() - 1];
+    logic
+        .verify_outcome_proof(
+            outcome_hash.as_ref().as_ptr() as _,
+            proof.len() as _,
+            proof.as_ptr() as _,
+            root.as_ref().as_ptr() as _,
+            0,
+        )
+        .unwrap();
+    let mut res = [1u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _).unwrap();
+    assert_eq!(res[0], 0);
+}
+
+#[test]
+fn test_verify_outcome_proof_length_limit() {
+    let outcomes = outcome_hashes(8);
+    let (root, paths) = merklize(&outcomes);
+    let outcome_hash = CryptoHash::hash_borsh(&outcomes[0]);
+    let limit = paths[0].len() as u64;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_outcome_proof_length = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(verify(&mut logic, &outcome_hash, &paths[0], &root), Ok(1));
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_outcome_proof_length = limit - 1;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, &outcome_hash, &paths[0], &root),
+        Err(HostError::OutcomeProofTooLong { length: limit, limit: limit - 1 }.into())
+    );
+}
@@ -2,10 +2,11 @@ use crate::tests::fixtures::get_context;
 use crate::tests::helpers::*;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
 use crate::types::PromiseResult;
-use crate::VMLogic;
+use crate::{map, ExtCosts, HostError, VMLogic};
 use borsh::BorshSerialize;
 use near_account_id::AccountId;
 use near_crypto::PublicKey;
+use near_primitives::hash::hash;
 use near_primitives::transaction::Action;
 use serde::Serialize;
 use serde_json;
@@ -46,6 +47,65 @@ fn test_promise_results() {
     assert_eq!(&buffer, b"test", "Only promise with result should write data into register");
 }
 
+#[test]
+fn test_promise_result_metadata() {
+    let promise_results = vec![
+        PromiseResult::Successful(vec![1; 10]),
+        PromiseResult::Successful(vec![2; 1000]),
+        PromiseResult::Successful(vec![]),
+        PromiseResult::Failed,
+    ];
+    let data_ids: Vec<_> = (0..promise_results.len() as u8).map(|i| hash(&[i])).collect();
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.promise_results = promise_results;
+    let mut context = get_context(vec![], false);
+    context.input_data_ids = data_ids.clone();
+    let mut logic = logic_builder.build(context);
+
+    for (result_idx, len) in [10, 1000, 0, 0].into_iter().enumerate() {
+        reset_costs_counter();
+        assert_eq!(logic.promise_result_len(result_idx as u64), Ok(len));
+        assert_costs(map! { ExtCosts::base: 1 });
+    }
+    assert_eq!(
+        logic.promise_result_len(4),
+        Err(HostError::InvalidPromiseResultIndex { result_idx: 4 }.into())
+    );
+
+    for (result_idx, data_id) in data_ids.iter().enumerate() {
+        reset_costs_counter();
+        logic.promise_result_receipt_id(result_idx as u64, 0).unwrap();
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 32,
+        });
+        let buffer = [0u8; 32];
+        logic.read_register(0, buffer.as_ptr() as u64).unwrap();
+        assert_eq!(&buffer, data_id.as_ref());
+    }
+    assert_eq!(
+        logic.promise_result_receipt_id(4, 0),
+        Err(HostError::InvalidPromiseResultIndex { result_idx: 4 }.into())
+    );
+}
+
+#[test]
+fn test_promise_result_metadata_view() {
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.promise_results = vec![PromiseResult::Successful(vec![1; 10])];
+    let mut logic = logic_builder.build(get_context(vec![], true));
+    assert_eq!(
+        logic.promise_result_len(0),
+        Err(HostError::ProhibitedInView { method_name: "promise_result_len".to_string() }.into())
+    );
+    assert_eq!(
+        logic.promise_result_receipt_id(0, 0),
+        Err(HostError::ProhibitedInView { method_name: "promise_result_receipt_id".to_string() }
+            .into())
+    );
+}
+
 #[test]
 fn test_promise_batch_action_function_call() {
     let mut logic_builder = VMLogicBuilder::default();
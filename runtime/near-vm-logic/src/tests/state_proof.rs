@@ -0,0 +1,331 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vectors::load_vectors;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, with_ext_cost_counter, ExtCosts, VMLogic};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::hash;
+use near_primitives::trie_key::TrieKey;
+use near_vm_errors::{HostError, VMLogicError};
+use serde::Deserialize;
+
+/// Proofs recorded by `Trie::recording_reads` while reading contract data of
+/// `alice.near` from a trie holding the contract data of the `test_view_state`
+/// integration test. An empty `value` denotes a proof of absence.
+#[derive(Deserialize)]
+struct StateProofTest {
+    name: String,
+    #[serde(with = "hex::serde")]
+    root: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    proof: Vec<u8>,
+    number_of_nodes: u64,
+    account_id: String,
+    #[serde(with = "hex::serde")]
+    key: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    value: Vec<u8>,
+}
+
+fn state_proof_tests() -> Vec<StateProofTest> {
+    load_vectors("state-proof-tests.json")
+}
+
+fn verify(
+    logic: &mut VMLogic,
+    root: &[u8],
+    proof: &[u8],
+    account_id: &str,
+    key: &[u8],
+    value: &[u8],
+) -> Result<u8, VMLogicError> {
+    logic.verify_near_state_proof(
+        root.as_ptr() as _,
+        proof.len() as _,
+        proof.as_ptr() as _,
+        account_id.len() as _,
+        account_id.as_ptr() as _,
+        key.len() as _,
+        key.as_ptr() as _,
+        value.len() as _,
+        value.as_ptr() as _,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_verify_near_state_proof() {
+    for StateProofTest { name, root, proof, number_of_nodes, account_id, key, value } in
+        state_proof_tests()
+    {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+
+        assert_eq!(verify(&mut logic, &root, &proof, &account_id, &key, &value), Ok(1), "{name}");
+
+        let inputs = if value.is_empty() { 4 } else { 5 };
+        let read_bytes = root.len() + proof.len() + account_id.len() + key.len() + value.len();
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: inputs,
+            ExtCosts::read_memory_byte: read_bytes as u64,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 1,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 1,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 1,
+            ExtCosts::utf8_decoding_base: 1,
+            ExtCosts::utf8_decoding_byte: account_id.len() as u64,
+            ExtCosts::verify_near_state_proof_base: 1,
+            ExtCosts::verify_near_state_proof_byte: proof.len() as u64,
+            ExtCosts::verify_near_state_proof_node: number_of_nodes,
+        });
+        reset_costs_counter();
+    }
+}
+
+#[test]
+fn test_verify_near_state_proof_invalid() {
+    for StateProofTest { name, root, proof, account_id, key, value, .. } in state_proof_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+
+        let mut bad_root = root.clone();
+        bad_root[0] ^= 1;
+        assert_eq!(
+            verify(&mut logic, &bad_root, &proof, &account_id, &key, &value),
+            Ok(0),
+            "{name}"
+        );
+
+        if value.is_empty() {
+            assert_eq!(verify(&mut logic, &root, &proof, &account_id, &key, b"x"), Ok(0), "{name}");
+        } else {
+            assert_eq!(verify(&mut logic, &root, &proof, &account_id, &key, b"x"), Ok(0), "{name}");
+            assert_eq!(verify(&mut logic, &root, &proof, &account_id, &key, &[]), Ok(0), "{name}");
+            // The same key under another account is stored at a different path.
+            assert_eq!(
+                verify(&mut logic, &root, &proof, "bob.near", &key, &value),
+                Ok(0),
+                "{name}"
+            );
+        }
+
+        // Truncated proofs fail to decode, which is not an error.
+        let truncated = &proof[..proof.len() / 2];
+        assert_eq!(
+            verify(&mut logic, &root, truncated, &account_id, &key, &value),
+            Ok(0),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_verify_near_state_proof_nodes_limit() {
+    let test = &state_proof_tests()[0];
+    let limit = test.number_of_nodes;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_number_state_proof_nodes = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, &test.root, &test.proof, &test.account_id, &test.key, &test.value),
+        Ok(1)
+    );
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_number_state_proof_nodes = limit - 1;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, &test.root, &test.proof, &test.account_id, &test.key, &test.value),
+        Err(HostError::NumberStateProofNodesExceeded { number_of_nodes: limit, limit: limit - 1 }
+            .into())
+    );
+}
+
+#[test]
+fn test_verify_near_state_proof_size_limit() {
+    let test = &state_proof_tests()[0];
+    let limit = test.proof.len() as u64;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_state_proof_size = limit;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, &test.root, &test.proof, &test.account_id, &test.key, &test.value),
+        Ok(1)
+    );
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_state_proof_size = limit - 1;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, &test.root, &test.proof, &test.account_id, &test.key, &test.value),
+        Err(HostError::StateProofTooLarge { size: limit, limit: limit - 1 }.into())
+    );
+}
+
+#[test]
+fn test_verify_near_state_proof_nodes_limit_before_decoding() {
+    let test = &state_proof_tests()[0];
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    // Claims more nodes than the limit without carrying any of them.
+    let proof = u32::MAX.to_le_bytes();
+    assert_eq!(
+        verify(&mut logic, &test.root, &proof, &test.account_id, &test.key, &test.value),
+        Err(HostError::NumberStateProofNodesExceeded {
+            number_of_nodes: u32::MAX as u64,
+            limit: 512
+        }
+        .into())
+    );
+    with_ext_cost_counter(|cc| assert_eq!(cc.get(&ExtCosts::verify_near_state_proof_node), None));
+}
+
+fn verify_status(
+    logic: &mut VMLogic,
+    root: &[u8],
+    proof: &[u8],
+    account_id: &str,
+    key: &[u8],
+    value: &[u8],
+) -> Result<u64, VMLogicError> {
+    logic.verify_near_state_proof_status(
+        root.as_ptr() as _,
+        proof.len() as _,
+        proof.as_ptr() as _,
+        account_id.len() as _,
+        account_id.as_ptr() as _,
+        key.len() as _,
+        key.as_ptr() as _,
+        value.len() as _,
+        value.as_ptr() as _,
+    )
+}
+
+#[test]
+fn test_verify_near_state_proof_status() {
+    let tests = state_proof_tests();
+    let exists = tests.iter().find(|test| !test.value.is_empty()).unwrap();
+    let absent = tests.iter().find(|test| test.value.is_empty()).unwrap();
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    for test in [exists, absent] {
+        let StateProofTest { name, root, proof, number_of_nodes, account_id, key, value } = test;
+        assert_eq!(verify_status(&mut logic, root, proof, account_id, key, value), Ok(0), "{name}");
+
+        let inputs = if value.is_empty() { 4 } else { 5 };
+        let read_bytes = root.len() + proof.len() + account_id.len() + key.len() + value.len();
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: inputs,
+            ExtCosts::read_memory_byte: read_bytes as u64,
+            ExtCosts::utf8_decoding_base: 1,
+            ExtCosts::utf8_decoding_byte: account_id.len() as u64,
+            ExtCosts::verify_near_state_proof_base: 1,
+            ExtCosts::verify_near_state_proof_byte: proof.len() as u64,
+            ExtCosts::verify_near_state_proof_node: *number_of_nodes,
+        });
+    }
+
+    // Value mismatch, both for another value and for an expected absence.
+    let StateProofTest { root, proof, account_id, key, .. } = exists;
+    assert_eq!(verify_status(&mut logic, root, proof, account_id, key, b"x"), Ok(1));
+    assert_eq!(verify_status(&mut logic, root, proof, account_id, key, &[]), Ok(1));
+
+    // Key absent.
+    let StateProofTest { root, proof, account_id, key, .. } = absent;
+    assert_eq!(verify_status(&mut logic, root, proof, account_id, key, b"x"), Ok(2));
+
+    // Malformed proofs: truncated, an undecodable root node and a proof
+    // holding only the root node.
+    let StateProofTest { root, proof, account_id, key, value, .. } = exists;
+    let truncated = &proof[..proof.len() / 2];
+    assert_eq!(verify_status(&mut logic, root, truncated, account_id, key, value), Ok(3));
+
+    let bad_node = vec![0xff; 16];
+    let bad_proof = vec![bad_node.clone()].try_to_vec().unwrap();
+    let bad_root = hash(&bad_node);
+    assert_eq!(
+        verify_status(&mut logic, bad_root.as_ref(), &bad_proof, account_id, key, value),
+        Ok(3)
+    );
+
+    // A leaf claiming a 4 GiB key is rejected without allocating the key.
+    let huge_key_node =
+        [&[0u8][..], &u32::MAX.to_le_bytes(), &[0; 4], hash(b"value").as_ref(), &[0; 8]].concat();
+    let huge_key_proof = vec![huge_key_node.clone()].try_to_vec().unwrap();
+    let huge_key_root = hash(&huge_key_node);
+    assert_eq!(
+        verify_status(&mut logic, huge_key_root.as_ref(), &huge_key_proof, account_id, key, value),
+        Ok(3)
+    );
+
+    let root_only: Vec<Vec<u8>> = Vec::<Vec<u8>>::try_from_slice(proof)
+        .unwrap()
+        .into_iter()
+        .filter(|node| hash(node).as_ref() == root.as_slice())
+        .collect();
+    assert_eq!(root_only.len(), 1);
+    let root_only = root_only.try_to_vec().unwrap();
+    assert_eq!(verify_status(&mut logic, root, &root_only, account_id, key, value), Ok(3));
+
+    // Root mismatch.
+    let mut other_root = root.clone();
+    other_root[0] ^= 1;
+    assert_eq!(verify_status(&mut logic, &other_root, proof, account_id, key, value), Ok(4));
+}
+
+#[test]
+fn test_contract_data_trie_prefix() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let account_id = b"alice.near";
+    let key = b"STATE";
+
+    logic
+        .contract_data_trie_prefix(
+            account_id.len() as _,
+            account_id.as_ptr() as _,
+            key.len() as _,
+            key.as_ptr() as _,
+            0,
+        )
+        .unwrap();
+    let expected =
+        TrieKey::ContractData { account_id: "alice.near".parse().unwrap(), key: key.to_vec() }
+            .to_vec();
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 2,
+        ExtCosts::read_memory_byte: (account_id.len() + key.len()) as u64,
+        ExtCosts::utf8_decoding_base: 1,
+        ExtCosts::utf8_decoding_byte: account_id.len() as u64,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: expected.len() as u64,
+    });
+    let res = vec![0u8; expected.len()];
+    logic.read_register(0, res.as_ptr() as _).unwrap();
+    assert_eq!(res, expected);
+
+    let bad_account_id = b"Alice..near";
+    assert_eq!(
+        logic.contract_data_trie_prefix(
+            bad_account_id.len() as _,
+            bad_account_id.as_ptr() as _,
+            key.len() as _,
+            key.as_ptr() as _,
+            1,
+        ),
+        Err(VMLogicError::HostError(HostError::InvalidAccountId))
+    );
+    assert_eq!(logic.register_len(1), Ok(u64::MAX));
+}
@@ -1,6 +1,9 @@
 use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
-use crate::External;
+use crate::{map, ExtCosts, External};
+use borsh::BorshSerialize;
+use near_vm_errors::HostError;
 
 #[test]
 fn test_storage_write_with_register() {
@@ -71,3 +74,61 @@ fn test_storage_has_key_with_register() {
 
     assert_eq!(logic.storage_has_key(u64::MAX, 1 as _), Ok(1));
 }
+
+#[test]
+fn test_storage_read_with_proof() {
+    let mut logic_builder = VMLogicBuilder::default();
+
+    let key: &[u8] = b"foo";
+    let val: &[u8] = b"bar";
+    logic_builder.ext.storage_set(key, val).unwrap();
+    let mut logic = logic_builder.build(get_context(vec![], true));
+
+    assert_eq!(logic.storage_read_with_proof(key.len() as _, key.as_ptr() as _, 0, 1), Ok(1));
+    // The mocked proof is the value itself.
+    let proof = vec![val.to_vec()].try_to_vec().unwrap();
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 1,
+        ExtCosts::read_memory_byte: key.len() as u64,
+        ExtCosts::storage_read_base: 1,
+        ExtCosts::storage_read_key_byte: key.len() as u64,
+        ExtCosts::storage_read_value_byte: val.len() as u64,
+        ExtCosts::storage_read_proof_byte: proof.len() as u64,
+        ExtCosts::write_register_base: 2,
+        ExtCosts::write_register_byte: (val.len() + proof.len()) as u64,
+        ExtCosts::touching_trie_node: 0,
+        ExtCosts::read_cached_trie_node: 0,
+    });
+    let res = [0u8; 3];
+    logic.read_register(0, res.as_ptr() as _).unwrap();
+    assert_eq!(&res, b"bar");
+    assert_eq!(logic.register_len(1), Ok(proof.len() as u64));
+
+    // Absent keys still get a proof, and leave the value register untouched.
+    let key: &[u8] = b"baz";
+    assert_eq!(logic.storage_read_with_proof(key.len() as _, key.as_ptr() as _, 2, 1), Ok(0));
+    assert_eq!(logic.register_len(1), Ok(Vec::<Vec<u8>>::new().try_to_vec().unwrap().len() as u64));
+    assert_eq!(logic.register_len(2), Ok(u64::MAX));
+
+    assert_eq!(
+        logic.storage_read_with_proof(key.len() as _, key.as_ptr() as _, 1, 1),
+        Err(HostError::MemoryAccessViolation.into())
+    );
+}
+
+#[test]
+fn test_storage_read_with_proof_in_transactions() {
+    let key: &[u8] = b"foo";
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        logic.storage_read_with_proof(key.len() as _, key.as_ptr() as _, 0, 1),
+        Err(HostError::StorageProofsDisabled.into())
+    );
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.allow_storage_proofs_in_transactions = true;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(logic.storage_read_with_proof(key.len() as _, key.as_ptr() as _, 0, 1), Ok(0));
+}
@@ -0,0 +1,156 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vectors::load_vectors;
+use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::{map, ExtCosts, VMLogic};
+use near_vm_errors::{HostError, VMLogicError};
+use serde::Deserialize;
+
+/// Commits at height 42 of `test-chain` signed by deterministic ed25519 keys,
+/// with sign bytes encoded independently of the `tendermint` module.
+/// `number_of_signatures` is the number of signatures verified before the
+/// outcome is known.
+#[derive(Deserialize)]
+struct TendermintTest {
+    name: String,
+    #[serde(with = "hex::serde")]
+    validators: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    commit: Vec<u8>,
+    chain_id: String,
+    trust_threshold_numerator: u64,
+    trust_threshold_denominator: u64,
+    valid: bool,
+    number_of_signatures: u64,
+}
+
+fn tendermint_tests() -> Vec<TendermintTest> {
+    load_vectors("tendermint-tests.json")
+}
+
+fn verify(
+    logic: &mut VMLogic,
+    validators: &[u8],
+    commit: &[u8],
+    chain_id: &str,
+    numerator: u64,
+    denominator: u64,
+) -> Result<u8, VMLogicError> {
+    logic.verify_tendermint_commit(
+        validators.len() as _,
+        validators.as_ptr() as _,
+        commit.len() as _,
+        commit.as_ptr() as _,
+        chain_id.len() as _,
+        chain_id.as_ptr() as _,
+        numerator,
+        denominator,
+        0,
+    )?;
+    let mut res = [0u8; 1];
+    logic.read_register(0, res.as_mut_ptr() as _)?;
+    Ok(res[0])
+}
+
+#[test]
+fn test_verify_tendermint_commit() {
+    for test in tendermint_tests() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+
+        assert_eq!(
+            verify(
+                &mut logic,
+                &test.validators,
+                &test.commit,
+                &test.chain_id,
+                test.trust_threshold_numerator,
+                test.trust_threshold_denominator,
+            ),
+            Ok(test.valid as u8),
+            "{}",
+            test.name
+        );
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: 3,
+            ExtCosts::read_memory_byte:
+                (test.validators.len() + test.commit.len() + test.chain_id.len()) as u64,
+            ExtCosts::write_memory_base: 1,
+            ExtCosts::write_memory_byte: 1,
+            ExtCosts::read_register_base: 1,
+            ExtCosts::read_register_byte: 1,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: 1,
+            ExtCosts::verify_tendermint_commit_base: 1,
+            ExtCosts::verify_tendermint_commit_byte:
+                (test.validators.len() + test.commit.len()) as u64,
+            ExtCosts::verify_tendermint_commit_signature: test.number_of_signatures,
+        });
+        reset_costs_counter();
+    }
+}
+
+#[test]
+fn test_verify_tendermint_commit_invalid() {
+    let test = &tendermint_tests()[0];
+    assert!(test.valid);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    // Truncated inputs fail to decode, which is not an error.
+    let truncated = &test.commit[..test.commit.len() / 2];
+    assert_eq!(verify(&mut logic, &test.validators, truncated, &test.chain_id, 2, 3), Ok(0));
+    let truncated = &test.validators[..test.validators.len() / 2];
+    assert_eq!(verify(&mut logic, truncated, &test.commit, &test.chain_id, 2, 3), Ok(0));
+
+    // Three out of four validators signed.
+    assert_eq!(verify(&mut logic, &test.validators, &test.commit, &test.chain_id, 3, 4), Ok(0));
+    assert_eq!(verify(&mut logic, &test.validators, &test.commit, &test.chain_id, 1, 1), Ok(0));
+}
+
+#[test]
+fn test_verify_tendermint_commit_invalid_trust_threshold() {
+    let test = &tendermint_tests()[0];
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    for (numerator, denominator) in [(1, 0), (1, 4), (4, 3)] {
+        assert_eq!(
+            verify(
+                &mut logic,
+                &test.validators,
+                &test.commit,
+                &test.chain_id,
+                numerator,
+                denominator
+            ),
+            Err(HostError::TendermintInvalidInput {
+                msg: format!(
+                    "The trust threshold: {}/{}, is not within [1/3, 1]",
+                    numerator, denominator
+                ),
+            }
+            .into())
+        );
+    }
+}
+
+#[test]
+fn test_verify_tendermint_commit_validators_limit() {
+    let test = &tendermint_tests()[0];
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_number_tendermint_validators = 4;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(verify(&mut logic, &test.validators, &test.commit, &test.chain_id, 2, 3), Ok(1));
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_number_tendermint_validators = 3;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    assert_eq!(
+        verify(&mut logic, &test.validators, &test.commit, &test.chain_id, 2, 3),
+        Err(HostError::NumberTendermintValidatorsExceeded { number_of_validators: 4, limit: 3 }
+            .into())
+    );
+}
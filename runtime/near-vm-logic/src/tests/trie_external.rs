@@ -0,0 +1,186 @@
+use crate::dependencies::Result;
+use crate::mocks::mock_external::MockedExternal;
+use crate::{External, ValuePtr};
+use near_crypto::PublicKey;
+use near_primitives::errors::StorageError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
+use near_primitives::types::TrieNodesCount;
+use near_primitives_core::types::{AccountId, Balance, BlockHeight};
+use near_store::test_utils::create_tries;
+use near_store::{TrieUpdate, TrieUpdateValuePtr};
+use near_vm_errors::{AnyError, HostError, VMLogicError};
+use std::collections::HashMap;
+
+/// Storage of the contract of `account_id` kept in a real trie over an in-memory store, so that
+/// keys are encoded as `TrieKey::ContractData` and touched trie nodes are counted the way the
+/// runtime does.
+///
+/// Everything but storage is delegated to `mocked`.
+pub struct TrieExternal {
+    pub trie_update: TrieUpdate,
+    pub mocked: MockedExternal,
+    account_id: AccountId,
+    /// Iterators hold a sorted copy of the key-values they iterate over.
+    iterators: HashMap<u64, std::vec::IntoIter<(Vec<u8>, Vec<u8>)>>,
+    next_iterator_index: u64,
+}
+
+struct TrieValuePtr<'a>(TrieUpdateValuePtr<'a>);
+
+impl<'a> ValuePtr for TrieValuePtr<'a> {
+    fn len(&self) -> u32 {
+        self.0.len()
+    }
+
+    fn deref(&self) -> Result<Vec<u8>> {
+        self.0.deref_value().map_err(wrap_storage_error)
+    }
+}
+
+fn wrap_storage_error(error: StorageError) -> VMLogicError {
+    VMLogicError::ExternalError(AnyError::new(error))
+}
+
+impl TrieExternal {
+    pub fn new(account_id: AccountId) -> Self {
+        let trie_update =
+            create_tries().new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        Self {
+            trie_update,
+            mocked: MockedExternal::default(),
+            account_id,
+            iterators: HashMap::new(),
+            next_iterator_index: 0,
+        }
+    }
+
+    fn storage_key(&self, key: &[u8]) -> TrieKey {
+        TrieKey::ContractData { account_id: self.account_id.clone(), key: key.to_vec() }
+    }
+
+    /// Keys and values of the contract data of the account starting with `prefix`, sorted.
+    fn key_values(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let raw_prefix =
+            trie_key_parsers::get_raw_prefix_for_contract_data(&self.account_id, prefix);
+        let mut key_values = vec![];
+        for raw_key in self.trie_update.iter(&raw_prefix).map_err(wrap_storage_error)? {
+            let raw_key = raw_key.map_err(wrap_storage_error)?;
+            let key =
+                trie_key_parsers::parse_data_key_from_contract_data_key(&raw_key, &self.account_id)
+                    .map_err(|_| {
+                        wrap_storage_error(StorageError::StorageInconsistentState(
+                            "Can't parse data key from raw key for ContractData".to_string(),
+                        ))
+                    })?
+                    .to_vec();
+            let value = self
+                .trie_update
+                .get(&self.storage_key(&key))
+                .map_err(wrap_storage_error)?
+                .unwrap_or_default();
+            key_values.push((key, value));
+        }
+        Ok(key_values)
+    }
+
+    fn create_iterator(&mut self, key_values: Vec<(Vec<u8>, Vec<u8>)>) -> u64 {
+        let iterator_index = self.next_iterator_index;
+        self.next_iterator_index += 1;
+        self.iterators.insert(iterator_index, key_values.into_iter());
+        iterator_index
+    }
+}
+
+impl External for TrieExternal {
+    fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.trie_update.set(self.storage_key(key), value.to_vec());
+        Ok(())
+    }
+
+    fn storage_get<'a>(&'a self, key: &[u8]) -> Result<Option<Box<dyn ValuePtr + 'a>>> {
+        self.trie_update
+            .get_ref(&self.storage_key(key))
+            .map_err(wrap_storage_error)
+            .map(|option| option.map(|ptr| Box::new(TrieValuePtr(ptr)) as Box<_>))
+    }
+
+    fn storage_remove(&mut self, key: &[u8]) -> Result<()> {
+        self.trie_update.remove(self.storage_key(key));
+        Ok(())
+    }
+
+    fn storage_remove_subtree(&mut self, prefix: &[u8]) -> Result<()> {
+        for (key, _) in self.key_values(prefix)? {
+            self.trie_update.remove(self.storage_key(&key));
+        }
+        Ok(())
+    }
+
+    fn storage_has_key(&mut self, key: &[u8]) -> Result<bool> {
+        self.trie_update
+            .get_ref(&self.storage_key(key))
+            .map(|ptr| ptr.is_some())
+            .map_err(wrap_storage_error)
+    }
+
+    fn storage_iter(&mut self, prefix: &[u8]) -> Result<u64> {
+        let key_values = self.key_values(prefix)?;
+        Ok(self.create_iterator(key_values))
+    }
+
+    fn storage_iter_range(&mut self, start: &[u8], end: &[u8]) -> Result<u64> {
+        let mut key_values = self.key_values(b"")?;
+        key_values.retain(|(key, _)| start <= key.as_slice() && key.as_slice() < end);
+        Ok(self.create_iterator(key_values))
+    }
+
+    fn storage_iter_next(&mut self, iterator_idx: u64) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match self.iterators.get_mut(&iterator_idx) {
+            Some(iterator) => Ok(iterator.next()),
+            None => Err(HostError::InvalidIteratorIndex { iterator_index: iterator_idx }.into()),
+        }
+    }
+
+    fn storage_proof(&self, key: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let storage_key = self.storage_key(key);
+        let trie = self
+            .trie_update
+            .trie()
+            .try_recording_reads()
+            .ok_or(HostError::StorageProofUnavailable)?;
+        let value = trie
+            .get(&self.trie_update.get_root(), &storage_key.to_vec())
+            .map_err(wrap_storage_error)?;
+        // Nodes of the state root only prove the current value if it wasn't modified since.
+        if value != self.trie_update.get(&storage_key).map_err(wrap_storage_error)? {
+            return Err(HostError::StorageProofUnavailable.into());
+        }
+        Ok(trie.recorded_storage().map(|storage| storage.nodes.0).unwrap_or_default())
+    }
+
+    fn generate_data_id(&mut self) -> CryptoHash {
+        self.mocked.generate_data_id()
+    }
+
+    fn get_trie_nodes_count(&self) -> TrieNodesCount {
+        self.trie_update.trie().get_trie_nodes_count()
+    }
+
+    fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>> {
+        self.mocked.validator_stake(account_id)
+    }
+
+    fn validator_public_key(&self, account_id: &AccountId) -> Result<Option<PublicKey>> {
+        self.mocked.validator_public_key(account_id)
+    }
+
+    fn validator_total_stake(&self) -> Result<Balance> {
+        self.mocked.validator_total_stake()
+    }
+
+    fn get_block_hash(&self, block_height: BlockHeight) -> Result<Option<CryptoHash>> {
+        self.mocked.get_block_hash(block_height)
+    }
+}
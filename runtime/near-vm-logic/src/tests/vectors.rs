@@ -0,0 +1,28 @@
+//! Loading of test vectors kept in JSON files next to the tests.
+//!
+//! Binary fields of the vectors are hex encoded and deserialized with
+//! `#[serde(with = "hex::serde")]`, or with [`deserialize_option_hex`] if they
+//! may be `null`.
+use hex::FromHex;
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer};
+use std::fmt::Display;
+use std::fs;
+
+/// Loads the list of vectors in `src/tests/{file_name}`.
+pub(crate) fn load_vectors<T: DeserializeOwned>(file_name: &str) -> Vec<T> {
+    let path = format!("src/tests/{}", file_name);
+    let data = fs::read(&path).unwrap_or_else(|err| panic!("cannot read {}: {}", path, err));
+    serde_json::from_slice(&data).unwrap_or_else(|err| panic!("cannot parse {}: {}", path, err))
+}
+
+pub(crate) fn deserialize_option_hex<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromHex,
+    <T as FromHex>::Error: Display,
+{
+    Deserialize::deserialize(deserializer)
+        .map(|v: Option<&str>| v.map(FromHex::from_hex).transpose().map_err(Error::custom))
+        .and_then(|v| v)
+}
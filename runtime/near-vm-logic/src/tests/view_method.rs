@@ -48,3 +48,14 @@ fn test_allowed_view_method() {
     let mut logic = logic_builder.build(context.clone());
     assert_eq!(logic.block_index().unwrap(), context.block_index);
 }
+
+#[test]
+fn test_chain_id_in_view() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let context = get_context(vec![], true);
+    let mut logic = logic_builder.build(context.clone());
+    logic.chain_id(0).unwrap();
+    let res = vec![0u8; context.chain_id.len()];
+    logic.read_register(0, res.as_ptr() as _).unwrap();
+    assert_eq!(res, context.chain_id.as_bytes());
+}
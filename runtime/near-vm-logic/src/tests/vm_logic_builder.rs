@@ -1,8 +1,9 @@
 use crate::mocks::mock_external::MockedExternal;
 use crate::mocks::mock_memory::MockedMemory;
+use crate::tests::trie_external::TrieExternal;
 use crate::types::PromiseResult;
 use crate::VMContext;
-use crate::{VMConfig, VMLogic};
+use crate::{External, VMConfig, VMLogic};
 use near_primitives_core::runtime::fees::RuntimeFeesConfig;
 use near_primitives_core::types::ProtocolVersion;
 
@@ -10,6 +11,8 @@ pub(crate) const LATEST_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::MAX
 
 pub struct VMLogicBuilder {
     pub ext: MockedExternal,
+    /// Used instead of `ext` when set, see [`VMLogicBuilder::with_trie`].
+    pub trie_ext: Option<TrieExternal>,
     pub config: VMConfig,
     pub fees_config: RuntimeFeesConfig,
     pub promise_results: Vec<PromiseResult>,
@@ -23,6 +26,7 @@ impl Default for VMLogicBuilder {
             config: VMConfig::test(),
             fees_config: RuntimeFeesConfig::test(),
             ext: MockedExternal::default(),
+            trie_ext: None,
             memory: MockedMemory::default(),
             promise_results: vec![],
             current_protocol_version: LATEST_PROTOCOL_VERSION,
@@ -32,8 +36,12 @@ impl Default for VMLogicBuilder {
 
 impl VMLogicBuilder {
     pub fn build(&mut self, context: VMContext) -> VMLogic<'_> {
+        let ext: &mut dyn External = match &mut self.trie_ext {
+            Some(trie_ext) => trie_ext,
+            None => &mut self.ext,
+        };
         VMLogic::new_with_protocol_version(
-            &mut self.ext,
+            ext,
             context,
             &self.config,
             &self.fees_config,
@@ -47,9 +55,19 @@ impl VMLogicBuilder {
             config: VMConfig::free(),
             fees_config: RuntimeFeesConfig::free(),
             ext: MockedExternal::default(),
+            trie_ext: None,
             memory: MockedMemory::default(),
             promise_results: vec![],
             current_protocol_version: LATEST_PROTOCOL_VERSION,
         }
     }
+
+    /// Keeps the contract storage of the account of `get_context` in a real trie rather than
+    /// in the `HashMap` of `MockedExternal`.
+    pub fn with_trie() -> Self {
+        VMLogicBuilder {
+            trie_ext: Some(TrieExternal::new("alice.near".parse().unwrap())),
+            ..Self::default()
+        }
+    }
 }
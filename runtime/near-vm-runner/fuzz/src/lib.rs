@@ -1,6 +1,7 @@
 use arbitrary::Arbitrary;
 use core::fmt;
 use near_primitives::contract::ContractCode;
+use near_primitives::types::EpochId;
 use near_vm_logic::VMContext;
 use near_vm_runner::internal::wasmparser::{Export, ExternalKind, Parser, Payload, TypeDef};
 
@@ -41,14 +42,18 @@ pub fn create_context(input: Vec<u8>) -> VMContext {
         block_index: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId::default(),
         account_balance: 2u128,
         account_locked_balance: 0,
         storage_usage: 12,
         attached_deposit: 2u128,
         prepaid_gas: 10_u64.pow(14),
+        gas_price: 100_000_000,
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        input_data_ids: vec![],
     }
 }
 
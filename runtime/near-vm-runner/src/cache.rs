@@ -9,6 +9,7 @@ use near_vm_logic::{ProtocolVersion, VMConfig};
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[cfg(target_arch = "x86_64")]
 use crate::prepare;
@@ -416,3 +417,38 @@ pub fn precompile_contract(
     let vm_kind = VMKind::for_protocol_version(current_protocol_version);
     precompile_contract_vm(vm_kind, wasm_code, config, cache)
 }
+
+/// Outcome of precompiling a single contract with [`precompile_contracts`].
+#[derive(Debug)]
+pub struct ContractPrecompilationReport {
+    pub code_hash: CryptoHash,
+    pub result: Result<Result<ContractPrecompilatonResult, CompilationError>, CacheError>,
+    /// Time spent looking up, compiling and storing the contract.
+    pub duration: Duration,
+}
+
+/// Precompiles `codes` for `vm_kind` one after another, storing the results to `cache`.
+///
+/// Contracts that are already in the cache are skipped, so an interrupted warm-up can be resumed
+/// by calling this again with the same contracts. Failing to compile one contract doesn't stop
+/// the others from being compiled, the outcome of each is returned in order.
+pub fn precompile_contracts(
+    codes: impl IntoIterator<Item = ContractCode>,
+    vm_kind: VMKind,
+    config: &VMConfig,
+    cache: &dyn CompiledContractCache,
+) -> Vec<ContractPrecompilationReport> {
+    let _span = tracing::debug_span!(target: "vm", "precompile_contracts").entered();
+    codes
+        .into_iter()
+        .map(|code| {
+            let start = Instant::now();
+            let result = precompile_contract_vm(vm_kind, &code, config, Some(cache));
+            ContractPrecompilationReport {
+                code_hash: *code.hash(),
+                result,
+                duration: start.elapsed(),
+            }
+        })
+        .collect()
+}
@@ -65,6 +65,19 @@ macro_rules! imports {
                 }
             )*}
         }
+
+        /// Whether `name` is a host function of this build, regardless of the protocol version
+        /// it is available at.
+        fn is_known_import(name: &str) -> bool {
+            $(
+                $(#[cfg(feature = $feature_name2)])?
+                $(#[cfg(feature = $feature_name)])*
+                if name == stringify!($func) {
+                    return true;
+                }
+            )*
+            false
+        }
     }
 }
 
@@ -87,6 +100,9 @@ imports! {
     block_index<[] -> [u64]>,
     block_timestamp<[] -> [u64]>,
     epoch_height<[] -> [u64]>,
+    #[EpochId] epoch_id<[register_id: u64] -> []>,
+    #[ChainId] chain_id<[register_id: u64] -> []>,
+    #[BlockHash] block_hash<[block_height: u64, register_id: u64] -> [u64]>,
     storage_usage<[] -> [u64]>,
     // #################
     // # Economics API #
@@ -96,6 +112,7 @@ imports! {
     attached_deposit<[balance_ptr: u64] -> []>,
     prepaid_gas<[] -> [u64]>,
     used_gas<[] -> [u64]>,
+    #[GasPrice] gas_price<[register_id: u64] -> []>,
     // ############
     // # Math API #
     // ############
@@ -109,11 +126,18 @@ imports! {
     // # Miscellaneous API #
     // #####################
     value_return<[value_len: u64, value_ptr: u64] -> []>,
+    #[ValueReturnAppend] value_return_append<[value_len: u64, value_ptr: u64] -> []>,
     panic<[] -> []>,
     panic_utf8<[len: u64, ptr: u64] -> []>,
     log_utf8<[len: u64, ptr: u64] -> []>,
     log_utf16<[len: u64, ptr: u64] -> []>,
     abort<[msg_ptr: u32, filename_ptr: u32, line: u32, col: u32] -> []>,
+    #[Events] emit_event<[
+        standard_len: u64,
+        standard_ptr: u64,
+        data_len: u64,
+        data_ptr: u64
+    ] -> []>,
     // ################
     // # Promises API #
     // ################
@@ -204,12 +228,27 @@ imports! {
     // #######################
     promise_results_count<[] -> [u64]>,
     promise_result<[result_idx: u64, register_id: u64] -> [u64]>,
+    #[PromiseResultMetadata] promise_result_len<[result_idx: u64] -> [u64]>,
+    #[PromiseResultMetadata] promise_result_receipt_id<[result_idx: u64, register_id: u64] -> []>,
     promise_return<[promise_idx: u64] -> []>,
     // ###############
     // # Storage API #
     // ###############
     storage_write<[key_len: u64, key_ptr: u64, value_len: u64, value_ptr: u64, register_id: u64] -> [u64]>,
     storage_read<[key_len: u64, key_ptr: u64, register_id: u64] -> [u64]>,
+    #[StorageReadWithProof] storage_read_with_proof<[
+        key_len: u64,
+        key_ptr: u64,
+        value_register_id: u64,
+        proof_register_id: u64
+    ] -> [u64]>,
+    #[ContractDataTriePrefix] contract_data_trie_prefix<[
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        register_id: u64
+    ] -> []>,
     storage_remove<[key_len: u64, key_ptr: u64, register_id: u64] -> [u64]>,
     storage_has_key<[key_len: u64, key_ptr: u64] -> [u64]>,
     storage_iter_prefix<[prefix_len: u64, prefix_ptr: u64] -> [u64]>,
@@ -221,6 +260,11 @@ imports! {
     // # Validator API #
     // ###############
     validator_stake<[account_id_len: u64, account_id_ptr: u64, stake_ptr: u64] -> []>,
+    #[ValidatorPublicKey] validator_public_key<[
+        account_id_len: u64,
+        account_id_ptr: u64,
+        register_id: u64
+    ] -> [u64]>,
     validator_total_stake<[stake_ptr: u64] -> []>,
     // #############
     // # Alt BN128 #
@@ -228,12 +272,115 @@ imports! {
     #[AltBn128] alt_bn128_g1_multiexp<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     #[AltBn128] alt_bn128_g1_sum<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     #[AltBn128] alt_bn128_pairing_check<[value_len: u64, value_ptr: u64] -> [u64]>,
+    // #########
+    // # ICS23 #
+    // #########
+    #[Ics23] verify_ics23_proof<[
+        spec_id: u64,
+        root_len: u64,
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+        register_id: u64
+    ] -> []>,
+    // #################
+    // # Outcome proof #
+    // #################
+    #[OutcomeProof] verify_outcome_proof<[
+        outcome_hash_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        block_outcome_root_ptr: u64,
+        register_id: u64
+    ] -> []>,
+    // ################
+    // # Light client #
+    // ################
+    #[LightClientBlock] validate_light_client_block<[
+        current_bps_len: u64,
+        current_bps_ptr: u64,
+        head_len: u64,
+        head_ptr: u64,
+        new_block_len: u64,
+        new_block_ptr: u64,
+        register_id: u64
+    ] -> []>,
+    // ##############
+    // # BEEFY, MMR #
+    // ##############
+    #[Beefy] verify_mmr_proof<[
+        root_len: u64,
+        root_ptr: u64,
+        leaf_len: u64,
+        leaf_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        leaf_index: u64,
+        leaf_count: u64,
+        register_id: u64
+    ] -> []>,
+    #[Beefy] verify_beefy_commitment<[
+        authorities_len: u64,
+        authorities_ptr: u64,
+        commitment_len: u64,
+        commitment_ptr: u64,
+        signatures_len: u64,
+        signatures_ptr: u64,
+        register_id: u64
+    ] -> []>,
+    // ##############
+    // # Tendermint #
+    // ##############
+    #[TendermintCommit] verify_tendermint_commit<[
+        validators_len: u64,
+        validators_ptr: u64,
+        commit_len: u64,
+        commit_ptr: u64,
+        chain_id_len: u64,
+        chain_id_ptr: u64,
+        trust_threshold_num: u64,
+        trust_threshold_den: u64,
+        register_id: u64
+    ] -> []>,
+    // ###############
+    // # State proof #
+    // ###############
+    #[NearStateProof] verify_near_state_proof<[
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64,
+        register_id: u64
+    ] -> []>,
+    #[StateProofStatus] verify_near_state_proof_status<[
+        root_ptr: u64,
+        proof_len: u64,
+        proof_ptr: u64,
+        account_id_len: u64,
+        account_id_ptr: u64,
+        key_len: u64,
+        key_ptr: u64,
+        value_len: u64,
+        value_ptr: u64
+    ] -> [u64]>,
     // #############
     // #  Sandbox  #
     // #############
     ##["sandbox"] sandbox_debug_log<[len: u64, ptr: u64] -> []>,
 }
 
+use near_primitives::version::ProtocolVersion;
+use near_vm_errors::{FunctionCallError, VMError};
+
 #[cfg(all(feature = "wasmer0_vm", target_arch = "x86_64"))]
 pub(crate) mod wasmer {
     use super::str_eq;
@@ -522,6 +669,61 @@ pub(crate) mod wasmtime {
     }
 }
 
+/// Returns the name of a host function imported by `code` that exists but isn't available at
+/// `protocol_version`.
+fn find_unavailable_import(code: &[u8], protocol_version: ProtocolVersion) -> Option<String> {
+    let mut available = Vec::new();
+    macro_rules! add_name {
+        (
+          $func:ident < [ $( $arg_name:ident : $arg_type:ident ),* ] -> [ $( $returns:ident ),* ] >
+        ) => {
+            available.push(stringify!($func));
+        };
+    }
+    for_each_available_import!(protocol_version, add_name);
+
+    for payload in wasmparser::Parser::new(0).parse_all(code) {
+        if let Ok(wasmparser::Payload::ImportSection(mut reader)) = payload {
+            for _ in 0..reader.get_count() {
+                let import = reader.read().ok()?;
+                let name = match import.field {
+                    Some(name) if import.module == "env" => name,
+                    _ => continue,
+                };
+                if is_known_import(name) && !available.iter().any(|&import| import == name) {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Replaces a link error of `code` with [`FunctionCallError::UnsupportedHostFunction`] if the
+/// contract imports a host function that isn't available at `protocol_version`, e.g. one added
+/// by a later protocol version.
+pub(crate) fn explain_link_error(
+    err: VMError,
+    code: &[u8],
+    protocol_version: ProtocolVersion,
+) -> VMError {
+    match err {
+        VMError::FunctionCallError(FunctionCallError::LinkError { msg }) => {
+            match find_unavailable_import(code, protocol_version) {
+                Some(name) => {
+                    VMError::FunctionCallError(FunctionCallError::UnsupportedHostFunction {
+                        name,
+                        protocol_version,
+                        link_error: msg,
+                    })
+                }
+                None => VMError::FunctionCallError(FunctionCallError::LinkError { msg }),
+            }
+        }
+        err => err,
+    }
+}
+
 /// Constant-time string equality, work-around for `"foo" == "bar"` not working
 /// in const context yet.
 const fn str_eq(s1: &str, s2: &str) -> bool {
@@ -22,8 +22,10 @@ pub use near_vm_errors::VMError;
 pub use near_vm_logic::with_ext_cost_counter;
 
 pub use cache::{
-    get_contract_cache_key, precompile_contract, precompile_contract_vm, MockCompiledContractCache,
+    get_contract_cache_key, precompile_contract, precompile_contract_vm, precompile_contracts,
+    ContractPrecompilationReport, MockCompiledContractCache,
 };
+pub use errors::ContractPrecompilatonResult;
 pub use runner::{run, VMResult, VM};
 
 /// This is public for internal experimentation use only, and should otherwise be considered an
@@ -162,6 +162,7 @@ impl VMResult {
             burnt_gas: 0,
             used_gas: 0,
             logs: Vec::new(),
+            events: Vec::new(),
             profile: ProfileData::default(),
             action_receipts: Vec::new(),
         };
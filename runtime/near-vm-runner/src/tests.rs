@@ -1,5 +1,6 @@
 mod cache;
 mod compile_errors;
+mod fork_imports;
 mod rs_contract;
 mod runtime_errors;
 pub(crate) mod test_builder;
@@ -7,6 +8,7 @@ mod ts_contract;
 mod wasm_validation;
 
 use crate::vm_kind::VMKind;
+use near_primitives::types::EpochId;
 use near_primitives::version::ProtocolVersion;
 use near_vm_logic::VMContext;
 
@@ -38,14 +40,18 @@ fn create_context(input: Vec<u8>) -> VMContext {
         block_index: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId::default(),
         account_balance: 2u128,
         account_locked_balance: 0,
         storage_usage: 12,
         attached_deposit: 2u128,
         prepaid_gas: 10_u64.pow(14),
+        gas_price: 100_000_000,
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        input_data_ids: vec![],
     }
 }
 
@@ -5,7 +5,9 @@ use super::{create_context, with_vm_variants, LATEST_PROTOCOL_VERSION};
 use crate::internal::VMKind;
 use crate::runner::VMResult;
 use crate::wasmer2_runner::Wasmer2VM;
-use crate::{prepare, MockCompiledContractCache};
+use crate::{
+    precompile_contracts, prepare, ContractPrecompilatonResult, MockCompiledContractCache,
+};
 use assert_matches::assert_matches;
 use near_primitives::contract::ContractCode;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
@@ -16,7 +18,7 @@ use near_vm_logic::mocks::mock_external::MockedExternal;
 use near_vm_logic::VMConfig;
 use std::hash::{Hash, Hasher};
 use std::io;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use wasmer_compiler::{CpuFeature, Target};
 use wasmer_engine::Executable;
 
@@ -70,6 +72,50 @@ fn test_does_not_cache_io_error() {
     })
 }
 
+#[test]
+fn test_precompile_contracts() {
+    with_vm_variants(|vm_kind: VMKind| {
+        match vm_kind {
+            VMKind::Wasmer0 | VMKind::Wasmer2 => {}
+            VMKind::Wasmtime => return,
+        }
+        let codes = [
+            near_test_contracts::trivial_contract().to_vec(),
+            near_test_contracts::rs_contract().to_vec(),
+            vec![42; 1000],
+        ];
+        let codes = || codes.iter().map(|code| ContractCode::new(code.clone(), None));
+        let cache = CountingCompiledContractCache::default();
+        let config = VMConfig::test();
+
+        let reports = precompile_contracts(codes(), vm_kind, &config, &cache);
+        assert_eq!(reports.len(), 3);
+        for (report, code) in reports.iter().zip(codes()) {
+            assert_eq!(&report.code_hash, code.hash());
+        }
+        assert_matches!(reports[0].result, Ok(Ok(ContractPrecompilatonResult::ContractCompiled)));
+        assert_matches!(reports[1].result, Ok(Ok(ContractPrecompilatonResult::ContractCompiled)));
+        assert_matches!(reports[2].result, Ok(Err(_)));
+        assert_eq!(cache.puts.load(Ordering::Relaxed), 3);
+
+        // Contracts compiled by a previous warm-up are skipped.
+        let reports = precompile_contracts(codes(), vm_kind, &config, &cache);
+        for report in reports {
+            assert_matches!(
+                report.result,
+                Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache))
+            );
+        }
+        assert_eq!(cache.puts.load(Ordering::Relaxed), 3);
+
+        // Calls after the warm-up don't compile the contract again.
+        let code = near_test_contracts::trivial_contract();
+        let result = make_cached_contract_call_vm(&cache, code, "main", 10u64.pow(12), vm_kind);
+        assert_eq!(result.error(), None);
+        assert_eq!(cache.puts.load(Ordering::Relaxed), 3);
+    })
+}
+
 fn make_cached_contract_call_vm(
     cache: &dyn CompiledContractCache,
     code: &[u8],
@@ -202,3 +248,21 @@ impl CompiledContractCache for FaultingCompiledContractCache {
         self.inner.get(key)
     }
 }
+
+/// [`CompiledContractCache`] which counts the artifacts written to it.
+#[derive(Default)]
+struct CountingCompiledContractCache {
+    puts: AtomicUsize,
+    inner: MockCompiledContractCache,
+}
+
+impl CompiledContractCache for CountingCompiledContractCache {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), io::Error> {
+        self.puts.fetch_add(1, Ordering::Relaxed);
+        self.inner.put(key, value)
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, io::Error> {
+        self.inner.get(key)
+    }
+}
@@ -0,0 +1,135 @@
+//! Checks that the host functions added by this fork are linked by every VM
+//! and behave the same in all of them.
+
+use super::{create_context, LATEST_PROTOCOL_VERSION};
+use crate::internal::VMKind;
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::version::ProtocolFeature;
+use near_vm_errors::{FunctionCallError, VMError};
+use near_vm_logic::mocks::mock_external::MockedExternal;
+
+/// Host functions added by this fork, with their number of `u64` parameters
+/// and whether they return a `u64`.
+const FORK_IMPORTS: &[(&str, usize, bool)] = &[
+    ("epoch_id", 1, false),
+    ("chain_id", 1, false),
+    ("block_hash", 2, true),
+    ("gas_price", 1, false),
+    ("emit_event", 4, false),
+    ("value_return_append", 2, false),
+    ("promise_result_len", 1, true),
+    ("promise_result_receipt_id", 2, false),
+    ("storage_read_with_proof", 4, true),
+    ("contract_data_trie_prefix", 5, false),
+    ("validator_public_key", 3, true),
+    ("verify_ics23_proof", 10, false),
+    ("verify_outcome_proof", 5, false),
+    ("validate_light_client_block", 7, false),
+    ("verify_mmr_proof", 9, false),
+    ("verify_beefy_commitment", 7, false),
+    ("verify_tendermint_commit", 9, false),
+    ("verify_near_state_proof", 10, false),
+    ("verify_near_state_proof_status", 9, true),
+];
+
+/// Contract calling `name` with all arguments set to zero.
+fn call_with_zeros_contract(name: &str, params: usize, returns: bool) -> Vec<u8> {
+    let param_types = " i64".repeat(params);
+    let args = " (i64.const 0)".repeat(params);
+    let (result, drop) = if returns { ("(result i64)", "drop") } else { ("", "") };
+    wat::parse_str(format!(
+        r#"
+(module
+  (import "env" "{name}" (func $f (param{param_types}) {result}))
+  (func (export "main")
+    (call $f{args})
+    {drop}
+  )
+)"#
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_fork_imports_consistent_across_vms() {
+    let runtime_config_store = RuntimeConfigStore::new(None);
+    let config = &runtime_config_store.get_config(LATEST_PROTOCOL_VERSION).wasm_config;
+    for &(name, params, returns) in FORK_IMPORTS {
+        let code = ContractCode::new(call_with_zeros_contract(name, params, returns), None);
+        let mut results = vec![];
+        for vm_kind in [VMKind::Wasmer2, VMKind::Wasmer0, VMKind::Wasmtime] {
+            let runtime = match vm_kind.runtime(config.clone()) {
+                Some(runtime) => runtime,
+                None => continue,
+            };
+            let res = runtime.run(
+                &code,
+                "main",
+                &mut MockedExternal::new(),
+                create_context(vec![]),
+                &RuntimeFeesConfig::test(),
+                &[],
+                LATEST_PROTOCOL_VERSION,
+                None,
+            );
+            if let Some(VMError::FunctionCallError(FunctionCallError::LinkError { msg })) =
+                res.error()
+            {
+                panic!("{vm_kind:?} failed linking `{name}`: {msg}");
+            }
+            results.push((vm_kind, format!("{:?} {:?}", res.outcome(), res.error())));
+        }
+        for (vm_kind, result) in &results[1..] {
+            assert_eq!(
+                result, &results[0].1,
+                "`{name}` behaves differently in {:?} and {vm_kind:?}",
+                results[0].0
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fork_import_unsupported_at_protocol_version() {
+    let protocol_version = ProtocolFeature::TendermintCommit.protocol_version() - 1;
+    let runtime_config_store = RuntimeConfigStore::new(None);
+    let config = &runtime_config_store.get_config(protocol_version).wasm_config;
+    let code =
+        ContractCode::new(call_with_zeros_contract("verify_tendermint_commit", 9, false), None);
+    for vm_kind in [VMKind::Wasmer2, VMKind::Wasmer0, VMKind::Wasmtime] {
+        let runtime = match vm_kind.runtime(config.clone()) {
+            Some(runtime) => runtime,
+            None => continue,
+        };
+        let res = runtime.run(
+            &code,
+            "main",
+            &mut MockedExternal::new(),
+            create_context(vec![]),
+            &RuntimeFeesConfig::test(),
+            &[],
+            protocol_version,
+            None,
+        );
+        match res.error() {
+            Some(VMError::FunctionCallError(FunctionCallError::UnsupportedHostFunction {
+                name,
+                protocol_version: version,
+                ..
+            })) => {
+                assert_eq!(name, "verify_tendermint_commit");
+                assert_eq!(*version, protocol_version);
+            }
+            err => panic!("{vm_kind:?} returned unexpected error: {err:?}"),
+        }
+        assert_eq!(
+            res.error().unwrap().to_string(),
+            format!(
+                "Host function verify_tendermint_commit is not available at protocol version {}",
+                protocol_version
+            )
+        );
+    }
+}
@@ -2,7 +2,7 @@ use crate::internal::VMKind;
 use near_primitives::{
     contract::ContractCode,
     runtime::{config_store::RuntimeConfigStore, fees::RuntimeFeesConfig},
-    types::Gas,
+    types::{EpochId, Gas},
     version::{ProtocolFeature, PROTOCOL_VERSION},
 };
 use near_vm_logic::{mocks::mock_external::MockedExternal, ProtocolVersion, VMContext};
@@ -19,14 +19,18 @@ pub(crate) fn test_builder() -> TestBuilder {
         block_index: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId::default(),
         account_balance: 2u128,
         account_locked_balance: 0,
         storage_usage: 12,
         attached_deposit: 2u128,
         prepaid_gas: 10_u64.pow(14),
+        gas_price: 100_000_000,
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        input_data_ids: vec![],
     };
     TestBuilder {
         code: ContractCode::new(Vec::new(), None),
@@ -521,7 +521,10 @@ impl crate::runner::VM for Wasmer2VM {
         }
         match self.run_method(&artifact, import, method_name) {
             Ok(()) => VMResult::ok(logic),
-            Err(err) => VMResult::abort(logic, err),
+            Err(err) => {
+                let err = imports::explain_link_error(err, code.code(), current_protocol_version);
+                VMResult::abort(logic, err)
+            }
         }
     }
 
@@ -321,7 +321,10 @@ impl crate::runner::VM for Wasmer0VM {
 
         match run_method(&module, &import_object, method_name) {
             Ok(()) => VMResult::ok(logic),
-            Err(err) => VMResult::abort(logic, err),
+            Err(err) => {
+                let err = imports::explain_link_error(err, code.code(), current_protocol_version);
+                VMResult::abort(logic, err)
+            }
         }
     }
 
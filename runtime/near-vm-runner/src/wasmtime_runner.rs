@@ -298,7 +298,14 @@ impl crate::runner::VM for WasmtimeVM {
                     );
                 }
             },
-            Err(err) => VMResult::abort(logic, err.into_vm_error()),
+            Err(err) => {
+                let err = imports::explain_link_error(
+                    err.into_vm_error(),
+                    code.code(),
+                    current_protocol_version,
+                );
+                VMResult::abort(logic, err)
+            }
         }
     }
 
@@ -440,21 +440,29 @@ pub enum Cost {
     /// (10kiB) and divide the cost by total key bytes.
     StorageHasKeyByte,
 
-    /// DEPRECATED: Was charged in `storage_iter_prefix`
+    /// Charged in `storage_iter_prefix`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterCreatePrefixBase,
-    /// DEPRECATED: Was charged in `storage_iter_prefix`
+    /// Charged in `storage_iter_prefix`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterCreatePrefixByte,
-    /// DEPRECATED: Was charged in `storage_iter_range`
+    /// Charged in `storage_iter_range`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterCreateRangeBase,
-    /// DEPRECATED: Was charged in `storage_iter_range`
+    /// Charged in `storage_iter_range`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterCreateFromByte,
-    /// DEPRECATED: Was charged in `storage_iter_range`
+    /// Charged in `storage_iter_range`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterCreateToByte,
-    /// DEPRECATED: Was charged in `storage_iter_next`
+    /// Charged in `storage_iter_next`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterNextBase,
-    /// DEPRECATED: Was charged in `storage_iter_next`
+    /// Charged in `storage_iter_next`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterNextKeyByte,
-    /// DEPRECATED: Was charged in `storage_iter_next`
+    /// Charged in `storage_iter_next`, not estimated yet. The default config prices it
+    /// like the corresponding `storage_read` cost.
     StorageIterNextValueByte,
 
     /// Estimates `touching_trie_node` which is charged when smart contracts
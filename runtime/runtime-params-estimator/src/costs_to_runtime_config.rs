@@ -128,16 +128,15 @@ fn ext_costs_config(cost_table: &CostTable) -> anyhow::Result<ExtCostsConfig> {
         storage_remove_ret_value_byte: get(Cost::StorageRemoveRetValueByte)?,
         storage_has_key_base: get(Cost::StorageHasKeyBase)?,
         storage_has_key_byte: get(Cost::StorageHasKeyByte)?,
-        // TODO: storage_iter_* operations below are deprecated, so just hardcode zero price,
-        // and remove those operations ASAP.
-        storage_iter_create_prefix_base: 0,
-        storage_iter_create_prefix_byte: 0,
-        storage_iter_create_range_base: 0,
-        storage_iter_create_from_byte: 0,
-        storage_iter_create_to_byte: 0,
-        storage_iter_next_base: 0,
-        storage_iter_next_key_byte: 0,
-        storage_iter_next_value_byte: 0,
+        // TODO: estimate storage iterators, until then they are priced like `storage_read`.
+        storage_iter_create_prefix_base: get(Cost::StorageReadBase)?,
+        storage_iter_create_prefix_byte: get(Cost::StorageReadKeyByte)?,
+        storage_iter_create_range_base: get(Cost::StorageReadBase)?,
+        storage_iter_create_from_byte: get(Cost::StorageReadKeyByte)?,
+        storage_iter_create_to_byte: get(Cost::StorageReadKeyByte)?,
+        storage_iter_next_base: get(Cost::StorageReadBase)?,
+        storage_iter_next_key_byte: get(Cost::StorageReadKeyByte)?,
+        storage_iter_next_value_byte: get(Cost::StorageReadValueByte)?,
         touching_trie_node: get(Cost::TouchingTrieNode)?,
         read_cached_trie_node: get(Cost::ReadCachedTrieNode)?,
         promise_and_base: get(Cost::PromiseAndBase)?,
@@ -154,6 +153,40 @@ fn ext_costs_config(cost_table: &CostTable) -> anyhow::Result<ExtCostsConfig> {
         alt_bn128_g1_multiexp_element: get(Cost::AltBn128G1MultiexpElement)?,
         alt_bn128_pairing_check_base: get(Cost::AltBn128PairingCheckBase)?,
         alt_bn128_pairing_check_element: get(Cost::AltBn128PairingCheckElement)?,
+        // TODO: estimate ICS-23 proof verification, these mirror the default config.
+        verify_ics23_proof_base: 20_000_000_000,
+        verify_ics23_proof_byte: 30_000_000,
+        verify_ics23_proof_inner_op: 5_000_000_000,
+        // TODO: estimate NEAR state proof verification, these mirror the default config.
+        verify_near_state_proof_base: 10_000_000_000,
+        verify_near_state_proof_byte: 30_000_000,
+        verify_near_state_proof_node: 5_000_000_000,
+        // TODO: estimate outcome proof verification, these mirror the default config.
+        verify_outcome_proof_base: 5_000_000_000,
+        verify_outcome_proof_item: 6_000_000_000,
+        // TODO: estimate light client block validation, these mirror the default config.
+        validate_light_client_block_base: 20_000_000_000,
+        validate_light_client_block_byte: 30_000_000,
+        validate_light_client_block_signature: 210_000_000_000,
+        // TODO: estimate MMR proof and BEEFY commitment verification, these
+        // mirror the default config.
+        verify_mmr_proof_base: 5_000_000_000,
+        verify_mmr_proof_byte: 21_471_105,
+        verify_mmr_proof_item: 7_500_000_000,
+        verify_beefy_commitment_base: 5_000_000_000,
+        verify_beefy_commitment_byte: 21_471_105,
+        verify_beefy_commitment_signature: 3_365_369_625_000,
+        // TODO: estimate the storage proof cost, this mirrors the default config.
+        storage_read_proof_byte: 30_000_000,
+        // TODO: estimate the block hash lookup, this mirrors the default config.
+        block_hash_base: 911_834_726_400,
+        // TODO: estimate events, until then they are priced like logs.
+        event_base: get(Cost::LogBase)?,
+        event_byte: get(Cost::LogByte)?,
+        // TODO: estimate Tendermint commit verification, this mirrors the default config.
+        verify_tendermint_commit_base: 5_000_000_000,
+        verify_tendermint_commit_byte: 30_000_000,
+        verify_tendermint_commit_signature: 210_611_100_000,
     };
 
     Ok(res)
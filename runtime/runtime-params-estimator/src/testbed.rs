@@ -66,6 +66,7 @@ impl RuntimeTestbed {
             block_hash: Default::default(),
             epoch_id: Default::default(),
             epoch_height: 0,
+            chain_id: "test-chain".to_string(),
             gas_price: 0,
             block_timestamp: 0,
             gas_limit: None,
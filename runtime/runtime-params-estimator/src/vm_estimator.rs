@@ -3,7 +3,7 @@ use crate::gas_cost::{GasCost, LeastSquaresTolerance};
 use crate::{utils::read_resource, REAL_CONTRACTS_SAMPLE};
 use near_primitives::contract::ContractCode;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
-use near_primitives::types::CompiledContractCache;
+use near_primitives::types::{CompiledContractCache, EpochId};
 use near_primitives::version::PROTOCOL_VERSION;
 use near_store::StoreCompiledContractCache;
 use near_vm_logic::VMContext;
@@ -27,14 +27,18 @@ pub(crate) fn create_context(input: Vec<u8>) -> VMContext {
         block_index: 10,
         block_timestamp: 42,
         epoch_height: 0,
+        chain_id: "test-chain".to_string(),
+        epoch_id: EpochId::default(),
         account_balance: 2u128,
         account_locked_balance: 1u128,
         storage_usage: 12,
         attached_deposit: 2u128,
         prepaid_gas: 10_u64.pow(18),
+        gas_price: 100_000_000,
         random_seed: vec![0, 1, 2],
         view_config: None,
         output_data_receivers: vec![],
+        input_data_ids: vec![],
     }
 }
 
@@ -10,7 +10,7 @@ use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::runtime::config::AccountCreationConfig;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::transaction::{
-    Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
+    Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction, Event,
     FunctionCallAction, StakeAction, TransferAction,
 };
 use near_primitives::types::validator_stake::ValidatorStake;
@@ -32,7 +32,7 @@ use near_vm_logic::VMContext;
 
 use crate::config::{safe_add_gas, RuntimeConfig};
 use crate::ext::{ExternalError, RuntimeExt};
-use crate::{ActionResult, ApplyState};
+use crate::{metrics, ActionResult, ApplyState};
 use near_primitives::config::ViewConfig;
 use near_vm_runner::{precompile_contract, VMResult};
 
@@ -88,14 +88,18 @@ pub(crate) fn execute_function_call(
         block_index: apply_state.block_index,
         block_timestamp: apply_state.block_timestamp,
         epoch_height: apply_state.epoch_height,
+        chain_id: apply_state.chain_id.clone(),
+        epoch_id: apply_state.epoch_id.clone(),
         account_balance: account.amount(),
         account_locked_balance: account.locked(),
         storage_usage: account.storage_usage(),
         attached_deposit: function_call.deposit,
         prepaid_gas: function_call.gas,
+        gas_price: apply_state.gas_price,
         random_seed,
         view_config,
         output_data_receivers,
+        input_data_ids: action_receipt.input_data_ids.clone(),
     };
 
     // Enable caching chunk mode for the function call. This allows to charge for nodes touched in a chunk only once for
@@ -206,6 +210,19 @@ pub(crate) fn action_function_call(
                 .into());
                 false
             }
+            FunctionCallError::UnsupportedHostFunction { name, protocol_version, link_error } => {
+                let err = if checked_feature!(
+                    "stable",
+                    UnsupportedHostFunctionError,
+                    apply_state.current_protocol_version
+                ) {
+                    ContractCallError::UnsupportedHostFunction { name, protocol_version }
+                } else {
+                    ContractCallError::ExecutionError { msg: format!("Link Error: {}", link_error) }
+                };
+                result.result = Err(ActionErrorKind::FunctionCallError(err.into()).into());
+                false
+            }
             FunctionCallError::_EVMError => unreachable!(),
         },
         Some(VMError::ExternalError(any_err)) => {
@@ -240,6 +257,9 @@ pub(crate) fn action_function_call(
     // `FunctionCall`s error.
     result.gas_used = safe_add_gas(result.gas_used, outcome.used_gas)?;
     result.logs.extend(outcome.logs);
+    result
+        .events
+        .extend(outcome.events.into_iter().map(|(standard, data)| Event { standard, data }));
     result.profile.merge(&outcome.profile);
     if execution_succeeded {
         let new_receipts: Vec<_> = outcome
@@ -479,13 +499,25 @@ pub(crate) fn action_deploy_contract(
     // Precompile the contract and store result (compiled code or error) in the database.
     // Note, that contract compilation costs are already accounted in deploy cost using
     // special logic in estimator (see get_runtime_config() function).
-    precompile_contract(
+    // Failing to precompile doesn't fail the deployment.
+    match precompile_contract(
         &code,
         &apply_state.config.wasm_config,
         current_protocol_version,
         apply_state.cache.as_deref(),
-    )
-    .ok();
+    ) {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => {
+            tracing::debug!(target: "runtime", %account_id, ?err, "Deployed contract does not compile");
+            metrics::CONTRACT_PRECOMPILATION_FAILED_TOTAL
+                .with_label_values(&["compilation_error"])
+                .inc();
+        }
+        Err(err) => {
+            tracing::warn!(target: "runtime", %account_id, ?err, "Failed caching deployed contract");
+            metrics::CONTRACT_PRECOMPILATION_FAILED_TOTAL.with_label_values(&["cache_error"]).inc();
+        }
+    }
     Ok(())
 }
 
@@ -3,11 +3,13 @@ use near_crypto::PublicKey;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::contract::ContractCode;
 use near_primitives::hash::CryptoHash;
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
-    AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, MerkleHash,
+    AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
 };
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::ViewStateResult;
+use std::sync::Arc;
 
 /// Adapter for querying runtime.
 pub trait ViewRuntimeAdapter {
@@ -18,6 +20,13 @@ pub trait ViewRuntimeAdapter {
         account_id: &AccountId,
     ) -> Result<Account, crate::state_viewer::errors::ViewAccountError>;
 
+    fn view_account_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+    ) -> Result<(Option<Account>, Vec<Arc<[u8]>>), crate::state_viewer::errors::ViewAccountError>;
+
     fn view_contract_code(
         &self,
         shard_uid: &ShardUId,
@@ -25,6 +34,17 @@ pub trait ViewRuntimeAdapter {
         account_id: &AccountId,
     ) -> Result<ContractCode, crate::state_viewer::errors::ViewContractCodeError>;
 
+    fn view_contract_code_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        include_code: bool,
+    ) -> Result<
+        (Account, Option<ContractCode>, Vec<Arc<[u8]>>),
+        crate::state_viewer::errors::ViewContractCodeError,
+    >;
+
     fn call_function(
         &self,
         shard_uid: &ShardUId,
@@ -38,6 +58,7 @@ pub trait ViewRuntimeAdapter {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        max_gas: Option<Gas>,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
@@ -58,11 +79,52 @@ pub trait ViewRuntimeAdapter {
         account_id: &AccountId,
     ) -> Result<Vec<(PublicKey, AccessKey)>, crate::state_viewer::errors::ViewAccessKeyError>;
 
+    fn view_access_key_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        public_key: &PublicKey,
+    ) -> Result<(Option<AccessKey>, Vec<Arc<[u8]>>), crate::state_viewer::errors::ViewAccessKeyError>;
+
+    fn view_access_keys_with_proof(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+    ) -> Result<
+        (Vec<(PublicKey, AccessKey)>, Vec<Arc<[u8]>>),
+        crate::state_viewer::errors::ViewAccessKeyError,
+    >;
+
     fn view_state(
         &self,
         shard_uid: &ShardUId,
         state_root: MerkleHash,
         account_id: &AccountId,
         prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
+        max_value_size: Option<u64>,
+        state_size_limit: Option<u64>,
+        include_proof: bool,
+        allow_partial: bool,
+    ) -> Result<ViewStateResult, crate::state_viewer::errors::ViewStateError>;
+
+    fn view_state_batch(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        prefix: &[u8],
+        from_key: Option<&[u8]>,
+        max_items: u64,
     ) -> Result<ViewStateResult, crate::state_viewer::errors::ViewStateError>;
+
+    fn prove_trie_key(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        key: &TrieKey,
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), crate::state_viewer::errors::ViewStateError>;
 }
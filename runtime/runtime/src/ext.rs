@@ -1,18 +1,20 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use tracing::debug;
 
+use near_crypto::PublicKey;
 use near_primitives::contract::ContractCode;
 use near_primitives::errors::{EpochError, StorageError};
 use near_primitives::hash::CryptoHash;
 use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{
-    AccountId, Balance, EpochId, EpochInfoProvider, TrieCacheMode, TrieNodesCount,
+    AccountId, Balance, BlockHeight, EpochId, EpochInfoProvider, TrieCacheMode, TrieNodesCount,
 };
 use near_primitives::utils::create_data_id;
 use near_primitives::version::ProtocolVersion;
-use near_store::{get_code, TrieUpdate, TrieUpdateValuePtr};
-use near_vm_errors::{AnyError, VMLogicError};
+use near_store::{get_code, TrieUpdate, TrieUpdateIterator, TrieUpdateValuePtr};
+use near_vm_errors::{AnyError, HostError, VMLogicError};
 use near_vm_logic::{External, ValuePtr};
 
 pub struct RuntimeExt<'a> {
@@ -25,6 +27,24 @@ pub struct RuntimeExt<'a> {
     last_block_hash: &'a CryptoHash,
     epoch_info_provider: &'a dyn EpochInfoProvider,
     current_protocol_version: ProtocolVersion,
+    iterators: HashMap<u64, StorageIterator>,
+    next_iterator_index: u64,
+}
+
+/// Position of a contract storage iterator.
+///
+/// Rather than borrowing the trie update for as long as the iterator lives,
+/// every step seeks to the first key at or after `start`. That yields the same
+/// keys as long as the storage isn't modified, which `VMLogic` enforces by
+/// invalidating iterators on writes and removals.
+struct StorageIterator {
+    /// Prefix all keys of the iterator have.
+    prefix: Vec<u8>,
+    /// Smallest key the next step may return.
+    start: Vec<u8>,
+    /// Key the iterator stops at, exclusive.
+    end: Option<Vec<u8>>,
+    exhausted: bool,
 }
 
 /// Error used by `RuntimeExt`.
@@ -76,6 +96,8 @@ impl<'a> RuntimeExt<'a> {
             last_block_hash,
             epoch_info_provider,
             current_protocol_version,
+            iterators: HashMap::new(),
+            next_iterator_index: 0,
         }
     }
 
@@ -105,6 +127,13 @@ impl<'a> RuntimeExt<'a> {
     pub fn protocol_version(&self) -> ProtocolVersion {
         self.current_protocol_version
     }
+
+    fn create_iterator(&mut self, iterator: StorageIterator) -> u64 {
+        let iterator_index = self.next_iterator_index;
+        self.next_iterator_index += 1;
+        self.iterators.insert(iterator_index, iterator);
+        iterator_index
+    }
 }
 
 fn wrap_storage_error(error: StorageError) -> VMLogicError {
@@ -162,6 +191,93 @@ impl<'a> External for RuntimeExt<'a> {
         Ok(())
     }
 
+    fn storage_iter(&mut self, prefix: &[u8]) -> ExtResult<u64> {
+        Ok(self.create_iterator(StorageIterator {
+            prefix: prefix.to_vec(),
+            start: prefix.to_vec(),
+            end: None,
+            exhausted: false,
+        }))
+    }
+
+    fn storage_iter_range(&mut self, start: &[u8], end: &[u8]) -> ExtResult<u64> {
+        Ok(self.create_iterator(StorageIterator {
+            prefix: vec![],
+            start: start.to_vec(),
+            end: Some(end.to_vec()),
+            exhausted: false,
+        }))
+    }
+
+    fn storage_iter_next(&mut self, iterator_idx: u64) -> ExtResult<Option<(Vec<u8>, Vec<u8>)>> {
+        let iterator = self
+            .iterators
+            .get_mut(&iterator_idx)
+            .ok_or(HostError::InvalidIteratorIndex { iterator_index: iterator_idx })?;
+        if iterator.exhausted {
+            return Ok(None);
+        }
+        let raw_prefix = trie_key_parsers::get_raw_prefix_for_contract_data(self.account_id, &[]);
+        let raw_key = TrieUpdateIterator::new(
+            self.trie_update,
+            &raw_prefix,
+            &iterator.start,
+            iterator.end.as_deref(),
+        )
+        .map_err(wrap_storage_error)?
+        .next()
+        .transpose()
+        .map_err(wrap_storage_error)?;
+        let key = match raw_key {
+            Some(raw_key) => {
+                trie_key_parsers::parse_data_key_from_contract_data_key(&raw_key, self.account_id)
+                    .map_err(|_e| {
+                        wrap_storage_error(StorageError::StorageInconsistentState(
+                            "Can't parse data key from raw key for ContractData".to_string(),
+                        ))
+                    })?
+                    .to_vec()
+            }
+            None => {
+                iterator.exhausted = true;
+                return Ok(None);
+            }
+        };
+        if !key.starts_with(&iterator.prefix) {
+            iterator.exhausted = true;
+            return Ok(None);
+        }
+        // The smallest key after `key`.
+        iterator.start = key.clone();
+        iterator.start.push(0);
+
+        let storage_key = self.create_storage_key(&key);
+        let value =
+            self.trie_update.get(&storage_key).map_err(wrap_storage_error)?.ok_or_else(|| {
+                wrap_storage_error(StorageError::StorageInconsistentState(
+                    "Iterated over a key without a value".to_string(),
+                ))
+            })?;
+        Ok(Some((key, value)))
+    }
+
+    fn storage_proof(&self, key: &[u8]) -> ExtResult<Vec<Vec<u8>>> {
+        let storage_key = self.create_storage_key(key);
+        let trie = self
+            .trie_update
+            .trie()
+            .try_recording_reads()
+            .ok_or(HostError::StorageProofUnavailable)?;
+        let value = trie
+            .get(&self.trie_update.get_root(), &storage_key.to_vec())
+            .map_err(wrap_storage_error)?;
+        // Nodes of the state root only prove the current value if it wasn't modified since.
+        if value != self.trie_update.get(&storage_key).map_err(wrap_storage_error)? {
+            return Err(HostError::StorageProofUnavailable.into());
+        }
+        Ok(trie.recorded_storage().map(|storage| storage.nodes.0).unwrap_or_default())
+    }
+
     fn generate_data_id(&mut self) -> CryptoHash {
         let data_id = create_data_id(
             self.current_protocol_version,
@@ -184,9 +300,148 @@ impl<'a> External for RuntimeExt<'a> {
             .map_err(|e| ExternalError::ValidatorError(e).into())
     }
 
+    fn validator_public_key(&self, account_id: &AccountId) -> ExtResult<Option<PublicKey>> {
+        self.epoch_info_provider
+            .validator_public_key(self.epoch_id, self.prev_block_hash, account_id)
+            .map_err(|e| ExternalError::ValidatorError(e).into())
+    }
+
     fn validator_total_stake(&self) -> ExtResult<Balance> {
         self.epoch_info_provider
             .validator_total_stake(self.epoch_id, self.prev_block_hash)
             .map_err(|e| ExternalError::ValidatorError(e).into())
     }
+
+    fn get_block_hash(&self, block_height: BlockHeight) -> ExtResult<Option<CryptoHash>> {
+        self.epoch_info_provider
+            .block_hash_by_height(self.prev_block_hash, block_height)
+            .map_err(|e| ExternalError::ValidatorError(e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::challenge::PartialState;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::test_utils::MockEpochInfoProvider;
+    use near_primitives::types::StateChangeCause;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_store::test_utils::{create_tries, test_populate_trie};
+    use near_store::{PartialStorage, Trie};
+
+    fn collect(ext: &mut RuntimeExt, iterator_id: u64) -> Vec<(Vec<u8>, Vec<u8>)> {
+        std::iter::from_fn(|| ext.storage_iter_next(iterator_id).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_storage_iterators() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let data_key = |account_id: &AccountId, key: &[u8]| TrieKey::ContractData {
+            account_id: account_id.clone(),
+            key: key.to_vec(),
+        };
+
+        // Some keys in the trie, some in committed and some in prospective changes.
+        let tries = create_tries();
+        let changes = [(&alice, "a1"), (&alice, "b1"), (&alice, "c"), (&bob, "a0"), (&bob, "b")]
+            .iter()
+            .map(|(account_id, key)| (data_key(account_id, key.as_bytes()).to_vec(), Some(vec![1])))
+            .collect();
+        let root =
+            test_populate_trie(&tries, &CryptoHash::default(), ShardUId::single_shard(), changes);
+        let mut trie_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        trie_update.set(data_key(&alice, b"a2"), vec![2]);
+        trie_update.remove(data_key(&alice, b"b1"));
+        trie_update.commit(StateChangeCause::InitialState);
+        trie_update.set(data_key(&alice, b"a0"), vec![3]);
+        trie_update.set(data_key(&alice, b"c"), vec![]);
+
+        let epoch_info_provider = MockEpochInfoProvider::default();
+        let (hash, epoch_id) = (CryptoHash::default(), EpochId::default());
+        let mut ext = RuntimeExt::new(
+            &mut trie_update,
+            &alice,
+            &hash,
+            &epoch_id,
+            &hash,
+            &hash,
+            &epoch_info_provider,
+            PROTOCOL_VERSION,
+        );
+
+        let iterator_id = ext.storage_iter(b"a").unwrap();
+        assert_eq!(
+            collect(&mut ext, iterator_id),
+            vec![(b"a0".to_vec(), vec![3]), (b"a1".to_vec(), vec![1]), (b"a2".to_vec(), vec![2])]
+        );
+        assert_eq!(ext.storage_iter_next(iterator_id), Ok(None));
+
+        let iterator_id = ext.storage_iter(b"").unwrap();
+        assert_eq!(collect(&mut ext, iterator_id).len(), 4);
+
+        let iterator_id = ext.storage_iter_range(b"a1", b"c").unwrap();
+        assert_eq!(
+            collect(&mut ext, iterator_id),
+            vec![(b"a1".to_vec(), vec![1]), (b"a2".to_vec(), vec![2])]
+        );
+        let iterator_id = ext.storage_iter_range(b"a2", b"d").unwrap();
+        assert_eq!(
+            collect(&mut ext, iterator_id),
+            vec![(b"a2".to_vec(), vec![2]), (b"c".to_vec(), vec![])]
+        );
+
+        // Empty ranges and prefixes.
+        let iterator_id = ext.storage_iter_range(b"c", b"a").unwrap();
+        assert_eq!(collect(&mut ext, iterator_id), vec![]);
+        let iterator_id = ext.storage_iter_range(b"b", b"b1").unwrap();
+        assert_eq!(collect(&mut ext, iterator_id), vec![]);
+        let iterator_id = ext.storage_iter(b"b").unwrap();
+        assert_eq!(collect(&mut ext, iterator_id), vec![]);
+
+        assert!(ext.storage_iter_next(100).is_err());
+    }
+
+    #[test]
+    fn test_storage_proof() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let data_key =
+            |key: &[u8]| TrieKey::ContractData { account_id: alice.clone(), key: key.to_vec() };
+
+        let tries = create_tries();
+        let changes = ["a", "b", "c"]
+            .iter()
+            .map(|key| (data_key(key.as_bytes()).to_vec(), Some(key.as_bytes().to_vec())))
+            .collect();
+        let root =
+            test_populate_trie(&tries, &CryptoHash::default(), ShardUId::single_shard(), changes);
+        let mut trie_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        trie_update.set(data_key(b"c"), vec![3]);
+
+        let epoch_info_provider = MockEpochInfoProvider::default();
+        let (hash, epoch_id) = (CryptoHash::default(), EpochId::default());
+        let ext = RuntimeExt::new(
+            &mut trie_update,
+            &alice,
+            &hash,
+            &epoch_id,
+            &hash,
+            &hash,
+            &epoch_info_provider,
+            PROTOCOL_VERSION,
+        );
+
+        let get_with_proof = |key: &[u8]| {
+            let proof = ext.storage_proof(key).unwrap();
+            let trie = Trie::from_recorded_storage(PartialStorage { nodes: PartialState(proof) });
+            trie.get(&root, &data_key(key).to_vec()).unwrap()
+        };
+        assert_eq!(get_with_proof(b"a"), Some(b"a".to_vec()));
+        assert_eq!(get_with_proof(b"b"), Some(b"b".to_vec()));
+        assert_eq!(get_with_proof(b"d"), None);
+
+        // Modified keys can't be proven against the state root.
+        assert_eq!(ext.storage_proof(b"c"), Err(HostError::StorageProofUnavailable.into()));
+    }
 }
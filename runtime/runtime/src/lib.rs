@@ -30,7 +30,7 @@ use near_primitives::{
     },
     state_record::StateRecord,
     transaction::{
-        Action, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus, LogEntry,
+        Action, Event, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus, LogEntry,
         SignedTransaction,
     },
     trie_key::TrieKey,
@@ -131,6 +131,7 @@ pub struct ActionResult {
     pub gas_used: Gas,
     pub result: Result<ReturnData, ActionError>,
     pub logs: Vec<LogEntry>,
+    pub events: Vec<Event>,
     pub new_receipts: Vec<Receipt>,
     pub validator_proposals: Vec<ValidatorStake>,
     pub profile: ProfileData,
@@ -154,6 +155,7 @@ impl ActionResult {
         self.profile.merge(&next_result.profile);
         self.result = next_result.result;
         self.logs.append(&mut next_result.logs);
+        self.events.append(&mut next_result.events);
         if let Ok(ReturnData::ReceiptIndex(ref mut receipt_index)) = self.result {
             // Shifting local receipt index to be global receipt index.
             *receipt_index += self.new_receipts.len() as u64;
@@ -177,6 +179,7 @@ impl Default for ActionResult {
             gas_used: 0,
             result: Ok(ReturnData::None),
             logs: vec![],
+            events: vec![],
             new_receipts: vec![],
             validator_proposals: vec![],
             profile: Default::default(),
@@ -272,6 +275,7 @@ impl Runtime {
                         // TODO: profile data is only counted in apply_action, which only happened at process_receipt
                         // VerificationResult needs updates to incorporate profile data to support profile data of txns
                         metadata: ExecutionMetadata::V1,
+                        events: vec![],
                     },
                 };
                 Ok((receipt, outcome))
@@ -733,6 +737,7 @@ impl Runtime {
                 tokens_burnt,
                 executor_id: account_id.clone(),
                 metadata: ExecutionMetadata::V2(result.profile),
+                events: result.events,
             },
         })
     }
@@ -1578,6 +1583,7 @@ mod tests {
             block_hash: Default::default(),
             epoch_id: Default::default(),
             epoch_height: 0,
+            chain_id: "test-chain".to_string(),
             gas_price: GAS_PRICE,
             block_timestamp: 100,
             gas_limit: Some(gas_limit),
@@ -2396,4 +2402,55 @@ mod tests {
             .expect("Compiled contract should be cached")
             .expect("Compilation result should be non-empty");
     }
+
+    #[test]
+    fn test_contract_precompilation_failure() {
+        let initial_balance = to_yocto(1_000_000);
+        let initial_locked = to_yocto(500_000);
+        let gas_limit = 10u64.pow(15);
+        let (runtime, tries, root, apply_state, signer, epoch_info_provider) =
+            setup_runtime(initial_balance, initial_locked, gas_limit);
+
+        let wasm_code = vec![42; 1000];
+        let actions =
+            vec![Action::DeployContract(DeployContractAction { code: wasm_code.clone() })];
+
+        let receipts = create_receipts_with_actions(alice_account(), signer, actions);
+
+        let failures = metrics::CONTRACT_PRECOMPILATION_FAILED_TOTAL
+            .with_label_values(&["compilation_error"])
+            .get();
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard()),
+                root,
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                None,
+            )
+            .unwrap();
+
+        // The contract is deployed even though it doesn't compile.
+        assert_eq!(apply_result.outcomes[0].outcome.status, ExecutionStatus::SuccessValue(vec![]));
+        assert!(
+            metrics::CONTRACT_PRECOMPILATION_FAILED_TOTAL
+                .with_label_values(&["compilation_error"])
+                .get()
+                > failures
+        );
+
+        // The compilation error is cached as well.
+        let contract_code = ContractCode::new(wasm_code, None);
+        let vm_kind = VMKind::for_protocol_version(apply_state.current_protocol_version);
+        let key = get_contract_cache_key(&contract_code, vm_kind, &apply_state.config.wasm_config);
+        apply_state
+            .cache
+            .unwrap()
+            .get(&key.0)
+            .expect("Compilation error should be cached")
+            .expect("Compilation result should be non-empty");
+    }
 }
@@ -31,3 +31,18 @@ pub static TRANSACTION_PROCESSED_FAILED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static CONTRACT_PRECOMPILATION_FAILED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_contract_precompilation_failed_total",
+        "The number of deployed contracts which could not be precompiled since starting this node",
+        &["reason"],
+    )
+    .unwrap()
+});
+pub static VIEW_STATE_PROOF_NODES_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_view_state_proof_nodes_total",
+        "The number of trie nodes recorded for the proofs of view_state queries",
+    )
+    .unwrap()
+});
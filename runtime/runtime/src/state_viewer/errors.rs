@@ -36,8 +36,14 @@ pub enum ViewStateError {
     InvalidAccountId { requested_account_id: near_primitives::types::AccountId },
     #[error("Account {requested_account_id} does not exist")]
     AccountDoesNotExist { requested_account_id: near_primitives::types::AccountId },
-    #[error("The state of {requested_account_id} is too large")]
-    AccountStateTooLarge { requested_account_id: near_primitives::types::AccountId },
+    #[error(
+        "The state of {requested_account_id} is too large: {state_size} bytes, the limit is {state_size_limit}"
+    )]
+    AccountStateTooLarge {
+        requested_account_id: near_primitives::types::AccountId,
+        state_size: u64,
+        state_size_limit: u64,
+    },
     #[error("Internal error: #{error_message}")]
     InternalError { error_message: String },
 }
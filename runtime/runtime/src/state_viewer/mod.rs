@@ -1,35 +1,114 @@
 use crate::near_primitives::version::PROTOCOL_VERSION;
-use crate::{actions::execute_function_call, ext::RuntimeExt};
+use crate::{actions::execute_function_call, ext::RuntimeExt, metrics};
 use near_crypto::{KeyType, PublicKey};
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::{
     account::{AccessKey, Account},
     borsh::BorshDeserialize,
+    challenge::PartialState,
     contract::ContractCode,
-    hash::CryptoHash,
+    hash::{hash, CryptoHash},
     receipt::ActionReceipt,
     runtime::{
         apply_state::ApplyState,
         migration_data::{MigrationData, MigrationFlags},
     },
-    serialize::to_base64,
+    serialize::{from_base64, to_base64},
     transaction::FunctionCallAction,
-    trie_key::trie_key_parsers,
-    types::{AccountId, EpochInfoProvider, Gas},
-    views::{StateItem, ViewApplyState, ViewStateResult},
+    trie_key::{trie_key_parsers, TrieKey},
+    types::{AccountId, EpochInfoProvider, Gas, StateRoot},
+    views::{
+        ElidedValueView, StateItem, TrieProofPath, ViewApplyState, ViewStateProofKind,
+        ViewStateResult,
+    },
+};
+use near_store::flat_contract_data::{FlatContractData, ValueRef};
+use near_store::proof_verifier::{contract_data_key, ProofVerifier};
+use near_store::{
+    get_access_key, get_account, get_code, PartialStorage, StorageError, Trie, TrieUpdate,
+    TrieUpdateIterator, TrieUpdateValuePtr,
 };
-use near_store::{get_access_key, get_account, get_code, TrieUpdate};
+use near_vm_errors::{FunctionCallError, HostError, VMError};
 use near_vm_logic::{ReturnData, ViewConfig};
-use std::{str, sync::Arc, time::Instant};
+use std::{collections::HashMap, rc::Rc, str, sync::Arc, time::Instant};
 use tracing::debug;
 
 pub mod errors;
 
+/// Absolute ceiling of the gas of view calls. It clamps both the node's limit and the one asked
+/// for by a query, so that no view call can keep a node busy for too long.
+pub const MAX_GAS_BURNT_VIEW_CEILING: Gas = 3_000_000_000_000_000;
+
+/// The value of a contract data item read by `view_state`, or where to read it from.
+enum StateValue {
+    Value(Vec<u8>),
+    Ref(ValueRef),
+}
+
+impl StateValue {
+    fn len(&self) -> u64 {
+        match self {
+            StateValue::Value(value) => value.len() as u64,
+            StateValue::Ref(value_ref) => value_ref.length as u64,
+        }
+    }
+
+    fn hash(&self) -> CryptoHash {
+        match self {
+            StateValue::Value(value) => hash(value),
+            StateValue::Ref(value_ref) => value_ref.hash,
+        }
+    }
+
+    fn into_value(self, trie: &Trie) -> Result<Vec<u8>, StorageError> {
+        match self {
+            StateValue::Value(value) => Ok(value),
+            StateValue::Ref(value_ref) => {
+                TrieUpdateValuePtr::HashAndSize(trie, value_ref.length, value_ref.hash)
+                    .deref_value()
+            }
+        }
+    }
+}
+
+/// Sets the `proof_node_indices` of the contract data `items` of `account_id`: the positions in
+/// the proof `nodes` of the nodes on the path to each item from `root`.
+fn set_proof_node_indices(
+    root: &StateRoot,
+    account_id: &AccountId,
+    nodes: &[Arc<[u8]>],
+    items: &mut [StateItem],
+) -> Result<(), errors::ViewStateError> {
+    let internal_error =
+        |error_message: String| errors::ViewStateError::InternalError { error_message };
+    let positions: HashMap<CryptoHash, u32> =
+        nodes.iter().enumerate().map(|(position, node)| (hash(node), position as u32)).collect();
+    let verifier = ProofVerifier::new(nodes);
+    for item in items {
+        let key = from_base64(&item.key).map_err(|err| internal_error(err.to_string()))?;
+        let path = verifier
+            .path(root, &contract_data_key(account_id, &key))
+            .map_err(|err| internal_error(err.to_string()))?;
+        item.proof_node_indices = path.iter().map(|node_hash| positions[node_hash]).collect();
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
 pub struct TrieViewer {
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     state_size_limit: Option<u64>,
-    /// Gas limit used when when handling call_function queries.
+    /// Highest `state_size_limit` that queries may ask for. None if they may only lower it.
+    state_size_override_ceiling: Option<u64>,
+    /// Whether `view_state` returns the items up to `state_size_limit` instead of failing when
+    /// the state is too large.
+    allow_partial: bool,
+    /// Gas limit used when when handling call_function queries which don't ask for their own.
     max_gas_burnt_view: Gas,
+    /// Max number of logs of call_function queries. None is a multiple of the consensus limit.
+    max_number_logs_view: Option<u64>,
+    /// Max total log length of call_function queries. None is a multiple of the consensus limit.
+    max_total_log_length_view: Option<u64>,
 }
 
 impl Default for TrieViewer {
@@ -37,15 +116,61 @@ impl Default for TrieViewer {
         let config_store = RuntimeConfigStore::new(None);
         let latest_runtime_config = config_store.get_config(PROTOCOL_VERSION);
         let max_gas_burnt = latest_runtime_config.wasm_config.limit_config.max_gas_burnt;
-        Self { state_size_limit: None, max_gas_burnt_view: max_gas_burnt }
+        Self {
+            state_size_limit: None,
+            state_size_override_ceiling: None,
+            allow_partial: false,
+            max_gas_burnt_view: max_gas_burnt,
+            max_number_logs_view: None,
+            max_total_log_length_view: None,
+        }
     }
 }
 
 impl TrieViewer {
-    pub fn new(state_size_limit: Option<u64>, max_gas_burnt_view: Option<Gas>) -> Self {
-        let max_gas_burnt_view =
-            max_gas_burnt_view.unwrap_or_else(|| TrieViewer::default().max_gas_burnt_view);
-        Self { state_size_limit, max_gas_burnt_view }
+    pub fn new(
+        state_size_limit: Option<u64>,
+        max_gas_burnt_view: Option<Gas>,
+        max_number_logs_view: Option<u64>,
+        max_total_log_length_view: Option<u64>,
+    ) -> Self {
+        let max_gas_burnt_view = max_gas_burnt_view
+            .unwrap_or_else(|| TrieViewer::default().max_gas_burnt_view)
+            .min(MAX_GAS_BURNT_VIEW_CEILING);
+        Self {
+            state_size_limit,
+            state_size_override_ceiling: None,
+            allow_partial: false,
+            max_gas_burnt_view,
+            max_number_logs_view,
+            max_total_log_length_view,
+        }
+    }
+
+    /// Lets queries raise the state size limit up to `ceiling`.
+    pub fn allow_state_size_override(&mut self, ceiling: u64) {
+        self.state_size_override_ceiling = Some(ceiling);
+    }
+
+    /// Returns the viewer to use for a query asking for the given state size limit. The limit
+    /// can always be lowered, but only raised up to the ceiling set with
+    /// `allow_state_size_override`; larger requests are capped.
+    pub fn with_requested_state_size_limit(&self, requested: Option<u64>) -> Self {
+        let max_allowed = self.state_size_limit.map(|limit| {
+            self.state_size_override_ceiling.map_or(limit, |ceiling| ceiling.max(limit))
+        });
+        let state_size_limit = match requested {
+            Some(requested) => Some(max_allowed.map_or(requested, |max| requested.min(max))),
+            None => self.state_size_limit,
+        };
+        Self { state_size_limit, ..self.clone() }
+    }
+
+    /// Returns the viewer to use for a query allowing partial results or not. With them,
+    /// `view_state` returns a page of the state of accounts too large to be viewed at once,
+    /// instead of failing.
+    pub fn with_partial_results(self, allow_partial: bool) -> Self {
+        Self { allow_partial, ..self }
     }
 
     pub fn view_account(
@@ -60,6 +185,45 @@ impl TrieViewer {
         })
     }
 
+    /// Returns the account as stored under the state root of `state_update`, `None` if it doesn't
+    /// exist, along with the trie nodes on the path to `TrieKey::Account`. The nodes prove the
+    /// borsh-encoded account, or its absence, against the state root.
+    pub fn view_account_with_proof(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+    ) -> Result<(Option<Account>, Vec<Arc<[u8]>>), errors::ViewAccountError> {
+        let key = TrieKey::Account { account_id: account_id.clone() };
+        Ok(Self::get_with_proof(state_update, &key)?)
+    }
+
+    /// Returns a trie recording the nodes read through it.
+    fn recording_trie(state_update: &TrieUpdate) -> Result<Trie, StorageError> {
+        // Tries of views always read from the store, so they can record reads.
+        state_update.trie.try_recording_reads().ok_or(StorageError::StorageInternalError)
+    }
+
+    fn recorded_nodes(trie: &Trie) -> Vec<Arc<[u8]>> {
+        let nodes = trie.recorded_storage().map(|storage| storage.nodes.0).unwrap_or_default();
+        nodes.into_iter().map(Arc::from).collect()
+    }
+
+    /// Reads the value under `key` from the state root of `state_update`, along with the trie
+    /// nodes on the path to `key` which prove the value, or its absence, against the state root.
+    fn get_with_proof<T: BorshDeserialize>(
+        state_update: &TrieUpdate,
+        key: &TrieKey,
+    ) -> Result<(Option<T>, Vec<Arc<[u8]>>), StorageError> {
+        let (value, nodes) = state_update.get_with_proof(key)?;
+        let value = match value {
+            Some(data) => Some(T::try_from_slice(&data).map_err(|_| {
+                StorageError::StorageInconsistentState(format!("Failed to deserialize {:?}", key))
+            })?),
+            None => None,
+        };
+        Ok((value, nodes))
+    }
+
     pub fn view_contract_code(
         &self,
         state_update: &TrieUpdate,
@@ -73,6 +237,54 @@ impl TrieViewer {
         })
     }
 
+    /// Returns the account and, if `include_code` is set, its contract code, along with the trie
+    /// nodes proving them against the state root of `state_update`.
+    ///
+    /// The nodes prove the account record, whose code hash commits to the code, and with
+    /// `include_code` the path to `TrieKey::ContractCode`. The code bytes are left out of the
+    /// proof as they are returned anyway and can be large.
+    pub fn view_contract_code_with_proof(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        include_code: bool,
+    ) -> Result<(Account, Option<ContractCode>, Vec<Arc<[u8]>>), errors::ViewContractCodeError>
+    {
+        let root = state_update.get_root();
+        let trie = Self::recording_trie(state_update)?;
+        let account_key = TrieKey::Account { account_id: account_id.clone() }.to_vec();
+        let account = match trie.get(&root, &account_key)? {
+            Some(data) => Account::try_from_slice(&data).map_err(|_| {
+                errors::ViewContractCodeError::InternalError {
+                    error_message: format!("Failed to deserialize account {}", account_id),
+                }
+            })?,
+            None => {
+                return Err(errors::ViewContractCodeError::AccountDoesNotExist {
+                    requested_account_id: account_id.clone(),
+                })
+            }
+        };
+        let code = if include_code {
+            let code_key = TrieKey::ContractCode { account_id: account_id.clone() }.to_vec();
+            // Only the path is recorded, the code itself is read without recording.
+            if trie.get_ref(&root, &code_key)?.is_none() {
+                return Err(errors::ViewContractCodeError::NoContractCode {
+                    contract_account_id: account_id.clone(),
+                });
+            }
+            let code = state_update.trie.get(&root, &code_key)?.ok_or_else(|| {
+                errors::ViewContractCodeError::NoContractCode {
+                    contract_account_id: account_id.clone(),
+                }
+            })?;
+            Some(ContractCode::new(code, Some(account.code_hash())))
+        } else {
+            None
+        };
+        Ok((account, code, Self::recorded_nodes(&trie)))
+    }
+
     pub fn view_access_key(
         &self,
         state_update: &TrieUpdate,
@@ -84,6 +296,55 @@ impl TrieViewer {
         })
     }
 
+    /// Same as `view_access_key`, but returns `None` if the key doesn't exist, along with the trie
+    /// nodes on the path to `TrieKey::AccessKey` which prove the access key, or its absence.
+    pub fn view_access_key_with_proof(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        public_key: &PublicKey,
+    ) -> Result<(Option<AccessKey>, Vec<Arc<[u8]>>), errors::ViewAccessKeyError> {
+        let key =
+            TrieKey::AccessKey { account_id: account_id.clone(), public_key: public_key.clone() };
+        Ok(Self::get_with_proof(state_update, &key)?)
+    }
+
+    /// Same as `view_access_keys`, but also returns the trie nodes read while scanning the access
+    /// keys of the account. They prove each of the keys and that the account has no other key.
+    pub fn view_access_keys_with_proof(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+    ) -> Result<(Vec<(PublicKey, AccessKey)>, Vec<Arc<[u8]>>), errors::ViewAccessKeyError> {
+        let prefix = trie_key_parsers::get_raw_prefix_for_access_keys(account_id);
+        let trie = Self::recording_trie(state_update)?;
+        let mut iter = trie.iter(&state_update.get_root())?;
+        iter.seek(&prefix)?;
+        let mut access_keys = vec![];
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let public_key = &key[prefix.len()..];
+            let public_key = PublicKey::try_from_slice(public_key).map_err(|_| {
+                errors::ViewAccessKeyError::InternalError {
+                    error_message: format!(
+                        "Unexpected invalid public key {:?} received from store",
+                        public_key
+                    ),
+                }
+            })?;
+            let access_key = AccessKey::try_from_slice(&value).map_err(|_| {
+                errors::ViewAccessKeyError::InternalError {
+                    error_message: format!("Failed to deserialize access key {}", public_key),
+                }
+            })?;
+            access_keys.push((public_key, access_key));
+        }
+        Ok((access_keys, Self::recorded_nodes(&trie)))
+    }
+
     pub fn view_access_keys(
         &self,
         state_update: &TrieUpdate,
@@ -114,69 +375,357 @@ impl TrieViewer {
         access_keys
     }
 
+    /// Returns the contract data of `account_id` whose keys start with `prefix`.
+    ///
+    /// If `from_key` or `limit` is given, a single page is returned instead: the items with keys
+    /// strictly after `from_key`, at most `limit` of them and at most `state_size_limit` bytes of
+    /// keys and values (but always at least one item). When the page is truncated, `next_key` of
+    /// the result is the key of its last item, to be passed as `from_key` for the next page.
+    ///
+    /// Values larger than `max_value_size` are left out, their items carry the hash and length of
+    /// the value instead. Elided values don't count towards the size of a page.
+    ///
+    /// If the state of the account is too large and neither is given, the query fails, unless
+    /// the viewer allows partial results: the first page is returned then, with `truncated` set
+    /// if there are more items and the `account_state_size` that was too large.
+    ///
+    /// With `include_proof`, the proof of the result holds the trie nodes visited while iterating,
+    /// but not the values. They show that the result has every item of the range it covers, see
+    /// `near_store::proof_verifier::verify_view_state_result`. If no key starts with `prefix`,
+    /// the proof is instead the path to the prefix up to where it diverges, which also shows
+    /// that the prefix is absent with `near_store::proof_verifier::verify_non_existence`. The
+    /// `proof_kind` of the result tells the two apart. Without `include_proof`, no trie nodes are
+    /// recorded at all.
     pub fn view_state(
         &self,
         state_update: &TrieUpdate,
         account_id: &AccountId,
         prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
+        max_value_size: Option<u64>,
+        include_proof: bool,
     ) -> Result<ViewStateResult, errors::ViewStateError> {
+        let paginated = from_key.is_some() || limit.is_some();
+        let account_state_size = self.check_state_size(state_update, account_id, paginated)?;
+        let paginated = paginated || account_state_size.is_some();
+
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+        let acc_sep_len = query.len() - prefix.len();
+        // The nodes visited by the iteration cover the whole range of the page, so they make up
+        // the proof.
+        let mut iter = state_update.iter_with_recording(&query)?;
+        if let Some(from_key) = from_key {
+            iter.seek(&trie_key_parsers::get_raw_prefix_for_contract_data(account_id, from_key))?;
+        }
+        let items = iter.by_ref().map(|item| -> Result<_, errors::ViewStateError> {
+            let (key, value) = item?;
+            Ok((key[acc_sep_len..].to_vec(), StateValue::Value(value)))
+        });
+        let (mut values, next_key) = self.collect_state_page(
+            state_update.trie(),
+            items,
+            from_key,
+            limit,
+            max_value_size,
+            paginated,
+        )?;
+        let (proof, proof_kind) = if include_proof {
+            let non_existence = if values.is_empty() && from_key.is_none() {
+                state_update.trie().prove_non_existence(&state_update.get_root(), &query)?
+            } else {
+                None
+            };
+            let (nodes, proof_kind) = match non_existence {
+                Some(nodes) => (nodes, ViewStateProofKind::NonExistence),
+                None => {
+                    let nodes = iter.into_recorded_nodes();
+                    set_proof_node_indices(
+                        &state_update.get_root(),
+                        account_id,
+                        &nodes,
+                        &mut values,
+                    )?;
+                    (nodes, ViewStateProofKind::Range)
+                }
+            };
+            let nodes: TrieProofPath = nodes.iter().map(to_base64).collect();
+            metrics::VIEW_STATE_PROOF_NODES_TOTAL.inc_by(nodes.len() as u64);
+            (nodes, Some(proof_kind))
+        } else {
+            (vec![], None)
+        };
+        let truncated = account_state_size.is_some() && next_key.is_some();
+        Ok(ViewStateResult { values, proof, next_key, proof_kind, truncated, account_state_size })
+    }
+
+    /// Same as `view_state` without proof, but reads the keys from `flat_contract_data`, the
+    /// flat index of the contract data at the state of `state_update`, instead of iterating over
+    /// the trie. Only the values returned are read from the trie.
+    pub fn view_state_flat(
+        &self,
+        state_update: &TrieUpdate,
+        flat_contract_data: &FlatContractData,
+        account_id: &AccountId,
+        prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
+        max_value_size: Option<u64>,
+    ) -> Result<ViewStateResult, errors::ViewStateError> {
+        let paginated = from_key.is_some() || limit.is_some();
+        let account_state_size = self.check_state_size(state_update, account_id, paginated)?;
+        let paginated = paginated || account_state_size.is_some();
+
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+        let acc_sep_len = query.len() - prefix.len();
+        let key_prefix = flat_contract_data.key(&query);
+        let items =
+            flat_contract_data.iter(&key_prefix).map(|item| -> Result<_, errors::ViewStateError> {
+                let (key, value_ref) = item.map_err(|err| {
+                    errors::ViewStateError::InternalError { error_message: err.to_string() }
+                })?;
+                Ok((key[acc_sep_len..].to_vec(), StateValue::Ref(value_ref)))
+            });
+        let (values, next_key) = self.collect_state_page(
+            state_update.trie(),
+            items,
+            from_key,
+            limit,
+            max_value_size,
+            paginated,
+        )?;
+        let truncated = account_state_size.is_some() && next_key.is_some();
+        Ok(ViewStateResult {
+            values,
+            proof: vec![],
+            next_key,
+            proof_kind: None,
+            truncated,
+            account_state_size,
+        })
+    }
+
+    /// Returns the value of the single contract data `key` of `account_id`, `None` if there is
+    /// none, along with the trie nodes on the path to it, in order from the root. The nodes prove
+    /// the value, or its absence, against the state root of `state_update`, whether or not the
+    /// account exists.
+    pub fn prove_contract_data(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        key: &[u8],
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), errors::ViewStateError> {
+        let key = TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() };
+        self.prove_trie_key(state_update, &key)
+    }
+
+    /// Like `prove_contract_data`, for any trie key.
+    pub fn prove_trie_key(
+        &self,
+        state_update: &TrieUpdate,
+        key: &TrieKey,
+    ) -> Result<(Option<Vec<u8>>, Vec<Arc<[u8]>>), errors::ViewStateError> {
+        Ok(state_update.get_with_proof(key)?)
+    }
+
+    /// Fails if the account doesn't exist, or if its state is too large to be viewed at once
+    /// and the query isn't `paginated`. With partial results allowed, returns the size of such a
+    /// state instead, to view it in pages.
+    fn check_state_size(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        paginated: bool,
+    ) -> Result<Option<u64>, errors::ViewStateError> {
         match get_account(state_update, account_id)? {
             Some(account) => {
                 let code_len = get_code(state_update, account_id, Some(account.code_hash()))?
                     .map(|c| c.code().len() as u64)
                     .unwrap_or_default();
-                if let Some(limit) = self.state_size_limit {
-                    if account.storage_usage().saturating_sub(code_len) > limit {
+                let state_size = account.storage_usage().saturating_sub(code_len);
+                if let Some(state_size_limit) = self.state_size_limit {
+                    if !paginated && state_size > state_size_limit {
+                        if self.allow_partial {
+                            return Ok(Some(state_size));
+                        }
                         return Err(errors::ViewStateError::AccountStateTooLarge {
                             requested_account_id: account_id.clone(),
+                            state_size,
+                            state_size_limit,
                         });
                     }
                 }
+                Ok(None)
             }
-            None => {
-                return Err(errors::ViewStateError::AccountDoesNotExist {
-                    requested_account_id: account_id.clone(),
-                })
-            }
-        };
+            None => Err(errors::ViewStateError::AccountDoesNotExist {
+                requested_account_id: account_id.clone(),
+            }),
+        }
+    }
 
+    /// Collects the page of `view_state` out of the contract data `items`, given in key order
+    /// with the data keys, from the first one at or after `from_key`. Returns the items of the
+    /// page and its `next_key`.
+    fn collect_state_page(
+        &self,
+        trie: &Trie,
+        items: impl Iterator<Item = Result<(Vec<u8>, StateValue), errors::ViewStateError>>,
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
+        max_value_size: Option<u64>,
+        paginated: bool,
+    ) -> Result<(Vec<StateItem>, Option<String>), errors::ViewStateError> {
         let mut values = vec![];
-        let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
-        let acc_sep_len = query.len() - prefix.len();
-        let mut iter = state_update.trie.iter(&state_update.get_root())?;
-        iter.seek(&query)?;
-        for item in iter {
-            let (key, value) = item?;
-            if !key.starts_with(query.as_ref()) {
+        let mut next_key = None;
+        let mut page_size = 0u64;
+        for item in items {
+            let (data_key, value) = item?;
+            if from_key.map_or(false, |from_key| data_key.as_slice() <= from_key) {
+                continue;
+            }
+            let value_len = value.len();
+            let elide_value = max_value_size.map_or(false, |max| value_len > max);
+            let value_size = if elide_value { 0 } else { value_len };
+            let item_size = data_key.len() as u64 + value_size;
+            let page_full = limit.map_or(false, |limit| values.len() as u64 >= limit)
+                || (paginated
+                    && !values.is_empty()
+                    && self.state_size_limit.map_or(false, |size_limit| {
+                        page_size.saturating_add(item_size) > size_limit
+                    }));
+            if page_full {
+                next_key = values.last().map(|item: &StateItem| item.key.clone());
                 break;
             }
-            values.push(StateItem {
-                key: to_base64(&key[acc_sep_len..]),
-                value: to_base64(&value),
-                proof: vec![],
+            page_size = page_size.saturating_add(item_size);
+            values.push(if elide_value {
+                StateItem {
+                    key: to_base64(&data_key),
+                    value: String::new(),
+                    proof: vec![],
+                    elided_value: Some(ElidedValueView { hash: value.hash(), length: value_len }),
+                    proof_node_indices: vec![],
+                }
+            } else {
+                StateItem {
+                    key: to_base64(&data_key),
+                    value: to_base64(&value.into_value(trie)?),
+                    proof: vec![],
+                    elided_value: None,
+                    proof_node_indices: vec![],
+                }
             });
         }
-        // TODO(2076): Add proofs for the storage items.
-        Ok(ViewStateResult { values, proof: vec![] })
+        Ok((values, next_key))
     }
 
+    /// Streams the contract data of `account_id` whose keys start with `prefix`, in key order.
+    ///
+    /// Unlike `view_state`, there is no limit on the size of the state: items are read as the
+    /// iterator advances, so the state is never held in memory as a whole.
+    pub fn iter_state<'a>(
+        &self,
+        state_update: &'a TrieUpdate,
+        account_id: &AccountId,
+        prefix: &[u8],
+    ) -> Result<
+        impl Iterator<Item = Result<StateItem, errors::ViewStateError>> + 'a,
+        errors::ViewStateError,
+    > {
+        self.iter_state_after(state_update, account_id, prefix, None)
+    }
+
+    /// Same as `iter_state`, but starting strictly after `from_key` when it is given, to resume
+    /// an iteration.
+    pub fn iter_state_after<'a>(
+        &self,
+        state_update: &'a TrieUpdate,
+        account_id: &AccountId,
+        prefix: &[u8],
+        from_key: Option<&[u8]>,
+    ) -> Result<
+        impl Iterator<Item = Result<StateItem, errors::ViewStateError>> + 'a,
+        errors::ViewStateError,
+    > {
+        if get_account(state_update, account_id)?.is_none() {
+            return Err(errors::ViewStateError::AccountDoesNotExist {
+                requested_account_id: account_id.clone(),
+            });
+        }
+        let account_prefix = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, &[]);
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+        let start = match from_key {
+            Some(from_key) if from_key > prefix => from_key,
+            _ => prefix,
+        };
+        let iter = TrieUpdateIterator::new(state_update, &account_prefix, start, None)?;
+        let account_id = account_id.clone();
+        let from_key = from_key.map(<[u8]>::to_vec);
+        Ok(iter
+            .take_while(move |key| key.as_ref().map_or(true, |key| key.starts_with(&query)))
+            .map(move |key| -> Result<Option<StateItem>, errors::ViewStateError> {
+                let key = key?;
+                let data_key =
+                    trie_key_parsers::parse_data_key_from_contract_data_key(&key, &account_id)
+                        .map_err(|err| errors::ViewStateError::InternalError {
+                            error_message: err.to_string(),
+                        })?;
+                if from_key.as_deref().map_or(false, |from_key| data_key <= from_key) {
+                    return Ok(None);
+                }
+                let trie_key = TrieKey::ContractData {
+                    account_id: account_id.clone(),
+                    key: data_key.to_vec(),
+                };
+                let value = state_update.get(&trie_key)?.ok_or_else(|| {
+                    errors::ViewStateError::InternalError {
+                        error_message: format!("missing value of iterated key {:?}", key),
+                    }
+                })?;
+                Ok(Some(StateItem {
+                    key: to_base64(data_key),
+                    value: to_base64(&value),
+                    proof: vec![],
+                    elided_value: None,
+                    proof_node_indices: vec![],
+                }))
+            })
+            .filter_map(Result::transpose))
+    }
+
+    /// Executes a view call and returns its result. With `record_witness`, also returns the trie
+    /// nodes the call read, which are enough to replay it with `verify_call_function_witness`.
     pub fn call_function(
         &self,
-        mut state_update: TrieUpdate,
+        state_update: TrieUpdate,
         view_state: ViewApplyState,
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        max_gas: Option<Gas>,
+        record_witness: bool,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
-    ) -> Result<Vec<u8>, errors::CallFunctionError> {
+    ) -> Result<(Vec<u8>, Vec<Arc<[u8]>>), errors::CallFunctionError> {
         let now = Instant::now();
+        let max_gas_burnt_view =
+            max_gas.unwrap_or(self.max_gas_burnt_view).min(MAX_GAS_BURNT_VIEW_CEILING);
         let root = state_update.get_root();
+        let mut state_update = if record_witness {
+            TrieUpdate::new(Rc::new(Self::recording_trie(&state_update)?), root)
+        } else {
+            state_update
+        };
         let mut account = get_account(&state_update, contract_id)?.ok_or_else(|| {
             errors::CallFunctionError::AccountDoesNotExist {
                 requested_account_id: contract_id.clone(),
             }
         })?;
+        if record_witness {
+            // The code may come from the in-memory code cache during execution, so read it here
+            // to have it in the witness.
+            get_code(&state_update, contract_id, Some(account.code_hash()))?;
+        }
         // TODO(#1015): Add ability to pass public key and originator_id
         let originator_id = contract_id;
         let public_key = PublicKey::empty(KeyType::ED25519);
@@ -200,6 +749,7 @@ impl TrieViewer {
             block_hash: view_state.block_hash,
             epoch_id: view_state.epoch_id.clone(),
             epoch_height: view_state.epoch_height,
+            chain_id: view_state.chain_id.clone(),
             gas_price: 0,
             block_timestamp: view_state.block_timestamp,
             gas_limit: None,
@@ -222,7 +772,7 @@ impl TrieViewer {
         let function_call = FunctionCallAction {
             method_name: method_name.to_string(),
             args: args.to_vec(),
-            gas: self.max_gas_burnt_view,
+            gas: max_gas_burnt_view,
             deposit: 0,
         };
         let (outcome, err) = execute_function_call(
@@ -236,7 +786,11 @@ impl TrieViewer {
             &empty_hash,
             config,
             true,
-            Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view }),
+            Some(ViewConfig {
+                max_gas_burnt: max_gas_burnt_view,
+                max_number_logs: self.max_number_logs_view,
+                max_total_log_length: self.max_total_log_length_view,
+            }),
         )
         .outcome_error();
         let elapsed = now.elapsed();
@@ -246,7 +800,18 @@ impl TrieViewer {
 
         if let Some(err) = err {
             logs.extend(outcome.logs);
-            let message = format!("wasm execution failed with error: {:?}", err);
+            let mut message = format!("wasm execution failed with error: {:?}", err);
+            if matches!(
+                err,
+                VMError::FunctionCallError(FunctionCallError::HostError(
+                    HostError::GasExceeded | HostError::GasLimitExceeded
+                ))
+            ) {
+                message.push_str(&format!(
+                    ", burnt {} gas with a limit of {}",
+                    outcome.burnt_gas, max_gas_burnt_view
+                ));
+            }
             debug!(target: "runtime", "(exec time {}) {}", time_str, message);
             Err(errors::CallFunctionError::VMError { error_message: message })
         } else {
@@ -256,7 +821,109 @@ impl TrieViewer {
                 ReturnData::Value(buf) => buf,
                 ReturnData::ReceiptIndex(_) | ReturnData::None => vec![],
             };
-            Ok(result)
+            Ok((result, Self::recorded_nodes(&state_update.trie)))
+        }
+    }
+
+    /// Replays a view call using only the trie nodes of `witness` as state, and checks that it
+    /// returns `expected_result` against `state_root`. Missing or tampered nodes fail the replay.
+    pub fn verify_call_function_witness(
+        &self,
+        state_root: StateRoot,
+        witness: &[Arc<[u8]>],
+        view_state: ViewApplyState,
+        contract_id: &AccountId,
+        method_name: &str,
+        args: &[u8],
+        max_gas: Option<Gas>,
+        epoch_info_provider: &dyn EpochInfoProvider,
+        expected_result: &[u8],
+    ) -> bool {
+        let nodes = witness.iter().map(|node| node.to_vec()).collect();
+        let trie = Trie::from_recorded_storage(PartialStorage { nodes: PartialState(nodes) });
+        let state_update = TrieUpdate::new(Rc::new(trie), state_root);
+        // Execution may take the code from the in-memory code cache, so check it separately.
+        match get_account(&state_update, contract_id) {
+            Ok(Some(account)) => {
+                let code = get_code(&state_update, contract_id, Some(account.code_hash()));
+                if !matches!(code, Ok(Some(_))) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+        match self.call_function(
+            state_update,
+            view_state,
+            contract_id,
+            method_name,
+            args,
+            max_gas,
+            false,
+            &mut vec![],
+            epoch_info_provider,
+        ) {
+            Ok((result, _)) => result == expected_result,
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::StateChangeCause;
+    use near_store::set_account;
+    use near_store::test_utils::create_tries;
+
+    #[test]
+    fn test_max_gas_burnt_view_ceiling() {
+        let viewer = TrieViewer::new(None, Some(Gas::MAX), None, None);
+        assert_eq!(viewer.max_gas_burnt_view, MAX_GAS_BURNT_VIEW_CEILING);
+        let viewer = TrieViewer::new(None, Some(1_000), None, None);
+        assert_eq!(viewer.max_gas_burnt_view, 1_000);
+    }
+
+    #[test]
+    fn test_view_state_without_proof_records_no_nodes() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let mut state_update = tries.new_trie_update(shard_uid, CryptoHash::default());
+        set_account(
+            &mut state_update,
+            account_id.clone(),
+            &Account::new(0, 0, CryptoHash::default(), 0),
+        );
+        for i in 0..100u32 {
+            let key = TrieKey::ContractData {
+                account_id: account_id.clone(),
+                key: i.to_be_bytes().to_vec(),
+            };
+            state_update.set(key, vec![1]);
+        }
+        state_update.commit(StateChangeCause::InitialState);
+        let trie_changes = state_update.finalize().unwrap().0;
+        let (db_changes, root) = tries.apply_all(&trie_changes, shard_uid);
+        db_changes.commit().unwrap();
+
+        let state_update = tries.new_trie_update_view(shard_uid, root);
+        let viewer = TrieViewer::default();
+        let recorded_nodes = || metrics::VIEW_STATE_PROOF_NODES_TOTAL.get();
+        for _ in 0..100 {
+            let before = recorded_nodes();
+            let result = viewer
+                .view_state(&state_update, &account_id, b"xyz", None, None, None, false)
+                .unwrap();
+            assert_eq!(result.proof, TrieProofPath::new());
+            assert_eq!(recorded_nodes(), before);
         }
+        let before = recorded_nodes();
+        let result =
+            viewer.view_state(&state_update, &account_id, b"xyz", None, None, None, true).unwrap();
+        assert!(!result.proof.is_empty());
+        assert_eq!(recorded_nodes() - before, result.proof.len() as u64);
     }
 }
@@ -87,6 +87,7 @@ impl StandaloneRuntime {
             block_hash: Default::default(),
             epoch_id: Default::default(),
             epoch_height: 0,
+            chain_id: "test-chain".to_string(),
             gas_price: 100,
             block_timestamp: 0,
             gas_limit: None,
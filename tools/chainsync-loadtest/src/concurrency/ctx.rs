@@ -1,4 +1,5 @@
 use crate::concurrency::Once;
+use std::any::{Any, TypeId};
 use std::fmt;
 use std::ops;
 use std::sync::Arc;
@@ -7,8 +8,8 @@ use tokio::time;
 // Error represents an error that Context can return.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Error {
-    /// Context (or one of the ancestors) has timed out.
-    Timeout,
+    /// Deadline of the context (or one of the ancestors) has passed.
+    DeadlineExceeded,
     /// Context (or one of the ancestors) has been manually cancelled.
     Cancelled,
 }
@@ -16,7 +17,9 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match self {
-            Self::Timeout => "ctx::Error::Timeout: context or one of the ancestors has timed out",
+            Self::DeadlineExceeded => {
+                "ctx::Error::DeadlineExceeded: deadline of context or one of the ancestors has passed"
+            }
             Self::Cancelled => {
                 "ctx::Error::Cancelled: context or one of the ancestors has been cancelled"
             }
@@ -34,6 +37,8 @@ struct Ctx_ {
     label: Option<String>,
     done: Arc<Once<Error>>,
     deadline: Option<time::Instant>,
+    // Value attached via Ctx::with_value, keyed by its type.
+    value: Option<(TypeId, Arc<dyn Any + Send + Sync>)>,
     parent: Option<Arc<Ctx_>>,
 }
 
@@ -74,6 +79,7 @@ impl Ctx {
             label: None,
             parent: None,
             deadline: None,
+            value: None,
             done: Arc::new(Once::new()),
         }));
     }
@@ -93,6 +99,13 @@ impl Ctx {
         self.0.done.wait().await
     }
 
+    // deadline() returns the instant at which the context will be cancelled
+    // automatically (the earliest deadline among the context and its ancestors),
+    // None if there is no such deadline.
+    pub fn deadline(&self) -> Option<time::Instant> {
+        self.0.deadline
+    }
+
     // wrap() executes the future f to completion, or until the context gets cancelled.
     // This function is expected to be called only by the low level code, which
     // wraps context-unaware code (context-aware code is expected to take ctx as an argument
@@ -118,13 +131,39 @@ impl Ctx {
         self.wrap(time::sleep(duration)).await
     }
 
-    fn new_child(&self, label: Option<String>, deadline: Option<time::Instant>) -> Ctx {
+    // value() returns the value of type K attached to the context
+    // (or to the closest ancestor, if the context doesn't have one), see with_value().
+    pub fn value<K: Any>(&self) -> Option<&K> {
+        let mut c = &self.0;
+        loop {
+            if let Some((id, v)) = &c.value {
+                if *id == TypeId::of::<K>() {
+                    return v.downcast_ref::<K>();
+                }
+            }
+            c = c.parent.as_ref()?;
+        }
+    }
+
+    fn new_child(
+        &self,
+        label: Option<String>,
+        deadline: Option<time::Instant>,
+        value: Option<(TypeId, Arc<dyn Any + Send + Sync>)>,
+    ) -> Ctx {
         let deadline = match (deadline, self.0.deadline) {
             (Some(cd), Some(pd)) => Some(std::cmp::min(cd, pd)),
             (None, pd) => pd,
             (cd, None) => cd,
         };
         let done = Arc::new(Once::<Error>::new());
+        // Make the child observe the parent's state immediately, rather than
+        // once the background task below gets scheduled.
+        if let Some(e) = self.err() {
+            let _ = done.set(e);
+        } else if deadline.map_or(false, |d| d <= time::Instant::now()) {
+            let _ = done.set(Error::DeadlineExceeded);
+        }
         tokio::spawn({
             let parent_done = self.0.done.clone();
             let child_done = done.clone();
@@ -136,39 +175,55 @@ impl Ctx {
                         std::future::pending().await
                     }
                 };
+                // If the parent gets cancelled at the same time the deadline passes,
+                // propagate the parent's error, so that the child never reports
+                // DeadlineExceeded after its parent has been cancelled first.
                 tokio::select! {
-                    _ = deadline_wait => { let _ = child_done.set(Error::Timeout); }
+                    biased;
                     e = parent_done.wait() => { let _ = child_done.set(e); }
+                    _ = deadline_wait => { let _ = child_done.set(Error::DeadlineExceeded); }
                     _ = child_done.wait() => {}
                 }
             }
         });
-        return Ctx(Arc::new(Ctx_ { label, parent: Some(self.0.clone()), done, deadline }));
+        return Ctx(Arc::new(Ctx_ { label, parent: Some(self.0.clone()), done, deadline, value }));
     }
 
     // with_cancel creates a child context which can be manually cancelled.
     // If the parent context gets cancelled, the child will also be cancelled,
     // but not vice versa (you cannot affect the parent context).
     pub fn with_cancel(&self) -> CtxWithCancel {
-        return CtxWithCancel(self.new_child(None, None));
+        return CtxWithCancel(self.new_child(None, None, None));
     }
 
-    // with_deadline() creates a child context which will be automatically cancelled,
-    // once deadline passes (or parent context gets cancelled).
-    pub fn with_deadline(&self, deadline: tokio::time::Instant) -> Ctx {
-        return self.new_child(None, Some(deadline));
+    // with_deadline() creates a child context which will be automatically cancelled
+    // with Error::DeadlineExceeded once deadline passes (or with the parent's error,
+    // if the parent context gets cancelled first). The child can also be cancelled
+    // manually. The deadline can only tighten the deadline of the parent: if the
+    // parent's deadline is earlier, the child expires together with the parent.
+    pub fn with_deadline(&self, deadline: time::Instant) -> CtxWithCancel {
+        return CtxWithCancel(self.new_child(None, Some(deadline), None));
     }
 
     // with_label() is a debugging utility which allows to create child context
     // with a custom label. The path of labels of the ancestors is returned
     // in the Debug implementation for Ctx.
     pub fn with_label(&self, label: &str) -> Ctx {
-        return self.new_child(Some(label.to_string()), None);
+        return self.new_child(Some(label.to_string()), None, None);
+    }
+
+    // with_value() creates a child context carrying value v, which can be read
+    // via value::<K>() from the child and all its descendants. Values are keyed
+    // by their type: a descendant context can shadow the value with its own
+    // value of the same type. Use dedicated types (rather than String, etc.)
+    // to avoid collisions. The cancellation of the child works as for with_label().
+    pub fn with_value<K: Any + Send + Sync>(&self, v: K) -> Ctx {
+        return self.new_child(None, None, Some((TypeId::of::<K>(), Arc::new(v))));
     }
 
     // with_timeout() is the same as with_deadline() but you provide a duration,
     // rather than an instant.
-    pub fn with_timeout(&self, timeout: time::Duration) -> Ctx {
+    pub fn with_timeout(&self, timeout: time::Duration) -> CtxWithCancel {
         return self.with_deadline(time::Instant::now() + timeout);
     }
 }
@@ -4,6 +4,7 @@ use futures::task;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use tokio::time;
 struct DummyWake;
 
 impl task::ArcWake for DummyWake {
@@ -43,3 +44,112 @@ async fn test_cancel_propagation() {
     assert_eq!(ctx::Error::Cancelled, h1.await);
     assert_eq!(ctx::Error::Cancelled, h2.await);
 }
+
+#[tokio::test]
+async fn test_deadline_exceeded() {
+    let ctx = Ctx::background().with_timeout(time::Duration::from_millis(10));
+    assert!(ctx.err().is_none());
+    assert_eq!(ctx::Error::DeadlineExceeded, ctx.done().await);
+    assert_eq!(Some(ctx::Error::DeadlineExceeded), ctx.err());
+
+    // A context with a deadline can still be cancelled manually.
+    let ctx = Ctx::background().with_timeout(time::Duration::from_secs(3600));
+    ctx.cancel();
+    assert_eq!(ctx::Error::Cancelled, ctx.done().await);
+
+    // A deadline which has already passed takes effect immediately.
+    let ctx = Ctx::background().with_deadline(time::Instant::now());
+    assert_eq!(Some(ctx::Error::DeadlineExceeded), ctx.err());
+}
+
+#[tokio::test]
+async fn test_nested_deadlines() {
+    // Child deadline tighter than the parent's.
+    let parent = Ctx::background().with_timeout(time::Duration::from_secs(3600));
+    let child = parent.with_timeout(time::Duration::from_millis(10));
+    assert!(child.deadline() < parent.deadline());
+    assert_eq!(ctx::Error::DeadlineExceeded, child.done().await);
+    assert!(parent.err().is_none());
+
+    // Child deadline looser than the parent's: the parent's deadline applies.
+    let parent = Ctx::background().with_timeout(time::Duration::from_millis(10));
+    let child = parent.with_timeout(time::Duration::from_secs(3600));
+    let grandchild = child.with_cancel();
+    assert_eq!(parent.deadline(), child.deadline());
+    assert_eq!(parent.deadline(), grandchild.deadline());
+    assert_eq!(ctx::Error::DeadlineExceeded, grandchild.done().await);
+    assert_eq!(Some(ctx::Error::DeadlineExceeded), child.err());
+    assert_eq!(Some(ctx::Error::DeadlineExceeded), parent.err());
+}
+
+#[tokio::test]
+async fn test_parent_cancel_races_child_deadline() {
+    // Parent cancelled before the child's deadline.
+    let parent = Ctx::background().with_cancel();
+    let child = parent.with_timeout(time::Duration::from_millis(10));
+    parent.cancel();
+    assert_eq!(ctx::Error::Cancelled, child.done().await);
+    time::sleep(time::Duration::from_millis(20)).await;
+    assert_eq!(Some(ctx::Error::Cancelled), child.err());
+
+    // Child's deadline passes before the parent gets cancelled.
+    let parent = Ctx::background().with_cancel();
+    let child = parent.with_timeout(time::Duration::from_millis(10));
+    assert_eq!(ctx::Error::DeadlineExceeded, child.done().await);
+    parent.cancel();
+    assert_eq!(ctx::Error::Cancelled, parent.done().await);
+    assert_eq!(Some(ctx::Error::DeadlineExceeded), child.err());
+
+    // Parent cancelled exactly when the child's deadline passes: whichever wins,
+    // the child's error is settled and consistent between err() and done().
+    for _ in 0..10 {
+        let parent = Ctx::background().with_cancel();
+        let deadline = time::Instant::now() + time::Duration::from_millis(5);
+        let child = parent.with_deadline(deadline);
+        time::sleep_until(deadline).await;
+        parent.cancel();
+        let err = child.done().await;
+        assert_eq!(Some(err.clone()), child.err());
+        assert!(err == ctx::Error::Cancelled || err == ctx::Error::DeadlineExceeded);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct TraceId(u64);
+
+#[derive(Debug, PartialEq)]
+struct PeerLabel(&'static str);
+
+#[tokio::test]
+async fn test_value_shadowing() {
+    let ctx1 = Ctx::background().with_value(TraceId(1));
+    let ctx2 = ctx1.with_cancel().with_value(PeerLabel("peer"));
+    let ctx3 = ctx2.with_value(TraceId(3));
+    assert_eq!(Some(&TraceId(1)), ctx1.value::<TraceId>());
+    assert_eq!(Some(&TraceId(1)), ctx2.value::<TraceId>());
+    // The child's value shadows the parent's one, without affecting the parent.
+    assert_eq!(Some(&TraceId(3)), ctx3.value::<TraceId>());
+    assert_eq!(Some(&TraceId(3)), ctx3.with_label("label").value::<TraceId>());
+    assert_eq!(Some(&PeerLabel("peer")), ctx3.value::<PeerLabel>());
+}
+
+#[tokio::test]
+async fn test_value_missing() {
+    let ctx = Ctx::background();
+    assert_eq!(None, ctx.value::<TraceId>());
+    let ctx = ctx.with_value(PeerLabel("peer"));
+    assert_eq!(None, ctx.value::<TraceId>());
+    // Values are keyed by type, so a different type with the same contents doesn't match.
+    assert_eq!(None, ctx.value::<&'static str>());
+}
+
+#[tokio::test]
+async fn test_value_cancellation() {
+    let ctx1 = Ctx::background().with_cancel();
+    let ctx2 = ctx1.with_value(TraceId(1));
+    assert!(ctx2.err().is_none());
+    ctx1.cancel();
+    assert_eq!(ctx::Error::Cancelled, ctx2.done().await);
+    // The values are still accessible after cancellation.
+    assert_eq!(Some(&TraceId(1)), ctx2.value::<TraceId>());
+}
@@ -7,10 +7,16 @@ pub mod weak_map;
 #[cfg(test)]
 mod ctx_test;
 #[cfg(test)]
+mod once_test;
+#[cfg(test)]
+mod rate_limiter_test;
+#[cfg(test)]
 mod scope_test;
+#[cfg(test)]
+mod weak_map_test;
 
 pub use ctx::{Ctx, CtxWithCancel};
 pub use once::Once;
-pub use rate_limiter::RateLimiter;
-pub use scope::Scope;
+pub use rate_limiter::{RateLimit, RateLimiter};
+pub use scope::{Scope, ScopeHandle};
 pub use weak_map::WeakMap;
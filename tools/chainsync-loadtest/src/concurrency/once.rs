@@ -1,5 +1,6 @@
 use parking_lot::RwLock;
 use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 fn is_send<T: Send>() {}
 fn is_sync<T: Sync>() {}
@@ -16,11 +17,27 @@ fn test<T: Clone + Send + Sync>() {
 pub struct Once<T> {
     value: RwLock<Option<T>>,
     notify: tokio::sync::Notify,
+    // Number of wait() futures which haven't returned the value yet.
+    waiters: AtomicUsize,
+}
+
+// WaiterGuard decrements Once::waiters once the wait() future
+// completes or gets dropped.
+struct WaiterGuard<'a>(&'a AtomicUsize);
+
+impl<'a> Drop for WaiterGuard<'a> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl<T: Clone + Send + Sync> Once<T> {
     pub fn new() -> Once<T> {
-        return Once { value: RwLock::new(None), notify: tokio::sync::Notify::new() };
+        return Once {
+            value: RwLock::new(None),
+            notify: tokio::sync::Notify::new(),
+            waiters: AtomicUsize::new(0),
+        };
     }
 
     // set() sets the value of Once to x.
@@ -46,13 +63,38 @@ impl<T: Clone + Send + Sync> Once<T> {
         let l = self.value.read();
         let v = (*l).clone();
         let n = self.notify.notified();
+        // Registering the waiter under the lock synchronizes it with take_and_reset().
+        let guard = if v.is_none() {
+            self.waiters.fetch_add(1, Ordering::SeqCst);
+            Some(WaiterGuard(&self.waiters))
+        } else {
+            None
+        };
         drop(l);
         async move {
             if let Some(v) = v {
                 return v;
             }
             n.await;
-            return self.get().unwrap();
+            // The value cannot be reset while the guard is alive.
+            let v = self.get().unwrap();
+            drop(guard);
+            return v;
+        }
+    }
+
+    // take_and_reset() takes the value out of Once (None if it wasn't set),
+    // so that Once can be set again. It is meant to be used by a single owner
+    // reusing Once, when no one else is waiting for it. To make sure that
+    // the waiters never miss the value they were waiting for, it returns an error
+    // if there are any wait() futures which haven't returned the value yet
+    // (even if the value has already been set).
+    pub fn take_and_reset(&self) -> anyhow::Result<Option<T>> {
+        let mut v = self.value.write();
+        let waiters = self.waiters.load(Ordering::SeqCst);
+        if waiters > 0 {
+            anyhow::bail!("Once has {} pending waiters", waiters);
         }
+        return Ok(v.take());
     }
 }
@@ -0,0 +1,61 @@
+use crate::concurrency::Once;
+
+use std::sync::Arc;
+use tokio::time;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_set_single_winner() {
+    for _ in 0..10 {
+        let once = Arc::new(Once::new());
+        let handles: Vec<_> = (0..32)
+            .map(|i| {
+                let once = once.clone();
+                tokio::spawn(async move { once.set(i).is_ok() })
+            })
+            .collect();
+        let mut winners = 0;
+        for h in handles {
+            if h.await.unwrap() {
+                winners += 1;
+            }
+        }
+        assert_eq!(1, winners);
+        assert!(once.get().is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_take_and_reset() {
+    let once = Once::new();
+    assert_eq!(None, once.take_and_reset().unwrap());
+    once.set(1).unwrap();
+    assert_eq!(1, once.wait().await);
+    assert_eq!(Some(1), once.take_and_reset().unwrap());
+    assert_eq!(None, once.get());
+    // Once can be set again after reset.
+    once.set(2).unwrap();
+    assert_eq!(2, once.wait().await);
+}
+
+#[tokio::test]
+async fn test_take_and_reset_with_waiters() {
+    let once = Arc::new(Once::new());
+    let waiter = tokio::spawn({
+        let once = once.clone();
+        async move { once.wait().await }
+    });
+    // Let the waiter block.
+    time::sleep(time::Duration::from_millis(10)).await;
+    assert!(once.take_and_reset().is_err());
+    once.set(1).unwrap();
+    // The waiter hasn't observed the value yet, so reset is still not allowed.
+    assert!(once.take_and_reset().is_err());
+    assert_eq!(1, waiter.await.unwrap());
+    assert_eq!(Some(1), once.take_and_reset().unwrap());
+
+    // Dropping a pending wait() future unregisters the waiter.
+    let w = once.wait();
+    assert!(once.take_and_reset().is_err());
+    drop(w);
+    assert_eq!(None, once.take_and_reset().unwrap());
+}
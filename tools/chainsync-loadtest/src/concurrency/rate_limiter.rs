@@ -19,22 +19,43 @@ impl RateLimiter_ {
     }
 }
 
-// RateLimiter is a Semaphore with periodically added permits.
+// RateLimit configures a RateLimiter:
+// - qps - the steady number of permits added per second.
+// - burst - the maximal number of permits that can accumulate while the
+//   RateLimiter is idle, i.e. the number of operations that can proceed
+//   immediately after an idle period. With burst == 1 the operations
+//   are evenly spaced at the steady rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub qps: u32,
+    pub burst: u64,
+}
+
+impl RateLimit {
+    fn interval(&self) -> time::Duration {
+        time::Duration::from_secs(1) / self.qps
+    }
+}
+
+// RateLimiter is a Semaphore with periodically added permits (aka token bucket).
 // It allows to rate limit any async-based operations.
-// It is parametrized by:
-// - interval - the amount of time after which a new permit is added.
+// It is parametrized by RateLimit:
+// - 1/qps - the amount of time after which a new permit is added.
 // - burst - the maximal number of permits in the semaphore.
 pub struct RateLimiter(Arc<tokio::sync::Mutex<RateLimiter_>>);
 
 impl RateLimiter {
-    pub fn new(interval: time::Duration, burst: u64) -> RateLimiter {
-        if interval.is_zero() {
-            panic!("interval has to be non-zero");
+    pub fn new(limit: RateLimit) -> RateLimiter {
+        if limit.qps == 0 {
+            panic!("qps has to be non-zero");
+        }
+        if limit.burst == 0 {
+            panic!("burst has to be non-zero");
         }
         return RateLimiter(Arc::new(tokio::sync::Mutex::new(RateLimiter_ {
-            interval,
-            burst,
-            tokens: burst,
+            interval: limit.interval(),
+            burst: limit.burst,
+            tokens: limit.burst,
             start: time::Instant::now(),
             ticks_processed: 0,
         })));
@@ -42,19 +63,36 @@ impl RateLimiter {
 
     // See semantics of https://pkg.go.dev/golang.org/x/time/rate
     pub async fn allow(&self, ctx: &Ctx) -> anyhow::Result<()> {
+        self.allow_n(ctx, 1).await
+    }
+
+    // allow_n() waits until n permits are available and takes all of them at once.
+    // It allows to rate limit operations of different cost (by assigning them
+    // a cost proportional weight n).
+    // - Returns an error immediately if n > burst, since that many permits
+    //   can never be available at the same time.
+    // - The waiters are served in FIFO order: a waiter holds the lock while it
+    //   waits for the missing permits, so that a large request cannot be starved
+    //   by a stream of small ones.
+    // - If ctx gets cancelled while waiting, no permits are taken.
+    pub async fn allow_n(&self, ctx: &Ctx, n: u64) -> anyhow::Result<()> {
         let mut rl = ctx.wrap(self.0.lock()).await?;
+        if n > rl.burst {
+            anyhow::bail!("requested {} permits, which exceeds burst = {}", n, rl.burst);
+        }
         let ticks_now = rl.ticks(time::Instant::now());
         rl.tokens = std::cmp::min(
             rl.burst,
             rl.tokens.wrapping_add(ticks_now.wrapping_sub(rl.ticks_processed)),
         );
         rl.ticks_processed = ticks_now;
-        if rl.tokens > 0 {
-            rl.tokens -= 1;
-            return Ok(());
+        if rl.tokens < n {
+            let missing = n - rl.tokens;
+            ctx.wait_until(rl.instant(rl.ticks_processed + missing)).await?;
+            rl.ticks_processed += missing;
+            rl.tokens += missing;
         }
-        ctx.wait_until(rl.instant(rl.ticks_processed + 1)).await?;
-        rl.ticks_processed += 1;
+        rl.tokens -= n;
         Ok(())
     }
 }
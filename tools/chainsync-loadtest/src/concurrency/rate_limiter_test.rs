@@ -0,0 +1,115 @@
+use crate::concurrency::{Ctx, RateLimit, RateLimiter};
+
+use tokio::time;
+
+#[tokio::test]
+async fn test_burst_after_idle() {
+    let ctx = Ctx::background();
+    let rl = RateLimiter::new(RateLimit { qps: 20, burst: 5 });
+    // Drain the initial burst.
+    for _ in 0..5 {
+        rl.allow(&ctx).await.unwrap();
+    }
+    // Stay idle long enough to accumulate the full burst (and more).
+    time::sleep(time::Duration::from_millis(500)).await;
+    // 5 permits should be available immediately.
+    let start = time::Instant::now();
+    for _ in 0..5 {
+        rl.allow(&ctx).await.unwrap();
+    }
+    assert!(start.elapsed() < time::Duration::from_millis(50));
+    // Then the bucket is empty and the permits are added every 50ms.
+    for _ in 0..2 {
+        rl.allow(&ctx).await.unwrap();
+    }
+    assert!(start.elapsed() >= time::Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_sustained_rate() {
+    let ctx = Ctx::background();
+    for burst in [1, 10] {
+        let qps = 100;
+        let rl = RateLimiter::new(RateLimit { qps, burst });
+        let start = time::Instant::now();
+        // After the initial burst, permits are added every 1/qps.
+        let n = 50;
+        for _ in 0..burst + n {
+            rl.allow(&ctx).await.unwrap();
+        }
+        let elapsed = start.elapsed();
+        let expected = time::Duration::from_secs(1) / qps * n as u32;
+        assert!(elapsed >= expected, "burst = {}: {:?} < {:?}", burst, elapsed, expected);
+        assert!(elapsed < expected * 2, "burst = {}: {:?} >= 2 * {:?}", burst, elapsed, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_allow_n() {
+    let ctx = Ctx::background();
+    let rl = RateLimiter::new(RateLimit { qps: 100, burst: 10 });
+    let start = time::Instant::now();
+    // The whole burst at once.
+    rl.allow_n(&ctx, 10).await.unwrap();
+    assert!(start.elapsed() < time::Duration::from_millis(10));
+    // Waits until all 5 permits have been added.
+    rl.allow_n(&ctx, 5).await.unwrap();
+    assert!(start.elapsed() >= time::Duration::from_millis(50));
+    // Taking 0 permits never waits.
+    let start = time::Instant::now();
+    rl.allow_n(&ctx, 0).await.unwrap();
+    assert!(start.elapsed() < time::Duration::from_millis(10));
+}
+
+#[tokio::test]
+async fn test_allow_n_exceeding_burst() {
+    let ctx = Ctx::background();
+    let rl = RateLimiter::new(RateLimit { qps: 100, burst: 10 });
+    // More than burst permits can never be available at once, so it fails immediately.
+    assert!(rl.allow_n(&ctx, 11).await.is_err());
+    // No permits have been taken.
+    let start = time::Instant::now();
+    rl.allow_n(&ctx, 10).await.unwrap();
+    assert!(start.elapsed() < time::Duration::from_millis(10));
+}
+
+#[tokio::test]
+async fn test_allow_n_cancelled() {
+    let rl = RateLimiter::new(RateLimit { qps: 10, burst: 10 });
+    rl.allow_n(&Ctx::background(), 10).await.unwrap();
+    // Cancel while waiting for the permits to be added.
+    let ctx = Ctx::background().with_timeout(time::Duration::from_millis(150));
+    let start = time::Instant::now();
+    assert!(rl.allow_n(&ctx, 5).await.is_err());
+    assert!(start.elapsed() < time::Duration::from_millis(500));
+    // The permits added in the meantime were not taken by the cancelled call.
+    time::sleep(time::Duration::from_millis(150)).await;
+    let start = time::Instant::now();
+    rl.allow_n(&Ctx::background(), 3).await.unwrap();
+    assert!(start.elapsed() < time::Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_allow_n_fairness() {
+    let ctx = Ctx::background();
+    let rl = std::sync::Arc::new(RateLimiter::new(RateLimit { qps: 100, burst: 10 }));
+    rl.allow_n(&ctx, 10).await.unwrap();
+    // A large request is queued first, then a stream of small ones.
+    let large = tokio::spawn({
+        let rl = rl.clone();
+        let ctx = ctx.clone();
+        async move {
+            rl.allow_n(&ctx, 10).await.unwrap();
+            time::Instant::now()
+        }
+    });
+    time::sleep(time::Duration::from_millis(5)).await;
+    let mut small_done = vec![];
+    for _ in 0..5 {
+        rl.allow(&ctx).await.unwrap();
+        small_done.push(time::Instant::now());
+    }
+    // The large request has been served before any of the small ones.
+    let large_done = large.await.unwrap();
+    assert!(small_done.iter().all(|t| large_done <= *t));
+}
@@ -44,6 +44,33 @@ impl WaitGroup {
     }
 }
 
+// ScopeHandle allows to await the result of a future spawned via Scope::spawn_with_handle.
+// Dropping the handle doesn't affect the future: it still runs to completion as
+// a member of the scope and its result is discarded.
+pub struct ScopeHandle<T> {
+    // Context of the scope that the future has been spawned in.
+    ctx: Ctx,
+    recv: tokio::sync::oneshot::Receiver<T>,
+}
+
+impl<T> ScopeHandle<T> {
+    // join() waits for the future to complete and returns its result.
+    // - If the future returns an error, the scope gets cancelled and the error
+    //   is returned from Scope::run. join() returns the cancellation error then,
+    //   as for any other handle of the cancelled scope.
+    // - If the scope gets cancelled, join() returns the cancellation error, even if
+    //   the future has completed successfully.
+    // - If ctx gets cancelled, join() returns ctx's error.
+    pub async fn join(self, ctx: &Ctx) -> anyhow::Result<T> {
+        let res = ctx.wrap(self.ctx.wrap(self.recv)).await??;
+        if let Some(e) = self.ctx.err() {
+            return Err(e.into());
+        }
+        // The value is not sent only if the future has failed, which cancels the scope.
+        Ok(res.map_err(|_| self.ctx.err().unwrap_or(ctx::Error::Cancelled))?)
+    }
+}
+
 struct ScopeState {
     err: Option<anyhow::Error>,
     cancelled: bool,
@@ -97,6 +124,61 @@ impl Scope {
         });
     }
 
+    // Spawn a "main" future in the scope and return a handle to await its result.
+    // See ScopeHandle for details.
+    pub fn spawn_with_handle<T, F>(
+        self: &Arc<Self>,
+        f: impl Send + FnOnce(Ctx, Arc<Self>) -> F,
+    ) -> ScopeHandle<T>
+    where
+        T: Send + 'static,
+        F: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self.spawn(|ctx, s| {
+            let fut = f(ctx, s);
+            async move {
+                // Ignore the error: it just means that the handle has been dropped.
+                let _ = send.send(fut.await?);
+                anyhow::Ok(())
+            }
+        });
+        ScopeHandle { ctx: (*self.ctx).clone(), recv }
+    }
+
+    // Spawn a "main" future in the scope, which will start executing only once it
+    // acquires a permit from the <limit> semaphore (and will release it on completion).
+    // It allows to bound the number of concurrently running futures: share the same
+    // semaphore between the spawn_bounded() calls which should be bounded together.
+    // The future is accounted as a member of the scope immediately (as with spawn()),
+    // even if it is still waiting for a permit. If the scope gets cancelled before
+    // that, the future is dropped without being executed.
+    pub fn spawn_bounded<F>(
+        self: &Arc<Self>,
+        limit: &Arc<tokio::sync::Semaphore>,
+        f: impl Send + FnOnce(Ctx, Arc<Self>) -> F,
+    ) where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let fut = f((*self.ctx).clone(), self.clone());
+        let s = self.clone();
+        let limit = limit.clone();
+        s.main_futures.inc();
+        tokio::spawn(async move {
+            let res = async {
+                let _permit = s.ctx.wrap(limit.acquire_owned()).await??;
+                // The permit and the cancellation might have become available at the same time.
+                if let Some(e) = s.ctx.err() {
+                    return Err(e.into());
+                }
+                fut.await
+            }
+            .await;
+            s.complete(res);
+            s.main_futures.dec();
+        });
+    }
+
     // Spawn a "weak" future in the scope.
     pub fn spawn_weak<F>(self: &Arc<Self>, f: impl Send + FnOnce(Ctx) -> F)
     where
@@ -1,4 +1,9 @@
 use crate::concurrency::scope::WaitGroup;
+use crate::concurrency::{ctx, Ctx, Scope};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::time;
 
 #[tokio::test]
 async fn test_wait_group() {
@@ -18,3 +23,127 @@ async fn test_wait_group() {
     // wg is empty again, so this should return immediately.
     wg.wait().await;
 }
+
+#[tokio::test]
+async fn test_spawn_bounded() {
+    let limit = 3;
+    let running = Arc::new(AtomicU64::new(0));
+    let high_water_mark = Arc::new(AtomicU64::new(0));
+    let done = Arc::new(AtomicU64::new(0));
+    Scope::run(&Ctx::background(), {
+        let running = running.clone();
+        let high_water_mark = high_water_mark.clone();
+        let done = done.clone();
+        move |_ctx, s| async move {
+            let sem = Arc::new(tokio::sync::Semaphore::new(limit));
+            for _ in 0..20 {
+                let running = running.clone();
+                let high_water_mark = high_water_mark.clone();
+                let done = done.clone();
+                s.spawn_bounded(&sem, |ctx, _s| async move {
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    high_water_mark.fetch_max(n, Ordering::SeqCst);
+                    ctx.wait(time::Duration::from_millis(5)).await?;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    done.fetch_add(1, Ordering::SeqCst);
+                    anyhow::Ok(())
+                });
+            }
+            anyhow::Ok(())
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(20, done.load(Ordering::SeqCst));
+    assert_eq!(limit as u64, high_water_mark.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_spawn_bounded_cancel() {
+    let started = Arc::new(AtomicU64::new(0));
+    let start = time::Instant::now();
+    let res = Scope::run(&Ctx::background(), {
+        let started = started.clone();
+        move |_ctx, s| async move {
+            let sem = Arc::new(tokio::sync::Semaphore::new(1));
+            // Blocks until the scope gets cancelled, holding the only permit.
+            s.spawn_bounded(&sem, |ctx, _s| async move {
+                ctx.done().await;
+                anyhow::Ok(())
+            });
+            // Queued spawns, waiting for the permit.
+            for _ in 0..10 {
+                let started = started.clone();
+                s.spawn_bounded(&sem, |_ctx, _s| async move {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    anyhow::Ok(())
+                });
+            }
+            s.spawn(|ctx, _s| async move {
+                ctx.wait(time::Duration::from_millis(10)).await?;
+                anyhow::bail!("boom")
+            });
+            anyhow::Ok(())
+        }
+    })
+    .await;
+    // The error cancels the scope, and the queued spawns are dropped without being executed.
+    assert_eq!("boom", res.unwrap_err().to_string());
+    assert_eq!(0, started.load(Ordering::SeqCst));
+    assert!(start.elapsed() < time::Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_spawn_with_handle() {
+    let res = Scope::run(&Ctx::background(), |ctx, s| async move {
+        let h1 = s.spawn_with_handle(|_ctx, _s| async move { anyhow::Ok(1) });
+        let h2 = s.spawn_with_handle(|ctx, _s| async move {
+            ctx.wait(time::Duration::from_millis(5)).await?;
+            anyhow::Ok("two")
+        });
+        anyhow::Ok((h1.join(&ctx).await?, h2.join(&ctx).await?))
+    })
+    .await;
+    assert_eq!((1, "two"), res.unwrap());
+}
+
+#[tokio::test]
+async fn test_spawn_with_handle_error() {
+    let res = Scope::run(&Ctx::background(), |ctx, s| async move {
+        let failing =
+            s.spawn_with_handle(|_ctx, _s| async move { Err::<(), _>(anyhow::anyhow!("boom")) });
+        let waiting = s.spawn_with_handle(|ctx, _s| async move {
+            ctx.done().await;
+            anyhow::Ok(())
+        });
+        // The failure cancels the scope, which is observed by all the handles.
+        let err = failing.join(&ctx).await.unwrap_err();
+        assert_eq!(Some(&ctx::Error::Cancelled), err.downcast_ref::<ctx::Error>());
+        let err = waiting.join(&ctx).await.unwrap_err();
+        assert_eq!(Some(&ctx::Error::Cancelled), err.downcast_ref::<ctx::Error>());
+        anyhow::Ok(())
+    })
+    .await;
+    // The original error is returned from the scope.
+    assert_eq!("boom", res.unwrap_err().to_string());
+}
+
+#[tokio::test]
+async fn test_spawn_with_handle_dropped() {
+    let done = Arc::new(AtomicU64::new(0));
+    Scope::run(&Ctx::background(), {
+        let done = done.clone();
+        |_ctx, s| async move {
+            // The handle is dropped immediately, but the future still runs to completion.
+            drop(s.spawn_with_handle(|ctx, _s| async move {
+                ctx.wait(time::Duration::from_millis(5)).await?;
+                done.fetch_add(1, Ordering::SeqCst);
+                anyhow::Ok(5)
+            }));
+            anyhow::Ok(())
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(1, done.load(Ordering::SeqCst));
+}
@@ -1,15 +1,74 @@
 use std::cmp::Eq;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+// WeakMapStats contains the numbers of entries evicted from the WeakMap.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WeakMapStats {
+    // Number of entries evicted because their ttl has passed.
+    pub expired: u64,
+    // Number of entries evicted because the map reached max_entries.
+    pub evicted: u64,
+}
+
+struct MapEntry<K: Hash + Eq + Clone, V> {
+    value: Weak<Ref<K, V>>,
+    inserted: Instant,
+    // Key of the entry in WeakMapInner::lru.
+    last_used: u64,
+}
+
+struct WeakMapInner<K: Hash + Eq + Clone, V> {
+    entries: HashMap<K, MapEntry<K, V>>,
+    // Keys of the entries ordered by the time of the last access.
+    lru: BTreeMap<u64, K>,
+    next_use: u64,
+    stats: WeakMapStats,
+}
+
+impl<K: Hash + Eq + Clone, V> WeakMapInner<K, V> {
+    fn touch(&mut self, key: &K) {
+        let e = match self.entries.get_mut(key) {
+            Some(e) => e,
+            None => return,
+        };
+        self.lru.remove(&e.last_used);
+        e.last_used = self.next_use;
+        self.lru.insert(self.next_use, key.clone());
+        self.next_use += 1;
+    }
+
+    fn insert(&mut self, key: &K, value: Weak<Ref<K, V>>, now: Instant) {
+        self.remove(key);
+        self.entries
+            .insert(key.clone(), MapEntry { value, inserted: now, last_used: self.next_use });
+        self.lru.insert(self.next_use, key.clone());
+        self.next_use += 1;
+    }
+
+    fn remove(&mut self, key: &K) -> Option<MapEntry<K, V>> {
+        let e = self.entries.remove(key)?;
+        self.lru.remove(&e.last_used);
+        Some(e)
+    }
+}
 
 // WeakMap is a collection of weak pointers.
 // Once the last reference to an element of the map is dropped,
 // the weak pointer is removed from the map.
+// Optionally the entries can be evicted earlier:
+// - once ttl passes since the entry was inserted,
+// - in the least recently used order, once the map reaches max_entries.
+// The evicted entries are absent from the map, but the references to them which are
+// still held remain valid. The eviction happens on insertion and on prune().
 pub struct WeakMap<K: Hash + Eq + Clone, V> {
-    inner: Mutex<HashMap<K, Weak<Ref<K, V>>>>,
+    inner: Mutex<WeakMapInner<K, V>>,
+    max_entries: Option<usize>,
+    ttl: Option<Duration>,
 }
 
 // Ref is a wrapper of V, which provides a custom drop()
@@ -34,40 +93,115 @@ impl<K: Hash + Eq + Clone, V> Drop for Ref<K, V> {
         // because that might trigger this function
         // recursively and cause a deadlock.
         let mut m = self.map.inner.lock().unwrap();
-        let e = match m.entry(self.key.clone()) {
+        let e = match m.entries.entry(self.key.clone()) {
             Entry::Occupied(e) => e,
             Entry::Vacant(_) => {
                 return;
             }
         };
-        if e.get().strong_count() == 0 {
-            e.remove_entry();
+        // If this element has been evicted, the entry may belong
+        // to a newer element with the same key, which is still alive.
+        if e.get().value.strong_count() == 0 {
+            let (_, e) = e.remove_entry();
+            m.lru.remove(&e.last_used);
         }
     }
 }
 
 impl<K: Hash + Eq + Clone, V> WeakMap<K, V> {
-    pub fn new() -> Arc<Self> {
-        return Arc::new(Self { inner: Mutex::new(HashMap::new()) });
+    // new() creates a WeakMap with optional eviction limits (see WeakMap).
+    pub fn new(max_entries: Option<usize>, ttl: Option<Duration>) -> Arc<Self> {
+        if max_entries == Some(0) {
+            panic!("max_entries has to be non-zero");
+        }
+        return Arc::new(Self {
+            inner: Mutex::new(WeakMapInner {
+                entries: HashMap::new(),
+                lru: BTreeMap::new(),
+                next_use: 0,
+                stats: WeakMapStats::default(),
+            }),
+            max_entries,
+            ttl,
+        });
+    }
+
+    fn is_expired(&self, e: &MapEntry<K, V>, now: Instant) -> bool {
+        self.ttl.map_or(false, |ttl| now >= e.inserted + ttl)
     }
 
     // get() returns a reference to map[key], or None if not present.
     pub fn get(self: &Arc<Self>, key: &K) -> Option<Arc<Ref<K, V>>> {
-        let m = self.inner.lock().unwrap();
-        return m.get(key).map(|w| w.upgrade()).flatten();
+        let mut m = self.inner.lock().unwrap();
+        let v = m.entries.get(key).filter(|e| !self.is_expired(e, Instant::now()))?.value.upgrade();
+        if v.is_some() {
+            m.touch(key);
+        }
+        return v;
     }
 
     // get() returns a reference to map[key].
     // Uses new_value to initialize the map entry if missing.
     pub fn get_or_insert(self: &Arc<Self>, key: &K, new_value: impl Fn() -> V) -> Arc<Ref<K, V>> {
         let mut m = self.inner.lock().unwrap();
-        if let Some(w) = m.get(key) {
-            if let Some(v) = w.upgrade() {
+        let now = Instant::now();
+        match m.entries.get(key).filter(|e| !self.is_expired(e, now)).map(|e| e.value.upgrade()) {
+            Some(Some(v)) => {
+                m.touch(key);
                 return v;
             }
+            // The last reference is being dropped concurrently.
+            Some(None) => {
+                m.remove(key);
+            }
+            None => {}
         }
+        // An expired entry for the key (if any) gets evicted here.
+        self.prune_locked(&mut m, now, 1);
         let p = Arc::new(Ref { key: key.clone(), map: self.clone(), value: new_value() });
-        m.insert(key.clone(), Arc::downgrade(&p));
+        m.insert(key, Arc::downgrade(&p), now);
         return p;
     }
+
+    // prune() evicts the expired entries and the least recently used entries
+    // exceeding max_entries. It is called on every insertion, but the caller may
+    // also call it periodically, so that the expired entries do not wait for
+    // the next insertion.
+    pub fn prune(self: &Arc<Self>) {
+        let mut m = self.inner.lock().unwrap();
+        self.prune_locked(&mut m, Instant::now(), 0);
+    }
+
+    // stats() returns the eviction counters.
+    pub fn stats(&self) -> WeakMapStats {
+        self.inner.lock().unwrap().stats
+    }
+
+    // prune_locked() evicts entries, so that <reserve> new entries can be inserted
+    // without exceeding max_entries. Only the weak pointers are dropped here,
+    // so it is safe to call with the lock held.
+    fn prune_locked(&self, m: &mut WeakMapInner<K, V>, now: Instant, reserve: usize) {
+        if self.ttl.is_some() {
+            let expired: Vec<K> = m
+                .entries
+                .iter()
+                .filter(|(_, e)| self.is_expired(e, now))
+                .map(|(k, _)| k.clone())
+                .collect();
+            for k in expired {
+                m.remove(&k);
+                m.stats.expired += 1;
+            }
+        }
+        if let Some(max_entries) = self.max_entries {
+            while m.entries.len() + reserve > max_entries {
+                let k = match m.lru.values().next() {
+                    Some(k) => k.clone(),
+                    None => break,
+                };
+                m.remove(&k);
+                m.stats.evicted += 1;
+            }
+        }
+    }
 }
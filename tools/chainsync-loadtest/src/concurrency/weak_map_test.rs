@@ -0,0 +1,87 @@
+use crate::concurrency::weak_map::WeakMapStats;
+use crate::concurrency::WeakMap;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn test_drop_removes_entry() {
+    let m = WeakMap::<u64, u64>::new(None, None);
+    let v = m.get_or_insert(&1, || 10);
+    assert_eq!(10, **m.get(&1).unwrap());
+    drop(v);
+    assert!(m.get(&1).is_none());
+    assert_eq!(WeakMapStats::default(), m.stats());
+}
+
+#[test]
+fn test_ttl() {
+    let m = WeakMap::<u64, u64>::new(None, Some(Duration::from_millis(20)));
+    let v1 = m.get_or_insert(&1, || 10);
+    std::thread::sleep(Duration::from_millis(30));
+    // An expired key behaves as absent, even though it is still referenced.
+    assert!(m.get(&1).is_none());
+    assert_eq!(10, **v1);
+    // A fresh entry is inserted for the expired key.
+    let v2 = m.get_or_insert(&1, || 11);
+    assert!(!Arc::ptr_eq(&v1, &v2));
+    assert_eq!(WeakMapStats { expired: 1, evicted: 0 }, m.stats());
+    // Dropping the expired element doesn't affect the fresh entry.
+    drop(v1);
+    assert_eq!(11, **m.get(&1).unwrap());
+
+    // prune() evicts expired entries without waiting for the next insertion.
+    let _v3 = m.get_or_insert(&3, || 30);
+    std::thread::sleep(Duration::from_millis(30));
+    m.prune();
+    assert_eq!(WeakMapStats { expired: 3, evicted: 0 }, m.stats());
+    assert!(m.get(&3).is_none());
+    assert_eq!(11, **v2);
+}
+
+#[test]
+fn test_lru() {
+    let m = WeakMap::<u64, u64>::new(Some(3), None);
+    let _v: Vec<_> = (0..3).map(|k| m.get_or_insert(&k, || k)).collect();
+    // Access 0 and 1, so that 2 becomes the least recently used.
+    m.get(&0).unwrap();
+    m.get_or_insert(&1, || unreachable!());
+    let _v3 = m.get_or_insert(&3, || 3);
+    assert!(m.get(&2).is_none());
+    assert_eq!(WeakMapStats { expired: 0, evicted: 1 }, m.stats());
+    // Now 0 is the least recently used.
+    let _v4 = m.get_or_insert(&4, || 4);
+    assert!(m.get(&0).is_none());
+    for k in [1, 3, 4] {
+        assert_eq!(k, **m.get(&k).unwrap());
+    }
+    assert_eq!(WeakMapStats { expired: 0, evicted: 2 }, m.stats());
+}
+
+#[test]
+fn test_concurrent_get_or_insert() {
+    let m = WeakMap::<u64, u64>::new(Some(1), None);
+    // Concurrent get_or_insert() calls for the same key share the element.
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let m = m.clone();
+            std::thread::spawn(move || m.get_or_insert(&1, || 10))
+        })
+        .collect();
+    let refs: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert!(refs.iter().all(|r| Arc::ptr_eq(r, &refs[0])));
+
+    // The element gets evicted by another key, but the references remain valid.
+    let v2 = m.get_or_insert(&2, || 20);
+    assert!(m.get(&1).is_none());
+    assert_eq!(10, **refs[0]);
+    // Reinserting the evicted key creates a new element.
+    let v1 = m.get_or_insert(&1, || 11);
+    assert!(!Arc::ptr_eq(&v1, &refs[0]));
+    assert!(m.get(&2).is_none());
+    // Dropping the references to the evicted element doesn't affect the new one.
+    drop(refs);
+    assert_eq!(11, **m.get(&1).unwrap());
+    assert_eq!(20, **v2);
+    assert_eq!(WeakMapStats { expired: 0, evicted: 2 }, m.stats());
+}
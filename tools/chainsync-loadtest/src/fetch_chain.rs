@@ -1,97 +1,319 @@
 use std::sync::Arc;
 
-use crate::concurrency::{Ctx, Scope};
+use crate::concurrency::{ctx, Ctx, Scope};
 use crate::network;
 use anyhow::Context;
-use log::info;
+use futures::future::BoxFuture;
+use log::{info, warn};
+use std::future::Future;
 use std::sync::atomic::Ordering;
 use tokio::time;
 
+use near_primitives::block::{Block, BlockHeader};
 use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ShardChunkHeader;
+use near_primitives::types::BlockHeight;
 
-// run() fetches the chain (headers,blocks and chunks)
-// starting with block having hash = <start_block_hash> and
-// ending with the current tip of the chain (snapshotted once
-// at the start of the routine, so that the amount of work
-// is bounded).
-pub async fn run(
-    ctx: Ctx,
-    network: Arc<network::Network>,
-    start_block_hash: CryptoHash,
-    block_limit: u64,
-) -> anyhow::Result<()> {
-    info!("SYNC start");
-    let peers = network.info(&ctx).await?;
-    let target_height = peers.highest_height_peers[0].chain_info.height as i64;
-    info!("SYNC target_height = {}", target_height);
+// ChainSource is the part of the Network API used by run().
+// It allows to test run() against a mock network.
+pub trait ChainSource: Clone + Send + Sync + 'static {
+    fn stats(&self) -> &network::Stats;
+    // head_height() returns the height of the highest block known to the peers.
+    fn head_height<'a>(&'a self, ctx: &'a Ctx) -> BoxFuture<'a, anyhow::Result<BlockHeight>>;
+    fn fetch_block_headers<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        hash: &'a CryptoHash,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<BlockHeader>>>;
+    fn fetch_block<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        hash: &'a CryptoHash,
+    ) -> BoxFuture<'a, anyhow::Result<Block>>;
+    fn fetch_chunk<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        ch: &'a ShardChunkHeader,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+impl ChainSource for Arc<network::Network> {
+    fn stats(&self) -> &network::Stats {
+        &self.stats
+    }
+    fn head_height<'a>(&'a self, ctx: &'a Ctx) -> BoxFuture<'a, anyhow::Result<BlockHeight>> {
+        Box::pin(async move {
+            let peers = self.info(ctx).await?;
+            Ok(peers.highest_height_peers.get(0).context("no peers")?.chain_info.height)
+        })
+    }
+    fn fetch_block_headers<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        hash: &'a CryptoHash,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<BlockHeader>>> {
+        Box::pin(network::Network::fetch_block_headers(self, ctx, hash))
+    }
+    fn fetch_block<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        hash: &'a CryptoHash,
+    ) -> BoxFuture<'a, anyhow::Result<Block>> {
+        Box::pin(network::Network::fetch_block(self, ctx, hash))
+    }
+    fn fetch_chunk<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        ch: &'a ShardChunkHeader,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            network::Network::fetch_chunk(self, ctx, ch).await?;
+            Ok(())
+        })
+    }
+}
 
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    // Fetch the blocks following the start block, in the order of increasing heights.
+    Forward,
+    // Fetch the start block and its ancestors, in the order of decreasing heights.
+    Backward,
+}
+
+pub struct Config {
+    pub start_block_hash: CryptoHash,
+    pub direction: Direction,
+    // Only the blocks with heights in [from_height,to_height] are fetched
+    // (the bounds are optional).
+    pub from_height: Option<BlockHeight>,
+    pub to_height: Option<BlockHeight>,
+    // Maximal number of blocks to fetch.
+    pub block_limit: u64,
+    // Maximal number of blocks fetched concurrently.
+    pub concurrency_limit: usize,
+    // Blocks (and chunks) which are not fetched within block_timeout are considered
+    // unavailable (the peers might have garbage collected them, or they might be
+    // out of the range known to the peers).
+    pub block_timeout: time::Duration,
+}
+
+// with_timeout() executes f with a child context which expires after <timeout>.
+// Returns None if f fails because the timeout has passed.
+async fn with_timeout<T, F>(
+    ctx: &Ctx,
+    timeout: time::Duration,
+    f: impl FnOnce(Ctx) -> F,
+) -> anyhow::Result<Option<T>>
+where
+    F: Future<Output = anyhow::Result<T>>,
+{
+    let child = ctx.with_timeout(timeout);
+    match f((*child).clone()).await {
+        Ok(v) => Ok(Some(v)),
+        Err(_) if ctx.err().is_none() && child.err() == Some(ctx::Error::DeadlineExceeded) => {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// spawn_fetch_chunks() spawns fetching of all chunks of the block in scope s.
+fn spawn_fetch_chunks<S: ChainSource>(
+    s: &Arc<Scope>,
+    source: &S,
+    block: &Block,
+    timeout: time::Duration,
+) {
+    for ch in block.chunks().iter() {
+        let ch = ch.clone();
+        let source = source.clone();
+        s.spawn(move |ctx, _s| async move {
+            let res = with_timeout(&ctx, timeout, |ctx| {
+                let source = source.clone();
+                async move { source.fetch_chunk(&ctx, &ch).await }
+            })
+            .await?;
+            if res.is_none() {
+                source.stats().chunks_unavailable.fetch_add(1, Ordering::Relaxed);
+            }
+            anyhow::Ok(())
+        });
+    }
+}
+
+// run() fetches the chain (headers,blocks and chunks) in the given direction,
+// starting with block having hash = <cfg.start_block_hash>:
+// - Forward: up to the current tip of the chain (snapshotted once at the start of the
+//   routine, so that the amount of work is bounded), or cfg.to_height if lower.
+// - Backward: down to the genesis block, or cfg.from_height if higher. The parent hashes
+//   are taken from the fetched blocks, so gaps in the heights are handled correctly.
+// Blocks which are unavailable (see Config::block_timeout) are counted in the stats,
+// rather than failing the run. In the backward direction an unavailable block
+// ends the traversal, since its parent cannot be determined.
+pub async fn run<S: ChainSource>(ctx: Ctx, source: S, cfg: Config) -> anyhow::Result<()> {
+    info!("SYNC start");
     let start_time = time::Instant::now();
     let res = Scope::run(&ctx, {
-        let network = network.clone();
+        let source = source.clone();
         |ctx, s| async move {
             s.spawn_weak({
-                let network = network.clone();
+                let source = source.clone();
                 |ctx| async move {
                     let ctx = ctx.with_label("stats");
                     loop {
-                        info!("stats = {:?}", network.stats);
+                        info!("stats = {:?}", source.stats());
                         ctx.wait(time::Duration::from_secs(2)).await?;
                     }
                 }
             });
-
-            let mut last_hash = start_block_hash;
-            let mut last_height = 0;
-            let mut blocks_count = 0;
-            while last_height < target_height {
-                // Fetch the next batch of headers.
-                let mut headers = network.fetch_block_headers(&ctx, &last_hash).await?;
-                headers.sort_by_key(|h| h.height());
-                let last_header = headers.last().context("no headers")?;
-                last_hash = last_header.hash().clone();
-                last_height = last_header.height() as i64;
-                info!(
-                    "SYNC last_height = {}, {} headers left",
-                    last_height,
-                    target_height - last_height
-                );
-                for h in headers {
-                    blocks_count += 1;
-                    if blocks_count == block_limit {
-                        return anyhow::Ok(());
-                    }
-                    s.spawn({
-                        let network = network.clone();
-                        |ctx, s| async move {
-                            let block = network.fetch_block(&ctx, h.hash()).await?;
-                            for ch in block.chunks().iter() {
-                                let ch = ch.clone();
-                                let network = network.clone();
-                                s.spawn(|ctx, _s| async move {
-                                    network.fetch_chunk(&ctx, &ch).await?;
-                                    anyhow::Ok(())
-                                });
-                            }
-                            anyhow::Ok(())
-                        }
-                    });
-                }
+            match cfg.direction {
+                Direction::Forward => fetch_forward(&ctx, &s, &source, &cfg).await,
+                Direction::Backward => fetch_backward(&ctx, &s, &source, &cfg).await,
             }
-            anyhow::Ok(())
         }
     })
     .await;
     let stop_time = time::Instant::now();
     let total_time = stop_time - start_time;
     let t = total_time.as_secs_f64();
-    let sent = network.stats.msgs_sent.load(Ordering::Relaxed);
-    let headers = network.stats.header_done.load(Ordering::Relaxed);
-    let blocks = network.stats.block_done.load(Ordering::Relaxed);
-    let chunks = network.stats.chunk_done.load(Ordering::Relaxed);
+    let stats = source.stats();
+    let sent = stats.msgs_sent.load(Ordering::Relaxed);
+    let headers = stats.header_done.load(Ordering::Relaxed);
+    let blocks = stats.block_done.load(Ordering::Relaxed);
+    let chunks = stats.chunk_done.load(Ordering::Relaxed);
     info!("running time: {:.2}s", t);
     info!("average QPS: {:.2}", (sent as f64) / t);
     info!("fetched {} header batches ({:.2} per second)", headers, headers as f64 / t);
     info!("fetched {} blocks ({:.2} per second)", blocks, blocks as f64 / t);
     info!("fetched {} chunks ({:.2} per second)", chunks, chunks as f64 / t);
+    info!(
+        "unavailable: {} header batches, {} blocks, {} chunks",
+        stats.headers_unavailable.load(Ordering::Relaxed),
+        stats.blocks_unavailable.load(Ordering::Relaxed),
+        stats.chunks_unavailable.load(Ordering::Relaxed),
+    );
     return res;
 }
+
+async fn fetch_forward<S: ChainSource>(
+    ctx: &Ctx,
+    s: &Arc<Scope>,
+    source: &S,
+    cfg: &Config,
+) -> anyhow::Result<()> {
+    let head_height = source.head_height(ctx).await?;
+    let target_height = match cfg.to_height {
+        Some(to_height) if to_height > head_height => {
+            warn!("to_height = {} is above the head height = {}", to_height, head_height);
+            head_height
+        }
+        Some(to_height) => to_height,
+        None => head_height,
+    };
+    info!("SYNC target_height = {}", target_height);
+
+    let blocks_sem = Arc::new(tokio::sync::Semaphore::new(cfg.concurrency_limit));
+    let mut last_hash = cfg.start_block_hash;
+    let mut last_height = 0;
+    let mut blocks_count = 0;
+    while last_height < target_height {
+        // Fetch the next batch of headers.
+        let headers = with_timeout(ctx, cfg.block_timeout, |ctx| {
+            let source = source.clone();
+            async move { source.fetch_block_headers(&ctx, &last_hash).await }
+        })
+        .await?;
+        let mut headers = match headers {
+            Some(headers) => headers,
+            None => {
+                source.stats().headers_unavailable.fetch_add(1, Ordering::Relaxed);
+                warn!("SYNC headers after {} are unavailable, stopping", last_hash);
+                return Ok(());
+            }
+        };
+        headers.sort_by_key(|h| h.height());
+        let last_header = headers.last().context("no headers")?;
+        last_hash = last_header.hash().clone();
+        last_height = last_header.height();
+        info!(
+            "SYNC last_height = {}, {} headers left",
+            last_height,
+            target_height.saturating_sub(last_height)
+        );
+        for h in headers {
+            if h.height() > target_height {
+                return Ok(());
+            }
+            if cfg.from_height.map_or(false, |from_height| h.height() < from_height) {
+                continue;
+            }
+            blocks_count += 1;
+            if blocks_count == cfg.block_limit {
+                return Ok(());
+            }
+            let source = source.clone();
+            let timeout = cfg.block_timeout;
+            s.spawn_bounded(&blocks_sem, move |ctx, s| async move {
+                let hash = *h.hash();
+                let block = with_timeout(&ctx, timeout, |ctx| {
+                    let source = source.clone();
+                    async move { source.fetch_block(&ctx, &hash).await }
+                })
+                .await?;
+                match block {
+                    Some(block) => spawn_fetch_chunks(&s, &source, &block, timeout),
+                    None => {
+                        source.stats().blocks_unavailable.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                anyhow::Ok(())
+            });
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_backward<S: ChainSource>(
+    ctx: &Ctx,
+    s: &Arc<Scope>,
+    source: &S,
+    cfg: &Config,
+) -> anyhow::Result<()> {
+    let mut hash = cfg.start_block_hash;
+    let mut blocks_count = 0;
+    loop {
+        let block = with_timeout(ctx, cfg.block_timeout, |ctx| {
+            let source = source.clone();
+            async move { source.fetch_block(&ctx, &hash).await }
+        })
+        .await?;
+        let block = match block {
+            Some(block) => block,
+            None => {
+                source.stats().blocks_unavailable.fetch_add(1, Ordering::Relaxed);
+                warn!("SYNC block {} is unavailable, stopping", hash);
+                return Ok(());
+            }
+        };
+        let height = block.header().height();
+        if cfg.from_height.map_or(false, |from_height| height < from_height) {
+            return Ok(());
+        }
+        if cfg.to_height.map_or(true, |to_height| height <= to_height) {
+            blocks_count += 1;
+            if blocks_count == cfg.block_limit {
+                return Ok(());
+            }
+            if height % 100 == 0 {
+                info!("SYNC height = {}", height);
+            }
+            spawn_fetch_chunks(s, source, &block, cfg.block_timeout);
+        }
+        hash = *block.header().prev_hash();
+        // The genesis block has no parent.
+        if hash == CryptoHash::default() {
+            return Ok(());
+        }
+    }
+}
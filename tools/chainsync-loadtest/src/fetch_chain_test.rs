@@ -0,0 +1,216 @@
+use crate::concurrency::Ctx;
+use crate::fetch_chain::{run, ChainSource, Config, Direction};
+use crate::network;
+
+use futures::future::BoxFuture;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use tokio::time;
+
+use near_crypto::KeyType;
+use near_primitives::block::{genesis_chunks, Block, BlockHeader};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ShardChunkHeader;
+use near_primitives::time::Clock;
+use near_primitives::types::BlockHeight;
+use near_primitives::validator_signer::InMemoryValidatorSigner;
+use near_primitives::version::PROTOCOL_VERSION;
+
+// Number of headers returned by MockChain::fetch_block_headers().
+const HEADERS_BATCH: usize = 3;
+
+const TIMEOUT: time::Duration = time::Duration::from_millis(50);
+
+struct MockChainInner {
+    stats: network::Stats,
+    // Blocks ordered by height.
+    blocks: Vec<Block>,
+    // Blocks below gc_height are not served (as if garbage collected by the peers).
+    // Requests for them never complete.
+    gc_height: BlockHeight,
+    // Heights of the blocks served so far.
+    fetched: Mutex<Vec<BlockHeight>>,
+}
+
+// MockChain is a ChainSource serving a fixed chain segment.
+#[derive(Clone)]
+struct MockChain(Arc<MockChainInner>);
+
+impl MockChain {
+    // new() creates a chain with blocks at the given heights (starting with a genesis at 0).
+    fn new(heights: &[BlockHeight], gc_height: BlockHeight) -> Self {
+        let signer =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let chunks = genesis_chunks(vec![Default::default()], 1, 1000, 0, PROTOCOL_VERSION);
+        let mut blocks = vec![Block::genesis(
+            PROTOCOL_VERSION,
+            chunks.into_iter().map(|c| c.take_header()).collect(),
+            Clock::utc(),
+            0,
+            1000,
+            1000,
+            CryptoHash::default(),
+        )];
+        for h in heights {
+            let b = Block::empty_with_height(blocks.last().unwrap(), *h, &signer);
+            blocks.push(b);
+        }
+        Self(Arc::new(MockChainInner {
+            stats: network::Stats::default(),
+            blocks,
+            gc_height,
+            fetched: Mutex::new(vec![]),
+        }))
+    }
+
+    fn hash_at(&self, height: BlockHeight) -> CryptoHash {
+        *self.0.blocks.iter().find(|b| b.header().height() == height).unwrap().hash()
+    }
+
+    fn fetched(&self) -> Vec<BlockHeight> {
+        let mut fetched = self.0.fetched.lock().unwrap().clone();
+        fetched.sort();
+        fetched
+    }
+
+    fn config(&self, direction: Direction, start_height: BlockHeight) -> Config {
+        Config {
+            start_block_hash: self.hash_at(start_height),
+            direction,
+            from_height: None,
+            to_height: None,
+            block_limit: 1000,
+            concurrency_limit: 2,
+            block_timeout: TIMEOUT,
+        }
+    }
+}
+
+impl ChainSource for MockChain {
+    fn stats(&self) -> &network::Stats {
+        &self.0.stats
+    }
+    fn head_height<'a>(&'a self, _ctx: &'a Ctx) -> BoxFuture<'a, anyhow::Result<BlockHeight>> {
+        Box::pin(async move { Ok(self.0.blocks.last().unwrap().header().height()) })
+    }
+    fn fetch_block_headers<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        hash: &'a CryptoHash,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<BlockHeader>>> {
+        Box::pin(async move {
+            let i = match self.0.blocks.iter().position(|b| b.hash() == hash) {
+                Some(i) => i,
+                None => return Ok(ctx.wrap(std::future::pending()).await?),
+            };
+            self.0.stats.header_done.fetch_add(1, Ordering::Relaxed);
+            Ok(self.0.blocks[i + 1..]
+                .iter()
+                .take(HEADERS_BATCH)
+                .map(|b| b.header().clone())
+                .collect())
+        })
+    }
+    fn fetch_block<'a>(
+        &'a self,
+        ctx: &'a Ctx,
+        hash: &'a CryptoHash,
+    ) -> BoxFuture<'a, anyhow::Result<Block>> {
+        Box::pin(async move {
+            let b = self
+                .0
+                .blocks
+                .iter()
+                .find(|b| b.hash() == hash && b.header().height() >= self.0.gc_height);
+            let b = match b {
+                Some(b) => b,
+                None => return Ok(ctx.wrap(std::future::pending()).await?),
+            };
+            self.0.fetched.lock().unwrap().push(b.header().height());
+            self.0.stats.block_done.fetch_add(1, Ordering::Relaxed);
+            Ok(b.clone())
+        })
+    }
+    fn fetch_chunk<'a>(
+        &'a self,
+        _ctx: &'a Ctx,
+        _ch: &'a ShardChunkHeader,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            self.0.stats.chunk_done.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_forward_range() {
+    let chain = MockChain::new(&[1, 2, 4, 5, 7, 8, 9, 10], 0);
+    let mut cfg = chain.config(Direction::Forward, 0);
+    cfg.from_height = Some(2);
+    cfg.to_height = Some(8);
+    run(Ctx::background(), chain.clone(), cfg).await.unwrap();
+    assert_eq!(chain.fetched(), vec![2, 4, 5, 7, 8]);
+    let stats = chain.stats();
+    assert_eq!(stats.chunk_done.load(Ordering::Relaxed), 5);
+    assert_eq!(stats.blocks_unavailable.load(Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn test_forward_to_height_above_head() {
+    let chain = MockChain::new(&[1, 2, 3], 0);
+    let mut cfg = chain.config(Direction::Forward, 1);
+    cfg.to_height = Some(100);
+    run(Ctx::background(), chain.clone(), cfg).await.unwrap();
+    assert_eq!(chain.fetched(), vec![2, 3]);
+}
+
+#[tokio::test]
+async fn test_backward_with_gaps() {
+    let chain = MockChain::new(&[1, 2, 4, 5, 7, 8, 9, 10], 0);
+    let mut cfg = chain.config(Direction::Backward, 10);
+    cfg.to_height = Some(8);
+    run(Ctx::background(), chain.clone(), cfg).await.unwrap();
+    // Blocks above to_height are fetched to resolve the parent hashes,
+    // but their chunks are not.
+    assert_eq!(chain.fetched(), vec![0, 1, 2, 4, 5, 7, 8, 9, 10]);
+    assert_eq!(chain.stats().chunk_done.load(Ordering::Relaxed), 7);
+
+    let chain = MockChain::new(&[1, 2, 4, 5, 7, 8, 9, 10], 0);
+    let mut cfg = chain.config(Direction::Backward, 9);
+    cfg.from_height = Some(3);
+    run(Ctx::background(), chain.clone(), cfg).await.unwrap();
+    // The first block below from_height is fetched to find out its height.
+    assert_eq!(chain.fetched(), vec![2, 4, 5, 7, 8, 9]);
+    assert_eq!(chain.stats().chunk_done.load(Ordering::Relaxed), 5);
+}
+
+#[tokio::test]
+async fn test_gc_blocks_unavailable() {
+    // Forward: the garbage collected blocks are counted as unavailable,
+    // the rest of the range is fetched.
+    let chain = MockChain::new(&[1, 2, 4, 5, 7], 4);
+    let cfg = chain.config(Direction::Forward, 0);
+    run(Ctx::background(), chain.clone(), cfg).await.unwrap();
+    assert_eq!(chain.fetched(), vec![4, 5, 7]);
+    assert_eq!(chain.stats().blocks_unavailable.load(Ordering::Relaxed), 2);
+
+    // Backward: the traversal stops at the first garbage collected block.
+    let chain = MockChain::new(&[1, 2, 4, 5, 7], 4);
+    let cfg = chain.config(Direction::Backward, 7);
+    run(Ctx::background(), chain.clone(), cfg).await.unwrap();
+    assert_eq!(chain.fetched(), vec![4, 5, 7]);
+    assert_eq!(chain.stats().blocks_unavailable.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_cancel_is_not_unavailable() {
+    // Expiring the context of the whole run should fail it,
+    // rather than count the pending blocks as unavailable.
+    let chain = MockChain::new(&[1, 2], 2);
+    let mut cfg = chain.config(Direction::Forward, 0);
+    cfg.block_timeout = time::Duration::from_secs(3600);
+    let ctx = Ctx::background().with_timeout(TIMEOUT);
+    assert!(run((*ctx).clone(), chain.clone(), cfg).await.is_err());
+    assert_eq!(chain.stats().blocks_unavailable.load(Ordering::Relaxed), 0);
+}
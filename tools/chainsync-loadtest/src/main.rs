@@ -1,6 +1,10 @@
 mod concurrency;
 mod fetch_chain;
 mod network;
+mod state_sync;
+
+#[cfg(test)]
+mod fetch_chain_test;
 
 use std::sync::Arc;
 
@@ -9,24 +13,30 @@ use anyhow::{anyhow, Context};
 use clap::Parser;
 use near_store::test_utils::create_test_store;
 use openssl_probe;
+use tokio::time;
 
-use concurrency::{Ctx, Scope};
+use concurrency::{Ctx, RateLimit, Scope};
 use network::{FakeClientActor, Network};
 
-use near_chain_configs::Genesis;
+use near_chain_configs::{Genesis, GenesisValidationMode};
 use near_network::test_utils::NetworkRecipient;
 use near_network::PeerManagerActor;
 use near_o11y::tracing::{error, info};
 use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, ShardId};
 use nearcore::config;
 use nearcore::config::NearConfig;
 
-pub fn start_with_config(config: NearConfig, qps_limit: u32) -> anyhow::Result<Arc<Network>> {
+pub fn start_with_config(
+    config: NearConfig,
+    rate_limit: RateLimit,
+    genesis_hash: Option<CryptoHash>,
+) -> anyhow::Result<Arc<Network>> {
     config.network_config.verify().context("start_with_config")?;
     let store = create_test_store();
 
     let network_adapter = Arc::new(NetworkRecipient::default());
-    let network = Network::new(&config, network_adapter.clone(), qps_limit);
+    let network = Network::new(&config, network_adapter.clone(), rate_limit, genesis_hash);
     let client_actor = FakeClientActor::start_in_arbiter(&Arbiter::new().handle(), {
         let network = network.clone();
         move |_| FakeClientActor::new(network)
@@ -54,41 +64,116 @@ fn download_configs(chain_id: &str, dir: &std::path::Path) -> anyhow::Result<Nea
     config::download_config(&url, config_path)?;
     let config = config::Config::from_file(config_path)?;
 
+    let mut genesis = Genesis::default();
+    genesis.config.chain_id = chain_id.to_string();
+    return Ok(new_near_config(config, genesis));
+}
+
+// load_configs() loads the config and the genesis file of a chain
+// from a node's home directory (e.g. for a localnet).
+fn load_configs(dir: &std::path::Path) -> anyhow::Result<NearConfig> {
+    let config = config::Config::from_file(&dir.join(config::CONFIG_FILENAME))?;
+    let genesis = Genesis::from_file(
+        dir.join(config::GENESIS_CONFIG_FILENAME),
+        GenesisValidationMode::UnsafeFast,
+    );
+    return Ok(new_near_config(config, genesis));
+}
+
+fn new_near_config(config: config::Config, genesis: Genesis) -> NearConfig {
     // Generate node key.
     let account_id = "node".parse().unwrap();
     let node_signer =
         near_crypto::InMemorySigner::from_random(account_id, near_crypto::KeyType::ED25519);
-    let mut genesis = Genesis::default();
-    genesis.config.chain_id = chain_id.to_string();
-    return Ok(NearConfig::new(config, genesis, (&node_signer).into(), None));
+    return NearConfig::new(config, genesis, (&node_signer).into(), None);
+}
+
+fn parse_hash(hash: &str) -> anyhow::Result<CryptoHash> {
+    hash.parse::<CryptoHash>().map_err(|x| anyhow!(x.to_string()))
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// Fetch headers, blocks and chunks, starting with start_block_hash.
+    Chain,
+    /// Fetch state headers and state parts of the given shards, at sync_hash.
+    StateSync,
 }
 
 #[derive(Parser, Debug)]
 struct Cmd {
     #[clap(long)]
     pub chain_id: String,
+    #[clap(long, arg_enum, default_value = "chain")]
+    pub mode: Mode,
+    /// Required in the chain mode.
+    #[clap(long)]
+    pub start_block_hash: Option<String>,
+    /// Direction in which the chain is traversed from start_block_hash in the chain mode.
+    #[clap(long, arg_enum, default_value = "forward")]
+    pub direction: fetch_chain::Direction,
+    /// Skip the blocks below this height in the chain mode.
+    #[clap(long)]
+    pub from_height: Option<BlockHeight>,
+    /// Skip the blocks above this height in the chain mode.
+    #[clap(long)]
+    pub to_height: Option<BlockHeight>,
+    /// Blocks and chunks not fetched within this time are counted as unavailable.
+    #[clap(long, default_value = "60")]
+    pub block_timeout_secs: u64,
+    /// Hash of the first block of the epoch to fetch the state for.
+    /// Required in the state-sync mode.
     #[clap(long)]
-    pub start_block_hash: String,
+    pub sync_hash: Option<String>,
+    /// Shards to fetch the state of in the state-sync mode. Can be repeated.
+    #[clap(long, default_value = "0")]
+    pub shard_id: Vec<ShardId>,
+    /// Load the configs from the given node home directory, rather than downloading them.
+    #[clap(long)]
+    pub home_dir: Option<std::path::PathBuf>,
+    /// Genesis hash of the chain. Required if it is not a well-known chain.
+    #[clap(long)]
+    pub genesis_hash: Option<String>,
     #[clap(long, default_value = "200")]
     pub qps_limit: u32,
+    /// Maximal number of requests that can be sent at once after an idle period.
+    /// Defaults to qps_limit.
+    #[clap(long)]
+    pub qps_burst: Option<u64>,
     #[clap(long, default_value = "2000")]
     pub block_limit: u64,
+    /// Maximal number of blocks (or state parts in the state-sync mode) fetched concurrently.
+    #[clap(long, default_value = "100")]
+    pub concurrency_limit: usize,
 }
 
 impl Cmd {
     fn parse_and_run() -> anyhow::Result<()> {
         let cmd = Self::parse();
-        let start_block_hash =
-            cmd.start_block_hash.parse::<CryptoHash>().map_err(|x| anyhow!(x.to_string()))?;
-
-        let mut cache_dir = dirs::cache_dir().context("dirs::cache_dir() = None")?;
-        cache_dir.push("near_configs");
-        cache_dir.push(&cmd.chain_id);
-
-        info!("downloading configs for chain {}", cmd.chain_id);
-        let home_dir = cache_dir.as_path();
-        let near_config =
-            download_configs(&cmd.chain_id, home_dir).context("Failed to initialize configs")?;
+        let rate_limit =
+            RateLimit { qps: cmd.qps_limit, burst: cmd.qps_burst.unwrap_or(cmd.qps_limit as u64) };
+        let start_hash = match cmd.mode {
+            Mode::Chain => cmd.start_block_hash.as_deref().context("missing --start-block-hash")?,
+            Mode::StateSync => cmd.sync_hash.as_deref().context("missing --sync-hash")?,
+        };
+        let start_hash = parse_hash(start_hash)?;
+        let genesis_hash = cmd.genesis_hash.as_deref().map(parse_hash).transpose()?;
+        if let (Some(from), Some(to)) = (cmd.from_height, cmd.to_height) {
+            anyhow::ensure!(from <= to, "--from-height is above --to-height");
+        }
+
+        let near_config = if let Some(home_dir) = &cmd.home_dir {
+            info!("loading configs from {}", home_dir.display());
+            load_configs(home_dir).context("Failed to load configs")?
+        } else {
+            let mut cache_dir = dirs::cache_dir().context("dirs::cache_dir() = None")?;
+            cache_dir.push("near_configs");
+            cache_dir.push(&cmd.chain_id);
+
+            info!("downloading configs for chain {}", cmd.chain_id);
+            let home_dir = cache_dir.as_path();
+            download_configs(&cmd.chain_id, home_dir).context("Failed to initialize configs")?
+        };
 
         info!("#boot nodes = {}", near_config.network_config.boot_nodes.len());
         // Dropping Runtime is blocking, while futures should never be blocking.
@@ -99,8 +184,8 @@ impl Cmd {
         let rt_ = Arc::new(tokio::runtime::Runtime::new()?);
         let rt = rt_;
         return actix::System::new().block_on(async move {
-            let network =
-                start_with_config(near_config, cmd.qps_limit).context("start_with_config")?;
+            let network = start_with_config(near_config, rate_limit, genesis_hash)
+                .context("start_with_config")?;
 
             // We execute the chain_sync on a totally separate set of system threads to minimize
             // the interaction with actix.
@@ -111,8 +196,30 @@ impl Cmd {
                         info!("Got CTRL+C, stopping...");
                         return Err(anyhow!("Got CTRL+C"));
                     });
-                    fetch_chain::run(ctx.clone(), network, start_block_hash, cmd.block_limit)
-                        .await?;
+                    match cmd.mode {
+                        Mode::Chain => {
+                            let cfg = fetch_chain::Config {
+                                start_block_hash: start_hash,
+                                direction: cmd.direction,
+                                from_height: cmd.from_height,
+                                to_height: cmd.to_height,
+                                block_limit: cmd.block_limit,
+                                concurrency_limit: cmd.concurrency_limit,
+                                block_timeout: time::Duration::from_secs(cmd.block_timeout_secs),
+                            };
+                            fetch_chain::run(ctx.clone(), network, cfg).await?
+                        }
+                        Mode::StateSync => {
+                            state_sync::run(
+                                ctx.clone(),
+                                network,
+                                start_hash,
+                                cmd.shard_id,
+                                cmd.concurrency_limit,
+                            )
+                            .await?
+                        }
+                    }
                     info!("Fetch completed");
                     anyhow::Ok(())
                 })
@@ -1,10 +1,10 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::concurrency::{Ctx, Once, RateLimiter, Scope, WeakMap};
+use crate::concurrency::{Ctx, Once, RateLimit, RateLimiter, Scope, WeakMap};
 
 use near_network_primitives::types::{
-    AccountIdOrPeerTrackingShard, NetworkViewClientMessages, NetworkViewClientResponses,
-    PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
+    AccountIdOrPeerTrackingShard, AccountOrPeerIdOrHash, NetworkViewClientMessages,
+    NetworkViewClientResponses, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
 };
 
 use actix::{Actor, Context, Handler};
@@ -16,7 +16,9 @@ use near_network::types::{
 use near_primitives::block::{Block, BlockHeader, GenesisId};
 use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
+use near_primitives::syncing::ShardStateSyncResponseHeader;
 use near_primitives::time::Clock;
+use near_primitives::types::ShardId;
 use nearcore::config::NearConfig;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
@@ -38,17 +40,62 @@ fn genesis_hash(chain_id: &str) -> CryptoHash {
     .unwrap();
 }
 
+// RequestKind is attached to the context of the fetch_*() calls (see Ctx::with_value),
+// so that the requests sent on their behalf can be attributed to them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RequestKind {
+    BlockHeaders,
+    Block,
+    Chunk,
+    StateHeader,
+    StatePart,
+}
+
 #[derive(Default, Debug)]
 pub struct Stats {
     pub msgs_sent: AtomicU64,
     pub msgs_recv: AtomicU64,
 
+    pub header_msgs_sent: AtomicU64,
+    pub block_msgs_sent: AtomicU64,
+    pub chunk_msgs_sent: AtomicU64,
+    pub state_header_msgs_sent: AtomicU64,
+    pub state_part_msgs_sent: AtomicU64,
+
     pub header_start: AtomicU64,
     pub header_done: AtomicU64,
     pub block_start: AtomicU64,
     pub block_done: AtomicU64,
     pub chunk_start: AtomicU64,
     pub chunk_done: AtomicU64,
+    pub state_header_start: AtomicU64,
+    pub state_header_done: AtomicU64,
+    pub state_part_start: AtomicU64,
+    pub state_part_done: AtomicU64,
+
+    // Number of requests which timed out in fetch_chain::run(), i.e. the requested data
+    // was not available (e.g. garbage collected or out of the range known to the peers).
+    pub headers_unavailable: AtomicU64,
+    pub blocks_unavailable: AtomicU64,
+    pub chunks_unavailable: AtomicU64,
+
+    // Stats of the received state parts, maintained by state_sync::run().
+    pub state_parts_valid: AtomicU64,
+    pub state_parts_invalid: AtomicU64,
+    pub state_part_bytes: AtomicU64,
+    pub state_part_latency_ms: AtomicU64,
+}
+
+impl Stats {
+    fn msgs_sent_by_kind(&self, kind: RequestKind) -> &AtomicU64 {
+        match kind {
+            RequestKind::BlockHeaders => &self.header_msgs_sent,
+            RequestKind::Block => &self.block_msgs_sent,
+            RequestKind::Chunk => &self.chunk_msgs_sent,
+            RequestKind::StateHeader => &self.state_header_msgs_sent,
+            RequestKind::StatePart => &self.state_part_msgs_sent,
+        }
+    }
 }
 
 // NetworkData contains the mutable private data of the Network struct.
@@ -66,9 +113,12 @@ pub struct Network {
     pub block_headers: Arc<WeakMap<CryptoHash, Once<Vec<BlockHeader>>>>,
     pub blocks: Arc<WeakMap<CryptoHash, Once<Block>>>,
     pub chunks: Arc<WeakMap<ChunkHash, Once<PartialEncodedChunkResponseMsg>>>,
+    pub state_headers: Arc<WeakMap<(ShardId, CryptoHash), Once<ShardStateSyncResponseHeader>>>,
+    pub state_parts: Arc<WeakMap<(ShardId, CryptoHash, u64), Once<Vec<u8>>>>,
     data: Mutex<NetworkData>,
 
     chain_id: String,
+    genesis_hash: CryptoHash,
     // client_config.min_num_peers
     min_peers: usize,
     // Currently it is equivalent to genesis_config.num_block_producer_seats,
@@ -78,13 +128,19 @@ pub struct Network {
 
     request_timeout: tokio::time::Duration,
     rate_limiter: RateLimiter,
+    // Number of rate limiter permits charged for a single chunk request.
+    // A chunk response consists of parts_per_chunk parts, so it is much larger than
+    // a block or headers response (which are charged 1 permit). Capped at the
+    // rate limiter burst, so that a chunk request can always get through.
+    chunk_request_weight: u64,
 }
 
 impl Network {
     pub fn new(
         config: &NearConfig,
         network_adapter: Arc<dyn PeerManagerAdapter>,
-        qps_limit: u32,
+        rate_limit: RateLimit,
+        genesis_hash: Option<CryptoHash>,
     ) -> Arc<Network> {
         Arc::new(Network {
             stats: Default::default(),
@@ -102,16 +158,21 @@ impl Network {
                 }),
                 info_futures: Default::default(),
             }),
-            blocks: WeakMap::new(),
-            block_headers: WeakMap::new(),
-            chunks: WeakMap::new(),
+            blocks: WeakMap::new(None, None),
+            block_headers: WeakMap::new(None, None),
+            chunks: WeakMap::new(None, None),
+            state_headers: WeakMap::new(None, None),
+            state_parts: WeakMap::new(None, None),
 
             chain_id: config.client_config.chain_id.clone(),
+            genesis_hash: genesis_hash
+                .unwrap_or_else(|| self::genesis_hash(&config.client_config.chain_id)),
             min_peers: config.client_config.min_num_peers,
             parts_per_chunk: config.genesis.config.num_block_producer_seats,
-            rate_limiter: RateLimiter::new(
-                time::Duration::from_secs(1) / qps_limit,
-                qps_limit as u64,
+            rate_limiter: RateLimiter::new(rate_limit),
+            chunk_request_weight: std::cmp::min(
+                config.genesis.config.num_block_producer_seats,
+                rate_limit.burst,
             ),
             request_timeout: time::Duration::from_secs(2),
         })
@@ -122,7 +183,9 @@ impl Network {
     // The requests are distributed uniformly among all the available peers.
     // - keep_sending() completes as soon as ctx expires.
     // - keep_sending() respects the global rate limits, so the actual frequency
-    //   of the sends may be lower than expected.
+    //   of the sends may be lower than expected. Each send is charged permits
+    //   according to the RequestKind of ctx.
+    // - keep_sending() counts the sends per RequestKind of ctx.
     // - keep_sending() may pause if the number of connected peers is too small.
     fn keep_sending(
         self: &Arc<Self>,
@@ -131,17 +194,25 @@ impl Network {
     ) -> impl Future<Output = anyhow::Result<()>> + Send {
         let self_ = self.clone();
         let ctx = ctx.with_label("keep_sending");
+        let kind = ctx.value::<RequestKind>().copied();
+        let weight = match kind {
+            Some(RequestKind::Chunk) => self.chunk_request_weight,
+            _ => 1,
+        };
         async move {
             loop {
                 let mut peers = self_.info(&ctx).await?.connected_peers.clone();
                 peers.shuffle(&mut thread_rng());
                 for peer in peers {
                     // TODO: rate limit per peer.
-                    self_.rate_limiter.allow(&ctx).await?;
+                    self_.rate_limiter.allow_n(&ctx, weight).await?;
                     self_
                         .network_adapter
                         .do_send(PeerManagerMessageRequest::NetworkRequests(new_req(peer.clone())));
                     self_.stats.msgs_sent.fetch_add(1, Ordering::Relaxed);
+                    if let Some(kind) = kind {
+                        self_.stats.msgs_sent_by_kind(kind).fetch_add(1, Ordering::Relaxed);
+                    }
                     ctx.wait(self_.request_timeout).await?;
                 }
             }
@@ -173,7 +244,7 @@ impl Network {
         ctx: &Ctx,
         hash: &CryptoHash,
     ) -> anyhow::Result<Vec<BlockHeader>> {
-        Scope::run(ctx, {
+        Scope::run(&ctx.with_value(RequestKind::BlockHeaders), {
             let self_ = self.clone();
             let hash = hash.clone();
             move |ctx, s| async move {
@@ -199,7 +270,7 @@ impl Network {
         ctx: &Ctx,
         hash: &CryptoHash,
     ) -> anyhow::Result<Block> {
-        Scope::run(ctx, {
+        Scope::run(&ctx.with_value(RequestKind::Block), {
             let self_ = self.clone();
             let hash = hash.clone();
             move |ctx, s| async move {
@@ -225,7 +296,7 @@ impl Network {
         ctx: &Ctx,
         ch: &ShardChunkHeader,
     ) -> anyhow::Result<PartialEncodedChunkResponseMsg> {
-        Scope::run(ctx, {
+        Scope::run(&ctx.with_value(RequestKind::Chunk), {
             let self_ = self.clone();
             let ch = ch.clone();
             move |ctx, s| async move {
@@ -260,6 +331,70 @@ impl Network {
         .await
     }
 
+    // fetch_state_header() fetches the state sync header of the given shard,
+    // for the epoch starting with the block <sync_hash>.
+    pub async fn fetch_state_header(
+        self: &Arc<Self>,
+        ctx: &Ctx,
+        shard_id: ShardId,
+        sync_hash: &CryptoHash,
+    ) -> anyhow::Result<ShardStateSyncResponseHeader> {
+        Scope::run(&ctx.with_value(RequestKind::StateHeader), {
+            let self_ = self.clone();
+            let sync_hash = sync_hash.clone();
+            move |ctx, s| async move {
+                self_.stats.state_header_start.fetch_add(1, Ordering::Relaxed);
+                let recv =
+                    self_.state_headers.get_or_insert(&(shard_id, sync_hash), || Once::new());
+                s.spawn_weak(|ctx| {
+                    self_.keep_sending(&ctx, move |peer| NetworkRequests::StateRequestHeader {
+                        shard_id,
+                        sync_hash,
+                        target: AccountOrPeerIdOrHash::PeerId(peer.peer_info.id),
+                    })
+                });
+                let res = ctx.wrap(recv.wait()).await;
+                self_.stats.state_header_done.fetch_add(1, Ordering::Relaxed);
+                anyhow::Ok(res?)
+            }
+        })
+        .await
+    }
+
+    // fetch_state_part() fetches the given state part of the given shard,
+    // for the epoch starting with the block <sync_hash>.
+    // The part is NOT validated.
+    pub async fn fetch_state_part(
+        self: &Arc<Self>,
+        ctx: &Ctx,
+        shard_id: ShardId,
+        sync_hash: &CryptoHash,
+        part_id: u64,
+    ) -> anyhow::Result<Vec<u8>> {
+        Scope::run(&ctx.with_value(RequestKind::StatePart), {
+            let self_ = self.clone();
+            let sync_hash = sync_hash.clone();
+            move |ctx, s| async move {
+                self_.stats.state_part_start.fetch_add(1, Ordering::Relaxed);
+                let recv = self_
+                    .state_parts
+                    .get_or_insert(&(shard_id, sync_hash, part_id), || Once::new());
+                s.spawn_weak(|ctx| {
+                    self_.keep_sending(&ctx, move |peer| NetworkRequests::StateRequestPart {
+                        shard_id,
+                        sync_hash,
+                        part_id,
+                        target: AccountOrPeerIdOrHash::PeerId(peer.peer_info.id),
+                    })
+                });
+                let res = ctx.wrap(recv.wait()).await;
+                self_.stats.state_part_done.fetch_add(1, Ordering::Relaxed);
+                anyhow::Ok(res?)
+            }
+        })
+        .await
+    }
+
     fn notify(&self, msg: NetworkClientMessages) {
         self.stats.msgs_recv.fetch_add(1, Ordering::Relaxed);
         match msg {
@@ -286,6 +421,19 @@ impl Network {
             NetworkClientMessages::PartialEncodedChunkResponse(resp, _) => {
                 self.chunks.get(&resp.chunk_hash.clone()).map(|p| p.set(resp));
             }
+            NetworkClientMessages::StateResponse(info) => {
+                let shard_id = info.shard_id();
+                let sync_hash = info.sync_hash();
+                let state_response = info.take_state_response();
+                // A peer which doesn't have the requested data responds with neither
+                // a header nor a part, in which case the request will be retried.
+                if state_response.part().is_some() {
+                    let (part_id, data) = state_response.take_part().unwrap();
+                    self.state_parts.get(&(shard_id, sync_hash, part_id)).map(|p| p.set(data));
+                } else if let Some(header) = state_response.take_header() {
+                    self.state_headers.get(&(shard_id, sync_hash)).map(|p| p.set(header));
+                }
+            }
             _ => {}
         }
     }
@@ -325,7 +473,7 @@ impl Handler<NetworkViewClientMessages> for FakeClientActor {
                 return NetworkViewClientResponses::ChainInfo {
                     genesis_id: GenesisId {
                         chain_id: self.network.chain_id.clone(),
-                        hash: genesis_hash(&self.network.chain_id),
+                        hash: self.network.genesis_hash,
                     },
                     height: 0,
                     tracked_shards: Default::default(),
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::concurrency::{Ctx, Scope};
+use crate::network;
+use log::info;
+use std::sync::atomic::Ordering;
+use tokio::time;
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::syncing::get_num_state_parts;
+use near_primitives::types::ShardId;
+use near_store::verify_state_part;
+
+// run() fetches the state of the given shards, for the epoch starting with
+// the block having hash = <sync_hash>: for each shard it fetches the state header
+// and then all the state parts. Each part is validated against the state root
+// from the header before it is counted as fetched. An invalid part fails the
+// whole run. Note that the header itself is NOT validated against the chain.
+// At most <concurrency_limit> parts are fetched concurrently.
+pub async fn run(
+    ctx: Ctx,
+    network: Arc<network::Network>,
+    sync_hash: CryptoHash,
+    shard_ids: Vec<ShardId>,
+    concurrency_limit: usize,
+) -> anyhow::Result<()> {
+    info!("STATE SYNC start, sync_hash = {}, shards = {:?}", sync_hash, shard_ids);
+    let start_time = time::Instant::now();
+    let res = Scope::run(&ctx, {
+        let network = network.clone();
+        |_ctx, s| async move {
+            s.spawn_weak({
+                let network = network.clone();
+                |ctx| async move {
+                    let ctx = ctx.with_label("stats");
+                    loop {
+                        info!("stats = {:?}", network.stats);
+                        ctx.wait(time::Duration::from_secs(2)).await?;
+                    }
+                }
+            });
+
+            let parts_sem = Arc::new(tokio::sync::Semaphore::new(concurrency_limit));
+            for shard_id in shard_ids {
+                let network = network.clone();
+                let parts_sem = parts_sem.clone();
+                s.spawn(move |ctx, s| async move {
+                    let header = network.fetch_state_header(&ctx, shard_id, &sync_hash).await?;
+                    let state_root = header.chunk_prev_state_root();
+                    let num_parts = get_num_state_parts(header.state_root_node().memory_usage);
+                    info!(
+                        "STATE SYNC shard {}: state_root = {}, {} parts",
+                        shard_id, state_root, num_parts
+                    );
+                    for part_id in 0..num_parts {
+                        let network = network.clone();
+                        s.spawn_bounded(&parts_sem, move |ctx, _s| async move {
+                            let start = time::Instant::now();
+                            let part = network
+                                .fetch_state_part(&ctx, shard_id, &sync_hash, part_id)
+                                .await?;
+                            let latency = start.elapsed();
+                            let stats = &network.stats;
+                            if !verify_state_part(&state_root, part_id, num_parts, &part) {
+                                stats.state_parts_invalid.fetch_add(1, Ordering::Relaxed);
+                                anyhow::bail!(
+                                    "shard {}: state part {}/{} is invalid",
+                                    shard_id,
+                                    part_id,
+                                    num_parts
+                                );
+                            }
+                            stats.state_parts_valid.fetch_add(1, Ordering::Relaxed);
+                            stats.state_part_bytes.fetch_add(part.len() as u64, Ordering::Relaxed);
+                            stats
+                                .state_part_latency_ms
+                                .fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+                            info!(
+                                "STATE SYNC shard {}: part {}/{}: {} bytes, latency {:.2}s",
+                                shard_id,
+                                part_id,
+                                num_parts,
+                                part.len(),
+                                latency.as_secs_f64()
+                            );
+                            anyhow::Ok(())
+                        });
+                    }
+                    anyhow::Ok(())
+                });
+            }
+            anyhow::Ok(())
+        }
+    })
+    .await;
+    let t = start_time.elapsed().as_secs_f64();
+    let sent = network.stats.msgs_sent.load(Ordering::Relaxed);
+    let parts = network.stats.state_parts_valid.load(Ordering::Relaxed);
+    let bytes = network.stats.state_part_bytes.load(Ordering::Relaxed);
+    let latency_ms = network.stats.state_part_latency_ms.load(Ordering::Relaxed);
+    info!("running time: {:.2}s", t);
+    info!("average QPS: {:.2}", (sent as f64) / t);
+    info!("fetched {} valid state parts ({:.2} per second)", parts, parts as f64 / t);
+    info!("fetched {} bytes of state parts ({:.2} per second)", bytes, bytes as f64 / t);
+    if parts > 0 {
+        info!("average state part latency: {:.2}s", latency_ms as f64 / 1000. / parts as f64);
+    }
+    return res;
+}